@@ -245,6 +245,38 @@ mod tests {
         assert_eq!(C::SIZE, 11 + 33 + 1);
     }
 
+    #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized)]
+    struct GenericStruct<T>
+    where
+        [T; 2]: BorshSerDeSized,
+    {
+        v: T,
+        a: [T; 2],
+    }
+
+    #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Debug)]
+    enum GenericEnum<T, const N: usize> {
+        A { v: T },
+        B { v: [u8; N], t: T },
+        C,
+    }
+
+    #[test]
+    fn test_borsh_ser_de_sized_generics() {
+        assert_eq!(GenericStruct::<u16>::SIZE, 2 + 2 * 2);
+
+        assert_eq!(GenericEnum::<u32, 5>::SIZE, 1 + 5 + 4);
+        assert_eq!(GenericEnum::<u64, 2>::SIZE, 1 + 2 + 8);
+        assert_eq!(GenericEnum::<u32, 5>::len(0), 4);
+        assert_eq!(GenericEnum::<u32, 5>::len(1), 9);
+        assert_eq!(GenericEnum::<u32, 5>::len(2), 0);
+
+        let v = GenericEnum::<u32, 5>::A { v: 7 };
+        let mut data = v.try_to_vec().unwrap();
+        data.extend(vec![255; GenericEnum::<u32, 5>::SIZE - data.len()]);
+        assert_eq!(GenericEnum::deserialize_enum(&mut &data[..]).unwrap(), v);
+    }
+
     #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Debug)]
     enum TestEnum {
         A { v: [u64; 1] },
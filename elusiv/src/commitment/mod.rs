@@ -1,3 +1,4 @@
+#[cfg(any(test, feature = "elusiv-client"))]
 #[cfg(not(tarpaulin_include))]
 mod poseidon_constants;
 pub mod poseidon_hash;
@@ -9,7 +10,10 @@ use solana_program::program_error::ProgramError;
 
 use crate::{
     bytes::usize_as_u32_safe,
-    commitment::poseidon_hash::{binary_poseidon_hash_partial, TOTAL_POSEIDON_ROUNDS},
+    commitment::poseidon_hash::{
+        binary_poseidon_hash_partial, PoseidonParams, TOTAL_POSEIDON_ROUNDS,
+    },
+    computation::PartialComputationDriver,
     error::ElusivError,
     state::commitment::{BaseCommitmentHashingAccount, CommitmentHashingAccount},
 };
@@ -23,29 +27,31 @@ const_assert_eq!(BaseCommitmentHashComputation::TX_COUNT, 2);
 
 pub fn compute_base_commitment_hash_partial(
     hashing_account: &mut BaseCommitmentHashingAccount,
+    params: &impl PoseidonParams,
+    slot: u64,
 ) -> Result<(), ProgramError> {
     let instruction = hashing_account.get_instruction();
     guard!(
-        (instruction as usize) < BaseCommitmentHashComputation::IX_COUNT,
+        !hashing_account.is_computation_finished(BaseCommitmentHashComputation::IX_COUNT),
         ElusivError::ComputationIsAlreadyFinished
     );
 
-    let start_round = hashing_account.get_round();
     let rounds = BaseCommitmentHashComputation::INSTRUCTION_ROUNDS[instruction as usize] as u32;
+    let rounds = hashing_account.advance_computation(
+        rounds,
+        BaseCommitmentHashComputation::TOTAL_ROUNDS,
+        slot,
+    )?;
 
     let mut state = hashing_account.get_state();
+    let matrix = params.mds_matrix();
 
-    for round in start_round..start_round + rounds {
-        guard!(
-            round < BaseCommitmentHashComputation::TOTAL_ROUNDS,
-            ElusivError::ComputationIsAlreadyFinished
-        );
-        binary_poseidon_hash_partial(round, &mut state);
+    for round in rounds {
+        let constants = params.round_constants(round as usize);
+        binary_poseidon_hash_partial(round, &mut state, constants, &matrix);
     }
 
     hashing_account.set_state(&state);
-    hashing_account.set_instruction(&(instruction + 1));
-    hashing_account.set_round(&(start_round + rounds));
 
     Ok(())
 }
@@ -149,27 +155,30 @@ const_assert_eq!(MAX_HT_COMMITMENTS, 16);
 
 pub fn compute_commitment_hash_partial(
     hashing_account: &mut CommitmentHashingAccount,
+    params: &impl PoseidonParams,
+    slot: u64,
 ) -> Result<(), ProgramError> {
     let batching_rate = hashing_account.get_batching_rate();
     let instruction = hashing_account.get_instruction();
     let instructions = commitment_hash_computation_instructions(batching_rate);
     guard!(
-        (instruction as usize) < instructions.len(),
+        !hashing_account.is_computation_finished(instructions.len()),
         ElusivError::ComputationIsAlreadyFinished
     );
 
-    let start_round = hashing_account.get_round();
-    let rounds = instructions[instruction as usize] as u32;
-    let total_rounds = commitment_hash_computation_rounds(batching_rate);
-    guard!(
-        start_round + rounds <= total_rounds,
-        ElusivError::ComputationIsAlreadyFinished
-    );
+    let rounds = hashing_account.advance_computation(
+        instructions[instruction as usize] as u32,
+        commitment_hash_computation_rounds(batching_rate),
+        slot,
+    )?;
 
     let mut state = hashing_account.get_state();
+    let matrix = params.mds_matrix();
 
-    for round in start_round..start_round + rounds {
-        binary_poseidon_hash_partial(round % TOTAL_POSEIDON_ROUNDS, &mut state);
+    for round in rounds {
+        let poseidon_round = round % TOTAL_POSEIDON_ROUNDS;
+        let constants = params.round_constants(poseidon_round as usize);
+        binary_poseidon_hash_partial(poseidon_round, &mut state, constants, &matrix);
 
         // A single hash is finished
         if round % TOTAL_POSEIDON_ROUNDS == 64 {
@@ -186,18 +195,18 @@ pub fn compute_commitment_hash_partial(
     }
 
     hashing_account.set_state(&state);
-    hashing_account.set_instruction(&(instruction + 1));
-    hashing_account.set_round(&(start_round + rounds));
 
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
+    use ark_bn254::Fr;
     use assert_matches::assert_matches;
     use solana_program::native_token::LAMPORTS_PER_SOL;
 
     use crate::{
+        commitment::poseidon_hash::{full_poseidon2_hash, DefaultPoseidonParams},
         fields::{u256_from_str, u256_to_fr_skip_mr},
         macros::zero_program_account,
         state::{commitment::base_commitment_request, storage::EMPTY_TREE},
@@ -243,13 +252,17 @@ mod tests {
         )];
 
         for request in requests {
-            account.setup(request.clone(), [0; 32]).unwrap();
+            account.setup(request.clone(), [0; 32], 0).unwrap();
 
             while account.get_instruction() < BaseCommitmentHashComputation::IX_COUNT as u32 {
-                compute_base_commitment_hash_partial(&mut account).unwrap();
+                compute_base_commitment_hash_partial(&mut account, &DefaultPoseidonParams, 0)
+                    .unwrap();
             }
 
-            assert_matches!(compute_base_commitment_hash_partial(&mut account), Err(_));
+            assert_matches!(
+                compute_base_commitment_hash_partial(&mut account, &DefaultPoseidonParams, 0),
+                Err(_)
+            );
             assert_eq!(
                 account.get_state().result(),
                 u256_to_fr_skip_mr(&request.commitment.reduce())
@@ -294,21 +307,78 @@ mod tests {
             zero_program_account!(mut account, CommitmentHashingAccount);
 
             let batching_rate = request.batching_rate;
-            account.setup(0, request.siblings).unwrap();
+            account.setup(0, request.siblings, 0).unwrap();
             account
                 .reset(batching_rate, 0, request.commitments)
                 .unwrap();
 
             let instructions = commitment_hash_computation_instructions(batching_rate).len() as u32;
             while account.get_instruction() < instructions {
-                compute_commitment_hash_partial(&mut account).unwrap();
+                compute_commitment_hash_partial(&mut account, &DefaultPoseidonParams, 0).unwrap();
             }
 
-            assert_matches!(compute_commitment_hash_partial(&mut account), Err(_));
+            assert_matches!(
+                compute_commitment_hash_partial(&mut account, &DefaultPoseidonParams, 0),
+                Err(_)
+            );
             assert_eq!(
                 account.get_state().result(),
                 u256_to_fr_skip_mr(&request.valid_root)
             );
         }
     }
+
+    #[test]
+    fn test_batched_insertion_matches_leaf_insertions() {
+        let empty_siblings: Vec<U256> = EMPTY_TREE.iter().take(MT_HEIGHT).copied().collect();
+
+        for batching_rate in 0..=MAX_COMMITMENT_BATCHING_RATE as u32 {
+            let commitments: Vec<U256> = (0..commitments_per_batch(batching_rate))
+                .map(|i| u256_from_str(&(i + 1).to_string()))
+                .collect();
+
+            // Single-leaf insertions: each leaf is hashed up to the MT-root
+            let mut levels: Vec<Vec<Fr>> = vec![Vec::new(); MT_HEIGHT + 1];
+            let node = |levels: &Vec<Vec<Fr>>, level: usize, index: usize| {
+                levels[level]
+                    .get(index)
+                    .copied()
+                    .unwrap_or_else(|| u256_to_fr_skip_mr(&EMPTY_TREE[level]))
+            };
+            let mut leaf_hash_count = 0;
+            for (index, commitment) in commitments.iter().enumerate() {
+                levels[0].push(u256_to_fr_skip_mr(commitment));
+                for level in 0..MT_HEIGHT {
+                    let index = index >> level;
+                    let parent = full_poseidon2_hash(
+                        node(&levels, level, index & !1),
+                        node(&levels, level, index | 1),
+                    );
+                    levels[level + 1].truncate(index >> 1);
+                    levels[level + 1].push(parent);
+                    leaf_hash_count += 1;
+                }
+            }
+
+            // Batched insertion: the HT is hashed first, then the path from the HT-root to the MT-root
+            zero_program_account!(mut account, CommitmentHashingAccount);
+            account.setup(0, &empty_siblings, 0).unwrap();
+            account.reset(batching_rate, 0, &commitments).unwrap();
+
+            let instructions = commitment_hash_computation_instructions(batching_rate).len() as u32;
+            while account.get_instruction() < instructions {
+                compute_commitment_hash_partial(&mut account, &DefaultPoseidonParams, 0).unwrap();
+            }
+
+            assert_eq!(account.get_state().result(), levels[MT_HEIGHT][0]);
+            assert_eq!(
+                commitment_hash_computation_rounds(batching_rate),
+                hash_count_per_batch(batching_rate) as u32 * TOTAL_POSEIDON_ROUNDS
+            );
+            assert_eq!(leaf_hash_count, commitments.len() * MT_HEIGHT);
+            if batching_rate > 0 {
+                assert!(hash_count_per_batch(batching_rate) < leaf_hash_count);
+            }
+        }
+    }
 }
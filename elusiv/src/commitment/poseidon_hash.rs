@@ -1,4 +1,5 @@
-use super::poseidon_constants::*;
+#[cfg(any(test, feature = "elusiv-client"))]
+use super::poseidon_constants::{constants, MATRIX};
 use crate::{
     bytes::BorshSerDeSized,
     fields::{fr_to_u256_le, u256_to_fr_skip_mr},
@@ -10,11 +11,40 @@ use borsh::{BorshDeserialize, BorshSerialize};
 
 pub const TOTAL_POSEIDON_ROUNDS: u32 = 65;
 
+/// Number of entries of the (3x3) MDS matrix
+pub const POSEIDON_MDS_MATRIX_SIZE: usize = 9;
+
+/// The round constants and MDS matrix of the Poseidon hash
+/// - the program reads them from the [`crate::state::poseidon::PoseidonParamsAccount`]
+pub trait PoseidonParams {
+    /// The three round constants of `round`
+    fn round_constants(&self, round: usize) -> [Fr; 3];
+
+    /// The MDS matrix (row-major)
+    fn mds_matrix(&self) -> [Fr; POSEIDON_MDS_MATRIX_SIZE];
+}
+
+/// The reference parameters (circomlib) used to load the [`crate::state::poseidon::PoseidonParamsAccount`]
+/// - not part of the program binary
+#[cfg(any(test, feature = "elusiv-client"))]
+pub struct DefaultPoseidonParams;
+
+#[cfg(any(test, feature = "elusiv-client"))]
+impl PoseidonParams for DefaultPoseidonParams {
+    fn round_constants(&self, round: usize) -> [Fr; 3] {
+        constants(round)
+    }
+
+    fn mds_matrix(&self) -> [Fr; POSEIDON_MDS_MATRIX_SIZE] {
+        MATRIX
+    }
+}
+
 macro_rules! matrix_mix {
-    ($new_state: ident, $s: literal, $i: literal, $state: ident) => {
-        $new_state[$s] += MATRIX[$i] * $state[0];
-        $new_state[$s] += MATRIX[$i + 1] * $state[1];
-        $new_state[$s] += MATRIX[$i + 2] * $state[2];
+    ($new_state: ident, $s: literal, $i: literal, $state: ident, $matrix: ident) => {
+        $new_state[$s] += $matrix[$i] * $state[0];
+        $new_state[$s] += $matrix[$i + 1] * $state[1];
+        $new_state[$s] += $matrix[$i + 2] * $state[2];
     };
 }
 
@@ -75,9 +105,13 @@ impl BorshDeserialize for BinarySpongeHashingState {
 /// - for input arity 2 we have 8 full rounds and 57 partial rounds (recommended in: https://eprint.iacr.org/2019/458.pdf (table 2, table 8))
 /// - in our implementation we use two types of rounds: computation rounds and Poseidon rounds
 /// - circom javascript reference implementation: https://github.com/iden3/circomlibjs/blob/9300d3f820b40a16d2f342ab5127a0cb9090bd15/src/poseidon_reference.js#L27
-pub fn binary_poseidon_hash_partial(round: u32, hashing_state: &mut BinarySpongeHashingState) {
-    // Load constants (~ 260 CUs)
-    let constants = constants(round as usize);
+/// - `constants` are the round constants of `round`, `matrix` is loaded once per computation (see [`PoseidonParams`])
+pub fn binary_poseidon_hash_partial(
+    round: u32,
+    hashing_state: &mut BinarySpongeHashingState,
+    constants: [Fr; 3],
+    matrix: &[Fr; POSEIDON_MDS_MATRIX_SIZE],
+) {
     let mut state = hashing_state.0;
 
     // Ark (~ 277 CUs)
@@ -98,21 +132,29 @@ pub fn binary_poseidon_hash_partial(round: u32, hashing_state: &mut BinarySponge
 
     // Mix (~ 17_740)
     let mut new_state = [Fr::zero(); 3];
-    matrix_mix!(new_state, 0, 0, state);
-    matrix_mix!(new_state, 1, 3, state);
-    matrix_mix!(new_state, 2, 6, state);
+    matrix_mix!(new_state, 0, 0, state, matrix);
+    matrix_mix!(new_state, 1, 3, state, matrix);
+    matrix_mix!(new_state, 2, 6, state, matrix);
 
     hashing_state.0 = new_state;
 }
 
-pub fn full_poseidon2_hash(a: Fr, b: Fr) -> Fr {
+pub fn poseidon2_hash(a: Fr, b: Fr, params: &impl PoseidonParams) -> Fr {
+    let matrix = params.mds_matrix();
     let mut state = BinarySpongeHashingState::new(a, b, false);
     for round in 0..TOTAL_POSEIDON_ROUNDS {
-        binary_poseidon_hash_partial(round, &mut state);
+        let constants = params.round_constants(round as usize);
+        binary_poseidon_hash_partial(round, &mut state, constants, &matrix);
     }
     state.result()
 }
 
+/// Computes the Poseidon hash using the [`DefaultPoseidonParams`]
+#[cfg(any(test, feature = "elusiv-client"))]
+pub fn full_poseidon2_hash(a: Fr, b: Fr) -> Fr {
+    poseidon2_hash(a, b, &DefaultPoseidonParams)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
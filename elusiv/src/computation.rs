@@ -0,0 +1,176 @@
+pub use elusiv_types::accounts::COMPUTATION_TIMEOUT_SLOTS;
+
+use crate::error::{ElusivError, ElusivResult};
+use elusiv_types::accounts::ComputationAccount;
+use elusiv_utils::guard;
+use solana_program::program_error::ProgramError;
+use std::ops::Range;
+
+/// Portion (in basis points) of the prepaid fee of a timed out computation that is slashed to the party resetting it
+pub const COMPUTATION_TIMEOUT_SLASHING_BPS: u64 = 5_000;
+
+/// Returns the current slot
+pub fn current_slot() -> Result<u64, ProgramError> {
+    use solana_program::sysvar::{clock::Clock, Sysvar};
+    Ok(Clock::get()?.slot)
+}
+
+/// Generic state machine of a partial computation (performed over the span of multiple instructions)
+///
+/// # Notes
+///
+/// The activity of a computation (`is_active`, `fee_payer`, etc.) is tracked by the individual accounts.
+/// The driver only manages the progress (`instruction` and `round`) and the timeouts.
+pub trait PartialComputationDriver: ComputationAccount {
+    /// Resets the progress of the computation
+    fn start_computation(&mut self, slot: u64) {
+        self.set_progress(0, 0, slot);
+    }
+
+    /// Advances the computation by one instruction performing `rounds` rounds
+    /// - returns the range of rounds that need to be computed by the caller
+    fn advance_computation(
+        &mut self,
+        rounds: u32,
+        total_rounds: u32,
+        slot: u64,
+    ) -> Result<Range<u32>, ElusivError> {
+        let instruction = self.instruction();
+        let start_round = self.round();
+        let end_round = start_round
+            .checked_add(rounds)
+            .ok_or(ElusivError::PartialComputationError)?;
+        guard!(
+            end_round <= total_rounds,
+            ElusivError::ComputationIsAlreadyFinished
+        );
+
+        self.set_progress(
+            instruction
+                .checked_add(1)
+                .ok_or(ElusivError::PartialComputationError)?,
+            end_round,
+            slot,
+        );
+
+        Ok(start_round..end_round)
+    }
+
+    fn is_computation_finished(&self, instruction_count: usize) -> bool {
+        self.instruction() as usize >= instruction_count
+    }
+
+    /// Requires all `instruction_count` instructions to have been performed
+    fn finish_computation(&self, instruction_count: usize) -> ElusivResult {
+        guard!(
+            self.is_computation_finished(instruction_count),
+            ElusivError::ComputationIsNotYetFinished
+        );
+
+        Ok(())
+    }
+
+    /// Records a round that does not advance the computation itself (e.g. a setup or finalization step)
+    fn record_computation_round(&mut self, slot: u64) {
+        self.set_progress(self.instruction(), self.round(), slot);
+    }
+
+    fn is_computation_timed_out(&self, slot: u64) -> bool {
+        slot >= self.last_round_slot().saturating_add(self.timeout_slots())
+    }
+
+    /// Allows a different party to continue a timed out computation
+    fn take_over_computation(&mut self, slot: u64) -> ElusivResult {
+        guard!(
+            self.is_computation_timed_out(slot),
+            ElusivError::ComputationIsNotTimedOut
+        );
+        self.record_computation_round(slot);
+
+        Ok(())
+    }
+
+    /// Resets the progress of a timed out computation
+    fn cancel_computation(&mut self, slot: u64) -> ElusivResult {
+        guard!(
+            self.is_computation_timed_out(slot),
+            ElusivError::ComputationIsNotTimedOut
+        );
+        self.start_computation(slot);
+
+        Ok(())
+    }
+}
+
+impl<T: ComputationAccount> PartialComputationDriver for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+    use crate::state::commitment::BaseCommitmentHashingAccount;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_advance_computation() {
+        zero_program_account!(mut account, BaseCommitmentHashingAccount);
+        account.start_computation(10);
+
+        assert_eq!(account.advance_computation(3, 5, 11).unwrap(), 0..3);
+        assert_eq!(account.advance_computation(2, 5, 12).unwrap(), 3..5);
+        assert_eq!(account.get_instruction(), 2);
+        assert_eq!(account.get_round(), 5);
+        assert_eq!(account.get_last_update_slot(), 12);
+
+        assert_matches!(account.advance_computation(1, 5, 13), Err(_));
+        assert_eq!(account.get_instruction(), 2);
+    }
+
+    #[test]
+    fn test_finish_computation() {
+        zero_program_account!(mut account, BaseCommitmentHashingAccount);
+        account.start_computation(0);
+        account.advance_computation(1, 2, 0).unwrap();
+
+        assert_matches!(account.finish_computation(2), Err(_));
+        account.advance_computation(1, 2, 0).unwrap();
+        assert_matches!(account.finish_computation(2), Ok(()));
+    }
+
+    #[test]
+    fn test_record_computation_round() {
+        zero_program_account!(mut account, BaseCommitmentHashingAccount);
+        account.start_computation(10);
+        account.advance_computation(1, 2, 11).unwrap();
+
+        account.record_computation_round(20);
+        assert_eq!(account.last_round_slot(), 20);
+        assert_eq!(account.get_instruction(), 1);
+        assert_eq!(account.get_round(), 1);
+        assert_eq!(account.timeout_slots(), COMPUTATION_TIMEOUT_SLOTS);
+    }
+
+    #[test]
+    fn test_computation_timeout() {
+        zero_program_account!(mut account, BaseCommitmentHashingAccount);
+        account.start_computation(100);
+        account.advance_computation(1, 2, 200).unwrap();
+
+        let timeout = 200 + COMPUTATION_TIMEOUT_SLOTS;
+        assert!(!account.is_computation_timed_out(timeout - 1));
+        assert!(account.is_computation_timed_out(timeout));
+
+        assert_matches!(account.take_over_computation(timeout - 1), Err(_));
+        assert_matches!(account.cancel_computation(timeout - 1), Err(_));
+
+        account.take_over_computation(timeout).unwrap();
+        assert_eq!(account.get_instruction(), 1);
+        assert_eq!(account.get_last_update_slot(), timeout);
+        assert!(!account.is_computation_timed_out(timeout));
+
+        let timeout = timeout + COMPUTATION_TIMEOUT_SLOTS;
+        account.cancel_computation(timeout).unwrap();
+        assert_eq!(account.get_instruction(), 0);
+        assert_eq!(account.get_round(), 0);
+    }
+}
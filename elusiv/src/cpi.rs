@@ -0,0 +1,270 @@
+//! Helpers for other programs depositing into the shielded pool using cross-program invocations
+//!
+//! # Notes
+//!
+//! - the invoking program passes all accounts of the instruction (plus the Elusiv program account) to its own instruction
+//! - PDAs of the invoking program (e.g. a `sender` holding the funds of its users) sign using the supplied signers seeds
+//! - lamports can only be deposited from a PDA that is owned by the system program and holds no data
+
+use crate::error::ElusivError;
+use crate::instruction::ElusivInstruction;
+use crate::macros::guard;
+use crate::processor::BaseCommitmentHashRequest;
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+    program_error::ProgramError,
+};
+
+/// The Elusiv program account and all accounts of [`ElusivInstruction::StoreBaseCommitment`] (in instruction order)
+#[derive(Clone)]
+pub struct StoreBaseCommitmentAccounts<'a> {
+    pub elusiv_program: AccountInfo<'a>,
+
+    pub sender: AccountInfo<'a>,
+    pub sender_account: AccountInfo<'a>,
+    pub fee_payer: AccountInfo<'a>,
+    pub fee_payer_account: AccountInfo<'a>,
+    pub pool: AccountInfo<'a>,
+    pub pool_account: AccountInfo<'a>,
+    pub fee_collector: AccountInfo<'a>,
+    pub fee_collector_account: AccountInfo<'a>,
+    pub sol_price_account: AccountInfo<'a>,
+    pub token_price_account: AccountInfo<'a>,
+    pub governor: AccountInfo<'a>,
+    pub fee: AccountInfo<'a>,
+    pub hashing_account: AccountInfo<'a>,
+    pub buffer: AccountInfo<'a>,
+    pub token_program: AccountInfo<'a>,
+    pub mint_account: AccountInfo<'a>,
+    pub system_program: AccountInfo<'a>,
+    pub event_sequence: AccountInfo<'a>,
+    pub stats: AccountInfo<'a>,
+    pub epoch_metrics: AccountInfo<'a>,
+}
+
+impl<'a> StoreBaseCommitmentAccounts<'a> {
+    fn account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(*self.sender.key, true),
+            AccountMeta::new(*self.sender_account.key, false),
+            AccountMeta::new(*self.fee_payer.key, true),
+            AccountMeta::new(*self.fee_payer_account.key, false),
+            AccountMeta::new(*self.pool.key, false),
+            AccountMeta::new(*self.pool_account.key, false),
+            AccountMeta::new(*self.fee_collector.key, false),
+            AccountMeta::new(*self.fee_collector_account.key, false),
+            AccountMeta::new_readonly(*self.sol_price_account.key, false),
+            AccountMeta::new_readonly(*self.token_price_account.key, false),
+            AccountMeta::new_readonly(*self.governor.key, false),
+            AccountMeta::new_readonly(*self.fee.key, false),
+            AccountMeta::new(*self.hashing_account.key, false),
+            AccountMeta::new(*self.buffer.key, false),
+            AccountMeta::new_readonly(*self.token_program.key, false),
+            AccountMeta::new_readonly(*self.mint_account.key, false),
+            AccountMeta::new_readonly(*self.system_program.key, false),
+            AccountMeta::new(*self.event_sequence.key, false),
+            AccountMeta::new(*self.stats.key, false),
+            AccountMeta::new(*self.epoch_metrics.key, false),
+        ]
+    }
+
+    fn account_infos(&self) -> Vec<AccountInfo<'a>> {
+        vec![
+            self.sender.clone(),
+            self.sender_account.clone(),
+            self.fee_payer.clone(),
+            self.fee_payer_account.clone(),
+            self.pool.clone(),
+            self.pool_account.clone(),
+            self.fee_collector.clone(),
+            self.fee_collector_account.clone(),
+            self.sol_price_account.clone(),
+            self.token_price_account.clone(),
+            self.governor.clone(),
+            self.fee.clone(),
+            self.hashing_account.clone(),
+            self.buffer.clone(),
+            self.token_program.clone(),
+            self.mint_account.clone(),
+            self.system_program.clone(),
+            self.event_sequence.clone(),
+            self.stats.clone(),
+            self.epoch_metrics.clone(),
+            self.elusiv_program.clone(),
+        ]
+    }
+}
+
+/// Builds the [`ElusivInstruction::StoreBaseCommitment`] instruction from the supplied `accounts`
+/// - in contrast to the client-side instruction builder, no PDAs are derived (they are verified by the Elusiv program)
+pub fn store_base_commitment_instruction(
+    accounts: &StoreBaseCommitmentAccounts,
+    hash_account_index: u32,
+    hash_account_bump: u8,
+    request: BaseCommitmentHashRequest,
+) -> Result<Instruction, ProgramError> {
+    let data = ElusivInstruction::StoreBaseCommitment {
+        hash_account_index,
+        hash_account_bump,
+        request,
+    }
+    .try_to_vec()?;
+
+    Ok(Instruction::new_with_bytes(
+        crate::id(),
+        &data,
+        accounts.account_metas(),
+    ))
+}
+
+/// Deposits `request.amount` from `accounts.sender` into the shielded pool
+/// - `signers_seeds` need to contain the seeds of all signing PDAs of the invoking program (`sender` and/or `fee_payer`)
+pub fn store_base_commitment(
+    accounts: &StoreBaseCommitmentAccounts,
+    hash_account_index: u32,
+    hash_account_bump: u8,
+    request: BaseCommitmentHashRequest,
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    guard!(
+        *accounts.elusiv_program.key == crate::ID,
+        ElusivError::InvalidAccount
+    );
+
+    let instruction = store_base_commitment_instruction(
+        accounts,
+        hash_account_index,
+        hash_account_bump,
+        request,
+    )?;
+    invoke_signed(&instruction, &accounts.account_infos(), signers_seeds)
+}
+
+#[cfg(all(test, feature = "elusiv-client"))]
+mod tests {
+    use super::*;
+    use crate::instruction::{
+        SignerAccount, UserAccount, WritableSignerAccount, WritableUserAccount,
+    };
+    use crate::types::RawU256;
+    use assert_matches::assert_matches;
+    use solana_program::pubkey::Pubkey;
+
+    fn test_request() -> BaseCommitmentHashRequest {
+        BaseCommitmentHashRequest {
+            base_commitment: RawU256::new([1; 32]),
+            commitment_index: 0,
+            amount: 1_000_000,
+            token_id: 0,
+            commitment: RawU256::new([2; 32]),
+            fee_version: 1,
+            min_batching_rate: 0,
+            fee_payer_repayment: 0,
+            anonymity_delay_slots: 0,
+            is_priority: false,
+        }
+    }
+
+    #[test]
+    fn test_store_base_commitment_instruction() {
+        let sender = Pubkey::new_unique();
+        let fee_payer = Pubkey::new_unique();
+        let hashing_account = Pubkey::new_unique();
+        let system_program = solana_program::system_program::id();
+
+        // The instruction created by the client-side builder contains the expected PDAs
+        let expected = ElusivInstruction::store_base_commitment_instruction(
+            3,
+            255,
+            test_request(),
+            SignerAccount(sender),
+            WritableUserAccount(sender),
+            WritableSignerAccount(fee_payer),
+            WritableUserAccount(fee_payer),
+            WritableUserAccount(Pubkey::new_unique()),
+            WritableUserAccount(Pubkey::new_unique()),
+            UserAccount(Pubkey::new_unique()),
+            UserAccount(Pubkey::new_unique()),
+            WritableUserAccount(hashing_account),
+            UserAccount(system_program),
+            UserAccount(system_program),
+        );
+
+        let mut lamports = vec![0; expected.accounts.len()];
+        let mut data = vec![Vec::new(); expected.accounts.len()];
+        let infos: Vec<AccountInfo> = expected
+            .accounts
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((meta, lamports), data)| {
+                AccountInfo::new(
+                    &meta.pubkey,
+                    false,
+                    false,
+                    lamports,
+                    data,
+                    &system_program,
+                    false,
+                    0,
+                )
+            })
+            .collect();
+
+        let program_id = crate::id();
+        let mut program_lamports = 0;
+        let mut program_data = Vec::new();
+        let elusiv_program = AccountInfo::new(
+            &program_id,
+            false,
+            false,
+            &mut program_lamports,
+            &mut program_data,
+            &system_program,
+            true,
+            0,
+        );
+
+        let accounts = StoreBaseCommitmentAccounts {
+            elusiv_program,
+            sender: infos[0].clone(),
+            sender_account: infos[1].clone(),
+            fee_payer: infos[2].clone(),
+            fee_payer_account: infos[3].clone(),
+            pool: infos[4].clone(),
+            pool_account: infos[5].clone(),
+            fee_collector: infos[6].clone(),
+            fee_collector_account: infos[7].clone(),
+            sol_price_account: infos[8].clone(),
+            token_price_account: infos[9].clone(),
+            governor: infos[10].clone(),
+            fee: infos[11].clone(),
+            hashing_account: infos[12].clone(),
+            buffer: infos[13].clone(),
+            token_program: infos[14].clone(),
+            mint_account: infos[15].clone(),
+            system_program: infos[16].clone(),
+            event_sequence: infos[17].clone(),
+            stats: infos[18].clone(),
+            epoch_metrics: infos[19].clone(),
+        };
+
+        assert_eq!(
+            store_base_commitment_instruction(&accounts, 3, 255, test_request()).unwrap(),
+            expected
+        );
+        assert_eq!(accounts.account_infos().len(), expected.accounts.len() + 1);
+
+        // Invalid program account
+        let mut accounts = accounts;
+        accounts.elusiv_program = infos[0].clone();
+        assert_matches!(
+            store_base_commitment(&accounts, 3, 255, test_request(), &[]),
+            Err(_)
+        );
+    }
+}
@@ -58,6 +58,75 @@ pub enum ElusivError {
     // Accounts
     SubAccountAlreadyExists,
     SubAccouttDoesNotExists,
+
+    // Partial computations
+    ComputationIsNotTimedOut,
+
+    // Monitoring
+    HealthCheckFailed,
+
+    // Recurring payments
+    InstallmentIsNotYetDue,
+    RecurringPaymentIsCompleted,
+
+    // Pending payments
+    PaymentIsExpired,
+    PaymentIsNotYetExpired,
+    InvalidHashlockPreimage,
+
+    // Anonymity delay
+    AnonymityDelayIsNotElapsed,
+
+    // Emergency mode
+    EmergencyModeIsActive,
+
+    // Crowdfunding
+    CrowdfundTargetIsNotReached,
+
+    // Nullifier-set seals
+    SealIsNotAllowed,
+    MerkleTreeIsSealed,
+
+    // State digests
+    StateDigestIsAlreadyAttested,
+
+    // Recipient denylist
+    RecipientIsDenied,
+
+    // Tree rent refunds
+    MerkleTreeIsNotArchived,
+
+    // Token registry
+    TokenIsNotSupported,
+
+    // Emergency pause
+    ProgramIsHalted,
+
+    // Governance multisig
+    MissingGovernanceApprovals,
+
+    // Warden registration
+    WardenIsNotRegistered,
+
+    // Deposit caps
+    EpochDepositCapIsExceeded,
+
+    // Timelocked withdrawals
+    WithdrawalTimelockIsRequired,
+    WithdrawalIsNotYetUnlocked,
+
+    // Poseidon parameters
+    PoseidonParamsAreNotFrozen,
+
+    // Arithmetic
+    Overflow,
+
+    // Crowdfund refunds
+    CrowdfundDeadlineIsNotReached,
+    CrowdfundFunderLimitIsReached,
+
+    // Withdraw-and-call refunds
+    WithdrawAndCallIsNotTimedOut,
 }
 
 #[cfg(not(tarpaulin_include))]
@@ -0,0 +1,214 @@
+use crate::macros::BorshSerDeSized;
+use crate::types::{EncryptedMemo, U256};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// Version of the binary event layout
+/// - needs to be incremented with every breaking change to [`EventHeader`] or [`ElusivEvent`]
+pub const EVENT_SCHEMA_VERSION: u8 = 1;
+
+/// Precedes every [`ElusivEvent`] in the program logs
+///
+/// # Notes
+///
+/// Events are emitted with `sol_log_data` as two separate fields: the serialized [`EventHeader`] followed by the serialized [`ElusivEvent`].
+/// The `sequence` is strictly increasing (without gaps), which allows indexers to detect missed events.
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct EventHeader {
+    pub schema_version: u8,
+    pub sequence: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum ElusivEvent {
+    /// A base-commitment has been stored and `amount` has been transferred into the pool
+    Deposit {
+        base_commitment: U256,
+        commitment: U256,
+        amount: u64,
+        token_id: u16,
+        fee_version: u32,
+    },
+
+    /// A proof has been successfully verified and `amount` has been transferred to the `recipient`
+    /// - emitted for each recipient of [`crate::types::BatchPayouts`] (all with the same `output_commitment`)
+    Withdrawal {
+        recipient: Pubkey,
+        amount: u64,
+        token_id: u16,
+        fee_version: u32,
+
+        /// The commitment enqueued for insertion into the MT
+        output_commitment: U256,
+    },
+
+    /// The root of the active MT has changed (after a commitment batch has been inserted or the active MT has been reset)
+    /// - roots are in mr-form (as stored in the [`crate::state::storage::StorageAccount`])
+    RootUpdate {
+        mt_index: u32,
+        new_root: U256,
+        previous_root: U256,
+
+        /// Amount of inserted commitments (zero if the active MT has been reset)
+        batch_size: u32,
+        slot: u64,
+    },
+
+    /// The next installment of a recurring payment has been released
+    /// - the released proof-request can now be verified using the regular proof verification
+    RecurringInstallmentReleased {
+        owner: Pubkey,
+        recurring_payment_index: u32,
+        installment_index: u32,
+
+        /// Hash of the serialized proof-request
+        installment: U256,
+        slot: u64,
+    },
+
+    /// A send without an external payout has been verified (zero `amount`)
+    /// - the `output_commitment` is addressed either to the sender (merge) or to another user (internal transfer), which is indistinguishable on-chain
+    ShieldedTransfer {
+        token_id: u16,
+        fee_version: u32,
+        output_commitment: U256,
+    },
+
+    /// Attestation of the `reserves` held by the pool versus the `liabilities` implied by the protocol accounting (the TVL)
+    ReservesAttestation {
+        token_id: u16,
+        epoch: u64,
+        reserves: u64,
+        liabilities: u64,
+        is_solvent: bool,
+    },
+
+    /// The sealing of the nullifier-set of a closed MT of the pool of `token_id` has been announced
+    /// - any nullifier activity in the MT before the `execution_slot` cancels the sealing
+    NullifierSetSealAnnounced {
+        token_id: u16,
+        mt_index: u32,
+        execution_slot: u64,
+    },
+
+    /// The digest of the program state has been attested for `epoch` (see [`crate::state::summary::StateSummaryAccount::state_digest`])
+    StateDigest { epoch: u64, digest: U256 },
+
+    /// The nullifier-set of a closed MT of the pool of `token_id` has been sealed (no further spends from the MT are possible)
+    NullifierSetSealed { token_id: u16, mt_index: u32 },
+
+    /// An entry of the commitment queue of `token_id` has been skipped, reordered or expired
+    CommitmentQueueAudit {
+        token_id: u16,
+        commitment: U256,
+        enqueue_slot: u64,
+        action: QueueAuditAction,
+
+        /// The program error code that caused the `action`
+        reason: u32,
+    },
+
+    /// A child-account of the sealed nullifier-set of an archived MT has been closed and its rent transferred to the fee-collector
+    RentReclaimed {
+        mt_index: u32,
+        child_index: u32,
+        amount: u64,
+    },
+
+    /// A commitment has been inserted into the active MT of `token_id` (emitted for each commitment of a batch, before the batch's [`ElusivEvent::RootUpdate`])
+    /// - the `commitment` has the same form as in [`ElusivEvent::Deposit`]
+    CommitmentInsertion {
+        token_id: u16,
+        mt_index: u32,
+        leaf_index: u32,
+        commitment: U256,
+    },
+
+    /// A nullifier-hash has been inserted into the nullifier-set of an MT, i.e. the corresponding commitment has been spent
+    NullifierSpend {
+        token_id: u16,
+        mt_index: u32,
+        nullifier_hash: U256,
+    },
+
+    /// The encrypted memo of the proof-request of the preceding [`ElusivEvent::Withdrawal`]
+    WithdrawalMemo {
+        recipient: Pubkey,
+        encrypted_memo: EncryptedMemo,
+    },
+}
+
+/// Describes why a [`ElusivEvent::CommitmentQueueAudit`] has been emitted
+/// - the commitment queue is strictly FIFO and entries never expire, so currently only skips occur
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum QueueAuditAction {
+    /// The entry could not be placed into the next commitment batch
+    Skipped,
+
+    /// The entry has been placed into a batch ahead of earlier entries
+    Reordered,
+
+    /// The entry has been removed from the queue without being hashed
+    Expired,
+}
+
+impl ElusivEvent {
+    /// Decodes the fields of a `sol_log_data` entry emitted by the program
+    /// - returns `None` for unknown schema versions
+    #[cfg(feature = "elusiv-client")]
+    pub fn decode(header: &[u8], event: &[u8]) -> Option<(EventHeader, Self)> {
+        let header = EventHeader::try_from_slice(header).ok()?;
+        if header.schema_version != EVENT_SCHEMA_VERSION {
+            return None;
+        }
+
+        Some((header, Self::try_from_slice(event).ok()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytes::BorshSerDeSized;
+
+    #[test]
+    fn test_event_header_size() {
+        assert_eq!(EventHeader::SIZE, 1 + 8);
+    }
+
+    #[test]
+    fn test_decode() {
+        let header = EventHeader {
+            schema_version: EVENT_SCHEMA_VERSION,
+            sequence: 7,
+        };
+        let event = ElusivEvent::Deposit {
+            base_commitment: [1; 32],
+            commitment: [2; 32],
+            amount: 123,
+            token_id: 1,
+            fee_version: 0,
+        };
+
+        assert_eq!(
+            ElusivEvent::decode(&header.try_to_vec().unwrap(), &event.try_to_vec().unwrap()),
+            Some((header, event.clone()))
+        );
+
+        // Unknown schema version
+        let header = EventHeader {
+            schema_version: EVENT_SCHEMA_VERSION + 1,
+            sequence: 7,
+        };
+        assert_eq!(
+            ElusivEvent::decode(&header.try_to_vec().unwrap(), &event.try_to_vec().unwrap()),
+            None
+        );
+    }
+}
@@ -3,24 +3,57 @@
 use super::processor;
 use super::processor::BaseCommitmentHashRequest;
 use crate::macros::*;
-use crate::processor::{FinalizeSendData, ProofRequest, VKeyAccountDataPacket, MAX_MT_COUNT};
+use crate::processor::{
+    pooled_verification_account_index, FinalizeSendData, ProofRequest, ReallocatableAccountKind,
+    VKeyAccountDataPacket, WithdrawAndCall, MAX_MT_COUNT,
+};
 use crate::state::{
+    child::{ChildAccountParent, ChildAccountRecordAccount},
     commitment::{
         BaseCommitmentBufferAccount, BaseCommitmentHashingAccount, CommitmentHashingAccount,
     },
-    fee::{FeeAccount, ProgramFee},
-    governor::{FeeCollectorAccount, GovernorAccount, PoolAccount},
-    nullifier::NullifierAccount,
-    proof::VerificationAccount,
-    queue::CommitmentQueueAccount,
-    storage::StorageAccount,
+    config::ConfigAccount,
+    crowdfund::CrowdfundAccount,
+    denylist::{
+        recipient_denylist_offset, recipient_denylist_shard, RecipientDenylistAccount,
+        MAX_DENIED_RECIPIENTS_BATCH,
+    },
+    escrow::DepositEscrowAccount,
+    event::EventSequenceAccount,
+    fee::{FeeAccount, NetworkFeeSplit, ProgramFee, TokenFeeSchedule},
+    governor::{
+        FeeCollectorAccount, GovernanceMultisig, GovernorAccount, PoolAccount,
+        REVENUE_DESTINATION_COUNT,
+    },
+    note::{NoteCiphertextAccount, NOTE_CIPHERTEXT_SIZE},
+    nullifier::{
+        nullifier_account_offsets, ArchivedTreeAccount, NullifierAccount,
+        TreeRentContributionAccount,
+    },
+    pending::PendingPaymentAccount,
+    poseidon::{PoseidonParamsAccount, POSEIDON_PARAMS_CHUNK_SIZE},
+    proof::{BatchVerificationAccount, VerificationAccount, VerificationAccountPoolAccount},
+    queue::{CommitmentQueueAccount, CommitmentSpillQueueAccount},
+    recurring::{RecurringPaymentAccount, MAX_RECURRING_INSTALLMENTS},
+    stats::{EpochMetricsAccount, StatsAccount, STATS_TOKEN_COUNT},
+    storage::{token_pool_offset, StorageAccount},
+    summary::{StateDigestHistoryAccount, StateSummaryAccount},
+    viewing_key::{ViewingKeyRegistryAccount, ENCRYPTED_VIEWING_KEY_SIZE},
     vkey::VKeyAccount,
+    warden::WardenAccount,
+    withdrawal::PendingWithdrawalAccount,
 };
-use crate::types::Proof;
+use crate::token::TokenID;
+use crate::types::{Proof, RawU256, U256};
 use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_types::{AccountRepr, ElusivOption};
 use solana_program::{pubkey::Pubkey, system_program, sysvar::instructions};
 
+/// Token-ids of the SPL-token pools validated by commitment batching rate changes
+/// - `USDC_TOKEN_ID` and `USDT_TOKEN_ID` are only generated for clients
+const USDC_POOL_TOKEN_ID: TokenID = 1;
+const USDT_POOL_TOKEN_ID: TokenID = 2;
+
 #[cfg(feature = "elusiv-client")]
 pub use elusiv_types::accounts::{
     SignerAccount, UserAccount, WritableSignerAccount, WritableUserAccount,
@@ -43,10 +76,15 @@ pub enum ElusivInstruction {
     #[acc(sol_price_account)]
     #[acc(token_price_account)]
     #[pda(governor, GovernorAccount)]
+    #[pda(fee, FeeAccount, pda_offset = Some(request.fee_version))]
     #[acc(hashing_account, { writable })]
     #[pda(buffer, BaseCommitmentBufferAccount, { writable })]
-    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` } (SPL-token or Token-2022)
+    #[acc(mint_account)] // if `token_id = 0` { `system_program` } else { `mint_account` }
     #[sys(system_program, key = system_program::ID)]
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
+    #[pda(stats, StatsAccount, { writable })]
+    #[pda(epoch_metrics, EpochMetricsAccount, { writable })]
     StoreBaseCommitment {
         hash_account_index: u32,
         hash_account_bump: u8,
@@ -54,37 +92,63 @@ pub enum ElusivInstruction {
     },
 
     #[pda(hashing_account, BaseCommitmentHashingAccount, pda_offset = Some(hash_account_index), { writable })]
+    #[pda(poseidon_params, PoseidonParamsAccount)]
     ComputeBaseCommitmentHash { hash_account_index: u32 },
 
     #[acc(original_fee_payer, { writable })]
+    #[acc(original_fee_payer_account, { writable })] // if `token_id = 0` { `original_fee_payer` }
     #[pda(pool, PoolAccount, { writable, account_info })]
+    #[acc(pool_account, { writable })]
     #[pda(fee, FeeAccount, pda_offset = Some(fee_version))]
     #[pda(hashing_account, BaseCommitmentHashingAccount, pda_offset = Some(hash_account_index), { writable, account_info })]
-    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(commitment_spill_queue, CommitmentSpillQueueAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(config, ConfigAccount)]
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
     FinalizeBaseCommitmentHash {
         hash_account_index: u32,
         fee_version: u32,
+        token_id: u16,
     },
 
     // -------- Commitment hashing --------
-    /// Hashes commitments in a new MT-root
-    #[pda(commitment_hashing_account, CommitmentHashingAccount, { writable })]
-    #[pda(storage_account, StorageAccount, { include_child_accounts })]
-    InitCommitmentHashSetup { insertion_can_fail: bool },
+    /// Hashes commitments in a new MT-root (of the shielded pool of `token_id`)
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(token_id), { include_child_accounts })]
+    InitCommitmentHashSetup {
+        insertion_can_fail: bool,
+        token_id: u16,
+    },
 
-    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
-    #[pda(commitment_hashing_account, CommitmentHashingAccount, { writable })]
-    InitCommitmentHash { insertion_can_fail: bool },
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
+    InitCommitmentHash {
+        insertion_can_fail: bool,
+        token_id: u16,
+    },
 
     #[acc(fee_payer, { writable, signer })]
     #[pda(fee, FeeAccount, pda_offset = Some(fee_version))]
     #[pda(pool, PoolAccount, { writable, account_info })]
-    #[pda(commitment_hashing_account, CommitmentHashingAccount, { writable })]
-    ComputeCommitmentHash { fee_version: u32, nonce: u32 },
+    #[pda(fee_collector, FeeCollectorAccount, { writable, account_info })]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(poseidon_params, PoseidonParamsAccount)]
+    #[pda(governor, GovernorAccount)]
+    #[pda(warden_account, WardenAccount, pda_pubkey = fee_payer.pubkey(), { account_info, skip_pda_verification })]
+    #[sys(instructions_account, key = instructions::ID)]
+    ComputeCommitmentHash {
+        fee_version: u32,
+        nonce: u32,
+        token_id: u16,
+    },
 
-    #[pda(commitment_hashing_account, CommitmentHashingAccount, { writable })]
-    #[pda(storage_account, StorageAccount, { include_child_accounts, writable })]
-    FinalizeCommitmentHash,
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(token_id), { include_child_accounts, writable })]
+    #[pda(state_summary, StateSummaryAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(stats, StatsAccount, { writable })]
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
+    FinalizeCommitmentHash { token_id: u16 },
 
     // -------- Proof Verification --------
     /// Proof verification initialization
@@ -94,9 +158,12 @@ pub enum ElusivInstruction {
     #[acc(nullifier_duplicate_account, { writable })]
     #[sys(system_program, key = system_program::ID, { ignore })]
     #[acc(identifier_account)]
-    #[pda(storage_account, StorageAccount)]
-    #[pda(nullifier_account0, NullifierAccount, pda_offset = Some(tree_indices[0]), { include_child_accounts })]
-    #[pda(nullifier_account1, NullifierAccount, pda_offset = Some(tree_indices[1]), { include_child_accounts })]
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(request.token_id()))]
+    #[pda(nullifier_account0, NullifierAccount, pda_offsets = nullifier_account_offsets(request.token_id(), tree_indices[0]), { include_child_accounts })]
+    #[pda(nullifier_account1, NullifierAccount, pda_offsets = nullifier_account_offsets(request.token_id(), tree_indices[1]), { include_child_accounts })]
+    #[pda(governor, GovernorAccount)]
+    #[pda(warden_account, WardenAccount, pda_pubkey = fee_payer.pubkey(), { account_info, skip_pda_verification })]
+    #[remaining(archived_tree_accounts)]
     InitVerification {
         verification_account_index: u8,
         vkey_id: u32,
@@ -141,10 +208,13 @@ pub enum ElusivInstruction {
     #[acc(identifier_account)]
     #[acc(transaction_reference_account)]
     #[acc(original_fee_payer, { ignore })]
-    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = token_pool_offset(data.token_id), { writable })]
     #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
-    #[pda(storage_account, StorageAccount)]
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(data.token_id))]
+    #[pda(recipient_denylist, RecipientDenylistAccount, pda_offset = recipient_denylist_offset(recipient_denylist_shard(&recipient.pubkey())))]
+    #[pda(governor, GovernorAccount)]
     #[sys(instructions_account, key = instructions::ID)]
+    #[remaining(lookup_accounts)]
     FinalizeVerificationSend {
         verification_account_index: u8,
         data: FinalizeSendData,
@@ -153,7 +223,10 @@ pub enum ElusivInstruction {
 
     #[acc(original_fee_payer, { ignore })]
     #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
-    #[pda(nullifier_account, NullifierAccount, pda_offset = Some(verification_account.get_tree_indices(0)), { writable, include_child_accounts, skip_abi })]
+    #[pda(nullifier_account, NullifierAccount, pda_offsets = nullifier_account_offsets(verification_account.get_other_data().token_id, verification_account.get_tree_indices(0)), { writable, include_child_accounts, skip_abi })]
+    #[pda(state_summary, StateSummaryAccount, pda_offset = token_pool_offset(verification_account.get_other_data().token_id), { writable, skip_abi })]
+    #[pda(stats, StatsAccount, { writable })]
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
     FinalizeVerificationInsertNullifier { verification_account_index: u8 },
 
     #[acc(original_fee_payer, { signer, writable })]
@@ -165,6 +238,12 @@ pub enum ElusivInstruction {
     #[acc(nullifier_duplicate_account, { writable, owned })]
     #[sys(system_program, key = system_program::ID, { ignore })]
     #[sys(instructions_account, key = instructions::ID)]
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
+    #[pda(stats, StatsAccount, { writable })]
+    #[pda(epoch_metrics, EpochMetricsAccount, { writable })]
+    #[pda(governor, GovernorAccount)]
+    #[pda(warden_account, WardenAccount, pda_pubkey = original_fee_payer.pubkey(), { writable, account_info, skip_pda_verification })]
+    #[remaining(batch_payout_recipients, { writable })]
     FinalizeVerificationTransferLamports { verification_account_index: u8 },
 
     #[acc(original_fee_payer, { signer, writable })]
@@ -175,7 +254,7 @@ pub enum ElusivInstruction {
     #[acc(pool_account, { writable })]
     #[pda(fee_collector, FeeCollectorAccount, { account_info, writable })]
     #[acc(fee_collector_account, { writable })]
-    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = token_pool_offset(token_id), { writable })]
     #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable, account_info })]
     #[acc(nullifier_duplicate_account, { writable, owned })]
     #[sys(a_token_program, key = spl_associated_token_account::ID, { ignore })]
@@ -183,7 +262,95 @@ pub enum ElusivInstruction {
     #[sys(system_program, key = system_program::ID, { ignore })]
     #[acc(mint_account)]
     #[sys(instructions_account, key = instructions::ID)]
-    FinalizeVerificationTransferToken { verification_account_index: u8 },
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
+    #[pda(stats, StatsAccount, { writable })]
+    #[pda(epoch_metrics, EpochMetricsAccount, { writable })]
+    #[pda(warden_account, WardenAccount, pda_pubkey = original_fee_payer.pubkey(), { writable, account_info, skip_pda_verification })]
+    FinalizeVerificationTransferToken {
+        verification_account_index: u8,
+        token_id: u16,
+    },
+
+    /// Resets an abandoned verification (see [`processor::reset_timed_out_verification`])
+    #[acc(resetter, { writable, signer })]
+    #[acc(original_fee_payer, { writable })]
+    #[pda(pool, PoolAccount, { account_info, writable })]
+    #[acc(pool_account, { writable })]
+    #[pda(fee_collector, FeeCollectorAccount, { account_info, writable })]
+    #[acc(fee_collector_account, { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable, account_info })]
+    #[acc(nullifier_duplicate_account, { writable, owned })]
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    #[pda(warden_account, WardenAccount, pda_pubkey = original_fee_payer.pubkey(), { writable, account_info, skip_pda_verification })]
+    ResetTimedOutVerification { verification_account_index: u8 },
+
+    /// Moves spilled commitments back into the commitment queue of `token_id` once it has room again
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(commitment_spill_queue, CommitmentSpillQueueAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(config, ConfigAccount)]
+    DrainCommitmentSpillQueue { token_id: u16 },
+
+    // -------- Batch proof verification --------
+    /// Batches proofs (of the same fee-payer and vkey) to share a single final exponentiation
+    #[acc(fee_payer, { writable, signer })]
+    #[pda(batch_verification_account, BatchVerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(batch_verification_account_index.into()), { writable, account_info, find_pda })]
+    #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id))]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    InitBatchVerification {
+        batch_verification_account_index: u8,
+        vkey_id: u32,
+    },
+
+    #[acc(fee_payer, { signer })]
+    #[pda(batch_verification_account, BatchVerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(batch_verification_account_index.into()), { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
+    AddBatchVerificationProof {
+        batch_verification_account_index: u8,
+        verification_account_index: u8,
+    },
+
+    /// Batch verification computation (`verification_account_index` is the first proof of the batch)
+    #[acc(original_fee_payer, { ignore })]
+    #[pda(batch_verification_account, BatchVerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(batch_verification_account_index.into()), { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
+    #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { include_child_accounts })]
+    #[sys(instructions_account, key = instructions::ID)]
+    ComputeBatchVerification {
+        batch_verification_account_index: u8,
+        verification_account_index: u8,
+        vkey_id: u32,
+    },
+
+    #[acc(original_fee_payer, { writable })]
+    #[pda(batch_verification_account, BatchVerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(batch_verification_account_index.into()), { writable, account_info })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = original_fee_payer.pubkey(), pda_offset = Some(verification_account_index.into()), { writable })]
+    FinalizeBatchVerification {
+        batch_verification_account_index: u8,
+        verification_account_index: u8,
+    },
+
+    // -------- Pooled verification accounts --------
+    #[acc(fee_payer, { writable, signer })]
+    #[pda(verification_account_pool, VerificationAccountPoolAccount, pda_pubkey = fee_payer.pubkey(), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenVerificationAccountPool,
+
+    #[acc(fee_payer, { writable, signer })]
+    #[pda(verification_account_pool, VerificationAccountPoolAccount, pda_pubkey = fee_payer.pubkey(), { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(pooled_verification_account_index(slot).into()), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenPooledVerificationAccount { slot: u8 },
+
+    #[acc(fee_payer, { writable, signer })]
+    #[pda(verification_account_pool, VerificationAccountPoolAccount, pda_pubkey = fee_payer.pubkey(), { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(pooled_verification_account_index(slot).into()), { writable, account_info })]
+    #[sys(system_program, key = system_program::ID)]
+    AcquirePooledVerificationAccount { slot: u8 },
+
+    #[acc(fee_payer, { writable, signer })]
+    #[pda(verification_account_pool, VerificationAccountPoolAccount, pda_pubkey = fee_payer.pubkey(), { writable })]
+    #[pda(verification_account, VerificationAccount, pda_pubkey = fee_payer.pubkey(), pda_offset = Some(pooled_verification_account_index(slot).into()), { writable, account_info })]
+    ReleasePooledVerificationAccount { slot: u8 },
 
     // -------- Verifying key management --------
     #[acc(signer, { writable, signer })]
@@ -214,6 +381,10 @@ pub enum ElusivInstruction {
         packet: VKeyAccountDataPacket,
     },
 
+    #[acc(signer, { signer })]
+    #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { writable, include_child_accounts })]
+    ComputeVkeyChecksum { vkey_id: u32 },
+
     #[acc(signer, { signer })]
     #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { writable })]
     FreezeVkey { vkey_id: u32 },
@@ -222,20 +393,88 @@ pub enum ElusivInstruction {
     #[pda(vkey_account, VKeyAccount, pda_offset = Some(vkey_id), { writable })]
     ChangeVkeyAuthority { vkey_id: u32, authority: Pubkey },
 
+    #[acc(payer, { writable, signer })]
+    #[pda(governor, GovernorAccount, { writable })]
+    #[pda(vkey_account, VKeyAccount, pda_offset = Some(staged_vkey_id), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    #[remaining(signers, min = 1, { signer })]
+    StageVkeyUpgrade {
+        vkey_id: u32,
+        staged_vkey_id: u32,
+        checksum: U256,
+    },
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[pda(vkey_account, VKeyAccount, pda_offset = Some(staged_vkey_id))]
+    #[remaining(signers, min = 1, { signer })]
+    ActivateVkeyUpgrade {
+        vkey_id: u32,
+        staged_vkey_id: u32,
+        acceptance_window: u64,
+    },
+
     // -------- MT management --------
-    /// Set the next MT as the active MT
-    #[pda(storage_account, StorageAccount, { writable, include_child_accounts })]
-    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
-    #[pda(active_nullifier_account, NullifierAccount, pda_offset = Some(active_mt_index), { writable })]
-    ResetActiveMerkleTree { active_mt_index: u32 },
+    /// Set the next MT as the active MT (of the shielded pool of `token_id`)
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(token_id), { writable, include_child_accounts })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(active_nullifier_account, NullifierAccount, pda_offsets = nullifier_account_offsets(token_id, active_mt_index), { writable })]
+    #[pda(state_summary, StateSummaryAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
+    ResetActiveMerkleTree { active_mt_index: u32, token_id: u16 },
+
+    /// Closes the full active MT and opens the next MT (and its `NullifierAccount`) of the shielded pool of `token_id`
+    #[acc(payer, { writable, signer })]
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(token_id), { writable, include_child_accounts })]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = token_pool_offset(token_id))]
+    #[pda(active_nullifier_account, NullifierAccount, pda_offsets = nullifier_account_offsets(token_id, active_mt_index), { writable })]
+    #[pda(next_nullifier_account, NullifierAccount, pda_offsets = nullifier_account_offsets(token_id, active_mt_index + 1), { writable, skip_pda_verification, account_info })]
+    #[pda(rent_contribution, TreeRentContributionAccount, pda_pubkey = payer.pubkey(), pda_offsets = nullifier_account_offsets(token_id, active_mt_index + 1), { writable, account_info, find_pda })]
+    #[pda(state_summary, StateSummaryAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    RotateMerkleTree { active_mt_index: u32, token_id: u16 },
 
-    /// Archives a `NullifierAccount` into a N-SMT
+    /// Archives the root and nullifier-set digest of a closed MT into an `ArchivedTreeAccount`
     #[acc(payer, { writable, signer })]
-    #[pda(storage_account, StorageAccount, { writable, include_child_accounts })]
-    #[pda(nullifier_account, NullifierAccount, pda_offset = Some(closed_mt_index), { writable, include_child_accounts })]
-    #[acc(archived_tree_account, { writable })]
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(token_id))]
+    #[pda(nullifier_account, NullifierAccount, pda_offsets = nullifier_account_offsets(token_id, closed_mt_index), { include_child_accounts })]
+    #[pda(archived_tree_account, ArchivedTreeAccount, pda_offsets = nullifier_account_offsets(token_id, closed_mt_index), { writable, account_info, find_pda })]
     #[sys(system_program, key = system_program::ID, { ignore })]
-    ArchiveClosedMerkleTree { closed_mt_index: u32 },
+    ArchiveClosedMerkleTree { closed_mt_index: u32, token_id: u16 },
+
+    /// Refunds a portion of the rent contributed to an archived MT (once all child-accounts of the MT are closed)
+    #[acc(contributor, { writable, signer })]
+    #[pda(rent_contribution, TreeRentContributionAccount, pda_pubkey = contributor.pubkey(), pda_offsets = nullifier_account_offsets(token_id, mt_index), { writable, account_info })]
+    #[pda(nullifier_account, NullifierAccount, pda_offsets = nullifier_account_offsets(token_id, mt_index), { writable })]
+    #[pda(fee_collector, FeeCollectorAccount, { writable, account_info })]
+    ClaimTreeRentRefund { mt_index: u32, token_id: u16 },
+
+    /// Announces the sealing of the nullifier-set of a closed MT of the pool of `token_id`
+    /// - `signers` need to satisfy the governance authority
+    #[pda(governor, GovernorAccount)]
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(token_id))]
+    #[pda(nullifier_account, NullifierAccount, pda_offsets = nullifier_account_offsets(token_id, closed_mt_index), { writable })]
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    AnnounceNullifierSetSeal { closed_mt_index: u32, token_id: u16 },
+
+    /// Seals an announced nullifier-set after its timelock
+    #[pda(nullifier_account, NullifierAccount, pda_offsets = nullifier_account_offsets(token_id, closed_mt_index), { writable })]
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
+    SealNullifierSet { closed_mt_index: u32, token_id: u16 },
+
+    /// Closes a child-account of the sealed `NullifierAccount` of an archived MT (the rent is transferred to the fee collector)
+    #[pda(fee_collector, FeeCollectorAccount, { writable, account_info })]
+    #[pda(nullifier_account, NullifierAccount, pda_offsets = nullifier_account_offsets(token_id, mt_index), { writable })]
+    #[pda(archived_tree_account, ArchivedTreeAccount, pda_offsets = nullifier_account_offsets(token_id, mt_index))]
+    #[acc(sub_account, { writable })]
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
+    CloseNullifierChildAccount {
+        mt_index: u32,
+        child_index: u32,
+        token_id: u16,
+    },
 
     // -------- Program state management --------
     #[acc(payer, { writable, signer })]
@@ -243,46 +482,311 @@ pub enum ElusivInstruction {
     #[pda(fee_collector_account, FeeCollectorAccount, { writable, skip_pda_verification, account_info })]
     #[pda(commitment_hashing_account, CommitmentHashingAccount, { writable, skip_pda_verification, account_info })]
     #[pda(commitment_queue_account, CommitmentQueueAccount, { writable, skip_pda_verification, account_info })]
+    #[pda(commitment_spill_queue_account, CommitmentSpillQueueAccount, { writable, skip_pda_verification, account_info })]
     #[pda(storage_account, StorageAccount, { writable, skip_pda_verification, account_info })]
     #[pda(base_commitment_buffer_account, BaseCommitmentBufferAccount, { writable, skip_pda_verification, account_info })]
+    #[pda(event_sequence_account, EventSequenceAccount, { writable, skip_pda_verification, account_info })]
+    #[pda(state_summary_account, StateSummaryAccount, { writable, skip_pda_verification, account_info })]
+    #[pda(stats_account, StatsAccount, { writable, skip_pda_verification, account_info })]
+    #[pda(epoch_metrics_account, EpochMetricsAccount, { writable, skip_pda_verification, account_info })]
+    #[pda(state_digest_history_account, StateDigestHistoryAccount, { writable, skip_pda_verification, account_info })]
+    #[pda(recipient_denylist_account, RecipientDenylistAccount, { writable, skip_pda_verification, account_info })]
+    #[pda(config_account, ConfigAccount, { writable, skip_pda_verification, account_info })]
     #[sys(system_program, key = system_program::ID, { ignore })]
     OpenSingleInstanceAccounts,
 
+    /// Opens the accounts of the shielded pool of a supported (non-lamports) `token_id`
+    #[acc(payer, { writable, signer })]
+    #[pda(governor, GovernorAccount)]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = token_pool_offset(token_id), { writable, skip_pda_verification, account_info })]
+    #[pda(commitment_queue_account, CommitmentQueueAccount, pda_offset = token_pool_offset(token_id), { writable, skip_pda_verification, account_info })]
+    #[pda(commitment_spill_queue_account, CommitmentSpillQueueAccount, pda_offset = token_pool_offset(token_id), { writable, skip_pda_verification, account_info })]
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(token_id), { writable, skip_pda_verification, account_info })]
+    #[pda(state_summary_account, StateSummaryAccount, pda_offset = token_pool_offset(token_id), { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenTokenPoolAccounts { token_id: u16 },
+
+    #[acc(payer, { writable, signer })]
+    #[pda(nullifier_account, NullifierAccount, pda_offsets = nullifier_account_offsets(token_id, mt_index), { writable, skip_pda_verification, account_info })]
+    #[pda(rent_contribution, TreeRentContributionAccount, pda_pubkey = payer.pubkey(), pda_offsets = nullifier_account_offsets(token_id, mt_index), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenNullifierAccount { mt_index: u32, token_id: u16 },
+
+    /// Creates a child-account of the exact size required by the `parent` (tagged with the parent's PDA and `child_index`)
+    #[acc(payer, { writable, signer })]
+    #[acc(sub_account, { writable, signer })]
+    #[pda(child_account_record, ChildAccountRecordAccount, pda_pubkey = sub_account.pubkey(), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    CreateChildAccount {
+        parent: ChildAccountParent,
+        child_index: u32,
+    },
+
+    /// Grows the data of a `ReallocatableAccount` towards its current size (repeated until the size is reached)
     #[acc(payer, { writable, signer })]
-    #[pda(nullifier_account, NullifierAccount, pda_offset = Some(mt_index), { writable, skip_pda_verification, account_info })]
+    #[acc(account, { writable })]
     #[sys(system_program, key = system_program::ID, { ignore })]
-    OpenNullifierAccount { mt_index: u32 },
+    ReallocAccount { kind: ReallocatableAccountKind },
 
-    #[pda(storage_account, StorageAccount, { writable })]
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(token_id), { writable })]
     #[acc(sub_account, { owned, writable })]
-    EnableStorageChildAccount { child_index: u32 },
+    EnableStorageChildAccount { child_index: u32, token_id: u16 },
 
-    #[pda(nullifier_account, NullifierAccount, pda_offset = Some(mt_index), { writable })]
+    /// The rent of the child-account is credited to the `creator` recorded by `CreateChildAccount`
+    #[acc(payer, { writable, signer })]
+    #[pda(nullifier_account, NullifierAccount, pda_offsets = nullifier_account_offsets(token_id, mt_index), { writable })]
     #[acc(sub_account, { owned, writable })]
-    EnableNullifierChildAccount { mt_index: u32, child_index: u32 },
+    #[pda(child_account_record, ChildAccountRecordAccount, pda_pubkey = sub_account.pubkey())]
+    #[pda(rent_contribution, TreeRentContributionAccount, pda_pubkey = creator, pda_offsets = nullifier_account_offsets(token_id, mt_index), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    EnableNullifierChildAccount {
+        mt_index: u32,
+        child_index: u32,
+        token_id: u16,
+        creator: Pubkey,
+    },
 
     #[acc(payer, { writable, signer })]
     #[pda(governor, GovernorAccount, { writable, skip_pda_verification, account_info })]
     #[sys(system_program, key = system_program::ID, { ignore })]
     SetupGovernorAccount,
 
-    #[acc(authority, { signer })]
     #[pda(governor, GovernorAccount, { writable })]
-    #[pda(commitment_hash_queue, CommitmentQueueAccount)]
+    #[pda(config, ConfigAccount)]
+    #[remaining(signers, min = 1, { signer })]
+    ProposeGovernorState {
+        fee_version: u32,
+        batching_rate: u32,
+        activation_slot: u64,
+    },
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[pda(fee, FeeAccount, pda_offset = Some(fee_version))]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount)]
+    #[pda(usdc_commitment_hash_queue, CommitmentQueueAccount, pda_offset = token_pool_offset(USDC_POOL_TOKEN_ID), { writable })]
+    #[pda(usdc_commitment_hashing_account, CommitmentHashingAccount, pda_offset = token_pool_offset(USDC_POOL_TOKEN_ID))]
+    #[pda(usdt_commitment_hash_queue, CommitmentQueueAccount, pda_offset = token_pool_offset(USDT_POOL_TOKEN_ID), { writable })]
+    #[pda(usdt_commitment_hashing_account, CommitmentHashingAccount, pda_offset = token_pool_offset(USDT_POOL_TOKEN_ID))]
     UpgradeGovernorState {
         fee_version: u32,
         batching_rate: u32,
     },
 
+    #[pda(governor, GovernorAccount, { writable })]
+    #[pda(config, ConfigAccount)]
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, { writable })]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount)]
+    #[pda(usdc_commitment_hash_queue, CommitmentQueueAccount, pda_offset = token_pool_offset(USDC_POOL_TOKEN_ID), { writable })]
+    #[pda(usdc_commitment_hashing_account, CommitmentHashingAccount, pda_offset = token_pool_offset(USDC_POOL_TOKEN_ID))]
+    #[pda(usdt_commitment_hash_queue, CommitmentQueueAccount, pda_offset = token_pool_offset(USDT_POOL_TOKEN_ID), { writable })]
+    #[pda(usdt_commitment_hashing_account, CommitmentHashingAccount, pda_offset = token_pool_offset(USDT_POOL_TOKEN_ID))]
+    #[remaining(signers, min = 1, { signer })]
+    SetCommitmentBatchingRate { batching_rate: u32 },
+
     #[acc(payer, { writable, signer })]
     #[pda(governor, GovernorAccount, { writable })]
     #[pda(fee, FeeAccount, pda_offset = Some(fee_version), { writable, skip_pda_verification, account_info })]
+    #[pda(fee_collector, FeeCollectorAccount, { writable })]
     #[sys(system_program, key = system_program::ID, { ignore })]
+    #[remaining(signers, min = 1, { signer })]
     InitNewFeeVersion {
         fee_version: u32,
         program_fee: ProgramFee,
     },
 
+    #[pda(governor, GovernorAccount)]
+    #[pda(fee, FeeAccount, pda_offset = Some(fee_version), { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetFeeComputeUnitPrice {
+        fee_version: u32,
+        lamports_per_cu: u64,
+    },
+
+    #[pda(governor, GovernorAccount)]
+    #[pda(fee, FeeAccount, pda_offset = Some(fee_version), { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetFeePriorityLaneSurcharge {
+        fee_version: u32,
+        priority_lane_surcharge: u64,
+    },
+
+    #[pda(governor, GovernorAccount)]
+    #[pda(fee, FeeAccount, pda_offset = Some(fee_version), { writable })]
+    #[pda(fee_collector, FeeCollectorAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetNetworkFeeSplit {
+        fee_version: u32,
+        split: NetworkFeeSplit,
+    },
+
+    #[pda(governor, GovernorAccount)]
+    #[pda(fee, FeeAccount, pda_offset = Some(fee_version), { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetTokenFeeSchedule {
+        fee_version: u32,
+        token_id: u16,
+        schedule: TokenFeeSchedule,
+    },
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetAnonymityDelayPolicy {
+        required_anonymity_delay_slots: u64,
+        anonymity_delay_thresholds: [u64; STATS_TOKEN_COUNT],
+    },
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetWithdrawalDelayPolicy {
+        required_withdrawal_delay_slots: u64,
+        withdrawal_delay_threshold: u64,
+    },
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    ActivateEmergencyMode,
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetHalted { is_halted: bool },
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetGovernanceMultisig { multisig: GovernanceMultisig },
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetSealPolicy { seal_sunset_slots: u64 },
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetSupportedToken { token_id: u16, is_supported: bool },
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetEpochDepositCap {
+        token_id: u16,
+        epoch_deposit_cap: u64,
+    },
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetComputeUnitMargin { compute_unit_margin: u16 },
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetPriceAccount { token_id: u16, price_account: U256 },
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetPriceOraclePolicy {
+        max_price_age: u64,
+        max_price_confidence_bps: u16,
+    },
+
+    /// Sets the runtime limits of the [`ConfigAccount`] (zero uses the built-in constant)
+    #[pda(governor, GovernorAccount)]
+    #[pda(config, ConfigAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetProgramConfig {
+        commitment_queue_capacity: u32,
+        max_commitment_batching_rate: u32,
+    },
+
+    /// Opens an additional shard of the recipient denylist (the first shard is a single instance account)
+    #[acc(payer, { writable, signer })]
+    #[pda(recipient_denylist_account, RecipientDenylistAccount, pda_offset = recipient_denylist_offset(shard), { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenRecipientDenylistShard { shard: u32 },
+
+    #[pda(governor, GovernorAccount)]
+    #[pda(recipient_denylist, RecipientDenylistAccount, pda_offset = recipient_denylist_offset(shard), { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetRecipientDenylistEnabled { shard: u32, enabled: bool },
+
+    #[pda(governor, GovernorAccount)]
+    #[pda(recipient_denylist, RecipientDenylistAccount, pda_offset = recipient_denylist_offset(recipient_denylist_shard(&recipient)), { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetDeniedRecipient { recipient: Pubkey, is_denied: bool },
+
+    /// Adds multiple recipients (all belonging to `shard`) to the recipient denylist
+    #[pda(governor, GovernorAccount)]
+    #[pda(recipient_denylist, RecipientDenylistAccount, pda_offset = recipient_denylist_offset(shard), { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    AddDeniedRecipients {
+        shard: u32,
+        recipient_count: u32,
+        recipients: [Pubkey; MAX_DENIED_RECIPIENTS_BATCH],
+    },
+
+    #[acc(payer, { writable, signer })]
+    #[pda(poseidon_params_account, PoseidonParamsAccount, { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenPoseidonParamsAccount,
+
+    /// Sets the first `count` `params` of the Poseidon parameters starting at `offset` (only while the parameters are not frozen)
+    #[pda(governor, GovernorAccount)]
+    #[pda(poseidon_params, PoseidonParamsAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetPoseidonParams {
+        offset: u32,
+        count: u32,
+        params: [U256; POSEIDON_PARAMS_CHUNK_SIZE],
+    },
+
+    /// Freezes the Poseidon parameters as `version`, if they match the pinned checksum of the `version`
+    #[pda(governor, GovernorAccount)]
+    #[pda(poseidon_params, PoseidonParamsAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    FreezePoseidonParams { version: u32 },
+
+    /// Unfreezes the Poseidon parameters for an upgrade to the next pinned version
+    #[pda(governor, GovernorAccount)]
+    #[pda(poseidon_params, PoseidonParamsAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    UnfreezePoseidonParams,
+
+    #[pda(fee_collector, FeeCollectorAccount, { writable })]
+    #[pda(governor, GovernorAccount)]
+    #[remaining(signers, min = 1, { signer })]
+    SetRevenueDestinations {
+        destinations: [U256; REVENUE_DESTINATION_COUNT],
+        secondary_split_bps: u16,
+    },
+
+    /// Withdraws collected network-fees of `token_id` from the fee collector to the allowlisted revenue destinations
+    #[pda(governor, GovernorAccount)]
+    #[pda(fee_collector, FeeCollectorAccount, { writable, account_info })]
+    #[acc(fee_collector_account, { writable })] // if `token_id = 0` { `fee_collector` }
+    #[acc(destination, { writable })]
+    #[acc(secondary_destination, { writable })]
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    #[remaining(signers, min = 1, { signer })]
+    WithdrawProtocolRevenue { token_id: u16, amount: u64 },
+
+    /// Distributes relayer incentives of `token_id` from the fee collector to the escrowed rewards of a registered warden
+    #[pda(governor, GovernorAccount)]
+    #[pda(fee_collector, FeeCollectorAccount, { writable, account_info })]
+    #[acc(fee_collector_account, { writable })] // if `token_id = 0` { `fee_collector` }
+    #[pda(pool, PoolAccount, { writable, account_info })]
+    #[acc(pool_account, { writable })] // if `token_id = 0` { `pool` }
+    #[pda(warden_account, WardenAccount, pda_pubkey = warden, { writable })]
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    #[remaining(signers, min = 1, { signer })]
+    DistributeRelayerIncentives {
+        warden: Pubkey,
+        token_id: u16,
+        amount: u64,
+    },
+
+    /// Burns the burn share of the collected network-fees of `token_id`
+    #[pda(fee_collector, FeeCollectorAccount, { writable, account_info })]
+    #[acc(fee_collector_account, { writable })] // if `token_id = 0` { `fee_collector` }
+    #[acc(burn_account, { writable })] // if `token_id = 0` { `incinerator` } else { `mint` }
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    BurnCollectedFees { token_id: u16 },
+
     #[cfg(not(feature = "mainnet"))]
     #[acc(payer, { signer })]
     #[acc(recipient, { writable })]
@@ -290,13 +794,333 @@ pub enum ElusivInstruction {
     #[sys(system_program, key = system_program::ID, { ignore })]
     CloseProgramAccount,
 
+    // -------- Recurring payments --------
+    /// Schedules the prepared proof-requests identified by `installments` (see [`processor::recurring_installment_hash`])
+    #[acc(owner, { writable, signer })]
+    #[pda(recurring_payment_account, RecurringPaymentAccount, pda_pubkey = owner.pubkey(), pda_offset = Some(recurring_payment_index), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    CreateRecurringPayment {
+        recurring_payment_index: u32,
+        period_slots: u64,
+        first_release_slot: u64,
+        installments_count: u32,
+        installments: [U256; MAX_RECURRING_INSTALLMENTS],
+    },
+
+    /// Releases the next due installment (permissionless)
+    #[pda(recurring_payment_account, RecurringPaymentAccount, pda_pubkey = owner, pda_offset = Some(recurring_payment_index), { writable })]
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
+    ReleaseRecurringInstallment {
+        owner: Pubkey,
+        recurring_payment_index: u32,
+        request: ProofRequest,
+    },
+
+    #[acc(owner, { writable, signer })]
+    #[pda(recurring_payment_account, RecurringPaymentAccount, pda_pubkey = owner.pubkey(), pda_offset = Some(recurring_payment_index), { writable, account_info })]
+    CancelRecurringPayment { recurring_payment_index: u32 },
+
+    // -------- Pending payments --------
+    /// Opens an escrow for an expiring payment (the escrow is then used as the recipient of a regular send)
+    #[acc(sender, { writable, signer })]
+    #[pda(pending_payment_account, PendingPaymentAccount, pda_pubkey = sender.pubkey(), pda_offset = Some(pending_payment_index), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    CreatePendingPayment {
+        pending_payment_index: u32,
+        claim_key: Pubkey,
+        recipient: Pubkey,
+        amount: u64,
+        expiry_slot: u64,
+        refund_base_commitment: RawU256,
+        hashlock: U256,
+    },
+
+    /// Releases a pending payment to its recipient (before the expiry, revealing the hashlock's preimage if set)
+    #[acc(claim_key, { signer })]
+    #[acc(recipient, { writable })]
+    #[acc(sender, { writable })]
+    #[pda(pending_payment_account, PendingPaymentAccount, pda_pubkey = sender.pubkey(), pda_offset = Some(pending_payment_index), { writable, account_info })]
+    ClaimPendingPayment {
+        pending_payment_index: u32,
+        preimage: U256,
+    },
+
+    /// Re-credits an expired pending payment to the sender
+    #[acc(fee_payer, { writable, signer })]
+    #[acc(sender, { writable })]
+    #[pda(pending_payment_account, PendingPaymentAccount, pda_pubkey = sender.pubkey(), pda_offset = Some(pending_payment_index), { writable, account_info })]
+    #[pda(pool, PoolAccount, { writable, account_info })]
+    #[pda(governor, GovernorAccount)]
+    #[acc(hashing_account, { writable })]
+    #[pda(buffer, BaseCommitmentBufferAccount, { writable })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    #[pda(stats, StatsAccount, { writable })]
+    #[pda(epoch_metrics, EpochMetricsAccount, { writable })]
+    RefundPendingPayment {
+        #[pda_offset_only]
+        pending_payment_index: u32,
+        hash_account_index: u32,
+        hash_account_bump: u8,
+    },
+
+    // -------- Crowdfunding --------
+    /// Opens an escrow collecting contributions for a single commitment
+    #[acc(creator, { writable, signer })]
+    #[pda(crowdfund_account, CrowdfundAccount, pda_pubkey = creator.pubkey(), pda_offset = Some(crowdfund_index), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    CreateCrowdfund {
+        crowdfund_index: u32,
+        base_commitment: RawU256,
+        target_amount: u64,
+        deadline_slot: u64,
+    },
+
+    /// Contributes `amount` lamports to a crowdfund
+    #[acc(funder, { writable, signer })]
+    #[acc(creator)]
+    #[pda(crowdfund_account, CrowdfundAccount, pda_pubkey = creator.pubkey(), pda_offset = Some(crowdfund_index), { writable, account_info })]
+    #[pda(governor, GovernorAccount)]
+    #[sys(system_program, key = system_program::ID)]
+    ContributeToCrowdfund { crowdfund_index: u32, amount: u64 },
+
+    /// Returns a funder's contribution once the deadline has passed without reaching the target
+    #[acc(funder, { writable, signer })]
+    #[acc(creator)]
+    #[pda(crowdfund_account, CrowdfundAccount, pda_pubkey = creator.pubkey(), pda_offset = Some(crowdfund_index), { writable, account_info })]
+    #[pda(governor, GovernorAccount)]
+    RefundCrowdfundContribution { crowdfund_index: u32 },
+
+    /// Closes a crowdfund that has not reached its target before the deadline, once all contributions have been refunded
+    #[acc(creator, { writable, signer })]
+    #[pda(crowdfund_account, CrowdfundAccount, pda_pubkey = creator.pubkey(), pda_offset = Some(crowdfund_index), { writable, account_info })]
+    CloseCrowdfund { crowdfund_index: u32 },
+
+    /// Starts the hashing of the crowdfund's commitment once the target is reached
+    #[acc(fee_payer, { writable, signer })]
+    #[acc(creator, { writable })]
+    #[pda(crowdfund_account, CrowdfundAccount, pda_pubkey = creator.pubkey(), pda_offset = Some(crowdfund_index), { writable, account_info })]
+    #[pda(pool, PoolAccount, { writable, account_info })]
+    #[pda(governor, GovernorAccount)]
+    #[acc(hashing_account, { writable })]
+    #[pda(buffer, BaseCommitmentBufferAccount, { writable })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    #[pda(stats, StatsAccount, { writable })]
+    #[pda(epoch_metrics, EpochMetricsAccount, { writable })]
+    FinalizeCrowdfund {
+        crowdfund_index: u32,
+        hash_account_index: u32,
+        hash_account_bump: u8,
+    },
+
+    // -------- Deposit escrows --------
+    /// Escrows a deposit until it is reserved by a warden
+    #[acc(sender, { writable, signer })]
+    #[pda(deposit_escrow_account, DepositEscrowAccount, pda_pubkey = sender.pubkey(), pda_offset = Some(deposit_escrow_index), { writable, account_info, find_pda })]
+    #[pda(governor, GovernorAccount)]
+    #[sys(system_program, key = system_program::ID)]
+    CreateDepositEscrow {
+        deposit_escrow_index: u32,
+        base_commitment: RawU256,
+        amount: u64,
+    },
+
+    /// Reserves an escrowed deposit by starting the hashing of its commitment
+    #[acc(fee_payer, { writable, signer })]
+    #[acc(sender, { writable })]
+    #[pda(deposit_escrow_account, DepositEscrowAccount, pda_pubkey = sender.pubkey(), pda_offset = Some(deposit_escrow_index), { writable, account_info })]
+    #[pda(pool, PoolAccount, { writable, account_info })]
+    #[pda(governor, GovernorAccount)]
+    #[acc(hashing_account, { writable })]
+    #[pda(buffer, BaseCommitmentBufferAccount, { writable })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    #[pda(stats, StatsAccount, { writable })]
+    #[pda(epoch_metrics, EpochMetricsAccount, { writable })]
+    ReserveDepositEscrow {
+        deposit_escrow_index: u32,
+        hash_account_index: u32,
+        hash_account_bump: u8,
+    },
+
+    /// Refunds an escrowed deposit that has not been reserved yet (minus an anti-spam fee)
+    #[acc(sender, { writable, signer })]
+    #[pda(deposit_escrow_account, DepositEscrowAccount, pda_pubkey = sender.pubkey(), pda_offset = Some(deposit_escrow_index), { writable, account_info })]
+    #[pda(fee_collector, FeeCollectorAccount, { writable, account_info })]
+    AbortDepositEscrow { deposit_escrow_index: u32 },
+
+    // -------- Timelocked withdrawals --------
+    /// Opens an escrow delaying the release of a withdrawal (the escrow is then used as the recipient of a regular send)
+    #[acc(owner, { writable, signer })]
+    #[pda(pending_withdrawal_account, PendingWithdrawalAccount, pda_pubkey = owner.pubkey(), pda_offset = Some(pending_withdrawal_index), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    CreatePendingWithdrawal {
+        pending_withdrawal_index: u32,
+        recipient: Pubkey,
+        delay_slots: u64,
+    },
+
+    /// Releases a timelocked withdrawal to its recipient once it is unlocked (permissionless)
+    #[acc(owner, { writable })]
+    #[acc(recipient, { writable })]
+    #[pda(pending_withdrawal_account, PendingWithdrawalAccount, pda_pubkey = owner.pubkey(), pda_offset = Some(pending_withdrawal_index), { writable, account_info })]
+    ReleasePendingWithdrawal { pending_withdrawal_index: u32 },
+
+    /// Closes an empty pending withdrawal escrow (sends to it need to be finalized before)
+    #[acc(owner, { writable, signer })]
+    #[pda(pending_withdrawal_account, PendingWithdrawalAccount, pda_pubkey = owner.pubkey(), pda_offset = Some(pending_withdrawal_index), { writable, account_info })]
+    ClosePendingWithdrawal { pending_withdrawal_index: u32 },
+
+    // -------- Viewing-key disclosures --------
+    /// Publishes (or replaces) a viewing key encrypted for an auditor (see [`crate::state::viewing_key::ViewingKeyRegistryAccount`])
+    #[acc(depositor, { writable, signer })]
+    #[pda(viewing_key_registry, ViewingKeyRegistryAccount, pda_pubkey = depositor.pubkey(), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    PublishViewingKey {
+        auditor: Pubkey,
+        viewing_key_hash: U256,
+        encrypted_viewing_key: [u8; ENCRYPTED_VIEWING_KEY_SIZE],
+    },
+
+    #[acc(depositor, { writable, signer })]
+    #[pda(viewing_key_registry, ViewingKeyRegistryAccount, pda_pubkey = depositor.pubkey(), { writable, account_info })]
+    RevokeViewingKey,
+
+    // -------- Recipient discovery --------
+    /// Opens the [`NoteCiphertextAccount`] of the shielded pool of a supported `token_id`
+    #[acc(payer, { writable, signer })]
+    #[pda(governor, GovernorAccount)]
+    #[pda(note_ciphertext_account, NoteCiphertextAccount, pda_offset = token_pool_offset(token_id), { writable, skip_pda_verification, account_info })]
+    #[sys(system_program, key = system_program::ID, { ignore })]
+    OpenNoteCiphertextAccount { token_id: u16 },
+
+    /// Appends the ciphertext of a deposit (stored in the same transaction) to the [`NoteCiphertextAccount`] of `token_id`
+    #[acc(sender, { signer })]
+    #[pda(base_commitment_buffer, BaseCommitmentBufferAccount)]
+    #[pda(note_ciphertext_account, NoteCiphertextAccount, pda_offset = token_pool_offset(token_id), { writable })]
+    StoreNoteCiphertext {
+        token_id: u16,
+        base_commitment: RawU256,
+        commitment: RawU256,
+        ciphertext: [u8; NOTE_CIPHERTEXT_SIZE],
+    },
+
+    // -------- Withdraw-and-call --------
+    /// Calls a whitelisted program with the funds sent to the call's authority (see [`processor::withdraw_and_call_authority`])
+    #[acc(call_authority, { writable })]
+    #[acc(program)]
+    #[acc(remainder_recipient, { writable })]
+    #[sys(system_program, key = system_program::ID)]
+    #[remaining(call_accounts, { writable })]
+    ExecuteWithdrawAndCall { call: WithdrawAndCall },
+
+    /// Refunds the funds of a call that has not been executed until its `refund_slot` to the `remainder_recipient`
+    #[acc(call_authority, { writable })]
+    #[acc(remainder_recipient, { writable })]
+    #[sys(system_program, key = system_program::ID)]
+    RefundWithdrawAndCall { call: WithdrawAndCall },
+
+    // -------- Wardens --------
+    /// Registers a warden by staking at least [`crate::state::warden::MIN_WARDEN_STAKE`]
+    #[acc(warden, { writable, signer })]
+    #[pda(warden_account, WardenAccount, pda_pubkey = warden.pubkey(), { writable, account_info, find_pda })]
+    #[sys(system_program, key = system_program::ID)]
+    RegisterWarden { stake: u64 },
+
+    #[acc(warden, { signer })]
+    #[pda(warden_account, WardenAccount, pda_pubkey = warden.pubkey(), { writable })]
+    DeregisterWarden,
+
+    #[acc(warden, { writable, signer })]
+    #[pda(warden_account, WardenAccount, pda_pubkey = warden.pubkey(), { writable, account_info })]
+    WithdrawWardenStake,
+
+    /// Claims the escrowed rewards of `token_id` (see [`processor::escrow_warden_reward`])
+    #[acc(warden, { signer })]
+    #[pda(warden_account, WardenAccount, pda_pubkey = warden.pubkey(), { writable })]
+    #[pda(pool, PoolAccount, { account_info, writable })]
+    #[acc(pool_account, { writable })]
+    #[acc(recipient, { writable })] // if `token_id = 0` { lamports-recipient } else { token-account }
+    #[acc(token_program)] // if `token_id = 0` { `system_program` } else { `token_program` }
+    ClaimRewards { token_id: u16 },
+
+    #[pda(governor, GovernorAccount, { writable })]
+    #[remaining(signers, min = 1, { signer })]
+    SetWardenRegistrationRequired { is_required: bool },
+
+    // -------- Monitoring --------
+    /// Read-only consistency check of the program state (fails if any invariant is violated)
+    #[pda(commitment_hash_queue, CommitmentQueueAccount, pda_offset = token_pool_offset(token_id))]
+    #[pda(commitment_hashing_account, CommitmentHashingAccount, pda_offset = token_pool_offset(token_id))]
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(token_id))]
+    #[pda(active_nullifier_account, NullifierAccount, pda_offsets = nullifier_account_offsets(token_id, active_mt_index))]
+    #[pda(state_summary, StateSummaryAccount, pda_offset = token_pool_offset(token_id))]
+    HealthCheck { active_mt_index: u32, token_id: u16 },
+
+    /// Emits an attestation of the pool's reserves versus the liabilities of `token_id`
+    #[pda(pool, PoolAccount, { account_info })]
+    #[acc(pool_account)]
+    #[pda(stats, StatsAccount)]
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
+    AttestReserves { token_id: u16 },
+
+    /// Records the digest of the program state for the current epoch (token pool accounts ordered by token-id)
+    #[pda(pool, PoolAccount, { account_info })]
+    #[pda(state_summary, StateSummaryAccount)]
+    #[pda(state_digest_history, StateDigestHistoryAccount, { writable })]
+    #[pda(event_sequence, EventSequenceAccount, { writable })]
+    #[remaining(token_pool_accounts)]
+    AttestStateDigest,
+
+    // -------- Queries (results are returned as Borsh-serialized return-data) --------
+    /// Returns the root of the active MT of the shielded pool of `token_id` (see [`processor::CurrentRoot`])
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(token_id), { include_child_accounts })]
+    GetCurrentRoot { token_id: u16 },
+
+    /// Returns the index of the next commitment in the active MT of the shielded pool of `token_id`
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(token_id))]
+    GetNextLeafIndex { token_id: u16 },
+
+    /// Attests whether `commitment` is the leaf with `leaf_index` in the active MT with `mt_index` of the shielded pool of `token_id` (see [`processor::MembershipAttestation`])
+    /// - can be used by other programs via CPI (the instruction never fails for non-members)
+    #[pda(storage_account, StorageAccount, pda_offset = token_pool_offset(token_id), { include_child_accounts })]
+    AttestMembership {
+        token_id: u16,
+        mt_index: u32,
+        leaf_index: u32,
+        commitment: RawU256,
+    },
+
+    #[pda(governor, GovernorAccount)]
+    GetFeeVersion,
+
+    #[pda(governor, GovernorAccount)]
+    GetBatchingRate,
+
+    /// Returns whether the program is in the exit-only emergency mode
+    #[pda(governor, GovernorAccount)]
+    GetPauseState,
+
+    /// Returns the collected and withdrawn protocol revenue of `token_id` (see [`processor::ProtocolRevenue`])
+    #[pda(fee_collector, FeeCollectorAccount)]
+    GetProtocolRevenue { token_id: u16 },
+
+    /// Returns the sizes the program has been built with and its runtime limits (see [`processor::ProgramConfig`])
+    #[pda(governor, GovernorAccount)]
+    #[pda(config, ConfigAccount)]
+    GetProgramConfig,
+
     // -------- NOP --------
     /// NOP-instruction
     Nop,
 }
 
+#[cfg(feature = "elusiv-client")]
+use crate::{
+    processor::program_token_account_address, token::elusiv_token, types::JoinSplitPublicInputs,
+};
 #[cfg(feature = "elusiv-client")]
 use elusiv_types::accounts::PDAAccount;
+#[cfg(feature = "elusiv-client")]
+use solana_program::{instruction::Instruction, program_error::ProgramError};
 
 #[cfg(feature = "elusiv-client")]
 impl ElusivInstruction {
@@ -323,9 +1147,46 @@ impl ElusivInstruction {
             UserAccount(system_program::id()),
             WritableUserAccount(hash_account_pubkey),
             UserAccount(system_program::id()),
+            UserAccount(system_program::id()),
         )
     }
 
+    /// `token_program` is either the SPL-token or the Token-2022 program (depending on the mint of `request.token_id`)
+    pub fn store_base_commitment_token_instruction(
+        hash_account_index: u32,
+        request: BaseCommitmentHashRequest,
+        client: Pubkey,
+        client_account: Pubkey,
+        warden: Pubkey,
+        warden_account: Pubkey,
+        token_program: Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let token_id = request.token_id;
+        let (hash_account_pubkey, hash_account_bump) =
+            BaseCommitmentHashingAccount::find(Some(hash_account_index));
+
+        Ok(ElusivInstruction::store_base_commitment_instruction(
+            hash_account_index,
+            hash_account_bump,
+            request,
+            SignerAccount(client),
+            WritableUserAccount(client_account),
+            WritableSignerAccount(warden),
+            WritableUserAccount(warden_account),
+            WritableUserAccount(program_token_account_address::<PoolAccount>(
+                token_id, None,
+            )?),
+            WritableUserAccount(program_token_account_address::<FeeCollectorAccount>(
+                token_id, None,
+            )?),
+            UserAccount(elusiv_token(0)?.pyth_usd_price_key),
+            UserAccount(elusiv_token(token_id)?.pyth_usd_price_key),
+            WritableUserAccount(hash_account_pubkey),
+            UserAccount(token_program),
+            UserAccount(elusiv_token(token_id)?.mint),
+        ))
+    }
+
     pub fn init_verification_transfer_fee_sol_instruction(
         verification_account_index: u8,
         warden: Pubkey,
@@ -350,8 +1211,6 @@ impl ElusivInstruction {
         pool_account: Pubkey,
         fee_collector_account: Pubkey,
     ) -> solana_program::instruction::Instruction {
-        use crate::token::elusiv_token;
-
         ElusivInstruction::init_verification_transfer_fee_instruction(
             verification_account_index,
             WritableSignerAccount(warden),
@@ -363,6 +1222,98 @@ impl ElusivInstruction {
             UserAccount(spl_token::id()),
         )
     }
+
+    /// `batch_payout_recipients` are the recipients of the [`crate::types::BatchPayouts`] of the proof (in order)
+    pub fn finalize_verification_transfer_lamports_sol_instruction(
+        verification_account_index: u8,
+        join_split: &JoinSplitPublicInputs,
+        warden: Pubkey,
+        recipient: Pubkey,
+        batch_payout_recipients: &[Pubkey],
+    ) -> Instruction {
+        ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+            verification_account_index,
+            WritableSignerAccount(warden),
+            WritableUserAccount(recipient),
+            WritableUserAccount(join_split.nullifier_duplicate_pda().0),
+            &batch_payout_recipients
+                .iter()
+                .map(|recipient| WritableUserAccount(*recipient))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// `recipient` is the token account of `recipient_wallet` (created if it does not exist yet)
+    pub fn finalize_verification_transfer_token_spl_instruction(
+        verification_account_index: u8,
+        join_split: &JoinSplitPublicInputs,
+        warden: Pubkey,
+        warden_account: Pubkey,
+        recipient: Pubkey,
+        recipient_wallet: Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let token_id = join_split.token_id;
+
+        Ok(
+            ElusivInstruction::finalize_verification_transfer_token_instruction(
+                verification_account_index,
+                token_id,
+                WritableSignerAccount(warden),
+                WritableUserAccount(warden_account),
+                WritableUserAccount(recipient),
+                UserAccount(recipient_wallet),
+                WritableUserAccount(program_token_account_address::<PoolAccount>(
+                    token_id, None,
+                )?),
+                WritableUserAccount(program_token_account_address::<FeeCollectorAccount>(
+                    token_id, None,
+                )?),
+                WritableUserAccount(join_split.nullifier_duplicate_pda().0),
+                UserAccount(elusiv_token(token_id)?.mint),
+            ),
+        )
+    }
+
+    pub fn reset_timed_out_verification_sol_instruction(
+        verification_account_index: u8,
+        join_split: &JoinSplitPublicInputs,
+        resetter: Pubkey,
+        original_fee_payer: Pubkey,
+    ) -> Instruction {
+        ElusivInstruction::reset_timed_out_verification_instruction(
+            verification_account_index,
+            WritableSignerAccount(resetter),
+            WritableUserAccount(original_fee_payer),
+            WritableUserAccount(PoolAccount::find(None).0),
+            WritableUserAccount(FeeCollectorAccount::find(None).0),
+            WritableUserAccount(join_split.nullifier_duplicate_pda().0),
+            UserAccount(system_program::id()),
+        )
+    }
+
+    pub fn reset_timed_out_verification_token_instruction(
+        verification_account_index: u8,
+        join_split: &JoinSplitPublicInputs,
+        resetter: Pubkey,
+        original_fee_payer: Pubkey,
+        token_program: Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let token_id = join_split.token_id;
+
+        Ok(ElusivInstruction::reset_timed_out_verification_instruction(
+            verification_account_index,
+            WritableSignerAccount(resetter),
+            WritableUserAccount(original_fee_payer),
+            WritableUserAccount(program_token_account_address::<PoolAccount>(
+                token_id, None,
+            )?),
+            WritableUserAccount(program_token_account_address::<FeeCollectorAccount>(
+                token_id, None,
+            )?),
+            WritableUserAccount(join_split.nullifier_duplicate_pda().0),
+            UserAccount(token_program),
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -399,4 +1350,96 @@ mod tests {
             ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX
         );
     }
+
+    #[cfg(feature = "elusiv-client")]
+    #[test]
+    fn test_pool_token_ids() {
+        use crate::token::{USDC_TOKEN_ID, USDT_TOKEN_ID};
+
+        assert_eq!(USDC_POOL_TOKEN_ID, USDC_TOKEN_ID);
+        assert_eq!(USDT_POOL_TOKEN_ID, USDT_TOKEN_ID);
+    }
+
+    #[cfg(feature = "elusiv-client")]
+    #[test]
+    fn test_instruction_builder_account_order() {
+        use crate::state::program_account::PDAAccount;
+        use crate::token::{LAMPORTS_TOKEN_ID, USDC_TOKEN_ID};
+
+        for token_id in [LAMPORTS_TOKEN_ID, USDC_TOKEN_ID] {
+            let child_accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+            let ix = ElusivInstruction::finalize_commitment_hash_instruction(
+                token_id,
+                &[
+                    WritableUserAccount(child_accounts[0]),
+                    WritableUserAccount(child_accounts[1]),
+                ],
+            );
+
+            assert_eq!(ix.program_id, crate::id());
+            assert_eq!(
+                ix.data[0],
+                ElusivInstruction::FINALIZE_COMMITMENT_HASH_INDEX
+            );
+            assert_eq!(
+                ix.accounts
+                    .iter()
+                    .map(|account| account.pubkey)
+                    .collect::<Vec<Pubkey>>(),
+                vec![
+                    CommitmentHashingAccount::find(token_pool_offset(token_id)).0,
+                    StorageAccount::find(token_pool_offset(token_id)).0,
+                    child_accounts[0],
+                    child_accounts[1],
+                    StateSummaryAccount::find(token_pool_offset(token_id)).0,
+                    StatsAccount::find(None).0,
+                    EventSequenceAccount::find(None).0,
+                ]
+            );
+            assert!(ix
+                .accounts
+                .iter()
+                .all(|account| account.is_writable && !account.is_signer));
+        }
+    }
+
+    #[cfg(feature = "idl")]
+    #[test]
+    fn test_idl() {
+        let idl: serde_json::Value = serde_json::from_str(&ElusivInstruction::idl()).unwrap();
+        let instructions = idl["instructions"].as_array().unwrap();
+        assert_eq!(instructions[0]["name"], "StoreBaseCommitment");
+
+        let instruction = instructions
+            .iter()
+            .find(|instruction| instruction["name"] == "FinalizeCommitmentHash")
+            .unwrap();
+        assert_eq!(
+            instruction["index"],
+            ElusivInstruction::FINALIZE_COMMITMENT_HASH_INDEX
+        );
+        assert_eq!(
+            instruction["args"],
+            serde_json::json!([{ "name": "token_id", "type": "u16" }])
+        );
+
+        let accounts = instruction["accounts"].as_array().unwrap();
+        assert_eq!(accounts.len(), 5);
+        assert_eq!(accounts[1]["name"], "storage_account");
+        assert_eq!(accounts[1]["pda"], "StorageAccount");
+        assert_eq!(accounts[1]["multiple"], true);
+        assert!(accounts
+            .iter()
+            .all(|account| account["writable"] == true && account["signer"] == false));
+
+        // Remaining signers
+        let instruction = instructions
+            .iter()
+            .find(|instruction| instruction["name"] == "SetHalted")
+            .unwrap();
+        let accounts = instruction["accounts"].as_array().unwrap();
+        assert_eq!(accounts[1]["kind"], "remaining");
+        assert_eq!(accounts[1]["signer"], true);
+        assert_eq!(accounts[1]["multiple"], true);
+    }
 }
@@ -2,15 +2,26 @@
 
 pub mod bytes;
 pub mod commitment;
+pub mod computation;
+#[cfg(feature = "cpi")]
+pub mod cpi;
 pub mod entrypoint;
 mod error;
+pub mod events;
 pub mod fields;
 pub mod instruction;
 mod macros;
 pub mod map;
+#[cfg(feature = "elusiv-client")]
+pub mod network;
+pub mod oracle;
 pub mod processor;
 pub mod proof;
+#[cfg(feature = "elusiv-client")]
+pub mod recovery;
 pub mod state;
+#[cfg(test)]
+mod syscall_stubs;
 pub mod token;
 pub mod types;
 
@@ -1,6 +1,6 @@
 pub use elusiv_derive::*;
 pub use elusiv_proc_macros::*;
-pub use elusiv_utils::{guard, pda_account, two_pow};
+pub use elusiv_utils::{assert_account_size, guard, pda_account, two_pow};
 
 /// Creates a dummy pyth-price-account [`solana_program::account_info::AccountInfo`] for testing
 ///
@@ -43,8 +43,12 @@ macro_rules! account_info {
         crate::macros::account_info!($id, pubkey, data, $data, $owner, $is_signer);
     };
     ($id: ident, $pubkey: expr, $data_id: ident, $data: expr, $owner: expr, $is_signer: literal) => {
-        let mut lamports = u32::MAX as u64;
         let mut $data_id = $data;
+        // Rent-exempt (with the default rent) and at least `u32::MAX` lamports
+        let mut lamports = std::cmp::max(
+            u32::MAX as u64,
+            solana_program::rent::Rent::default().minimum_balance($data_id.len()),
+        );
         let owner = $owner;
         let $id = solana_program::account_info::AccountInfo::new(
             &$pubkey,
@@ -102,11 +106,48 @@ macro_rules! test_pda_account_info {
     };
     ($id: ident, $ty: ty, $offset: expr) => {
         let (pk, bump) = <$ty as elusiv_types::PDAAccount>::find($offset);
-        crate::macros::account_info!($id, pk, vec![bump])
+        let mut data = vec![0; <$ty as elusiv_types::SizedAccount>::SIZE];
+        data[0] = bump;
+        crate::macros::account_info!($id, pk, data)
     };
     ($id: ident, $ty: ty, $pubkey: expr, $offset: expr) => {
         let (pk, bump) = <$ty as elusiv_types::PDAAccount>::find_with_pubkey($pubkey, $offset);
-        crate::macros::account_info!($id, pk, vec![bump])
+        let mut data = vec![0; <$ty as elusiv_types::SizedAccount>::SIZE];
+        data[0] = bump;
+        crate::macros::account_info!($id, pk, data)
+    };
+}
+
+/// Creates the instructions sysvar [`solana_program::account_info::AccountInfo`] of a transaction for testing
+///
+/// # Usage
+///
+/// `instructions_account_info!($id: ident, $instructions: expr, $current_index: expr)`
+#[cfg(test)]
+macro_rules! instructions_account_info {
+    ($id: ident, $instructions: expr, $current_index: expr) => {
+        let instructions: Vec<solana_program::instruction::Instruction> = $instructions;
+        let borrowed_instructions: Vec<_> = instructions
+            .iter()
+            .map(
+                |ix| solana_program::sysvar::instructions::BorrowedInstruction {
+                    program_id: &ix.program_id,
+                    accounts: Vec::new(),
+                    data: &ix.data,
+                },
+            )
+            .collect();
+        let mut data = solana_program::sysvar::instructions::construct_instructions_data(
+            &borrowed_instructions,
+        );
+        solana_program::sysvar::instructions::store_current_index(&mut data, $current_index);
+        crate::macros::account_info!(
+            $id,
+            solana_program::sysvar::instructions::ID,
+            data,
+            solana_program::sysvar::ID,
+            false
+        );
     };
 }
 
@@ -160,7 +201,7 @@ macro_rules! parent_account {
         let mut child_accounts = Vec::with_capacity(<$ty as elusiv_types::accounts::ParentAccount>::COUNT);
         elusiv_proc_macros::repeat!({
             let pk = solana_program::pubkey::Pubkey::new_unique();
-            crate::macros::account_info!(acc_index, pk, vec![0; <<$ty as elusiv_types::accounts::ParentAccount>::Child as elusiv_types::accounts::SizedAccount>::SIZE]);
+            crate::macros::account_info!(acc_index, pk, vec![0; <$ty as elusiv_types::accounts::ParentAccount>::child_account_size(_index)]);
             child_accounts.push(Some(&acc_index));
         }, 32);
 
@@ -180,6 +221,8 @@ macro_rules! parent_account {
 #[cfg(test)]
 pub(crate) use account_info;
 #[cfg(test)]
+pub(crate) use instructions_account_info;
+#[cfg(test)]
 pub(crate) use parent_account;
 #[cfg(test)]
 pub(crate) use program_token_account_info;
@@ -0,0 +1,139 @@
+//! Canonical types exchanged between clients and Wardens (relayers) over the Warden network API
+//!
+//! # Notes
+//!
+//! All jobs embed the on-chain request types, so the API cannot drift from the program's instruction data.
+//! Types are serializable with borsh and (using the `serde` feature) with serde.
+
+use crate::processor::{BaseCommitmentHashRequest, FinalizeSendData, ProofRequest};
+use crate::state::proof::VerificationState;
+use crate::types::RawProof;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/// Version of the network API types
+/// - needs to be incremented with every breaking change to the types of this module (or the embedded request types)
+pub const NETWORK_API_VERSION: u16 = 1;
+
+/// A deposit for which the Warden acts as the fee-payer of the base-commitment hash computation
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct DepositJob {
+    pub sender: Pubkey,
+
+    /// Serialized and signed transaction of the `sender` storing the base-commitment
+    pub signed_transaction: Vec<u8>,
+    pub request: BaseCommitmentHashRequest,
+}
+
+/// A send whose proof is verified and finalized by the Warden
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SendJob {
+    pub request: ProofRequest,
+    pub proof: RawProof,
+    pub finalize_data: FinalizeSendData,
+
+    pub recipient: Pubkey,
+    pub identifier: Pubkey,
+    pub transaction_reference: Option<Pubkey>,
+    pub memo: Option<Vec<u8>>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum Job {
+    Deposit(DepositJob),
+    Send(Box<SendJob>),
+}
+
+/// Binding offer of a Warden to process a job
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Quote {
+    pub api_version: u16,
+    pub warden: Pubkey,
+    pub token_id: u16,
+    pub fee_version: u32,
+
+    /// Total fee charged by the Warden for the job (in the token)
+    pub fee: u64,
+
+    /// Slot after which the quote is no longer honored
+    pub valid_until_slot: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum JobStatus {
+    Queued,
+
+    /// The base-commitment or commitment hash computation is in progress
+    Hashing,
+
+    /// The proof verification is in progress
+    Verifying {
+        state: VerificationState,
+    },
+
+    /// The job has been completed with the transaction `signature`
+    Completed {
+        signature: Vec<u8>,
+    },
+
+    /// The job failed with a program error code
+    Failed {
+        error_code: u32,
+    },
+}
+
+impl JobStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, JobStatus::Completed { .. } | JobStatus::Failed { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RawU256;
+
+    #[test]
+    fn test_job_serialization() {
+        let job = Job::Deposit(DepositJob {
+            sender: Pubkey::new_unique(),
+            signed_transaction: vec![1, 2, 3],
+            request: BaseCommitmentHashRequest {
+                base_commitment: RawU256::new([1; 32]),
+                commitment_index: 0,
+                amount: 100,
+                token_id: 0,
+                commitment: RawU256::new([2; 32]),
+                fee_version: 0,
+                min_batching_rate: 0,
+                fee_payer_repayment: 0,
+                anonymity_delay_slots: 0,
+                is_priority: false,
+            },
+        });
+
+        assert_eq!(
+            Job::try_from_slice(&job.try_to_vec().unwrap()).unwrap(),
+            job
+        );
+        assert_eq!(
+            serde_json::from_str::<Job>(&serde_json::to_string(&job).unwrap()).unwrap(),
+            job
+        );
+    }
+
+    #[test]
+    fn test_job_status() {
+        assert!(!JobStatus::Queued.is_terminal());
+        assert!(!JobStatus::Verifying {
+            state: VerificationState::ProofSetup
+        }
+        .is_terminal());
+        assert!(JobStatus::Failed { error_code: 0 }.is_terminal());
+    }
+}
@@ -0,0 +1,171 @@
+//! Pyth price oracle used to convert lamport-denominated fees into SPL tokens
+//!
+//! # Notes
+//!
+//! The price accounts are taken from the registry in the [`GovernorAccount`] (falling back to the built-in price accounts of the tokens).
+//! Prices are rejected if the feed is not trading, is older than `max_price_age` or has a confidence interval wider than `max_price_confidence_bps`.
+
+use crate::error::ElusivError;
+use crate::macros::guard;
+use crate::state::governor::GovernorAccount;
+use crate::token::{elusiv_token, load_price_feed_from_account_info, Price, TokenID, TokenPrice};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError};
+
+/// Loads the [`TokenPrice`] of `token_id` from the Pyth price accounts registered in the `governor`
+pub fn token_price(
+    governor: &GovernorAccount,
+    sol_usd_price_account: &AccountInfo,
+    token_usd_price_account: &AccountInfo,
+    token_id: TokenID,
+) -> Result<TokenPrice, ProgramError> {
+    if token_id == 0 {
+        return Ok(TokenPrice::new_lamports());
+    }
+
+    let lamports_usd = load_usd_price(governor, sol_usd_price_account, 0)?;
+    let token_usd = load_usd_price(governor, token_usd_price_account, token_id)?;
+
+    Ok(TokenPrice::new_from_price(
+        lamports_usd,
+        token_usd,
+        token_id,
+    ))
+}
+
+/// Loads the USD price of the smallest unit of `token_id`
+fn load_usd_price(
+    governor: &GovernorAccount,
+    price_account: &AccountInfo,
+    token_id: TokenID,
+) -> Result<Price, ProgramError> {
+    guard!(
+        *price_account.key == governor.price_account(token_id)?,
+        ElusivError::OracleError
+    );
+
+    let price_feed =
+        load_price_feed_from_account_info(price_account).or(Err(ElusivError::OracleError))?;
+    let price = price_feed
+        .get_current_price()
+        .ok_or(ElusivError::OracleError)?;
+
+    let max_price_age = governor.get_max_price_age();
+    if max_price_age > 0 {
+        let age = current_timestamp()?.saturating_sub(price_feed.publish_time);
+        guard!(age <= max_price_age as i64, ElusivError::OracleError);
+    }
+
+    guard!(
+        is_price_confident(&price, governor.get_max_price_confidence_bps()),
+        ElusivError::OracleError
+    );
+
+    let price = price
+        .cmul(1, -(elusiv_token(token_id)?.price_base_exp as i32))
+        .ok_or(ElusivError::OracleError)?;
+
+    Ok(price)
+}
+
+/// Whether the confidence interval of `price` is at most `max_confidence_bps` basis points of the price (zero disables the check)
+pub fn is_price_confident(price: &Price, max_confidence_bps: u16) -> bool {
+    if max_confidence_bps == 0 {
+        return true;
+    }
+
+    if price.price <= 0 {
+        return false;
+    }
+
+    price.conf as u128 * 10_000 <= price.price as u128 * max_confidence_bps as u128
+}
+
+/// Returns the current unix timestamp
+fn current_timestamp() -> Result<i64, ProgramError> {
+    use solana_program::sysvar::{clock::Clock, Sysvar};
+    Ok(Clock::get()?.unix_timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{account_info, pyth_price_account_info, zero_program_account};
+    use crate::syscall_stubs::set_test_unix_timestamp;
+    use crate::token::{Lamports, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID};
+    use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+
+    #[test]
+    fn test_is_price_confident() {
+        let price = Price {
+            price: 10_000,
+            conf: 100,
+            expo: 0,
+        };
+
+        assert!(is_price_confident(&price, 0));
+        assert!(is_price_confident(&price, 100));
+        assert!(!is_price_confident(&price, 99));
+
+        let negative = Price {
+            price: -10_000,
+            conf: 0,
+            expo: 0,
+        };
+        assert!(!is_price_confident(&negative, 100));
+    }
+
+    #[test]
+    fn test_token_price() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        let sol_usd = Price {
+            price: 39,
+            conf: 1,
+            expo: 0,
+        };
+        let usdc_usd = Price {
+            price: 1,
+            conf: 1,
+            expo: 0,
+        };
+        pyth_price_account_info!(sol, LAMPORTS_TOKEN_ID, sol_usd);
+        pyth_price_account_info!(usdc, USDC_TOKEN_ID, usdc_usd);
+
+        let price = token_price(&governor, &sol, &usdc, USDC_TOKEN_ID).unwrap();
+        assert_eq!(
+            Lamports(LAMPORTS_PER_SOL)
+                .into_token(&price, USDC_TOKEN_ID)
+                .ok()
+                .map(|token| token.amount()),
+            Some(39_000_000)
+        );
+
+        // Invalid price account
+        assert!(token_price(&governor, &usdc, &usdc, USDC_TOKEN_ID).is_err());
+
+        // Price is not confident enough (1 / 39 > 1%)
+        governor.set_max_price_confidence_bps(&100);
+        assert!(token_price(&governor, &sol, &usdc, USDC_TOKEN_ID).is_err());
+        governor.set_max_price_confidence_bps(&0);
+
+        // Price is older than the max price age (the feeds are published at timestamp zero)
+        governor.set_max_price_age(&60);
+        set_test_unix_timestamp(61);
+        assert!(token_price(&governor, &sol, &usdc, USDC_TOKEN_ID).is_err());
+        set_test_unix_timestamp(60);
+        assert!(token_price(&governor, &sol, &usdc, USDC_TOKEN_ID).is_ok());
+        governor.set_max_price_age(&0);
+
+        // Registered price account
+        let key = Pubkey::new_unique();
+        governor.set_price_accounts(USDC_TOKEN_ID as usize, &key.to_bytes());
+        assert!(token_price(&governor, &sol, &usdc, USDC_TOKEN_ID).is_err());
+
+        let data = crate::token::pyth_price_account_data(&usdc_usd).unwrap();
+        account_info!(registered_usdc, key, data);
+        assert!(token_price(&governor, &sol, &registered_usdc, USDC_TOKEN_ID).is_ok());
+
+        // Lamports require no price
+        assert!(token_price(&governor, &usdc, &usdc, LAMPORTS_TOKEN_ID).is_ok());
+    }
+}
@@ -1,97 +1,370 @@
 use super::utils::*;
 use crate::bytes::{is_zero, BorshSerDeSized, ElusivOption};
-use crate::commitment::DEFAULT_COMMITMENT_BATCHING_RATE;
+use crate::commitment::{
+    commitments_per_batch, DEFAULT_COMMITMENT_BATCHING_RATE, MAX_COMMITMENT_BATCHING_RATE,
+};
+use crate::computation::current_slot;
 use crate::error::ElusivError;
+use crate::events::ElusivEvent;
 use crate::macros::*;
+use crate::proof::verifier::MAX_COMPUTE_UNIT_MARGIN;
 use crate::state::commitment::{BaseCommitmentBufferAccount, CommitmentHashingAccount};
 use crate::state::{
-    fee::{FeeAccount, ProgramFee},
-    governor::{FeeCollectorAccount, GovernorAccount, PoolAccount},
-    nullifier::{NullifierAccount, NullifierChildAccount},
-    queue::{CommitmentQueue, CommitmentQueueAccount, Queue},
-    storage::{StorageAccount, MT_COMMITMENT_COUNT},
+    child::{ChildAccountParent, ChildAccountRecordAccount},
+    config::ConfigAccount,
+    denylist::{
+        recipient_denylist_offset, recipient_denylist_shard, RecipientDenylistAccount,
+        MAX_DENIED_RECIPIENTS_BATCH, RECIPIENT_DENYLIST_SHARD_COUNT,
+    },
+    event::EventSequenceAccount,
+    fee::{
+        FeeAccount, ProgramFee, TokenFeeSchedule, DEFAULT_NETWORK_FEE_SPLIT, MAX_LAMPORTS_PER_CU,
+    },
+    governor::{
+        FeeCollectorAccount, GovernanceMultisig, GovernorAccount, PoolAccount,
+        StagedGovernorParameters, PARAMETER_CHANGE_TIMELOCK_SLOTS,
+    },
+    note::NoteCiphertextAccount,
+    nullifier::{
+        nullifier_account_offsets, ArchivedTreeAccount, NullifierAccount, NullifierChildAccount,
+        TreeRentContributionAccount, MIN_SEAL_SUNSET_SLOTS,
+    },
+    poseidon::{PoseidonParamsAccount, POSEIDON_PARAMS_CHUNK_SIZE},
+    program_account::{Lifecycle, ReallocatableAccount},
+    queue::{
+        is_reallocatable_queue, CommitmentQueue, CommitmentQueueAccount,
+        CommitmentSpillQueueAccount, Queue, RingQueue,
+    },
+    stats::{EpochMetricsAccount, StatsAccount, STATS_TOKEN_COUNT},
+    storage::{token_pool_offset, StorageAccount, MT_COMMITMENT_COUNT},
+    summary::{StateDigestHistoryAccount, StateSummaryAccount},
 };
+use crate::token::{elusiv_token, TokenID};
+use crate::types::U256;
 use crate::{bytes::usize_as_u32_safe, map::ElusivMap};
+use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_types::{
-    split_child_account_data_mut, ChildAccount, ChildAccountConfig, ParentAccount, SizedAccount,
-    UnverifiedAccountInfo,
+    split_child_account_data_mut, ChildAccount, ChildAccountConfig, PDAAccount, PDAOffsets,
+    ParentAccount, UnverifiedAccountInfo,
 };
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError, rent::Rent,
-    sysvar::Sysvar,
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
 };
 
 /// Opens one single instance [`elusiv_types::PDAAccount`], as long this PDA does not already exist
+#[allow(clippy::too_many_arguments)]
 pub fn open_single_instance_accounts<'a, 'b>(
     payer: &AccountInfo<'b>,
     pool_account: UnverifiedAccountInfo<'a, 'b>,
     fee_collector_account: UnverifiedAccountInfo<'a, 'b>,
     commitment_hashing_account: UnverifiedAccountInfo<'a, 'b>,
     commitment_queue_account: UnverifiedAccountInfo<'a, 'b>,
+    commitment_spill_queue_account: UnverifiedAccountInfo<'a, 'b>,
     storage_account: UnverifiedAccountInfo<'a, 'b>,
     base_commitment_buffer_account: UnverifiedAccountInfo<'a, 'b>,
+    event_sequence_account: UnverifiedAccountInfo<'a, 'b>,
+    state_summary_account: UnverifiedAccountInfo<'a, 'b>,
+    stats_account: UnverifiedAccountInfo<'a, 'b>,
+    epoch_metrics_account: UnverifiedAccountInfo<'a, 'b>,
+    state_digest_history_account: UnverifiedAccountInfo<'a, 'b>,
+    recipient_denylist_account: UnverifiedAccountInfo<'a, 'b>,
+    config_account: UnverifiedAccountInfo<'a, 'b>,
 ) -> ProgramResult {
-    open_pda_account_without_offset::<PoolAccount>(
-        &crate::id(),
+    PoolAccount::open(payer, pool_account.get_unsafe(), None, None, None)?;
+    FeeCollectorAccount::open(payer, fee_collector_account.get_unsafe(), None, None, None)?;
+    CommitmentHashingAccount::open(
+        payer,
+        commitment_hashing_account.get_unsafe(),
+        None,
+        None,
+        None,
+    )?;
+    CommitmentQueueAccount::open(
+        payer,
+        commitment_queue_account.get_unsafe(),
+        None,
+        None,
+        None,
+    )?;
+    CommitmentSpillQueueAccount::open(
+        payer,
+        commitment_spill_queue_account.get_unsafe(),
+        None,
+        None,
+        None,
+    )?;
+    StorageAccount::open(payer, storage_account.get_unsafe(), None, None, None)?;
+    BaseCommitmentBufferAccount::open(
+        payer,
+        base_commitment_buffer_account.get_unsafe(),
+        None,
+        None,
+        None,
+    )?;
+    EventSequenceAccount::open(payer, event_sequence_account.get_unsafe(), None, None, None)?;
+    StateSummaryAccount::open(payer, state_summary_account.get_unsafe(), None, None, None)?;
+    StatsAccount::open(payer, stats_account.get_unsafe(), None, None, None)?;
+    EpochMetricsAccount::open(payer, epoch_metrics_account.get_unsafe(), None, None, None)?;
+    StateDigestHistoryAccount::open(
         payer,
-        pool_account.get_unsafe(),
+        state_digest_history_account.get_unsafe(),
+        None,
+        None,
         None,
     )?;
-    open_pda_account_without_offset::<FeeCollectorAccount>(
-        &crate::id(),
+    RecipientDenylistAccount::open(
         payer,
-        fee_collector_account.get_unsafe(),
+        recipient_denylist_account.get_unsafe(),
+        None,
+        None,
         None,
     )?;
-    open_pda_account_without_offset::<CommitmentHashingAccount>(
-        &crate::id(),
+    ConfigAccount::open(payer, config_account.get_unsafe(), None, None, None)?;
+
+    Ok(())
+}
+
+/// Opens the accounts of the shielded pool of `token_id` (the lamports pool is opened with [`open_single_instance_accounts`])
+/// - each pool has its own active MT, commitment queue and state summary (and [`NullifierAccount`]s)
+#[allow(clippy::too_many_arguments)]
+pub fn open_token_pool_accounts<'a, 'b>(
+    payer: &AccountInfo<'b>,
+    governor: &GovernorAccount,
+    commitment_hashing_account: UnverifiedAccountInfo<'a, 'b>,
+    commitment_queue_account: UnverifiedAccountInfo<'a, 'b>,
+    commitment_spill_queue_account: UnverifiedAccountInfo<'a, 'b>,
+    storage_account: UnverifiedAccountInfo<'a, 'b>,
+    state_summary_account: UnverifiedAccountInfo<'a, 'b>,
+
+    token_id: u16,
+) -> ProgramResult {
+    let pool_offset = token_pool_offset(token_id);
+    guard!(pool_offset.is_some(), ElusivError::InvalidInstructionData);
+    guard!(
+        governor.is_token_supported(token_id),
+        ElusivError::TokenIsNotSupported
+    );
+
+    CommitmentHashingAccount::open(
         payer,
         commitment_hashing_account.get_unsafe(),
         None,
+        pool_offset,
+        None,
     )?;
-    open_pda_account_without_offset::<CommitmentQueueAccount>(
-        &crate::id(),
+    CommitmentQueueAccount::open(
         payer,
         commitment_queue_account.get_unsafe(),
         None,
+        pool_offset,
+        None,
     )?;
-    open_pda_account_without_offset::<StorageAccount>(
-        &crate::id(),
+    CommitmentSpillQueueAccount::open(
         payer,
-        storage_account.get_unsafe(),
+        commitment_spill_queue_account.get_unsafe(),
+        None,
+        pool_offset,
         None,
     )?;
-    open_pda_account_without_offset::<BaseCommitmentBufferAccount>(
-        &crate::id(),
+    StorageAccount::open(payer, storage_account.get_unsafe(), None, pool_offset, None)?;
+    StateSummaryAccount::open(
         payer,
-        base_commitment_buffer_account.get_unsafe(),
+        state_summary_account.get_unsafe(),
+        None,
+        pool_offset,
         None,
     )?;
 
     Ok(())
 }
 
+/// Opens the [`NoteCiphertextAccount`] of the shielded pool of `token_id`
+pub fn open_note_ciphertext_account<'b>(
+    payer: &AccountInfo<'b>,
+    governor: &GovernorAccount,
+    note_ciphertext_account: UnverifiedAccountInfo<'_, 'b>,
+
+    token_id: u16,
+) -> ProgramResult {
+    guard!(
+        governor.is_token_supported(token_id),
+        ElusivError::TokenIsNotSupported
+    );
+
+    NoteCiphertextAccount::open(
+        payer,
+        note_ciphertext_account.get_unsafe(),
+        None,
+        token_pool_offset(token_id),
+        None,
+    )
+}
+
 pub fn open_nullifier_account<'b>(
     payer: &AccountInfo<'b>,
     nullifier_account: UnverifiedAccountInfo<'_, 'b>,
+    rent_contribution: &AccountInfo<'b>,
 
     mt_index: u32,
+    token_id: u16,
 ) -> ProgramResult {
-    open_pda_account_with_offset::<NullifierAccount>(
-        &crate::id(),
+    let offsets = nullifier_account_offsets(token_id, mt_index);
+    let nullifier_account = nullifier_account.get_unsafe();
+    NullifierAccount::open_with_offsets(payer, nullifier_account, None, &offsets, None)?;
+
+    record_tree_rent_contribution(
         payer,
-        nullifier_account.get_unsafe(),
+        payer.key,
+        rent_contribution,
         mt_index,
-        None,
+        &offsets,
+        nullifier_account.lamports(),
     )
 }
 
+/// Adds `amount` to the [`TreeRentContributionAccount`] of `contributor` (opened by `payer` if it does not exist yet)
+fn record_tree_rent_contribution<'b>(
+    payer: &AccountInfo<'b>,
+    contributor: &Pubkey,
+    rent_contribution: &AccountInfo<'b>,
+    mt_index: u32,
+    offsets: PDAOffsets,
+    amount: u64,
+) -> ProgramResult {
+    if rent_contribution.lamports() == 0 {
+        TreeRentContributionAccount::open_with_offsets(
+            payer,
+            rent_contribution,
+            Some(*contributor),
+            offsets,
+            None,
+        )?;
+
+        pda_account!(
+            mut contribution,
+            TreeRentContributionAccount,
+            rent_contribution
+        );
+        contribution.set_contributor(contributor);
+        contribution.set_mt_index(&mt_index);
+    }
+
+    pda_account!(
+        mut contribution,
+        TreeRentContributionAccount,
+        rent_contribution
+    );
+    let amount = contribution
+        .get_amount()
+        .checked_add(amount)
+        .ok_or(ElusivError::InvalidAmount)?;
+    contribution.set_amount(&amount);
+
+    Ok(())
+}
+
+/// Creates the child-account with `child_index` of the `parent` with the exact required size and records the `payer` and the tag
+///
+/// # Notes
+///
+/// The program can only create child-accounts of up to [`solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE`] bytes.
+/// Larger child-accounts need to be created (program-owned and zeroed) by the `payer` in the same transaction, they are only verified.
+pub fn create_child_account<'b>(
+    payer: &AccountInfo<'b>,
+    sub_account: &AccountInfo<'b>,
+    child_account_record: &AccountInfo<'b>,
+
+    parent: ChildAccountParent,
+    child_index: u32,
+) -> ProgramResult {
+    let parent_pubkey = parent.pubkey()?;
+    let size = parent.child_account_size(child_index)?;
+
+    if sub_account.lamports() == 0 {
+        create_program_data_account(payer, sub_account, size)?;
+    } else {
+        verify_extern_data_account(sub_account, size, true, &DefaultRentSysvar)?;
+    }
+
+    ChildAccountRecordAccount::open(
+        payer,
+        child_account_record,
+        Some(*sub_account.key),
+        None,
+        None,
+    )?;
+
+    pda_account!(mut record, ChildAccountRecordAccount, child_account_record);
+    record.set_payer(payer.key);
+    record.set_parent(&parent_pubkey);
+    record.set_child_index(&child_index);
+
+    Ok(())
+}
+
+/// A [`ReallocatableAccount`] that can be grown with [`realloc_account`]
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum ReallocatableAccountKind {
+    Governor,
+    CommitmentQueue { token_id: TokenID },
+    FeeCollector,
+    Nullifier { token_id: TokenID, mt_index: u32 },
+}
+
+/// Grows the data of a [`ReallocatableAccount`] by up to [`solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE`] bytes towards its current size
+/// - the signing `payer` funds the additional rent
+/// - a [`CommitmentQueueAccount`] can only be grown while its ring buffer has not wrapped around
+/// - needs to be repeated (in separate transactions) until the account has reached its size
+pub fn realloc_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+
+    kind: ReallocatableAccountKind,
+) -> ProgramResult {
+    let pubkey = match kind {
+        ReallocatableAccountKind::Governor => GovernorAccount::find(None).0,
+        ReallocatableAccountKind::CommitmentQueue { token_id } => {
+            elusiv_token(token_id)?;
+            CommitmentQueueAccount::find(token_pool_offset(token_id)).0
+        }
+        ReallocatableAccountKind::FeeCollector => FeeCollectorAccount::find(None).0,
+        ReallocatableAccountKind::Nullifier { token_id, mt_index } => {
+            elusiv_token(token_id)?;
+            NullifierAccount::find_with_offsets(
+                None,
+                &nullifier_account_offsets(token_id, mt_index),
+            )
+            .0
+        }
+    };
+    guard!(*account.key == pubkey, ElusivError::InvalidAccount);
+    guard!(*account.owner == crate::id(), ElusivError::InvalidAccount);
+
+    match kind {
+        ReallocatableAccountKind::Governor => GovernorAccount::realloc(payer, account)?,
+        ReallocatableAccountKind::CommitmentQueue { .. } => {
+            guard!(
+                is_reallocatable_queue(&account.data.borrow()),
+                ElusivError::InvalidAccountState
+            );
+            CommitmentQueueAccount::realloc(payer, account)?
+        }
+        ReallocatableAccountKind::FeeCollector => FeeCollectorAccount::realloc(payer, account)?,
+        ReallocatableAccountKind::Nullifier { .. } => NullifierAccount::realloc(payer, account)?,
+    };
+
+    Ok(())
+}
+
 /// Enables the supplied child-account for the [`StorageAccount`]
 pub fn enable_storage_child_account(
     storage_account: &mut StorageAccount,
     child_account: &AccountInfo,
 
     child_index: u32,
+    _token_id: u16,
 ) -> ProgramResult {
     // Note: we don't zero-check these accounts, since we will never access data that has not been set by the program
     setup_child_account(
@@ -110,13 +383,34 @@ pub fn enable_storage_child_account(
 /// Requires a prior call to [`open_multi_instance_account`].
 ///
 /// The [`NullifierAccount`] will be useless until the MT with `index = merkle_tree_index - 1` is closed.
-pub fn enable_nullifier_child_account(
+///
+/// The rent of the `child_account` is recorded as contributed by the `creator` (the payer recorded by [`create_child_account`]).
+#[allow(clippy::too_many_arguments)]
+pub fn enable_nullifier_child_account<'b>(
+    payer: &AccountInfo<'b>,
     nullifier_account: &mut NullifierAccount,
-    child_account: &AccountInfo,
+    child_account: &AccountInfo<'b>,
+    child_account_record: &ChildAccountRecordAccount,
+    rent_contribution: &AccountInfo<'b>,
 
-    _merkle_tree_index: u32,
+    merkle_tree_index: u32,
     child_index: u32,
+    token_id: u16,
+    creator: Pubkey,
 ) -> ProgramResult {
+    let parent = ChildAccountParent::Nullifier {
+        token_id,
+        mt_index: merkle_tree_index,
+    };
+    guard!(
+        child_account_record.is_child_of(&parent.pubkey()?, child_index),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        child_account_record.get_payer() == creator,
+        ElusivError::InvalidAccount
+    );
+
     // Note: we don't zero-check these accounts, BUT we need to manipulate the maps we store in each account and set the size to zero
     setup_child_account(
         nullifier_account,
@@ -129,7 +423,14 @@ pub fn enable_nullifier_child_account(
     // Set map size to zero
     reset_map_child_account::<NullifierChildAccount>(child_account)?;
 
-    Ok(())
+    record_tree_rent_contribution(
+        payer,
+        &creator,
+        rent_contribution,
+        merkle_tree_index,
+        &nullifier_account_offsets(token_id, merkle_tree_index),
+        child_account.lamports(),
+    )
 }
 
 /// Closes the active MT and activates the next one
@@ -143,8 +444,11 @@ pub fn reset_active_merkle_tree(
     storage_account: &mut StorageAccount,
     queue: &mut CommitmentQueueAccount,
     active_nullifier_account: &mut NullifierAccount,
+    state_summary: &mut StateSummaryAccount,
+    event_sequence: &mut EventSequenceAccount,
 
     active_merkle_tree_index: u32,
+    _token_id: u16,
 ) -> ProgramResult {
     guard!(
         storage_account.get_trees_count() == active_merkle_tree_index,
@@ -157,11 +461,75 @@ pub fn reset_active_merkle_tree(
         ElusivError::MerkleTreeIsNotFullYet
     );
 
-    storage_account.set_trees_count(&(active_merkle_tree_index.checked_add(1).ok_or(MATH_ERR)?));
-    active_nullifier_account.set_root(&storage_account.get_root()?);
+    let previous_root = storage_account.get_root()?;
+    let next_merkle_tree_index = active_merkle_tree_index.checked_add(1).ok_or(MATH_ERR)?;
+    storage_account.set_trees_count(&next_merkle_tree_index);
+    active_nullifier_account.set_root(&previous_root);
+    active_nullifier_account.set_last_activity_slot(&current_slot()?);
+    state_summary.update_closed_mt(storage_account, active_merkle_tree_index)?;
     storage_account.reset();
 
-    Ok(())
+    event_sequence.emit(&ElusivEvent::RootUpdate {
+        mt_index: next_merkle_tree_index,
+        new_root: storage_account.get_root()?,
+        previous_root,
+        batch_size: 0,
+        slot: current_slot()?,
+    })
+}
+
+/// Closes the full active MT and opens the next MT together with its [`NullifierAccount`]
+/// - the commitment hashing needs to be idle, so that the next commitment batch is inserted into the new MT
+/// - an already opened [`NullifierAccount`] of the next MT is reused
+#[allow(clippy::too_many_arguments)]
+pub fn rotate_merkle_tree<'b>(
+    payer: &AccountInfo<'b>,
+    storage_account: &mut StorageAccount,
+    queue: &mut CommitmentQueueAccount,
+    hashing_account: &CommitmentHashingAccount,
+    active_nullifier_account: &mut NullifierAccount,
+    next_nullifier_account: UnverifiedAccountInfo<'_, 'b>,
+    rent_contribution: &AccountInfo<'b>,
+    state_summary: &mut StateSummaryAccount,
+    event_sequence: &mut EventSequenceAccount,
+
+    active_mt_index: u32,
+    token_id: u16,
+) -> ProgramResult {
+    guard!(
+        !hashing_account.get_is_active() && !hashing_account.get_setup(),
+        ElusivError::ComputationIsNotYetFinished
+    );
+
+    reset_active_merkle_tree(
+        storage_account,
+        queue,
+        active_nullifier_account,
+        state_summary,
+        event_sequence,
+        active_mt_index,
+        token_id,
+    )?;
+
+    let next_mt_index = storage_account.get_trees_count();
+    let account = next_nullifier_account.get_unsafe();
+    if account.lamports() > 0 {
+        let offsets = nullifier_account_offsets(token_id, next_mt_index);
+        guard!(
+            *account.key == NullifierAccount::find_with_offsets(None, &offsets).0,
+            ElusivError::InvalidAccount
+        );
+
+        return Ok(());
+    }
+
+    open_nullifier_account(
+        payer,
+        next_nullifier_account,
+        rent_contribution,
+        next_mt_index,
+        token_id,
+    )
 }
 
 fn is_mt_full(
@@ -181,20 +549,86 @@ fn is_mt_full(
     Ok(false)
 }
 
-/// Archives a closed MT by creating creating a N-SMT in an [`ArchivedTreeAccount`]
+/// Archives a closed MT by storing its root and the digest of its nullifier-set in an [`ArchivedTreeAccount`]
+/// - proofs can be verified against the root of the [`ArchivedTreeAccount`] (see [`crate::processor::init_verification`])
+/// - the compaction of the [`NullifierAccount`] requires the N-SMT, the rent is reclaimed by closing the child-accounts of sealed MTs (see [`claim_tree_rent_refund`])
 pub fn archive_closed_merkle_tree<'a>(
-    _payer: &AccountInfo<'a>,
-    storage_account: &mut StorageAccount,
-    _nullifier_account: &mut NullifierAccount,
-    _archived_tree_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    storage_account: &StorageAccount,
+    nullifier_account: &NullifierAccount,
+    archived_tree_account: &AccountInfo<'a>,
 
     closed_merkle_tree_index: u32,
+    token_id: u16,
 ) -> ProgramResult {
     guard!(
         storage_account.get_trees_count() > closed_merkle_tree_index,
         ElusivError::InvalidInstructionData
     );
-    panic!("N-SMT not implemented yet");
+
+    ArchivedTreeAccount::open_with_offsets(
+        payer,
+        archived_tree_account,
+        None,
+        &nullifier_account_offsets(token_id, closed_merkle_tree_index),
+        None,
+    )?;
+
+    pda_account!(
+        mut archived_tree,
+        ArchivedTreeAccount,
+        archived_tree_account
+    );
+    archived_tree.set_mt_index(&closed_merkle_tree_index);
+    archived_tree.set_commitment_root(&nullifier_account.get_root());
+    archived_tree.set_nullifier_digest(&nullifier_account.get_nullifier_digest());
+    archived_tree.set_nullifier_hash_count(&nullifier_account.get_nullifier_hash_count());
+
+    Ok(())
+}
+
+/// Refunds a portion of the rent contributed to an archived MT (see [`TreeRentContributionAccount`])
+/// - requires all child-accounts of the MT to be closed (see [`crate::processor::close_nullifier_child_account`]), independent of the pool of the MT
+/// - the `fee_collector` pays the refund out of the rent reclaimed from the MT
+pub fn claim_tree_rent_refund<'a>(
+    contributor: &AccountInfo<'a>,
+    rent_contribution: &AccountInfo<'a>,
+    nullifier_account: &mut NullifierAccount,
+    fee_collector: &AccountInfo<'a>,
+
+    mt_index: u32,
+    _token_id: u16,
+) -> ProgramResult {
+    guard!(
+        nullifier_account.are_child_accounts_closed(),
+        ElusivError::MerkleTreeIsNotArchived
+    );
+
+    let refund = {
+        pda_account!(contribution, TreeRentContributionAccount, rent_contribution);
+        guard!(
+            contribution.get_contributor() == *contributor.key,
+            ElusivError::InvalidAccount
+        );
+        guard!(
+            contribution.get_mt_index() == mt_index,
+            ElusivError::InvalidAccount
+        );
+        std::cmp::min(
+            contribution.refund_amount(),
+            nullifier_account.refundable_rent(),
+        )
+    };
+
+    let refunded_rent = nullifier_account
+        .get_refunded_rent()
+        .checked_add(refund)
+        .ok_or(ElusivError::Overflow)?;
+    nullifier_account.set_refunded_rent(&refunded_rent);
+
+    // `fee_collector` transfers `refund` to `contributor` (lamports)
+    transfer_lamports_from_pda_checked(fee_collector, contributor, refund)?;
+    TreeRentContributionAccount::close(contributor, rent_contribution)
 }
 
 /// Setup the [`GovernorAccount`] with the default values
@@ -206,325 +640,2502 @@ pub fn setup_governor_account<'b>(
     payer: &AccountInfo<'b>,
     governor_account: UnverifiedAccountInfo<'_, 'b>,
 ) -> ProgramResult {
-    open_pda_account_without_offset::<GovernorAccount>(
-        &crate::id(),
-        payer,
-        governor_account.get_unsafe(),
-        None,
-    )?;
+    GovernorAccount::open(payer, governor_account.get_unsafe(), None, None, None)?;
 
     pda_account!(mut governor, GovernorAccount, governor_account.get_unsafe());
     governor.set_commitment_batching_rate(&usize_as_u32_safe(DEFAULT_COMMITMENT_BATCHING_RATE));
 
-    Ok(())
-}
-
-/// Changes the state of the [`GovernorAccount`]
-pub fn upgrade_governor_state(
-    _authority: &AccountInfo,
-    _governor_account: &mut GovernorAccount,
-    _commitment_queue: &CommitmentQueueAccount,
+    for token_id in 0..STATS_TOKEN_COUNT {
+        governor.set_supported_tokens(token_id, &true);
+    }
 
-    _fee_version: u32,
-    _batching_rate: u32,
-) -> ProgramResult {
-    todo!("Not implemented yet");
-    // TODO: changes in the batching rate are only possible when checking the commitment queue
-    // TODO: fee changes require empty queues
+    Ok(())
 }
 
-/// Setup a new [`FeeAccount`]
-///
-/// # Note
-///
-/// There is no way of upgrading the program fees atm.
-pub fn init_new_fee_version<'b>(
-    payer: &AccountInfo<'b>,
+/// Proposes a change of the fee-version and the commitment batching rate, activated with [`upgrade_governor_state`] at `activation_slot`
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - `activation_slot` needs to be at least [`PARAMETER_CHANGE_TIMELOCK_SLOTS`] in the future
+/// - a previously proposed change is replaced
+pub fn propose_governor_state(
     governor: &mut GovernorAccount,
-    mut new_fee_account: UnverifiedAccountInfo<'_, 'b>,
+    config: &ConfigAccount,
+    signers: &[&AccountInfo],
 
     fee_version: u32,
-    program_fee: ProgramFee,
+    batching_rate: u32,
+    activation_slot: u64,
 ) -> ProgramResult {
-    // Note: we have no upgrade-authroity check here since with the current setup it's impossible to have a fee version higher than zero, so will be added once that changes
+    verify_governance_authority(governor, signers)?;
+
+    let current_fee_version = governor.get_fee_version();
     guard!(
-        fee_version == governor.get_fee_version(),
+        fee_version == current_fee_version
+            || Some(fee_version) == current_fee_version.checked_add(1),
         ElusivError::InvalidFeeVersion
     );
-    guard!(program_fee.is_valid(), ElusivError::InvalidInstructionData);
+    guard!(
+        batching_rate <= config.effective_max_commitment_batching_rate(),
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        activation_slot >= current_slot()?.saturating_add(PARAMETER_CHANGE_TIMELOCK_SLOTS),
+        ElusivError::InvalidInstructionData
+    );
 
-    open_pda_account_with_offset::<FeeAccount>(
-        &crate::id(),
-        payer,
-        new_fee_account.get_unsafe_and_set_is_verified(),
+    governor.set_staged_parameters(&StagedGovernorParameters {
+        is_staged: true,
         fee_version,
-        None,
-    )?;
-
-    pda_account!(mut fee_account, FeeAccount, new_fee_account.get_safe()?);
-    fee_account.set_program_fee(&program_fee);
-    governor.set_program_fee(&program_fee);
+        commitment_batching_rate: batching_rate,
+        activation_slot,
+    });
 
     Ok(())
 }
 
-/// Closes a program owned account in devnet and localhost
-///
-/// # Note
-///
-/// - `signer` needs to be the program's keypair
-/// - `recipient` receives the accounts Lamports
-#[cfg(not(feature = "mainnet"))]
-pub fn close_program_account<'a>(
-    signer: &AccountInfo,
-    recipient: &AccountInfo<'a>,
-    program_account: &AccountInfo<'a>,
-) -> ProgramResult {
-    assert!(!cfg!(feature = "mainnet"));
-    assert_eq!(*signer.key, crate::ID);
+// The commitment batching rate is shared by all pools, whose queues are passed explicitly to the instructions changing it
+#[cfg(test)]
+const_assert_eq!(STATS_TOKEN_COUNT, 3);
 
-    elusiv_utils::close_account(recipient, program_account)
-}
+/// Activates the fee-version and the commitment batching rate proposed with [`propose_governor_state`] once its activation slot is reached (permissionless)
+/// - `fee_version` and `batching_rate` need to match the proposed change
+/// - the [`FeeAccount`] of `fee_version` needs to be initialized (see [`init_new_fee_version`]), its fees are mirrored into the governor
+/// - the batching rate is only activated in between two batches of every pool (see [`verify_commitment_batching_rate_change`])
+#[allow(clippy::too_many_arguments)]
+pub fn upgrade_governor_state(
+    governor: &mut GovernorAccount,
+    fee: &FeeAccount,
+    commitment_hash_queue: &mut CommitmentQueueAccount,
+    commitment_hashing_account: &CommitmentHashingAccount,
+    usdc_commitment_hash_queue: &mut CommitmentQueueAccount,
+    usdc_commitment_hashing_account: &CommitmentHashingAccount,
+    usdt_commitment_hash_queue: &mut CommitmentQueueAccount,
+    usdt_commitment_hashing_account: &CommitmentHashingAccount,
 
-/// Verifies a single user-supplied [`ChildAccount`] and then saves it's pubkey in the `parent_account`
-///
-/// # Note
-///
-/// If `size` is manually supplied (not the default [`C::SIZE`] is used) [`elusiv_types::ChildAccountConfig::SIZE`] needs to be contained in the size.
-pub fn setup_child_account<'a, 'b, 't, P: ParentAccount<'a, 'b, 't>>(
-    parent_account: &mut P,
-    child_account: &AccountInfo,
-    child_index: usize,
-    check_zeroness: bool,
-    size: Option<usize>,
+    fee_version: u32,
+    batching_rate: u32,
 ) -> ProgramResult {
-    if parent_account.get_child_pubkey(child_index).is_some() {
-        return Err(ElusivError::SubAccountAlreadyExists.into());
-    }
+    let staged = governor.get_staged_parameters();
+    guard!(
+        staged.is_activatable(current_slot()?),
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        staged.fee_version == fee_version && staged.commitment_batching_rate == batching_rate,
+        ElusivError::InvalidInstructionData
+    );
 
-    verify_extern_data_account(
-        child_account,
-        size.unwrap_or(<P::Child as SizedAccount>::SIZE),
-        check_zeroness,
-    )?;
-    parent_account.set_child_pubkey(child_index, ElusivOption::Some(*child_account.key));
-    P::Child::try_start_using_account(child_account)?;
+    guard!(
+        fee.get_program_fee().is_valid(),
+        ElusivError::InvalidAccountState
+    );
 
-    Ok(())
-}
+    let current_rate = governor.get_commitment_batching_rate();
+    verify_commitment_batching_rate_change(
+        current_rate,
+        batching_rate,
+        commitment_hash_queue,
+        commitment_hashing_account,
+    )?;
+    verify_commitment_batching_rate_change(
+        current_rate,
+        batching_rate,
+        usdc_commitment_hash_queue,
+        usdc_commitment_hashing_account,
+    )?;
+    verify_commitment_batching_rate_change(
+        current_rate,
+        batching_rate,
+        usdt_commitment_hash_queue,
+        usdt_commitment_hashing_account,
+    )?;
 
-fn reset_map_child_account<C: ChildAccount>(child_account: &AccountInfo) -> ProgramResult {
-    let data = &mut child_account.data.borrow_mut()[..];
-    let (_, inner_data) = split_child_account_data_mut(data)?;
-    let mut map = ElusivMap::<(), (), 1>::new(&mut inner_data[..ElusivMap::<(), (), 1>::SIZE]);
-    map.reset();
+    governor.set_fee_version(&fee_version);
+    governor.set_program_fee(&fee.get_program_fee());
+    for token_id in 0..STATS_TOKEN_COUNT {
+        governor.set_token_fee_schedules(token_id, &fee.get_token_fee_schedules(token_id));
+    }
+    governor.set_commitment_batching_rate(&batching_rate);
+    governor.set_staged_parameters(&StagedGovernorParameters::default());
 
     Ok(())
 }
 
-/// Verifies that an account with `data_len` > 10 KiB (non PDA) is formatted correctly
-fn verify_extern_data_account(
-    account: &AccountInfo,
-    data_len: usize,
-    check_zeroness: bool,
+/// Sets the commitment batching rate with immediate effect (for all commitments enqueued afterwards)
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - the rate can only be changed in between two batches of every pool (see [`verify_commitment_batching_rate_change`]) and not while a change is staged (see [`propose_governor_state`])
+#[allow(clippy::too_many_arguments)]
+pub fn set_commitment_batching_rate(
+    governor: &mut GovernorAccount,
+    config: &ConfigAccount,
+    commitment_hash_queue: &mut CommitmentQueueAccount,
+    commitment_hashing_account: &CommitmentHashingAccount,
+    usdc_commitment_hash_queue: &mut CommitmentQueueAccount,
+    usdc_commitment_hashing_account: &CommitmentHashingAccount,
+    usdt_commitment_hash_queue: &mut CommitmentQueueAccount,
+    usdt_commitment_hashing_account: &CommitmentHashingAccount,
+    signers: &[&AccountInfo],
+
+    batching_rate: u32,
 ) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
     guard!(
-        account.data_len() == data_len,
+        batching_rate <= config.effective_max_commitment_batching_rate(),
         ElusivError::InvalidInstructionData
     );
     guard!(
-        data_len >= ChildAccountConfig::SIZE,
-        ElusivError::InvalidInstructionData
+        !governor.get_staged_parameters().is_staged,
+        ElusivError::InvalidAccountState
     );
 
-    if check_zeroness {
+    let current_rate = governor.get_commitment_batching_rate();
+    verify_commitment_batching_rate_change(
+        current_rate,
+        batching_rate,
+        commitment_hash_queue,
+        commitment_hashing_account,
+    )?;
+    verify_commitment_batching_rate_change(
+        current_rate,
+        batching_rate,
+        usdc_commitment_hash_queue,
+        usdc_commitment_hashing_account,
+    )?;
+    verify_commitment_batching_rate_change(
+        current_rate,
+        batching_rate,
+        usdt_commitment_hash_queue,
+        usdt_commitment_hashing_account,
+    )?;
+
+    governor.set_commitment_batching_rate(&batching_rate);
+
+    Ok(())
+}
+
+/// Verifies that the commitment batching rate of a single pool can be changed from `current_rate` to `batching_rate`
+/// - the pool cannot be in between two batches (no active [`CommitmentHashingAccount`])
+/// - raising the rate requires a non-empty commitment queue to hold at least one full batch of the new rate, so that batches can still be filled
+fn verify_commitment_batching_rate_change(
+    current_rate: u32,
+    batching_rate: u32,
+    commitment_hash_queue: &mut CommitmentQueueAccount,
+    commitment_hashing_account: &CommitmentHashingAccount,
+) -> ProgramResult {
+    guard!(
+        !commitment_hashing_account.get_is_active(),
+        ElusivError::ComputationIsNotYetFinished
+    );
+
+    if batching_rate > current_rate {
+        let queue = CommitmentQueue::new(commitment_hash_queue);
         guard!(
-            is_zero(&account.data.borrow()[..]),
+            queue.is_empty() || queue.len() as usize >= commitments_per_batch(batching_rate),
             ElusivError::InvalidInstructionData
         );
     }
 
-    // Check rent-exemption
-    if cfg!(test) {
-        // only unit-testing (since we have no ledger there)
-        guard!(
-            account.lamports() >= u32::MAX as u64,
-            ElusivError::InvalidInstructionData
+    Ok(())
+}
+
+/// Verifies that the governance authority approves a privileged instruction
+/// - with a configured [`GovernanceMultisig`], at least `threshold` distinct members need to be among the `signers`
+/// - otherwise the program's keypair needs to be among the `signers`
+pub fn verify_governance_authority(
+    governor: &GovernorAccount,
+    signers: &[&AccountInfo],
+) -> ProgramResult {
+    let multisig = governor.get_governance_multisig();
+    let keys = signers.iter().map(|signer| signer.key);
+
+    if multisig.is_configured() {
+        guard!(
+            multisig.is_approved(keys),
+            ElusivError::MissingGovernanceApprovals
+        );
+    } else {
+        guard!(
+            keys.into_iter().any(|key| *key == crate::ID),
+            ElusivError::InvalidAccount
+        );
+    }
+
+    Ok(())
+}
+
+/// Replaces the [`GovernanceMultisig`] authorizing privileged governance instructions
+/// - `signers` need to satisfy the current authority (see [`verify_governance_authority`])
+pub fn set_governance_multisig(
+    governor: &mut GovernorAccount,
+    signers: &[&AccountInfo],
+
+    multisig: GovernanceMultisig,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    guard!(multisig.is_valid(), ElusivError::InvalidInstructionData);
+
+    governor.set_governance_multisig(&multisig);
+
+    Ok(())
+}
+
+/// Setup a new [`FeeAccount`]
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - either the current `fee_version` (genesis) or the next one (activated with [`upgrade_governor_state`]) can be initialized
+pub fn init_new_fee_version<'b>(
+    payer: &AccountInfo<'b>,
+    governor: &mut GovernorAccount,
+    mut new_fee_account: UnverifiedAccountInfo<'_, 'b>,
+    fee_collector: &mut FeeCollectorAccount,
+    signers: &[&AccountInfo],
+
+    fee_version: u32,
+    program_fee: ProgramFee,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    let current_fee_version = governor.get_fee_version();
+    guard!(
+        fee_version == current_fee_version
+            || Some(fee_version) == current_fee_version.checked_add(1),
+        ElusivError::InvalidFeeVersion
+    );
+    guard!(program_fee.is_valid(), ElusivError::InvalidInstructionData);
+
+    FeeAccount::open(
+        payer,
+        new_fee_account.get_unsafe_and_set_is_verified(),
+        None,
+        Some(fee_version),
+        None,
+    )?;
+
+    pda_account!(mut fee_account, FeeAccount, new_fee_account.get_safe()?);
+    fee_account.set_program_fee(&program_fee);
+    fee_account.set_recipient_rent_minimum(&system_program_account_rent()?.0);
+    fee_account.set_network_fee_split(&DEFAULT_NETWORK_FEE_SPLIT);
+    fee_collector.set_network_fee_split(fee_version, DEFAULT_NETWORK_FEE_SPLIT);
+    if fee_version == current_fee_version {
+        governor.set_program_fee(&program_fee);
+        for token_id in 0..STATS_TOKEN_COUNT {
+            governor.set_token_fee_schedules(token_id, &TokenFeeSchedule::default());
+        }
+    }
+
+    Ok(())
+}
+
+/// Sets the compute-unit price up to which wardens are compensated for priority fees of computations with `fee_version`
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - only a fee-version that is not active yet can be modified, since queued requests have been paid with the active fee-version
+pub fn set_fee_compute_unit_price(
+    governor: &GovernorAccount,
+    fee: &mut FeeAccount,
+    signers: &[&AccountInfo],
+
+    fee_version: u32,
+    lamports_per_cu: u64,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    guard!(
+        fee_version > governor.get_fee_version(),
+        ElusivError::InvalidFeeVersion
+    );
+    guard!(
+        lamports_per_cu <= MAX_LAMPORTS_PER_CU,
+        ElusivError::InvalidInstructionData
+    );
+
+    fee.set_lamports_per_cu(&lamports_per_cu);
+
+    Ok(())
+}
+
+/// Sets the surcharge of base-commitments with `fee_version` for the priority lane of the commitment queue
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - only a fee-version that is not active yet can be modified, since queued requests have been paid with the active fee-version
+pub fn set_fee_priority_lane_surcharge(
+    governor: &GovernorAccount,
+    fee: &mut FeeAccount,
+    signers: &[&AccountInfo],
+
+    fee_version: u32,
+    priority_lane_surcharge: u64,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    guard!(
+        fee_version > governor.get_fee_version(),
+        ElusivError::InvalidFeeVersion
+    );
+
+    fee.set_priority_lane_surcharge(&priority_lane_surcharge);
+
+    Ok(())
+}
+
+/// Sets the fee schedule of the (non-lamports) `token_id` for `fee_version`
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - only a fee-version that is not active yet can be modified, since queued requests have been paid with the active fee-version
+/// - the schedules are mirrored into the [`GovernorAccount`] once `fee_version` is activated (see [`upgrade_governor_state`])
+pub fn set_token_fee_schedule(
+    governor: &GovernorAccount,
+    fee: &mut FeeAccount,
+    signers: &[&AccountInfo],
+
+    fee_version: u32,
+    token_id: u16,
+    schedule: TokenFeeSchedule,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    guard!(
+        fee_version > governor.get_fee_version(),
+        ElusivError::InvalidFeeVersion
+    );
+    guard!(
+        token_id != 0 && (token_id as usize) < STATS_TOKEN_COUNT,
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        fee.get_program_fee()
+            .with_token_schedule(&schedule)
+            .is_valid(),
+        ElusivError::InvalidInstructionData
+    );
+
+    fee.set_token_fee_schedules(token_id as usize, &schedule);
+
+    Ok(())
+}
+
+/// Sets the anonymity-delay required for large deposits
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+pub fn set_anonymity_delay_policy(
+    governor: &mut GovernorAccount,
+    signers: &[&AccountInfo],
+
+    required_anonymity_delay_slots: u64,
+    anonymity_delay_thresholds: [u64; STATS_TOKEN_COUNT],
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    governor.set_required_anonymity_delay_slots(&required_anonymity_delay_slots);
+    for (token_id, threshold) in anonymity_delay_thresholds.iter().enumerate() {
+        governor.set_anonymity_delay_thresholds(token_id, threshold);
+    }
+
+    Ok(())
+}
+
+/// Sets the timelock required for large lamports withdrawals (a zero threshold disables the requirement)
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+pub fn set_withdrawal_delay_policy(
+    governor: &mut GovernorAccount,
+    signers: &[&AccountInfo],
+
+    required_withdrawal_delay_slots: u64,
+    withdrawal_delay_threshold: u64,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    governor.set_required_withdrawal_delay_slots(&required_withdrawal_delay_slots);
+    governor.set_withdrawal_delay_threshold(&withdrawal_delay_threshold);
+
+    Ok(())
+}
+
+/// Enables (or disables with zero) the sealing of the nullifier-sets of closed MTs
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+pub fn set_seal_policy(
+    governor: &mut GovernorAccount,
+    signers: &[&AccountInfo],
+
+    seal_sunset_slots: u64,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    guard!(
+        seal_sunset_slots == 0 || seal_sunset_slots >= MIN_SEAL_SUNSET_SLOTS,
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_seal_sunset_slots(&seal_sunset_slots);
+
+    Ok(())
+}
+
+/// Adds an SPL token to (or removes it from) the tokens accepted for deposits
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - withdrawals of a removed token remain possible
+pub fn set_supported_token(
+    governor: &mut GovernorAccount,
+    signers: &[&AccountInfo],
+
+    token_id: u16,
+    is_supported: bool,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    guard!(
+        token_id != 0 && (token_id as usize) < STATS_TOKEN_COUNT,
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_supported_tokens(token_id as usize, &is_supported);
+
+    Ok(())
+}
+
+/// Sets the maximum deposit volume of `token_id` per epoch (zero disables the cap)
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+pub fn set_epoch_deposit_cap(
+    governor: &mut GovernorAccount,
+    signers: &[&AccountInfo],
+
+    token_id: u16,
+    epoch_deposit_cap: u64,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    guard!(
+        (token_id as usize) < STATS_TOKEN_COUNT,
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_epoch_deposit_caps(token_id as usize, &epoch_deposit_cap);
+
+    Ok(())
+}
+
+/// Sets the compute-unit safety margin of public-input preparation instructions
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - a larger margin trades additional instructions (and fees) for reliability against CU variance
+pub fn set_compute_unit_margin(
+    governor: &mut GovernorAccount,
+    signers: &[&AccountInfo],
+
+    compute_unit_margin: u16,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    guard!(
+        compute_unit_margin <= MAX_COMPUTE_UNIT_MARGIN,
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_compute_unit_margin(&compute_unit_margin);
+
+    Ok(())
+}
+
+/// Registers the Pyth USD price account of a token (zero restores the built-in price account)
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+pub fn set_price_account(
+    governor: &mut GovernorAccount,
+    signers: &[&AccountInfo],
+
+    token_id: u16,
+    price_account: U256,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    guard!(
+        (token_id as usize) < STATS_TOKEN_COUNT,
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_price_accounts(token_id as usize, &price_account);
+
+    Ok(())
+}
+
+/// Sets the staleness and confidence limits of Pyth prices (zero disables a limit)
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+pub fn set_price_oracle_policy(
+    governor: &mut GovernorAccount,
+    signers: &[&AccountInfo],
+
+    max_price_age: u64,
+    max_price_confidence_bps: u16,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    guard!(
+        max_price_confidence_bps <= 10_000,
+        ElusivError::InvalidInstructionData
+    );
+
+    governor.set_max_price_age(&max_price_age);
+    governor.set_max_price_confidence_bps(&max_price_confidence_bps);
+
+    Ok(())
+}
+
+/// Sets the runtime limits of the [`ConfigAccount`] (zero uses the built-in constant)
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - the limits are bounded by the built-in constants, a full batch of the maximum batching rate needs to fit into the commitment queue
+/// - the maximum batching rate cannot be lowered below the current or a staged batching rate
+pub fn set_program_config(
+    governor: &GovernorAccount,
+    config: &mut ConfigAccount,
+    signers: &[&AccountInfo],
+
+    commitment_queue_capacity: u32,
+    max_commitment_batching_rate: u32,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    guard!(
+        commitment_queue_capacity <= CommitmentQueue::CAPACITY
+            && max_commitment_batching_rate as usize <= MAX_COMMITMENT_BATCHING_RATE,
+        ElusivError::InvalidInstructionData
+    );
+
+    config.set_commitment_queue_capacity(&commitment_queue_capacity);
+    config.set_max_commitment_batching_rate(&max_commitment_batching_rate);
+
+    let max_rate = config.effective_max_commitment_batching_rate();
+    guard!(
+        commitments_per_batch(max_rate) <= config.effective_commitment_queue_capacity() as usize,
+        ElusivError::InvalidInstructionData
+    );
+
+    let staged = governor.get_staged_parameters();
+    guard!(
+        governor.get_commitment_batching_rate() <= max_rate
+            && (!staged.is_staged || staged.commitment_batching_rate <= max_rate),
+        ElusivError::InvalidAccountState
+    );
+
+    Ok(())
+}
+
+/// Opens an additional shard of the [`RecipientDenylistAccount`] (the first shard is opened with the single instance accounts)
+pub fn open_recipient_denylist_shard<'b>(
+    payer: &AccountInfo<'b>,
+    recipient_denylist_account: UnverifiedAccountInfo<'_, 'b>,
+
+    shard: u32,
+) -> ProgramResult {
+    guard!(
+        shard > 0 && shard < RECIPIENT_DENYLIST_SHARD_COUNT,
+        ElusivError::InvalidInstructionData
+    );
+
+    RecipientDenylistAccount::open(
+        payer,
+        recipient_denylist_account.get_unsafe(),
+        None,
+        recipient_denylist_offset(shard),
+        None,
+    )
+}
+
+/// Enables or disables the consultation of a [`RecipientDenylistAccount`] shard during the finalization of sends
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+pub fn set_recipient_denylist_enabled(
+    governor: &GovernorAccount,
+    recipient_denylist: &mut RecipientDenylistAccount,
+    signers: &[&AccountInfo],
+
+    _shard: u32,
+    enabled: bool,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    recipient_denylist.set_enabled(&enabled);
+
+    Ok(())
+}
+
+/// Adds `recipient` to or removes it from its [`RecipientDenylistAccount`] shard
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+pub fn set_denied_recipient(
+    governor: &GovernorAccount,
+    recipient_denylist: &mut RecipientDenylistAccount,
+    signers: &[&AccountInfo],
+
+    recipient: Pubkey,
+    is_denied: bool,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    if is_denied {
+        recipient_denylist.add(&recipient)
+    } else {
+        recipient_denylist.remove(&recipient);
+        Ok(())
+    }
+}
+
+/// Adds the first `recipient_count` `recipients` to the [`RecipientDenylistAccount`] `shard`
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+pub fn add_denied_recipients(
+    governor: &GovernorAccount,
+    recipient_denylist: &mut RecipientDenylistAccount,
+    signers: &[&AccountInfo],
+
+    shard: u32,
+    recipient_count: u32,
+    recipients: [Pubkey; MAX_DENIED_RECIPIENTS_BATCH],
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    let count = recipient_count as usize;
+    guard!(
+        count <= MAX_DENIED_RECIPIENTS_BATCH,
+        ElusivError::InvalidInstructionData
+    );
+    let recipients = &recipients[..count];
+    guard!(
+        recipients
+            .iter()
+            .all(|recipient| recipient_denylist_shard(recipient) == shard),
+        ElusivError::InvalidInstructionData
+    );
+
+    recipient_denylist.add_batch(recipients)
+}
+
+/// Opens the [`PoseidonParamsAccount`] (the parameters are set and frozen by the governance)
+pub fn open_poseidon_params_account<'b>(
+    payer: &AccountInfo<'b>,
+    poseidon_params_account: UnverifiedAccountInfo<'_, 'b>,
+) -> ProgramResult {
+    PoseidonParamsAccount::open(
+        payer,
+        poseidon_params_account.get_unsafe(),
+        None,
+        None,
+        None,
+    )
+}
+
+/// Sets the first `count` `params` of the [`PoseidonParamsAccount`] starting at `offset`
+/// - the parameters cannot be changed while frozen (see [`freeze_poseidon_params`] and [`unfreeze_poseidon_params`])
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+pub fn set_poseidon_params(
+    governor: &GovernorAccount,
+    poseidon_params: &mut PoseidonParamsAccount,
+    signers: &[&AccountInfo],
+
+    offset: u32,
+    count: u32,
+    params: [U256; POSEIDON_PARAMS_CHUNK_SIZE],
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    let count = count as usize;
+    guard!(
+        count <= POSEIDON_PARAMS_CHUNK_SIZE,
+        ElusivError::InvalidInstructionData
+    );
+
+    poseidon_params.set_params_chunk(offset as usize, &params[..count])
+}
+
+/// Freezes the [`PoseidonParamsAccount`] as `version`, if its parameters match the pinned checksum of the `version`
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+pub fn freeze_poseidon_params(
+    governor: &GovernorAccount,
+    poseidon_params: &mut PoseidonParamsAccount,
+    signers: &[&AccountInfo],
+
+    version: u32,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    poseidon_params.freeze(version)
+}
+
+/// Unfreezes the [`PoseidonParamsAccount`] for an upgrade to the next pinned version (see [`crate::state::poseidon::poseidon_params_checksum`])
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - commitment hashing is rejected until the upgraded parameters are frozen
+pub fn unfreeze_poseidon_params(
+    governor: &GovernorAccount,
+    poseidon_params: &mut PoseidonParamsAccount,
+    signers: &[&AccountInfo],
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    poseidon_params.unfreeze()
+}
+
+/// Irreversibly activates the emergency mode, in which users can only exit the pool
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - users exit with sends they relay themselves, whose proof is the MT-inclusion and nullifier check of the spent commitments
+/// - a proof-less exit revealing the commitment's opening is not possible, since the nullifier-hash of a commitment is only derived inside the circuits
+pub fn activate_emergency_mode(
+    governor: &mut GovernorAccount,
+    signers: &[&AccountInfo],
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    guard!(
+        !governor.get_emergency_mode(),
+        ElusivError::EmergencyModeIsActive
+    );
+
+    governor.set_emergency_mode(&true);
+
+    Ok(())
+}
+
+/// Pauses or unpauses all deposits, proof verifications and their finalizations
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+pub fn set_halted(
+    governor: &mut GovernorAccount,
+    signers: &[&AccountInfo],
+
+    is_halted: bool,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    governor.set_is_halted(&is_halted);
+
+    Ok(())
+}
+
+/// Closes a program owned account in devnet and localhost
+///
+/// # Note
+///
+/// - `signer` needs to be the program's keypair
+/// - `recipient` receives the accounts Lamports
+#[cfg(not(feature = "mainnet"))]
+pub fn close_program_account<'a>(
+    signer: &AccountInfo,
+    recipient: &AccountInfo<'a>,
+    program_account: &AccountInfo<'a>,
+) -> ProgramResult {
+    assert!(!cfg!(feature = "mainnet"));
+    assert_eq!(*signer.key, crate::ID);
+
+    elusiv_utils::close_account(recipient, program_account)
+}
+
+/// Verifies a single user-supplied [`ChildAccount`] and then saves it's pubkey in the `parent_account`
+///
+/// # Note
+///
+/// If `size` is manually supplied (not the default [`ParentAccount::child_account_size`] is used) [`elusiv_types::ChildAccountConfig::SIZE`] needs to be contained in the size.
+pub fn setup_child_account<'a, 'b, 't, P: ParentAccount<'a, 'b, 't>>(
+    parent_account: &mut P,
+    child_account: &AccountInfo,
+    child_index: usize,
+    check_zeroness: bool,
+    size: Option<usize>,
+) -> ProgramResult {
+    guard!(
+        child_index < parent_account.child_count(),
+        ElusivError::InvalidInstructionData
+    );
+
+    if parent_account.get_child_pubkey(child_index).is_some() {
+        return Err(ElusivError::SubAccountAlreadyExists.into());
+    }
+
+    verify_extern_data_account(
+        child_account,
+        size.unwrap_or_else(|| P::child_account_size(child_index)),
+        check_zeroness,
+        &DefaultRentSysvar,
+    )?;
+    parent_account.set_child_pubkey(child_index, ElusivOption::Some(*child_account.key));
+    P::Child::try_start_using_account(child_account)?;
+
+    Ok(())
+}
+
+fn reset_map_child_account<C: ChildAccount>(child_account: &AccountInfo) -> ProgramResult {
+    let data = &mut child_account.data.borrow_mut()[..];
+    let (_, inner_data) = split_child_account_data_mut(data)?;
+    let mut map = ElusivMap::<(), (), 1>::new(&mut inner_data[..ElusivMap::<(), (), 1>::SIZE]);
+    map.reset();
+
+    Ok(())
+}
+
+/// Verifies that an account with `data_len` > 10 KiB (non PDA) is formatted correctly
+fn verify_extern_data_account(
+    account: &AccountInfo,
+    data_len: usize,
+    check_zeroness: bool,
+    rent: &impl RentSysvar,
+) -> ProgramResult {
+    guard!(
+        account.data_len() == data_len,
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        data_len >= ChildAccountConfig::SIZE,
+        ElusivError::InvalidInstructionData
+    );
+
+    if check_zeroness {
+        guard!(
+            is_zero(&account.data.borrow()[..]),
+            ElusivError::InvalidInstructionData
+        );
+    }
+
+    // Check rent-exemption
+    guard!(
+        account.lamports() >= rent.minimum_balance(data_len)?,
+        ElusivError::InvalidInstructionData
+    );
+
+    // Check ownership
+    guard!(
+        *account.owner == crate::id(),
+        ElusivError::InvalidInstructionData
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syscall_stubs::set_test_slot;
+    use crate::{
+        macros::account_info,
+        processor::CommitmentHashRequest,
+        state::{
+            governor::MAX_GOVERNANCE_SIGNERS,
+            poseidon::{
+                default_poseidon_params, default_poseidon_params_checksum,
+                DEFAULT_POSEIDON_PARAMS_VERSION,
+            },
+            program_account::SizedAccount,
+            storage::StorageChildAccount,
+        },
+        token::{usdc_token, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID},
+    };
+    use assert_matches::assert_matches;
+    use elusiv_types::{PDAAccount, PDAAccountData, ProgramAccount};
+    use solana_program::{entrypoint::MAX_PERMITTED_DATA_INCREASE, pubkey::Pubkey};
+
+    #[test]
+    fn test_open_token_pool_accounts() {
+        zero_program_account!(mut governor, GovernorAccount);
+        test_account_info!(payer, 0);
+        test_account_info!(any, 0);
+
+        macro_rules! open_token_pool_accounts {
+            ($token_id: expr) => {
+                open_token_pool_accounts(
+                    &payer,
+                    &governor,
+                    UnverifiedAccountInfo::new(&any),
+                    UnverifiedAccountInfo::new(&any),
+                    UnverifiedAccountInfo::new(&any),
+                    UnverifiedAccountInfo::new(&any),
+                    UnverifiedAccountInfo::new(&any),
+                    $token_id,
+                )
+            };
+        }
+
+        // The lamports pool consists of single instance accounts
+        assert_matches!(open_token_pool_accounts!(LAMPORTS_TOKEN_ID), Err(_));
+
+        // Unsupported token
+        assert_matches!(open_token_pool_accounts!(USDC_TOKEN_ID), Err(_));
+
+        // Invalid PDAs
+        governor.set_supported_tokens(USDC_TOKEN_ID as usize, &true);
+        assert_matches!(open_token_pool_accounts!(USDC_TOKEN_ID), Err(_));
+    }
+
+    #[test]
+    fn test_create_child_account() {
+        test_account_info!(payer, 0);
+        let parent = ChildAccountParent::Storage { token_id: 0 };
+
+        // Invalid size
+        test_account_info!(sub_account, StorageChildAccount::SIZE - 1);
+        account_info!(
+            record,
+            ChildAccountRecordAccount::find_with_pubkey(*sub_account.key, None).0,
+            vec![0; ChildAccountRecordAccount::SIZE]
+        );
+        assert_matches!(
+            create_child_account(&payer, &sub_account, &record, parent, 0),
+            Err(_)
+        );
+
+        // Invalid child index
+        test_account_info!(sub_account, StorageChildAccount::SIZE);
+        account_info!(
+            record,
+            ChildAccountRecordAccount::find_with_pubkey(*sub_account.key, None).0,
+            vec![0; ChildAccountRecordAccount::SIZE]
+        );
+        assert_matches!(
+            create_child_account(
+                &payer,
+                &sub_account,
+                &record,
+                parent,
+                StorageAccount::COUNT as u32
+            ),
+            Err(_)
+        );
+
+        // Non-zero data
+        sub_account.data.borrow_mut()[0] = 1;
+        assert_matches!(
+            create_child_account(&payer, &sub_account, &record, parent, 0),
+            Err(_)
+        );
+        sub_account.data.borrow_mut()[0] = 0;
+
+        // Too large to be created by the program
+        let lamports = sub_account.lamports();
+        **sub_account.lamports.borrow_mut() = 0;
+        assert_matches!(
+            create_child_account(&payer, &sub_account, &record, parent, 0),
+            Err(_)
+        );
+
+        // Not rent-exempt
+        let rent = DefaultRentSysvar
+            .minimum_balance(StorageChildAccount::SIZE)
+            .unwrap();
+        **sub_account.lamports.borrow_mut() = rent - 1;
+        assert_matches!(
+            create_child_account(&payer, &sub_account, &record, parent, 0),
+            Err(_)
+        );
+        **sub_account.lamports.borrow_mut() = lamports;
+
+        assert_matches!(
+            create_child_account(&payer, &sub_account, &record, parent, 1),
+            Ok(())
+        );
+
+        let data = &mut record.data.borrow_mut()[..];
+        let record = ChildAccountRecordAccount::new(data).unwrap();
+        assert_eq!(record.get_payer(), *payer.key);
+        assert!(record.is_child_of(&StorageAccount::find(None).0, 1));
+    }
+
+    #[test]
+    fn test_enable_storage_child_account() {
+        let mut data = vec![0; StorageAccount::SIZE];
+        let mut storage_account = StorageAccount::new(&mut data).unwrap();
+        storage_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+
+        // Account has invalid size
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; StorageChildAccount::SIZE - 1]
+        );
+        assert_matches!(
+            enable_storage_child_account(&mut storage_account, &child_account, 0, 0),
+            Err(_)
+        );
+
+        // Account has already been setup
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; StorageChildAccount::SIZE]
+        );
+        assert_matches!(
+            enable_storage_child_account(&mut storage_account, &child_account, 0, 0),
+            Err(_)
+        );
+
+        // Invalid child index
+        assert_matches!(
+            enable_storage_child_account(
+                &mut storage_account,
+                &child_account,
+                StorageAccount::COUNT as u32,
+                0
+            ),
+            Err(_)
+        );
+
+        // Success at different index
+        assert_matches!(
+            enable_storage_child_account(&mut storage_account, &child_account, 3, 0),
+            Ok(())
+        );
+        assert_eq!(child_account.data.borrow()[0], 1);
+
+        // Account already is use
+        assert_matches!(
+            enable_storage_child_account(&mut storage_account, &child_account, 1, 0),
+            Err(_)
+        );
+
+        // The last child-account has a different size
+        let last_index = StorageAccount::COUNT - 1;
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; StorageChildAccount::SIZE]
+        );
+        assert_matches!(
+            enable_storage_child_account(
+                &mut storage_account,
+                &child_account,
+                last_index as u32,
+                0
+            ),
+            Err(_)
+        );
+
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; StorageAccount::child_account_size(last_index)]
+        );
+        assert_matches!(
+            enable_storage_child_account(
+                &mut storage_account,
+                &child_account,
+                last_index as u32,
+                0
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_enable_nullifier_child_account() {
+        let mut data = vec![0; NullifierAccount::SIZE];
+        let mut nullifier_account = NullifierAccount::new(&mut data).unwrap();
+        nullifier_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+
+        let payer_pk = Pubkey::new_unique();
+        let creator_pk = Pubkey::new_unique();
+        account_info!(payer, payer_pk, vec![]);
+        account_info!(
+            rent_contribution,
+            TreeRentContributionAccount::find_with_pubkey(creator_pk, Some(0)).0,
+            vec![0; TreeRentContributionAccount::SIZE]
+        );
+        zero_program_account!(mut record, ChildAccountRecordAccount);
+        record.set_payer(&creator_pk);
+        record.set_parent(
+            &ChildAccountParent::Nullifier {
+                token_id: 0,
+                mt_index: 0,
+            }
+            .pubkey()
+            .unwrap(),
+        );
+        record.set_child_index(&3);
+
+        macro_rules! enable_nullifier_child_account {
+            ($child_account: expr, $child_index: expr, $creator: expr) => {
+                enable_nullifier_child_account(
+                    &payer,
+                    &mut nullifier_account,
+                    &$child_account,
+                    &record,
+                    &rent_contribution,
+                    0,
+                    $child_index,
+                    0,
+                    $creator,
+                )
+            };
+        }
+
+        // Account has invalid size
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; NullifierChildAccount::SIZE - 1]
+        );
+        assert_matches!(
+            enable_nullifier_child_account!(child_account, 3, creator_pk),
+            Err(_)
+        );
+
+        // Account has already been setup
+        account_info!(
+            child_account,
+            Pubkey::new_unique(),
+            vec![0; NullifierChildAccount::SIZE]
+        );
+        record.set_child_index(&0);
+        assert_matches!(
+            enable_nullifier_child_account!(child_account, 0, creator_pk),
+            Err(_)
+        );
+        record.set_child_index(&3);
+
+        // The child-account has been created for a different index
+        assert_matches!(
+            enable_nullifier_child_account!(child_account, 2, creator_pk),
+            Err(_)
+        );
+
+        // The rent cannot be credited to the (front-running) payer
+        assert_matches!(
+            enable_nullifier_child_account!(child_account, 3, payer_pk),
+            Err(_)
+        );
+
+        // Success at different index with
+        assert_matches!(
+            enable_nullifier_child_account!(child_account, 3, creator_pk),
+            Ok(())
+        );
+        assert_eq!(child_account.data.borrow()[0], 1);
+
+        // The rent of the child-account is recorded as contribution of the creator
+        {
+            let data = &mut rent_contribution.data.borrow_mut()[..];
+            let contribution = TreeRentContributionAccount::new(data).unwrap();
+            assert_eq!(contribution.get_amount(), child_account.lamports());
+        }
+
+        // Account already is use
+        record.set_child_index(&1);
+        assert_matches!(
+            enable_nullifier_child_account!(child_account, 1, creator_pk),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_claim_tree_rent_refund() {
+        let contributor_pk = Pubkey::new_unique();
+        account_info!(contributor, contributor_pk, vec![]);
+        account_info!(
+            rent_contribution,
+            TreeRentContributionAccount::find_with_pubkey(contributor_pk, Some(0)).0,
+            vec![0; TreeRentContributionAccount::SIZE]
+        );
+        account_info!(fee_collector, FeeCollectorAccount::find(None).0, vec![]);
+        let mut data = vec![0; NullifierAccount::SIZE];
+        let mut nullifier_account = NullifierAccount::new(&mut data).unwrap();
+        nullifier_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+        nullifier_account.set_reclaimed_rent(&500);
+
+        {
+            let data = &mut rent_contribution.data.borrow_mut()[..];
+            let mut contribution = TreeRentContributionAccount::new(data).unwrap();
+            contribution.set_contributor(&contributor_pk);
+            contribution.set_amount(&1_000);
+        }
+
+        macro_rules! claim_tree_rent_refund {
+            ($contributor: expr) => {
+                claim_tree_rent_refund(
+                    &$contributor,
+                    &rent_contribution,
+                    &mut nullifier_account,
+                    &fee_collector,
+                    0,
+                    0,
+                )
+            };
+        }
+
+        // A child-account has not been closed yet
+        assert_matches!(claim_tree_rent_refund!(contributor), Err(_));
+
+        // Invalid contributor
+        nullifier_account.set_child_pubkey(0, ElusivOption::None);
+        account_info!(invalid_contributor, Pubkey::new_unique(), vec![]);
+        assert_matches!(claim_tree_rent_refund!(invalid_contributor), Err(_));
+
+        // The refund is limited to the reclaimed rent
+        let lamports = u32::MAX as u64;
+        let contribution_lamports = rent_contribution.lamports();
+        assert_matches!(claim_tree_rent_refund!(contributor), Ok(()));
+        assert_eq!(nullifier_account.get_refunded_rent(), 500);
+        assert_eq!(nullifier_account.refundable_rent(), 0);
+        assert_eq!(fee_collector.lamports(), lamports - 500);
+        assert_eq!(
+            contributor.lamports(),
+            lamports + 500 + contribution_lamports
+        );
+    }
+
+    #[test]
+    fn test_is_mt_full() {
+        let mut data = vec![0; StorageAccount::SIZE];
+        let mut storage_account = StorageAccount::new(&mut data).unwrap();
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
+
+        let mut q_data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut q_data).unwrap();
+        let mut queue = CommitmentQueue::new(&mut queue);
+        queue
+            .enqueue(CommitmentHashRequest {
+                min_batching_rate: 1,
+                enqueue_slot: 0,
+                commitment: [0; 32],
+                fee_version: 0,
+            })
+            .unwrap();
+        queue
+            .enqueue(CommitmentHashRequest {
+                min_batching_rate: 1,
+                enqueue_slot: 0,
+                commitment: [0; 32],
+                fee_version: 0,
+            })
+            .unwrap();
+
+        assert!(is_mt_full(&storage_account, &queue).unwrap());
+
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 3));
+        assert!(!is_mt_full(&storage_account, &queue).unwrap());
+
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 2));
+        assert!(is_mt_full(&storage_account, &queue).unwrap());
+    }
+
+    #[test]
+    fn test_rotate_merkle_tree() {
+        set_test_slot(0);
+        test_account_info!(payer, 0);
+        parent_account!(mut storage_account, StorageAccount);
+        zero_program_account!(mut queue, CommitmentQueueAccount);
+        zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut active_nullifier_account, NullifierAccount);
+        zero_program_account!(mut state_summary, StateSummaryAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+        account_info!(
+            next_nullifier_account,
+            NullifierAccount::find_with_offsets(None, &nullifier_account_offsets(0, 1)).0,
+            vec![0; NullifierAccount::SIZE]
+        );
+        account_info!(
+            rent_contribution,
+            TreeRentContributionAccount::find_with_pubkey(*payer.key, Some(1)).0,
+            vec![0; TreeRentContributionAccount::SIZE]
+        );
+
+        macro_rules! rotate_merkle_tree {
+            ($active_mt_index: expr) => {
+                rotate_merkle_tree(
+                    &payer,
+                    &mut storage_account,
+                    &mut queue,
+                    &hashing_account,
+                    &mut active_nullifier_account,
+                    UnverifiedAccountInfo::new(&next_nullifier_account),
+                    &rent_contribution,
+                    &mut state_summary,
+                    &mut event_sequence,
+                    $active_mt_index,
+                    0,
+                )
+            };
+        }
+
+        // MT is not full
+        assert_matches!(rotate_merkle_tree!(0), Err(_));
+
+        // Commitment hashing is not idle
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
+        hashing_account.set_setup(&true);
+        assert_matches!(rotate_merkle_tree!(0), Err(_));
+        hashing_account.set_setup(&false);
+
+        // Invalid active MT index
+        assert_matches!(rotate_merkle_tree!(1), Err(_));
+
+        **next_nullifier_account.lamports.borrow_mut() = 0;
+        assert_matches!(rotate_merkle_tree!(0), Ok(()));
+
+        assert_eq!(storage_account.get_trees_count(), 1);
+        assert_eq!(storage_account.get_next_commitment_ptr(), 0);
+        assert_eq!(event_sequence.get_sequence(), 1);
+
+        // The next NullifierAccount has already been opened with an invalid PDA
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
+        **next_nullifier_account.lamports.borrow_mut() = 1;
+        assert_matches!(rotate_merkle_tree!(1), Err(_));
+    }
+
+    #[test]
+    fn test_realloc_account() {
+        signing_test_account_info!(payer);
+        let governor_pk = GovernorAccount::find(None).0;
+        let queue_pk = CommitmentQueueAccount::find(token_pool_offset(USDC_TOKEN_ID)).0;
+
+        assert_eq!(
+            GovernorAccount::next_data_len(0).unwrap(),
+            GovernorAccount::SIZE
+        );
+        assert_eq!(
+            CommitmentQueueAccount::next_data_len(0).unwrap(),
+            std::cmp::min(CommitmentQueueAccount::SIZE, MAX_PERMITTED_DATA_INCREASE)
+        );
+
+        // Already reached its size
+        account_info!(account, governor_pk, vec![0; GovernorAccount::SIZE]);
+        assert_matches!(
+            realloc_account(&payer, &account, ReallocatableAccountKind::Governor),
+            Err(_)
+        );
+
+        account_info!(account, governor_pk, vec![0; GovernorAccount::SIZE - 1]);
+
+        // Missing payer signature
+        test_account_info!(non_signing_payer, 0);
+        assert_matches!(
+            realloc_account(
+                &non_signing_payer,
+                &account,
+                ReallocatableAccountKind::Governor
+            ),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        assert_matches!(
+            realloc_account(&payer, &account, ReallocatableAccountKind::Governor),
+            Ok(())
+        );
+
+        // Invalid PDA
+        assert_matches!(
+            realloc_account(
+                &payer,
+                &account,
+                ReallocatableAccountKind::CommitmentQueue {
+                    token_id: USDC_TOKEN_ID
+                }
+            ),
+            Err(_)
+        );
+
+        // Invalid owner
+        account_info!(
+            account,
+            governor_pk,
+            vec![0; GovernorAccount::SIZE - 1],
+            Pubkey::new_unique(),
+            false
+        );
+        assert_matches!(
+            realloc_account(&payer, &account, ReallocatableAccountKind::Governor),
+            Err(_)
+        );
+
+        // Unsupported token
+        account_info!(account, queue_pk, vec![]);
+        assert_matches!(
+            realloc_account(
+                &payer,
+                &account,
+                ReallocatableAccountKind::CommitmentQueue { token_id: u16::MAX }
+            ),
+            Err(_)
+        );
+
+        assert_matches!(
+            realloc_account(
+                &payer,
+                &account,
+                ReallocatableAccountKind::CommitmentQueue {
+                    token_id: USDC_TOKEN_ID
+                }
+            ),
+            Ok(())
+        );
+
+        // A `CommitmentQueueAccount` created before the addition of the priority lane
+        account_info!(account, queue_pk, vec![0; CommitmentQueueAccount::SIZE - 4]);
+
+        macro_rules! realloc_queue {
+            ($head: expr, $tail: expr) => {{
+                {
+                    let data = &mut account.data.borrow_mut()[PDAAccountData::SIZE..];
+                    data[..4].copy_from_slice(&u32::to_le_bytes($head));
+                    data[4..8].copy_from_slice(&u32::to_le_bytes($tail));
+                }
+                realloc_account(
+                    &payer,
+                    &account,
+                    ReallocatableAccountKind::CommitmentQueue {
+                        token_id: USDC_TOKEN_ID,
+                    },
+                )
+            }};
+        }
+
+        // Wrapped around queue
+        assert_matches!(realloc_queue!(5, 2), Err(_));
+
+        // Empty queue
+        assert_matches!(realloc_queue!(5, 5), Ok(()));
+
+        assert_matches!(realloc_queue!(2, 5), Ok(()));
+
+        // The zero-initialized data migrates the queue with an empty priority lane
+        let mut data = account.data.borrow().to_vec();
+        data.extend(vec![0; 4]);
+        let mut queue_account = CommitmentQueueAccount::new(&mut data).unwrap();
+        assert_eq!(queue_account.get_priority_count(), 0);
+        let queue = CommitmentQueue::new(&mut queue_account);
+        assert_eq!(queue.get_head(), 2);
+        assert_eq!(queue.get_tail(), 5);
+
+        // A `NullifierAccount` created before the addition of its latest fields
+        let nullifier_pk =
+            NullifierAccount::find_with_offsets(None, &nullifier_account_offsets(0, 1)).0;
+        account_info!(account, nullifier_pk, vec![0; NullifierAccount::SIZE - 32]);
+        assert_eq!(
+            NullifierAccount::next_data_len(account.data_len()).unwrap(),
+            NullifierAccount::SIZE
+        );
+        assert_matches!(
+            realloc_account(
+                &payer,
+                &account,
+                ReallocatableAccountKind::Nullifier {
+                    token_id: 0,
+                    mt_index: 0
+                }
+            ),
+            Err(_)
+        );
+        assert_matches!(
+            realloc_account(
+                &payer,
+                &account,
+                ReallocatableAccountKind::Nullifier {
+                    token_id: 0,
+                    mt_index: 1
+                }
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_archive_closed_merkle_tree() {
+        test_account_info!(payer, 0);
+        let mut data = vec![0; StorageAccount::SIZE];
+        let mut storage_account = StorageAccount::new(&mut data).unwrap();
+        parent_account!(mut nullifier_account, NullifierAccount);
+        nullifier_account.set_root(&[1; 32]);
+        nullifier_account
+            .try_insert_nullifier_hash([2; 32])
+            .unwrap();
+
+        let pk = ArchivedTreeAccount::find_with_offsets(None, &nullifier_account_offsets(0, 0)).0;
+        account_info!(
+            archived_tree_account,
+            pk,
+            vec![0; ArchivedTreeAccount::SIZE]
+        );
+
+        // Active MT cannot be archived
+        assert_matches!(
+            archive_closed_merkle_tree(
+                &payer,
+                &storage_account,
+                &nullifier_account,
+                &archived_tree_account,
+                0,
+                0,
+            ),
+            Err(_)
+        );
+
+        storage_account.set_trees_count(&1);
+
+        // Invalid ArchivedTreeAccount PDA
+        test_account_info!(invalid_archived_tree_account, 0);
+        assert_matches!(
+            archive_closed_merkle_tree(
+                &payer,
+                &storage_account,
+                &nullifier_account,
+                &invalid_archived_tree_account,
+                0,
+                0,
+            ),
+            Err(_)
+        );
+
+        archive_closed_merkle_tree(
+            &payer,
+            &storage_account,
+            &nullifier_account,
+            &archived_tree_account,
+            0,
+            0,
+        )
+        .unwrap();
+
+        let data = &mut archived_tree_account.data.borrow_mut()[..];
+        let archived_tree = ArchivedTreeAccount::new(data).unwrap();
+        assert_eq!(archived_tree.get_mt_index(), 0);
+        assert_eq!(archived_tree.get_commitment_root(), [1; 32]);
+        assert_eq!(
+            archived_tree.get_nullifier_digest(),
+            nullifier_account.get_nullifier_digest()
+        );
+        assert_eq!(archived_tree.get_nullifier_hash_count(), 1);
+
+        // Archiving does not compact the MT
+        assert!(!storage_account.is_archived(0));
+    }
+
+    #[test]
+    fn test_propose_governor_state() {
+        set_test_slot(0);
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(mut config, ConfigAccount);
+        account_info!(invalid_authority, Pubkey::new_unique(), vec![]);
+        account_info!(authority, crate::ID, vec![]);
+        let slot = PARAMETER_CHANGE_TIMELOCK_SLOTS;
+
+        // Invalid authority
+        assert_matches!(
+            propose_governor_state(&mut governor, &config, &[&invalid_authority], 1, 2, slot),
+            Err(_)
+        );
+
+        // Invalid fee-version
+        assert_matches!(
+            propose_governor_state(&mut governor, &config, &[&authority], 2, 2, slot),
+            Err(_)
+        );
+
+        // Invalid batching rate
+        assert_matches!(
+            propose_governor_state(
+                &mut governor,
+                &config,
+                &[&authority],
+                1,
+                MAX_COMMITMENT_BATCHING_RATE as u32 + 1,
+                slot
+            ),
+            Err(_)
+        );
+
+        // Above the configured maximum
+        config.set_max_commitment_batching_rate(&1);
+        assert_matches!(
+            propose_governor_state(&mut governor, &config, &[&authority], 1, 2, slot),
+            Err(_)
+        );
+        config.set_max_commitment_batching_rate(&0);
+
+        // Timelock too short
+        assert_matches!(
+            propose_governor_state(&mut governor, &config, &[&authority], 1, 2, slot - 1),
+            Err(_)
+        );
+        assert!(!governor.get_staged_parameters().is_staged);
+
+        propose_governor_state(&mut governor, &config, &[&authority], 1, 2, slot).unwrap();
+        assert_eq!(
+            governor.get_staged_parameters(),
+            StagedGovernorParameters {
+                is_staged: true,
+                fee_version: 1,
+                commitment_batching_rate: 2,
+                activation_slot: slot,
+            }
+        );
+
+        // The parameters are not changed before the activation
+        assert_eq!(governor.get_fee_version(), 0);
+        assert_eq!(governor.get_commitment_batching_rate(), 0);
+    }
+
+    #[test]
+    fn test_upgrade_governor_state() {
+        set_test_slot(0);
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(mut fee, FeeAccount);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut usdc_commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(usdc_hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut usdt_commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(usdt_hashing_account, CommitmentHashingAccount);
+
+        macro_rules! upgrade {
+            ($fee_version: expr, $rate: expr) => {
+                upgrade_governor_state(
+                    &mut governor,
+                    &fee,
+                    &mut commitment_queue,
+                    &hashing_account,
+                    &mut usdc_commitment_queue,
+                    &usdc_hashing_account,
+                    &mut usdt_commitment_queue,
+                    &usdt_hashing_account,
+                    $fee_version,
+                    $rate,
+                )
+            };
+        }
+
+        // No proposed change
+        assert_matches!(upgrade!(0, 0), Err(_));
+
+        // Activation slot not reached (the unit-test slot is zero)
+        let mut staged = StagedGovernorParameters {
+            is_staged: true,
+            fee_version: 1,
+            commitment_batching_rate: 2,
+            activation_slot: 1,
+        };
+        governor.set_staged_parameters(&staged);
+        assert_matches!(upgrade!(1, 2), Err(_));
+
+        staged.activation_slot = 0;
+        governor.set_staged_parameters(&staged);
+
+        // Mismatched parameters
+        assert_matches!(upgrade!(1, 3), Err(_));
+        assert_matches!(upgrade!(0, 2), Err(_));
+
+        // The fee-version has not been initialized
+        assert_matches!(upgrade!(1, 2), Err(_));
+
+        let program_fee = ProgramFee::new(5000, 11, 100, 33, 44, 88, 300, 555).unwrap();
+        let schedule = TokenFeeSchedule {
+            is_set: true,
+            base_commitment_network_fee: 1,
+            proof_network_fee: 2,
+            warden_hash_tx_reward: 300,
+            warden_proof_reward: 4,
+        };
+        fee.set_program_fee(&program_fee);
+        fee.set_token_fee_schedules(1, &schedule);
+
+        // Active batch
+        hashing_account.set_is_active(&true);
+        assert_matches!(upgrade!(1, 2), Err(_));
+        hashing_account.set_is_active(&false);
+
+        // Raising the rate requires a full batch of the new rate in every non-empty queue
+        CommitmentQueue::new(&mut commitment_queue)
+            .enqueue(CommitmentHashRequest {
+                commitment: [0; 32],
+                fee_version: 0,
+                min_batching_rate: 0,
+                enqueue_slot: 0,
+            })
+            .unwrap();
+        assert_matches!(upgrade!(1, 2), Err(_));
+        {
+            let mut queue = CommitmentQueue::new(&mut commitment_queue);
+            for _ in 1..commitments_per_batch(2) {
+                queue
+                    .enqueue(CommitmentHashRequest {
+                        commitment: [0; 32],
+                        fee_version: 0,
+                        min_batching_rate: 0,
+                        enqueue_slot: 0,
+                    })
+                    .unwrap();
+            }
+        }
+
+        upgrade!(1, 2).unwrap();
+        assert_eq!(governor.get_fee_version(), 1);
+        assert_eq!(governor.get_program_fee(), program_fee);
+        assert_eq!(governor.get_token_fee_schedules(1), schedule);
+        assert_eq!(governor.get_commitment_batching_rate(), 2);
+        assert!(!governor.get_staged_parameters().is_staged);
+
+        // The change can only be activated once
+        assert_matches!(upgrade!(1, 2), Err(_));
+    }
+
+    #[test]
+    fn test_set_commitment_batching_rate() {
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(mut config, ConfigAccount);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut usdc_commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(usdc_hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut usdt_commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut usdt_hashing_account, CommitmentHashingAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_account_info!(invalid_authority, 0);
+        governor.set_commitment_batching_rate(&2);
+
+        macro_rules! set_rate {
+            ($authority: ident, $rate: expr) => {
+                set_commitment_batching_rate(
+                    &mut governor,
+                    &config,
+                    &mut commitment_queue,
+                    &hashing_account,
+                    &mut usdc_commitment_queue,
+                    &usdc_hashing_account,
+                    &mut usdt_commitment_queue,
+                    &usdt_hashing_account,
+                    &[&$authority],
+                    $rate,
+                )
+            };
+        }
+
+        let request = CommitmentHashRequest {
+            commitment: [0; 32],
+            fee_version: 0,
+            min_batching_rate: 1,
+            enqueue_slot: 0,
+        };
+
+        assert_matches!(set_rate!(invalid_authority, 1), Err(_));
+        assert_matches!(
+            set_rate!(authority, MAX_COMMITMENT_BATCHING_RATE as u32 + 1),
+            Err(_)
+        );
+
+        // Above the configured maximum
+        config.set_max_commitment_batching_rate(&1);
+        assert_matches!(set_rate!(authority, 2), Err(_));
+        config.set_max_commitment_batching_rate(&0);
+
+        // Lowering is always possible
+        set_rate!(authority, 1).unwrap();
+        assert_eq!(governor.get_commitment_batching_rate(), 1);
+
+        // Raising requires a full batch of the new rate in every non-empty queue
+        CommitmentQueue::new(&mut usdc_commitment_queue)
+            .enqueue(request)
+            .unwrap();
+        assert_matches!(set_rate!(authority, 2), Err(_));
+        {
+            let mut queue = CommitmentQueue::new(&mut usdc_commitment_queue);
+            for _ in 1..commitments_per_batch(2) {
+                queue.enqueue(request).unwrap();
+            }
+        }
+        set_rate!(authority, 2).unwrap();
+        assert_eq!(governor.get_commitment_batching_rate(), 2);
+
+        // Active batch in any pool
+        usdt_hashing_account.set_is_active(&true);
+        assert_matches!(set_rate!(authority, 0), Err(_));
+        usdt_hashing_account.set_is_active(&false);
+
+        // Staged change
+        governor.set_staged_parameters(&StagedGovernorParameters {
+            is_staged: true,
+            ..Default::default()
+        });
+        assert_matches!(set_rate!(authority, 0), Err(_));
+    }
+
+    #[test]
+    fn test_set_program_config() {
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(mut config, ConfigAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_account_info!(invalid_authority, 0);
+        governor.set_commitment_batching_rate(&2);
+
+        assert_matches!(
+            set_program_config(&governor, &mut config, &[&invalid_authority], 16, 2),
+            Err(_)
+        );
+
+        // Above the built-in constants
+        assert_matches!(
+            set_program_config(
+                &governor,
+                &mut config,
+                &[&authority],
+                CommitmentQueue::CAPACITY + 1,
+                2
+            ),
+            Err(_)
+        );
+        assert_matches!(
+            set_program_config(
+                &governor,
+                &mut config,
+                &[&authority],
+                16,
+                MAX_COMMITMENT_BATCHING_RATE as u32 + 1
+            ),
+            Err(_)
+        );
+
+        // A full batch of the maximum batching rate needs to fit into the commitment queue
+        assert_matches!(
+            set_program_config(&governor, &mut config, &[&authority], 3, 2),
+            Err(_)
+        );
+        assert_matches!(
+            set_program_config(&governor, &mut config, &[&authority], 15, 0),
+            Err(_)
+        );
+
+        // Below the current batching rate
+        assert_matches!(
+            set_program_config(&governor, &mut config, &[&authority], 16, 1),
+            Err(_)
+        );
+
+        // Below a staged batching rate
+        governor.set_staged_parameters(&StagedGovernorParameters {
+            is_staged: true,
+            commitment_batching_rate: 3,
+            ..Default::default()
+        });
+        assert_matches!(
+            set_program_config(&governor, &mut config, &[&authority], 16, 2),
+            Err(_)
+        );
+        governor.set_staged_parameters(&StagedGovernorParameters::default());
+
+        set_program_config(&governor, &mut config, &[&authority], 16, 2).unwrap();
+        assert_eq!(config.effective_commitment_queue_capacity(), 16);
+        assert_eq!(config.effective_max_commitment_batching_rate(), 2);
+
+        set_program_config(&governor, &mut config, &[&authority], 0, 0).unwrap();
+        assert_eq!(
+            config.effective_commitment_queue_capacity(),
+            CommitmentQueue::CAPACITY
+        );
+        assert_eq!(
+            config.effective_max_commitment_batching_rate(),
+            MAX_COMMITMENT_BATCHING_RATE as u32
+        );
+    }
+
+    #[test]
+    fn test_set_anonymity_delay_policy() {
+        zero_program_account!(mut governor, GovernorAccount);
+        account_info!(invalid_authority, Pubkey::new_unique(), vec![]);
+        account_info!(authority, crate::ID, vec![]);
+
+        assert_matches!(
+            set_anonymity_delay_policy(&mut governor, &[&invalid_authority], 100, [10, 0, 0]),
+            Err(_)
+        );
+
+        set_anonymity_delay_policy(&mut governor, &[&authority], 100, [10, 0, 0]).unwrap();
+        assert_eq!(governor.anonymity_delay_slots(0, 9, 5), 5);
+        assert_eq!(governor.anonymity_delay_slots(0, 10, 5), 100);
+        assert_eq!(governor.anonymity_delay_slots(0, 10, 200), 200);
+
+        // Disabled for other tokens
+        assert_eq!(governor.anonymity_delay_slots(1, u64::MAX, 0), 0);
+        assert_eq!(governor.anonymity_delay_slots(u16::MAX, u64::MAX, 0), 0);
+    }
+
+    #[test]
+    fn test_activate_emergency_mode() {
+        zero_program_account!(mut governor, GovernorAccount);
+        account_info!(invalid_authority, Pubkey::new_unique(), vec![]);
+        account_info!(authority, crate::ID, vec![]);
+
+        assert_matches!(
+            activate_emergency_mode(&mut governor, &[&invalid_authority]),
+            Err(_)
+        );
+
+        activate_emergency_mode(&mut governor, &[&authority]).unwrap();
+        assert!(governor.get_emergency_mode());
+
+        // Irreversible
+        assert_matches!(
+            activate_emergency_mode(&mut governor, &[&authority]),
+            Err(_)
+        );
+        assert!(governor.get_emergency_mode());
+    }
+
+    #[test]
+    fn test_set_halted() {
+        zero_program_account!(mut governor, GovernorAccount);
+        account_info!(invalid_authority, Pubkey::new_unique(), vec![]);
+        account_info!(authority, crate::ID, vec![]);
+
+        assert_matches!(
+            set_halted(&mut governor, &[&invalid_authority], true),
+            Err(_)
+        );
+        assert!(!governor.get_is_halted());
+
+        set_halted(&mut governor, &[&authority], true).unwrap();
+        assert!(governor.get_is_halted());
+
+        // Reversible
+        set_halted(&mut governor, &[&authority], false).unwrap();
+        assert!(!governor.get_is_halted());
+    }
+
+    #[test]
+    fn test_set_fee_compute_unit_price() {
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut fee, FeeAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_account_info!(invalid_authority, 0);
+
+        assert_matches!(
+            set_fee_compute_unit_price(&governor, &mut fee, &[&invalid_authority], 1, 1_000),
+            Err(_)
         );
-    } else {
-        guard!(
-            account.lamports() >= Rent::get()?.minimum_balance(data_len),
-            ElusivError::InvalidInstructionData
+        assert_matches!(
+            set_fee_compute_unit_price(
+                &governor,
+                &mut fee,
+                &[&authority],
+                1,
+                MAX_LAMPORTS_PER_CU + 1
+            ),
+            Err(_)
         );
+
+        // The active fee-version cannot be modified
+        assert_matches!(
+            set_fee_compute_unit_price(&governor, &mut fee, &[&authority], 0, 1_000),
+            Err(_)
+        );
+
+        set_fee_compute_unit_price(&governor, &mut fee, &[&authority], 1, 1_000).unwrap();
+        assert_eq!(fee.get_lamports_per_cu(), 1_000);
     }
 
-    // Check ownership
-    guard!(
-        *account.owner == crate::id(),
-        ElusivError::InvalidInstructionData
-    );
+    #[test]
+    fn test_set_fee_priority_lane_surcharge() {
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut fee, FeeAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_account_info!(invalid_authority, 0);
 
-    Ok(())
-}
+        assert_matches!(
+            set_fee_priority_lane_surcharge(&governor, &mut fee, &[&invalid_authority], 1, 1_000),
+            Err(_)
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        macros::account_info,
-        processor::CommitmentHashRequest,
-        state::{program_account::SizedAccount, queue::RingQueue, storage::StorageChildAccount},
-        types::U256,
-    };
-    use assert_matches::assert_matches;
-    use elusiv_types::ProgramAccount;
-    use solana_program::pubkey::Pubkey;
+        // The active fee-version cannot be modified
+        assert_matches!(
+            set_fee_priority_lane_surcharge(&governor, &mut fee, &[&authority], 0, 1_000),
+            Err(_)
+        );
+
+        set_fee_priority_lane_surcharge(&governor, &mut fee, &[&authority], 1, 1_000).unwrap();
+        assert_eq!(fee.get_priority_lane_surcharge(), 1_000);
+    }
 
     #[test]
-    fn test_enable_storage_child_account() {
-        let mut data = vec![0; StorageAccount::SIZE];
-        let mut storage_account = StorageAccount::new(&mut data).unwrap();
-        storage_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+    fn test_set_token_fee_schedule() {
+        set_test_slot(0);
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(mut fee, FeeAccount);
+        zero_program_account!(mut commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut usdc_commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(mut usdt_commitment_queue, CommitmentQueueAccount);
+        zero_program_account!(hashing_account, CommitmentHashingAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_account_info!(invalid_authority, 0);
+
+        let program_fee = ProgramFee::new(5000, 11, 100, 33, 44, 88, 300, 555).unwrap();
+        fee.set_program_fee(&program_fee);
+        governor.set_program_fee(&program_fee);
+
+        let schedule = TokenFeeSchedule {
+            is_set: true,
+            base_commitment_network_fee: 1,
+            proof_network_fee: 2,
+            warden_hash_tx_reward: 300,
+            warden_proof_reward: 4,
+        };
+
+        macro_rules! set {
+            ($signer: ident, $fee_version: expr, $token_id: expr, $schedule: expr) => {
+                set_token_fee_schedule(
+                    &governor,
+                    &mut fee,
+                    &[&$signer],
+                    $fee_version,
+                    $token_id,
+                    $schedule,
+                )
+            };
+        }
+
+        assert_matches!(set!(invalid_authority, 1, USDC_TOKEN_ID, schedule), Err(_));
+
+        // Lamports use the `ProgramFee` itself
+        assert_matches!(set!(authority, 1, LAMPORTS_TOKEN_ID, schedule), Err(_));
+
+        // The active fee-version cannot be modified
+        assert_matches!(set!(authority, 0, USDC_TOKEN_ID, schedule), Err(_));
+
+        // Without a schedule the lamports `ProgramFee` is used
+        assert_eq!(fee.program_fee_for_token(USDC_TOKEN_ID), program_fee);
+
+        assert_matches!(set!(authority, 1, USDC_TOKEN_ID, schedule), Ok(()));
+        assert_eq!(
+            fee.get_token_fee_schedules(USDC_TOKEN_ID as usize),
+            schedule
+        );
 
-        // Account has invalid size
-        account_info!(
-            child_account,
-            Pubkey::new_unique(),
-            vec![0; StorageChildAccount::SIZE - 1]
+        // The schedules are mirrored once the fee-version is activated
+        assert_eq!(governor.program_fee_for_token(USDC_TOKEN_ID), program_fee);
+
+        governor.set_staged_parameters(&StagedGovernorParameters {
+            is_staged: true,
+            fee_version: 1,
+            commitment_batching_rate: 0,
+            activation_slot: 0,
+        });
+        upgrade_governor_state(
+            &mut governor,
+            &fee,
+            &mut commitment_queue,
+            &hashing_account,
+            &mut usdc_commitment_queue,
+            &hashing_account,
+            &mut usdt_commitment_queue,
+            &hashing_account,
+            1,
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            governor.program_fee_for_token(USDC_TOKEN_ID),
+            fee.program_fee_for_token(USDC_TOKEN_ID)
         );
+        assert_matches!(set!(authority, 1, USDC_TOKEN_ID, schedule), Err(_));
+    }
+
+    #[test]
+    fn test_set_governance_multisig() {
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(config, ConfigAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_account_info!(a, 0);
+        test_account_info!(b, 0);
+        test_account_info!(c, 0);
+
+        let mut signers = [[0; 32]; MAX_GOVERNANCE_SIGNERS];
+        signers[0] = a.key.to_bytes();
+        signers[1] = b.key.to_bytes();
+        signers[2] = c.key.to_bytes();
+        let multisig = GovernanceMultisig {
+            threshold: 2,
+            signer_count: 3,
+            signers,
+        };
+
+        // Only the program's keypair can configure the initial multisig
         assert_matches!(
-            enable_storage_child_account(&mut storage_account, &child_account, 0),
+            set_governance_multisig(&mut governor, &[&a, &b], multisig),
             Err(_)
         );
 
-        // Account has already been setup
-        account_info!(
-            child_account,
-            Pubkey::new_unique(),
-            vec![0; StorageChildAccount::SIZE]
-        );
+        // Invalid multisig
         assert_matches!(
-            enable_storage_child_account(&mut storage_account, &child_account, 0),
+            set_governance_multisig(
+                &mut governor,
+                &[&authority],
+                GovernanceMultisig {
+                    threshold: 4,
+                    ..multisig
+                }
+            ),
             Err(_)
         );
 
-        // Success at different index
+        set_governance_multisig(&mut governor, &[&authority], multisig).unwrap();
+        assert_eq!(governor.get_governance_multisig(), multisig);
+
+        // The program's keypair is replaced by the multisig
+        assert_matches!(set_halted(&mut governor, &[&authority], true), Err(_));
+        assert_matches!(set_halted(&mut governor, &[&a, &a], true), Err(_));
+        set_halted(&mut governor, &[&a, &c], true).unwrap();
+        assert!(governor.get_is_halted());
         assert_matches!(
-            enable_storage_child_account(&mut storage_account, &child_account, 3),
-            Ok(())
+            activate_emergency_mode(&mut governor, &[&authority]),
+            Err(_)
         );
-        assert_eq!(child_account.data.borrow()[0], 1);
+        assert_matches!(
+            propose_governor_state(&mut governor, &config, &[&authority], 0, 0, u64::MAX),
+            Err(_)
+        );
+        activate_emergency_mode(&mut governor, &[&b, &c]).unwrap();
 
-        // Account already is use
+        // Reconfiguration requires the multisig's approval
+        let mut reconfigured = multisig;
+        reconfigured.threshold = 3;
         assert_matches!(
-            enable_storage_child_account(&mut storage_account, &child_account, 1),
+            set_governance_multisig(&mut governor, &[&authority], reconfigured),
             Err(_)
         );
+        set_governance_multisig(&mut governor, &[&b, &c], reconfigured).unwrap();
+        assert_matches!(set_halted(&mut governor, &[&a, &c], false), Err(_));
+        set_halted(&mut governor, &[&a, &b, &c], false).unwrap();
+        assert!(!governor.get_is_halted());
     }
 
     #[test]
-    fn test_enable_nullifier_child_account() {
-        let mut data = vec![0; NullifierAccount::SIZE];
-        let mut nullifier_account = NullifierAccount::new(&mut data).unwrap();
-        nullifier_account.set_child_pubkey(0, ElusivOption::Some(Pubkey::new_unique()));
+    fn test_set_supported_token() {
+        zero_program_account!(mut governor, GovernorAccount);
+        account_info!(invalid_authority, Pubkey::new_unique(), vec![]);
+        account_info!(authority, crate::ID, vec![]);
 
-        // Account has invalid size
-        account_info!(
-            child_account,
-            Pubkey::new_unique(),
-            vec![0; NullifierChildAccount::SIZE - 1]
+        assert!(governor.is_token_supported(0));
+        assert!(!governor.is_token_supported(1));
+
+        assert_matches!(
+            set_supported_token(&mut governor, &[&invalid_authority], 1, true),
+            Err(_)
         );
+
+        // Lamports cannot be removed
         assert_matches!(
-            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 0),
+            set_supported_token(&mut governor, &[&authority], 0, false),
             Err(_)
         );
 
-        // Account has already been setup
-        account_info!(
-            child_account,
-            Pubkey::new_unique(),
-            vec![0; NullifierChildAccount::SIZE]
+        // Unknown token-id
+        assert_matches!(
+            set_supported_token(&mut governor, &[&authority], STATS_TOKEN_COUNT as u16, true),
+            Err(_)
         );
+
+        set_supported_token(&mut governor, &[&authority], 1, true).unwrap();
+        assert!(governor.is_token_supported(1));
+        assert!(!governor.is_token_supported(2));
+
+        set_supported_token(&mut governor, &[&authority], 1, false).unwrap();
+        assert!(!governor.is_token_supported(1));
+    }
+
+    #[test]
+    fn test_set_epoch_deposit_cap() {
+        zero_program_account!(mut governor, GovernorAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_account_info!(invalid_authority, 0);
+
         assert_matches!(
-            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 0),
+            set_epoch_deposit_cap(&mut governor, &[&invalid_authority], 0, 100),
             Err(_)
         );
 
-        // Success at different index with
+        // Unknown token-id
         assert_matches!(
-            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 3),
-            Ok(())
+            set_epoch_deposit_cap(&mut governor, &[&authority], STATS_TOKEN_COUNT as u16, 100),
+            Err(_)
         );
-        assert_eq!(child_account.data.borrow()[0], 1);
 
-        // Account already is use
+        set_epoch_deposit_cap(&mut governor, &[&authority], 0, 100).unwrap();
+        assert_eq!(governor.get_epoch_deposit_caps(0), 100);
+    }
+
+    #[test]
+    fn test_set_withdrawal_delay_policy() {
+        zero_program_account!(mut governor, GovernorAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_account_info!(invalid_authority, 0);
+
         assert_matches!(
-            enable_nullifier_child_account(&mut nullifier_account, &child_account, 0, 1),
+            set_withdrawal_delay_policy(&mut governor, &[&invalid_authority], 100, 1_000),
             Err(_)
         );
+
+        set_withdrawal_delay_policy(&mut governor, &[&authority], 100, 1_000).unwrap();
+        assert_eq!(governor.get_required_withdrawal_delay_slots(), 100);
+        assert_eq!(governor.get_withdrawal_delay_threshold(), 1_000);
     }
 
     #[test]
-    fn test_is_mt_full() {
-        let mut data = vec![0; StorageAccount::SIZE];
-        let mut storage_account = StorageAccount::new(&mut data).unwrap();
-        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
+    fn test_add_denied_recipients() {
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut denylist, RecipientDenylistAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_account_info!(invalid_authority, 0);
+
+        let shard = 3;
+        let mut recipients = [Pubkey::default(); MAX_DENIED_RECIPIENTS_BATCH];
+        for (i, recipient) in recipients.iter_mut().enumerate() {
+            let mut bytes = [i as u8 + 1; 32];
+            bytes[0] = shard as u8;
+            *recipient = Pubkey::new_from_array(bytes);
+        }
+        let count = MAX_DENIED_RECIPIENTS_BATCH as u32;
 
-        let mut q_data = vec![0; CommitmentQueueAccount::SIZE];
-        let mut queue = CommitmentQueueAccount::new(&mut q_data).unwrap();
-        let mut queue = CommitmentQueue::new(&mut queue);
-        queue
-            .enqueue(CommitmentHashRequest {
-                min_batching_rate: 1,
-                commitment: [0; 32],
-                fee_version: 0,
-            })
+        assert_matches!(
+            add_denied_recipients(
+                &governor,
+                &mut denylist,
+                &[&invalid_authority],
+                shard,
+                count,
+                recipients
+            ),
+            Err(_)
+        );
+
+        // Recipients of another shard
+        assert_matches!(
+            add_denied_recipients(
+                &governor,
+                &mut denylist,
+                &[&authority],
+                shard + 1,
+                count,
+                recipients
+            ),
+            Err(_)
+        );
+
+        // Invalid recipient count
+        assert_matches!(
+            add_denied_recipients(
+                &governor,
+                &mut denylist,
+                &[&authority],
+                shard,
+                count + 1,
+                recipients
+            ),
+            Err(_)
+        );
+
+        add_denied_recipients(
+            &governor,
+            &mut denylist,
+            &[&authority],
+            shard,
+            2,
+            recipients,
+        )
+        .unwrap();
+
+        set_recipient_denylist_enabled(&governor, &mut denylist, &[&authority], shard, true)
             .unwrap();
-        queue
-            .enqueue(CommitmentHashRequest {
-                min_batching_rate: 1,
-                commitment: [0; 32],
-                fee_version: 0,
-            })
+        assert!(denylist.is_denied(&recipients[0]));
+        assert!(!denylist.is_denied(&recipients[2]));
+
+        set_denied_recipient(
+            &governor,
+            &mut denylist,
+            &[&authority],
+            recipients[0],
+            false,
+        )
+        .unwrap();
+        assert!(!denylist.is_denied(&recipients[0]));
+
+        assert_matches!(
+            set_denied_recipient(
+                &governor,
+                &mut denylist,
+                &[&invalid_authority],
+                recipients[0],
+                true
+            ),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_set_and_freeze_poseidon_params() {
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut poseidon_params, PoseidonParamsAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_account_info!(invalid_authority, 0);
+
+        let default_params = default_poseidon_params();
+        let mut chunk = [[0; 32]; POSEIDON_PARAMS_CHUNK_SIZE];
+        chunk.copy_from_slice(&default_params[..POSEIDON_PARAMS_CHUNK_SIZE]);
+
+        assert_matches!(
+            set_poseidon_params(
+                &governor,
+                &mut poseidon_params,
+                &[&invalid_authority],
+                0,
+                POSEIDON_PARAMS_CHUNK_SIZE as u32,
+                chunk
+            ),
+            Err(_)
+        );
+
+        // Invalid count
+        assert_matches!(
+            set_poseidon_params(
+                &governor,
+                &mut poseidon_params,
+                &[&authority],
+                0,
+                POSEIDON_PARAMS_CHUNK_SIZE as u32 + 1,
+                chunk
+            ),
+            Err(_)
+        );
+
+        for (i, params) in default_params
+            .chunks(POSEIDON_PARAMS_CHUNK_SIZE)
+            .enumerate()
+        {
+            let mut chunk = [[0; 32]; POSEIDON_PARAMS_CHUNK_SIZE];
+            chunk[..params.len()].copy_from_slice(params);
+            set_poseidon_params(
+                &governor,
+                &mut poseidon_params,
+                &[&authority],
+                (i * POSEIDON_PARAMS_CHUNK_SIZE) as u32,
+                params.len() as u32,
+                chunk,
+            )
             .unwrap();
+        }
 
-        assert!(is_mt_full(&storage_account, &queue).unwrap());
+        let checksum = default_poseidon_params_checksum();
+        assert_matches!(
+            freeze_poseidon_params(
+                &governor,
+                &mut poseidon_params,
+                &[&invalid_authority],
+                DEFAULT_POSEIDON_PARAMS_VERSION
+            ),
+            Err(_)
+        );
 
-        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 3));
-        assert!(!is_mt_full(&storage_account, &queue).unwrap());
+        // Version without a pinned checksum
+        assert_matches!(
+            freeze_poseidon_params(
+                &governor,
+                &mut poseidon_params,
+                &[&authority],
+                DEFAULT_POSEIDON_PARAMS_VERSION + 1
+            ),
+            Err(_)
+        );
 
-        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 2));
-        assert!(is_mt_full(&storage_account, &queue).unwrap());
+        freeze_poseidon_params(
+            &governor,
+            &mut poseidon_params,
+            &[&authority],
+            DEFAULT_POSEIDON_PARAMS_VERSION,
+        )
+        .unwrap();
+        assert!(poseidon_params.get_is_frozen());
+        assert_eq!(poseidon_params.get_checksum(), checksum);
+
+        // Upgrades require the governance authority and a newer pinned version
+        assert_matches!(
+            unfreeze_poseidon_params(&governor, &mut poseidon_params, &[&invalid_authority]),
+            Err(_)
+        );
+        assert_matches!(
+            unfreeze_poseidon_params(&governor, &mut poseidon_params, &[&authority]),
+            Err(_)
+        );
+
+        // Frozen parameters cannot be changed
+        assert_matches!(
+            set_poseidon_params(
+                &governor,
+                &mut poseidon_params,
+                &[&authority],
+                0,
+                1,
+                [[0; 32]; POSEIDON_PARAMS_CHUNK_SIZE]
+            ),
+            Err(_)
+        );
+        assert_eq!(poseidon_params.compute_checksum(), checksum);
     }
 
     #[test]
-    #[should_panic]
-    fn test_archive_closed_merkle_tree() {
-        test_account_info!(payer, 0);
-        let mut data = vec![0; StorageAccount::SIZE];
-        let mut storage_account = StorageAccount::new(&mut data).unwrap();
-        let mut data = vec![0; NullifierAccount::SIZE];
-        let mut nullifier_account = NullifierAccount::new(&mut data).unwrap();
-        test_account_info!(archived_tree_account, 0);
+    fn test_set_compute_unit_margin() {
+        zero_program_account!(mut governor, GovernorAccount);
+        account_info!(invalid_authority, Pubkey::new_unique(), vec![]);
+        account_info!(authority, crate::ID, vec![]);
 
-        archive_closed_merkle_tree(
-            &payer,
-            &mut storage_account,
-            &mut nullifier_account,
-            &archived_tree_account,
-            0,
+        assert_matches!(
+            set_compute_unit_margin(&mut governor, &[&invalid_authority], 100),
+            Err(_)
+        );
+
+        assert_matches!(
+            set_compute_unit_margin(&mut governor, &[&authority], MAX_COMPUTE_UNIT_MARGIN + 1),
+            Err(_)
+        );
+
+        set_compute_unit_margin(&mut governor, &[&authority], MAX_COMPUTE_UNIT_MARGIN).unwrap();
+        assert_eq!(governor.get_compute_unit_margin(), MAX_COMPUTE_UNIT_MARGIN);
+    }
+
+    #[test]
+    fn test_set_price_oracle() {
+        zero_program_account!(mut governor, GovernorAccount);
+        account_info!(invalid_authority, Pubkey::new_unique(), vec![]);
+        account_info!(authority, crate::ID, vec![]);
+        let price_account = Pubkey::new_unique();
+
+        assert_eq!(
+            governor.price_account(USDC_TOKEN_ID).unwrap(),
+            usdc_token().pyth_usd_price_key
+        );
+
+        assert_matches!(
+            set_price_account(
+                &mut governor,
+                &[&invalid_authority],
+                USDC_TOKEN_ID,
+                price_account.to_bytes()
+            ),
+            Err(_)
+        );
+        assert_matches!(
+            set_price_account(
+                &mut governor,
+                &[&authority],
+                STATS_TOKEN_COUNT as u16,
+                price_account.to_bytes()
+            ),
+            Err(_)
+        );
+
+        set_price_account(
+            &mut governor,
+            &[&authority],
+            USDC_TOKEN_ID,
+            price_account.to_bytes(),
         )
         .unwrap();
+        assert_eq!(
+            governor.price_account(USDC_TOKEN_ID).unwrap(),
+            price_account
+        );
+
+        set_price_account(&mut governor, &[&authority], USDC_TOKEN_ID, [0; 32]).unwrap();
+        assert_eq!(
+            governor.price_account(USDC_TOKEN_ID).unwrap(),
+            usdc_token().pyth_usd_price_key
+        );
+
+        assert_matches!(
+            set_price_oracle_policy(&mut governor, &[&invalid_authority], 60, 100),
+            Err(_)
+        );
+        assert_matches!(
+            set_price_oracle_policy(&mut governor, &[&authority], 60, 10_001),
+            Err(_)
+        );
+
+        set_price_oracle_policy(&mut governor, &[&authority], 60, 100).unwrap();
+        assert_eq!(governor.get_max_price_age(), 60);
+        assert_eq!(governor.get_max_price_confidence_bps(), 100);
     }
 
-    #[test]
-    #[should_panic]
-    fn test_upgrade_governor_state() {
-        test_account_info!(authority, 0);
-        zero_program_account!(mut governor_account, GovernorAccount);
-        zero_program_account!(commitment_queue, CommitmentQueueAccount);
+    /// Rent-exemption requiring one lamport per byte
+    struct TestRentSysvar;
 
-        upgrade_governor_state(&authority, &mut governor_account, &commitment_queue, 1, 1).unwrap();
+    impl RentSysvar for TestRentSysvar {
+        fn minimum_balance(&self, data_len: usize) -> Result<u64, ProgramError> {
+            Ok(data_len as u64)
+        }
     }
 
     #[test]
@@ -533,18 +3144,50 @@ mod tests {
 
         // Mismatched size
         account_info!(account, pk, vec![0; 100]);
-        assert_matches!(verify_extern_data_account(&account, 99, true), Err(_));
+        assert_matches!(
+            verify_extern_data_account(&account, 99, true, &TestRentSysvar),
+            Err(_)
+        );
 
         // Non-zero
         account_info!(account, pk, vec![1; 100]);
-        assert_matches!(verify_extern_data_account(&account, 100, true), Err(_));
+        assert_matches!(
+            verify_extern_data_account(&account, 100, true, &TestRentSysvar),
+            Err(_)
+        );
 
         // Ignore zero
-        assert_matches!(verify_extern_data_account(&account, 100, false), Ok(()));
+        assert_matches!(
+            verify_extern_data_account(&account, 100, false, &TestRentSysvar),
+            Ok(())
+        );
 
         // Check zero
         account_info!(account, pk, vec![0; 100]);
-        assert_matches!(verify_extern_data_account(&account, 100, true), Ok(()));
+        assert_matches!(
+            verify_extern_data_account(&account, 100, true, &TestRentSysvar),
+            Ok(())
+        );
+
+        // Not rent-exempt
+        **account.lamports.borrow_mut() = 99;
+        assert_matches!(
+            verify_extern_data_account(&account, 100, true, &TestRentSysvar),
+            Err(_)
+        );
+
+        **account.lamports.borrow_mut() = 100;
+        assert_matches!(
+            verify_extern_data_account(&account, 100, true, &TestRentSysvar),
+            Ok(())
+        );
+
+        // Invalid owner
+        account_info!(account, pk, vec![0; 100], Pubkey::new_unique(), false);
+        assert_matches!(
+            verify_extern_data_account(&account, 100, true, &TestRentSysvar),
+            Err(_)
+        );
     }
 
     struct TestChildAccount;
@@ -0,0 +1,515 @@
+use crate::bytes::ElusivOption;
+use crate::computation::{current_slot, PartialComputationDriver};
+use crate::error::ElusivError;
+use crate::fields::Wrap;
+use crate::macros::{guard, pda_account};
+use crate::proof::verifier::{verify_batch_partial, BatchVerificationStep, VerificationStep};
+use crate::proof::vkey::VerifyingKey;
+use crate::state::program_account::Lifecycle;
+use crate::state::proof::{
+    BatchVerificationAccount, VerificationAccount, VerificationState, MAX_BATCH_VERIFICATION_PROOFS,
+};
+use crate::state::vkey::VKeyAccount;
+use elusiv_types::ParentAccount;
+use solana_program::sysvar::instructions;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+
+/// Opens a [`BatchVerificationAccount`] for proofs of the vkey `vkey_id`
+pub fn init_batch_verification<'a>(
+    fee_payer: &AccountInfo<'a>,
+    batch_verification_account: &AccountInfo<'a>,
+    vkey_account: &VKeyAccount,
+
+    batch_verification_account_index: u8,
+    vkey_id: u32,
+) -> ProgramResult {
+    guard!(vkey_account.get_version() != 0, ElusivError::InvalidAccount);
+    guard!(vkey_account.is_sealed(), ElusivError::InvalidAccount);
+
+    BatchVerificationAccount::reopen(
+        fee_payer,
+        batch_verification_account,
+        Some(*fee_payer.key),
+        Some(batch_verification_account_index as u32),
+        None,
+    )?;
+
+    pda_account!(
+        mut batch_verification_account,
+        BatchVerificationAccount,
+        batch_verification_account
+    );
+    batch_verification_account.setup(vkey_id, current_slot()?);
+
+    Ok(())
+}
+
+/// Adds a proof, whose miller loop has been computed, to a [`BatchVerificationAccount`]
+/// - the proof can no longer be computed on its own until the batch is finalized
+pub fn add_batch_verification_proof(
+    fee_payer: &AccountInfo,
+    batch_verification_account: &mut BatchVerificationAccount,
+    verification_account: &mut VerificationAccount,
+
+    _batch_verification_account_index: u8,
+    verification_account_index: u8,
+) -> ProgramResult {
+    // The batch is sealed with the first computation
+    guard!(
+        batch_verification_account
+            .get_is_verified()
+            .option()
+            .is_none(),
+        ElusivError::ComputationIsAlreadyFinished
+    );
+    guard!(
+        matches!(
+            batch_verification_account.get_step(),
+            BatchVerificationStep::MultiExponentiation
+        ) && batch_verification_account.get_instruction() == 0,
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        (batch_verification_account.get_proofs_count() as usize) < MAX_BATCH_VERIFICATION_PROOFS,
+        ElusivError::InvalidAccountState
+    );
+
+    guard!(
+        verification_account.get_other_data().fee_payer.skip_mr() == fee_payer.key.to_bytes(),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        verification_account.get_vkey_id() == batch_verification_account.get_vkey_id(),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        verification_account.get_is_verified().option().is_none(),
+        ElusivError::ComputationIsAlreadyFinished
+    );
+    guard!(
+        matches!(
+            verification_account.get_state(),
+            VerificationState::ProofSetup
+        ),
+        ElusivError::InvalidAccountState
+    );
+
+    // Only the final exponentiation is left
+    guard!(
+        matches!(
+            verification_account.get_step(),
+            VerificationStep::FinalExponentiation
+        ) && verification_account.get_instruction() == 0
+            && verification_account.get_round() == 0,
+        ElusivError::ComputationIsNotYetFinished
+    );
+
+    batch_verification_account
+        .add_proof(verification_account_index, verification_account.f.get().0);
+    verification_account.set_state(&VerificationState::Batched);
+
+    Ok(())
+}
+
+/// Partial batch verification computation
+/// - `verification_account` is the first [`VerificationAccount`] of the batch
+pub fn compute_batch_verification(
+    batch_verification_account: &mut BatchVerificationAccount,
+    verification_account: &mut VerificationAccount,
+    vkey_account: &VKeyAccount,
+    instructions_account: &AccountInfo,
+
+    _batch_verification_account_index: u8,
+    verification_account_index: u8,
+    vkey_id: u32,
+) -> ProgramResult {
+    guard!(vkey_account.get_version() != 0, ElusivError::InvalidAccount);
+    guard!(
+        batch_verification_account.get_vkey_id() == vkey_id,
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        batch_verification_account
+            .get_is_verified()
+            .option()
+            .is_none(),
+        ElusivError::ComputationIsAlreadyFinished
+    );
+    guard!(
+        batch_verification_account.get_proofs_count() > 0,
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        batch_verification_account.position(verification_account_index) == Some(0),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        matches!(verification_account.get_state(), VerificationState::Batched),
+        ElusivError::InvalidAccountState
+    );
+
+    // instruction_index is used to allow a uniform number of ixs per tx
+    let instruction_index = instructions::load_current_index_checked(instructions_account)?;
+
+    let slot = current_slot()?;
+    let result = vkey_account.execute_on_child_account_mut(0, |data| {
+        let vkey = VerifyingKey::new(data, vkey_account.get_public_inputs_count() as usize)
+            .ok_or(ElusivError::InvalidAccountState)?;
+
+        verify_batch_partial(
+            batch_verification_account,
+            verification_account,
+            &vkey,
+            instruction_index,
+            slot,
+        )
+    })?;
+
+    match result {
+        Ok(result) => {
+            if let Some(final_result) = result {
+                batch_verification_account.set_is_verified(&ElusivOption::Some(final_result));
+            }
+
+            Ok(())
+        }
+        Err(e) => match e {
+            ElusivError::InvalidAccountState => Err(e.into()),
+            _ => {
+                // The proofs need to be verified individually
+                batch_verification_account.set_is_verified(&ElusivOption::Some(false));
+                Ok(())
+            }
+        },
+    }
+}
+
+/// Applies the result of a [`BatchVerificationAccount`] to one of its [`VerificationAccount`]s
+/// - if the batch is invalid, the proof has to be verified on its own
+/// - the batch is closed once all of its proofs are finalized
+pub fn finalize_batch_verification<'a>(
+    original_fee_payer: &AccountInfo<'a>,
+    batch_verification_account_info: &AccountInfo<'a>,
+    verification_account: &mut VerificationAccount,
+
+    _batch_verification_account_index: u8,
+    verification_account_index: u8,
+) -> ProgramResult {
+    let is_fully_finalized = {
+        pda_account!(
+            mut batch_verification_account,
+            BatchVerificationAccount,
+            batch_verification_account_info
+        );
+
+        let is_verified = batch_verification_account
+            .get_is_verified()
+            .option()
+            .ok_or(ElusivError::ComputationIsNotYetFinished)?;
+
+        let position = batch_verification_account
+            .position(verification_account_index)
+            .ok_or(ElusivError::InvalidAccount)?;
+        guard!(
+            !batch_verification_account.get_is_finalized(position),
+            ElusivError::InvalidAccountState
+        );
+        guard!(
+            matches!(verification_account.get_state(), VerificationState::Batched),
+            ElusivError::InvalidAccountState
+        );
+
+        if is_verified {
+            verification_account.set_is_verified(&ElusivOption::Some(true));
+        } else if position == 0 {
+            // The final exponentiation of the batch has been computed by the first proof
+            verification_account.f.set(Wrap(
+                batch_verification_account.get_miller_loop_outputs(0).0,
+            ));
+            verification_account.set_step(&VerificationStep::FinalExponentiation);
+            verification_account.start_computation(current_slot()?);
+        }
+
+        verification_account.set_state(&VerificationState::ProofSetup);
+        batch_verification_account.set_is_finalized(position, &true);
+
+        batch_verification_account.is_fully_finalized()
+    };
+
+    if is_fully_finalized {
+        BatchVerificationAccount::close(original_fee_payer, batch_verification_account_info)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{
+        account_info, instructions_account_info, test_account_info, test_pda_account_info,
+        zero_program_account,
+    };
+    use crate::processor::COMPUTE_VERIFICATION_IX_COUNT;
+    use crate::proof::verifier::FINAL_EXPONENTIATION_IXS;
+    use crate::proof::vkey::{SendQuadraVKey, VerifyingKeyInfo};
+    use crate::state::program_account::ProgramAccount;
+    use crate::state::proof::VerificationAccountData;
+    use crate::syscall_stubs::set_test_slot;
+    use crate::types::RawU256;
+    use ark_bn254::Fq12;
+    use ark_ff::{Field, One};
+    use assert_matches::assert_matches;
+    use solana_program::pubkey::Pubkey;
+
+    fn setup_verification_account(
+        verification_account: &mut VerificationAccount,
+        fee_payer: &Pubkey,
+    ) {
+        verification_account.set_other_data(&VerificationAccountData {
+            fee_payer: RawU256::new(fee_payer.to_bytes()),
+            ..Default::default()
+        });
+        verification_account.set_vkey_id(&SendQuadraVKey::VKEY_ID);
+        verification_account.set_state(&VerificationState::ProofSetup);
+        verification_account.set_step(&VerificationStep::FinalExponentiation);
+        verification_account.f.set(Wrap(Fq12::one().double()));
+    }
+
+    #[test]
+    fn test_add_batch_verification_proof() {
+        let pk = Pubkey::new_unique();
+        account_info!(fee_payer, pk, vec![0; 0]);
+        zero_program_account!(mut batch, BatchVerificationAccount);
+        batch.setup(SendQuadraVKey::VKEY_ID, 0);
+
+        zero_program_account!(mut verification_account, VerificationAccount);
+        setup_verification_account(&mut verification_account, &pk);
+
+        // Miller loop is not yet computed
+        verification_account.set_step(&VerificationStep::CombinedMillerLoop);
+        assert_matches!(
+            add_batch_verification_proof(&fee_payer, &mut batch, &mut verification_account, 0, 3),
+            Err(_)
+        );
+        verification_account.set_step(&VerificationStep::FinalExponentiation);
+
+        // Invalid vkey
+        verification_account.set_vkey_id(&(SendQuadraVKey::VKEY_ID + 1));
+        assert_matches!(
+            add_batch_verification_proof(&fee_payer, &mut batch, &mut verification_account, 0, 3),
+            Err(_)
+        );
+        verification_account.set_vkey_id(&SendQuadraVKey::VKEY_ID);
+
+        // Invalid fee-payer
+        test_account_info!(invalid_fee_payer, 0);
+        assert_matches!(
+            add_batch_verification_proof(
+                &invalid_fee_payer,
+                &mut batch,
+                &mut verification_account,
+                0,
+                3
+            ),
+            Err(_)
+        );
+
+        // Batch is sealed
+        batch.set_instruction(&1);
+        assert_matches!(
+            add_batch_verification_proof(&fee_payer, &mut batch, &mut verification_account, 0, 3),
+            Err(_)
+        );
+        batch.set_instruction(&0);
+
+        // Success
+        assert_matches!(
+            add_batch_verification_proof(&fee_payer, &mut batch, &mut verification_account, 0, 3),
+            Ok(())
+        );
+        assert_matches!(verification_account.get_state(), VerificationState::Batched);
+        assert_eq!(batch.get_proofs_count(), 1);
+        assert_eq!(batch.position(3), Some(0));
+        assert_eq!(batch.all_miller_loop_outputs(), vec![Fq12::one().double()]);
+
+        // Proof is already batched
+        assert_matches!(
+            add_batch_verification_proof(&fee_payer, &mut batch, &mut verification_account, 0, 3),
+            Err(_)
+        );
+
+        // Batch is full
+        for i in 1..MAX_BATCH_VERIFICATION_PROOFS {
+            zero_program_account!(mut verification_account, VerificationAccount);
+            setup_verification_account(&mut verification_account, &pk);
+            assert_matches!(
+                add_batch_verification_proof(
+                    &fee_payer,
+                    &mut batch,
+                    &mut verification_account,
+                    0,
+                    3 + i as u8
+                ),
+                Ok(())
+            );
+        }
+
+        zero_program_account!(mut verification_account, VerificationAccount);
+        setup_verification_account(&mut verification_account, &pk);
+        assert_matches!(
+            add_batch_verification_proof(&fee_payer, &mut batch, &mut verification_account, 0, 100),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_compute_batch_verification_invalid_accounts() {
+        zero_program_account!(mut batch, BatchVerificationAccount);
+        zero_program_account!(mut verification_account, VerificationAccount);
+        zero_program_account!(vkey, VKeyAccount);
+        instructions_account_info!(
+            instructions_account,
+            Vec::new(),
+            COMPUTE_VERIFICATION_IX_COUNT - 1
+        );
+        batch.setup(SendQuadraVKey::VKEY_ID, 0);
+        batch.add_proof(3, Fq12::one());
+
+        // Verification account is not batched
+        assert_matches!(
+            compute_batch_verification(
+                &mut batch,
+                &mut verification_account,
+                &vkey,
+                &instructions_account,
+                0,
+                3,
+                SendQuadraVKey::VKEY_ID
+            ),
+            Err(_)
+        );
+        verification_account.set_state(&VerificationState::Batched);
+
+        // Not the first proof of the batch
+        assert_matches!(
+            compute_batch_verification(
+                &mut batch,
+                &mut verification_account,
+                &vkey,
+                &instructions_account,
+                0,
+                4,
+                SendQuadraVKey::VKEY_ID
+            ),
+            Err(_)
+        );
+
+        // Invalid vkey-id
+        assert_matches!(
+            compute_batch_verification(
+                &mut batch,
+                &mut verification_account,
+                &vkey,
+                &instructions_account,
+                0,
+                3,
+                SendQuadraVKey::VKEY_ID + 1
+            ),
+            Err(_)
+        );
+
+        // Batch is already verified
+        batch.set_is_verified(&ElusivOption::Some(true));
+        assert_matches!(
+            compute_batch_verification(
+                &mut batch,
+                &mut verification_account,
+                &vkey,
+                &instructions_account,
+                0,
+                3,
+                SendQuadraVKey::VKEY_ID
+            ),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_finalize_batch_verification() {
+        set_test_slot(0);
+        for is_verified in [true, false] {
+            let pk = Pubkey::new_unique();
+            account_info!(fee_payer, pk, vec![0; 0]);
+            test_pda_account_info!(batch_account, BatchVerificationAccount, pk, Some(0));
+            zero_program_account!(mut leader, VerificationAccount);
+            zero_program_account!(mut member, VerificationAccount);
+
+            {
+                let mut data = batch_account.data.borrow_mut();
+                let mut batch = BatchVerificationAccount::new(&mut data).unwrap();
+                batch.setup(SendQuadraVKey::VKEY_ID, 0);
+                batch.add_proof(0, Fq12::one().double());
+                batch.add_proof(1, Fq12::one());
+            }
+            leader.set_state(&VerificationState::Batched);
+            leader.set_step(&VerificationStep::FinalExponentiation);
+            leader.set_instruction(&(FINAL_EXPONENTIATION_IXS as u32));
+            leader.f.set(Wrap(Fq12::one()));
+            member.set_state(&VerificationState::Batched);
+
+            // Batch is not yet computed
+            assert_matches!(
+                finalize_batch_verification(&fee_payer, &batch_account, &mut leader, 0, 0),
+                Err(_)
+            );
+
+            {
+                let mut data = batch_account.data.borrow_mut();
+                let mut batch = BatchVerificationAccount::new(&mut data).unwrap();
+                batch.set_is_verified(&ElusivOption::Some(is_verified));
+            }
+
+            // Proof is not part of the batch
+            assert_matches!(
+                finalize_batch_verification(&fee_payer, &batch_account, &mut member, 0, 2),
+                Err(_)
+            );
+
+            assert_matches!(
+                finalize_batch_verification(&fee_payer, &batch_account, &mut leader, 0, 0),
+                Ok(())
+            );
+            assert_matches!(leader.get_state(), VerificationState::ProofSetup);
+
+            if is_verified {
+                assert_matches!(leader.get_is_verified().option(), Some(true));
+            } else {
+                // The leader can continue with its own final exponentiation
+                assert_matches!(leader.get_is_verified().option(), None);
+                assert_eq!(leader.f.get().0, Fq12::one().double());
+                assert_eq!(leader.get_instruction(), 0);
+            }
+
+            // Proof is already finalized
+            leader.set_state(&VerificationState::Batched);
+            assert_matches!(
+                finalize_batch_verification(&fee_payer, &batch_account, &mut leader, 0, 0),
+                Err(_)
+            );
+            assert_ne!(batch_account.lamports(), 0);
+
+            // The batch is closed with the last proof
+            assert_matches!(
+                finalize_batch_verification(&fee_payer, &batch_account, &mut member, 0, 1),
+                Ok(())
+            );
+            assert_matches!(member.get_state(), VerificationState::ProofSetup);
+            assert_eq!(
+                member.get_is_verified().option(),
+                is_verified.then_some(true)
+            );
+            assert_eq!(batch_account.lamports(), 0);
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use crate::computation::current_slot;
+use crate::error::ElusivError;
+use crate::macros::guard;
+use crate::state::call::{is_withdraw_and_call_program, WithdrawAndCallAuthority};
+use crate::state::program_account::PDAAccount;
+use crate::types::U256;
+use borsh::{BorshDeserialize, BorshSerialize};
+use elusiv_utils::signers_seeds;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    hash::hashv,
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+};
+
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Clone, Debug)]
+pub struct WithdrawAndCallAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_writable: bool,
+}
+
+/// A CPI into a whitelisted program performed with withdrawn funds
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Clone, Debug)]
+pub struct WithdrawAndCall {
+    pub program_id: Pubkey,
+
+    /// Accounts of the CPI following the [`WithdrawAndCallAuthority`] (which is the only signer)
+    pub accounts: Vec<WithdrawAndCallAccountMeta>,
+    pub data: Vec<u8>,
+
+    /// Receives the lamports remaining after the CPI (or all lamports of a refund)
+    pub remainder_recipient: Pubkey,
+
+    /// Slot after which the funds of a call that has not been executed can be refunded to the `remainder_recipient`
+    pub refund_slot: u64,
+}
+
+/// Identifies a [`WithdrawAndCall`] by the hash of the serialized call
+pub fn withdraw_and_call_hash(call: &WithdrawAndCall) -> Result<U256, ProgramError> {
+    Ok(hashv(&[&call.try_to_vec()?]).to_bytes())
+}
+
+/// The [`WithdrawAndCallAuthority`] that needs to be used as the recipient of the send funding `call`
+pub fn withdraw_and_call_authority(call: &WithdrawAndCall) -> Result<(Pubkey, u8), ProgramError> {
+    let call_hash = withdraw_and_call_hash(call)?;
+    Ok(WithdrawAndCallAuthority::find_with_pubkey(
+        Pubkey::new_from_array(call_hash),
+        None,
+    ))
+}
+
+/// Performs the CPI of a funded [`WithdrawAndCallAuthority`] (permissionless)
+/// - `call_accounts` need to be supplied in the order of `call.accounts`
+/// - afterwards all remaining lamports of the authority are transferred to the `remainder_recipient`
+pub fn execute_withdraw_and_call<'a>(
+    call_authority: &AccountInfo<'a>,
+    program: &AccountInfo<'a>,
+    remainder_recipient: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    call_accounts: &[&AccountInfo<'a>],
+
+    call: WithdrawAndCall,
+) -> ProgramResult {
+    guard!(
+        is_withdraw_and_call_program(&call.program_id),
+        ElusivError::InvalidAccount
+    );
+    guard!(*program.key == call.program_id, ElusivError::InvalidAccount);
+    guard!(
+        *remainder_recipient.key == call.remainder_recipient,
+        ElusivError::InvalidRecipient
+    );
+    guard!(
+        call_accounts.len() == call.accounts.len(),
+        ElusivError::InvalidInstructionData
+    );
+    for (account, meta) in call_accounts.iter().zip(&call.accounts) {
+        guard!(*account.key == meta.pubkey, ElusivError::InvalidAccount);
+    }
+
+    // The call is bound to the funds by the authority's address
+    let (call_hash, bump) = verify_withdraw_and_call_authority(call_authority, &call)?;
+    let authority = *call_authority.key;
+    let seeds = WithdrawAndCallAuthority::signers_seeds(Some(call_hash), None, bump);
+    let signers_seeds = signers_seeds!(seeds);
+
+    // The authority can't be part of `call.accounts` (its address depends on them)
+    let mut accounts = vec![AccountMeta::new(authority, true)];
+    accounts.extend(call.accounts.iter().map(|meta| AccountMeta {
+        pubkey: meta.pubkey,
+        is_signer: false,
+        is_writable: meta.is_writable,
+    }));
+    let instruction = Instruction {
+        program_id: call.program_id,
+        accounts,
+        data: call.data,
+    };
+
+    let mut account_infos: Vec<AccountInfo> = call_accounts
+        .iter()
+        .map(|account| (*account).clone())
+        .collect();
+    account_infos.push(call_authority.clone());
+    account_infos.push(program.clone());
+    invoke_signed(&instruction, &account_infos, &[&signers_seeds])?;
+
+    // `call_authority` transfers the remaining lamports to `remainder_recipient`
+    transfer_withdraw_and_call_remainder(
+        call_authority,
+        remainder_recipient,
+        system_program,
+        &signers_seeds,
+    )
+}
+
+/// Refunds the funds of a [`WithdrawAndCallAuthority`] whose call has not been executed until `call.refund_slot` (permissionless)
+/// - all lamports of the authority are transferred to the `remainder_recipient` bound into the call
+pub fn refund_withdraw_and_call<'a>(
+    call_authority: &AccountInfo<'a>,
+    remainder_recipient: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+
+    call: WithdrawAndCall,
+) -> ProgramResult {
+    guard!(
+        *remainder_recipient.key == call.remainder_recipient,
+        ElusivError::InvalidRecipient
+    );
+    let (call_hash, bump) = verify_withdraw_and_call_authority(call_authority, &call)?;
+    guard!(
+        current_slot()? > call.refund_slot,
+        ElusivError::WithdrawAndCallIsNotTimedOut
+    );
+
+    let seeds = WithdrawAndCallAuthority::signers_seeds(Some(call_hash), None, bump);
+    let signers_seeds = signers_seeds!(seeds);
+
+    // `call_authority` transfers all lamports to `remainder_recipient`
+    transfer_withdraw_and_call_remainder(
+        call_authority,
+        remainder_recipient,
+        system_program,
+        &signers_seeds,
+    )
+}
+
+/// Verifies that `call_authority` is the funded [`WithdrawAndCallAuthority`] of `call` and returns its seed and bump
+fn verify_withdraw_and_call_authority(
+    call_authority: &AccountInfo,
+    call: &WithdrawAndCall,
+) -> Result<(Pubkey, u8), ProgramError> {
+    let call_hash = Pubkey::new_from_array(withdraw_and_call_hash(call)?);
+    let (authority, bump) = WithdrawAndCallAuthority::find_with_pubkey(call_hash, None);
+    guard!(
+        *call_authority.key == authority,
+        ElusivError::InvalidAccount
+    );
+    guard!(call_authority.lamports() > 0, ElusivError::InvalidAmount);
+
+    Ok((call_hash, bump))
+}
+
+fn transfer_withdraw_and_call_remainder<'a>(
+    call_authority: &AccountInfo<'a>,
+    remainder_recipient: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    signers_seeds: &[&[u8]],
+) -> ProgramResult {
+    let remainder = call_authority.lamports();
+    if remainder > 0 {
+        invoke_signed(
+            &system_instruction::transfer(call_authority.key, remainder_recipient.key, remainder),
+            &[
+                call_authority.clone(),
+                remainder_recipient.clone(),
+                system_program.clone(),
+            ],
+            &[signers_seeds],
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::account_info;
+    use crate::state::call::WITHDRAW_AND_CALL_PROGRAMS;
+    use crate::syscall_stubs::set_test_slot;
+    use assert_matches::assert_matches;
+
+    fn test_call(program_id: Pubkey, accounts: &[Pubkey]) -> WithdrawAndCall {
+        WithdrawAndCall {
+            program_id,
+            accounts: accounts
+                .iter()
+                .map(|pubkey| WithdrawAndCallAccountMeta {
+                    pubkey: *pubkey,
+                    is_writable: true,
+                })
+                .collect(),
+            data: vec![1, 2, 3],
+            remainder_recipient: Pubkey::new_unique(),
+            refund_slot: 100,
+        }
+    }
+
+    #[test]
+    fn test_withdraw_and_call_authority() {
+        let call = test_call(WITHDRAW_AND_CALL_PROGRAMS[0], &[Pubkey::new_unique()]);
+        let (authority, _) = withdraw_and_call_authority(&call).unwrap();
+
+        // Any change of the call results in a different authority
+        let mut redirected_call = call.clone();
+        redirected_call.accounts[0].pubkey = Pubkey::new_unique();
+        assert_ne!(
+            withdraw_and_call_authority(&redirected_call).unwrap().0,
+            authority
+        );
+
+        let mut redirected_call = call;
+        redirected_call.data = vec![1, 2, 4];
+        assert_ne!(
+            withdraw_and_call_authority(&redirected_call).unwrap().0,
+            authority
+        );
+    }
+
+    #[test]
+    fn test_execute_withdraw_and_call_invalid_accounts() {
+        let program_id = WITHDRAW_AND_CALL_PROGRAMS[0];
+        let account_pk = Pubkey::new_unique();
+        let call = test_call(program_id, &[account_pk]);
+        let (authority_pk, _) = withdraw_and_call_authority(&call).unwrap();
+
+        account_info!(authority, authority_pk, vec![]);
+        account_info!(program, program_id, vec![]);
+        account_info!(remainder_recipient, call.remainder_recipient, vec![]);
+        account_info!(system_program, solana_program::system_program::id(), vec![]);
+        account_info!(account, account_pk, vec![]);
+        account_info!(invalid, Pubkey::new_unique(), vec![]);
+
+        // Program is not whitelisted
+        let mut invalid_call = test_call(Pubkey::new_unique(), &[account_pk]);
+        invalid_call.remainder_recipient = call.remainder_recipient;
+        assert_matches!(
+            execute_withdraw_and_call(
+                &authority,
+                &invalid,
+                &remainder_recipient,
+                &system_program,
+                &[&account],
+                invalid_call
+            ),
+            Err(_)
+        );
+
+        // Invalid program
+        assert_matches!(
+            execute_withdraw_and_call(
+                &authority,
+                &invalid,
+                &remainder_recipient,
+                &system_program,
+                &[&account],
+                call.clone()
+            ),
+            Err(_)
+        );
+
+        // Invalid remainder recipient
+        assert_matches!(
+            execute_withdraw_and_call(
+                &authority,
+                &program,
+                &invalid,
+                &system_program,
+                &[&account],
+                call.clone()
+            ),
+            Err(_)
+        );
+
+        // Invalid call account
+        assert_matches!(
+            execute_withdraw_and_call(
+                &authority,
+                &program,
+                &remainder_recipient,
+                &system_program,
+                &[&invalid],
+                call.clone()
+            ),
+            Err(_)
+        );
+
+        // Authority of a different call
+        assert_matches!(
+            execute_withdraw_and_call(
+                &invalid,
+                &program,
+                &remainder_recipient,
+                &system_program,
+                &[&account],
+                call
+            ),
+            Err(_)
+        );
+    }
+    #[test]
+    fn test_refund_withdraw_and_call() {
+        set_test_slot(0);
+        let call = test_call(WITHDRAW_AND_CALL_PROGRAMS[0], &[Pubkey::new_unique()]);
+        let (authority_pk, _) = withdraw_and_call_authority(&call).unwrap();
+
+        account_info!(authority, authority_pk, vec![]);
+        account_info!(remainder_recipient, call.remainder_recipient, vec![]);
+        account_info!(system_program, solana_program::system_program::id(), vec![]);
+        account_info!(invalid, Pubkey::new_unique(), vec![]);
+
+        // Invalid remainder recipient
+        assert_matches!(
+            refund_withdraw_and_call(&authority, &invalid, &system_program, call.clone()),
+            Err(_)
+        );
+
+        // Refund slot is not reached
+        assert_matches!(
+            refund_withdraw_and_call(
+                &authority,
+                &remainder_recipient,
+                &system_program,
+                call.clone()
+            ),
+            Err(ProgramError::Custom(code)) if code == ElusivError::WithdrawAndCallIsNotTimedOut as u32
+        );
+
+        // Authority of a different call
+        assert_matches!(
+            refund_withdraw_and_call(&invalid, &remainder_recipient, &system_program, call),
+            Err(_)
+        );
+    }
+}
@@ -1,34 +1,52 @@
-use super::utils::{close_account, open_pda_account_with_offset};
 use crate::bytes::usize_as_u32_safe;
 use crate::commitment::{
     commitment_hash_computation_instructions, commitments_per_batch,
     compute_base_commitment_hash_partial, compute_commitment_hash_partial,
     BaseCommitmentHashComputation, MAX_HT_COMMITMENTS,
 };
+use crate::computation::{current_slot, PartialComputationDriver};
 use crate::error::ElusivError;
+use crate::events::{ElusivEvent, QueueAuditAction};
 use crate::fields::{fr_to_u256_le, is_element_scalar_field, u256_to_big_uint, u256_to_fr_skip_mr};
 use crate::macros::{guard, pda_account, BorshSerDeSized};
+use crate::oracle::token_price;
 use crate::processor::utils::{
-    transfer_lamports_from_pda_checked, transfer_token, transfer_token_from_pda,
+    declared_compute_unit_price, received_token_amount, transfer_lamports_from_pda_checked,
+    transfer_token_from_pda, transfer_token_from_pda_with_mint, transfer_token_with_mint,
     transfer_with_system_program, verify_program_token_account,
+    verify_program_token_account_with_program, verify_token_mint, DefaultInstructionsSysvar,
+    DefaultRentSysvar, InstructionsSysvar, RentSysvar,
 };
+use crate::processor::verify_registered_warden;
 use crate::state::commitment::{
     BaseCommitmentBufferAccount, BaseCommitmentHashingAccount, CommitmentHashingAccount,
 };
-use crate::state::governor::FeeCollectorAccount;
+use crate::state::config::ConfigAccount;
+use crate::state::event::EventSequenceAccount;
+use crate::state::governor::{FeeCollectorAccount, PoolAccount};
+use crate::state::poseidon::PoseidonParamsAccount;
+use crate::state::program_account::Lifecycle;
+use crate::state::stats::{current_epoch, EpochMetricsAccount, StatsAccount};
 use crate::state::storage::{StorageAccount, MT_COMMITMENT_COUNT};
+use crate::state::summary::StateSummaryAccount;
 use crate::state::{
     fee::FeeAccount,
     governor::GovernorAccount,
-    queue::{CommitmentQueue, CommitmentQueueAccount, Queue, RingQueue},
+    queue::{
+        batch_fee_version, CommitmentQueue, CommitmentQueueAccount, CommitmentSpillQueue,
+        CommitmentSpillQueueAccount, Queue, RingQueue,
+    },
 };
-use crate::token::{Token, TokenPrice};
+use crate::token::{Lamports, Token};
 use crate::types::{RawU256, U256};
 use ark_bn254::Fr;
 use ark_ff::BigInteger256;
 use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_computation::PartialComputation;
-use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+use elusiv_types::UnverifiedAccountInfo;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+};
 
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -42,6 +60,18 @@ pub struct BaseCommitmentHashRequest {
 
     /// The minimum allowed batching rate (since the fee is precomputed with the concrete batching rate)
     pub min_batching_rate: u32,
+
+    /// Part of `amount` that repays the fee-payer for fronting the computation fee (zero if the sender pays the fee upfront)
+    /// - the commitment is computed with `amount - fee_payer_repayment`
+    /// - the repayment is held by the pool until the base-commitment hash has been finalized
+    pub fee_payer_repayment: u64,
+
+    /// Opt-in minimum number of slots between the deposit and the insertion of the commitment into the MT (and therefore its first possible spend)
+    /// - raised to the [`GovernorAccount::required_anonymity_delay_slots`] for large deposits
+    pub anonymity_delay_slots: u64,
+
+    /// Whether the commitment is enqueued into the priority lane of the commitment queue (for the [`FeeAccount::priority_lane_surcharge`])
+    pub is_priority: bool,
 }
 
 #[derive(
@@ -51,6 +81,9 @@ pub struct CommitmentHashRequest {
     pub commitment: U256,
     pub fee_version: u32,
     pub min_batching_rate: u32,
+
+    /// The slot in which the request has been enqueued
+    pub enqueue_slot: u64,
 }
 
 /// poseidon(0, 0)
@@ -81,6 +114,12 @@ pub const ZERO_COMMITMENT_RAW: U256 = [
 ///
 /// Signatures of both `sender` and `fee_payer` are required.
 ///
+/// Existing shielded balances are topped-up with a deposit followed by a merge (a zero-amount send receiving the `merge_subvention`),
+/// whose join-split consumes the existing and the deposited commitment inside the proof (so frequent deposits don't fragment the balance).
+///
+/// For mints with the transfer-fee extension, the commitment's `amount` is the amount received by the pool (`amount` minus the epoch's transfer fee).
+/// Token-2022 mints are rejected, since no withdrawal path supports the Token-2022 program yet.
+///
 /// `sender`: wants to store the commitment (pays amount and fee).
 ///
 /// `fee_payer`:
@@ -102,23 +141,50 @@ pub fn store_base_commitment<'a>(
     token_usd_price_account: &AccountInfo,
 
     governor: &GovernorAccount,
+    fee_account: &FeeAccount,
     hashing_account: &AccountInfo<'a>,
     base_commitment_buffer: &mut BaseCommitmentBufferAccount,
     token_program: &AccountInfo<'a>,
+    mint_account: &AccountInfo<'a>,
     system_program: &AccountInfo<'a>,
+    event_sequence: &mut EventSequenceAccount,
+    stats: &mut StatsAccount,
+    epoch_metrics: &mut EpochMetricsAccount,
 
     hash_account_index: u32,
     hash_account_bump: u8,
     request: BaseCommitmentHashRequest,
 ) -> ProgramResult {
+    guard!(!governor.get_is_halted(), ElusivError::ProgramIsHalted);
+    guard!(
+        !governor.get_emergency_mode(),
+        ElusivError::EmergencyModeIsActive
+    );
+
     let token_id = request.token_id;
+    guard!(
+        governor.is_token_supported(token_id),
+        ElusivError::TokenIsNotSupported
+    );
     let amount = Token::new_checked(token_id, request.amount)?;
-    let price = TokenPrice::new(sol_usd_price_account, token_usd_price_account, token_id)?;
+    verify_token_mint(mint_account, token_program, token_id)?;
 
+    // Withdrawals, fee payouts and burns only use the SPL-token program, so Token-2022 funds could never leave the pool
     guard!(
-        is_element_scalar_field(u256_to_big_uint(&request.base_commitment.skip_mr())),
-        ElusivError::NonScalarValue
+        *token_program.key != spl_token_2022::ID,
+        ElusivError::UnsupportedToken
     );
+
+    // Token-2022 transfer fees are withheld at the pool, so only the received amount is hashed
+    let received_amount = received_token_amount(mint_account, amount)?;
+    let price = token_price(
+        governor,
+        sol_usd_price_account,
+        token_usd_price_account,
+        token_id,
+    )?;
+
+    verify_base_commitment(&request.base_commitment)?;
     guard!(
         is_element_scalar_field(u256_to_big_uint(&request.commitment.skip_mr())),
         ElusivError::NonScalarValue
@@ -126,12 +192,6 @@ pub fn store_base_commitment<'a>(
 
     // TODO: verify commitment-index in the next SDK version
 
-    // Zero-commitment cannot be inserted by user
-    guard!(
-        u256_to_fr_skip_mr(&request.base_commitment.reduce()) != ZERO_BASE_COMMITMENT,
-        ElusivError::InvalidInstructionData
-    );
-
     guard!(
         request.fee_version == governor.get_fee_version(),
         ElusivError::InvalidFeeVersion
@@ -141,7 +201,7 @@ pub fn store_base_commitment<'a>(
         ElusivError::InvalidBatchingRate
     );
 
-    let fee = governor.get_program_fee();
+    let fee = governor.program_fee_for_token(token_id);
     let subvention = fee
         .base_commitment_subvention
         .into_token(&price, token_id)?;
@@ -153,68 +213,233 @@ pub fn store_base_commitment<'a>(
         fee.base_commitment_network_fee.calc(amount.amount()),
     );
 
-    verify_program_token_account(pool, pool_account, token_id)?;
-    verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
+    // Priority-lane commitments additionally pay the surcharge as part of the network fee
+    let network_fee = if request.is_priority {
+        let surcharge = fee_account.get_priority_lane_surcharge();
+        guard!(surcharge > 0, ElusivError::FeatureNotAvailable);
+        (network_fee + Lamports(surcharge).into_token(&price, token_id)?)?
+    } else {
+        network_fee
+    };
 
-    // `sender` transfers `computation_fee_token` - `subvention` to `fee_payer` (token)
-    transfer_token(
-        sender,
-        sender_account,
-        fee_payer_account,
+    verify_program_token_account_with_program(pool, pool_account, token_program, token_id)?;
+    verify_program_token_account_with_program(
+        fee_collector,
+        fee_collector_account,
         token_program,
-        (computation_fee_token - subvention)?,
+        token_id,
     )?;
 
+    if request.fee_payer_repayment == 0 {
+        // `sender` transfers `computation_fee_token` - `subvention` to `fee_payer` (token)
+        transfer_token_with_mint(
+            sender,
+            sender_account,
+            fee_payer_account,
+            token_program,
+            mint_account,
+            (computation_fee_token - subvention)?,
+        )?;
+    } else {
+        // `fee_payer` fronts the computation fee and is repaid from `amount` once the base-commitment hash is finalized
+        guard!(
+            request.fee_payer_repayment >= (computation_fee_token - subvention)?.amount(),
+            ElusivError::InvalidFee
+        );
+        guard!(
+            request.fee_payer_repayment < received_amount.amount(),
+            ElusivError::InvalidAmount
+        );
+    }
+
+    // The repayment of the fee-payer is not part of the deposit
+    let deposited_amount = received_amount.amount() - request.fee_payer_repayment;
+    verify_epoch_deposit_cap(governor, epoch_metrics, token_id, deposited_amount)?;
+
     // `fee_payer` transfers `computation_fee` to `pool` (lamports)
     transfer_with_system_program(fee_payer, pool, system_program, computation_fee.0)?;
 
     // `sender` transfers `network_fee` to `fee_collector` (token)
-    transfer_token(
+    transfer_token_with_mint(
         sender,
         sender_account,
         fee_collector_account,
         token_program,
+        mint_account,
         network_fee,
     )?;
 
     // `sender` transfers `amount` to `pool` (token)
-    transfer_token(sender, sender_account, pool_account, token_program, amount)?;
+    transfer_token_with_mint(
+        sender,
+        sender_account,
+        pool_account,
+        token_program,
+        mint_account,
+        amount,
+    )?;
 
     // `fee_payer` rents `hashing_account`
-    open_pda_account_with_offset::<BaseCommitmentHashingAccount>(
-        &crate::id(),
+    BaseCommitmentHashingAccount::reopen(
         fee_payer,
         hashing_account,
-        hash_account_index,
+        None,
+        Some(hash_account_index),
         Some(hash_account_bump),
     )?;
 
     // `fee_collector` transfers `subvention` to `fee_payer` (token)
-    transfer_token_from_pda::<FeeCollectorAccount>(
+    transfer_token_from_pda_with_mint::<FeeCollectorAccount>(
         fee_collector,
         fee_collector_account,
         fee_payer_account,
         token_program,
+        mint_account,
         subvention,
         None,
         None,
     )?;
 
+    {
+        // Only the network-fee received after transfer fees is held by `fee_collector`
+        let received_network_fee = received_token_amount(mint_account, network_fee)?;
+
+        pda_account!(mut fee_collector, FeeCollectorAccount, fee_collector);
+        fee_collector.add_network_fee(request.fee_version, token_id, received_network_fee.amount());
+        fee_collector.add_subvention(request.fee_version, token_id, subvention.amount());
+    }
+
     // Buffer duplicate check and insertion
     base_commitment_buffer.try_insert(&request.base_commitment.skip_mr())?;
 
+    stats.add_deposit(token_id, deposited_amount);
+    epoch_metrics.add_deposit(current_epoch()?, token_id, deposited_amount);
+
+    let event = ElusivEvent::Deposit {
+        base_commitment: request.base_commitment.reduce(),
+        commitment: request.commitment.reduce(),
+        amount: deposited_amount,
+        token_id,
+        fee_version: request.fee_version,
+    };
+
+    // Large deposits are delayed by at least the required anonymity-delay
+    let anonymity_delay_slots =
+        governor.anonymity_delay_slots(token_id, deposited_amount, request.anonymity_delay_slots);
+
     // `hashing_account` setup
     pda_account!(
         mut hashing_account,
         BaseCommitmentHashingAccount,
         hashing_account
     );
-    hashing_account.setup(request, fee_payer.key.to_bytes())
+    hashing_account.setup(
+        BaseCommitmentHashRequest {
+            amount: received_amount.amount(),
+            anonymity_delay_slots,
+            ..request
+        },
+        fee_payer.key.to_bytes(),
+        current_slot()?,
+    )?;
+    hashing_account.set_fee_payer_account(&fee_payer_account.key.to_bytes());
+
+    event_sequence.emit(&event)
+}
+
+/// Verifies that a user-supplied `base_commitment` can be hashed
+pub(crate) fn verify_base_commitment(base_commitment: &RawU256) -> ProgramResult {
+    guard!(
+        is_element_scalar_field(u256_to_big_uint(&base_commitment.skip_mr())),
+        ElusivError::NonScalarValue
+    );
+
+    // Zero-commitment cannot be inserted by user
+    guard!(
+        u256_to_fr_skip_mr(&base_commitment.reduce()) != ZERO_BASE_COMMITMENT,
+        ElusivError::InvalidInstructionData
+    );
+
+    Ok(())
+}
+
+/// Verifies that a deposit of `amount` does not exceed the current epoch's deposit cap of `token_id`
+pub(crate) fn verify_epoch_deposit_cap(
+    governor: &GovernorAccount,
+    epoch_metrics: &EpochMetricsAccount,
+    token_id: u16,
+    amount: u64,
+) -> ProgramResult {
+    let epoch_deposit_volume = epoch_metrics.deposit_volume(current_epoch()?, token_id);
+    guard!(
+        !governor.exceeds_epoch_deposit_cap(token_id, epoch_deposit_volume, amount),
+        ElusivError::EpochDepositCapIsExceeded
+    );
+
+    Ok(())
+}
+
+/// Starts the hashing of `base_commitment` for `amount` lamports that are already held by the `pool`
+/// - the computation fees are deducted from `amount` and remain in the `pool` (they are paid out during the commitment computations)
+/// - `fee_payer` rents the `hashing_account` and continues with [`compute_base_commitment_hash`]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn start_lamports_base_commitment_hash<'a>(
+    fee_payer: &AccountInfo<'a>,
+    hashing_account: &AccountInfo<'a>,
+    governor: &GovernorAccount,
+    base_commitment_buffer: &mut BaseCommitmentBufferAccount,
+    base_commitment: U256,
+    amount: u64,
+    hash_account_index: u32,
+    hash_account_bump: u8,
+) -> ProgramResult {
+    let fee = governor.get_program_fee();
+    let min_batching_rate = governor.get_commitment_batching_rate();
+    let computation_fee = (fee.base_commitment_hash_computation_fee()
+        + fee.commitment_hash_computation_fee(min_batching_rate))?;
+    let amount = amount
+        .checked_sub(computation_fee.0)
+        .ok_or(ElusivError::InvalidAmount)?;
+
+    // `fee_payer` rents `hashing_account`
+    BaseCommitmentHashingAccount::reopen(
+        fee_payer,
+        hashing_account,
+        None,
+        Some(hash_account_index),
+        Some(hash_account_bump),
+    )?;
+
+    // Buffer duplicate check and insertion
+    base_commitment_buffer.try_insert(&base_commitment)?;
+
+    pda_account!(
+        mut hashing_account,
+        BaseCommitmentHashingAccount,
+        hashing_account
+    );
+    hashing_account.setup(
+        BaseCommitmentHashRequest {
+            base_commitment: RawU256::new(base_commitment),
+            commitment_index: 0,
+            amount,
+            token_id: 0,
+            commitment: RawU256::new([0; 32]),
+            fee_version: governor.get_fee_version(),
+            min_batching_rate,
+            fee_payer_repayment: 0,
+            anonymity_delay_slots: governor.anonymity_delay_slots(0, amount, 0),
+            is_priority: false,
+        },
+        fee_payer.key.to_bytes(),
+        current_slot()?,
+    )
 }
 
 // TODO: add functionality for a Warden to compute other uncomputed base-commitments (initiated by other Wardens)
 pub fn compute_base_commitment_hash(
     hashing_account: &mut BaseCommitmentHashingAccount,
+    poseidon_params: &PoseidonParamsAccount,
 
     _hash_account_index: u32,
 ) -> ProgramResult {
@@ -222,19 +447,29 @@ pub fn compute_base_commitment_hash(
         hashing_account.get_is_active(),
         ElusivError::ComputationIsNotYetStarted
     );
-    compute_base_commitment_hash_partial(hashing_account)
+    guard!(
+        poseidon_params.get_is_frozen(),
+        ElusivError::PoseidonParamsAreNotFrozen
+    );
+    compute_base_commitment_hash_partial(hashing_account, poseidon_params, current_slot()?)
 }
 
 #[allow(clippy::too_many_arguments)]
 pub fn finalize_base_commitment_hash<'a>(
     original_fee_payer: &AccountInfo<'a>,
+    original_fee_payer_account: &AccountInfo<'a>,
     pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
     fee: &FeeAccount,
     hashing_account_info: &AccountInfo<'a>,
     commitment_hash_queue: &mut CommitmentQueueAccount,
+    commitment_spill_queue: &mut CommitmentSpillQueueAccount,
+    config: &ConfigAccount,
+    token_program: &AccountInfo<'a>,
 
     _hash_account_index: u32,
     fee_version: u32,
+    token_id: u16,
 ) -> ProgramResult {
     pda_account!(
         mut hashing_account,
@@ -245,6 +480,12 @@ pub fn finalize_base_commitment_hash<'a>(
         hashing_account.get_fee_version() == fee_version,
         ElusivError::InvalidFeeVersion
     );
+
+    // The commitment is enqueued into the pool of its token
+    guard!(
+        hashing_account.get_token_id() == token_id,
+        ElusivError::InvalidAccount
+    );
     guard!(
         hashing_account.get_is_active(),
         ElusivError::ComputationIsNotYetStarted
@@ -253,31 +494,95 @@ pub fn finalize_base_commitment_hash<'a>(
         hashing_account.get_fee_payer() == original_fee_payer.key.to_bytes(),
         ElusivError::InvalidAccount
     );
+    hashing_account.finish_computation(BaseCommitmentHashComputation::IX_COUNT)?;
     guard!(
-        (hashing_account.get_instruction() as usize) == BaseCommitmentHashComputation::IX_COUNT,
-        ElusivError::ComputationIsNotYetFinished
+        current_slot()? >= hashing_account.get_min_finalization_slot(),
+        ElusivError::AnonymityDelayIsNotElapsed
     );
 
     // `pool` transfers `base_commitment_hash_fee` to `original_fee_payer` (lamports)
     transfer_lamports_from_pda_checked(
         pool,
         original_fee_payer,
-        fee.get_program_fee()
+        fee.program_fee_for_token(token_id)
             .base_commitment_hash_computation_fee()
             .0,
     )?;
 
+    let fee_payer_repayment = hashing_account.get_fee_payer_repayment();
+    if fee_payer_repayment > 0 {
+        guard!(
+            hashing_account.get_fee_payer_account() == original_fee_payer_account.key.to_bytes(),
+            ElusivError::InvalidAccount
+        );
+
+        verify_program_token_account(pool, pool_account, token_id)?;
+
+        // `pool` transfers `fee_payer_repayment` to `original_fee_payer` (token)
+        transfer_token_from_pda::<PoolAccount>(
+            pool,
+            pool_account,
+            original_fee_payer_account,
+            token_program,
+            Token::new(token_id, fee_payer_repayment),
+            None,
+            None,
+        )?;
+    }
+
     let commitment = hashing_account.get_state().result();
     let mut commitment_queue = CommitmentQueue::new(commitment_hash_queue);
-    commitment_queue.enqueue(CommitmentHashRequest {
+    let request = CommitmentHashRequest {
         commitment: fr_to_u256_le(&commitment),
         fee_version,
         min_batching_rate: hashing_account.get_min_batching_rate(),
-    })?;
+        enqueue_slot: current_slot()?,
+    };
+    if hashing_account.get_is_priority() {
+        commitment_queue.enqueue_priority(request)?;
+    } else {
+        // Commitments are spilled while the queue is filled up to its configured capacity (or while older commitments are spilled, to keep the FIFO order)
+        let mut spill_queue = CommitmentSpillQueue::new(commitment_spill_queue);
+        if !spill_queue.is_empty()
+            || commitment_queue.len() >= config.effective_commitment_queue_capacity()
+        {
+            spill_queue.enqueue(request)?;
+        } else {
+            commitment_queue.enqueue(request)?;
+        }
+    }
 
     // Close hashing account
     hashing_account.set_is_active(&false);
-    close_account(original_fee_payer, hashing_account_info)
+    BaseCommitmentHashingAccount::close(original_fee_payer, hashing_account_info)
+}
+
+/// Moves the commitments of the [`CommitmentSpillQueueAccount`] of `token_id` into its commitment queue (as many as fit below its configured capacity, in FIFO order)
+pub fn drain_commitment_spill_queue(
+    commitment_hash_queue: &mut CommitmentQueueAccount,
+    commitment_spill_queue: &mut CommitmentSpillQueueAccount,
+    config: &ConfigAccount,
+
+    _token_id: u16,
+) -> ProgramResult {
+    let mut commitment_queue = CommitmentQueue::new(commitment_hash_queue);
+    let mut spill_queue = CommitmentSpillQueue::new(commitment_spill_queue);
+    guard!(!spill_queue.is_empty(), ElusivError::QueueIsEmpty);
+
+    let empty_slots = std::cmp::min(
+        commitment_queue.empty_slots(),
+        config
+            .effective_commitment_queue_capacity()
+            .saturating_sub(commitment_queue.len()),
+    );
+    guard!(empty_slots > 0, ElusivError::QueueIsFull);
+
+    let count = std::cmp::min(spill_queue.len(), empty_slots);
+    for _ in 0..count {
+        commitment_queue.enqueue(spill_queue.dequeue_first()?)?;
+    }
+
+    Ok(())
 }
 
 /// Places the hash siblings into the hashing account
@@ -286,6 +591,7 @@ pub fn init_commitment_hash_setup(
     storage_account: &StorageAccount,
 
     insertion_can_fail: bool,
+    _token_id: u16,
 ) -> ProgramResult {
     match init_commitment_hash_setup_inner(hashing_account, storage_account) {
         Ok(()) => Ok(()),
@@ -312,22 +618,25 @@ fn init_commitment_hash_setup_inner(
     let ordering = storage_account.get_next_commitment_ptr();
     let siblings = storage_account.get_mt_opening(ordering as usize)?;
 
-    hashing_account.setup(ordering, &siblings)
+    hashing_account.setup(ordering, &siblings, current_slot()?)
 }
 
 /// Places the next batch from the commitment queue in the [`CommitmentHashingAccount`]
+/// - if `insertion_can_fail`, the entries of the next batch are reported as skipped instead of failing
 pub fn init_commitment_hash(
     queue: &mut CommitmentQueueAccount,
     hashing_account: &mut CommitmentHashingAccount,
+    event_sequence: &mut EventSequenceAccount,
 
     insertion_can_fail: bool,
+    token_id: u16,
 ) -> ProgramResult {
     match init_commitment_hash_inner(queue, hashing_account) {
         Ok(()) => Ok(()),
         Err(e) => {
             if insertion_can_fail {
                 solana_program::msg!("Instruction failed: {:?}", e);
-                Ok(())
+                emit_skipped_commitments(queue, event_sequence, token_id, &e)
             } else {
                 Err(e)
             }
@@ -335,8 +644,41 @@ pub fn init_commitment_hash(
     }
 }
 
+/// Emits a [`ElusivEvent::CommitmentQueueAudit`] for each entry of the next batch that could not be hashed
+fn emit_skipped_commitments(
+    queue_account: &mut CommitmentQueueAccount,
+    event_sequence: &mut EventSequenceAccount,
+    token_id: u16,
+    error: &ProgramError,
+) -> ProgramResult {
+    let reason = match error {
+        ProgramError::Custom(reason) => *reason,
+        _ => return Ok(()),
+    };
+
+    // The queue is not accessed while the hashing account is busy
+    if reason == ElusivError::ComputationIsNotYetFinished as u32 {
+        return Ok(());
+    }
+
+    let queue = CommitmentQueue::new(queue_account);
+    let count = std::cmp::min(queue.len() as usize, MAX_HT_COMMITMENTS);
+    for i in 0..count {
+        let request = queue.view(i)?;
+        event_sequence.emit(&ElusivEvent::CommitmentQueueAudit {
+            token_id,
+            commitment: request.commitment,
+            enqueue_slot: request.enqueue_slot,
+            action: QueueAuditAction::Skipped,
+            reason,
+        })?;
+    }
+
+    Ok(())
+}
+
 fn init_commitment_hash_inner(
-    queue: &mut CommitmentQueueAccount,
+    queue_account: &mut CommitmentQueueAccount,
     hashing_account: &mut CommitmentHashingAccount,
 ) -> ProgramResult {
     guard!(
@@ -348,19 +690,25 @@ fn init_commitment_hash_inner(
         ElusivError::ComputationIsNotYetFinished
     );
 
-    let mut queue = CommitmentQueue::new(queue);
+    let mut queue = CommitmentQueue::new(queue_account);
     let (batch, batching_rate) = queue.next_batch()?;
-    queue.remove(usize_as_u32_safe(batch.len()))?;
 
-    // The fee/batch-upgrader logic has to guarantee that there are no lower fees in a batch
-    let fee_version = batch.first().unwrap().fee_version;
-
-    // Check for room for the commitment batch
+    // Check for room for the commitment batch (before dequeuing, so that no commitments are lost)
     guard!(
         hashing_account.get_ordering() as usize + batch.len() <= MT_COMMITMENT_COUNT,
         ElusivError::NoRoomForCommitment
     );
 
+    queue.remove_batch(usize_as_u32_safe(batch.len()))?;
+
+    let slot = current_slot()?;
+    for request in &batch {
+        queue_account.record_wait_slots(slot.saturating_sub(request.enqueue_slot));
+    }
+
+    // Requests queued before a fee-version change are hashed with the (honored) fee-version they paid for
+    let fee_version = batch_fee_version(&batch).ok_or(ElusivError::QueueIsEmpty)?;
+
     let mut commitments = [[0; 32]; MAX_HT_COMMITMENTS];
     for i in 0..batch.len() {
         commitments[i] = batch[i].commitment;
@@ -369,15 +717,26 @@ fn init_commitment_hash_inner(
     hashing_account.reset(batching_rate, fee_version, &commitments)
 }
 
+/// Performs a partial commitment hash computation and compensates `fee_payer` for the transaction
+/// - priority fees are compensated from the `fee_collector` (see [`compensate_priority_fee`])
+#[allow(clippy::too_many_arguments)]
 pub fn compute_commitment_hash<'a>(
     fee_payer: &AccountInfo<'a>,
     fee: &FeeAccount,
     pool: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
     hashing_account: &mut CommitmentHashingAccount,
+    poseidon_params: &PoseidonParamsAccount,
+    governor: &GovernorAccount,
+    warden_account: UnverifiedAccountInfo,
+    instructions_account: &AccountInfo,
 
     fee_version: u32,
     _nonce: u32,
+    token_id: u16,
 ) -> ProgramResult {
+    verify_registered_warden(governor, fee_payer, warden_account)?;
+
     guard!(
         hashing_account.get_is_active(),
         ElusivError::ComputationIsNotYetStarted
@@ -386,20 +745,70 @@ pub fn compute_commitment_hash<'a>(
         hashing_account.get_fee_version() == fee_version,
         ElusivError::InvalidFeeVersion
     );
+    guard!(
+        poseidon_params.get_is_frozen(),
+        ElusivError::PoseidonParamsAreNotFrozen
+    );
 
-    compute_commitment_hash_partial(hashing_account)?;
+    compute_commitment_hash_partial(hashing_account, poseidon_params, current_slot()?)?;
 
     transfer_lamports_from_pda_checked(
         pool,
         fee_payer,
-        fee.get_program_fee().hash_tx_compensation().0,
+        fee.program_fee_for_token(token_id).hash_tx_compensation().0,
+    )?;
+
+    if fee.get_lamports_per_cu() == 0 {
+        return Ok(());
+    }
+    compensate_priority_fee(
+        fee_payer,
+        fee,
+        fee_collector,
+        &DefaultInstructionsSysvar(instructions_account),
+        &DefaultRentSysvar,
+        fee_version,
     )
 }
 
+/// Transfers the [`FeeAccount::priority_fee_compensation`] for the compute-unit price declared by the current transaction from the `fee_collector` to `fee_payer`
+/// - the compensation is limited to the lamports the `fee_collector` holds above its rent-exemption, so that computations are never blocked
+/// - the compensation is accounted as a subvention of `fee_version`
+fn compensate_priority_fee<'a>(
+    fee_payer: &AccountInfo<'a>,
+    fee: &FeeAccount,
+    fee_collector: &AccountInfo<'a>,
+    instructions_sysvar: &impl InstructionsSysvar,
+    rent: &impl RentSysvar,
+    fee_version: u32,
+) -> ProgramResult {
+    let compensation =
+        fee.priority_fee_compensation(declared_compute_unit_price(instructions_sysvar)?);
+    let available = fee_collector
+        .lamports()
+        .saturating_sub(rent.minimum_balance(fee_collector.data_len())?);
+    let amount = std::cmp::min(compensation.0, available);
+    if amount == 0 {
+        return Ok(());
+    }
+
+    transfer_lamports_from_pda_checked(fee_collector, fee_payer, amount)?;
+
+    pda_account!(mut fee_collector, FeeCollectorAccount, fee_collector);
+    fee_collector.add_subvention(fee_version, 0, amount);
+
+    Ok(())
+}
+
 /// Requires `batching_rate + 1` calls
 pub fn finalize_commitment_hash(
     hashing_account: &mut CommitmentHashingAccount,
     storage_account: &mut StorageAccount,
+    state_summary: &mut StateSummaryAccount,
+    stats: &mut StatsAccount,
+    event_sequence: &mut EventSequenceAccount,
+
+    token_id: u16,
 ) -> ProgramResult {
     guard!(
         hashing_account.get_is_active(),
@@ -413,11 +822,10 @@ pub fn finalize_commitment_hash(
         ElusivError::ComputationIsAlreadyFinished
     );
 
-    let instruction = hashing_account.get_instruction();
     let instructions =
         commitment_hash_computation_instructions(hashing_account.get_batching_rate());
     guard!(
-        (instruction as usize) >= instructions.len(),
+        hashing_account.is_computation_finished(instructions.len()),
         ElusivError::ComputationIsAlreadyFinished
     );
 
@@ -427,11 +835,33 @@ pub fn finalize_commitment_hash(
         ElusivError::NoRoomForCommitment
     );
 
+    let previous_root = storage_account.get_root()?;
     hashing_account.update_mt(storage_account, finalization_ix);
     hashing_account.set_finalization_ix(&(finalization_ix + 1));
     if finalization_ix == batching_rate {
         hashing_account.set_is_active(&false);
         hashing_account.set_setup(&false);
+        state_summary.update_batch(storage_account)?;
+        stats.add_commitments(commitments_per_batch(batching_rate) as u64);
+
+        let mt_index = storage_account.get_trees_count();
+        let ordering = hashing_account.get_ordering();
+        for i in 0..commitments_per_batch(batching_rate) {
+            event_sequence.emit(&ElusivEvent::CommitmentInsertion {
+                token_id,
+                mt_index,
+                leaf_index: ordering + i as u32,
+                commitment: hashing_account.get_hash_tree(i),
+            })?;
+        }
+
+        event_sequence.emit(&ElusivEvent::RootUpdate {
+            mt_index,
+            new_root: storage_account.get_root()?,
+            previous_root,
+            batch_size: commitments_per_batch(batching_rate) as u32,
+            slot: current_slot()?,
+        })?;
     }
     Ok(())
 }
@@ -447,14 +877,20 @@ mod tests {
         account_info, parent_account, program_token_account_info, pyth_price_account_info,
         test_account_info, test_pda_account_info, zero_program_account,
     };
+    use crate::processor::utils::{TestInstructionsSysvar, COMPUTE_BUDGET_PROGRAM_ID};
     use crate::state::governor::PoolAccount;
-    use crate::state::program_account::{PDAAccount, SizedAccount};
+    use crate::state::program_account::{PDAAccount, ProgramAccount, SizedAccount};
     use crate::state::storage::{EMPTY_TREE, MT_HEIGHT};
-    use crate::token::{lamports_token, usdc_token, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID};
+    use crate::syscall_stubs::set_test_slot;
+    use crate::token::{
+        elusiv_token, lamports_token, usdc_token, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID,
+    };
     use ark_ff::Zero;
     use assert_matches::assert_matches;
     use elusiv_types::tokens::Price;
+    use solana_program::instruction::Instruction;
     use solana_program::native_token::LAMPORTS_PER_SOL;
+    use solana_program::program_pack::Pack;
     use solana_program::pubkey::Pubkey;
     use solana_program::system_program;
     use std::str::FromStr;
@@ -486,12 +922,17 @@ mod tests {
 
     #[test]
     fn test_store_base_commitment_lamports() {
+        set_test_slot(0);
         zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(fee, FeeAccount);
         zero_program_account!(mut buffer, BaseCommitmentBufferAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut epoch_metrics, EpochMetricsAccount);
         test_account_info!(sender, 0);
         test_account_info!(fee_payer, 0);
         test_account_info!(pool, 0);
-        test_account_info!(fee_collector, 0);
+        test_account_info!(fee_collector, FeeCollectorAccount::SIZE);
         test_account_info!(any, 0);
         account_info!(sys, system_program::id(), vec![]);
         account_info!(spl, spl_token::id(), vec![]);
@@ -509,10 +950,13 @@ mod tests {
             base_commitment: RawU256::new(u256_from_str_skip_mr("1")),
             commitment_index: 123,
             amount: LAMPORTS_PER_SOL,
-            token_id: LAMPORTS_TOKEN_ID,
+            token_id: 0,
             commitment: RawU256::new(u256_from_str_skip_mr("1")),
             fee_version: 1,
             min_batching_rate: 4,
+            fee_payer_repayment: 0,
+            anonymity_delay_slots: 0,
+            is_priority: false,
         };
 
         // Amount too low
@@ -546,6 +990,10 @@ mod tests {
         requests.push(request.clone());
         requests.last_mut().unwrap().min_batching_rate = 0;
 
+        // Priority lane without a surcharge
+        requests.push(request.clone());
+        requests.last_mut().unwrap().is_priority = true;
+
         for request in requests {
             assert_matches!(
                 store_base_commitment(
@@ -560,10 +1008,15 @@ mod tests {
                     &any,
                     &any,
                     &governor,
+                    &fee,
                     &hashing_acc,
                     &mut buffer,
                     &sys,
                     &sys,
+                    &sys,
+                    &mut event_sequence,
+                    &mut stats,
+                    &mut epoch_metrics,
                     0,
                     bump,
                     request
@@ -572,6 +1025,70 @@ mod tests {
             );
         }
 
+        // Halted program
+        governor.set_is_halted(&true);
+        assert_matches!(
+            store_base_commitment(
+                &sender,
+                &sender,
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &fee,
+                &hashing_acc,
+                &mut buffer,
+                &sys,
+                &sys,
+                &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                0,
+                bump,
+                request.clone()
+            ),
+            Err(_)
+        );
+        governor.set_is_halted(&false);
+
+        // Epoch deposit cap exceeded
+        governor.set_epoch_deposit_caps(0, &(LAMPORTS_PER_SOL - 1));
+        assert_matches!(
+            store_base_commitment(
+                &sender,
+                &sender,
+                &fee_payer,
+                &fee_payer,
+                &pool,
+                &pool,
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                &governor,
+                &fee,
+                &hashing_acc,
+                &mut buffer,
+                &sys,
+                &sys,
+                &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                0,
+                bump,
+                request.clone()
+            ),
+            Err(_)
+        );
+        governor.set_epoch_deposit_caps(0, &0);
+
         // Invalid pool_account
         assert_matches!(
             store_base_commitment(
@@ -586,10 +1103,15 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &sys,
                 &sys,
+                &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request.clone()
@@ -611,10 +1133,15 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &sys,
                 &sys,
+                &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request.clone()
@@ -636,10 +1163,15 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &spl,
                 &sys,
+                &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request.clone()
@@ -661,10 +1193,15 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &sys,
                 &sys,
+                &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 1,
                 bump,
                 request.clone()
@@ -686,10 +1223,15 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &sys,
                 &sys,
+                &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 0,
                 request.clone()
@@ -710,16 +1252,29 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &sys,
                 &sys,
+                &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request.clone()
             ),
             Ok(())
         );
+        assert_eq!(event_sequence.get_sequence(), 1);
+        assert_eq!(stats.get_deposit_count(), 1);
+        assert_eq!(epoch_metrics.epoch_metrics(0).unwrap().deposit_count, 1);
+        {
+            let mut data = fee_collector.data.borrow_mut();
+            let fee_collector = <FeeCollectorAccount as ProgramAccount>::new(&mut data).unwrap();
+            assert!(fee_collector.fee_revenue(1).is_some());
+        }
 
         // Immediate uplicate insertion will fail
         assert_matches!(
@@ -735,10 +1290,15 @@ mod tests {
                 &any,
                 &any,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &sys,
                 &sys,
+                &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request
@@ -749,8 +1309,13 @@ mod tests {
 
     #[test]
     fn test_store_base_commitment_token() {
-        zero_program_account!(governor, GovernorAccount);
+        set_test_slot(0);
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(fee, FeeAccount);
         zero_program_account!(mut buffer, BaseCommitmentBufferAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut epoch_metrics, EpochMetricsAccount);
         test_account_info!(sender);
         test_account_info!(fee_payer);
         test_account_info!(sender_token, 0, spl_token::id());
@@ -761,6 +1326,20 @@ mod tests {
         program_token_account_info!(fee_c_token, FeeCollectorAccount, USDC_TOKEN_ID);
         account_info!(sys, system_program::id(), vec![]);
         account_info!(spl, spl_token::id(), vec![]);
+        let mut mint_data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut mint_data);
+        account_info!(
+            mint,
+            elusiv_token(USDC_TOKEN_ID).unwrap().mint,
+            mint_data,
+            spl_token::id(),
+            false
+        );
         let (hasing_account_pubkey, bump) = BaseCommitmentHashingAccount::find(Some(0));
         account_info!(
             hashing_acc,
@@ -789,8 +1368,43 @@ mod tests {
             commitment: RawU256::new(u256_from_str_skip_mr("1")),
             fee_version: 0,
             min_batching_rate: 0,
+            fee_payer_repayment: 0,
+            anonymity_delay_slots: 0,
+            is_priority: false,
         };
 
+        // Token is not supported
+        assert_matches!(
+            store_base_commitment(
+                &sender,
+                &sender_token,
+                &fee_payer,
+                &fee_payer_token,
+                &pool,
+                &pool_token,
+                &fee_c,
+                &fee_c_token,
+                &sol,
+                &usdc,
+                &governor,
+                &fee,
+                &hashing_acc,
+                &mut buffer,
+                &spl,
+                &mint,
+                &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                0,
+                bump,
+                request.clone()
+            ),
+            Err(_)
+        );
+
+        governor.set_supported_tokens(USDC_TOKEN_ID as usize, &true);
+
         // Amount too low
         let mut requests = vec![request.clone()];
         requests.last_mut().unwrap().amount = usdc_token().min - 1;
@@ -813,10 +1427,15 @@ mod tests {
                     &sol,
                     &usdc,
                     &governor,
+                    &fee,
                     &hashing_acc,
                     &mut buffer,
                     &spl,
+                    &mint,
                     &sys,
+                    &mut event_sequence,
+                    &mut stats,
+                    &mut epoch_metrics,
                     0,
                     bump,
                     request
@@ -825,6 +1444,44 @@ mod tests {
             );
         }
 
+        // Token-2022 mint
+        account_info!(spl_2022, spl_token_2022::id(), vec![]);
+        account_info!(
+            mint_2022,
+            elusiv_token(USDC_TOKEN_ID).unwrap().mint,
+            mint.data.borrow().to_vec(),
+            spl_token_2022::id(),
+            false
+        );
+        assert_matches!(
+            store_base_commitment(
+                &sender,
+                &sender_token,
+                &fee_payer,
+                &fee_payer_token,
+                &pool,
+                &pool_token,
+                &fee_c,
+                &fee_c_token,
+                &sol,
+                &usdc,
+                &governor,
+                &fee,
+                &hashing_acc,
+                &mut buffer,
+                &spl_2022,
+                &mint_2022,
+                &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                0,
+                bump,
+                request.clone()
+            ),
+            Err(_)
+        );
+
         // Invalid pool_account
         assert_matches!(
             store_base_commitment(
@@ -839,10 +1496,15 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &spl,
+                &mint,
                 &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request.clone()
@@ -864,10 +1526,15 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &spl,
+                &mint,
                 &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request.clone()
@@ -889,10 +1556,15 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &sys,
+                &mint,
                 &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request.clone()
@@ -914,10 +1586,15 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &spl,
+                &mint,
                 &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 1,
                 bump,
                 request.clone()
@@ -939,10 +1616,15 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &spl,
+                &mint,
                 &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request.clone()
@@ -964,10 +1646,15 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &spl,
+                &mint,
                 &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request.clone()
@@ -989,10 +1676,15 @@ mod tests {
                 &usdc,
                 &usdc,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &spl,
+                &mint,
                 &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request.clone()
@@ -1014,10 +1706,15 @@ mod tests {
                 &sol,
                 &sol,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &spl,
+                &mint,
                 &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request.clone()
@@ -1038,16 +1735,24 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &spl,
+                &mint,
                 &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request.clone()
             ),
             Ok(())
         );
+        assert_eq!(event_sequence.get_sequence(), 1);
+        assert_eq!(stats.get_deposit_count(), 1);
+        assert_eq!(epoch_metrics.epoch_metrics(0).unwrap().deposit_count, 1);
 
         // Immediate uplicate insertion will fail
         assert_matches!(
@@ -1063,10 +1768,15 @@ mod tests {
                 &sol,
                 &usdc,
                 &governor,
+                &fee,
                 &hashing_acc,
                 &mut buffer,
                 &spl,
+                &mint,
                 &sys,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
                 0,
                 bump,
                 request
@@ -1077,26 +1787,36 @@ mod tests {
 
     #[test]
     fn test_compute_base_commitment_hash() {
+        set_test_slot(0);
         zero_program_account!(mut hashing_account, BaseCommitmentHashingAccount);
+        zero_program_account!(mut poseidon_params, PoseidonParamsAccount);
 
         // Inactive
         assert_matches!(
-            compute_base_commitment_hash(&mut hashing_account, 0),
+            compute_base_commitment_hash(&mut hashing_account, &poseidon_params, 0),
             Err(_)
         );
 
         hashing_account.set_is_active(&true);
 
+        // Poseidon parameters are not frozen
+        assert_matches!(
+            compute_base_commitment_hash(&mut hashing_account, &poseidon_params, 0),
+            Err(_)
+        );
+
+        poseidon_params.load_default_params();
+
         for _ in 0..BaseCommitmentHashComputation::IX_COUNT {
             assert_matches!(
-                compute_base_commitment_hash(&mut hashing_account, 0),
+                compute_base_commitment_hash(&mut hashing_account, &poseidon_params, 0),
                 Ok(())
             );
         }
 
         // Additional computations will fail
         assert_matches!(
-            compute_base_commitment_hash(&mut hashing_account, 0),
+            compute_base_commitment_hash(&mut hashing_account, &poseidon_params, 0),
             Err(_)
         );
         assert_eq!(
@@ -1110,6 +1830,7 @@ mod tests {
 
     #[test]
     fn test_finalize_base_commitment_hash() -> ProgramResult {
+        set_test_slot(0);
         account_info!(fee_payer, Pubkey::new_unique(), vec![0]);
         account_info!(
             h_account,
@@ -1117,7 +1838,9 @@ mod tests {
             vec![0; BaseCommitmentHashingAccount::SIZE]
         );
         zero_program_account!(mut q, CommitmentQueueAccount);
+        zero_program_account!(mut spill, CommitmentSpillQueueAccount);
         zero_program_account!(fee, FeeAccount);
+        zero_program_account!(config, ConfigAccount);
         test_account_info!(pool, 0);
 
         // Inactive hashing account
@@ -1127,7 +1850,10 @@ mod tests {
             h.set_fee_payer(&fee_payer.key.to_bytes());
         }
         assert_matches!(
-            finalize_base_commitment_hash(&fee_payer, &pool, &fee, &h_account, &mut q, 0, 0),
+            finalize_base_commitment_hash(
+                &fee_payer, &fee_payer, &pool, &pool, &fee, &h_account, &mut q, &mut spill,
+                &config, &pool, 0, 0, 0
+            ),
             Err(_)
         );
 
@@ -1138,7 +1864,10 @@ mod tests {
             h.set_fee_payer(&[0; 32]);
         }
         assert_matches!(
-            finalize_base_commitment_hash(&fee_payer, &pool, &fee, &h_account, &mut q, 0, 0),
+            finalize_base_commitment_hash(
+                &fee_payer, &fee_payer, &pool, &pool, &fee, &h_account, &mut q, &mut spill,
+                &config, &pool, 0, 0, 0
+            ),
             Err(_)
         );
 
@@ -1149,53 +1878,188 @@ mod tests {
             h.set_fee_payer(&fee_payer.key.to_bytes());
         }
         assert_matches!(
-            finalize_base_commitment_hash(&fee_payer, &pool, &fee, &h_account, &mut q, 0, 0),
+            finalize_base_commitment_hash(
+                &fee_payer, &fee_payer, &pool, &pool, &fee, &h_account, &mut q, &mut spill,
+                &config, &pool, 0, 0, 0
+            ),
             Err(_)
         );
 
         // Invalid fee version
         assert_matches!(
-            finalize_base_commitment_hash(&fee_payer, &pool, &fee, &h_account, &mut q, 0, 1),
+            finalize_base_commitment_hash(
+                &fee_payer, &fee_payer, &pool, &pool, &fee, &h_account, &mut q, &mut spill,
+                &config, &pool, 0, 1, 0
+            ),
             Err(_)
         );
 
-        // Commitment queue is full
+        // Commitment queue and spill queue are full
+        let request = CommitmentHashRequest {
+            commitment: [0; 32],
+            min_batching_rate: 0,
+            enqueue_slot: 0,
+            fee_version: 0,
+        };
         {
             pda_account!(mut h, BaseCommitmentHashingAccount, h_account);
             h.set_instruction(&(BaseCommitmentHashComputation::IX_COUNT as u32));
 
             let mut q = CommitmentQueue::new(&mut q);
             for _ in 0..CommitmentQueue::CAPACITY {
-                q.enqueue(CommitmentHashRequest {
-                    commitment: [0; 32],
-                    min_batching_rate: 0,
-                    fee_version: 0,
-                })
-                .unwrap();
+                q.enqueue(request).unwrap();
+            }
+
+            let mut spill = CommitmentSpillQueue::new(&mut spill);
+            for _ in 0..CommitmentSpillQueue::CAPACITY {
+                spill.enqueue(request).unwrap();
             }
         }
         assert_matches!(
-            finalize_base_commitment_hash(&fee_payer, &pool, &fee, &h_account, &mut q, 0, 0),
+            finalize_base_commitment_hash(
+                &fee_payer, &fee_payer, &pool, &pool, &fee, &h_account, &mut q, &mut spill,
+                &config, &pool, 0, 0, 0
+            ),
             Err(_)
         );
 
-        zero_program_account!(mut q, CommitmentQueueAccount);
+        // The commitment is spilled while the commitment queue is full
+        zero_program_account!(mut spill, CommitmentSpillQueueAccount);
+
+        // Anonymity-delay has not elapsed
+        {
+            pda_account!(mut h, BaseCommitmentHashingAccount, h_account);
+            h.set_min_finalization_slot(&1);
+        }
         assert_matches!(
-            finalize_base_commitment_hash(&fee_payer, &pool, &fee, &h_account, &mut q, 0, 0),
+            finalize_base_commitment_hash(
+                &fee_payer, &fee_payer, &pool, &pool, &fee, &h_account, &mut q, &mut spill,
+                &config, &pool, 0, 0, 0
+            ),
+            Err(_)
+        );
+
+        {
+            pda_account!(mut h, BaseCommitmentHashingAccount, h_account);
+            h.set_min_finalization_slot(&0);
+        }
+
+        // Commitment of a different token pool
+        assert_matches!(
+            finalize_base_commitment_hash(
+                &fee_payer, &fee_payer, &pool, &pool, &fee, &h_account, &mut q, &mut spill,
+                &config, &pool, 0, 0, 1
+            ),
+            Err(_)
+        );
+
+        assert_matches!(
+            finalize_base_commitment_hash(
+                &fee_payer, &fee_payer, &pool, &pool, &fee, &h_account, &mut q, &mut spill,
+                &config, &pool, 0, 0, 0
+            ),
             Ok(())
         );
+        assert_eq!(
+            CommitmentQueue::new(&mut q).len(),
+            CommitmentQueue::CAPACITY
+        );
+        assert_eq!(CommitmentSpillQueue::new(&mut spill).len(), 1);
         Ok(())
     }
 
+    #[test]
+    fn test_drain_commitment_spill_queue() {
+        zero_program_account!(mut q, CommitmentQueueAccount);
+        zero_program_account!(mut spill, CommitmentSpillQueueAccount);
+        zero_program_account!(mut config, ConfigAccount);
+
+        let request = |commitment: u8| CommitmentHashRequest {
+            commitment: [commitment; 32],
+            min_batching_rate: 0,
+            enqueue_slot: 0,
+            fee_version: 0,
+        };
+
+        // Empty spill queue
+        assert_matches!(
+            drain_commitment_spill_queue(&mut q, &mut spill, &config, 0),
+            Err(_)
+        );
+
+        {
+            let mut q = CommitmentQueue::new(&mut q);
+            for _ in 0..CommitmentQueue::CAPACITY - 2 {
+                q.enqueue(request(0)).unwrap();
+            }
+
+            let mut spill = CommitmentSpillQueue::new(&mut spill);
+            for i in 1..=3 {
+                spill.enqueue(request(i)).unwrap();
+            }
+        }
+
+        // Only two commitments fit into the commitment queue
+        assert_matches!(
+            drain_commitment_spill_queue(&mut q, &mut spill, &config, 0),
+            Ok(())
+        );
+        {
+            let q = CommitmentQueue::new(&mut q);
+            assert_eq!(q.len(), CommitmentQueue::CAPACITY);
+            assert_eq!(
+                q.view(CommitmentQueue::CAPACITY as usize - 2).unwrap(),
+                request(1)
+            );
+            assert_eq!(
+                q.view(CommitmentQueue::CAPACITY as usize - 1).unwrap(),
+                request(2)
+            );
+
+            let spill = CommitmentSpillQueue::new(&mut spill);
+            assert_eq!(spill.view_first().unwrap(), request(3));
+        }
+
+        // Full commitment queue
+        assert_matches!(
+            drain_commitment_spill_queue(&mut q, &mut spill, &config, 0),
+            Err(_)
+        );
+
+        CommitmentQueue::new(&mut q).remove(1).unwrap();
+
+        // Commitment queue filled up to its configured capacity
+        config.set_commitment_queue_capacity(&(CommitmentQueue::CAPACITY - 1));
+        assert_matches!(
+            drain_commitment_spill_queue(&mut q, &mut spill, &config, 0),
+            Err(_)
+        );
+
+        config.set_commitment_queue_capacity(&0);
+        assert_matches!(
+            drain_commitment_spill_queue(&mut q, &mut spill, &config, 0),
+            Ok(())
+        );
+        assert!(CommitmentSpillQueue::new(&mut spill).is_empty());
+    }
+
     #[test]
     fn test_init_commitment_hash_empty_queue() {
+        set_test_slot(0);
         parent_account!(storage_account, StorageAccount);
         zero_program_account!(mut queue, CommitmentQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
 
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, false, 0).unwrap();
         assert_matches!(
-            init_commitment_hash(&mut queue, &mut hashing_account, false),
+            init_commitment_hash(
+                &mut queue,
+                &mut hashing_account,
+                &mut event_sequence,
+                false,
+                0
+            ),
             Err(_)
         );
     }
@@ -1204,11 +2068,13 @@ mod tests {
     fn test_init_commitment_hash_active_computation() {
         zero_program_account!(mut queue, CommitmentQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
 
         let mut q = CommitmentQueue::new(&mut queue);
         q.enqueue(CommitmentHashRequest {
             commitment: [0; 32],
             min_batching_rate: 0,
+            enqueue_slot: 0,
             fee_version: 0,
         })
         .unwrap();
@@ -1216,72 +2082,119 @@ mod tests {
         hashing_account.set_is_active(&true);
         hashing_account.set_setup(&true);
         assert_matches!(
-            init_commitment_hash(&mut queue, &mut hashing_account, false),
+            init_commitment_hash(
+                &mut queue,
+                &mut hashing_account,
+                &mut event_sequence,
+                false,
+                0
+            ),
             Err(_)
         );
     }
 
     #[test]
     fn test_init_commitment_hash_full_storage() {
+        set_test_slot(0);
         parent_account!(mut storage_account, StorageAccount);
         zero_program_account!(mut queue, CommitmentQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
 
         let mut q = CommitmentQueue::new(&mut queue);
         q.enqueue(CommitmentHashRequest {
             commitment: [0; 32],
             min_batching_rate: 0,
+            enqueue_slot: 0,
             fee_version: 0,
         })
         .unwrap();
 
         storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, false, 0).unwrap();
         assert_matches!(
-            init_commitment_hash(&mut queue, &mut hashing_account, false),
+            init_commitment_hash(
+                &mut queue,
+                &mut hashing_account,
+                &mut event_sequence,
+                false,
+                0
+            ),
             Err(_)
         );
+
+        // Skipped commitments remain in the queue
+        assert_matches!(
+            init_commitment_hash(
+                &mut queue,
+                &mut hashing_account,
+                &mut event_sequence,
+                true,
+                0
+            ),
+            Ok(())
+        );
+        assert_eq!(CommitmentQueue::new(&mut queue).len(), 1);
+        assert_eq!(event_sequence.get_sequence(), 1);
     }
 
     #[test]
     fn test_init_commitment_hash_incomplete_batch() {
+        set_test_slot(0);
         parent_account!(storage_account, StorageAccount);
         zero_program_account!(mut queue, CommitmentQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
 
         let mut q = CommitmentQueue::new(&mut queue);
         q.enqueue(CommitmentHashRequest {
             commitment: [0; 32],
             min_batching_rate: 1,
+            enqueue_slot: 0,
             fee_version: 0,
         })
         .unwrap();
 
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, false, 0).unwrap();
         assert_matches!(
-            init_commitment_hash(&mut queue, &mut hashing_account, false),
+            init_commitment_hash(
+                &mut queue,
+                &mut hashing_account,
+                &mut event_sequence,
+                false,
+                0
+            ),
             Err(_)
         );
     }
 
     #[test]
     fn test_init_commitment_hash_batch_too_big() {
+        set_test_slot(0);
         parent_account!(mut storage_account, StorageAccount);
         zero_program_account!(mut queue, CommitmentQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
 
         let mut q = CommitmentQueue::new(&mut queue);
         q.enqueue(CommitmentHashRequest {
             commitment: [0; 32],
             min_batching_rate: 1,
+            enqueue_slot: 0,
             fee_version: 0,
         })
         .unwrap();
 
         storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32 - 1));
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, false, 0).unwrap();
         assert_matches!(
-            init_commitment_hash(&mut queue, &mut hashing_account, false),
+            init_commitment_hash(
+                &mut queue,
+                &mut hashing_account,
+                &mut event_sequence,
+                false,
+                0
+            ),
             Err(_)
         );
     }
@@ -1289,38 +2202,51 @@ mod tests {
     #[test]
     #[allow(clippy::needless_range_loop)]
     fn test_init_commitment_hash_valid() {
+        set_test_slot(0);
         parent_account!(storage_account, StorageAccount);
         zero_program_account!(mut queue, CommitmentQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
 
         let mut q = CommitmentQueue::new(&mut queue);
         q.enqueue(CommitmentHashRequest {
             commitment: [1; 32],
             min_batching_rate: 2,
+            enqueue_slot: 0,
             fee_version: 0,
         })
         .unwrap();
         q.enqueue(CommitmentHashRequest {
             commitment: [2; 32],
             min_batching_rate: 0,
+            enqueue_slot: 0,
             fee_version: 0,
         })
         .unwrap();
         q.enqueue(CommitmentHashRequest {
             commitment: [3; 32],
             min_batching_rate: 0,
+            enqueue_slot: 0,
             fee_version: 0,
         })
         .unwrap();
         q.enqueue(CommitmentHashRequest {
             commitment: [4; 32],
             min_batching_rate: 0,
+            enqueue_slot: 0,
             fee_version: 0,
         })
         .unwrap();
 
-        init_commitment_hash_setup(&mut hashing_account, &storage_account, false).unwrap();
-        init_commitment_hash(&mut queue, &mut hashing_account, false).unwrap();
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, false, 0).unwrap();
+        init_commitment_hash(
+            &mut queue,
+            &mut hashing_account,
+            &mut event_sequence,
+            false,
+            0,
+        )
+        .unwrap();
 
         assert_eq!(hashing_account.get_batching_rate(), 2);
 
@@ -1335,17 +2261,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_init_commitment_hash_mixed_fee_versions() {
+        set_test_slot(0);
+        parent_account!(storage_account, StorageAccount);
+        zero_program_account!(mut queue, CommitmentQueueAccount);
+        zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+
+        // A request of the new fee-version is queued before a finalized request of the old fee-version
+        let mut q = CommitmentQueue::new(&mut queue);
+        for fee_version in [2, 1] {
+            q.enqueue(CommitmentHashRequest {
+                commitment: [1; 32],
+                min_batching_rate: 1,
+                enqueue_slot: 0,
+                fee_version,
+            })
+            .unwrap();
+        }
+
+        init_commitment_hash_setup(&mut hashing_account, &storage_account, false, 0).unwrap();
+        init_commitment_hash(
+            &mut queue,
+            &mut hashing_account,
+            &mut event_sequence,
+            false,
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(hashing_account.get_fee_version(), 1);
+        assert_eq!(CommitmentQueue::new(&mut queue).len(), 0);
+    }
+
     #[test]
     fn test_init_commitment_hash_setup_insertion_can_fail() {
         parent_account!(storage_account, StorageAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
         hashing_account.set_is_active(&true);
         assert_matches!(
-            init_commitment_hash_setup(&mut hashing_account, &storage_account, false),
+            init_commitment_hash_setup(&mut hashing_account, &storage_account, false, 0),
             Err(_)
         );
         assert_matches!(
-            init_commitment_hash_setup(&mut hashing_account, &storage_account, true),
+            init_commitment_hash_setup(&mut hashing_account, &storage_account, true, 0),
             Ok(())
         );
     }
@@ -1354,49 +2314,159 @@ mod tests {
     fn test_init_commitment_hash_insertion_can_fail() {
         zero_program_account!(mut queue, CommitmentQueueAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
         assert_matches!(
-            init_commitment_hash(&mut queue, &mut hashing_account, false),
+            init_commitment_hash(
+                &mut queue,
+                &mut hashing_account,
+                &mut event_sequence,
+                false,
+                0
+            ),
             Err(_)
         );
         assert_matches!(
-            init_commitment_hash(&mut queue, &mut hashing_account, true),
+            init_commitment_hash(
+                &mut queue,
+                &mut hashing_account,
+                &mut event_sequence,
+                true,
+                0
+            ),
             Ok(())
         );
+
+        // No skip events for an empty queue
+        assert_eq!(event_sequence.get_sequence(), 0);
     }
 
     #[test]
     fn test_compute_commitment_hash() {
+        set_test_slot(0);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut poseidon_params, PoseidonParamsAccount);
         zero_program_account!(fee, FeeAccount);
+        zero_program_account!(governor, GovernorAccount);
         test_account_info!(pool, 0);
         test_account_info!(fee_payer, 0);
 
+        test_account_info!(fee_collector, FeeCollectorAccount::SIZE);
+        test_account_info!(warden_account, 0);
+        test_account_info!(instructions_account, 0);
+
+        macro_rules! compute_commitment_hash {
+            ($fee_version: expr) => {
+                compute_commitment_hash(
+                    &fee_payer,
+                    &fee,
+                    &pool,
+                    &fee_collector,
+                    &mut hashing_account,
+                    &poseidon_params,
+                    &governor,
+                    UnverifiedAccountInfo::new(&warden_account),
+                    &instructions_account,
+                    $fee_version,
+                    0,
+                    0,
+                )
+            };
+        }
+
         // Inactive account
-        assert_matches!(
-            compute_commitment_hash(&fee_payer, &fee, &pool, &mut hashing_account, 0, 0),
-            Err(_)
-        );
+        assert_matches!(compute_commitment_hash!(0), Err(_));
 
         // Invalid fee_version
         hashing_account.set_is_active(&true);
-        assert_matches!(
-            compute_commitment_hash(&fee_payer, &fee, &pool, &mut hashing_account, 1, 0),
-            Err(_)
-        );
+        assert_matches!(compute_commitment_hash!(1), Err(_));
 
-        compute_commitment_hash(&fee_payer, &fee, &pool, &mut hashing_account, 0, 0).unwrap();
+        // Poseidon parameters are not frozen
+        assert_matches!(compute_commitment_hash!(0), Err(_));
+
+        poseidon_params.load_default_params();
+
+        // Without `lamports_per_cu` the instructions sysvar is not accessed
+        compute_commitment_hash!(0).unwrap();
+    }
+
+    #[test]
+    fn test_compensate_priority_fee() {
+        zero_program_account!(mut fee, FeeAccount);
+        test_account_info!(fee_payer, 0);
+        test_account_info!(fee_collector, FeeCollectorAccount::SIZE);
+        let rent = DefaultRentSysvar
+            .minimum_balance(FeeCollectorAccount::SIZE)
+            .unwrap();
+
+        let mut data = vec![3];
+        data.extend(1_000u64.to_le_bytes());
+        let instructions_sysvar = TestInstructionsSysvar(vec![Instruction {
+            program_id: COMPUTE_BUDGET_PROGRAM_ID,
+            accounts: Vec::new(),
+            data,
+        }]);
+
+        macro_rules! compensate {
+            () => {
+                compensate_priority_fee(
+                    &fee_payer,
+                    &fee,
+                    &fee_collector,
+                    &instructions_sysvar,
+                    &DefaultRentSysvar,
+                    1,
+                )
+                .unwrap()
+            };
+        }
+
+        // Compensation disabled
+        compensate!();
+        assert_eq!(fee_payer.lamports(), u32::MAX as u64);
+
+        fee.set_lamports_per_cu(&10_000);
+        compensate!();
+        assert_eq!(fee_payer.lamports(), u32::MAX as u64 + 1_400);
+        {
+            let data = &mut fee_collector.data.borrow_mut()[..];
+            let fee_collector = FeeCollectorAccount::new(data).unwrap();
+            assert_eq!(
+                fee_collector.fee_revenue(1).unwrap().subventions[LAMPORTS_TOKEN_ID as usize],
+                1_400
+            );
+        }
+
+        // Limited to the lamports above the rent-exemption
+        **fee_collector.try_borrow_mut_lamports().unwrap() = rent + 100;
+        compensate!();
+        assert_eq!(fee_payer.lamports(), u32::MAX as u64 + 1_500);
+        assert_eq!(fee_collector.lamports(), rent);
+
+        compensate!();
+        assert_eq!(fee_payer.lamports(), u32::MAX as u64 + 1_500);
     }
 
     #[test]
     fn test_finalize_commitment_hash() {
+        set_test_slot(0);
         parent_account!(mut storage_account, StorageAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut state_summary, StateSummaryAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
 
         // Computation not finished
         hashing_account.set_is_active(&true);
         hashing_account.set_instruction(&0);
         assert_matches!(
-            finalize_commitment_hash(&mut hashing_account, &mut storage_account),
+            finalize_commitment_hash(
+                &mut hashing_account,
+                &mut storage_account,
+                &mut state_summary,
+                &mut stats,
+                &mut event_sequence,
+                0
+            ),
             Err(_)
         );
 
@@ -1405,7 +2475,14 @@ mod tests {
         hashing_account
             .set_instruction(&(commitment_hash_computation_instructions(0).len() as u32));
         assert_matches!(
-            finalize_commitment_hash(&mut hashing_account, &mut storage_account),
+            finalize_commitment_hash(
+                &mut hashing_account,
+                &mut storage_account,
+                &mut state_summary,
+                &mut stats,
+                &mut event_sequence,
+                0
+            ),
             Err(_)
         );
 
@@ -1413,18 +2490,37 @@ mod tests {
         hashing_account.set_is_active(&true);
         storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
         assert_matches!(
-            finalize_commitment_hash(&mut hashing_account, &mut storage_account),
+            finalize_commitment_hash(
+                &mut hashing_account,
+                &mut storage_account,
+                &mut state_summary,
+                &mut stats,
+                &mut event_sequence,
+                0
+            ),
             Err(_)
         );
 
         storage_account.set_next_commitment_ptr(&0);
-        finalize_commitment_hash(&mut hashing_account, &mut storage_account).unwrap();
+        finalize_commitment_hash(
+            &mut hashing_account,
+            &mut storage_account,
+            &mut state_summary,
+            &mut stats,
+            &mut event_sequence,
+            0,
+        )
+        .unwrap();
     }
 
     #[test]
     fn test_finalize_commitment_hash_valid() {
+        set_test_slot(0);
         parent_account!(mut storage_account, StorageAccount);
         zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        zero_program_account!(mut state_summary, StateSummaryAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
 
         let batching_rate = 4;
         let commitment_count = commitments_per_batch(batching_rate);
@@ -1446,7 +2542,15 @@ mod tests {
         }
 
         for _ in 0..=batching_rate {
-            finalize_commitment_hash(&mut hashing_account, &mut storage_account).unwrap();
+            finalize_commitment_hash(
+                &mut hashing_account,
+                &mut storage_account,
+                &mut state_summary,
+                &mut stats,
+                &mut event_sequence,
+                0,
+            )
+            .unwrap();
         }
 
         assert!(!hashing_account.get_is_active());
@@ -1455,6 +2559,15 @@ mod tests {
             storage_account.get_next_commitment_ptr(),
             commitment_count as u32
         );
+        assert_eq!(state_summary.get_batch_count(), 1);
+        assert_eq!(stats.get_commitment_count(), commitment_count as u64);
+
+        // A commitment-insertion event per commitment and a single root-update event are emitted per batch
+        assert_eq!(event_sequence.get_sequence(), commitment_count as u64 + 1);
+        assert_eq!(
+            state_summary.get_active_mt_root(),
+            storage_account.get_root().unwrap()
+        );
 
         // Check that MT is updated
         for level_inv in 0..=MT_HEIGHT {
@@ -0,0 +1,413 @@
+use super::commitment::{
+    start_lamports_base_commitment_hash, verify_base_commitment, verify_epoch_deposit_cap,
+};
+use crate::computation::current_slot;
+use crate::error::ElusivError;
+use crate::macros::{guard, pda_account};
+use crate::processor::utils::transfer_lamports_from_pda_checked;
+use crate::state::{
+    commitment::BaseCommitmentBufferAccount,
+    crowdfund::CrowdfundAccount,
+    governor::GovernorAccount,
+    program_account::Lifecycle,
+    stats::{current_epoch, EpochMetricsAccount, StatsAccount},
+};
+use crate::types::RawU256;
+use elusiv_utils::transfer_with_system_program;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+
+/// Opens a [`CrowdfundAccount`] accepting contributions until `deadline_slot`
+pub fn create_crowdfund<'a>(
+    creator: &AccountInfo<'a>,
+    crowdfund_account: &AccountInfo<'a>,
+
+    crowdfund_index: u32,
+    base_commitment: RawU256,
+    target_amount: u64,
+    deadline_slot: u64,
+) -> ProgramResult {
+    guard!(target_amount > 0, ElusivError::InvalidAmount);
+    guard!(
+        deadline_slot > current_slot()?,
+        ElusivError::InvalidInstructionData
+    );
+    verify_base_commitment(&base_commitment)?;
+
+    CrowdfundAccount::reopen(
+        creator,
+        crowdfund_account,
+        Some(*creator.key),
+        Some(crowdfund_index),
+        None,
+    )?;
+
+    pda_account!(mut crowdfund_account, CrowdfundAccount, crowdfund_account);
+    crowdfund_account.set_creator(creator.key);
+    crowdfund_account.set_base_commitment(&base_commitment.skip_mr());
+    crowdfund_account.set_target_amount(&target_amount);
+    crowdfund_account.set_deadline_slot(&deadline_slot);
+
+    Ok(())
+}
+
+/// Transfers a contribution of `amount` lamports from the `funder` to the `crowdfund_account` (only before the deadline)
+pub fn contribute_to_crowdfund<'a>(
+    funder: &AccountInfo<'a>,
+    creator: &AccountInfo<'a>,
+    crowdfund_account_info: &AccountInfo<'a>,
+    governor: &GovernorAccount,
+    system_program: &AccountInfo<'a>,
+
+    _crowdfund_index: u32,
+    amount: u64,
+) -> ProgramResult {
+    guard!(!governor.get_is_halted(), ElusivError::ProgramIsHalted);
+    guard!(
+        !governor.get_emergency_mode(),
+        ElusivError::EmergencyModeIsActive
+    );
+
+    {
+        pda_account!(
+            mut crowdfund_account,
+            CrowdfundAccount,
+            crowdfund_account_info
+        );
+        guard!(
+            crowdfund_account.get_creator() == *creator.key,
+            ElusivError::InvalidAccount
+        );
+        guard!(
+            !crowdfund_account.is_deadline_reached(current_slot()?),
+            ElusivError::InvalidAccountState
+        );
+        crowdfund_account.contribute(funder.key, amount)?;
+    }
+
+    // `funder` transfers `amount` to `crowdfund_account` (lamports)
+    transfer_with_system_program(funder, crowdfund_account_info, system_program, amount)
+}
+
+/// Returns the contribution of the `funder` once the deadline has passed without reaching the target
+pub fn refund_crowdfund_contribution<'a>(
+    funder: &AccountInfo<'a>,
+    creator: &AccountInfo<'a>,
+    crowdfund_account_info: &AccountInfo<'a>,
+    governor: &GovernorAccount,
+
+    _crowdfund_index: u32,
+) -> ProgramResult {
+    guard!(!governor.get_is_halted(), ElusivError::ProgramIsHalted);
+
+    let amount = {
+        pda_account!(
+            mut crowdfund_account,
+            CrowdfundAccount,
+            crowdfund_account_info
+        );
+        guard!(
+            crowdfund_account.get_creator() == *creator.key,
+            ElusivError::InvalidAccount
+        );
+        crowdfund_account.refund(funder.key, current_slot()?)?
+    };
+
+    // `crowdfund_account` transfers `amount` to `funder` (lamports)
+    transfer_lamports_from_pda_checked(crowdfund_account_info, funder, amount)
+}
+
+/// Closes a [`CrowdfundAccount`] that has not reached its target before the deadline, once all contributions have been refunded
+pub fn close_crowdfund<'a>(
+    creator: &AccountInfo<'a>,
+    crowdfund_account_info: &AccountInfo<'a>,
+
+    _crowdfund_index: u32,
+) -> ProgramResult {
+    {
+        pda_account!(crowdfund_account, CrowdfundAccount, crowdfund_account_info);
+        guard!(
+            crowdfund_account.is_deadline_reached(current_slot()?),
+            ElusivError::CrowdfundDeadlineIsNotReached
+        );
+        guard!(
+            !crowdfund_account.is_target_reached() && crowdfund_account.get_raised_amount() == 0,
+            ElusivError::InvalidAccountState
+        );
+    }
+
+    CrowdfundAccount::close(creator, crowdfund_account_info)
+}
+
+/// Starts the hashing of the commitment of the `raised_amount`
+/// - permissionless once the target is reached (crowdfunds below the target can only be refunded)
+#[allow(clippy::too_many_arguments)]
+pub fn finalize_crowdfund<'a>(
+    fee_payer: &AccountInfo<'a>,
+    creator: &AccountInfo<'a>,
+    crowdfund_account_info: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    governor: &GovernorAccount,
+    hashing_account: &AccountInfo<'a>,
+    base_commitment_buffer: &mut BaseCommitmentBufferAccount,
+    stats: &mut StatsAccount,
+    epoch_metrics: &mut EpochMetricsAccount,
+
+    _crowdfund_index: u32,
+    hash_account_index: u32,
+    hash_account_bump: u8,
+) -> ProgramResult {
+    guard!(!governor.get_is_halted(), ElusivError::ProgramIsHalted);
+    guard!(
+        !governor.get_emergency_mode(),
+        ElusivError::EmergencyModeIsActive
+    );
+
+    let (base_commitment, amount) = {
+        pda_account!(crowdfund_account, CrowdfundAccount, crowdfund_account_info);
+        guard!(
+            crowdfund_account.get_creator() == *creator.key,
+            ElusivError::InvalidAccount
+        );
+        guard!(
+            crowdfund_account.is_target_reached(),
+            ElusivError::CrowdfundTargetIsNotReached
+        );
+
+        (
+            crowdfund_account.get_base_commitment(),
+            crowdfund_account.get_raised_amount(),
+        )
+    };
+    guard!(amount > 0, ElusivError::InvalidAmount);
+
+    // `crowdfund_account` transfers `amount` to `pool` (lamports)
+    transfer_lamports_from_pda_checked(crowdfund_account_info, pool, amount)?;
+    CrowdfundAccount::close(creator, crowdfund_account_info)?;
+
+    verify_epoch_deposit_cap(governor, epoch_metrics, 0, amount)?;
+    stats.add_deposit(0, amount);
+    epoch_metrics.add_deposit(current_epoch()?, 0, amount);
+
+    start_lamports_base_commitment_hash(
+        fee_payer,
+        hashing_account,
+        governor,
+        base_commitment_buffer,
+        base_commitment,
+        amount,
+        hash_account_index,
+        hash_account_bump,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{
+        account_info, test_account_info, test_pda_account_info, zero_program_account,
+    };
+    use crate::state::program_account::ProgramAccount;
+    use crate::syscall_stubs::set_test_slot;
+    use assert_matches::assert_matches;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_create_crowdfund_invalid_inputs() {
+        test_account_info!(creator, 0);
+        test_account_info!(crowdfund_account, 0);
+
+        // Zero target
+        assert_matches!(
+            create_crowdfund(&creator, &crowdfund_account, 0, RawU256::new([1; 32]), 0, 1),
+            Err(_)
+        );
+
+        // Passed deadline
+        assert_matches!(
+            create_crowdfund(&creator, &crowdfund_account, 0, RawU256::new([1; 32]), 1, 0),
+            Err(_)
+        );
+
+        // Non-scalar base commitment
+        assert_matches!(
+            create_crowdfund(
+                &creator,
+                &crowdfund_account,
+                0,
+                RawU256::new([255; 32]),
+                1,
+                1
+            ),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_finalize_crowdfund_target_not_reached() {
+        let creator_pk = Pubkey::new_unique();
+        account_info!(creator, creator_pk, true);
+        test_pda_account_info!(crowdfund_account, CrowdfundAccount, creator_pk, Some(0));
+        {
+            let data = &mut crowdfund_account.data.borrow_mut()[..];
+            let mut crowdfund = CrowdfundAccount::new(data).unwrap();
+            crowdfund.set_creator(&creator_pk);
+            crowdfund.set_target_amount(&100);
+            crowdfund.set_deadline_slot(&1);
+            crowdfund.contribute(&Pubkey::new_unique(), 99).unwrap();
+        }
+
+        test_account_info!(fee_payer, 0);
+        test_account_info!(pool, 0);
+        test_account_info!(hashing_account, 0);
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(mut buffer, BaseCommitmentBufferAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut epoch_metrics, EpochMetricsAccount);
+
+        macro_rules! finalize {
+            () => {
+                finalize_crowdfund(
+                    &fee_payer,
+                    &creator,
+                    &crowdfund_account,
+                    &pool,
+                    &governor,
+                    &hashing_account,
+                    &mut buffer,
+                    &mut stats,
+                    &mut epoch_metrics,
+                    0,
+                    0,
+                    0,
+                )
+            };
+        }
+
+        assert_matches!(finalize!(), Err(_));
+        assert_eq!(stats.get_deposit_count(), 0);
+
+        // Crowdfunds below the target can't be finalized after the deadline
+        {
+            let data = &mut crowdfund_account.data.borrow_mut()[..];
+            let mut crowdfund = CrowdfundAccount::new(data).unwrap();
+            crowdfund.set_deadline_slot(&0);
+        }
+        assert_matches!(finalize!(), Err(_));
+        assert_eq!(stats.get_deposit_count(), 0);
+
+        governor.set_is_halted(&true);
+        assert_matches!(finalize!(), Err(_));
+        governor.set_is_halted(&false);
+
+        governor.set_emergency_mode(&true);
+        assert_matches!(finalize!(), Err(_));
+        assert_eq!(stats.get_deposit_count(), 0);
+    }
+
+    #[test]
+    fn test_refund_crowdfund_contribution() {
+        set_test_slot(0);
+        let creator_pk = Pubkey::new_unique();
+        let funder_pk = Pubkey::new_unique();
+        account_info!(creator, creator_pk);
+        account_info!(funder, funder_pk, true);
+        test_pda_account_info!(crowdfund_account, CrowdfundAccount, creator_pk, Some(0));
+        {
+            let data = &mut crowdfund_account.data.borrow_mut()[..];
+            let mut crowdfund = CrowdfundAccount::new(data).unwrap();
+            crowdfund.set_creator(&creator_pk);
+            crowdfund.set_target_amount(&100);
+            crowdfund.set_deadline_slot(&1);
+            crowdfund.contribute(&funder_pk, 60).unwrap();
+            crowdfund.contribute(&Pubkey::new_unique(), 30).unwrap();
+        }
+        zero_program_account!(mut governor, GovernorAccount);
+
+        // Deadline not reached
+        assert_matches!(
+            refund_crowdfund_contribution(&funder, &creator, &crowdfund_account, &governor, 0),
+            Err(_)
+        );
+
+        {
+            let data = &mut crowdfund_account.data.borrow_mut()[..];
+            let mut crowdfund = CrowdfundAccount::new(data).unwrap();
+            crowdfund.set_deadline_slot(&0);
+        }
+
+        governor.set_is_halted(&true);
+        assert_matches!(
+            refund_crowdfund_contribution(&funder, &creator, &crowdfund_account, &governor, 0),
+            Err(_)
+        );
+        governor.set_is_halted(&false);
+
+        // Invalid creator
+        test_account_info!(invalid_creator, 0);
+        assert_matches!(
+            refund_crowdfund_contribution(
+                &funder,
+                &invalid_creator,
+                &crowdfund_account,
+                &governor,
+                0
+            ),
+            Err(_)
+        );
+
+        let funder_lamports = funder.lamports();
+        let crowdfund_lamports = crowdfund_account.lamports();
+        refund_crowdfund_contribution(&funder, &creator, &crowdfund_account, &governor, 0).unwrap();
+        assert_eq!(funder.lamports(), funder_lamports + 60);
+        assert_eq!(crowdfund_account.lamports(), crowdfund_lamports - 60);
+
+        {
+            let data = &mut crowdfund_account.data.borrow_mut()[..];
+            let crowdfund = CrowdfundAccount::new(data).unwrap();
+            assert_eq!(crowdfund.get_raised_amount(), 30);
+            assert_eq!(crowdfund.contribution(&funder_pk), 0);
+        }
+
+        // Only refunded once
+        assert_matches!(
+            refund_crowdfund_contribution(&funder, &creator, &crowdfund_account, &governor, 0),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_close_crowdfund() {
+        set_test_slot(0);
+        let creator_pk = Pubkey::new_unique();
+        account_info!(creator, creator_pk, true);
+        test_pda_account_info!(crowdfund_account, CrowdfundAccount, creator_pk, Some(0));
+
+        macro_rules! set_crowdfund {
+            ($deadline_slot: expr, $raised_amount: expr) => {{
+                let data = &mut crowdfund_account.data.borrow_mut()[..];
+                let mut crowdfund = CrowdfundAccount::new(data).unwrap();
+                crowdfund.set_target_amount(&100);
+                crowdfund.set_deadline_slot(&$deadline_slot);
+                crowdfund.set_raised_amount(&$raised_amount);
+            }};
+        }
+
+        // Deadline not reached
+        set_crowdfund!(1, 0);
+        assert_matches!(close_crowdfund(&creator, &crowdfund_account, 0), Err(_));
+
+        // Contributions are not yet refunded
+        set_crowdfund!(0, 30);
+        assert_matches!(close_crowdfund(&creator, &crowdfund_account, 0), Err(_));
+
+        // Target reached
+        set_crowdfund!(0, 100);
+        assert_matches!(close_crowdfund(&creator, &crowdfund_account, 0), Err(_));
+
+        set_crowdfund!(0, 0);
+        let lamports = creator.lamports() + crowdfund_account.lamports();
+        close_crowdfund(&creator, &crowdfund_account, 0).unwrap();
+        assert_eq!(creator.lamports(), lamports);
+        assert_eq!(crowdfund_account.lamports(), 0);
+    }
+}
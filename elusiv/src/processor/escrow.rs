@@ -0,0 +1,242 @@
+use super::commitment::{
+    start_lamports_base_commitment_hash, verify_base_commitment, verify_epoch_deposit_cap,
+};
+use crate::error::ElusivError;
+use crate::macros::{guard, pda_account};
+use crate::processor::utils::transfer_lamports_from_pda_checked;
+use crate::state::{
+    commitment::BaseCommitmentBufferAccount,
+    escrow::{DepositEscrowAccount, DEPOSIT_ESCROW_ABORT_FEE},
+    governor::GovernorAccount,
+    program_account::Lifecycle,
+    stats::{current_epoch, EpochMetricsAccount, StatsAccount},
+};
+use crate::types::RawU256;
+use elusiv_utils::transfer_with_system_program;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+
+/// Opens a [`DepositEscrowAccount`] and transfers `amount` lamports from the `sender` to it
+#[allow(clippy::too_many_arguments)]
+pub fn create_deposit_escrow<'a>(
+    sender: &AccountInfo<'a>,
+    deposit_escrow_account: &AccountInfo<'a>,
+    governor: &GovernorAccount,
+    system_program: &AccountInfo<'a>,
+
+    deposit_escrow_index: u32,
+    base_commitment: RawU256,
+    amount: u64,
+) -> ProgramResult {
+    guard!(!governor.get_is_halted(), ElusivError::ProgramIsHalted);
+    guard!(
+        !governor.get_emergency_mode(),
+        ElusivError::EmergencyModeIsActive
+    );
+    guard!(
+        amount > DEPOSIT_ESCROW_ABORT_FEE,
+        ElusivError::InvalidAmount
+    );
+    verify_base_commitment(&base_commitment)?;
+
+    DepositEscrowAccount::reopen(
+        sender,
+        deposit_escrow_account,
+        Some(*sender.key),
+        Some(deposit_escrow_index),
+        None,
+    )?;
+
+    {
+        pda_account!(
+            mut deposit_escrow,
+            DepositEscrowAccount,
+            deposit_escrow_account
+        );
+        deposit_escrow.set_sender(sender.key);
+        deposit_escrow.set_base_commitment(&base_commitment.skip_mr());
+        deposit_escrow.set_amount(&amount);
+    }
+
+    // `sender` transfers `amount` to `deposit_escrow_account` (lamports)
+    transfer_with_system_program(sender, deposit_escrow_account, system_program, amount)
+}
+
+/// Reserves an escrowed deposit by starting the hashing of its commitment (permissionless)
+/// - afterwards the deposit can no longer be aborted
+#[allow(clippy::too_many_arguments)]
+pub fn reserve_deposit_escrow<'a>(
+    fee_payer: &AccountInfo<'a>,
+    sender: &AccountInfo<'a>,
+    deposit_escrow_account: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    governor: &GovernorAccount,
+    hashing_account: &AccountInfo<'a>,
+    base_commitment_buffer: &mut BaseCommitmentBufferAccount,
+    stats: &mut StatsAccount,
+    epoch_metrics: &mut EpochMetricsAccount,
+
+    _deposit_escrow_index: u32,
+    hash_account_index: u32,
+    hash_account_bump: u8,
+) -> ProgramResult {
+    guard!(!governor.get_is_halted(), ElusivError::ProgramIsHalted);
+    guard!(
+        !governor.get_emergency_mode(),
+        ElusivError::EmergencyModeIsActive
+    );
+
+    let (base_commitment, amount) = {
+        pda_account!(deposit_escrow, DepositEscrowAccount, deposit_escrow_account);
+        guard!(
+            deposit_escrow.get_sender() == *sender.key,
+            ElusivError::InvalidAccount
+        );
+
+        (
+            deposit_escrow.get_base_commitment(),
+            deposit_escrow.get_amount(),
+        )
+    };
+
+    // `deposit_escrow_account` transfers `amount` to `pool` (lamports)
+    transfer_lamports_from_pda_checked(deposit_escrow_account, pool, amount)?;
+    DepositEscrowAccount::close(sender, deposit_escrow_account)?;
+
+    verify_epoch_deposit_cap(governor, epoch_metrics, 0, amount)?;
+    stats.add_deposit(0, amount);
+    epoch_metrics.add_deposit(current_epoch()?, 0, amount);
+
+    start_lamports_base_commitment_hash(
+        fee_payer,
+        hashing_account,
+        governor,
+        base_commitment_buffer,
+        base_commitment,
+        amount,
+        hash_account_index,
+        hash_account_bump,
+    )
+}
+
+/// Aborts an escrowed deposit that has not been reserved yet
+/// - the `sender` receives the escrowed `amount` minus [`DEPOSIT_ESCROW_ABORT_FEE`] and the rent
+pub fn abort_deposit_escrow<'a>(
+    sender: &AccountInfo<'a>,
+    deposit_escrow_account: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+
+    _deposit_escrow_index: u32,
+) -> ProgramResult {
+    {
+        pda_account!(deposit_escrow, DepositEscrowAccount, deposit_escrow_account);
+        guard!(
+            deposit_escrow.get_sender() == *sender.key,
+            ElusivError::InvalidAccount
+        );
+    }
+
+    // `deposit_escrow_account` transfers the abort fee to `fee_collector` (lamports)
+    transfer_lamports_from_pda_checked(
+        deposit_escrow_account,
+        fee_collector,
+        DEPOSIT_ESCROW_ABORT_FEE,
+    )?;
+
+    DepositEscrowAccount::close(sender, deposit_escrow_account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{
+        account_info, test_account_info, test_pda_account_info, zero_program_account,
+    };
+    use crate::state::program_account::ProgramAccount;
+    use assert_matches::assert_matches;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_create_deposit_escrow_invalid_inputs() {
+        test_account_info!(sender, 0);
+        test_account_info!(deposit_escrow_account, 0);
+        test_account_info!(system_program, 0);
+        zero_program_account!(mut governor, GovernorAccount);
+
+        // Amount does not exceed the abort fee
+        assert_matches!(
+            create_deposit_escrow(
+                &sender,
+                &deposit_escrow_account,
+                &governor,
+                &system_program,
+                0,
+                RawU256::new([1; 32]),
+                DEPOSIT_ESCROW_ABORT_FEE
+            ),
+            Err(_)
+        );
+
+        // Non-scalar base commitment
+        assert_matches!(
+            create_deposit_escrow(
+                &sender,
+                &deposit_escrow_account,
+                &governor,
+                &system_program,
+                0,
+                RawU256::new([255; 32]),
+                DEPOSIT_ESCROW_ABORT_FEE + 1
+            ),
+            Err(_)
+        );
+
+        // Emergency mode
+        governor.set_emergency_mode(&true);
+        assert_matches!(
+            create_deposit_escrow(
+                &sender,
+                &deposit_escrow_account,
+                &governor,
+                &system_program,
+                0,
+                RawU256::new([1; 32]),
+                DEPOSIT_ESCROW_ABORT_FEE + 1
+            ),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_abort_deposit_escrow() {
+        let sender_pk = Pubkey::new_unique();
+        account_info!(sender, sender_pk, vec![]);
+        account_info!(invalid_sender, Pubkey::new_unique(), vec![]);
+        account_info!(fee_collector, Pubkey::new_unique(), vec![]);
+        test_pda_account_info!(
+            deposit_escrow_account,
+            DepositEscrowAccount,
+            sender_pk,
+            Some(0)
+        );
+        {
+            let data = &mut deposit_escrow_account.data.borrow_mut()[..];
+            let mut deposit_escrow = DepositEscrowAccount::new(data).unwrap();
+            deposit_escrow.set_sender(&sender_pk);
+            deposit_escrow.set_amount(&(DEPOSIT_ESCROW_ABORT_FEE + 100));
+        }
+
+        assert_matches!(
+            abort_deposit_escrow(&invalid_sender, &deposit_escrow_account, &fee_collector, 0),
+            Err(_)
+        );
+
+        let lamports = u32::MAX as u64;
+        abort_deposit_escrow(&sender, &deposit_escrow_account, &fee_collector, 0).unwrap();
+        assert_eq!(
+            fee_collector.lamports(),
+            lamports + DEPOSIT_ESCROW_ABORT_FEE
+        );
+        assert_eq!(sender.lamports(), lamports * 2 - DEPOSIT_ESCROW_ABORT_FEE);
+        assert_eq!(deposit_escrow_account.lamports(), 0);
+    }
+}
@@ -0,0 +1,306 @@
+use super::utils::verify_program_token_account;
+use crate::error::ElusivError;
+use crate::events::ElusivEvent;
+use crate::macros::guard;
+use crate::state::{
+    commitment::CommitmentHashingAccount,
+    event::EventSequenceAccount,
+    nullifier::NullifierAccount,
+    queue::CommitmentQueueAccount,
+    stats::{current_epoch, StatsAccount, STATS_TOKEN_COUNT},
+    storage::{StorageAccount, MT_COMMITMENT_COUNT},
+    summary::{StateDigestHistoryAccount, StateSummaryAccount},
+};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    program_pack::Pack,
+};
+
+/// Checks the invariants between the commitment queue, the commitment hashing state, the active MT and the nullifier-hash counts
+/// - the result of every check is logged, so monitoring services can report the violated invariants
+pub fn health_check(
+    commitment_hash_queue: &CommitmentQueueAccount,
+    commitment_hashing_account: &CommitmentHashingAccount,
+    storage_account: &StorageAccount,
+    active_nullifier_account: &NullifierAccount,
+    state_summary: &StateSummaryAccount,
+
+    active_mt_index: u32,
+    _token_id: u16,
+) -> ProgramResult {
+    guard!(
+        storage_account.get_trees_count() == active_mt_index,
+        ElusivError::InvalidInstructionData
+    );
+
+    let checks = health_checks(
+        commitment_hash_queue,
+        commitment_hashing_account,
+        storage_account,
+        active_nullifier_account,
+        state_summary,
+    );
+
+    let mut passed = true;
+    for (name, result) in checks {
+        msg!(
+            "Health-check {}: {}",
+            name,
+            if result { "passed" } else { "failed" }
+        );
+        passed &= result;
+    }
+
+    guard!(passed, ElusivError::HealthCheckFailed);
+
+    Ok(())
+}
+
+fn health_checks(
+    commitment_hash_queue: &CommitmentQueueAccount,
+    commitment_hashing_account: &CommitmentHashingAccount,
+    storage_account: &StorageAccount,
+    active_nullifier_account: &NullifierAccount,
+    state_summary: &StateSummaryAccount,
+) -> [(&'static str, bool); 7] {
+    let next_commitment_ptr = storage_account.get_next_commitment_ptr();
+    let is_hashing =
+        commitment_hashing_account.get_setup() || commitment_hashing_account.get_is_active();
+
+    [
+        ("queue-pointers", commitment_hash_queue.has_valid_pointers()),
+        (
+            "storage-next-index",
+            next_commitment_ptr as usize <= MT_COMMITMENT_COUNT,
+        ),
+        // A batch is always inserted at the next free index of the active MT
+        (
+            "hashing-ordering",
+            !is_hashing || commitment_hashing_account.get_ordering() == next_commitment_ptr,
+        ),
+        (
+            "summary-mt-index",
+            state_summary.get_active_mt_index() == storage_account.get_trees_count(),
+        ),
+        (
+            "summary-commitment-count",
+            state_summary.get_active_mt_commitment_count() == next_commitment_ptr,
+        ),
+        (
+            "summary-total-commitment-count",
+            state_summary.get_total_commitment_count() >= next_commitment_ptr as u64,
+        ),
+        // The summary tracks the nullifier-hashes of all MTs
+        (
+            "nullifier-count",
+            active_nullifier_account.get_nullifier_hash_count() as u64
+                <= state_summary.get_nullifier_hash_count(),
+        ),
+    ]
+}
+
+/// Logs and emits the reserves of the `pool` for `token_id` versus the liabilities tracked by the [`StatsAccount`] (permissionless)
+/// - the attestation does not fail for an insolvent pool, so the event is always emitted
+pub fn attest_reserves(
+    pool: &AccountInfo,
+    pool_account: &AccountInfo,
+    stats: &StatsAccount,
+    event_sequence: &mut EventSequenceAccount,
+
+    token_id: u16,
+) -> ProgramResult {
+    guard!(
+        (token_id as usize) < STATS_TOKEN_COUNT,
+        ElusivError::InvalidInstructionData
+    );
+    verify_program_token_account(pool, pool_account, token_id)?;
+
+    let reserves = pool_reserves(pool_account, token_id)?;
+    let liabilities = stats.get_tvl(token_id as usize);
+    let is_solvent = reserves >= liabilities;
+
+    msg!(
+        "Reserves of token {}: {} (liabilities: {})",
+        token_id,
+        reserves,
+        liabilities
+    );
+
+    event_sequence.emit(&ElusivEvent::ReservesAttestation {
+        token_id,
+        epoch: current_epoch()?,
+        reserves,
+        liabilities,
+        is_solvent,
+    })
+}
+
+/// Records the digest of the program state for the current epoch (permissionless, once per epoch)
+/// - `token_pool_accounts` are the pool's token accounts ordered by token-id (starting with token-id 1)
+pub fn attest_state_digest(
+    pool: &AccountInfo,
+    state_summary: &StateSummaryAccount,
+    state_digest_history: &mut StateDigestHistoryAccount,
+    event_sequence: &mut EventSequenceAccount,
+    token_pool_accounts: &[&AccountInfo],
+) -> ProgramResult {
+    guard!(
+        token_pool_accounts.len() == STATS_TOKEN_COUNT - 1,
+        ElusivError::InvalidInstructionData
+    );
+
+    let mut pool_balances = vec![pool_reserves(pool, 0)?];
+    for (i, pool_account) in token_pool_accounts.iter().enumerate() {
+        let token_id = i as u16 + 1;
+        verify_program_token_account(pool, pool_account, token_id)?;
+        pool_balances.push(pool_reserves(pool_account, token_id)?);
+    }
+
+    let epoch = current_epoch()?;
+    let digest = state_summary.state_digest(epoch, &pool_balances);
+    state_digest_history.record(epoch, digest)?;
+
+    event_sequence.emit(&ElusivEvent::StateDigest { epoch, digest })
+}
+
+/// The balance of the `pool_account` (for lamports this includes the pool's rent and not yet paid out computation fees)
+fn pool_reserves(pool_account: &AccountInfo, token_id: u16) -> Result<u64, ProgramError> {
+    if token_id == 0 {
+        return Ok(pool_account.lamports());
+    }
+
+    guard!(
+        *pool_account.owner == spl_token::id(),
+        ElusivError::InvalidAccount
+    );
+    let data = pool_account.data.borrow();
+    Ok(spl_token::state::Account::unpack(&data)?.amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{account_info, parent_account, zero_program_account};
+    use crate::syscall_stubs::set_test_epoch;
+    use assert_matches::assert_matches;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_health_check() {
+        zero_program_account!(queue, CommitmentQueueAccount);
+        zero_program_account!(mut hashing_account, CommitmentHashingAccount);
+        parent_account!(mut storage_account, StorageAccount);
+        parent_account!(mut nullifier_account, NullifierAccount);
+        zero_program_account!(mut state_summary, StateSummaryAccount);
+
+        macro_rules! health_check {
+            ($active_mt_index: expr) => {
+                health_check(
+                    &queue,
+                    &hashing_account,
+                    &storage_account,
+                    &nullifier_account,
+                    &state_summary,
+                    $active_mt_index,
+                    0,
+                )
+            };
+        }
+
+        assert_matches!(health_check!(0), Ok(()));
+
+        // Invalid active MT index
+        assert_matches!(health_check!(1), Err(_));
+
+        // Active hashing with an invalid ordering
+        storage_account.set_next_commitment_ptr(&4);
+        state_summary.set_active_mt_commitment_count(&4);
+        state_summary.set_total_commitment_count(&4);
+        hashing_account.set_is_active(&true);
+        assert_matches!(health_check!(0), Err(_));
+
+        hashing_account.set_ordering(&4);
+        assert_matches!(health_check!(0), Ok(()));
+
+        // Summary out of sync with the storage account
+        state_summary.set_active_mt_commitment_count(&3);
+        assert_matches!(health_check!(0), Err(_));
+        state_summary.set_active_mt_commitment_count(&4);
+
+        // Nullifier-hash counts out of sync
+        nullifier_account.set_nullifier_hash_count(&1);
+        assert_matches!(health_check!(0), Err(_));
+        state_summary.set_nullifier_hash_count(&1);
+        assert_matches!(health_check!(0), Ok(()));
+    }
+
+    #[test]
+    fn test_attest_reserves() {
+        set_test_epoch(0);
+        account_info!(pool, Pubkey::new_unique(), vec![]);
+        account_info!(invalid_pool_account, Pubkey::new_unique(), vec![]);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+        stats.add_deposit(0, u32::MAX as u64 + 1);
+
+        // Invalid token-id
+        assert_matches!(
+            attest_reserves(
+                &pool,
+                &pool,
+                &stats,
+                &mut event_sequence,
+                STATS_TOKEN_COUNT as u16
+            ),
+            Err(_)
+        );
+
+        // Invalid pool account
+        assert_matches!(
+            attest_reserves(&pool, &invalid_pool_account, &stats, &mut event_sequence, 0),
+            Err(_)
+        );
+
+        // Insolvent pools are attested as well
+        assert_eq!(pool_reserves(&pool, 0).unwrap(), u32::MAX as u64);
+        attest_reserves(&pool, &pool, &stats, &mut event_sequence, 0).unwrap();
+        assert_eq!(event_sequence.get_sequence(), 1);
+    }
+
+    #[test]
+    fn test_attest_state_digest_invalid_token_accounts() {
+        account_info!(pool, Pubkey::new_unique(), vec![]);
+        account_info!(invalid_pool_account, Pubkey::new_unique(), vec![]);
+        zero_program_account!(state_summary, StateSummaryAccount);
+        zero_program_account!(mut history, StateDigestHistoryAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+
+        // Missing token accounts
+        assert_matches!(
+            attest_state_digest(
+                &pool,
+                &state_summary,
+                &mut history,
+                &mut event_sequence,
+                &[]
+            ),
+            Err(_)
+        );
+
+        // Invalid token accounts
+        let token_pool_accounts = vec![&invalid_pool_account; STATS_TOKEN_COUNT - 1];
+        assert_matches!(
+            attest_state_digest(
+                &pool,
+                &state_summary,
+                &mut history,
+                &mut event_sequence,
+                &token_pool_accounts
+            ),
+            Err(_)
+        );
+
+        assert_eq!(history.state_digest(0), None);
+        assert_eq!(event_sequence.get_sequence(), 0);
+    }
+}
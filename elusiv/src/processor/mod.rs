@@ -1,11 +1,41 @@
 mod accounts;
+mod batch;
+mod call;
 mod commitment;
+mod crowdfund;
+mod escrow;
+mod health;
+mod note;
+mod pending;
 mod proof;
+mod query;
+mod recurring;
+mod revenue;
+mod seal;
 mod utils;
+mod verification_pool;
+mod viewing_key;
 mod vkey;
+mod warden;
+mod withdrawal;
 
 pub use accounts::*;
+pub use batch::*;
+pub use call::*;
 pub use commitment::*;
+pub use crowdfund::*;
+pub use escrow::*;
+pub use health::*;
+pub use note::*;
+pub use pending::*;
 pub use proof::*;
+pub use query::*;
+pub use recurring::*;
+pub use revenue::*;
+pub use seal::*;
 pub use utils::{nop, program_token_account_address};
+pub use verification_pool::*;
+pub use viewing_key::*;
 pub use vkey::*;
+pub use warden::*;
+pub use withdrawal::*;
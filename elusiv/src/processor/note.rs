@@ -0,0 +1,90 @@
+use crate::computation::current_slot;
+use crate::error::ElusivError;
+use crate::macros::guard;
+use crate::state::{
+    commitment::BaseCommitmentBufferAccount,
+    note::{NoteCiphertext, NoteCiphertextAccount, NOTE_CIPHERTEXT_SIZE},
+};
+use crate::types::RawU256;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+
+/// Appends the `ciphertext` of a deposit into the shielded pool of `token_id` to its [`NoteCiphertextAccount`]
+/// - the ciphertext is only accepted alongside a recent deposit, so the `base_commitment` needs to be contained in the [`BaseCommitmentBufferAccount`]
+/// - the program cannot verify the ciphertext, wallets simply skip entries they cannot decrypt
+pub fn store_note_ciphertext(
+    _sender: &AccountInfo,
+    base_commitment_buffer: &BaseCommitmentBufferAccount,
+    note_ciphertext_account: &mut NoteCiphertextAccount,
+
+    _token_id: u16,
+    base_commitment: RawU256,
+    commitment: RawU256,
+    ciphertext: [u8; NOTE_CIPHERTEXT_SIZE],
+) -> ProgramResult {
+    guard!(
+        base_commitment_buffer.contains(&base_commitment.skip_mr()),
+        ElusivError::InvalidInstructionData
+    );
+
+    note_ciphertext_account.push(&NoteCiphertext {
+        commitment: commitment.reduce(),
+        ciphertext,
+        slot: current_slot()?,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields::u256_from_str_skip_mr;
+    use crate::macros::{account_info, zero_program_account};
+    use crate::syscall_stubs::set_test_slot;
+    use assert_matches::assert_matches;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_store_note_ciphertext() {
+        set_test_slot(0);
+        account_info!(sender, Pubkey::new_unique(), vec![]);
+        zero_program_account!(mut buffer, BaseCommitmentBufferAccount);
+        zero_program_account!(mut note_ciphertext_account, NoteCiphertextAccount);
+
+        let base_commitment = RawU256::new(u256_from_str_skip_mr("123"));
+        let commitment = RawU256::new(u256_from_str_skip_mr("456"));
+
+        // Unknown base-commitment
+        assert_matches!(
+            store_note_ciphertext(
+                &sender,
+                &buffer,
+                &mut note_ciphertext_account,
+                0,
+                base_commitment,
+                commitment,
+                [1; NOTE_CIPHERTEXT_SIZE]
+            ),
+            Err(_)
+        );
+
+        buffer.try_insert(&base_commitment.skip_mr()).unwrap();
+        assert_matches!(
+            store_note_ciphertext(
+                &sender,
+                &buffer,
+                &mut note_ciphertext_account,
+                0,
+                base_commitment,
+                commitment,
+                [1; NOTE_CIPHERTEXT_SIZE]
+            ),
+            Ok(())
+        );
+
+        let note_ciphertexts = note_ciphertext_account.note_ciphertexts_since(0);
+        assert_eq!(note_ciphertexts.len(), 1);
+        assert_eq!(note_ciphertexts[0].commitment, commitment.reduce());
+        assert_eq!(note_ciphertexts[0].ciphertext, [1; NOTE_CIPHERTEXT_SIZE]);
+    }
+}
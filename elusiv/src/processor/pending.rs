@@ -0,0 +1,432 @@
+use super::commitment::{
+    start_lamports_base_commitment_hash, verify_base_commitment, verify_epoch_deposit_cap,
+};
+use crate::computation::current_slot;
+use crate::error::ElusivError;
+use crate::macros::{guard, pda_account};
+use crate::processor::utils::transfer_lamports_from_pda_checked;
+use crate::state::{
+    commitment::BaseCommitmentBufferAccount,
+    governor::GovernorAccount,
+    pending::PendingPaymentAccount,
+    program_account::Lifecycle,
+    stats::{current_epoch, EpochMetricsAccount, StatsAccount},
+};
+use crate::types::{RawU256, U256};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+/// Opens a [`PendingPaymentAccount`]
+/// - the sender afterwards performs a regular send of `amount` lamports with the account as recipient
+/// - using the `recipient` as `claim_key` requires the recipient to claim the payment with its own signature
+/// - `hashlock` is either zero or the sha256 hash of the preimage that needs to be revealed by the claim
+#[allow(clippy::too_many_arguments)]
+pub fn create_pending_payment<'a>(
+    sender: &AccountInfo<'a>,
+    pending_payment_account: &AccountInfo<'a>,
+
+    pending_payment_index: u32,
+    claim_key: Pubkey,
+    recipient: Pubkey,
+    amount: u64,
+    expiry_slot: u64,
+    refund_base_commitment: RawU256,
+    hashlock: U256,
+) -> ProgramResult {
+    guard!(amount > 0, ElusivError::InvalidAmount);
+    verify_base_commitment(&refund_base_commitment)?;
+
+    PendingPaymentAccount::reopen(
+        sender,
+        pending_payment_account,
+        Some(*sender.key),
+        Some(pending_payment_index),
+        None,
+    )?;
+
+    pda_account!(
+        mut pending_payment_account,
+        PendingPaymentAccount,
+        pending_payment_account
+    );
+    pending_payment_account.set_sender(sender.key);
+    pending_payment_account.set_claim_key(&claim_key);
+    pending_payment_account.set_recipient(&recipient);
+    pending_payment_account.set_amount(&amount);
+    pending_payment_account.set_expiry_slot(&expiry_slot);
+    pending_payment_account.set_refund_base_commitment(&refund_base_commitment.skip_mr());
+    pending_payment_account.set_hashlock(&hashlock);
+
+    Ok(())
+}
+
+/// First finalization variant: the `claim_key` releases the `amount` to the `recipient` before the payment expires
+/// - for hashlocked payments the `preimage` is revealed on-chain (allowing the counterparty of an atomic swap to claim its side)
+pub fn claim_pending_payment<'a>(
+    claim_key: &AccountInfo<'a>,
+    recipient: &AccountInfo<'a>,
+    sender: &AccountInfo<'a>,
+    pending_payment_account_info: &AccountInfo<'a>,
+
+    _pending_payment_index: u32,
+    preimage: U256,
+) -> ProgramResult {
+    let amount = {
+        pda_account!(
+            pending_payment_account,
+            PendingPaymentAccount,
+            pending_payment_account_info
+        );
+        pending_payment_account.verify_claim(claim_key.key, &preimage, current_slot()?)?;
+        guard!(
+            pending_payment_account.get_recipient() == *recipient.key,
+            ElusivError::InvalidRecipient
+        );
+        guard!(
+            pending_payment_account.get_sender() == *sender.key,
+            ElusivError::InvalidAccount
+        );
+
+        pending_payment_account.get_amount()
+    };
+
+    // `pending_payment_account` transfers `amount` to `recipient` (lamports)
+    transfer_lamports_from_pda_checked(pending_payment_account_info, recipient, amount)?;
+
+    PendingPaymentAccount::close(sender, pending_payment_account_info)
+}
+
+/// Second finalization variant: after the payment has expired, the `amount` (reduced by the computation fees) is re-credited to the sender
+/// - the `amount` flows back into the `pool` and a new commitment is computed from the `refund_base_commitment` (like a deposit)
+#[allow(clippy::too_many_arguments)]
+pub fn refund_pending_payment<'a>(
+    fee_payer: &AccountInfo<'a>,
+    sender: &AccountInfo<'a>,
+    pending_payment_account_info: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    governor: &GovernorAccount,
+    hashing_account: &AccountInfo<'a>,
+    base_commitment_buffer: &mut BaseCommitmentBufferAccount,
+    stats: &mut StatsAccount,
+    epoch_metrics: &mut EpochMetricsAccount,
+
+    hash_account_index: u32,
+    hash_account_bump: u8,
+) -> ProgramResult {
+    guard!(!governor.get_is_halted(), ElusivError::ProgramIsHalted);
+
+    let slot = current_slot()?;
+    let (amount, refund_base_commitment) = {
+        pda_account!(
+            pending_payment_account,
+            PendingPaymentAccount,
+            pending_payment_account_info
+        );
+        pending_payment_account.verify_refund(slot)?;
+        guard!(
+            pending_payment_account.get_sender() == *sender.key,
+            ElusivError::InvalidAccount
+        );
+
+        (
+            pending_payment_account.get_amount(),
+            pending_payment_account.get_refund_base_commitment(),
+        )
+    };
+
+    // `pending_payment_account` transfers `amount` to `pool` (lamports)
+    transfer_lamports_from_pda_checked(pending_payment_account_info, pool, amount)?;
+    PendingPaymentAccount::close(sender, pending_payment_account_info)?;
+    verify_epoch_deposit_cap(governor, epoch_metrics, 0, amount)?;
+    stats.add_deposit(0, amount);
+    epoch_metrics.add_deposit(current_epoch()?, 0, amount);
+
+    start_lamports_base_commitment_hash(
+        fee_payer,
+        hashing_account,
+        governor,
+        base_commitment_buffer,
+        refund_base_commitment,
+        amount,
+        hash_account_index,
+        hash_account_bump,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{
+        account_info, test_account_info, test_pda_account_info, zero_program_account,
+    };
+    use crate::state::commitment::BaseCommitmentHashingAccount;
+    use crate::state::governor::PoolAccount;
+    use crate::state::program_account::{PDAAccount, ProgramAccount};
+    use crate::syscall_stubs::set_test_slot;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_create_pending_payment_invalid_inputs() {
+        test_account_info!(sender, 0);
+        test_account_info!(pending_payment_account, 0);
+
+        // Zero amount
+        assert_matches!(
+            create_pending_payment(
+                &sender,
+                &pending_payment_account,
+                0,
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                0,
+                0,
+                RawU256::new([1; 32]),
+                [0; 32]
+            ),
+            Err(_)
+        );
+
+        // Non-scalar base commitment
+        assert_matches!(
+            create_pending_payment(
+                &sender,
+                &pending_payment_account,
+                0,
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                1,
+                0,
+                RawU256::new([255; 32]),
+                [0; 32]
+            ),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_claim_pending_payment() {
+        set_test_slot(0);
+        let sender_pk = Pubkey::new_unique();
+        let claim_key_pk = Pubkey::new_unique();
+        let recipient_pk = Pubkey::new_unique();
+        account_info!(sender, sender_pk, vec![]);
+        account_info!(claim_key, claim_key_pk, vec![]);
+        account_info!(recipient, recipient_pk, vec![]);
+        test_pda_account_info!(
+            pending_payment_account,
+            PendingPaymentAccount,
+            sender_pk,
+            Some(0)
+        );
+
+        {
+            let data = &mut pending_payment_account.data.borrow_mut()[..];
+            let mut pending_payment = PendingPaymentAccount::new(data).unwrap();
+            pending_payment.set_sender(&sender_pk);
+            pending_payment.set_claim_key(&claim_key_pk);
+            pending_payment.set_recipient(&recipient_pk);
+            pending_payment.set_amount(&100);
+            pending_payment.set_expiry_slot(&1);
+        }
+
+        // Invalid recipient
+        assert_matches!(
+            claim_pending_payment(
+                &claim_key,
+                &sender,
+                &sender,
+                &pending_payment_account,
+                0,
+                [0; 32]
+            ),
+            Err(_)
+        );
+
+        // Invalid claim key
+        assert_matches!(
+            claim_pending_payment(
+                &recipient,
+                &recipient,
+                &sender,
+                &pending_payment_account,
+                0,
+                [0; 32]
+            ),
+            Err(_)
+        );
+
+        let lamports = u32::MAX as u64;
+        claim_pending_payment(
+            &claim_key,
+            &recipient,
+            &sender,
+            &pending_payment_account,
+            0,
+            [0; 32],
+        )
+        .unwrap();
+        assert_eq!(recipient.lamports(), lamports + 100);
+        assert_eq!(sender.lamports(), lamports * 2 - 100);
+        assert_eq!(pending_payment_account.lamports(), 0);
+    }
+
+    #[test]
+    fn test_claim_pending_payment_by_recipient() {
+        set_test_slot(0);
+        let sender_pk = Pubkey::new_unique();
+        let recipient_pk = Pubkey::new_unique();
+        account_info!(sender, sender_pk, vec![]);
+        account_info!(recipient, recipient_pk, vec![]);
+        account_info!(any, Pubkey::new_unique(), vec![]);
+        test_pda_account_info!(
+            pending_payment_account,
+            PendingPaymentAccount,
+            sender_pk,
+            Some(0)
+        );
+
+        {
+            let data = &mut pending_payment_account.data.borrow_mut()[..];
+            let mut pending_payment = PendingPaymentAccount::new(data).unwrap();
+            pending_payment.set_sender(&sender_pk);
+            pending_payment.set_claim_key(&recipient_pk);
+            pending_payment.set_recipient(&recipient_pk);
+            pending_payment.set_amount(&100);
+            pending_payment.set_expiry_slot(&1);
+        }
+
+        // Only the recipient itself can claim the payment
+        assert_matches!(
+            claim_pending_payment(
+                &any,
+                &recipient,
+                &sender,
+                &pending_payment_account,
+                0,
+                [0; 32]
+            ),
+            Err(_)
+        );
+
+        claim_pending_payment(
+            &recipient,
+            &recipient,
+            &sender,
+            &pending_payment_account,
+            0,
+            [0; 32],
+        )
+        .unwrap();
+        assert_eq!(recipient.lamports(), u32::MAX as u64 + 100);
+    }
+
+    #[test]
+    fn test_refund_pending_payment_not_expired() {
+        let sender_pk = Pubkey::new_unique();
+        account_info!(sender, sender_pk, vec![]);
+        test_pda_account_info!(
+            pending_payment_account,
+            PendingPaymentAccount,
+            sender_pk,
+            Some(0)
+        );
+        {
+            let data = &mut pending_payment_account.data.borrow_mut()[..];
+            let mut pending_payment = PendingPaymentAccount::new(data).unwrap();
+            pending_payment.set_sender(&sender_pk);
+            pending_payment.set_expiry_slot(&1);
+        }
+
+        test_account_info!(fee_payer, 0);
+        test_account_info!(pool, 0);
+        test_account_info!(hashing_account, 0);
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut buffer, BaseCommitmentBufferAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut epoch_metrics, EpochMetricsAccount);
+
+        assert_matches!(
+            refund_pending_payment(
+                &fee_payer,
+                &sender,
+                &pending_payment_account,
+                &pool,
+                &governor,
+                &hashing_account,
+                &mut buffer,
+                &mut stats,
+                &mut epoch_metrics,
+                0,
+                0
+            ),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_refund_pending_payment() {
+        set_test_slot(0);
+        let sender_pk = Pubkey::new_unique();
+        account_info!(sender, sender_pk, vec![]);
+        test_pda_account_info!(
+            pending_payment_account,
+            PendingPaymentAccount,
+            sender_pk,
+            Some(0)
+        );
+        {
+            let data = &mut pending_payment_account.data.borrow_mut()[..];
+            let mut pending_payment = PendingPaymentAccount::new(data).unwrap();
+            pending_payment.set_sender(&sender_pk);
+            pending_payment.set_amount(&1_000_000);
+            pending_payment.set_expiry_slot(&0);
+            pending_payment.set_refund_base_commitment(&[1; 32]);
+        }
+
+        test_account_info!(fee_payer, 0);
+        test_pda_account_info!(pool, PoolAccount);
+        test_pda_account_info!(hashing_account, BaseCommitmentHashingAccount, Some(0));
+        let hashing_account_bump = BaseCommitmentHashingAccount::find(Some(0)).1;
+        zero_program_account!(mut governor, GovernorAccount);
+        zero_program_account!(mut buffer, BaseCommitmentBufferAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut epoch_metrics, EpochMetricsAccount);
+
+        macro_rules! refund {
+            () => {
+                refund_pending_payment(
+                    &fee_payer,
+                    &sender,
+                    &pending_payment_account,
+                    &pool,
+                    &governor,
+                    &hashing_account,
+                    &mut buffer,
+                    &mut stats,
+                    &mut epoch_metrics,
+                    0,
+                    hashing_account_bump,
+                )
+            };
+        }
+
+        governor.set_is_halted(&true);
+        assert_matches!(refund!(), Err(_));
+        governor.set_is_halted(&false);
+
+        let lamports = u32::MAX as u64;
+        refund!().unwrap();
+
+        // The `amount` flows into the pool and the sender receives the rent
+        assert_eq!(pool.lamports(), lamports + 1_000_000);
+        assert_eq!(sender.lamports(), lamports * 2 - 1_000_000);
+        assert_eq!(pending_payment_account.lamports(), 0);
+
+        // The refund is accounted as a deposit
+        assert_eq!(stats.get_deposit_count(), 1);
+        assert_eq!(stats.get_tvl(0), 1_000_000);
+
+        let data = &mut hashing_account.data.borrow_mut()[..];
+        let hashing_account = BaseCommitmentHashingAccount::new(data).unwrap();
+        assert!(hashing_account.get_is_active());
+    }
+}
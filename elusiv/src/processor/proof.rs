@@ -1,36 +1,53 @@
 use super::utils::{DefaultInstructionsSysvar, InstructionsSysvar};
 use super::CommitmentHashRequest;
 use crate::bytes::{usize_as_u32_safe, BorshSerDeSized, ElusivOption};
+use crate::computation::{
+    current_slot, PartialComputationDriver, COMPUTATION_TIMEOUT_SLASHING_BPS,
+};
 use crate::error::ElusivError;
+use crate::events::ElusivEvent;
 use crate::instruction::ElusivInstruction;
 use crate::macros::{guard, pda_account, BorshSerDeSized, EnumVariantIndex};
+use crate::oracle::token_price;
 use crate::processor::utils::{
-    close_account, create_associated_token_account, spl_token_account_rent,
-    system_program_account_rent, transfer_lamports_from_pda_checked, transfer_token,
-    transfer_token_from_pda, verify_program_token_account,
+    create_associated_token_account, spl_token_account_rent, system_program_account_rent,
+    transfer_lamports_from_pda_checked, transfer_token, transfer_token_from_pda,
+    verify_program_token_account,
+};
+use crate::processor::{
+    escrow_warden_reward, is_pending_withdrawal_account, lock_pending_withdrawal,
+    recurring_installment_hash, slash_warden_stake, transfer_pooled_verification_account_deposit,
+    verify_registered_warden, ZERO_COMMITMENT_RAW,
 };
-use crate::processor::ZERO_COMMITMENT_RAW;
 use crate::proof::verifier::{prepare_public_inputs_instructions, verify_partial};
 use crate::proof::vkey::{MigrateUnaryVKey, SendQuadraVKey, VerifyingKey, VerifyingKeyInfo};
+use crate::state::denylist::{
+    recipient_denylist_offset, recipient_denylist_shard, RecipientDenylistAccount,
+};
+use crate::state::event::EventSequenceAccount;
 use crate::state::governor::{FeeCollectorAccount, GovernorAccount, PoolAccount};
-use crate::state::nullifier::NullifierAccount;
+use crate::state::nullifier::{nullifier_account_offsets, ArchivedTreeAccount, NullifierAccount};
+use crate::state::program_account::Lifecycle;
 use crate::state::proof::{
     NullifierDuplicateAccount, VerificationAccount, VerificationAccountData, VerificationState,
+    VERIFICATION_ACCOUNT_POOL_SIZE,
 };
 use crate::state::queue::{CommitmentQueue, CommitmentQueueAccount, Queue, RingQueue};
+use crate::state::recurring::RecurringPaymentAccount;
+use crate::state::stats::{current_epoch, EpochMetricsAccount, StatsAccount};
 use crate::state::storage::{StorageAccount, MT_COMMITMENT_COUNT};
+use crate::state::summary::StateSummaryAccount;
 use crate::state::vkey::VKeyAccount;
 use crate::token::{
     elusiv_token, verify_associated_token_account, verify_token_account, Lamports, Token,
-    TokenPrice,
 };
 use crate::types::{
-    generate_hashed_inputs, InputCommitment, JoinSplitPublicInputs, MigratePublicInputs, Proof,
-    PublicInputs, RawU256, SendPublicInputs, JOIN_SPLIT_MAX_N_ARITY, U256,
+    generate_hashed_inputs, BatchPayouts, HashedInputs, InputCommitment, JoinSplitPublicInputs,
+    MigratePublicInputs, Proof, PublicInputs, RawU256, RecurringPaymentBinding, SendPublicInputs,
+    JOIN_SPLIT_MAX_N_ARITY, U256,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
-use elusiv_types::ParentAccount;
-use elusiv_utils::open_pda_account_with_associated_pubkey;
+use elusiv_types::{PDAAccount, ParentAccount, UnverifiedAccountInfo};
 use solana_program::instruction::Instruction;
 use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
@@ -66,6 +83,15 @@ impl ProofRequest {
         )
     }
 
+    /// The token (and with it the shielded pool) of the join-split
+    pub fn token_id(&self) -> u16 {
+        proof_request!(
+            self,
+            public_inputs,
+            public_inputs.join_split_inputs().token_id
+        )
+    }
+
     /// The amount used to compute the fee
     pub fn proof_fee_amount(&self) -> u64 {
         match self {
@@ -88,6 +114,17 @@ pub const MAX_MT_COUNT: usize = 2;
 /// The maximum [`PDAOffset`] for [`VerificationAccount`] for a single fee payer
 pub const RESERVED_VERIFICATION_ACCOUNT_IDS: u8 = 128;
 
+/// The `verification_account_index` of the pooled [`VerificationAccount`] of `slot` (see [`crate::state::proof::VerificationAccountPoolAccount`])
+pub fn pooled_verification_account_index(slot: u8) -> u8 {
+    RESERVED_VERIFICATION_ACCOUNT_IDS + 1 + slot
+}
+
+pub fn is_pooled_verification_account_index(verification_account_index: u8) -> bool {
+    verification_account_index > RESERVED_VERIFICATION_ACCOUNT_IDS
+        && ((verification_account_index - RESERVED_VERIFICATION_ACCOUNT_IDS - 1) as usize)
+            < VERIFICATION_ACCOUNT_POOL_SIZE
+}
+
 /// Initializes a new proof verification
 /// - subsequent calls of [`init_verification_transfer_fee`] and [`init_verification_proof`] required to start the computation
 /// - both need to be called by the same signer (-> the fee structure "enforces" [`init_verification_transfer_fee`] to be called in the same transaction)
@@ -101,6 +138,9 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
     storage_account: &StorageAccount,
     nullifier_account0: &NullifierAccount<'b, 'c, 'd>,
     nullifier_account1: &NullifierAccount<'b, 'c, 'd>,
+    governor: &GovernorAccount,
+    warden_account: UnverifiedAccountInfo,
+    archived_tree_accounts: &[&AccountInfo],
 
     verification_account_index: u8,
     vkey_id: u32,
@@ -108,14 +148,35 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
     request: ProofRequest,
     skip_nullifier_pda: bool,
 ) -> ProgramResult {
+    guard!(
+        !governor.is_proof_verification_halted(),
+        ElusivError::ProgramIsHalted
+    );
+
+    // In emergency mode users can exit without a relayer, by initializing (and computing) the verification themselves
+    if !governor.get_emergency_mode() {
+        verify_registered_warden(governor, fee_payer, warden_account)?;
+    }
+
     let raw_public_inputs = proof_request!(&request, public_inputs, public_inputs.public_signals());
 
     // Verify that an immutable vkey is setup
     guard!(vkey_account.get_version() != 0, ElusivError::InvalidAccount);
+    guard!(vkey_account.is_sealed(), ElusivError::InvalidAccount);
 
-    guard!(vkey_id == request.vkey_id(), ElusivError::InvalidAccount);
+    // The verifying key needs to be the active (or the previous, during the acceptance window) key of the circuit
+    guard!(
+        governor.vkey_upgrade(request.vkey_id())?.accepts(
+            request.vkey_id(),
+            vkey_id,
+            &vkey_account.get_checksum(),
+            current_slot()?
+        ),
+        ElusivError::InvalidAccount
+    );
+    let is_pooled = is_pooled_verification_account_index(verification_account_index);
     guard!(
-        verification_account_index <= RESERVED_VERIFICATION_ACCOUNT_IDS,
+        verification_account_index <= RESERVED_VERIFICATION_ACCOUNT_IDS || is_pooled,
         ElusivError::InvalidAccount
     );
 
@@ -126,6 +187,7 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
             public_inputs.public_signals_skip_mr()
         ),
         vkey_account.get_public_inputs_count() as usize,
+        governor.get_compute_unit_margin(),
     );
 
     // TODO: reject zero-commitment nullifier
@@ -152,6 +214,7 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
         storage_account,
         [nullifier_account0, nullifier_account1],
         &tree_indices,
+        archived_tree_accounts,
     )?;
 
     // Open [`NullifierDuplicateAccount`]
@@ -169,31 +232,49 @@ pub fn init_verification<'a, 'b, 'c, 'd>(
             return Err(ElusivError::InvalidInstructionData.into());
         }
     } else {
-        open_pda_account_with_associated_pubkey::<NullifierDuplicateAccount>(
-            &crate::id(),
+        NullifierDuplicateAccount::reopen(
             fee_payer,
             nullifier_duplicate_account,
-            &join_split.associated_nullifier_duplicate_pda_pubkey(),
+            Some(join_split.associated_nullifier_duplicate_pda_pubkey()),
             None,
             None,
         )?;
     }
 
-    // Open `VerificationAccount`
-    open_pda_account_with_associated_pubkey::<VerificationAccount>(
-        &crate::id(),
-        fee_payer,
-        verification_account,
-        fee_payer.key,
-        Some(verification_account_index as u32),
-        None,
-    )?;
+    // Open `VerificationAccount` (pooled accounts are already open and need to be acquired)
+    if is_pooled {
+        guard!(
+            *verification_account.owner == crate::ID,
+            ElusivError::InvalidAccount
+        );
+        pda_account!(
+            verification_account,
+            VerificationAccount,
+            verification_account
+        );
+        guard!(
+            verification_account.get_is_pooled()
+                && verification_account.get_is_acquired()
+                && matches!(verification_account.get_state(), VerificationState::None)
+                && verification_account.get_other_data() == VerificationAccountData::default(),
+            ElusivError::InvalidAccountState
+        );
+    } else {
+        VerificationAccount::reopen(
+            fee_payer,
+            verification_account,
+            Some(*fee_payer.key),
+            Some(verification_account_index as u32),
+            None,
+        )?;
+    }
 
     pda_account!(
         mut verification_account,
         VerificationAccount,
         verification_account
     );
+    verification_account.start_computation(current_slot()?);
     verification_account.setup(
         RawU256::new(fee_payer.key.to_bytes()),
         skip_nullifier_pda,
@@ -245,10 +326,17 @@ pub fn init_verification_transfer_fee<'a>(
         ElusivError::InvalidFeeVersion
     );
     let token_id = join_split.token_id;
-    let price = TokenPrice::new(sol_usd_price_account, token_usd_price_account, token_id)?;
+    let price = token_price(
+        governor,
+        sol_usd_price_account,
+        token_usd_price_account,
+        token_id,
+    )?;
     let min_batching_rate = governor.get_commitment_batching_rate();
-    let fee = governor.get_program_fee();
-    let subvention = fee.proof_subvention.into_token(&price, token_id)?;
+    let fee = governor.program_fee_for_token(token_id);
+    let subvention = fee
+        .proof_subvention(join_split.amount)
+        .into_token(&price, token_id)?;
     let input_preparation_tx_count =
         verification_account.get_prepare_inputs_instructions_count() as usize;
     let proof_verification_fee = fee
@@ -256,7 +344,13 @@ pub fn init_verification_transfer_fee<'a>(
         .into_token(&price, token_id)?;
     let commitment_hash_fee = fee.commitment_hash_computation_fee(min_batching_rate);
     let commitment_hash_fee_token = commitment_hash_fee.into_token(&price, token_id)?;
-    let network_fee = Token::new(token_id, fee.proof_network_fee.calc(join_split.amount));
+
+    // No network-fee is charged for exits in emergency mode
+    let network_fee = if governor.get_emergency_mode() {
+        Token::new(token_id, 0)
+    } else {
+        Token::new(token_id, fee.proof_network_fee.calc(join_split.amount))
+    };
 
     let fee =
         (((commitment_hash_fee_token + proof_verification_fee)? + network_fee)? - subvention)?;
@@ -274,6 +368,7 @@ pub fn init_verification_transfer_fee<'a>(
         }
 
         // Enforce minimum-send-amount for Lamports (zero-amount is always allowed for merges)
+        // - the withdrawal funds the rent-exemption of brand-new recipients (see `FeeAccount::recipient_rent_minimum`)
         if token_id == 0 && public_inputs.join_split.amount != 0 {
             guard!(
                 public_inputs.join_split.amount >= system_program_account_rent()?.0,
@@ -315,6 +410,11 @@ pub fn init_verification_transfer_fee<'a>(
         None,
     )?;
 
+    {
+        pda_account!(mut fee_collector, FeeCollectorAccount, fee_collector);
+        fee_collector.add_subvention(governor.get_fee_version(), token_id, subvention.amount());
+    }
+
     // TODO: switch fee_payer_token_account to associated-token-account
     guard!(
         verify_token_account(fee_payer_token_account, token_id)?,
@@ -325,6 +425,8 @@ pub fn init_verification_transfer_fee<'a>(
         fee_payer: RawU256::new(fee_payer.key.to_bytes()),
         fee_payer_account: RawU256::new(fee_payer_token_account.key.to_bytes()),
         recipient_wallet: ElusivOption::None,
+        recipient_is_pending_withdrawal: false,
+        batch_payouts: ElusivOption::None,
         skip_nullifier_pda: other_data.skip_nullifier_pda,
         min_batching_rate,
         token_id,
@@ -337,6 +439,7 @@ pub fn init_verification_transfer_fee<'a>(
     });
 
     verification_account.set_state(&VerificationState::FeeTransferred);
+    verification_account.record_computation_round(current_slot()?);
 
     Ok(())
 }
@@ -376,6 +479,7 @@ pub fn init_verification_proof(
     verification_account.c.set(proof.c);
 
     verification_account.set_state(&VerificationState::ProofSetup);
+    verification_account.record_computation_round(current_slot()?);
 
     Ok(())
 }
@@ -411,17 +515,14 @@ pub fn compute_verification(
     );
 
     // instruction_index is used to allow a uniform number of ixs per tx
-    let instruction_index = if cfg!(test) {
-        COMPUTE_VERIFICATION_IX_COUNT - 1
-    } else {
-        instructions::load_current_index_checked(instructions_account)?
-    };
+    let instruction_index = instructions::load_current_index_checked(instructions_account)?;
 
+    let slot = current_slot()?;
     let result = vkey_account.execute_on_child_account_mut(0, |data| {
         let vkey = VerifyingKey::new(data, vkey_account.get_public_inputs_count() as usize)
             .ok_or(ElusivError::InvalidAccountState)?;
 
-        verify_partial(verification_account, &vkey, instruction_index)
+        verify_partial(verification_account, &vkey, instruction_index, slot)
     })?;
 
     match result {
@@ -446,7 +547,9 @@ pub fn compute_verification(
     }
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Clone, Default)]
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Clone, Default)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct FinalizeSendData {
     pub total_amount: u64,
     pub token_id: u16,
@@ -459,6 +562,12 @@ pub struct FinalizeSendData {
 
     pub iv: U256,
     pub encrypted_owner: U256,
+
+    /// Installment of a recurring payment the proof is bound to
+    pub recurring_payment: Option<RecurringPaymentBinding>,
+
+    /// Recipients splitting the withdrawn amount (instead of the `recipient`)
+    pub batch_payouts: Option<BatchPayouts>,
 }
 
 const SPL_MEMO_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
@@ -479,6 +588,12 @@ const SPL_MEMO_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
 /// - for an invalid proof:
 ///     [`finalize_verification_send`],
 ///     [`finalize_verification_transfer_lamports`] or [`finalize_verification_transfer_token`].
+///
+/// A proof bound to a recurring payment installment can only be finalized after the release of the installment,
+/// which requires its [`RecurringPaymentAccount`] in `lookup_accounts`.
+///
+/// A proof bound to [`BatchPayouts`] pays all of their recipients (instead of the `recipient`) in [`finalize_verification_transfer_lamports`],
+/// which requires the [`RecipientDenylistAccount`] shards of all recipients in `lookup_accounts`.
 #[allow(clippy::too_many_arguments)]
 pub fn finalize_verification_send(
     recipient: &AccountInfo,
@@ -487,12 +602,19 @@ pub fn finalize_verification_send(
     commitment_hash_queue: &mut CommitmentQueueAccount,
     verification_account: &mut VerificationAccount,
     storage_account: &StorageAccount,
+    recipient_denylist: &RecipientDenylistAccount,
+    governor: &GovernorAccount,
     instructions_account: &AccountInfo,
+    lookup_accounts: &[&AccountInfo],
 
     verification_account_index: u8,
     data: FinalizeSendData,
     uses_memo: bool,
 ) -> ProgramResult {
+    guard!(
+        !governor.is_proof_verification_halted(),
+        ElusivError::ProgramIsHalted
+    );
     guard!(
         matches!(
             verification_account.get_state(),
@@ -502,7 +624,7 @@ pub fn finalize_verification_send(
     );
 
     let request = verification_account.get_request();
-    let public_inputs = match request {
+    let public_inputs = match &request {
         ProofRequest::Send(public_inputs) => public_inputs,
         _ => return Err(ElusivError::FeatureNotAvailable.into()),
     };
@@ -518,27 +640,34 @@ pub fn finalize_verification_send(
     };
 
     // Verify `hashed_inputs`
-    let hash = generate_hashed_inputs(
-        recipient.key.to_bytes(),
-        identifier_account.key.to_bytes(),
-        data.iv,
-        data.encrypted_owner,
-        if transaction_reference.key != instructions_account.key {
+    let hash = generate_hashed_inputs(&HashedInputs {
+        recipient: recipient.key.to_bytes(),
+        identifier: identifier_account.key.to_bytes(),
+        iv: data.iv,
+        encrypted_owner: data.encrypted_owner,
+        transaction_reference: if transaction_reference.key != instructions_account.key {
             transaction_reference.key.to_bytes()
         } else {
             [0; 32]
         },
-        public_inputs.recipient_is_associated_token_account,
-        &memo,
-    );
+        is_associated_token_account: public_inputs.recipient_is_associated_token_account,
+        memo: memo.as_deref(),
+        encrypted_memo: public_inputs.encrypted_memo.as_ref(),
+        recurring_payment: data.recurring_payment.as_ref(),
+        batch_payouts: data.batch_payouts.as_ref(),
+    });
     guard!(
         hash == public_inputs.hashed_inputs,
         ElusivError::InputsMismatch
     );
 
-    // Set `recipient_wallet`
+    // Set `recipient_wallet` and `batch_payouts`
+    let recipient_is_pending_withdrawal = is_pending_withdrawal_account(recipient);
+    let batch_payouts = data.batch_payouts;
     verification_account.set_other_data(&mutate(&verification_account.get_other_data(), |data| {
-        data.recipient_wallet = ElusivOption::Some(RawU256::new(recipient.key.to_bytes()))
+        data.recipient_wallet = ElusivOption::Some(RawU256::new(recipient.key.to_bytes()));
+        data.recipient_is_pending_withdrawal = recipient_is_pending_withdrawal;
+        data.batch_payouts = batch_payouts.into();
     }));
 
     match verification_account.get_is_verified() {
@@ -550,6 +679,53 @@ pub fn finalize_verification_send(
         _ => {}
     }
 
+    // Payouts to denied recipients are rejected (Wardens should consult the denylist before relaying)
+    guard!(
+        !recipient_denylist.is_denied(recipient.key),
+        ElusivError::RecipientIsDenied
+    );
+
+    // Installments of recurring payments can only be finalized after their release
+    if let Some(recurring_payment) = &data.recurring_payment {
+        guard!(
+            is_released_installment(
+                lookup_accounts,
+                recurring_payment,
+                &recurring_installment_hash(&request)?,
+            )?,
+            ElusivError::InstallmentIsNotYetDue
+        );
+    }
+
+    // Batch payouts split the amount between their recipients (which is only supported for lamports)
+    if let Some(batch_payouts) = &data.batch_payouts {
+        guard!(
+            public_inputs.join_split.token_id == 0 && !public_inputs.solana_pay_transfer,
+            ElusivError::FeatureNotAvailable
+        );
+        guard!(
+            batch_payouts.total_amount() == Some(public_inputs.join_split.amount),
+            ElusivError::InvalidAmount
+        );
+
+        for (recipient, _) in batch_payouts.payouts() {
+            guard!(
+                !is_denied_recipient(lookup_accounts, &recipient)?,
+                ElusivError::RecipientIsDenied
+            );
+        }
+    }
+
+    // Large withdrawals need to be timelocked (which is only supported for lamports and single recipients)
+    if public_inputs.join_split.token_id == 0
+        && governor.requires_withdrawal_timelock(public_inputs.join_split.amount)
+    {
+        guard!(
+            recipient_is_pending_withdrawal && data.batch_payouts.is_none(),
+            ElusivError::WithdrawalTimelockIsRequired
+        );
+    }
+
     enforce_finalize_send_instructions(
         instructions_account,
         public_inputs.join_split.token_id == 0,
@@ -576,7 +752,7 @@ pub fn finalize_verification_send(
     guard!(data.mt_index == mt_index, ElusivError::InputsMismatch);
 
     verification_account.set_state(&VerificationState::InsertNullifiers);
-    verification_account.set_instruction(&0);
+    verification_account.start_computation(current_slot()?);
 
     Ok(())
 }
@@ -584,6 +760,9 @@ pub fn finalize_verification_send(
 pub fn finalize_verification_insert_nullifier(
     verification_account: &mut VerificationAccount,
     nullifier_account: &mut NullifierAccount,
+    state_summary: &mut StateSummaryAccount,
+    stats: &mut StatsAccount,
+    event_sequence: &mut EventSequenceAccount,
 
     _verification_account_index: u8,
 ) -> ProgramResult {
@@ -628,8 +807,16 @@ pub fn finalize_verification_insert_nullifier(
             }
 
             if index == input_commitment_index {
-                nullifier_account
-                    .try_insert_nullifier_hash(input_commitment.nullifier_hash.reduce())?;
+                let nullifier_hash = input_commitment.nullifier_hash.reduce();
+                nullifier_account.try_insert_nullifier_hash(nullifier_hash)?;
+                nullifier_account.set_last_activity_slot(&current_slot()?);
+                state_summary.update_nullifier_hash(&nullifier_hash)?;
+                stats.add_nullifier();
+                event_sequence.emit(&ElusivEvent::NullifierSpend {
+                    token_id: verification_account.get_other_data().token_id,
+                    mt_index: verification_account.get_tree_indices(tree_index),
+                    nullifier_hash,
+                })?;
                 break;
             }
         }
@@ -639,6 +826,7 @@ pub fn finalize_verification_insert_nullifier(
         // Insert moved nullifier hashes
         nullifier_account.move_nullifier_hashes_to_next_account()?;
     }
+    verification_account.record_computation_round(current_slot()?);
 
     if input_commitment_index >= public_inputs.join_split.input_commitments.len() - 1
         && nullifier_account.is_moved_nullifier_empty()
@@ -659,6 +847,12 @@ pub fn finalize_verification_transfer_lamports<'a>(
     verification_account_info: &AccountInfo<'a>,
     nullifier_duplicate_account: &AccountInfo<'a>,
     instructions_account: &AccountInfo,
+    event_sequence: &mut EventSequenceAccount,
+    stats: &mut StatsAccount,
+    epoch_metrics: &mut EpochMetricsAccount,
+    governor: &GovernorAccount,
+    warden_account: UnverifiedAccountInfo,
+    batch_payout_recipients: &[&AccountInfo<'a>],
 
     _verification_account_index: u8,
 ) -> ProgramResult {
@@ -692,11 +886,14 @@ pub fn finalize_verification_transfer_lamports<'a>(
 
     // Invalid proof
     if let ElusivOption::Some(false) = verification_account.get_is_verified() {
-        // `rent` and `commitment_hash_fee` flow to `fee_collector`
-        close_account(fee_collector, verification_account_info)?;
-        if !data.skip_nullifier_pda {
-            close_account(fee_collector, nullifier_duplicate_account)?;
-        }
+        // `rent` (or the deposit of pooled accounts) and `commitment_hash_fee` flow to `fee_collector`
+        close_verification_pdas(
+            fee_collector,
+            verification_account_info,
+            nullifier_duplicate_account,
+            data.skip_nullifier_pda,
+            verification_account.get_is_pooled(),
+        )?;
 
         verification_account.set_state(&VerificationState::Closed);
 
@@ -706,6 +903,9 @@ pub fn finalize_verification_transfer_lamports<'a>(
         // `pool` transfers `commitment_hash_fee` to `fee_collector` (lamports)
         transfer_lamports_from_pda_checked(pool, fee_collector, data.commitment_hash_fee.0)?;
 
+        pda_account!(mut fee_collector, FeeCollectorAccount, fee_collector);
+        fee_collector.refund_subvention(join_split.fee_version, 0, data.subvention);
+
         return Ok(());
     }
 
@@ -716,53 +916,84 @@ pub fn finalize_verification_transfer_lamports<'a>(
                 ElusivError::InvalidRecipient
             );
 
-            if public_inputs.solana_pay_transfer {
-                // `pool` transfers `amount` to `original_fee_payer` (lamports)
-                transfer_lamports_from_pda_checked(
-                    pool,
-                    original_fee_payer,
-                    public_inputs.join_split.amount,
-                )?;
+            if let ElusivOption::Some(batch_payouts) = &data.batch_payouts {
+                guard!(
+                    batch_payout_recipients.len() == batch_payouts.recipient_count as usize,
+                    ElusivError::InvalidRecipient
+                );
 
-                // Last instruction: `original_fee_payer` transfers `amount` to `recipient`
-                let instructions_sysvar = DefaultInstructionsSysvar(instructions_account);
-                enforce_instruction(
-                    &instructions_sysvar,
-                    instructions_sysvar.find_instruction_count()? - 1,
-                    &system_instruction::transfer(
-                        original_fee_payer.key,
-                        recipient.key,
-                        public_inputs.join_split.amount,
-                    ),
-                    false,
-                )?;
+                for (recipient, (key, amount)) in
+                    batch_payout_recipients.iter().zip(batch_payouts.payouts())
+                {
+                    guard!(*recipient.key == key, ElusivError::InvalidRecipient);
+
+                    // `pool` transfers `amount` to `recipient` (lamports)
+                    transfer_lamports_from_pda_checked(pool, recipient, amount)?;
+                }
             } else {
-                // `pool` transfers `amount` to `recipient` (lamports)
-                transfer_lamports_from_pda_checked(
-                    pool,
+                if public_inputs.solana_pay_transfer {
+                    // `pool` transfers `amount` to `original_fee_payer` (lamports)
+                    transfer_lamports_from_pda_checked(
+                        pool,
+                        original_fee_payer,
+                        public_inputs.join_split.amount,
+                    )?;
+
+                    // Last instruction: `original_fee_payer` transfers `amount` to `recipient`
+                    let instructions_sysvar = DefaultInstructionsSysvar(instructions_account);
+                    enforce_instruction(
+                        &instructions_sysvar,
+                        instructions_sysvar.find_instruction_count()? - 1,
+                        &system_instruction::transfer(
+                            original_fee_payer.key,
+                            recipient.key,
+                            public_inputs.join_split.amount,
+                        ),
+                        false,
+                    )?;
+                } else {
+                    // `pool` transfers `amount` to `recipient` (lamports)
+                    transfer_lamports_from_pda_checked(
+                        pool,
+                        recipient,
+                        public_inputs.join_split.amount,
+                    )?;
+                }
+
+                lock_pending_withdrawal(
                     recipient,
+                    governor,
                     public_inputs.join_split.amount,
+                    current_slot()?,
+                    data.recipient_is_pending_withdrawal,
                 )?;
             }
         }
     }
 
-    // `pool` transfers `commitment_hash_fee_token (incl. subvention) + proof_verification_fee` to `fee_payer` (lamports)
-    transfer_lamports_from_pda_checked(
-        pool,
-        original_fee_payer,
-        (Lamports(data.commitment_hash_fee_token) + Lamports(data.proof_verification_fee))?.0,
-    )?;
+    // `commitment_hash_fee_token (incl. subvention) + proof_verification_fee` are escrowed for a warden
+    let reward =
+        (Lamports(data.commitment_hash_fee_token) + Lamports(data.proof_verification_fee))?.0;
+    if !escrow_warden_reward(original_fee_payer, warden_account, 0, reward)? {
+        // `pool` transfers `reward` to `fee_payer` (lamports)
+        transfer_lamports_from_pda_checked(pool, original_fee_payer, reward)?;
+    }
 
     // `pool` transfers `network_fee` to `fee_collector` (lamports)
     transfer_lamports_from_pda_checked(pool, fee_collector, data.network_fee)?;
 
+    {
+        pda_account!(mut fee_collector, FeeCollectorAccount, fee_collector);
+        fee_collector.add_network_fee(join_split.fee_version, 0, data.network_fee);
+    }
+
     // Close `verification_account` and `nullifier_duplicate_account`
     close_verification_pdas(
         original_fee_payer,
         verification_account_info,
         nullifier_duplicate_account,
         data.skip_nullifier_pda,
+        verification_account.get_is_pooled(),
     )?;
 
     let mut commitment_queue = CommitmentQueue::new(commitment_hash_queue);
@@ -770,13 +1001,50 @@ pub fn finalize_verification_transfer_lamports<'a>(
         commitment: join_split.output_commitment.reduce(),
         fee_version: join_split.fee_version,
         min_batching_rate: data.min_batching_rate,
+        enqueue_slot: current_slot()?,
     })?;
 
     verification_account.set_state(&VerificationState::Closed);
 
+    if let ProofRequest::Send(public_inputs) = &request {
+        if public_inputs.join_split.amount > 0 {
+            stats.add_withdrawal(0, public_inputs.join_split.amount);
+            epoch_metrics.add_withdrawal(current_epoch()?, 0, public_inputs.join_split.amount);
+
+            let payouts = match &data.batch_payouts {
+                ElusivOption::Some(batch_payouts) => batch_payouts.payouts().collect(),
+                ElusivOption::None => vec![(*recipient.key, public_inputs.join_split.amount)],
+            };
+            for (recipient, amount) in payouts {
+                event_sequence.emit(&ElusivEvent::Withdrawal {
+                    recipient,
+                    amount,
+                    token_id: 0,
+                    fee_version: join_split.fee_version,
+                    output_commitment: join_split.output_commitment.reduce(),
+                })?;
+                if let Some(encrypted_memo) = public_inputs.encrypted_memo {
+                    event_sequence.emit(&ElusivEvent::WithdrawalMemo {
+                        recipient,
+                        encrypted_memo,
+                    })?;
+                }
+            }
+        } else {
+            event_sequence.emit(&ElusivEvent::ShieldedTransfer {
+                token_id: 0,
+                fee_version: join_split.fee_version,
+                output_commitment: join_split.output_commitment.reduce(),
+            })?;
+        }
+    }
+
     Ok(())
 }
 
+/// Last finalize instruction for token transfers
+/// - if the recipient is an associated token account that does not exist yet, it is created (with `original_fee_payer` paying the rent)
+/// - the rent is prepaid by the warden in [`init_verification_transfer_fee`] and refunded from the withdrawn amount, so withdrawals to fresh wallets don't fail
 #[allow(clippy::too_many_arguments)]
 pub fn finalize_verification_transfer_token<'a>(
     original_fee_payer: &AccountInfo<'a>,
@@ -793,8 +1061,13 @@ pub fn finalize_verification_transfer_token<'a>(
     token_program: &AccountInfo<'a>,
     mint_account: &AccountInfo<'a>,
     instructions_account: &AccountInfo,
+    event_sequence: &mut EventSequenceAccount,
+    stats: &mut StatsAccount,
+    epoch_metrics: &mut EpochMetricsAccount,
+    warden_account: UnverifiedAccountInfo,
 
     _verification_account_index: u8,
+    token_id: u16,
 ) -> ProgramResult {
     pda_account!(
         mut verification_account,
@@ -806,9 +1079,14 @@ pub fn finalize_verification_transfer_token<'a>(
     let join_split = proof_request!(&request, public_inputs, public_inputs.join_split_inputs());
     let recipient_address = data.recipient_wallet.option().unwrap().skip_mr();
 
-    let token_id = join_split.token_id;
     guard!(token_id > 0, ElusivError::InvalidAccountState);
 
+    // The output commitment is enqueued into the pool of the join-split token
+    guard!(
+        token_id == join_split.token_id,
+        ElusivError::InvalidAccountState
+    );
+
     guard!(
         matches!(
             verification_account.get_state(),
@@ -835,12 +1113,13 @@ pub fn finalize_verification_transfer_token<'a>(
 
     // Invalid proof
     if let ElusivOption::Some(false) = verification_account.get_is_verified() {
-        // rent flows to `fee_collector`
+        // `rent` (or the deposit of pooled accounts) flows to `fee_collector`
         close_verification_pdas(
             fee_collector,
             verification_account_info,
             nullifier_duplicate_account,
             data.skip_nullifier_pda,
+            verification_account.get_is_pooled(),
         )?;
 
         verification_account.set_state(&VerificationState::Closed);
@@ -863,6 +1142,9 @@ pub fn finalize_verification_transfer_token<'a>(
             (data.commitment_hash_fee + spl_token_account_rent()?)?.0,
         )?;
 
+        pda_account!(mut fee_collector, FeeCollectorAccount, fee_collector);
+        fee_collector.refund_subvention(join_split.fee_version, token_id, data.subvention);
+
         return Ok(());
     }
 
@@ -964,15 +1246,25 @@ pub fn finalize_verification_transfer_token<'a>(
         }
     }
 
-    // `pool` transfers `commitment_hash_fee_token (incl. subvention) + proof_verification_fee + associated_token_account_rent_token?` to `fee_payer` (token)
+    // `commitment_hash_fee_token (incl. subvention) + proof_verification_fee` are escrowed for a warden
+    let mut reward = (Token::new(token_id, data.commitment_hash_fee_token)
+        + Token::new(token_id, data.proof_verification_fee))?;
+    if escrow_warden_reward(
+        original_fee_payer,
+        warden_account,
+        token_id,
+        reward.amount(),
+    )? {
+        reward = Token::new(token_id, 0);
+    }
+
+    // `pool` transfers `reward? + associated_token_account_rent_token?` to `fee_payer` (token)
     transfer_token_from_pda::<PoolAccount>(
         pool,
         pool_account,
         original_fee_payer_account,
         token_program,
-        ((Token::new(token_id, data.commitment_hash_fee_token)
-            + Token::new(token_id, data.proof_verification_fee))?
-            + Token::new(token_id, associated_token_account_rent_token.unwrap_or(0)))?,
+        (reward + Token::new(token_id, associated_token_account_rent_token.unwrap_or(0)))?,
         None,
         None,
     )?;
@@ -988,12 +1280,18 @@ pub fn finalize_verification_transfer_token<'a>(
         None,
     )?;
 
+    {
+        pda_account!(mut fee_collector, FeeCollectorAccount, fee_collector);
+        fee_collector.add_network_fee(join_split.fee_version, token_id, data.network_fee);
+    }
+
     // Close `verification_account` and `nullifier_duplicate_account`
     close_verification_pdas(
         original_fee_payer,
         verification_account_info,
         nullifier_duplicate_account,
         data.skip_nullifier_pda,
+        verification_account.get_is_pooled(),
     )?;
 
     if associated_token_account_rent_token.is_some() {
@@ -1005,22 +1303,164 @@ pub fn finalize_verification_transfer_token<'a>(
         commitment: join_split.output_commitment.reduce(),
         fee_version: join_split.fee_version,
         min_batching_rate: data.min_batching_rate,
+        enqueue_slot: current_slot()?,
     })?;
 
     verification_account.set_state(&VerificationState::Closed);
 
+    if let ProofRequest::Send(public_inputs) = &request {
+        if public_inputs.join_split.amount > 0 {
+            stats.add_withdrawal(token_id, public_inputs.join_split.amount);
+            epoch_metrics.add_withdrawal(
+                current_epoch()?,
+                token_id,
+                public_inputs.join_split.amount,
+            );
+            event_sequence.emit(&ElusivEvent::Withdrawal {
+                recipient: Pubkey::new_from_array(recipient_address),
+                amount: public_inputs.join_split.amount,
+                token_id,
+                fee_version: join_split.fee_version,
+                output_commitment: join_split.output_commitment.reduce(),
+            })?;
+            if let Some(encrypted_memo) = public_inputs.encrypted_memo {
+                event_sequence.emit(&ElusivEvent::WithdrawalMemo {
+                    recipient: Pubkey::new_from_array(recipient_address),
+                    encrypted_memo,
+                })?;
+            }
+        } else {
+            event_sequence.emit(&ElusivEvent::ShieldedTransfer {
+                token_id,
+                fee_version: join_split.fee_version,
+                output_commitment: join_split.output_commitment.reduce(),
+            })?;
+        }
+    }
+
     Ok(())
 }
 
+/// Resets a verification that has been abandoned before its finalization (permissionless)
+/// - the verification needs to be timed out (see [`is_verification_resettable`])
+/// - closing the verification PDAs releases the nullifier-hashes, the rent is refunded to `original_fee_payer`
+/// - [`COMPUTATION_TIMEOUT_SLASHING_BPS`] of the prepaid fee is slashed to `resetter`, the remainder is refunded to `original_fee_payer`
+/// - if `original_fee_payer` is a warden, its stake is slashed as well (see [`slash_warden_stake`])
+#[allow(clippy::too_many_arguments)]
+pub fn reset_timed_out_verification<'a>(
+    resetter: &AccountInfo<'a>,
+    original_fee_payer: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+    verification_account_info: &AccountInfo<'a>,
+    nullifier_duplicate_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    warden_account: UnverifiedAccountInfo<'_, 'a>,
+
+    _verification_account_index: u8,
+) -> ProgramResult {
+    pda_account!(
+        mut verification_account,
+        VerificationAccount,
+        verification_account_info
+    );
+    let data = verification_account.get_other_data();
+    let request = verification_account.get_request();
+    let join_split = proof_request!(&request, public_inputs, public_inputs.join_split_inputs());
+    let state = verification_account.get_state();
+
+    guard!(
+        is_verification_resettable(&verification_account, current_slot()?),
+        ElusivError::ComputationIsNotTimedOut
+    );
+    guard!(
+        original_fee_payer.key.to_bytes() == data.fee_payer.skip_mr(),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        *nullifier_duplicate_account.key
+            == join_split.create_nullifier_duplicate_pda(nullifier_duplicate_account)?,
+        ElusivError::InvalidAccount
+    );
+
+    // Fees are only prepaid after the fee transfer
+    if !matches!(state, VerificationState::None) {
+        let token_id = join_split.token_id;
+        verify_program_token_account(pool, pool_account, token_id)?;
+        verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
+
+        // `pool` transfers `subvention` to `fee_collector` (token)
+        transfer_token_from_pda::<PoolAccount>(
+            pool,
+            pool_account,
+            fee_collector_account,
+            token_program,
+            Token::new(token_id, data.subvention),
+            None,
+            None,
+        )?;
+
+        {
+            pda_account!(mut fee_collector, FeeCollectorAccount, fee_collector);
+            fee_collector.refund_subvention(join_split.fee_version, token_id, data.subvention);
+        }
+
+        let associated_token_account_rent = match &request {
+            ProofRequest::Send(public_inputs)
+                if public_inputs.recipient_is_associated_token_account =>
+            {
+                spl_token_account_rent()?
+            }
+            _ => Lamports(0),
+        };
+        let prepaid_fee = (data.commitment_hash_fee + associated_token_account_rent)?.0;
+        let slashed_fee =
+            (prepaid_fee as u128 * COMPUTATION_TIMEOUT_SLASHING_BPS as u128 / 10_000) as u64;
+
+        // `pool` transfers `slashed_fee` to `resetter` and the remaining prepaid fee to `original_fee_payer` (lamports)
+        transfer_lamports_from_pda_checked(pool, resetter, slashed_fee)?;
+        transfer_lamports_from_pda_checked(pool, original_fee_payer, prepaid_fee - slashed_fee)?;
+    }
+
+    close_verification_pdas(
+        original_fee_payer,
+        verification_account_info,
+        nullifier_duplicate_account,
+        data.skip_nullifier_pda,
+        verification_account.get_is_pooled(),
+    )?;
+
+    verification_account.set_state(&VerificationState::Closed);
+
+    slash_warden_stake(original_fee_payer, warden_account, resetter)
+}
+
+/// Whether a verification is timed out at `slot`, without any inserted nullifier-hashes
+/// - once nullifier-hashes are inserted, a verification can only be finalized
+fn is_verification_resettable(verification_account: &VerificationAccount, slot: u64) -> bool {
+    matches!(
+        verification_account.get_state(),
+        VerificationState::None | VerificationState::FeeTransferred | VerificationState::ProofSetup
+    ) && verification_account.is_computation_timed_out(slot)
+}
+
+/// Pooled [`VerificationAccount`]s are kept open (see [`crate::processor::release_pooled_verification_account`]), instead their deposit flows to the `beneficiary`
 fn close_verification_pdas<'a>(
     beneficiary: &AccountInfo<'a>,
     verification_account: &AccountInfo<'a>,
     nullifier_duplicate_account: &AccountInfo<'a>,
     skipped_nullifier_pda: bool,
+    pooled_verification_account: bool,
 ) -> ProgramResult {
-    close_account(beneficiary, verification_account)?;
+    if pooled_verification_account {
+        transfer_pooled_verification_account_deposit(verification_account, beneficiary)?;
+    } else {
+        VerificationAccount::close(beneficiary, verification_account)?;
+    }
     if !skipped_nullifier_pda {
-        close_account(beneficiary, nullifier_duplicate_account)?;
+        NullifierDuplicateAccount::close(beneficiary, nullifier_duplicate_account)?;
     }
 
     Ok(())
@@ -1054,6 +1494,7 @@ fn check_join_split_public_inputs(
     storage_account: &StorageAccount,
     nullifier_accounts: [&NullifierAccount; MAX_MT_COUNT],
     tree_indices: &[u32; MAX_MT_COUNT],
+    archived_tree_accounts: &[&AccountInfo],
 ) -> ProgramResult {
     // Check that the resulting commitment is not the zero-commitment
     guard!(
@@ -1097,9 +1538,16 @@ fn check_join_split_public_inputs(
                         ElusivError::InvalidMerkleRoot
                     );
                 } else {
-                    // Closed tree
+                    // Closed tree (or its archived root)
+                    let root = root.reduce();
                     guard!(
-                        root.reduce() == nullifier_accounts[index].get_root(),
+                        root == nullifier_accounts[index].get_root()
+                            || is_archived_root(
+                                archived_tree_accounts,
+                                public_inputs.token_id,
+                                tree_indices[index],
+                                &root
+                            )?,
                         ElusivError::InvalidMerkleRoot
                     );
                 }
@@ -1155,23 +1603,89 @@ fn check_join_split_public_inputs(
     Ok(())
 }
 
-fn enforce_finalize_send_instructions(
-    instructions_account: &AccountInfo,
-    uses_lamports: bool,
-    verification_account_index: u8,
-) -> Result<(), ProgramError> {
-    if cfg!(test) {
-        return Ok(());
+/// Whether `root` is the root of the [`ArchivedTreeAccount`] of the MT with `mt_index` (contained in `archived_tree_accounts`)
+fn is_archived_root(
+    archived_tree_accounts: &[&AccountInfo],
+    token_id: u16,
+    mt_index: u32,
+    root: &U256,
+) -> Result<bool, ProgramError> {
+    let pubkey = ArchivedTreeAccount::find_with_offsets(
+        None,
+        &nullifier_account_offsets(token_id, mt_index),
+    )
+    .0;
+
+    for account in archived_tree_accounts {
+        if *account.key != pubkey || *account.owner != crate::id() {
+            continue;
+        }
+
+        pda_account!(archived_tree, ArchivedTreeAccount, account);
+        return Ok(archived_tree.get_commitment_root() == *root);
     }
 
-    enforce_finalize_send_instructions_inner(
-        &DefaultInstructionsSysvar(instructions_account),
-        uses_lamports,
-        verification_account_index,
+    Ok(false)
+}
+
+fn is_released_installment(
+    lookup_accounts: &[&AccountInfo],
+    recurring_payment: &RecurringPaymentBinding,
+    installment: &U256,
+) -> Result<bool, ProgramError> {
+    let pubkey = RecurringPaymentAccount::find_with_pubkey(
+        recurring_payment.owner,
+        Some(recurring_payment.recurring_payment_index),
     )
+    .0;
+
+    for account in lookup_accounts {
+        if *account.key != pubkey || *account.owner != crate::id() {
+            continue;
+        }
+
+        pda_account!(recurring_payment_account, RecurringPaymentAccount, account);
+        return Ok(recurring_payment_account.is_released(installment));
+    }
+
+    Ok(false)
 }
 
-fn verify_finalize_send_instruction<I: InstructionsSysvar>(
+/// Whether `recipient` is denied by its [`RecipientDenylistAccount`] shard (which needs to be contained in `lookup_accounts`)
+fn is_denied_recipient(
+    lookup_accounts: &[&AccountInfo],
+    recipient: &Pubkey,
+) -> Result<bool, ProgramError> {
+    let pubkey = RecipientDenylistAccount::find(recipient_denylist_offset(
+        recipient_denylist_shard(recipient),
+    ))
+    .0;
+
+    for account in lookup_accounts {
+        if *account.key != pubkey || *account.owner != crate::id() {
+            continue;
+        }
+
+        pda_account!(recipient_denylist, RecipientDenylistAccount, account);
+        return Ok(recipient_denylist.is_denied(recipient));
+    }
+
+    Err(ElusivError::InvalidAccount.into())
+}
+
+fn enforce_finalize_send_instructions(
+    instructions_account: &AccountInfo,
+    uses_lamports: bool,
+    verification_account_index: u8,
+) -> Result<(), ProgramError> {
+    enforce_finalize_send_instructions_inner(
+        &DefaultInstructionsSysvar(instructions_account),
+        uses_lamports,
+        verification_account_index,
+    )
+}
+
+fn verify_finalize_send_instruction<I: InstructionsSysvar>(
     ix_index: usize,
     instruction_sysvar: &I,
     expected_variant_index: u8,
@@ -1362,10 +1876,12 @@ pub(crate) use vkey_account;
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::computation::COMPUTATION_TIMEOUT_SLOTS;
     use crate::fields::{u256_from_str, u256_from_str_skip_mr};
     use crate::macros::{
-        account_info, parent_account, program_token_account_info, pyth_price_account_info,
-        test_account_info, test_pda_account_info, two_pow, zero_program_account,
+        account_info, instructions_account_info, parent_account, program_token_account_info,
+        pyth_price_account_info, test_account_info, test_pda_account_info, two_pow,
+        zero_program_account,
     };
     use crate::processor::ZERO_COMMITMENT_RAW;
     use crate::proof::verifier::{
@@ -1376,7 +1892,10 @@ mod tests {
     use crate::state::nullifier::NullifierChildAccount;
     use crate::state::program_account::{PDAAccount, SizedAccount};
     use crate::state::storage::empty_root_raw;
-    use crate::token::{spl_token_account_data, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID, USDT_TOKEN_ID};
+    use crate::syscall_stubs::set_test_slot;
+    use crate::token::{
+        spl_token_account_data, TokenPrice, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID, USDT_TOKEN_ID,
+    };
     use crate::types::{
         compute_fee_rec, compute_fee_rec_lamports, Proof, RawU256, JOIN_SPLIT_MAX_N_ARITY,
     };
@@ -1388,17 +1907,20 @@ mod tests {
     use solana_program::system_program;
 
     fn fee() -> ProgramFee {
-        ProgramFee::new(5000, 11, 100, 33, 44, 300, 555).unwrap()
+        ProgramFee::new(5000, 11, 100, 33, 44, 88, 300, 555).unwrap()
     }
 
     #[test]
     fn test_init_verification() {
+        set_test_slot(0);
         use ProofRequest::*;
 
         parent_account!(storage, StorageAccount);
         parent_account!(mut nullifier, NullifierAccount);
+        zero_program_account!(mut governor, GovernorAccount);
         test_account_info!(fee_payer, 0);
         test_account_info!(identifier, 0);
+        test_account_info!(warden_account, 0);
         account_info!(
             v_acc,
             VerificationAccount::find_with_pubkey(*fee_payer.key, Some(0)).0,
@@ -1421,8 +1943,9 @@ mod tests {
             recipient_is_associated_token_account: true,
             hashed_inputs: u256_from_str_skip_mr("1"),
             solana_pay_transfer: false,
+            encrypted_memo: None,
         };
-        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee(), 0);
 
         account_info!(
             n_duplicate_acc,
@@ -1436,6 +1959,56 @@ mod tests {
         vkey.set_public_inputs_count(&SendQuadraVKey::PUBLIC_INPUTS_COUNT);
         vkey.set_version(&1);
 
+        // Unfrozen vkey
+        assert_matches!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &storage,
+                &nullifier,
+                &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false
+            ),
+            Err(_)
+        );
+
+        // Mismatching checksum
+        vkey.set_is_frozen(&true);
+        vkey.set_checksum(&MigrateUnaryVKey::CHECKSUM);
+        assert_matches!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &storage,
+                &nullifier,
+                &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false
+            ),
+            Err(_)
+        );
+
+        vkey.set_checksum(&SendQuadraVKey::CHECKSUM);
+
         // TODO: test skip nullifier pda
         // TODO: wrong vkey-id
         // TODO: vkey not checked
@@ -1451,6 +2024,9 @@ mod tests {
                 &storage,
                 &nullifier,
                 &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
                 RESERVED_VERIFICATION_ACCOUNT_IDS,
                 vkey_id,
                 [0, 1],
@@ -1471,6 +2047,9 @@ mod tests {
                 &storage,
                 &nullifier,
                 &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
                 0,
                 vkey_id,
                 [0, 1],
@@ -1493,6 +2072,9 @@ mod tests {
                 &storage,
                 &nullifier,
                 &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
                 0,
                 vkey_id,
                 [0, 1],
@@ -1516,6 +2098,9 @@ mod tests {
                 &storage,
                 &nullifier,
                 &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
                 0,
                 vkey_id,
                 [0, 1],
@@ -1538,6 +2123,9 @@ mod tests {
                 &storage,
                 &nullifier,
                 &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
                 0,
                 vkey_id,
                 [1, 0],
@@ -1558,6 +2146,9 @@ mod tests {
                 &storage,
                 &nullifier,
                 &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
                 0,
                 vkey_id,
                 [0, 1],
@@ -1587,6 +2178,9 @@ mod tests {
                 &storage,
                 &nullifier,
                 &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
                 0,
                 vkey_id,
                 [0, 1],
@@ -1613,6 +2207,9 @@ mod tests {
                 &storage,
                 &nullifier,
                 &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
                 0,
                 vkey_id,
                 [0, 1],
@@ -1633,6 +2230,9 @@ mod tests {
                 &storage,
                 &nullifier,
                 &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
                 0,
                 vkey_id,
                 [0, 1],
@@ -1653,6 +2253,9 @@ mod tests {
                 &storage,
                 &nullifier,
                 &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
                 0,
                 vkey_id,
                 [0, 1],
@@ -1666,6 +2269,59 @@ mod tests {
             Err(_)
         );
 
+        // Halted program
+        governor.set_is_halted(&true);
+        assert_matches!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &storage,
+                &nullifier,
+                &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false
+            ),
+            Err(_)
+        );
+        governor.set_is_halted(&false);
+
+        // Unregistered fee-payer
+        governor.set_is_warden_registration_required(&true);
+        assert_matches!(
+            init_verification(
+                &fee_payer,
+                &v_acc,
+                &vkey,
+                &n_duplicate_acc,
+                &identifier,
+                &storage,
+                &nullifier,
+                &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
+                0,
+                vkey_id,
+                [0, 1],
+                Send(inputs.clone()),
+                false
+            ),
+            Err(_)
+        );
+
+        // In emergency mode users can exit without a relayer (even if the program is halted)
+        governor.set_emergency_mode(&true);
+        governor.set_is_halted(&true);
+
         assert_matches!(
             init_verification(
                 &fee_payer,
@@ -1676,6 +2332,9 @@ mod tests {
                 &storage,
                 &nullifier,
                 &nullifier,
+                &governor,
+                UnverifiedAccountInfo::new(&warden_account),
+                &[],
                 0,
                 vkey_id,
                 [0, 1],
@@ -1691,8 +2350,10 @@ mod tests {
     fn test_init_verification_commitment_count_too_high() {
         parent_account!(storage, StorageAccount);
         parent_account!(nullifier, NullifierAccount);
+        zero_program_account!(governor, GovernorAccount);
         test_account_info!(fee_payer, 0);
         test_account_info!(identifier, 0);
+        test_account_info!(warden_account, 0);
         account_info!(
             v_acc,
             VerificationAccount::find_with_pubkey(*fee_payer.key, Some(0)).0,
@@ -1715,8 +2376,9 @@ mod tests {
             recipient_is_associated_token_account: true,
             hashed_inputs: u256_from_str_skip_mr("1"),
             solana_pay_transfer: false,
+            encrypted_memo: None,
         };
-        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee(), 0);
 
         account_info!(
             n_duplicate_acc,
@@ -1745,6 +2407,9 @@ mod tests {
             &storage,
             &nullifier,
             &nullifier,
+            &governor,
+            UnverifiedAccountInfo::new(&warden_account),
+            &[],
             0,
             0,
             [0, 1],
@@ -1755,9 +2420,10 @@ mod tests {
 
     #[test]
     fn test_init_verification_transfer_fee_lamports() {
+        set_test_slot(0);
         test_account_info!(fee_payer, 0);
         test_account_info!(pool, 0);
-        test_account_info!(fee_collector, 0);
+        test_account_info!(fee_collector, FeeCollectorAccount::SIZE);
         test_account_info!(any, 0);
         account_info!(sys, system_program::id());
         account_info!(spl, spl_token::id());
@@ -1780,11 +2446,13 @@ mod tests {
             recipient_is_associated_token_account: false,
             hashed_inputs: u256_from_str_skip_mr("1"),
             solana_pay_transfer: false,
+            encrypted_memo: None,
         };
-        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee(), 0);
         let instructions = prepare_public_inputs_instructions(
             &inputs.public_signals_skip_mr(),
             SendQuadraVKey::public_inputs_count(),
+            0,
         );
 
         zero_program_account!(mut verification_acc, VerificationAccount);
@@ -1886,7 +2554,7 @@ mod tests {
 
         // Invalid system_program
         inputs.join_split.fee = 0;
-        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee());
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut inputs, &fee(), 0);
         verification_acc.set_request(&ProofRequest::Send(inputs));
         assert_matches!(
             init_verification_transfer_fee(
@@ -1974,6 +2642,7 @@ mod tests {
 
     #[test]
     fn test_init_verification_transfer_fee_token() {
+        set_test_slot(0);
         test_account_info!(fee_payer, 0);
         account_info!(sys, system_program::id());
         account_info!(spl, spl_token::id());
@@ -2030,11 +2699,13 @@ mod tests {
             recipient_is_associated_token_account: false,
             hashed_inputs: u256_from_str_skip_mr("1"),
             solana_pay_transfer: false,
+            encrypted_memo: None,
         };
-        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), &price);
+        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), 0, &price);
         let instructions = prepare_public_inputs_instructions(
             &inputs.public_signals_skip_mr(),
             SendQuadraVKey::public_inputs_count(),
+            0,
         );
 
         zero_program_account!(mut verification_acc, VerificationAccount);
@@ -2068,7 +2739,7 @@ mod tests {
         );
 
         inputs.join_split.fee = 0;
-        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), &price);
+        compute_fee_rec::<SendQuadraVKey, _>(&mut inputs, &fee(), 0, &price);
         verification_acc.set_request(&ProofRequest::Send(inputs.clone()));
 
         // Invalid system_program
@@ -2238,6 +2909,7 @@ mod tests {
 
     #[test]
     fn test_init_verification_proof() {
+        set_test_slot(0);
         let proof = test_proof();
         let valid_pk = Pubkey::new(&[0; 32]);
         account_info!(fee_payer, valid_pk, vec![0; 0]);
@@ -2294,10 +2966,15 @@ mod tests {
 
     #[test]
     fn test_compute_verification() {
+        set_test_slot(0);
         zero_program_account!(mut verification_account, VerificationAccount);
         vkey_account!(vkey, SendQuadraVKey);
         vkey.set_version(&1);
-        test_account_info!(any, 0);
+        instructions_account_info!(
+            instructions_account,
+            Vec::new(),
+            COMPUTE_VERIFICATION_IX_COUNT - 1
+        );
 
         // Setup
         let public_inputs = test_public_inputs();
@@ -2307,6 +2984,7 @@ mod tests {
         let instructions = prepare_public_inputs_instructions(
             &public_inputs,
             SendQuadraVKey::public_inputs_count(),
+            0,
         );
         verification_account.set_prepare_inputs_instructions_count(&(instructions.len() as u32));
         for (i, &ix) in instructions.iter().enumerate() {
@@ -2319,7 +2997,7 @@ mod tests {
             compute_verification(
                 &mut verification_account,
                 &vkey,
-                &any,
+                &instructions_account,
                 0,
                 SendQuadraVKey::VKEY_ID
             ),
@@ -2333,7 +3011,7 @@ mod tests {
                 compute_verification(
                     &mut verification_account,
                     &vkey,
-                    &any,
+                    &instructions_account,
                     0,
                     SendQuadraVKey::VKEY_ID
                 ),
@@ -2346,7 +3024,7 @@ mod tests {
             compute_verification(
                 &mut verification_account,
                 &vkey,
-                &any,
+                &instructions_account,
                 0,
                 SendQuadraVKey::VKEY_ID
             ),
@@ -2365,7 +3043,7 @@ mod tests {
                 compute_verification(
                     &mut verification_account,
                     &vkey,
-                    &any,
+                    &instructions_account,
                     0,
                     SendQuadraVKey::VKEY_ID
                 ),
@@ -2378,7 +3056,7 @@ mod tests {
             compute_verification(
                 &mut verification_account,
                 &vkey,
-                &any,
+                &instructions_account,
                 0,
                 SendQuadraVKey::VKEY_ID
             ),
@@ -2398,6 +3076,56 @@ mod tests {
             $reference: ident,
             $finalize_data: ident
         ) => {
+            finalize_send_test!(
+                $token_id,
+                $amount,
+                $public_inputs,
+                $v_data,
+                $recipient,
+                $identifier,
+                $reference,
+                $finalize_data,
+                None
+            );
+        };
+        (
+            $token_id: expr,
+            $amount: expr,
+            $public_inputs: ident,
+            $v_data: ident,
+            $recipient: ident,
+            $identifier: ident,
+            $reference: ident,
+            $finalize_data: ident,
+            $recurring_payment: expr
+        ) => {
+            finalize_send_test!(
+                $token_id,
+                $amount,
+                $public_inputs,
+                $v_data,
+                $recipient,
+                $identifier,
+                $reference,
+                $finalize_data,
+                $recurring_payment,
+                None
+            );
+        };
+        (
+            $token_id: expr,
+            $amount: expr,
+            $public_inputs: ident,
+            $v_data: ident,
+            $recipient: ident,
+            $identifier: ident,
+            $reference: ident,
+            $finalize_data: ident,
+            $recurring_payment: expr,
+            $batch_payouts: expr
+        ) => {
+            let recurring_payment: Option<RecurringPaymentBinding> = $recurring_payment;
+            let batch_payouts: Option<BatchPayouts> = $batch_payouts;
             let $recipient = Pubkey::new_unique().to_bytes();
             let $identifier = Pubkey::new_unique().to_bytes();
             let $reference = Pubkey::new_unique().to_bytes();
@@ -2418,16 +3146,20 @@ mod tests {
                     token_id: $token_id,
                 },
                 recipient_is_associated_token_account: false,
-                hashed_inputs: generate_hashed_inputs(
-                    $recipient.clone(),
-                    $identifier.clone(),
-                    iv.clone(),
+                hashed_inputs: generate_hashed_inputs(&HashedInputs {
+                    recipient: $recipient.clone(),
+                    identifier: $identifier.clone(),
+                    iv: iv.clone(),
                     encrypted_owner,
-                    $reference,
-                    false,
-                    &None,
-                ),
+                    transaction_reference: $reference,
+                    is_associated_token_account: false,
+                    memo: None,
+                    encrypted_memo: None,
+                    recurring_payment: recurring_payment.as_ref(),
+                    batch_payouts: batch_payouts.as_ref(),
+                }),
                 solana_pay_transfer: false,
+                encrypted_memo: None,
             };
 
             let mut $v_data = vec![0; VerificationAccount::SIZE];
@@ -2460,10 +3192,40 @@ mod tests {
                 commitment_index: 0,
                 encrypted_owner,
                 iv,
+                recurring_payment,
+                batch_payouts,
             };
         };
     }
 
+    /// The instructions of a complete finalization transaction
+    fn finalize_send_instructions(
+        uses_lamports: bool,
+        verification_account_index: u8,
+    ) -> Vec<Instruction> {
+        let transfer_ix_variant_index = if uses_lamports {
+            ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_LAMPORTS_INDEX
+        } else {
+            ElusivInstruction::FINALIZE_VERIFICATION_TRANSFER_TOKEN_INDEX
+        };
+
+        [
+            ElusivInstruction::FINALIZE_VERIFICATION_SEND_INDEX,
+            ElusivInstruction::FINALIZE_VERIFICATION_INSERT_NULLIFIER_INDEX,
+            transfer_ix_variant_index,
+        ]
+        .into_iter()
+        .map(|variant_index| {
+            StubInstruction(
+                variant_index,
+                Some(vec![verification_account_index]),
+                crate::id(),
+            )
+            .into()
+        })
+        .collect()
+    }
+
     macro_rules! storage_account {
         ($id: ident) => {
             let mut data = vec![0; StorageAccount::SIZE];
@@ -2474,6 +3236,7 @@ mod tests {
 
     #[test]
     fn test_finalize_verification_send_valid() {
+        set_test_slot(0);
         finalize_send_test!(
             USDC_TOKEN_ID,
             LAMPORTS_PER_SOL,
@@ -2489,11 +3252,17 @@ mod tests {
         let mut data = vec![0; CommitmentQueueAccount::SIZE];
         let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
         storage_account!(storage);
+        zero_program_account!(mut denylist, RecipientDenylistAccount);
+        zero_program_account!(mut governor, GovernorAccount);
 
         account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
         account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
         account_info!(reference, Pubkey::new_from_array(reference_bytes));
-        test_account_info!(any, 0);
+        instructions_account_info!(
+            instructions_account,
+            finalize_send_instructions(false, 0),
+            0
+        );
 
         // Verification is not finished
         verification_acc.set_is_verified(&ElusivOption::None);
@@ -2505,7 +3274,10 @@ mod tests {
                 &mut queue,
                 &mut verification_acc,
                 &storage,
-                &any,
+                &denylist,
+                &governor,
+                &instructions_account,
+                &[],
                 0,
                 finalize_data.clone(),
                 false
@@ -2526,7 +3298,10 @@ mod tests {
                     &mut queue,
                     &mut verification_acc,
                     &storage,
-                    &any,
+                    &denylist,
+                    &governor,
+                    &instructions_account,
+                    &[],
                     0,
                     finalize_data.clone(),
                     false
@@ -2546,7 +3321,10 @@ mod tests {
                     &mut queue,
                     &mut verification_acc,
                     &storage,
-                    &any,
+                    &denylist,
+                    &governor,
+                    &instructions_account,
+                    &[],
                     0,
                     finalize_data.clone(),
                     false
@@ -2566,7 +3344,10 @@ mod tests {
                     &mut queue,
                     &mut verification_acc,
                     &storage,
-                    &any,
+                    &denylist,
+                    &governor,
+                    &instructions_account,
+                    &[],
                     0,
                     finalize_data.clone(),
                     false
@@ -2594,7 +3375,10 @@ mod tests {
                     &mut queue,
                     &mut verification_acc,
                     &storage,
-                    &any,
+                    &denylist,
+                    &governor,
+                    &instructions_account,
+                    &[],
                     0,
                     invalid_data,
                     false
@@ -2603,6 +3387,53 @@ mod tests {
             );
         }
 
+        // Denied recipient
+        denylist.add(recipient.key).unwrap();
+        denylist.set_enabled(&true);
+        assert_matches!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &denylist,
+                &governor,
+                &instructions_account,
+                &[],
+                0,
+                finalize_data.clone(),
+                false
+            ),
+            Err(_)
+        );
+        denylist.set_enabled(&false);
+
+        // Halted program
+        governor.set_is_halted(&true);
+        assert_matches!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &denylist,
+                &governor,
+                &instructions_account,
+                &[],
+                0,
+                finalize_data.clone(),
+                false
+            ),
+            Err(_)
+        );
+
+        // Exits remain possible in emergency mode
+        governor.set_emergency_mode(&true);
+
         // Success
         assert_matches!(
             finalize_verification_send(
@@ -2612,7 +3443,10 @@ mod tests {
                 &mut queue,
                 &mut verification_acc,
                 &storage,
-                &any,
+                &denylist,
+                &governor,
+                &instructions_account,
+                &[],
                 0,
                 finalize_data.clone(),
                 false
@@ -2634,7 +3468,10 @@ mod tests {
                 &mut queue,
                 &mut verification_acc,
                 &storage,
-                &any,
+                &denylist,
+                &governor,
+                &instructions_account,
+                &[],
                 0,
                 finalize_data,
                 false
@@ -2644,7 +3481,10 @@ mod tests {
     }
 
     #[test]
-    fn test_finalize_verification_send_invalid() {
+    fn test_finalize_verification_send_recurring_installment() {
+        set_test_slot(0);
+        let owner = Pubkey::new_unique();
+        let recurring_payment_index = 1;
         finalize_send_test!(
             USDC_TOKEN_ID,
             LAMPORTS_PER_SOL,
@@ -2653,21 +3493,40 @@ mod tests {
             recipient_bytes,
             identifier_bytes,
             reference_bytes,
-            finalize_data
+            finalize_data,
+            Some(RecurringPaymentBinding {
+                owner,
+                recurring_payment_index,
+            })
         );
 
         let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
         let mut data = vec![0; CommitmentQueueAccount::SIZE];
         let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
         storage_account!(storage);
-        test_account_info!(any, 0);
+        zero_program_account!(denylist, RecipientDenylistAccount);
+        zero_program_account!(governor, GovernorAccount);
 
         account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
         account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
         account_info!(reference, Pubkey::new_from_array(reference_bytes));
+        instructions_account_info!(
+            instructions_account,
+            finalize_send_instructions(false, 0),
+            0
+        );
 
-        verification_acc.set_is_verified(&ElusivOption::Some(false));
+        let installment =
+            recurring_installment_hash(&ProofRequest::Send(public_inputs.clone())).unwrap();
+        let pubkey =
+            RecurringPaymentAccount::find_with_pubkey(owner, Some(recurring_payment_index)).0;
+        let mut recurring_payment_data = vec![0; RecurringPaymentAccount::SIZE];
+        RecurringPaymentAccount::new(&mut recurring_payment_data)
+            .unwrap()
+            .setup(&owner, 1, 0, &[installment])
+            .unwrap();
 
+        // Missing recurring payment account
         assert_matches!(
             finalize_verification_send(
                 &recipient,
@@ -2676,49 +3535,431 @@ mod tests {
                 &mut queue,
                 &mut verification_acc,
                 &storage,
-                &any,
+                &denylist,
+                &governor,
+                &instructions_account,
+                &[],
                 0,
-                finalize_data,
+                finalize_data.clone(),
                 false
             ),
-            Ok(())
+            Err(_)
         );
-        assert_matches!(verification_acc.get_state(), VerificationState::Finalized);
-    }
-
-    #[test]
-    fn test_finalize_verification_migrate() {
-        let migrate_public_inputs = MigratePublicInputs {
-            join_split: JoinSplitPublicInputs {
-                input_commitments: vec![InputCommitment {
-                    root: Some(empty_root_raw()),
-                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
-                }],
-                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
-                output_commitment_index: 123,
-                fee_version: 0,
-                amount: LAMPORTS_PER_SOL,
-                fee: 10000,
-                token_id: 0,
-            },
-            current_nsmt_root: RawU256::new([0; 32]),
-            next_nsmt_root: RawU256::new([0; 32]),
-        };
-
-        let pk = Pubkey::new_unique();
-        account_info!(acc, pk);
-
-        let mut data = vec![0; VerificationAccount::SIZE];
-        let mut v_account = VerificationAccount::new(&mut data).unwrap();
-        v_account.set_request(&ProofRequest::Migrate(migrate_public_inputs));
-        v_account.set_state(&VerificationState::ProofSetup);
-        v_account.set_is_verified(&ElusivOption::Some(true));
-
-        let mut data = vec![0; CommitmentQueueAccount::SIZE];
-        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+
+        // Installment is not yet released
+        {
+            account_info!(account, pubkey, recurring_payment_data.clone());
+            assert_matches!(
+                finalize_verification_send(
+                    &recipient,
+                    &identifier,
+                    &reference,
+                    &mut queue,
+                    &mut verification_acc,
+                    &storage,
+                    &denylist,
+                    &governor,
+                    &instructions_account,
+                    &[&account],
+                    0,
+                    finalize_data.clone(),
+                    false
+                ),
+                Err(_)
+            );
+        }
+
+        RecurringPaymentAccount::new(&mut recurring_payment_data)
+            .unwrap()
+            .release_next_installment(0, &installment)
+            .unwrap();
+
+        // Recurring payment account of a different owner
+        {
+            account_info!(
+                account,
+                RecurringPaymentAccount::find_with_pubkey(
+                    Pubkey::new_unique(),
+                    Some(recurring_payment_index)
+                )
+                .0,
+                recurring_payment_data.clone()
+            );
+            assert_matches!(
+                finalize_verification_send(
+                    &recipient,
+                    &identifier,
+                    &reference,
+                    &mut queue,
+                    &mut verification_acc,
+                    &storage,
+                    &denylist,
+                    &governor,
+                    &instructions_account,
+                    &[&account],
+                    0,
+                    finalize_data.clone(),
+                    false
+                ),
+                Err(_)
+            );
+        }
+
+        // The binding can't be dropped from the finalize data
+        account_info!(account, pubkey, recurring_payment_data);
+        assert_matches!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &denylist,
+                &governor,
+                &instructions_account,
+                &[&account],
+                0,
+                mutate(&finalize_data, |d| d.recurring_payment = None),
+                false
+            ),
+            Err(_)
+        );
+
+        // Released installment
+        assert_matches!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &denylist,
+                &governor,
+                &instructions_account,
+                &[&account],
+                0,
+                finalize_data,
+                false
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_finalize_verification_send_batch_payouts() {
+        set_test_slot(0);
+        let recipients = [
+            Pubkey::new_from_array([1; 32]),
+            Pubkey::new_from_array([2; 32]),
+        ];
+        let batch_payouts = BatchPayouts::new(&[
+            (recipients[0], LAMPORTS_PER_SOL / 4),
+            (recipients[1], LAMPORTS_PER_SOL / 4 * 3),
+        ])
+        .unwrap();
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            identifier_bytes,
+            reference_bytes,
+            finalize_data,
+            None,
+            Some(batch_payouts)
+        );
+
+        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        storage_account!(storage);
+        zero_program_account!(denylist, RecipientDenylistAccount);
+        zero_program_account!(governor, GovernorAccount);
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
+        account_info!(reference, Pubkey::new_from_array(reference_bytes));
+        instructions_account_info!(instructions_account, finalize_send_instructions(true, 0), 0);
+
+        let shard_pubkeys = recipients.map(|recipient| {
+            RecipientDenylistAccount::find(recipient_denylist_offset(recipient_denylist_shard(
+                &recipient,
+            )))
+            .0
+        });
+        assert_ne!(shard_pubkeys[0], shard_pubkeys[1]);
+        let mut denying_shard_data = vec![0; RecipientDenylistAccount::SIZE];
+        {
+            let mut shard = RecipientDenylistAccount::new(&mut denying_shard_data).unwrap();
+            shard.set_enabled(&true);
+            shard.add(&recipients[1]).unwrap();
+        }
+        account_info!(
+            shard0,
+            shard_pubkeys[0],
+            vec![0; RecipientDenylistAccount::SIZE]
+        );
+        account_info!(
+            shard1,
+            shard_pubkeys[1],
+            vec![0; RecipientDenylistAccount::SIZE]
+        );
+        account_info!(denying_shard1, shard_pubkeys[1], denying_shard_data);
+
+        // Missing denylist shard
+        assert_matches!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &denylist,
+                &governor,
+                &instructions_account,
+                &[&shard0],
+                0,
+                finalize_data.clone(),
+                false
+            ),
+            Err(_)
+        );
+
+        // Denied batch payout recipient
+        assert_matches!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &denylist,
+                &governor,
+                &instructions_account,
+                &[&shard0, &denying_shard1],
+                0,
+                finalize_data.clone(),
+                false
+            ),
+            Err(_)
+        );
+
+        // The batch payouts can't be altered or dropped from the finalize data
+        for batch_payouts in [
+            None,
+            BatchPayouts::new(&[(recipients[0], LAMPORTS_PER_SOL)]),
+            BatchPayouts::new(&[
+                (recipients[1], LAMPORTS_PER_SOL / 4),
+                (recipients[0], LAMPORTS_PER_SOL / 4 * 3),
+            ]),
+        ] {
+            assert_matches!(
+                finalize_verification_send(
+                    &recipient,
+                    &identifier,
+                    &reference,
+                    &mut queue,
+                    &mut verification_acc,
+                    &storage,
+                    &denylist,
+                    &governor,
+                    &instructions_account,
+                    &[&shard0, &shard1],
+                    0,
+                    mutate(&finalize_data, |d| d.batch_payouts = batch_payouts),
+                    false
+                ),
+                Err(_)
+            );
+        }
+
+        finalize_verification_send(
+            &recipient,
+            &identifier,
+            &reference,
+            &mut queue,
+            &mut verification_acc,
+            &storage,
+            &denylist,
+            &governor,
+            &instructions_account,
+            &[&shard0, &shard1],
+            0,
+            finalize_data,
+            false,
+        )
+        .unwrap();
+
+        assert_matches!(
+            verification_acc.get_state(),
+            VerificationState::InsertNullifiers
+        );
+        assert_eq!(
+            verification_acc.get_other_data().batch_payouts.option(),
+            Some(batch_payouts)
+        );
+    }
+
+    #[test]
+    fn test_finalize_verification_send_batch_payouts_amount_mismatch() {
+        set_test_slot(0);
+        let recipients = [
+            Pubkey::new_from_array([1; 32]),
+            Pubkey::new_from_array([2; 32]),
+        ];
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            identifier_bytes,
+            reference_bytes,
+            finalize_data,
+            None,
+            BatchPayouts::new(&[
+                (recipients[0], LAMPORTS_PER_SOL / 4),
+                (recipients[1], LAMPORTS_PER_SOL / 4),
+            ])
+        );
+
+        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        storage_account!(storage);
+        zero_program_account!(denylist, RecipientDenylistAccount);
+        zero_program_account!(governor, GovernorAccount);
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
+        account_info!(reference, Pubkey::new_from_array(reference_bytes));
+        instructions_account_info!(instructions_account, finalize_send_instructions(true, 0), 0);
+        let shard_pubkeys = recipients.map(|recipient| {
+            RecipientDenylistAccount::find(recipient_denylist_offset(recipient_denylist_shard(
+                &recipient,
+            )))
+            .0
+        });
+        account_info!(
+            shard0,
+            shard_pubkeys[0],
+            vec![0; RecipientDenylistAccount::SIZE]
+        );
+        account_info!(
+            shard1,
+            shard_pubkeys[1],
+            vec![0; RecipientDenylistAccount::SIZE]
+        );
+
+        // The batch payouts need to add up to the withdrawn amount
+        assert_matches!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &denylist,
+                &governor,
+                &instructions_account,
+                &[&shard0, &shard1],
+                0,
+                finalize_data,
+                false
+            ),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_finalize_verification_send_invalid() {
+        finalize_send_test!(
+            USDC_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            identifier_bytes,
+            reference_bytes,
+            finalize_data
+        );
+
+        let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        storage_account!(storage);
+        zero_program_account!(denylist, RecipientDenylistAccount);
+        zero_program_account!(governor, GovernorAccount);
+        test_account_info!(any, 0);
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        account_info!(identifier, Pubkey::new_from_array(identifier_bytes));
+        account_info!(reference, Pubkey::new_from_array(reference_bytes));
+
+        verification_acc.set_is_verified(&ElusivOption::Some(false));
+
+        assert_matches!(
+            finalize_verification_send(
+                &recipient,
+                &identifier,
+                &reference,
+                &mut queue,
+                &mut verification_acc,
+                &storage,
+                &denylist,
+                &governor,
+                &any,
+                &[],
+                0,
+                finalize_data,
+                false
+            ),
+            Ok(())
+        );
+        assert_matches!(verification_acc.get_state(), VerificationState::Finalized);
+    }
+
+    #[test]
+    fn test_finalize_verification_migrate() {
+        let migrate_public_inputs = MigratePublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments: vec![InputCommitment {
+                    root: Some(empty_root_raw()),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
+                }],
+                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+                output_commitment_index: 123,
+                fee_version: 0,
+                amount: LAMPORTS_PER_SOL,
+                fee: 10000,
+                token_id: 0,
+            },
+            current_nsmt_root: RawU256::new([0; 32]),
+            next_nsmt_root: RawU256::new([0; 32]),
+        };
+
+        let pk = Pubkey::new_unique();
+        account_info!(acc, pk);
+
+        let mut data = vec![0; VerificationAccount::SIZE];
+        let mut v_account = VerificationAccount::new(&mut data).unwrap();
+        v_account.set_request(&ProofRequest::Migrate(migrate_public_inputs));
+        v_account.set_state(&VerificationState::ProofSetup);
+        v_account.set_is_verified(&ElusivOption::Some(true));
+
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
 
         let finalize_data = FinalizeSendData::default();
         storage_account!(storage);
+        zero_program_account!(denylist, RecipientDenylistAccount);
+        zero_program_account!(governor, GovernorAccount);
         test_account_info!(any, 0);
 
         assert_matches!(
@@ -2729,7 +3970,10 @@ mod tests {
                 &mut queue,
                 &mut v_account,
                 &storage,
+                &denylist,
+                &governor,
                 &any,
+                &[],
                 0,
                 finalize_data,
                 false
@@ -2740,6 +3984,7 @@ mod tests {
 
     #[test]
     fn test_finalize_verification_insert_nullifier() {
+        set_test_slot(0);
         finalize_send_test!(
             USDC_TOKEN_ID,
             LAMPORTS_PER_SOL,
@@ -2753,6 +3998,9 @@ mod tests {
 
         let mut verification_acc = VerificationAccount::new(&mut verification_acc_data).unwrap();
         parent_account!(mut n_acc_0, NullifierAccount);
+        zero_program_account!(mut state_summary, StateSummaryAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
 
         // finalize_verification_send not called
         verification_acc.set_state(&VerificationState::InsertNullifiers);
@@ -2766,7 +4014,14 @@ mod tests {
             )
             .unwrap();
         assert_matches!(
-            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0),
+            finalize_verification_insert_nullifier(
+                &mut verification_acc,
+                &mut n_acc_0,
+                &mut state_summary,
+                &mut stats,
+                &mut event_sequence,
+                0
+            ),
             Err(_)
         );
 
@@ -2774,7 +4029,14 @@ mod tests {
 
         // Success
         assert_matches!(
-            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0),
+            finalize_verification_insert_nullifier(
+                &mut verification_acc,
+                &mut n_acc_0,
+                &mut state_summary,
+                &mut stats,
+                &mut event_sequence,
+                0
+            ),
             Ok(())
         );
 
@@ -2786,16 +4048,370 @@ mod tests {
             )
             .unwrap());
         assert_matches!(verification_acc.get_state(), VerificationState::Finalized);
+        assert_eq!(state_summary.get_nullifier_hash_count(), 1);
+        assert_eq!(stats.get_nullifier_count(), 1);
+        assert_eq!(event_sequence.get_sequence(), 1);
+
+        // Called twice
+        assert_matches!(
+            finalize_verification_insert_nullifier(
+                &mut verification_acc,
+                &mut n_acc_0,
+                &mut state_summary,
+                &mut stats,
+                &mut event_sequence,
+                0
+            ),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_finalize_verification_transfer_lamports() -> ProgramResult {
+        set_test_slot(0);
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            _i,
+            _r,
+            _f
+        );
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        let fee_payer_pk = Pubkey::new(
+            &VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(f, fee_payer_pk); // fee_payer
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, FeeCollectorAccount::SIZE);
+        test_account_info!(any, 0);
+        zero_program_account!(governor, GovernorAccount);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut epoch_metrics, EpochMetricsAccount);
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::None);
+            v_acc.set_is_verified(&ElusivOption::Some(true));
+        }
+
+        // Invalid state
+        assert_matches!(
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
+                &pool,
+                &fee_collector,
+                &mut queue,
+                &v_acc,
+                &n_pda,
+                &any,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                &governor,
+                UnverifiedAccountInfo::new(&any),
+                &[],
+                0
+            ),
+            Err(_)
+        );
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::Finalized);
+        }
+
+        // Invalid nullifier_duplicate_account
+        account_info!(
+            invalid_n_pda,
+            VerificationAccount::find_with_pubkey(*f.key, Some(0)).0,
+            vec![1]
+        );
+        assert_matches!(
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
+                &pool,
+                &fee_collector,
+                &mut queue,
+                &v_acc,
+                &invalid_n_pda,
+                &any,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                &governor,
+                UnverifiedAccountInfo::new(&any),
+                &[],
+                0
+            ),
+            Err(_)
+        );
+
+        // Invalid original_fee_payer
+        assert_matches!(
+            finalize_verification_transfer_lamports(
+                &any,
+                &recipient,
+                &pool,
+                &fee_collector,
+                &mut queue,
+                &v_acc,
+                &n_pda,
+                &any,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                &governor,
+                UnverifiedAccountInfo::new(&any),
+                &[],
+                0
+            ),
+            Err(_)
+        );
+
+        // Invalid recipient
+        assert_matches!(
+            finalize_verification_transfer_lamports(
+                &f,
+                &any,
+                &pool,
+                &fee_collector,
+                &mut queue,
+                &v_acc,
+                &n_pda,
+                &any,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                &governor,
+                UnverifiedAccountInfo::new(&any),
+                &[],
+                0
+            ),
+            Err(_)
+        );
+
+        // Commitment queue is full
+        {
+            let mut queue = CommitmentQueue::new(&mut queue);
+            for _ in 0..CommitmentQueue::CAPACITY {
+                queue
+                    .enqueue(CommitmentHashRequest {
+                        commitment: [0; 32],
+                        fee_version: 0,
+                        min_batching_rate: 0,
+                        enqueue_slot: 0,
+                    })
+                    .unwrap();
+            }
+        }
+        assert_matches!(
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
+                &pool,
+                &fee_collector,
+                &mut queue,
+                &v_acc,
+                &n_pda,
+                &any,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                &governor,
+                UnverifiedAccountInfo::new(&any),
+                &[],
+                0
+            ),
+            Err(_)
+        );
+
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+
+        assert_matches!(
+            finalize_verification_transfer_lamports(
+                &f,
+                &recipient,
+                &pool,
+                &fee_collector,
+                &mut queue,
+                &v_acc,
+                &n_pda,
+                &any,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                &governor,
+                UnverifiedAccountInfo::new(&any),
+                &[],
+                0
+            ),
+            Ok(())
+        );
+
+        assert_eq!(n_pda.lamports(), 0);
+        assert_eq!(v_acc.lamports(), 0);
+        pda_account!(v_acc, VerificationAccount, v_acc);
+        assert_matches!(v_acc.get_state(), VerificationState::Closed);
+        assert_eq!(event_sequence.get_sequence(), 1);
+        assert_eq!(stats.get_withdrawal_count(), 1);
+        assert_eq!(epoch_metrics.epoch_metrics(0).unwrap().withdrawal_count, 1);
+
+        // The change commitment is enqueued for insertion into the MT
+        let queue = CommitmentQueue::new(&mut queue);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(
+            queue.view_first().unwrap().commitment,
+            public_inputs.join_split.output_commitment.reduce()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_finalize_verification_transfer_lamports_batch_payouts() -> ProgramResult {
+        set_test_slot(0);
+        let recipients = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let batch_payouts = BatchPayouts::new(&[
+            (recipients[0], LAMPORTS_PER_SOL / 4),
+            (recipients[1], LAMPORTS_PER_SOL / 4 * 3),
+        ])
+        .unwrap();
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            recipient_bytes,
+            _i,
+            _r,
+            _f,
+            None,
+            Some(batch_payouts)
+        );
+
+        account_info!(recipient, Pubkey::new_from_array(recipient_bytes));
+        account_info!(recipient0, recipients[0]);
+        account_info!(recipient1, recipients[1]);
+        let fee_payer_pk = Pubkey::new(
+            &VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(f, fee_payer_pk); // fee_payer
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, FeeCollectorAccount::SIZE);
+        test_account_info!(any, 0);
+        zero_program_account!(governor, GovernorAccount);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+        let mut data = vec![0; CommitmentQueueAccount::SIZE];
+        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut epoch_metrics, EpochMetricsAccount);
+
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::Finalized);
+            v_acc.set_other_data(&mutate(&v_acc.get_other_data(), |data| {
+                data.batch_payouts = ElusivOption::Some(batch_payouts);
+            }));
+        }
+
+        // Missing, additional or reordered batch payout recipients
+        for batch_payout_recipients in [
+            vec![&recipient0],
+            vec![&recipient0, &recipient1, &recipient],
+            vec![&recipient1, &recipient0],
+        ] {
+            assert_matches!(
+                finalize_verification_transfer_lamports(
+                    &f,
+                    &recipient,
+                    &pool,
+                    &fee_collector,
+                    &mut queue,
+                    &v_acc,
+                    &n_pda,
+                    &any,
+                    &mut event_sequence,
+                    &mut stats,
+                    &mut epoch_metrics,
+                    &governor,
+                    UnverifiedAccountInfo::new(&any),
+                    &batch_payout_recipients,
+                    0
+                ),
+                Err(_)
+            );
+        }
 
-        // Called twice
-        assert_matches!(
-            finalize_verification_insert_nullifier(&mut verification_acc, &mut n_acc_0, 0),
-            Err(_)
-        );
+        let recipient_lamports = recipient.lamports();
+        let lamports = recipient0.lamports();
+        finalize_verification_transfer_lamports(
+            &f,
+            &recipient,
+            &pool,
+            &fee_collector,
+            &mut queue,
+            &v_acc,
+            &n_pda,
+            &any,
+            &mut event_sequence,
+            &mut stats,
+            &mut epoch_metrics,
+            &governor,
+            UnverifiedAccountInfo::new(&any),
+            &[&recipient0, &recipient1],
+            0,
+        )?;
+
+        // All recipients are paid in the same instruction (and not the `recipient`)
+        assert_eq!(recipient0.lamports(), lamports + LAMPORTS_PER_SOL / 4);
+        assert_eq!(recipient1.lamports(), lamports + LAMPORTS_PER_SOL / 4 * 3);
+        assert_eq!(recipient.lamports(), recipient_lamports);
+
+        // A withdrawal is emitted for each recipient
+        assert_eq!(event_sequence.get_sequence(), 2);
+        assert_eq!(stats.get_withdrawal_count(), 1);
+
+        Ok(())
     }
 
     #[test]
-    fn test_finalize_verification_transfer_lamports() -> ProgramResult {
+    fn test_finalize_verification_transfer_lamports_invalid_pooled() -> ProgramResult {
         finalize_send_test!(
             LAMPORTS_TOKEN_ID,
             LAMPORTS_PER_SOL,
@@ -2817,8 +4433,9 @@ mod tests {
         );
         account_info!(f, fee_payer_pk); // fee_payer
         test_account_info!(pool, 0);
-        test_account_info!(fee_collector, 0);
+        test_account_info!(fee_collector, FeeCollectorAccount::SIZE);
         test_account_info!(any, 0);
+        zero_program_account!(governor, GovernorAccount);
         test_pda_account_info!(
             n_pda,
             NullifierDuplicateAccount,
@@ -2830,117 +4447,30 @@ mod tests {
         account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
         let mut data = vec![0; CommitmentQueueAccount::SIZE];
         let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut epoch_metrics, EpochMetricsAccount);
 
-        {
-            pda_account!(mut v_acc, VerificationAccount, v_acc);
-            v_acc.set_state(&VerificationState::None);
-            v_acc.set_is_verified(&ElusivOption::Some(true));
-        }
-
-        // Invalid state
-        assert_matches!(
-            finalize_verification_transfer_lamports(
-                &f,
-                &recipient,
-                &pool,
-                &fee_collector,
-                &mut queue,
-                &v_acc,
-                &n_pda,
-                &any,
-                0
-            ),
-            Err(_)
-        );
-
+        let subvention = 100;
+        let commitment_hash_fee = 200;
         {
             pda_account!(mut v_acc, VerificationAccount, v_acc);
             v_acc.set_state(&VerificationState::Finalized);
+            v_acc.set_is_verified(&ElusivOption::Some(false));
+            v_acc.set_is_pooled(&true);
+            v_acc.set_is_acquired(&true);
+
+            let mut data = v_acc.get_other_data();
+            data.subvention = subvention;
+            data.commitment_hash_fee = Lamports(commitment_hash_fee);
+            v_acc.set_other_data(&data);
         }
 
-        // Invalid nullifier_duplicate_account
-        account_info!(
-            invalid_n_pda,
-            VerificationAccount::find_with_pubkey(*f.key, Some(0)).0,
-            vec![1]
-        );
-        assert_matches!(
-            finalize_verification_transfer_lamports(
-                &f,
-                &recipient,
-                &pool,
-                &fee_collector,
-                &mut queue,
-                &v_acc,
-                &invalid_n_pda,
-                &any,
-                0
-            ),
-            Err(_)
-        );
-
-        // Invalid original_fee_payer
-        assert_matches!(
-            finalize_verification_transfer_lamports(
-                &any,
-                &recipient,
-                &pool,
-                &fee_collector,
-                &mut queue,
-                &v_acc,
-                &n_pda,
-                &any,
-                0
-            ),
-            Err(_)
-        );
-
-        // Invalid recipient
-        assert_matches!(
-            finalize_verification_transfer_lamports(
-                &f,
-                &any,
-                &pool,
-                &fee_collector,
-                &mut queue,
-                &v_acc,
-                &n_pda,
-                &any,
-                0
-            ),
-            Err(_)
-        );
-
-        // Commitment queue is full
-        {
-            let mut queue = CommitmentQueue::new(&mut queue);
-            for _ in 0..CommitmentQueue::CAPACITY {
-                queue
-                    .enqueue(CommitmentHashRequest {
-                        commitment: [0; 32],
-                        fee_version: 0,
-                        min_batching_rate: 0,
-                    })
-                    .unwrap();
-            }
-        }
-        assert_matches!(
-            finalize_verification_transfer_lamports(
-                &f,
-                &recipient,
-                &pool,
-                &fee_collector,
-                &mut queue,
-                &v_acc,
-                &n_pda,
-                &any,
-                0
-            ),
-            Err(_)
-        );
-
-        let mut data = vec![0; CommitmentQueueAccount::SIZE];
-        let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        let deposit = crate::processor::pooled_verification_account_deposit()?;
+        let v_acc_lamports = v_acc.lamports();
+        let fee_collector_lamports = fee_collector.lamports();
+        let pool_lamports = pool.lamports();
+        let n_pda_lamports = n_pda.lamports();
 
         assert_matches!(
             finalize_verification_transfer_lamports(
@@ -2952,21 +4482,43 @@ mod tests {
                 &v_acc,
                 &n_pda,
                 &any,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                &governor,
+                UnverifiedAccountInfo::new(&any),
+                &[],
                 0
             ),
             Ok(())
         );
 
+        // The deposit (instead of the rent) is confiscated, the pooled account stays open
+        assert_eq!(v_acc.lamports(), v_acc_lamports - deposit);
+        assert_eq!(
+            fee_collector.lamports(),
+            fee_collector_lamports + n_pda_lamports + deposit + subvention + commitment_hash_fee
+        );
+        assert_eq!(
+            pool.lamports(),
+            pool_lamports - subvention - commitment_hash_fee
+        );
         assert_eq!(n_pda.lamports(), 0);
-        assert_eq!(v_acc.lamports(), 0);
+
         pda_account!(v_acc, VerificationAccount, v_acc);
+        assert!(v_acc.get_is_pooled());
         assert_matches!(v_acc.get_state(), VerificationState::Closed);
 
+        // No commitment is enqueued
+        let queue = CommitmentQueue::new(&mut queue);
+        assert_eq!(queue.len(), 0);
+
         Ok(())
     }
 
     #[test]
     fn test_finalize_verification_transfer_lamports_merge() -> ProgramResult {
+        set_test_slot(0);
         finalize_send_test!(
             LAMPORTS_TOKEN_ID,
             0,
@@ -2987,8 +4539,9 @@ mod tests {
         );
         account_info!(f, fee_payer_pk); // fee_payer
         test_account_info!(pool, 0);
-        test_account_info!(fee_collector, 0);
+        test_account_info!(fee_collector, FeeCollectorAccount::SIZE);
         test_account_info!(any, 0);
+        zero_program_account!(governor, GovernorAccount);
         test_pda_account_info!(
             n_pda,
             NullifierDuplicateAccount,
@@ -3001,6 +4554,9 @@ mod tests {
         account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
         let mut data = vec![0; CommitmentQueueAccount::SIZE];
         let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut epoch_metrics, EpochMetricsAccount);
 
         {
             pda_account!(mut v_acc, VerificationAccount, v_acc);
@@ -3020,16 +4576,123 @@ mod tests {
                 &v_acc,
                 &n_pda,
                 &any,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                &governor,
+                UnverifiedAccountInfo::new(&any),
+                &[],
                 0
             ),
             Ok(())
         );
 
+        // Merges and internal transfers emit a shielded-transfer event instead of a withdrawal event
+        assert_eq!(event_sequence.get_sequence(), 1);
+        assert_eq!(stats.get_withdrawal_count(), 0);
+        assert_eq!(epoch_metrics.epoch_metrics(0).unwrap().withdrawal_count, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reset_timed_out_verification() -> ProgramResult {
+        finalize_send_test!(
+            LAMPORTS_TOKEN_ID,
+            LAMPORTS_PER_SOL,
+            public_inputs,
+            verification_acc_data,
+            _recipient,
+            _i,
+            _r,
+            _f
+        );
+
+        let fee_payer_pk = Pubkey::new(
+            &VerificationAccount::new(&mut verification_acc_data)
+                .unwrap()
+                .get_other_data()
+                .fee_payer
+                .skip_mr(),
+        );
+        account_info!(f, fee_payer_pk); // fee_payer
+        test_account_info!(resetter, 0);
+        test_account_info!(pool, 0);
+        test_account_info!(fee_collector, FeeCollectorAccount::SIZE);
+        test_account_info!(any, 0);
+        test_pda_account_info!(
+            n_pda,
+            NullifierDuplicateAccount,
+            public_inputs
+                .join_split
+                .associated_nullifier_duplicate_pda_pubkey(),
+            None
+        );
+        account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
+
+        macro_rules! reset {
+            ($fee_payer: ident) => {
+                reset_timed_out_verification(
+                    &resetter,
+                    &$fee_payer,
+                    &pool,
+                    &pool,
+                    &fee_collector,
+                    &fee_collector,
+                    &v_acc,
+                    &n_pda,
+                    &any,
+                    UnverifiedAccountInfo::new(&any),
+                    0,
+                )
+            };
+        }
+
+        // Not timed out (`current_slot` is zero in tests)
+        assert_matches!(reset!(f), Err(_));
+
+        // Nullifier-hashes are already inserted
+        {
+            pda_account!(mut v_acc, VerificationAccount, v_acc);
+            v_acc.set_state(&VerificationState::Finalized);
+        }
+        assert_matches!(reset!(f), Err(_));
+
         Ok(())
     }
 
+    #[test]
+    fn test_is_verification_resettable() {
+        let mut data = vec![0; VerificationAccount::SIZE];
+        let mut account = VerificationAccount::new(&mut data).unwrap();
+        account.start_computation(100);
+        let timeout = 100 + COMPUTATION_TIMEOUT_SLOTS;
+
+        assert!(!is_verification_resettable(&account, timeout - 1));
+        assert!(is_verification_resettable(&account, timeout));
+
+        for state in [
+            VerificationState::FeeTransferred,
+            VerificationState::ProofSetup,
+        ] {
+            account.set_state(&state);
+            assert!(is_verification_resettable(&account, timeout));
+        }
+
+        for state in [
+            VerificationState::InsertNullifiers,
+            VerificationState::Finalized,
+            VerificationState::Closed,
+            VerificationState::Batched,
+        ] {
+            account.set_state(&state);
+            assert!(!is_verification_resettable(&account, timeout));
+        }
+    }
+
     #[test]
     fn test_finalize_verification_transfer_token() -> ProgramResult {
+        set_test_slot(0);
         finalize_send_test!(
             USDC_TOKEN_ID,
             LAMPORTS_PER_SOL,
@@ -3076,6 +4739,9 @@ mod tests {
         account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
         let mut data = vec![0; CommitmentQueueAccount::SIZE];
         let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut epoch_metrics, EpochMetricsAccount);
 
         {
             pda_account!(mut v_acc, VerificationAccount, v_acc);
@@ -3100,7 +4766,12 @@ mod tests {
                 &spl,
                 &any,
                 &any,
-                0
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                UnverifiedAccountInfo::new(&any),
+                0,
+                USDC_TOKEN_ID
             ),
             Err(_)
         );
@@ -3122,7 +4793,12 @@ mod tests {
                 &spl,
                 &any,
                 &any,
-                0
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                UnverifiedAccountInfo::new(&any),
+                0,
+                USDC_TOKEN_ID
             ),
             Err(_)
         );
@@ -3144,7 +4820,12 @@ mod tests {
                 &any,
                 &any,
                 &any,
-                0
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                UnverifiedAccountInfo::new(&any),
+                0,
+                USDC_TOKEN_ID
             ),
             Err(_)
         );
@@ -3166,7 +4847,12 @@ mod tests {
                 &spl,
                 &any,
                 &any,
-                0
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                UnverifiedAccountInfo::new(&any),
+                0,
+                USDC_TOKEN_ID
             ),
             Err(_)
         );
@@ -3188,11 +4874,17 @@ mod tests {
                 &spl,
                 &any,
                 &any,
-                0
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                UnverifiedAccountInfo::new(&any),
+                0,
+                USDC_TOKEN_ID
             ),
             Err(_)
         );
 
+        // Commitment queue of a different token pool
         assert_matches!(
             finalize_verification_transfer_token(
                 &fee_payer,
@@ -3209,7 +4901,38 @@ mod tests {
                 &spl,
                 &any,
                 &any,
-                0
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                UnverifiedAccountInfo::new(&any),
+                0,
+                USDT_TOKEN_ID
+            ),
+            Err(_)
+        );
+
+        assert_matches!(
+            finalize_verification_transfer_token(
+                &fee_payer,
+                &fee_payer_token,
+                &r,
+                &r,
+                &pool,
+                &pool_token,
+                &fee_collector,
+                &fee_collector_token,
+                &mut queue,
+                &v_acc,
+                &n_pda,
+                &spl,
+                &any,
+                &any,
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                UnverifiedAccountInfo::new(&any),
+                0,
+                USDC_TOKEN_ID
             ),
             Ok(())
         );
@@ -3218,12 +4941,16 @@ mod tests {
         assert_eq!(v_acc.lamports(), 0);
         pda_account!(v_acc, VerificationAccount, v_acc);
         assert_matches!(v_acc.get_state(), VerificationState::Closed);
+        assert_eq!(event_sequence.get_sequence(), 1);
+        assert_eq!(stats.get_withdrawal_count(), 1);
+        assert_eq!(epoch_metrics.epoch_metrics(0).unwrap().withdrawal_count, 1);
 
         Ok(())
     }
 
     #[test]
     fn test_finalize_verification_transfer_token_merge() -> ProgramResult {
+        set_test_slot(0);
         finalize_send_test!(
             USDC_TOKEN_ID,
             0,
@@ -3269,6 +4996,9 @@ mod tests {
         account_info!(v_acc, Pubkey::new_unique(), verification_acc_data);
         let mut data = vec![0; CommitmentQueueAccount::SIZE];
         let mut queue = CommitmentQueueAccount::new(&mut data).unwrap();
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+        zero_program_account!(mut stats, StatsAccount);
+        zero_program_account!(mut epoch_metrics, EpochMetricsAccount);
 
         {
             pda_account!(mut v_acc, VerificationAccount, v_acc);
@@ -3294,11 +5024,21 @@ mod tests {
                 &spl,
                 &any,
                 &any,
-                0
+                &mut event_sequence,
+                &mut stats,
+                &mut epoch_metrics,
+                UnverifiedAccountInfo::new(&any),
+                0,
+                USDC_TOKEN_ID
             ),
             Ok(())
         );
 
+        // Merges and internal transfers emit a shielded-transfer event instead of a withdrawal event
+        assert_eq!(event_sequence.get_sequence(), 1);
+        assert_eq!(stats.get_withdrawal_count(), 0);
+        assert_eq!(epoch_metrics.epoch_metrics(0).unwrap().withdrawal_count, 0);
+
         Ok(())
     }
 
@@ -3406,7 +5146,8 @@ mod tests {
                     &public_inputs,
                     &storage,
                     [&n_account, &n_account],
-                    &[0, 1]
+                    &[0, 1],
+                    &[]
                 ),
                 Err(_)
             );
@@ -3429,7 +5170,8 @@ mod tests {
                 }),
                 &storage,
                 [&n_account, &n_account],
-                &[0, 0]
+                &[0, 0],
+                &[]
             ),
             Err(_)
         );
@@ -3440,7 +5182,8 @@ mod tests {
                 &valid_inputs,
                 &storage,
                 [&n_account, &n_account],
-                &[0, 1]
+                &[0, 1],
+                &[]
             ),
             Ok(())
         );
@@ -3467,7 +5210,8 @@ mod tests {
                     &public_inputs,
                     &storage,
                     [&n_account, &n_account],
-                    &[0, 1]
+                    &[0, 1],
+                    &[]
                 ),
                 Ok(())
             );
@@ -3497,12 +5241,71 @@ mod tests {
                 }),
                 &storage,
                 [&n_account, &n_account],
-                &[0, 1]
+                &[0, 1],
+                &[]
             ),
             Err(_)
         );
     }
 
+    #[test]
+    fn test_check_join_split_public_inputs_archived_root() {
+        storage_account!(storage);
+        parent_account!(n_account, NullifierAccount);
+
+        let root = RawU256::new(u256_from_str_skip_mr("5"));
+        let public_inputs = JoinSplitPublicInputs {
+            input_commitments: vec![
+                InputCommitment {
+                    root: Some(empty_root_raw()),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("0")),
+                },
+                InputCommitment {
+                    root: Some(root),
+                    nullifier_hash: RawU256::new(u256_from_str_skip_mr("1")),
+                },
+            ],
+            output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+            output_commitment_index: 123,
+            fee_version: 0,
+            amount: 0,
+            fee: 456,
+            token_id: 0,
+        };
+
+        let mut data = vec![0; ArchivedTreeAccount::SIZE];
+        {
+            let mut archived_tree = ArchivedTreeAccount::new(&mut data).unwrap();
+            archived_tree.set_mt_index(&1);
+            archived_tree.set_commitment_root(&root.reduce());
+        }
+        let pk = ArchivedTreeAccount::find_with_offsets(None, &nullifier_account_offsets(0, 1)).0;
+        account_info!(archived_tree_account, pk, data.clone());
+        account_info!(invalid_archived_tree_account, Pubkey::new_unique(), data);
+
+        let check = |archived_tree_accounts: &[&AccountInfo]| {
+            check_join_split_public_inputs(
+                &public_inputs,
+                &storage,
+                [&n_account, &n_account],
+                &[0, 1],
+                archived_tree_accounts,
+            )
+        };
+
+        // The root is not the root of the closed MT
+        assert_matches!(check(&[]), Err(_));
+
+        // Invalid ArchivedTreeAccount
+        assert_matches!(check(&[&invalid_archived_tree_account]), Err(_));
+
+        // Root of the ArchivedTreeAccount
+        assert_matches!(
+            check(&[&invalid_archived_tree_account, &archived_tree_account]),
+            Ok(())
+        );
+    }
+
     struct StubInstruction(u8, Option<Vec<u8>>, Pubkey);
 
     impl From<StubInstruction> for Instruction {
@@ -4014,6 +5817,23 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_is_pooled_verification_account_index() {
+        assert!(!is_pooled_verification_account_index(0));
+        assert!(!is_pooled_verification_account_index(
+            RESERVED_VERIFICATION_ACCOUNT_IDS
+        ));
+        assert!(is_pooled_verification_account_index(
+            pooled_verification_account_index(0)
+        ));
+        assert!(is_pooled_verification_account_index(
+            pooled_verification_account_index(VERIFICATION_ACCOUNT_POOL_SIZE as u8 - 1)
+        ));
+        assert!(!is_pooled_verification_account_index(
+            pooled_verification_account_index(VERIFICATION_ACCOUNT_POOL_SIZE as u8)
+        ));
+    }
+
     fn test_public_inputs() -> Vec<U256> {
         vec![
             "7889586699914970744657798935358222218486353295005298675075639741334684257960",
@@ -0,0 +1,287 @@
+use crate::error::ElusivError;
+use crate::fields::{is_element_scalar_field, u256_to_big_uint};
+use crate::macros::{guard, BorshSerDeSized};
+use crate::state::{
+    config::ConfigAccount,
+    governor::{FeeCollectorAccount, GovernorAccount},
+    nullifier::NULLIFIERS_PER_ACCOUNT,
+    queue::{CommitmentQueue, RingQueue},
+    stats::STATS_TOKEN_COUNT,
+    storage::{StorageAccount, MT_HEIGHT},
+};
+use crate::types::{RawU256, U256};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{entrypoint::ProgramResult, program::set_return_data};
+
+/// The root of the active MT of a shielded pool (returned by [`get_current_root`])
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Debug)]
+pub struct CurrentRoot {
+    pub mt_index: u32,
+    pub root: U256,
+}
+
+/// Returns the [`CurrentRoot`] of the shielded pool of `token_id`
+pub fn get_current_root(storage_account: &StorageAccount, _token_id: u16) -> ProgramResult {
+    return_data(&CurrentRoot {
+        mt_index: storage_account.get_trees_count(),
+        root: storage_account.get_root()?,
+    })
+}
+
+/// Returns the index of the next commitment inserted into the active MT of the shielded pool of `token_id` (`u32`)
+pub fn get_next_leaf_index(storage_account: &StorageAccount, _token_id: u16) -> ProgramResult {
+    return_data(&storage_account.get_next_commitment_ptr())
+}
+
+/// Attests whether a commitment is a leaf of the active MT of a shielded pool (returned by [`attest_membership`])
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Debug)]
+pub struct MembershipAttestation {
+    pub mt_index: u32,
+    pub leaf_index: u32,
+
+    /// The root of the active MT at the time of the attestation
+    pub root: U256,
+    pub is_member: bool,
+}
+
+/// Returns the [`MembershipAttestation`] of `commitment` at `leaf_index` in the MT with `mt_index` of the shielded pool of `token_id`
+/// - the [`StorageAccount`] contains the complete active MT, so the opening of the leaf is verified by reading the leaf itself
+/// - commitments in closed (or archived) MTs cannot be attested, since only their roots remain on-chain (`is_member` is `false`)
+pub fn attest_membership(
+    storage_account: &StorageAccount,
+
+    _token_id: u16,
+    mt_index: u32,
+    leaf_index: u32,
+    commitment: RawU256,
+) -> ProgramResult {
+    let is_member = mt_index == storage_account.get_trees_count()
+        && leaf_index < storage_account.get_next_commitment_ptr()
+        && is_element_scalar_field(u256_to_big_uint(commitment.skip_mr_ref()))
+        && storage_account.get_node(leaf_index as usize, MT_HEIGHT as usize)?
+            == commitment.reduce();
+
+    return_data(&MembershipAttestation {
+        mt_index: storage_account.get_trees_count(),
+        leaf_index,
+        root: storage_account.get_root()?,
+        is_member,
+    })
+}
+
+/// Returns the current fee-version (`u32`)
+pub fn get_fee_version(governor: &GovernorAccount) -> ProgramResult {
+    return_data(&governor.get_fee_version())
+}
+
+/// Returns the number of commitments in a MT-root hashing batch (`u32`)
+pub fn get_batching_rate(governor: &GovernorAccount) -> ProgramResult {
+    return_data(&governor.get_commitment_batching_rate())
+}
+
+/// Returns whether the program is paused, i.e. halted or in the exit-only emergency mode (`bool`)
+pub fn get_pause_state(governor: &GovernorAccount) -> ProgramResult {
+    return_data(&(governor.get_is_halted() || governor.get_emergency_mode()))
+}
+
+/// The protocol revenue of a token (returned by [`get_protocol_revenue`])
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Debug)]
+pub struct ProtocolRevenue {
+    /// Collected network-fees of all fee-versions
+    pub collected: u64,
+    pub withdrawn: u64,
+}
+
+/// Returns the [`ProtocolRevenue`] of `token_id`
+pub fn get_protocol_revenue(fee_collector: &FeeCollectorAccount, token_id: u16) -> ProgramResult {
+    guard!(
+        (token_id as usize) < STATS_TOKEN_COUNT,
+        ElusivError::InvalidInstructionData
+    );
+
+    return_data(&ProtocolRevenue {
+        collected: fee_collector.get_total_network_fees(token_id as usize),
+        withdrawn: fee_collector.get_total_withdrawn(token_id as usize),
+    })
+}
+
+/// The sizes the program has been built with, its runtime limits and its current batching rate (returned by [`get_program_config`])
+/// - the sizes are compile-time constants, since they define the layouts of the accounts and the circuits
+/// - the limits are configured in the [`ConfigAccount`] (bounded by the sizes)
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Debug)]
+pub struct ProgramConfig {
+    pub mt_height: u32,
+    pub commitment_queue_size: u32,
+    pub nullifiers_per_account: u32,
+    pub commitment_queue_capacity: u32,
+    pub max_commitment_batching_rate: u32,
+    pub commitment_batching_rate: u32,
+}
+
+/// Returns the [`ProgramConfig`]
+pub fn get_program_config(governor: &GovernorAccount, config: &ConfigAccount) -> ProgramResult {
+    return_data(&ProgramConfig {
+        mt_height: MT_HEIGHT,
+        commitment_queue_size: CommitmentQueue::CAPACITY,
+        nullifiers_per_account: NULLIFIERS_PER_ACCOUNT as u32,
+        commitment_queue_capacity: config.effective_commitment_queue_capacity(),
+        max_commitment_batching_rate: config.effective_max_commitment_batching_rate(),
+        commitment_batching_rate: governor.get_commitment_batching_rate(),
+    })
+}
+
+/// Sets the Borsh-serialized `value` as the return-data of the instruction
+fn return_data<T: BorshSerialize>(value: &T) -> ProgramResult {
+    set_return_data(&value.try_to_vec()?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields::u256_from_str_skip_mr;
+    use crate::macros::{parent_account, zero_program_account};
+    use crate::syscall_stubs::install_syscall_stubs;
+
+    fn returned<T: BorshDeserialize>() -> T {
+        let (program_id, data) = solana_program::program::get_return_data().unwrap();
+        assert_eq!(program_id, crate::id());
+        T::try_from_slice(&data).unwrap()
+    }
+
+    macro_rules! query {
+        ($query: expr, $ty: ty) => {{
+            install_syscall_stubs();
+            $query.unwrap();
+            returned::<$ty>()
+        }};
+    }
+
+    #[test]
+    fn test_get_current_root() {
+        parent_account!(mut storage_account, StorageAccount);
+        storage_account.set_trees_count(&2);
+
+        assert_eq!(
+            query!(get_current_root(&storage_account, 0), CurrentRoot),
+            CurrentRoot {
+                mt_index: 2,
+                root: storage_account.get_root().unwrap(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_next_leaf_index() {
+        parent_account!(mut storage_account, StorageAccount);
+        storage_account.set_next_commitment_ptr(&123);
+
+        assert_eq!(query!(get_next_leaf_index(&storage_account, 0), u32), 123);
+    }
+
+    #[test]
+    fn test_attest_membership() {
+        parent_account!(mut storage_account, StorageAccount);
+        storage_account.set_trees_count(&1);
+
+        let commitment = RawU256::new(u256_from_str_skip_mr("123"));
+        storage_account
+            .set_node(&commitment.reduce(), 2, MT_HEIGHT as usize)
+            .unwrap();
+
+        let attest = |storage_account: &StorageAccount,
+                      mt_index: u32,
+                      leaf_index: u32,
+                      commitment: RawU256| {
+            query!(
+                attest_membership(storage_account, 0, mt_index, leaf_index, commitment),
+                MembershipAttestation
+            )
+            .is_member
+        };
+
+        // Not yet inserted
+        assert!(!attest(&storage_account, 1, 2, commitment));
+
+        storage_account.set_next_commitment_ptr(&3);
+        assert_eq!(
+            query!(
+                attest_membership(&storage_account, 0, 1, 2, commitment),
+                MembershipAttestation
+            ),
+            MembershipAttestation {
+                mt_index: 1,
+                leaf_index: 2,
+                root: storage_account.get_root().unwrap(),
+                is_member: true,
+            }
+        );
+
+        // Other leaf, MT or commitment
+        assert!(!attest(&storage_account, 1, 1, commitment));
+        assert!(!attest(&storage_account, 0, 2, commitment));
+        assert!(!attest(
+            &storage_account,
+            1,
+            2,
+            RawU256::new(u256_from_str_skip_mr("124"))
+        ));
+
+        // Not a field element
+        assert!(!attest(&storage_account, 1, 2, RawU256::new([255; 32])));
+    }
+
+    #[test]
+    fn test_governor_queries() {
+        zero_program_account!(mut governor, GovernorAccount);
+        governor.set_fee_version(&3);
+        governor.set_commitment_batching_rate(&4);
+
+        assert_eq!(query!(get_fee_version(&governor), u32), 3);
+        assert_eq!(query!(get_batching_rate(&governor), u32), 4);
+        assert!(!query!(get_pause_state(&governor), bool));
+
+        governor.set_is_halted(&true);
+        assert!(query!(get_pause_state(&governor), bool));
+
+        governor.set_is_halted(&false);
+        governor.set_emergency_mode(&true);
+        assert!(query!(get_pause_state(&governor), bool));
+
+        zero_program_account!(mut config_account, ConfigAccount);
+        let config = query!(
+            get_program_config(&governor, &config_account),
+            ProgramConfig
+        );
+        assert_eq!(config.mt_height, MT_HEIGHT);
+        assert_eq!(config.commitment_queue_size, CommitmentQueue::CAPACITY);
+        assert_eq!(config.commitment_queue_capacity, CommitmentQueue::CAPACITY);
+        assert_eq!(config.commitment_batching_rate, 4);
+
+        config_account.set_commitment_queue_capacity(&16);
+        config_account.set_max_commitment_batching_rate(&2);
+        let config = query!(
+            get_program_config(&governor, &config_account),
+            ProgramConfig
+        );
+        assert_eq!(config.commitment_queue_size, CommitmentQueue::CAPACITY);
+        assert_eq!(config.commitment_queue_capacity, 16);
+        assert_eq!(config.max_commitment_batching_rate, 2);
+    }
+
+    #[test]
+    fn test_get_protocol_revenue() {
+        zero_program_account!(mut fee_collector, FeeCollectorAccount);
+        fee_collector.add_network_fee(0, 0, 100);
+        fee_collector.set_total_withdrawn(0, &40);
+
+        assert_eq!(
+            query!(get_protocol_revenue(&fee_collector, 0), ProtocolRevenue),
+            ProtocolRevenue {
+                collected: 100,
+                withdrawn: 40,
+            }
+        );
+        assert!(get_protocol_revenue(&fee_collector, STATS_TOKEN_COUNT as u16).is_err());
+    }
+}
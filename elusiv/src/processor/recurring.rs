@@ -0,0 +1,185 @@
+use super::ProofRequest;
+use crate::computation::current_slot;
+use crate::error::ElusivError;
+use crate::events::ElusivEvent;
+use crate::state::{
+    event::EventSequenceAccount,
+    program_account::Lifecycle,
+    recurring::{RecurringPaymentAccount, MAX_RECURRING_INSTALLMENTS},
+};
+use crate::types::U256;
+use borsh::BorshSerialize;
+use elusiv_utils::{guard, pda_account};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, hash::hashv, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Identifies a prepared proof-request as an installment of a [`RecurringPaymentAccount`]
+pub fn recurring_installment_hash(request: &ProofRequest) -> Result<U256, ProgramError> {
+    Ok(hashv(&[&request.try_to_vec()?]).to_bytes())
+}
+
+/// Opens a [`RecurringPaymentAccount`] with the hashes of `installments_count` prepared proof-requests
+pub fn create_recurring_payment<'a>(
+    owner: &AccountInfo<'a>,
+    recurring_payment_account: &AccountInfo<'a>,
+
+    recurring_payment_index: u32,
+    period_slots: u64,
+    first_release_slot: u64,
+    installments_count: u32,
+    installments: [U256; MAX_RECURRING_INSTALLMENTS],
+) -> ProgramResult {
+    guard!(
+        installments_count as usize <= MAX_RECURRING_INSTALLMENTS,
+        ElusivError::InvalidInstructionData
+    );
+
+    RecurringPaymentAccount::reopen(
+        owner,
+        recurring_payment_account,
+        Some(*owner.key),
+        Some(recurring_payment_index),
+        None,
+    )?;
+
+    pda_account!(
+        mut recurring_payment_account,
+        RecurringPaymentAccount,
+        recurring_payment_account
+    );
+    recurring_payment_account.setup(
+        owner.key,
+        period_slots,
+        first_release_slot,
+        &installments[..installments_count as usize],
+    )
+}
+
+/// Permissionless crank releasing the next due installment of a [`RecurringPaymentAccount`]
+/// - `request` needs to be the prepared proof-request of the installment
+pub fn release_recurring_installment(
+    recurring_payment_account: &mut RecurringPaymentAccount,
+    event_sequence: &mut EventSequenceAccount,
+
+    owner: Pubkey,
+    recurring_payment_index: u32,
+    request: ProofRequest,
+) -> ProgramResult {
+    guard!(
+        recurring_payment_account.get_owner() == owner,
+        ElusivError::InvalidAccount
+    );
+
+    let slot = current_slot()?;
+    let installment = recurring_installment_hash(&request)?;
+    let installment_index =
+        recurring_payment_account.release_next_installment(slot, &installment)?;
+
+    event_sequence.emit(&ElusivEvent::RecurringInstallmentReleased {
+        owner,
+        recurring_payment_index,
+        installment_index,
+        installment,
+        slot,
+    })
+}
+
+/// Closes a [`RecurringPaymentAccount`], preventing the release of all remaining installments
+pub fn cancel_recurring_payment<'a>(
+    owner: &AccountInfo<'a>,
+    recurring_payment_account: &AccountInfo<'a>,
+
+    _recurring_payment_index: u32,
+) -> ProgramResult {
+    RecurringPaymentAccount::close(owner, recurring_payment_account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{test_account_info, zero_program_account};
+    use crate::syscall_stubs::set_test_slot;
+    use crate::types::{JoinSplitPublicInputs, RawU256, SendPublicInputs};
+    use assert_matches::assert_matches;
+
+    fn request(amount: u64) -> ProofRequest {
+        ProofRequest::Send(SendPublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments: Vec::new(),
+                output_commitment: RawU256::new([0; 32]),
+                output_commitment_index: 0,
+                fee_version: 0,
+                amount,
+                fee: 0,
+                token_id: 0,
+            },
+            recipient_is_associated_token_account: false,
+            solana_pay_transfer: false,
+            hashed_inputs: [0; 32],
+            encrypted_memo: None,
+        })
+    }
+
+    #[test]
+    fn test_release_recurring_installment() {
+        set_test_slot(0);
+        zero_program_account!(mut account, RecurringPaymentAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+        let owner = Pubkey::new_unique();
+        let installments = [
+            recurring_installment_hash(&request(1)).unwrap(),
+            recurring_installment_hash(&request(2)).unwrap(),
+        ];
+        account.setup(&owner, 1, 0, &installments).unwrap();
+
+        // Invalid owner
+        assert_matches!(
+            release_recurring_installment(
+                &mut account,
+                &mut event_sequence,
+                Pubkey::new_unique(),
+                0,
+                request(1)
+            ),
+            Err(_)
+        );
+
+        // Invalid request
+        assert_matches!(
+            release_recurring_installment(&mut account, &mut event_sequence, owner, 0, request(2)),
+            Err(_)
+        );
+
+        release_recurring_installment(&mut account, &mut event_sequence, owner, 0, request(1))
+            .unwrap();
+        assert_eq!(account.get_released_count(), 1);
+        assert_eq!(event_sequence.get_sequence(), 1);
+
+        // Not yet due (at slot zero)
+        assert_matches!(
+            release_recurring_installment(&mut account, &mut event_sequence, owner, 0, request(2)),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_create_recurring_payment_invalid_installments_count() {
+        test_account_info!(owner, 0);
+        test_account_info!(recurring_payment_account, 0);
+
+        assert_matches!(
+            create_recurring_payment(
+                &owner,
+                &recurring_payment_account,
+                0,
+                1,
+                0,
+                MAX_RECURRING_INSTALLMENTS as u32 + 1,
+                [[0; 32]; MAX_RECURRING_INSTALLMENTS]
+            ),
+            Err(_)
+        );
+    }
+}
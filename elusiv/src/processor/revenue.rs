@@ -0,0 +1,549 @@
+use crate::error::ElusivError;
+use crate::macros::{guard, pda_account};
+use crate::processor::utils::{
+    burn_token_from_pda, transfer_token_from_pda, verify_program_token_account,
+};
+use crate::processor::verify_governance_authority;
+use crate::state::{
+    fee::{FeeAccount, NetworkFeeSplit},
+    governor::{FeeCollectorAccount, GovernorAccount, REVENUE_DESTINATION_COUNT},
+    warden::WardenAccount,
+};
+use crate::token::Token;
+use crate::types::U256;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+/// Maximum value of the `secondary_revenue_split_bps` of the [`FeeCollectorAccount`]
+const MAX_REVENUE_SPLIT_BPS: u16 = 10_000;
+
+/// Sets the allowlisted revenue destinations and the share of withdrawals sent to the second destination
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - a second destination requires a first one and a split requires a second destination
+pub fn set_revenue_destinations(
+    fee_collector: &mut FeeCollectorAccount,
+    governor: &GovernorAccount,
+    signers: &[&AccountInfo],
+
+    destinations: [U256; REVENUE_DESTINATION_COUNT],
+    secondary_split_bps: u16,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    guard!(
+        secondary_split_bps <= MAX_REVENUE_SPLIT_BPS,
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        destinations[0] != [0; 32] || destinations[1] == [0; 32],
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        destinations[1] != [0; 32] || secondary_split_bps == 0,
+        ElusivError::InvalidInstructionData
+    );
+
+    for (i, destination) in destinations.iter().enumerate() {
+        fee_collector.set_revenue_destinations(i, destination);
+    }
+    fee_collector.set_secondary_revenue_split_bps(&secondary_split_bps);
+
+    Ok(())
+}
+
+/// Withdraws `amount` of the collected network-fees of `token_id` from the [`FeeCollectorAccount`] to the revenue destinations
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - only the collected network-fees can be withdrawn, the remaining balance is reserved for subventions
+/// - `secondary_destination` receives the `secondary_revenue_split_bps` share of `amount` (it is ignored without a split)
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_protocol_revenue<'a>(
+    governor: &GovernorAccount,
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    secondary_destination: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    signers: &[&AccountInfo],
+
+    token_id: u16,
+    amount: u64,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
+
+    let secondary_amount = {
+        pda_account!(fee_collector, FeeCollectorAccount, fee_collector);
+        guard!(amount > 0, ElusivError::InvalidAmount);
+        guard!(
+            amount <= fee_collector.withdrawable_revenue(token_id),
+            ElusivError::InsufficientFunds
+        );
+
+        let destinations = [
+            fee_collector.get_revenue_destinations(0),
+            fee_collector.get_revenue_destinations(1),
+        ];
+        guard!(
+            destinations[0] != [0; 32] && destination.key.to_bytes() == destinations[0],
+            ElusivError::InvalidAccount
+        );
+
+        let split_bps = fee_collector.get_secondary_revenue_split_bps();
+        if split_bps > 0 {
+            guard!(
+                secondary_destination.key.to_bytes() == destinations[1],
+                ElusivError::InvalidAccount
+            );
+        }
+
+        (amount as u128 * split_bps as u128 / MAX_REVENUE_SPLIT_BPS as u128) as u64
+    };
+
+    for (recipient, recipient_amount) in [
+        (destination, amount - secondary_amount),
+        (secondary_destination, secondary_amount),
+    ] {
+        if recipient_amount == 0 {
+            continue;
+        }
+
+        transfer_token_from_pda::<FeeCollectorAccount>(
+            fee_collector,
+            fee_collector_account,
+            recipient,
+            token_program,
+            Token::new(token_id, recipient_amount),
+            None,
+            None,
+        )?;
+    }
+
+    pda_account!(mut fee_collector, FeeCollectorAccount, fee_collector);
+    let withdrawn = fee_collector.get_total_withdrawn(token_id as usize);
+    fee_collector.set_total_withdrawn(token_id as usize, &(withdrawn + amount));
+
+    Ok(())
+}
+
+/// Sets the [`NetworkFeeSplit`] of `fee_version`, applied to the network-fees collected from now on
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - the split is stored in the [`FeeAccount`] and mirrored into the [`FeeCollectorAccount`], which apportions the collected network-fees
+pub fn set_network_fee_split(
+    governor: &GovernorAccount,
+    fee: &mut FeeAccount,
+    fee_collector: &mut FeeCollectorAccount,
+    signers: &[&AccountInfo],
+
+    fee_version: u32,
+    split: NetworkFeeSplit,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    guard!(split.is_valid(), ElusivError::InvalidInstructionData);
+
+    // The revenue of outdated fee-versions is no longer tracked
+    guard!(
+        fee_collector.is_fee_version_tracked(fee_version),
+        ElusivError::InvalidFeeVersion
+    );
+
+    fee_collector.set_network_fee_split(fee_version, split);
+    fee.set_network_fee_split(&split);
+
+    Ok(())
+}
+
+/// Distributes `amount` of the relayer-incentive share of the collected network-fees of `token_id` to a registered warden
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - `amount` is moved from the fee collector to the pool and escrowed as reward of the warden (see [`crate::processor::claim_rewards`])
+#[allow(clippy::too_many_arguments)]
+pub fn distribute_relayer_incentives<'a>(
+    governor: &GovernorAccount,
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    warden_account: &mut WardenAccount,
+    token_program: &AccountInfo<'a>,
+    signers: &[&AccountInfo],
+
+    _warden: Pubkey,
+    token_id: u16,
+    amount: u64,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
+    verify_program_token_account(pool, pool_account, token_id)?;
+    guard!(
+        warden_account.is_registered(),
+        ElusivError::WardenIsNotRegistered
+    );
+
+    {
+        pda_account!(fee_collector, FeeCollectorAccount, fee_collector);
+        guard!(amount > 0, ElusivError::InvalidAmount);
+        guard!(
+            amount <= fee_collector.pending_relayer_incentives(token_id),
+            ElusivError::InsufficientFunds
+        );
+    }
+
+    // `fee_collector` transfers `amount` to `pool` (token)
+    transfer_token_from_pda::<FeeCollectorAccount>(
+        fee_collector,
+        fee_collector_account,
+        pool_account,
+        token_program,
+        Token::new(token_id, amount),
+        None,
+        None,
+    )?;
+    warden_account.accrue_reward(token_id, amount)?;
+
+    pda_account!(mut fee_collector, FeeCollectorAccount, fee_collector);
+    let distributed = fee_collector.get_total_relayer_incentives_distributed(token_id as usize);
+    fee_collector
+        .set_total_relayer_incentives_distributed(token_id as usize, &(distributed + amount));
+
+    Ok(())
+}
+
+/// Burns the burn share of the collected network-fees of `token_id` (permissionless)
+pub fn burn_collected_fees<'a>(
+    fee_collector: &AccountInfo<'a>,
+    fee_collector_account: &AccountInfo<'a>,
+    burn_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+
+    token_id: u16,
+) -> ProgramResult {
+    verify_program_token_account(fee_collector, fee_collector_account, token_id)?;
+
+    let amount = {
+        pda_account!(fee_collector, FeeCollectorAccount, fee_collector);
+        fee_collector.pending_burn(token_id)
+    };
+    guard!(amount > 0, ElusivError::InvalidAmount);
+
+    burn_token_from_pda::<FeeCollectorAccount>(
+        fee_collector,
+        fee_collector_account,
+        burn_account,
+        token_program,
+        Token::new(token_id, amount),
+        None,
+        None,
+    )?;
+
+    pda_account!(mut fee_collector, FeeCollectorAccount, fee_collector);
+    let burned = fee_collector.get_total_burned(token_id as usize);
+    fee_collector.set_total_burned(token_id as usize, &(burned + amount));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{
+        account_info, test_account_info, test_pda_account_info, zero_program_account,
+    };
+    use crate::state::governor::{PoolAccount, FEE_REVENUE_VERSION_COUNT};
+    use crate::state::warden::MIN_WARDEN_STAKE;
+    use crate::token::LAMPORTS_TOKEN_ID;
+    use assert_matches::assert_matches;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_set_revenue_destinations() {
+        zero_program_account!(mut fee_collector, FeeCollectorAccount);
+        zero_program_account!(governor, GovernorAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_account_info!(invalid_authority, 0);
+
+        let a = Pubkey::new_unique().to_bytes();
+        let b = Pubkey::new_unique().to_bytes();
+
+        macro_rules! set {
+            ($signer: ident, $destinations: expr, $bps: expr) => {
+                set_revenue_destinations(
+                    &mut fee_collector,
+                    &governor,
+                    &[&$signer],
+                    $destinations,
+                    $bps,
+                )
+            };
+        }
+
+        assert_matches!(set!(invalid_authority, [a, b], 100), Err(_));
+
+        // Invalid split or destinations
+        assert_matches!(set!(authority, [a, b], MAX_REVENUE_SPLIT_BPS + 1), Err(_));
+        assert_matches!(set!(authority, [a, [0; 32]], 100), Err(_));
+        assert_matches!(set!(authority, [[0; 32], b], 0), Err(_));
+
+        assert_matches!(set!(authority, [a, b], 100), Ok(()));
+        assert_eq!(fee_collector.get_revenue_destinations(0), a);
+        assert_eq!(fee_collector.get_revenue_destinations(1), b);
+        assert_eq!(fee_collector.get_secondary_revenue_split_bps(), 100);
+    }
+
+    #[test]
+    fn test_withdraw_protocol_revenue() -> ProgramResult {
+        zero_program_account!(governor, GovernorAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_pda_account_info!(fee_collector, FeeCollectorAccount);
+        test_account_info!(destination, 0);
+        test_account_info!(secondary_destination, 0);
+        test_account_info!(any, 0);
+
+        {
+            pda_account!(mut fee_c, FeeCollectorAccount, fee_collector);
+            fee_c.add_network_fee(0, LAMPORTS_TOKEN_ID, 1_000);
+            fee_c.set_revenue_destinations(0, &destination.key.to_bytes());
+            fee_c.set_revenue_destinations(1, &secondary_destination.key.to_bytes());
+            fee_c.set_secondary_revenue_split_bps(&2_500);
+        }
+
+        macro_rules! withdraw {
+            ($signer: ident, $destination: ident, $secondary_destination: ident, $amount: expr) => {
+                withdraw_protocol_revenue(
+                    &governor,
+                    &fee_collector,
+                    &fee_collector,
+                    &$destination,
+                    &$secondary_destination,
+                    &any,
+                    &[&$signer],
+                    LAMPORTS_TOKEN_ID,
+                    $amount,
+                )
+            };
+        }
+
+        // Missing governance approval
+        assert_matches!(
+            withdraw!(any, destination, secondary_destination, 100),
+            Err(_)
+        );
+
+        // Destinations are not allowlisted
+        assert_matches!(
+            withdraw!(authority, any, secondary_destination, 100),
+            Err(_)
+        );
+        assert_matches!(withdraw!(authority, destination, any, 100), Err(_));
+
+        // Exceeds the collected network-fees
+        assert_matches!(
+            withdraw!(authority, destination, secondary_destination, 1_001),
+            Err(_)
+        );
+
+        let destination_lamports = destination.lamports();
+        let secondary_lamports = secondary_destination.lamports();
+        assert_matches!(
+            withdraw!(authority, destination, secondary_destination, 400),
+            Ok(())
+        );
+        assert_eq!(destination.lamports(), destination_lamports + 300);
+        assert_eq!(secondary_destination.lamports(), secondary_lamports + 100);
+
+        pda_account!(fee_c, FeeCollectorAccount, fee_collector);
+        assert_eq!(fee_c.get_total_withdrawn(LAMPORTS_TOKEN_ID as usize), 400);
+        assert_eq!(fee_c.withdrawable_revenue(LAMPORTS_TOKEN_ID), 600);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_network_fee_split() {
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut fee, FeeAccount);
+        zero_program_account!(mut fee_collector, FeeCollectorAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_account_info!(invalid_authority, 0);
+
+        let split = NetworkFeeSplit {
+            relayer_incentive_bps: 1_000,
+            burn_bps: 500,
+        };
+
+        macro_rules! set {
+            ($signer: ident, $fee_version: expr, $split: expr) => {
+                set_network_fee_split(
+                    &governor,
+                    &mut fee,
+                    &mut fee_collector,
+                    &[&$signer],
+                    $fee_version,
+                    $split,
+                )
+            };
+        }
+
+        assert_matches!(set!(invalid_authority, 1, split), Err(_));
+        assert_matches!(
+            set!(
+                authority,
+                1,
+                NetworkFeeSplit {
+                    relayer_incentive_bps: 10_000,
+                    burn_bps: 1,
+                }
+            ),
+            Err(_)
+        );
+
+        assert_matches!(set!(authority, 1, split), Ok(()));
+        assert_eq!(fee.get_network_fee_split(), split);
+        assert_eq!(fee_collector.fee_revenue(1).unwrap().split, split);
+
+        // Outdated fee-version (nothing is written)
+        fee_collector.add_network_fee(1 + FEE_REVENUE_VERSION_COUNT as u32, 0, 1);
+        let revenue = fee_collector.fee_revenue(1 + FEE_REVENUE_VERSION_COUNT as u32);
+        assert_matches!(
+            set!(
+                authority,
+                1,
+                NetworkFeeSplit {
+                    relayer_incentive_bps: 0,
+                    burn_bps: 0,
+                }
+            ),
+            Err(_)
+        );
+        assert_eq!(
+            fee_collector.fee_revenue(1 + FEE_REVENUE_VERSION_COUNT as u32),
+            revenue
+        );
+        assert_eq!(fee.get_network_fee_split(), split);
+    }
+
+    #[test]
+    fn test_distribute_relayer_incentives() -> ProgramResult {
+        zero_program_account!(governor, GovernorAccount);
+        zero_program_account!(mut warden_account, WardenAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_pda_account_info!(fee_collector, FeeCollectorAccount);
+        test_pda_account_info!(pool, PoolAccount);
+        test_account_info!(any, 0);
+
+        {
+            pda_account!(mut fee_c, FeeCollectorAccount, fee_collector);
+            fee_c.set_network_fee_split(
+                0,
+                NetworkFeeSplit {
+                    relayer_incentive_bps: 2_000,
+                    burn_bps: 0,
+                },
+            );
+            fee_c.add_network_fee(0, LAMPORTS_TOKEN_ID, 1_000);
+            assert_eq!(fee_c.pending_relayer_incentives(LAMPORTS_TOKEN_ID), 200);
+            assert_eq!(fee_c.withdrawable_revenue(LAMPORTS_TOKEN_ID), 800);
+        }
+
+        macro_rules! distribute {
+            ($signer: ident, $amount: expr) => {
+                distribute_relayer_incentives(
+                    &governor,
+                    &fee_collector,
+                    &fee_collector,
+                    &pool,
+                    &pool,
+                    &mut warden_account,
+                    &any,
+                    &[&$signer],
+                    Pubkey::new_unique(),
+                    LAMPORTS_TOKEN_ID,
+                    $amount,
+                )
+            };
+        }
+
+        // Missing governance approval
+        assert_matches!(distribute!(any, 100), Err(_));
+
+        // Unregistered warden
+        assert_matches!(distribute!(authority, 100), Err(_));
+        warden_account.set_stake(&MIN_WARDEN_STAKE);
+
+        // Exceeds the pending relayer incentives
+        assert_matches!(distribute!(authority, 201), Err(_));
+
+        let pool_lamports = pool.lamports();
+        assert_matches!(distribute!(authority, 150), Ok(()));
+        assert_eq!(pool.lamports(), pool_lamports + 150);
+        assert_eq!(
+            warden_account.get_pending_rewards(LAMPORTS_TOKEN_ID as usize),
+            150
+        );
+
+        pda_account!(fee_c, FeeCollectorAccount, fee_collector);
+        assert_eq!(fee_c.pending_relayer_incentives(LAMPORTS_TOKEN_ID), 50);
+        assert_eq!(fee_c.withdrawable_revenue(LAMPORTS_TOKEN_ID), 800);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_burn_collected_fees() -> ProgramResult {
+        test_pda_account_info!(fee_collector, FeeCollectorAccount);
+        account_info!(incinerator, solana_program::incinerator::ID, vec![]);
+        test_account_info!(any, 0);
+
+        // Nothing to burn
+        assert_matches!(
+            burn_collected_fees(
+                &fee_collector,
+                &fee_collector,
+                &incinerator,
+                &any,
+                LAMPORTS_TOKEN_ID
+            ),
+            Err(_)
+        );
+
+        {
+            pda_account!(mut fee_c, FeeCollectorAccount, fee_collector);
+            fee_c.set_network_fee_split(
+                0,
+                NetworkFeeSplit {
+                    relayer_incentive_bps: 0,
+                    burn_bps: 1_000,
+                },
+            );
+            fee_c.add_network_fee(0, LAMPORTS_TOKEN_ID, 1_000);
+        }
+
+        // Invalid burn account
+        assert_matches!(
+            burn_collected_fees(
+                &fee_collector,
+                &fee_collector,
+                &any,
+                &any,
+                LAMPORTS_TOKEN_ID
+            ),
+            Err(_)
+        );
+
+        let lamports = incinerator.lamports();
+        assert_matches!(
+            burn_collected_fees(
+                &fee_collector,
+                &fee_collector,
+                &incinerator,
+                &any,
+                LAMPORTS_TOKEN_ID
+            ),
+            Ok(())
+        );
+        assert_eq!(incinerator.lamports(), lamports + 100);
+
+        pda_account!(fee_c, FeeCollectorAccount, fee_collector);
+        assert_eq!(fee_c.pending_burn(LAMPORTS_TOKEN_ID), 0);
+        assert_eq!(fee_c.withdrawable_revenue(LAMPORTS_TOKEN_ID), 900);
+
+        Ok(())
+    }
+}
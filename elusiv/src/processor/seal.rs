@@ -0,0 +1,292 @@
+use crate::computation::current_slot;
+use crate::error::ElusivError;
+use crate::events::ElusivEvent;
+use crate::macros::guard;
+use crate::processor::verify_governance_authority;
+use crate::state::{
+    event::EventSequenceAccount,
+    governor::GovernorAccount,
+    nullifier::{ArchivedTreeAccount, NullifierAccount, SEAL_TIMELOCK_SLOTS},
+    storage::StorageAccount,
+};
+use crate::token::elusiv_token;
+use elusiv_types::{ElusivOption, ParentAccount};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult};
+
+/// Announces the sealing of the nullifier-set of a closed MT of the pool of `token_id`
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - the MT needs to be untouched for the governor's `seal_sunset_slots`
+/// - a previous announcement is replaced (nullifier activity in the MT after an announcement prevents the sealing, see [`NullifierAccount::is_seal_executable`])
+///
+/// # Note
+///
+/// No value is moved out of the pool: the values of the notes of an MT are private, so the unspent value of an MT cannot be bounded.
+pub fn announce_nullifier_set_seal(
+    governor: &GovernorAccount,
+    storage_account: &StorageAccount,
+    nullifier_account: &mut NullifierAccount,
+    event_sequence: &mut EventSequenceAccount,
+    signers: &[&AccountInfo],
+
+    closed_mt_index: u32,
+    token_id: u16,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+    elusiv_token(token_id)?;
+    guard!(
+        storage_account.get_trees_count() > closed_mt_index,
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        !nullifier_account.get_is_sealed(),
+        ElusivError::MerkleTreeIsSealed
+    );
+
+    let slot = current_slot()?;
+    guard!(
+        nullifier_account.is_sunset(governor.get_seal_sunset_slots(), slot),
+        ElusivError::SealIsNotAllowed
+    );
+
+    nullifier_account.set_seal_announcement_slot(&slot);
+
+    event_sequence.emit(&ElusivEvent::NullifierSetSealAnnounced {
+        token_id,
+        mt_index: closed_mt_index,
+        execution_slot: slot.saturating_add(SEAL_TIMELOCK_SLOTS),
+    })
+}
+
+/// Seals the nullifier-set of a closed MT once the timelock of its announcement has passed (permissionless)
+/// - no further nullifier-hashes can be inserted, which allows closing the child-accounts (see [`close_nullifier_child_account`])
+pub fn seal_nullifier_set(
+    nullifier_account: &mut NullifierAccount,
+    event_sequence: &mut EventSequenceAccount,
+
+    closed_mt_index: u32,
+    token_id: u16,
+) -> ProgramResult {
+    guard!(
+        nullifier_account.is_seal_executable(current_slot()?),
+        ElusivError::SealIsNotAllowed
+    );
+
+    nullifier_account.set_is_sealed(&true);
+
+    event_sequence.emit(&ElusivEvent::NullifierSetSealed {
+        token_id,
+        mt_index: closed_mt_index,
+    })
+}
+
+/// Closes the child-account with `child_index` of the sealed [`NullifierAccount`] of an archived MT and transfers its rent to the `fee_collector` (permissionless)
+/// - the reclaimed rent is partially refunded to the contributors (see [`crate::processor::claim_tree_rent_refund`])
+/// - the [`ArchivedTreeAccount`] needs to contain the final nullifier-set (no insertions after archiving)
+///
+/// # Note
+///
+/// The child-accounts of the [`StorageAccount`] are reused by the active MT of the pool and cannot be closed.
+#[allow(clippy::too_many_arguments)]
+pub fn close_nullifier_child_account<'a>(
+    fee_collector: &AccountInfo<'a>,
+    nullifier_account: &mut NullifierAccount,
+    archived_tree_account: &ArchivedTreeAccount,
+    sub_account: &AccountInfo<'a>,
+    event_sequence: &mut EventSequenceAccount,
+
+    mt_index: u32,
+    child_index: u32,
+    _token_id: u16,
+) -> ProgramResult {
+    guard!(
+        nullifier_account.get_is_sealed(),
+        ElusivError::InvalidAccountState
+    );
+    guard!(
+        archived_tree_account.get_mt_index() == mt_index
+            && archived_tree_account.get_nullifier_hash_count()
+                == nullifier_account.get_nullifier_hash_count()
+            && archived_tree_account.get_nullifier_digest()
+                == nullifier_account.get_nullifier_digest(),
+        ElusivError::MerkleTreeIsNotArchived
+    );
+
+    let index = child_index as usize;
+    guard!(
+        index < nullifier_account.child_count(),
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        nullifier_account.get_child_pubkey(index) == Some(*sub_account.key),
+        ElusivError::InvalidAccount
+    );
+
+    let amount = sub_account.lamports();
+    nullifier_account.set_child_pubkey(index, ElusivOption::None);
+    nullifier_account.set_reclaimed_rent(
+        &nullifier_account
+            .get_reclaimed_rent()
+            .saturating_add(amount),
+    );
+    elusiv_utils::close_account(fee_collector, sub_account)?;
+
+    event_sequence.emit(&ElusivEvent::RentReclaimed {
+        mt_index,
+        child_index,
+        amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{account_info, parent_account, test_account_info, zero_program_account};
+    use crate::state::nullifier::{NullifierChildAccount, MIN_SEAL_SUNSET_SLOTS};
+    use crate::state::program_account::SizedAccount;
+    use crate::token::LAMPORTS_TOKEN_ID;
+    use assert_matches::assert_matches;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_announce_nullifier_set_seal() {
+        account_info!(authority, crate::ID, vec![]);
+        account_info!(invalid_authority, Pubkey::new_unique(), vec![]);
+        zero_program_account!(mut governor, GovernorAccount);
+        parent_account!(mut storage_account, StorageAccount);
+        parent_account!(mut nullifier_account, NullifierAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+        storage_account.set_trees_count(&1);
+
+        macro_rules! announce_nullifier_set_seal {
+            ($authority: expr, $closed_mt_index: expr, $token_id: expr) => {
+                announce_nullifier_set_seal(
+                    &governor,
+                    &storage_account,
+                    &mut nullifier_account,
+                    &mut event_sequence,
+                    &[&$authority],
+                    $closed_mt_index,
+                    $token_id,
+                )
+            };
+        }
+
+        // Seals are disabled
+        assert_matches!(
+            announce_nullifier_set_seal!(authority, 0, LAMPORTS_TOKEN_ID),
+            Err(_)
+        );
+
+        // The unit-test slot is zero, so no MT can be sunset
+        governor.set_seal_sunset_slots(&MIN_SEAL_SUNSET_SLOTS);
+        assert_matches!(
+            announce_nullifier_set_seal!(authority, 0, LAMPORTS_TOKEN_ID),
+            Err(_)
+        );
+
+        // Invalid authority, active MT or token-id
+        assert_matches!(
+            announce_nullifier_set_seal!(invalid_authority, 0, LAMPORTS_TOKEN_ID),
+            Err(_)
+        );
+        assert_matches!(
+            announce_nullifier_set_seal!(authority, 1, LAMPORTS_TOKEN_ID),
+            Err(_)
+        );
+        assert_matches!(announce_nullifier_set_seal!(authority, 0, u16::MAX), Err(_));
+
+        // Already sealed
+        nullifier_account.set_is_sealed(&true);
+        assert_matches!(
+            announce_nullifier_set_seal!(authority, 0, LAMPORTS_TOKEN_ID),
+            Err(_)
+        );
+
+        assert_eq!(event_sequence.get_sequence(), 0);
+    }
+
+    #[test]
+    fn test_seal_nullifier_set_not_announced() {
+        parent_account!(mut nullifier_account, NullifierAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+
+        assert_matches!(
+            seal_nullifier_set(
+                &mut nullifier_account,
+                &mut event_sequence,
+                0,
+                LAMPORTS_TOKEN_ID
+            ),
+            Err(_)
+        );
+        assert!(!nullifier_account.get_is_sealed());
+    }
+
+    #[test]
+    fn test_close_nullifier_child_account() {
+        account_info!(fee_collector, Pubkey::new_unique(), vec![]);
+        parent_account!(mut nullifier_account, NullifierAccount);
+        zero_program_account!(mut archived_tree, ArchivedTreeAccount);
+        zero_program_account!(mut event_sequence, EventSequenceAccount);
+
+        let pk = Pubkey::new_unique();
+        account_info!(sub_account, pk, vec![0; NullifierChildAccount::SIZE]);
+        nullifier_account.set_child_pubkey(1, ElusivOption::Some(pk));
+        nullifier_account
+            .try_insert_nullifier_hash([1; 32])
+            .unwrap();
+
+        macro_rules! close_nullifier_child_account {
+            ($sub_account: expr, $mt_index: expr, $child_index: expr) => {
+                close_nullifier_child_account(
+                    &fee_collector,
+                    &mut nullifier_account,
+                    &archived_tree,
+                    &$sub_account,
+                    &mut event_sequence,
+                    $mt_index,
+                    $child_index,
+                    0,
+                )
+            };
+        }
+
+        // The nullifier-set is not sealed
+        assert_matches!(close_nullifier_child_account!(sub_account, 0, 1), Err(_));
+
+        // The MT has not been archived with the final nullifier-set
+        nullifier_account.set_is_sealed(&true);
+        assert_matches!(close_nullifier_child_account!(sub_account, 0, 1), Err(_));
+
+        archived_tree.set_nullifier_hash_count(&1);
+        archived_tree.set_nullifier_digest(&nullifier_account.get_nullifier_digest());
+
+        // Invalid MT
+        assert_matches!(close_nullifier_child_account!(sub_account, 1, 1), Err(_));
+
+        // Invalid child-account
+        test_account_info!(invalid_sub_account, NullifierChildAccount::SIZE);
+        assert_matches!(
+            close_nullifier_child_account!(invalid_sub_account, 0, 1),
+            Err(_)
+        );
+        assert_matches!(close_nullifier_child_account!(sub_account, 0, 2), Err(_));
+        assert_matches!(
+            close_nullifier_child_account!(sub_account, 0, NullifierAccount::COUNT as u32),
+            Err(_)
+        );
+
+        let amount = sub_account.lamports();
+        let fee_collector_lamports = fee_collector.lamports();
+        assert_matches!(close_nullifier_child_account!(sub_account, 0, 1), Ok(()));
+
+        assert_eq!(sub_account.lamports(), 0);
+        assert_eq!(fee_collector.lamports(), fee_collector_lamports + amount);
+        assert_eq!(nullifier_account.get_child_pubkey(1), None);
+        assert_eq!(nullifier_account.get_reclaimed_rent(), amount);
+        assert_eq!(event_sequence.get_sequence(), 1);
+
+        // Already closed
+        assert_matches!(close_nullifier_child_account!(sub_account, 0, 1), Err(_));
+    }
+}
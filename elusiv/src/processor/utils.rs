@@ -1,6 +1,7 @@
 use crate::error::ElusivError;
 use crate::macros::guard;
 use crate::state::program_account::{PDAAccount, PDAOffset};
+use crate::state::stats::current_epoch;
 use crate::token::{elusiv_token, Lamports, SPLToken, Token};
 use solana_program::instruction::Instruction;
 use solana_program::program::invoke;
@@ -8,10 +9,18 @@ use solana_program::program_pack::Pack;
 use solana_program::pubkey::Pubkey;
 use solana_program::sysvar::instructions;
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError, rent::Rent,
+    account_info::AccountInfo,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    program_error::ProgramError,
+    rent::Rent,
+    system_instruction,
     sysvar::Sysvar,
 };
-use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+};
+use spl_token_2022::extension::{transfer_fee::TransferFeeConfig, StateWithExtensions};
+use spl_token_2022::state::Mint;
 
 pub use elusiv_utils::*;
 
@@ -45,6 +54,71 @@ impl<'a, 'b> InstructionsSysvar for DefaultInstructionsSysvar<'a, 'b> {
     }
 }
 
+/// `ComputeBudget111111111111111111111111111111`
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    3, 6, 70, 111, 229, 33, 23, 50, 255, 236, 173, 186, 114, 195, 155, 231, 188, 140, 229, 187,
+    197, 247, 18, 107, 44, 67, 155, 58, 64, 0, 0, 0,
+]);
+
+/// Borsh tag of the `ComputeBudgetInstruction::SetComputeUnitPrice` variant
+const SET_COMPUTE_UNIT_PRICE_TAG: u8 = 3;
+
+/// The compute-unit price (in micro-lamports) declared by the current transaction (zero without a `SetComputeUnitPrice` instruction)
+pub fn declared_compute_unit_price(
+    instructions_sysvar: &impl InstructionsSysvar,
+) -> Result<u64, ProgramError> {
+    let mut price = 0;
+    for index in 0..instructions_sysvar.find_instruction_count()? {
+        let ix = instructions_sysvar.instruction_at_index(index)?;
+        if ix.program_id == COMPUTE_BUDGET_PROGRAM_ID
+            && ix.data.len() == 9
+            && ix.data[0] == SET_COMPUTE_UNIT_PRICE_TAG
+        {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&ix.data[1..]);
+            price = u64::from_le_bytes(bytes);
+        }
+    }
+
+    Ok(price)
+}
+
+/// The instructions of a transaction (starting with the current instruction)
+#[cfg(test)]
+pub(crate) struct TestInstructionsSysvar(pub Vec<Instruction>);
+
+#[cfg(test)]
+impl InstructionsSysvar for TestInstructionsSysvar {
+    fn current_index(&self) -> Result<u16, ProgramError> {
+        Ok(0)
+    }
+
+    fn instruction_at_index(&self, index: usize) -> Result<Instruction, ProgramError> {
+        self.0
+            .get(index)
+            .cloned()
+            .ok_or(ProgramError::InvalidArgument)
+    }
+}
+
+pub trait RentSysvar {
+    /// Minimum balance for the rent-exemption of an account with `data_len` bytes
+    fn minimum_balance(&self, data_len: usize) -> Result<u64, ProgramError>;
+}
+
+/// Uses the rent sysvar (or the deterministic default [`Rent`] with the `test-unit` feature, since there is no sysvar in unit tests)
+pub struct DefaultRentSysvar;
+
+impl RentSysvar for DefaultRentSysvar {
+    fn minimum_balance(&self, data_len: usize) -> Result<u64, ProgramError> {
+        if cfg!(feature = "test-unit") {
+            return Ok(Rent::default().minimum_balance(data_len));
+        }
+
+        Ok(Rent::get()?.minimum_balance(data_len))
+    }
+}
+
 pub fn transfer_token<'a>(
     source: &AccountInfo<'a>,
     source_token_account: &AccountInfo<'a>,
@@ -99,6 +173,65 @@ pub fn transfer_token_from_pda<'a, T: PDAAccount>(
     }
 }
 
+/// Burns `token` held by the PDA `source`
+/// - lamports are transferred to the incinerator (`burn_account`)
+/// - SPL tokens are burned from `source_token_account` (`burn_account` is the mint)
+pub fn burn_token_from_pda<'a, T: PDAAccount>(
+    source: &AccountInfo<'a>,
+    source_token_account: &AccountInfo<'a>,
+    burn_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    token: Token,
+    pda_pubkey: Option<Pubkey>,
+    pda_offset: PDAOffset,
+) -> ProgramResult {
+    guard!(*source.owner == crate::ID, ElusivError::InvalidAccount);
+
+    match token {
+        Token::Lamports(lamports) => {
+            guard!(
+                *burn_account.key == solana_program::incinerator::ID,
+                ElusivError::InvalidAccount
+            );
+            transfer_lamports_from_pda_checked(source, burn_account, lamports.0)
+        }
+        Token::SPLToken(SPLToken { id, amount }) => {
+            guard!(
+                *token_program.key == spl_token::ID,
+                ElusivError::InvalidAccount
+            );
+            guard!(
+                *burn_account.key == elusiv_token(id.get())?.mint,
+                ElusivError::InvalidAccount
+            );
+
+            let bump = T::get_bump(source);
+            let seeds = T::signers_seeds(pda_pubkey, pda_offset, bump);
+            let signers_seeds = signers_seeds!(seeds);
+
+            let instruction = spl_token::instruction::burn(
+                &spl_token::id(),
+                source_token_account.key,
+                burn_account.key,
+                source.key,
+                &[source.key],
+                amount,
+            )?;
+
+            solana_program::program::invoke_signed(
+                &instruction,
+                &[
+                    source_token_account.clone(),
+                    burn_account.clone(),
+                    source.clone(),
+                    token_program.clone(),
+                ],
+                &[&signers_seeds],
+            )
+        }
+    }
+}
+
 fn transfer_with_token_program<'a>(
     source: &AccountInfo<'a>,
     source_token_account: &AccountInfo<'a>,
@@ -154,6 +287,185 @@ fn transfer_with_token_program<'a>(
     }
 }
 
+/// Transfers `token` with `TransferChecked`, which is accepted by both SPL-token programs
+/// - required for Token-2022 mints (the `mint_account` is verified by the token program)
+pub fn transfer_token_with_mint<'a>(
+    source: &AccountInfo<'a>,
+    source_token_account: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    mint_account: &AccountInfo<'a>,
+    token: Token,
+) -> ProgramResult {
+    match token {
+        Token::Lamports(lamports) => {
+            transfer_with_system_program(source, destination, token_program, lamports.0)
+        }
+        Token::SPLToken(SPLToken { amount, .. }) => transfer_checked_with_token_program(
+            source,
+            source_token_account,
+            destination,
+            token_program,
+            mint_account,
+            amount,
+            None,
+        ),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_token_from_pda_with_mint<'a, T: PDAAccount>(
+    source: &AccountInfo<'a>,
+    source_token_account: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    mint_account: &AccountInfo<'a>,
+    token: Token,
+    pda_pubkey: Option<Pubkey>,
+    pda_offset: PDAOffset,
+) -> ProgramResult {
+    guard!(*source.owner == crate::ID, ElusivError::InvalidAccount);
+
+    match token {
+        Token::Lamports(lamports) => {
+            transfer_lamports_from_pda_checked(source, destination, lamports.0)
+        }
+        Token::SPLToken(SPLToken { amount, .. }) => {
+            let bump = T::get_bump(source);
+            let seeds = T::signers_seeds(pda_pubkey, pda_offset, bump);
+            let signers_seeds = signers_seeds!(seeds);
+
+            transfer_checked_with_token_program(
+                source,
+                source_token_account,
+                destination,
+                token_program,
+                mint_account,
+                amount,
+                Some(&[&signers_seeds]),
+            )
+        }
+    }
+}
+
+fn transfer_checked_with_token_program<'a>(
+    source: &AccountInfo<'a>,
+    source_token_account: &AccountInfo<'a>,
+    destination_token_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    mint_account: &AccountInfo<'a>,
+    amount: u64,
+    signers_seeds: Option<&[&[&[u8]]]>,
+) -> ProgramResult {
+    guard!(
+        is_token_program(token_program.key),
+        ElusivError::InvalidAccount
+    );
+
+    guard!(
+        source_token_account.owner == token_program.key,
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        destination_token_account.owner == token_program.key,
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        mint_account.owner == token_program.key,
+        ElusivError::InvalidAccount
+    );
+
+    let decimals = {
+        let data = mint_account.data.borrow();
+        StateWithExtensions::<Mint>::unpack(&data)?.base.decimals
+    };
+
+    let instruction = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        source_token_account.key,
+        mint_account.key,
+        destination_token_account.key,
+        source.key,
+        &[source.key],
+        amount,
+        decimals,
+    )?;
+
+    let accounts = [
+        source.clone(),
+        source_token_account.clone(),
+        mint_account.clone(),
+        destination_token_account.clone(),
+        token_program.clone(),
+    ];
+
+    if let Some(signers_seeds) = signers_seeds {
+        solana_program::program::invoke_signed(&instruction, &accounts, signers_seeds)
+    } else {
+        solana_program::program::invoke(&instruction, &accounts)
+    }
+}
+
+/// Both the SPL-token and the Token-2022 program
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == spl_token::ID || *program_id == spl_token_2022::ID
+}
+
+/// Verifies that `mint_account` is the mint of `token_id`, owned by `token_program`
+/// - the token program is detected from the mint owner, so Token-2022 mints are supported
+pub fn verify_token_mint(
+    mint_account: &AccountInfo,
+    token_program: &AccountInfo,
+    token_id: u16,
+) -> ProgramResult {
+    if token_id == 0 {
+        return Ok(());
+    }
+
+    guard!(
+        *mint_account.key == elusiv_token(token_id)?.mint,
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        is_token_program(mint_account.owner),
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        mint_account.owner == token_program.key,
+        ElusivError::InvalidAccount
+    );
+
+    Ok(())
+}
+
+/// The amount actually received by the recipient of a transfer of `token`
+/// - Token-2022 mints with the transfer-fee extension withhold the epoch's fee at the recipient
+pub fn received_token_amount(
+    mint_account: &AccountInfo,
+    token: Token,
+) -> Result<Token, ProgramError> {
+    match token {
+        Token::Lamports(_) => Ok(token),
+        Token::SPLToken(SPLToken { id, amount }) => {
+            if *mint_account.owner != spl_token_2022::ID {
+                return Ok(token);
+            }
+
+            let data = mint_account.data.borrow();
+            let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+            let fee = match mint.get_extension::<TransferFeeConfig>() {
+                Ok(config) => config
+                    .calculate_epoch_fee(current_epoch()?, amount)
+                    .ok_or(ElusivError::InvalidAmount)?,
+                Err(_) => 0,
+            };
+
+            let received = amount.checked_sub(fee).ok_or(ElusivError::InvalidAmount)?;
+            Ok(Token::new_checked(id.get(), received)?)
+        }
+    }
+}
+
 pub fn create_associated_token_account<'a>(
     payer: &AccountInfo<'a>,
     wallet_account: &AccountInfo<'a>,
@@ -178,6 +490,36 @@ pub fn create_associated_token_account<'a>(
     )
 }
 
+/// Creates a zeroed, program-owned `account` of `size` bytes, with the rent paid by `payer`
+/// - `account` needs to sign the transaction
+/// - accounts created via CPI are limited to [`MAX_PERMITTED_DATA_INCREASE`] bytes
+pub fn create_program_data_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    size: usize,
+) -> ProgramResult {
+    guard!(
+        size <= MAX_PERMITTED_DATA_INCREASE,
+        ElusivError::InvalidInstructionData
+    );
+
+    // The system program is not available in unit tests
+    if cfg!(test) {
+        return Ok(());
+    }
+
+    invoke(
+        &system_instruction::create_account(
+            payer.key,
+            account.key,
+            Rent::get()?.minimum_balance(size),
+            size as u64,
+            &crate::id(),
+        ),
+        &[payer.clone(), account.clone()],
+    )
+}
+
 pub fn program_token_account_address<A: PDAAccount>(
     token_id: u16,
     offset: PDAOffset,
@@ -206,6 +548,27 @@ pub fn verify_program_token_account(
     Ok(())
 }
 
+/// Verifies a program token account that is associated with the mint of the specific `token_program`
+pub fn verify_program_token_account_with_program(
+    owner_pda: &AccountInfo,
+    token_account: &AccountInfo,
+    token_program: &AccountInfo,
+    token_id: u16,
+) -> ProgramResult {
+    if token_id == 0 {
+        return verify_program_token_account(owner_pda, token_account, token_id);
+    }
+
+    let pubkey = get_associated_token_address_with_program_id(
+        owner_pda.key,
+        &elusiv_token(token_id)?.mint,
+        token_program.key,
+    );
+    guard!(pubkey == *token_account.key, ElusivError::InvalidAccount);
+
+    Ok(())
+}
+
 pub fn system_program_account_rent() -> Result<Lamports, ProgramError> {
     #[cfg(test)]
     {
@@ -227,13 +590,53 @@ pub fn spl_token_account_rent() -> Result<Lamports, ProgramError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::syscall_stubs::set_test_epoch;
     use crate::{
         macros::{account_info, test_account_info},
         state::{governor::PoolAccount, proof::VerificationAccount},
-        token::TOKENS,
+        token::{LAMPORTS_TOKEN_ID, TOKENS, USDC_TOKEN_ID},
     };
     use assert_matches::assert_matches;
     use solana_program::{pubkey::Pubkey, system_program};
+    use spl_token_2022::extension::{ExtensionType, StateWithExtensionsMut};
+
+    #[test]
+    fn test_compute_budget_program_id() {
+        assert_eq!(
+            COMPUTE_BUDGET_PROGRAM_ID.to_string(),
+            "ComputeBudget111111111111111111111111111111"
+        );
+    }
+
+    #[test]
+    fn test_declared_compute_unit_price() {
+        let ix = |program_id: Pubkey, data: Vec<u8>| Instruction {
+            program_id,
+            accounts: Vec::new(),
+            data,
+        };
+        let set_price = |price: u64| {
+            let mut data = vec![SET_COMPUTE_UNIT_PRICE_TAG];
+            data.extend(price.to_le_bytes());
+            ix(COMPUTE_BUDGET_PROGRAM_ID, data)
+        };
+
+        // Not declared
+        let sysvar = TestInstructionsSysvar(vec![ix(crate::id(), vec![3, 1, 0, 0, 0, 0, 0, 0, 0])]);
+        assert_eq!(declared_compute_unit_price(&sysvar).unwrap(), 0);
+
+        // `SetComputeUnitLimit`
+        let sysvar =
+            TestInstructionsSysvar(vec![ix(COMPUTE_BUDGET_PROGRAM_ID, vec![2, 0, 0, 1, 0])]);
+        assert_eq!(declared_compute_unit_price(&sysvar).unwrap(), 0);
+
+        let sysvar = TestInstructionsSysvar(vec![
+            ix(COMPUTE_BUDGET_PROGRAM_ID, vec![2, 0, 0, 1, 0]),
+            set_price(1_234),
+            ix(crate::id(), vec![]),
+        ]);
+        assert_eq!(declared_compute_unit_price(&sysvar).unwrap(), 1_234);
+    }
 
     #[test]
     fn test_transfer_token_from_pda() {
@@ -350,6 +753,91 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_verify_token_mint() {
+        let usdc_mint = elusiv_token(USDC_TOKEN_ID).unwrap().mint;
+        account_info!(spl, spl_token::id(), vec![]);
+        account_info!(spl_2022, spl_token_2022::id(), vec![]);
+        account_info!(mint, usdc_mint, vec![], spl_token::id(), false);
+        account_info!(mint_2022, usdc_mint, vec![], spl_token_2022::id(), false);
+        account_info!(
+            invalid_mint,
+            Pubkey::new_unique(),
+            vec![],
+            spl_token::id(),
+            false
+        );
+        account_info!(invalid_owner, usdc_mint, vec![]);
+
+        assert_matches!(verify_token_mint(&mint, &spl, USDC_TOKEN_ID), Ok(()));
+        assert_matches!(
+            verify_token_mint(&mint_2022, &spl_2022, USDC_TOKEN_ID),
+            Ok(())
+        );
+
+        // Token program does not own the mint
+        assert_matches!(verify_token_mint(&mint, &spl_2022, USDC_TOKEN_ID), Err(_));
+        assert_matches!(verify_token_mint(&mint_2022, &spl, USDC_TOKEN_ID), Err(_));
+
+        // Invalid mint
+        assert_matches!(
+            verify_token_mint(&invalid_mint, &spl, USDC_TOKEN_ID),
+            Err(_)
+        );
+        assert_matches!(
+            verify_token_mint(&invalid_owner, &invalid_owner, USDC_TOKEN_ID),
+            Err(_)
+        );
+
+        // Lamports require no mint
+        assert_matches!(
+            verify_token_mint(&invalid_mint, &spl, LAMPORTS_TOKEN_ID),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_received_token_amount() {
+        set_test_epoch(0);
+        let usdc_mint = elusiv_token(USDC_TOKEN_ID).unwrap().mint;
+        let token = Token::new(USDC_TOKEN_ID, 1_000_000);
+
+        // SPL-token mints withhold no fee
+        account_info!(mint, usdc_mint, vec![], spl_token::id(), false);
+        assert_eq!(received_token_amount(&mint, token).unwrap(), token);
+
+        // Token-2022 mint with a 1% transfer fee (capped at 5_000)
+        let len = ExtensionType::get_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]);
+        let mut data = vec![0; len];
+        {
+            let mut state =
+                StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+            let config = state.init_extension::<TransferFeeConfig>(false).unwrap();
+            config.newer_transfer_fee.transfer_fee_basis_points = 100.into();
+            config.newer_transfer_fee.maximum_fee = 5_000.into();
+            state.base.decimals = 6;
+            state.base.is_initialized = true;
+            state.pack_base();
+            state.init_account_type().unwrap();
+        }
+        account_info!(mint_2022, usdc_mint, data, spl_token_2022::id(), false);
+
+        assert_eq!(
+            received_token_amount(&mint_2022, token).unwrap(),
+            Token::new(USDC_TOKEN_ID, 995_000)
+        );
+        assert_eq!(
+            received_token_amount(&mint_2022, Token::new(USDC_TOKEN_ID, 100_000)).unwrap(),
+            Token::new(USDC_TOKEN_ID, 99_000)
+        );
+
+        // Lamports are not affected
+        assert_eq!(
+            received_token_amount(&mint_2022, Token::new(LAMPORTS_TOKEN_ID, 100)).unwrap(),
+            Token::new(LAMPORTS_TOKEN_ID, 100)
+        );
+    }
+
     #[test]
     fn test_open_pda_account_with_offset() {
         test_account_info!(payer, 0);
@@ -413,6 +901,72 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_open_pda_account_with_offsets() {
+        test_account_info!(payer, 0);
+        let (pubkey, bump) = VerificationAccount::find_with_offsets(None, &[3, 1]);
+        account_info!(pda_account, pubkey, vec![]);
+
+        // A single offset is equivalent to a `PDAOffset`
+        assert_eq!(
+            VerificationAccount::find_with_offsets(None, &[3]),
+            VerificationAccount::find(Some(3))
+        );
+        assert_ne!(
+            pubkey,
+            VerificationAccount::find_with_offsets(None, &[1, 3]).0
+        );
+
+        assert_matches!(
+            open_pda_account_with_offsets::<VerificationAccount>(
+                &crate::id(),
+                &payer,
+                &pda_account,
+                None,
+                &[3],
+                None
+            ),
+            Err(_)
+        );
+
+        assert_matches!(
+            open_pda_account_with_offsets::<VerificationAccount>(
+                &crate::id(),
+                &payer,
+                &pda_account,
+                None,
+                &[3, 1],
+                None
+            ),
+            Ok(())
+        );
+
+        // Using bump:
+        assert_matches!(
+            open_pda_account_with_offsets::<VerificationAccount>(
+                &crate::id(),
+                &payer,
+                &pda_account,
+                Some(Pubkey::new_unique()),
+                &[3, 1],
+                Some(bump)
+            ),
+            Err(_)
+        );
+
+        assert_matches!(
+            open_pda_account_with_offsets::<VerificationAccount>(
+                &crate::id(),
+                &payer,
+                &pda_account,
+                None,
+                &[3, 1],
+                Some(bump)
+            ),
+            Ok(())
+        );
+    }
+
     #[test]
     fn test_open_pda_account_without_offset() {
         test_account_info!(payer, 0);
@@ -0,0 +1,281 @@
+use super::proof::pooled_verification_account_index;
+use super::utils::{
+    transfer_lamports_from_pda, transfer_lamports_from_pda_checked, DefaultRentSysvar, RentSysvar,
+};
+use crate::bytes::BorshSerDeSized;
+use crate::error::ElusivError;
+use crate::macros::{guard, pda_account};
+use crate::state::program_account::{Lifecycle, PDAAccountData, SizedAccount};
+use crate::state::proof::{
+    VerificationAccount, VerificationAccountData, VerificationAccountPoolAccount,
+    VerificationState, VERIFICATION_ACCOUNT_POOL_SIZE,
+};
+use elusiv_utils::{transfer_with_system_program, MATH_ERR};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+};
+
+/// Deposit held by an acquired pooled [`VerificationAccount`] (in addition to its rent)
+/// - equals the rent of a [`VerificationAccount`], so pooled verifications of invalid proofs are penalized like non-pooled ones
+/// - the deposit takes the place of the rent when the verification PDAs are closed (refunded or confiscated)
+pub fn pooled_verification_account_deposit() -> Result<u64, ProgramError> {
+    DefaultRentSysvar.minimum_balance(VerificationAccount::SIZE)
+}
+
+/// Opens the [`VerificationAccountPoolAccount`] of `fee_payer`
+pub fn open_verification_account_pool<'a>(
+    fee_payer: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+) -> ProgramResult {
+    VerificationAccountPoolAccount::open(fee_payer, pool_account, Some(*fee_payer.key), None, None)
+}
+
+/// Pre-allocates the pooled [`VerificationAccount`] of the next `slot`
+/// - the rent is paid once by `fee_payer`, the account is added to the free-list
+pub fn open_pooled_verification_account<'a>(
+    fee_payer: &AccountInfo<'a>,
+    pool: &mut VerificationAccountPoolAccount,
+    verification_account: &AccountInfo<'a>,
+
+    slot: u8,
+) -> ProgramResult {
+    guard!(
+        slot == pool.get_slot_count() && (slot as usize) < VERIFICATION_ACCOUNT_POOL_SIZE,
+        ElusivError::InvalidInstructionData
+    );
+
+    VerificationAccount::open(
+        fee_payer,
+        verification_account,
+        Some(*fee_payer.key),
+        Some(pooled_verification_account_index(slot) as u32),
+        None,
+    )?;
+
+    pda_account!(
+        mut verification_account,
+        VerificationAccount,
+        verification_account
+    );
+    verification_account.set_is_pooled(&true);
+
+    pool.set_slot_count(&(slot + 1));
+    pool.release(slot);
+
+    Ok(())
+}
+
+/// Transfers the [`pooled_verification_account_deposit`] of an acquired pooled [`VerificationAccount`] to `beneficiary`
+/// - the account's data can already be borrowed (so the rent is computed from [`VerificationAccount::SIZE`])
+pub fn transfer_pooled_verification_account_deposit<'a>(
+    verification_account: &AccountInfo<'a>,
+    beneficiary: &AccountInfo<'a>,
+) -> ProgramResult {
+    let rent = DefaultRentSysvar.minimum_balance(VerificationAccount::SIZE)?;
+    let deposit = pooled_verification_account_deposit()?;
+    guard!(
+        verification_account
+            .lamports()
+            .checked_sub(deposit)
+            .ok_or(MATH_ERR)?
+            >= rent,
+        ProgramError::AccountNotRentExempt
+    );
+
+    unsafe { transfer_lamports_from_pda(verification_account, beneficiary, deposit) }
+}
+
+/// Acquires a free pooled [`VerificationAccount`] for a single verification (see [`crate::processor::init_verification`])
+/// - `fee_payer` transfers the [`pooled_verification_account_deposit`] to the account
+pub fn acquire_pooled_verification_account<'a>(
+    fee_payer: &AccountInfo<'a>,
+    pool: &mut VerificationAccountPoolAccount,
+    verification_account_info: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+
+    slot: u8,
+) -> ProgramResult {
+    {
+        pda_account!(
+            mut verification_account,
+            VerificationAccount,
+            verification_account_info
+        );
+        guard!(
+            verification_account.get_is_pooled() && !verification_account.get_is_acquired(),
+            ElusivError::InvalidAccountState
+        );
+        guard!(pool.acquire(slot), ElusivError::InvalidAccountState);
+
+        verification_account.set_is_acquired(&true);
+    }
+
+    // `fee_payer` transfers the deposit to `verification_account` (lamports)
+    transfer_with_system_program(
+        fee_payer,
+        verification_account_info,
+        system_program,
+        pooled_verification_account_deposit()?,
+    )
+}
+
+/// Zeroes an acquired pooled [`VerificationAccount`] and returns it to the free-list
+/// - the verification either needs to be closed or never have been initialized
+/// - a deposit that has not been refunded or confiscated by a verification is refunded to `fee_payer`
+pub fn release_pooled_verification_account<'a>(
+    fee_payer: &AccountInfo<'a>,
+    pool: &mut VerificationAccountPoolAccount,
+    verification_account_info: &AccountInfo<'a>,
+
+    slot: u8,
+) -> ProgramResult {
+    {
+        pda_account!(
+            verification_account,
+            VerificationAccount,
+            verification_account_info
+        );
+        guard!(
+            verification_account.get_is_pooled() && verification_account.get_is_acquired(),
+            ElusivError::InvalidAccountState
+        );
+
+        let is_uninitialized = matches!(verification_account.get_state(), VerificationState::None)
+            && verification_account.get_other_data() == VerificationAccountData::default();
+        guard!(
+            is_uninitialized
+                || matches!(verification_account.get_state(), VerificationState::Closed),
+            ElusivError::InvalidAccountState
+        );
+    }
+
+    // Zeroing pass (the `PDAAccountData` is kept)
+    verification_account_info.data.borrow_mut()[PDAAccountData::SIZE..].fill(0);
+
+    {
+        pda_account!(
+            mut verification_account,
+            VerificationAccount,
+            verification_account_info
+        );
+        verification_account.set_is_pooled(&true);
+        pool.release(slot);
+    }
+
+    // `verification_account` transfers the remaining deposit to `fee_payer` (lamports)
+    let rent = DefaultRentSysvar.minimum_balance(VerificationAccount::SIZE)?;
+    let deposit = verification_account_info.lamports().saturating_sub(rent);
+    if deposit > 0 {
+        transfer_lamports_from_pda_checked(verification_account_info, fee_payer, deposit)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{
+        account_info, test_account_info, test_pda_account_info, zero_program_account,
+    };
+    use assert_matches::assert_matches;
+    use solana_program::system_program;
+
+    #[test]
+    fn test_acquire_release_pooled_verification_account() -> ProgramResult {
+        test_account_info!(fee_payer, 0);
+        account_info!(sys, system_program::id(), vec![]);
+        zero_program_account!(mut pool, VerificationAccountPoolAccount);
+        test_pda_account_info!(
+            verification_account,
+            VerificationAccount,
+            *fee_payer.key,
+            Some(pooled_verification_account_index(0) as u32)
+        );
+        pool.set_slot_count(&1);
+        pool.release(0);
+
+        // Not pooled
+        assert_matches!(
+            acquire_pooled_verification_account(
+                &fee_payer,
+                &mut pool,
+                &verification_account,
+                &sys,
+                0
+            ),
+            Err(_)
+        );
+        {
+            pda_account!(mut v_account, VerificationAccount, verification_account);
+            v_account.set_is_pooled(&true);
+        }
+
+        // Release requires an acquired account
+        assert_matches!(
+            release_pooled_verification_account(&fee_payer, &mut pool, &verification_account, 0),
+            Err(_)
+        );
+
+        assert_matches!(
+            acquire_pooled_verification_account(
+                &fee_payer,
+                &mut pool,
+                &verification_account,
+                &sys,
+                1
+            ),
+            Err(_)
+        );
+        assert_matches!(
+            acquire_pooled_verification_account(
+                &fee_payer,
+                &mut pool,
+                &verification_account,
+                &sys,
+                0
+            ),
+            Ok(())
+        );
+        assert_eq!(pool.next_free_slot(), None);
+
+        {
+            // Verification in progress
+            pda_account!(mut v_account, VerificationAccount, verification_account);
+            v_account.set_state(&VerificationState::ProofSetup);
+            v_account.set_vkey_id(&1);
+        }
+
+        assert_matches!(
+            release_pooled_verification_account(&fee_payer, &mut pool, &verification_account, 0),
+            Err(_)
+        );
+
+        {
+            pda_account!(mut v_account, VerificationAccount, verification_account);
+            v_account.set_state(&VerificationState::Closed);
+        }
+
+        // Unused deposit is refunded to `fee_payer`
+        let rent = DefaultRentSysvar.minimum_balance(VerificationAccount::SIZE)?;
+        let deposit = pooled_verification_account_deposit()?;
+        **verification_account.lamports.borrow_mut() = rent + deposit;
+        let fee_payer_lamports = fee_payer.lamports();
+
+        assert_matches!(
+            release_pooled_verification_account(&fee_payer, &mut pool, &verification_account, 0),
+            Ok(())
+        );
+        assert_eq!(pool.next_free_slot(), Some(0));
+        assert_eq!(verification_account.lamports(), rent);
+        assert_eq!(fee_payer.lamports(), fee_payer_lamports + deposit);
+
+        pda_account!(v_account, VerificationAccount, verification_account);
+        assert!(v_account.get_is_pooled());
+        assert!(!v_account.get_is_acquired());
+        assert_eq!(v_account.get_vkey_id(), 0);
+        assert_matches!(v_account.get_state(), VerificationState::None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+use crate::computation::current_slot;
+use crate::error::ElusivError;
+use crate::macros::{guard, pda_account};
+use crate::state::{
+    program_account::Lifecycle,
+    viewing_key::{ViewingKeyRegistryAccount, ENCRYPTED_VIEWING_KEY_SIZE},
+};
+use crate::types::U256;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+/// Publishes (or replaces) the encrypted viewing key of the `depositor` in its [`ViewingKeyRegistryAccount`]
+pub fn publish_viewing_key<'a>(
+    depositor: &AccountInfo<'a>,
+    viewing_key_registry: &AccountInfo<'a>,
+
+    auditor: Pubkey,
+    viewing_key_hash: U256,
+    encrypted_viewing_key: [u8; ENCRYPTED_VIEWING_KEY_SIZE],
+) -> ProgramResult {
+    guard!(
+        viewing_key_hash != [0; 32],
+        ElusivError::InvalidInstructionData
+    );
+
+    if viewing_key_registry.data_is_empty() {
+        ViewingKeyRegistryAccount::reopen(
+            depositor,
+            viewing_key_registry,
+            Some(*depositor.key),
+            None,
+            None,
+        )?;
+    }
+
+    pda_account!(
+        mut viewing_key_registry,
+        ViewingKeyRegistryAccount,
+        viewing_key_registry
+    );
+    viewing_key_registry.set_depositor(depositor.key);
+    viewing_key_registry.set_auditor(&auditor);
+    viewing_key_registry.set_viewing_key_hash(&viewing_key_hash);
+    for (i, byte) in encrypted_viewing_key.iter().enumerate() {
+        viewing_key_registry.set_encrypted_viewing_key(i, byte);
+    }
+    viewing_key_registry.set_publication_slot(&current_slot()?);
+
+    Ok(())
+}
+
+/// Revokes the viewing key of the `depositor` by closing its [`ViewingKeyRegistryAccount`]
+pub fn revoke_viewing_key<'a>(
+    depositor: &AccountInfo<'a>,
+    viewing_key_registry_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    {
+        pda_account!(
+            viewing_key_registry,
+            ViewingKeyRegistryAccount,
+            viewing_key_registry_info
+        );
+        guard!(
+            viewing_key_registry.get_depositor() == *depositor.key,
+            ElusivError::InvalidAccount
+        );
+    }
+
+    ViewingKeyRegistryAccount::close(depositor, viewing_key_registry_info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{account_info, test_pda_account_info};
+    use crate::state::program_account::ProgramAccount;
+    use crate::syscall_stubs::set_test_slot;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_publish_viewing_key() {
+        set_test_slot(0);
+        let depositor_pk = Pubkey::new_unique();
+        account_info!(depositor, depositor_pk, vec![]);
+        test_pda_account_info!(
+            viewing_key_registry,
+            ViewingKeyRegistryAccount,
+            depositor_pk,
+            None
+        );
+        let auditor = Pubkey::new_unique();
+
+        // Zero viewing key hash
+        assert_matches!(
+            publish_viewing_key(
+                &depositor,
+                &viewing_key_registry,
+                auditor,
+                [0; 32],
+                [1; ENCRYPTED_VIEWING_KEY_SIZE]
+            ),
+            Err(_)
+        );
+
+        // An existing registration is replaced
+        publish_viewing_key(
+            &depositor,
+            &viewing_key_registry,
+            auditor,
+            [2; 32],
+            [3; ENCRYPTED_VIEWING_KEY_SIZE],
+        )
+        .unwrap();
+
+        let data = &mut viewing_key_registry.data.borrow_mut()[..];
+        let registry = ViewingKeyRegistryAccount::new(data).unwrap();
+        assert_eq!(registry.get_depositor(), depositor_pk);
+        assert_eq!(registry.get_auditor(), auditor);
+        assert_eq!(registry.get_viewing_key_hash(), [2; 32]);
+        for i in 0..ENCRYPTED_VIEWING_KEY_SIZE {
+            assert_eq!(registry.get_encrypted_viewing_key(i), 3);
+        }
+    }
+
+    #[test]
+    fn test_revoke_viewing_key() {
+        let depositor_pk = Pubkey::new_unique();
+        account_info!(depositor, depositor_pk, vec![]);
+        account_info!(other, Pubkey::new_unique(), vec![]);
+        test_pda_account_info!(
+            viewing_key_registry,
+            ViewingKeyRegistryAccount,
+            depositor_pk,
+            None
+        );
+        {
+            let data = &mut viewing_key_registry.data.borrow_mut()[..];
+            let mut registry = ViewingKeyRegistryAccount::new(data).unwrap();
+            registry.set_depositor(&depositor_pk);
+        }
+
+        // Only the depositor can revoke its viewing key
+        assert_matches!(revoke_viewing_key(&other, &viewing_key_registry), Err(_));
+
+        let lamports = viewing_key_registry.lamports();
+        revoke_viewing_key(&depositor, &viewing_key_registry).unwrap();
+        assert_eq!(depositor.lamports(), u32::MAX as u64 + lamports);
+        assert_eq!(viewing_key_registry.lamports(), 0);
+    }
+}
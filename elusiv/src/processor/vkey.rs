@@ -1,12 +1,18 @@
 use crate::{
-    error::ElusivError, processor::setup_child_account, proof::vkey::VerifyingKey,
-    state::vkey::VKeyAccount,
+    bytes::div_ceiling_usize,
+    computation::current_slot,
+    error::ElusivError,
+    processor::{setup_child_account, verify_governance_authority},
+    proof::vkey::{
+        vkey_checksum, vkey_checksum_step, vkey_public_inputs_count, VerifyingKey,
+        VKEY_CHECKSUM_CHUNK_SIZE,
+    },
+    state::{governor::GovernorAccount, program_account::Lifecycle, vkey::VKeyAccount},
+    types::U256,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_types::{BorshSerDeSized, ChildAccountConfig, ElusivOption, ParentAccount};
-use elusiv_utils::{
-    guard, open_pda_account_with_offset, pda_account, transfer_with_system_program,
-};
+use elusiv_utils::{guard, pda_account, transfer_with_system_program};
 use solana_program::{
     account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
     pubkey::Pubkey,
@@ -15,6 +21,14 @@ use solana_program::{
 pub const VKEY_ACCOUNT_DATA_PACKET_SIZE: usize = 964;
 const MAX_NUMBER_OF_VKEYS: u32 = 1;
 
+/// Number of data packets required to populate a verifying key source with `public_inputs_count` public inputs
+pub const fn vkey_data_packet_count(public_inputs_count: usize) -> usize {
+    div_ceiling_usize(
+        VerifyingKey::source_size(public_inputs_count),
+        VKEY_ACCOUNT_DATA_PACKET_SIZE,
+    )
+}
+
 /// A binary data packet containing [`VKEY_ACCOUNT_DATA_PACKET_SIZE`] bytes
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct VKeyAccountDataPacket(pub Vec<u8>);
@@ -37,7 +51,7 @@ pub fn create_vkey_account<'a>(
         ElusivError::InvalidAccountState
     );
 
-    open_pda_account_with_offset::<VKeyAccount>(&crate::id(), signer, vkey_account, vkey_id, None)?;
+    VKeyAccount::open(signer, vkey_account, None, Some(vkey_id), None)?;
 
     pda_account!(mut vkey_account, VKeyAccount, vkey_account);
     vkey_account.set_authority(&authority);
@@ -72,9 +86,14 @@ pub fn create_new_vkey_version(
         Some(binary_data_account_size),
     )?;
 
+    vkey_account.set_data_packet_count(&0);
+
     Ok(())
 }
 
+/// Writes the data packet at `data_position` into the pending verifying key source of a [`VKeyAccount`]
+/// - packets are written sequentially, `data_position` can either be the next packet or a rewrite of a previous packet
+/// - setup tooling can resume the population at `data_packet_count` (see [`VKeyAccount::data_cursor`])
 pub fn set_vkey_data(
     signer: &AccountInfo,
     vkey_account: &mut VKeyAccount,
@@ -85,6 +104,12 @@ pub fn set_vkey_data(
 ) -> ProgramResult {
     verify_vkey_modification(signer, vkey_account)?;
 
+    let data_packet_count = vkey_account.get_data_packet_count();
+    guard!(
+        data_position <= data_packet_count,
+        ElusivError::InvalidInstructionData
+    );
+
     let public_inputs_count = vkey_account.get_public_inputs_count();
     let len = VerifyingKey::source_size(public_inputs_count as usize);
     let start = data_position as usize * VKEY_ACCOUNT_DATA_PACKET_SIZE;
@@ -98,10 +123,15 @@ pub fn set_vkey_data(
             .copy_from_slice(&packet.0[..VKEY_ACCOUNT_DATA_PACKET_SIZE - cutoff])
     })?;
 
+    if data_position == data_packet_count {
+        vkey_account.set_data_packet_count(&(data_packet_count + 1));
+    }
+
     Ok(())
 }
 
 /// Updates a [`VKeyAccount`]
+/// - requires all data packets of the pending verifying key source to be written
 pub fn update_vkey_version<'a>(
     signer: &AccountInfo<'a>,
     vkey_account: &mut VKeyAccount,
@@ -116,6 +146,10 @@ pub fn update_vkey_version<'a>(
         vkey_account.get_child_pubkey(1).is_some(),
         ElusivError::InvalidAccountState
     );
+    guard!(
+        vkey_account.is_data_complete(),
+        ElusivError::InvalidAccountState
+    );
 
     // Close old vkey account
     if let Some(old_vkey_account) = vkey_account.get_child_pubkey(0) {
@@ -136,6 +170,11 @@ pub fn update_vkey_version<'a>(
     vkey_account.set_child_pubkey(0, vkey_account.get_child_pubkey(1).into());
     vkey_account.set_child_pubkey(1, None.into());
 
+    // The checksum needs to be recomputed for the new version
+    vkey_account.set_checksum(&[0; 32]);
+    vkey_account.set_checksum_position(&0);
+    vkey_account.set_data_packet_count(&0);
+
     // Inc version
     let version = vkey_account.get_version();
     vkey_account.set_version(
@@ -147,7 +186,34 @@ pub fn update_vkey_version<'a>(
     Ok(())
 }
 
+/// Hashes the next chunk of the active verifying key source into the checksum of a [`VKeyAccount`]
+/// - requires multiple calls, since a single verifying key source exceeds the compute budget
+pub fn compute_vkey_checksum(
+    signer: &AccountInfo,
+    vkey_account: &mut VKeyAccount,
+
+    _vkey_id: u32,
+) -> ProgramResult {
+    verify_vkey_modification(signer, vkey_account)?;
+
+    let len = VerifyingKey::source_size(vkey_account.get_public_inputs_count() as usize);
+    let start = vkey_account.get_checksum_position() as usize;
+    let end = std::cmp::min(start + VKEY_CHECKSUM_CHUNK_SIZE, len);
+
+    guard!(start < len, ElusivError::InvalidAccountState);
+
+    let checksum = vkey_account.get_checksum();
+    let checksum = vkey_account
+        .execute_on_child_account(0, |data| vkey_checksum_step(&checksum, &data[start..end]))?;
+
+    vkey_account.set_checksum(&checksum);
+    vkey_account.set_checksum_position(&(end as u32));
+
+    Ok(())
+}
+
 /// Freezes a [`VKeyAccount`]
+/// - requires the checksum to be computed over the entire verifying key source
 pub fn freeze_vkey(
     signer: &AccountInfo,
     vkey_account: &mut VKeyAccount,
@@ -155,9 +221,14 @@ pub fn freeze_vkey(
     _vkey_id: u32,
 ) -> ProgramResult {
     verify_vkey_modification(signer, vkey_account)?;
-    vkey_account.set_is_frozen(&true);
 
-    Ok(())
+    let len = VerifyingKey::source_size(vkey_account.get_public_inputs_count() as usize);
+    guard!(
+        vkey_account.get_checksum_position() as usize == len,
+        ElusivError::InvalidAccountState
+    );
+
+    vkey_account.seal()
 }
 
 /// Changes the modification authority of a [`VKeyAccount`]
@@ -174,11 +245,95 @@ pub fn change_vkey_authority(
     Ok(())
 }
 
-fn verify_vkey_modification(signer: &AccountInfo, vkey_account: &VKeyAccount) -> ProgramResult {
+/// Stages the upgrade of the verifying key of the circuit `vkey_id` into a fresh [`VKeyAccount`] with `staged_vkey_id`
+///
+/// # Notes
+///
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - the staged [`VKeyAccount`] is modified by the program's keypair (using the default [`VKeyAccount`] instructions) and then frozen
+/// - `checksum` is the expected checksum of the staged verifying key source (verified by [`activate_vkey_upgrade`])
+pub fn stage_vkey_upgrade<'a>(
+    payer: &AccountInfo<'a>,
+    governor: &mut GovernorAccount,
+    vkey_account: &AccountInfo<'a>,
+    signers: &[&AccountInfo],
+
+    vkey_id: u32,
+    staged_vkey_id: u32,
+    checksum: U256,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    let mut upgrade = governor.vkey_upgrade(vkey_id)?;
+    let public_inputs_count =
+        vkey_public_inputs_count(vkey_id).ok_or(ElusivError::InvalidInstructionData)?;
+
+    // Built-in and accepted verifying keys can't be replaced
     guard!(
-        !vkey_account.get_is_frozen(),
+        vkey_checksum(staged_vkey_id).is_none(),
+        ElusivError::InvalidInstructionData
+    );
+    guard!(
+        !upgrade.is_upgraded
+            || (staged_vkey_id != upgrade.active_vkey_id
+                && staged_vkey_id != upgrade.previous_vkey_id),
+        ElusivError::InvalidInstructionData
+    );
+
+    VKeyAccount::open(payer, vkey_account, None, Some(staged_vkey_id), None)?;
+
+    pda_account!(mut vkey_account, VKeyAccount, vkey_account);
+    vkey_account.set_authority(&Some(crate::ID).into());
+    vkey_account.set_public_inputs_count(&public_inputs_count);
+
+    upgrade.stage(staged_vkey_id, checksum);
+    governor.set_vkey_upgrades(vkey_id as usize, &upgrade);
+
+    Ok(())
+}
+
+/// Activates the staged verifying key of the circuit `vkey_id`, after verifying its integrity
+///
+/// # Notes
+///
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+/// - the staged [`VKeyAccount`] needs to be frozen with the expected checksum
+/// - proofs using the previously active verifying key are still accepted for `acceptance_window` slots
+pub fn activate_vkey_upgrade(
+    governor: &mut GovernorAccount,
+    vkey_account: &VKeyAccount,
+    signers: &[&AccountInfo],
+
+    vkey_id: u32,
+    staged_vkey_id: u32,
+    acceptance_window: u64,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    let mut upgrade = governor.vkey_upgrade(vkey_id)?;
+    guard!(
+        upgrade.is_staged && upgrade.staged_vkey_id == staged_vkey_id,
+        ElusivError::InvalidAccountState
+    );
+
+    // Integrity of the staged verifying key
+    guard!(
+        vkey_account.get_version() != 0 && vkey_account.is_sealed(),
         ElusivError::InvalidAccountState
     );
+    guard!(
+        vkey_account.get_checksum() == upgrade.staged_checksum,
+        ElusivError::InvalidAccount
+    );
+
+    upgrade.activate(vkey_id, current_slot()?, acceptance_window)?;
+    governor.set_vkey_upgrades(vkey_id as usize, &upgrade);
+
+    Ok(())
+}
+
+fn verify_vkey_modification(signer: &AccountInfo, vkey_account: &VKeyAccount) -> ProgramResult {
+    guard!(!vkey_account.is_sealed(), ElusivError::InvalidAccountState);
 
     if let Some(authority) = vkey_account.get_authority().option() {
         guard!(
@@ -196,11 +351,17 @@ fn verify_vkey_modification(signer: &AccountInfo, vkey_account: &VKeyAccount) ->
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::syscall_stubs::set_test_slot;
     use crate::{
-        bytes::div_ceiling_usize,
-        macros::{signing_test_account_info, test_account_info},
+        macros::{
+            account_info, signing_test_account_info, test_account_info, zero_program_account,
+        },
         processor::vkey_account,
-        proof::vkey::{TestVKey, VerifyingKeyInfo},
+        proof::vkey::{SendQuadraVKey, TestVKey, VKeySourceSection, VerifyingKeyInfo},
+        state::{
+            program_account::{PDAAccount, ProgramAccount, SizedAccount},
+            vkey::{VKeyUpgrade, UPGRADABLE_VKEY_COUNT},
+        },
     };
     use assert_matches::assert_matches;
 
@@ -285,22 +446,52 @@ mod test {
             })
             .unwrap();
 
-        let positions = div_ceiling_usize(
-            VerifyingKey::source_size(TestVKey::public_inputs_count()),
-            VKEY_ACCOUNT_DATA_PACKET_SIZE,
-        );
-        for i in 0..positions {
+        let positions = vkey_data_packet_count(TestVKey::public_inputs_count());
+        let packet = |i: usize| {
             let slice = &data[i * VKEY_ACCOUNT_DATA_PACKET_SIZE
                 ..std::cmp::min((i + 1) * VKEY_ACCOUNT_DATA_PACKET_SIZE, data.len())];
+            VKeyAccountDataPacket(slice.to_vec())
+        };
+
+        assert_eq!(vkey_account.data_cursor(), VKeySourceSection::AlphaBeta);
+
+        // Packets can't be skipped
+        assert_matches!(
+            set_vkey_data(&signer, &mut vkey_account, 0, 1, packet(1)),
+            Err(_)
+        );
+
+        for i in 0..positions {
+            set_vkey_data(&signer, &mut vkey_account, 0, i as u32, packet(i)).unwrap();
+            assert_eq!(vkey_account.get_data_packet_count() as usize, i + 1);
+
+            // Previous packets can be rewritten
+            set_vkey_data(&signer, &mut vkey_account, 0, i as u32, packet(i)).unwrap();
+            assert_eq!(vkey_account.get_data_packet_count() as usize, i + 1);
+
+            if i == positions / 2 {
+                assert_matches!(
+                    vkey_account.data_cursor(),
+                    VKeySourceSection::GammaAbc { .. }
+                );
+                assert!(!vkey_account.is_data_complete());
+            }
+        }
+
+        assert_eq!(vkey_account.data_cursor(), VKeySourceSection::Complete);
+        assert!(vkey_account.is_data_complete());
+
+        // Past the end of the source
+        assert_matches!(
             set_vkey_data(
                 &signer,
                 &mut vkey_account,
                 0,
-                i as u32,
-                VKeyAccountDataPacket(slice.to_vec()),
-            )
-            .unwrap();
-        }
+                positions as u32,
+                packet(positions - 1)
+            ),
+            Err(_)
+        );
 
         vkey_account
             .execute_on_child_account(1, |d| {
@@ -327,12 +518,26 @@ mod test {
         vkey_account.set_child_pubkey(0, None.into());
         vkey_account.set_child_pubkey(1, Some(*vkey_binary_data_account.key).into());
 
+        // Incomplete data
+        vkey_account.set_data_packet_count(
+            &(vkey_data_packet_count(TestVKey::public_inputs_count()) as u32 - 1),
+        );
+        assert_matches!(
+            update_vkey_version(&signer, &mut vkey_account, &acc, &acc, 0),
+            Err(_)
+        );
+
+        vkey_account.set_data_packet_count(
+            &(vkey_data_packet_count(TestVKey::public_inputs_count()) as u32),
+        );
         assert_matches!(
             update_vkey_version(&signer, &mut vkey_account, &acc, &acc, 0),
             Ok(())
         );
 
         assert_eq!(vkey_account.get_version(), 1);
+        assert_eq!(vkey_account.get_checksum_position(), 0);
+        assert_eq!(vkey_account.get_data_packet_count(), 0);
         assert_eq!(
             vkey_account.get_child_pubkey(0).unwrap(),
             *vkey_binary_data_account.key
@@ -352,6 +557,24 @@ mod test {
             })
             .unwrap();
 
+        // Checksum has not been computed
+        assert_matches!(freeze_vkey(&signer, &mut vkey_account, 0), Err(_));
+
+        let chunks = div_ceiling_usize(
+            VerifyingKey::source_size(TestVKey::public_inputs_count()),
+            VKEY_CHECKSUM_CHUNK_SIZE,
+        );
+        for _ in 0..chunks - 1 {
+            compute_vkey_checksum(&signer, &mut vkey_account, 0).unwrap();
+        }
+
+        // Checksum is incomplete
+        assert_matches!(freeze_vkey(&signer, &mut vkey_account, 0), Err(_));
+
+        compute_vkey_checksum(&signer, &mut vkey_account, 0).unwrap();
+        assert_matches!(compute_vkey_checksum(&signer, &mut vkey_account, 0), Err(_));
+        assert_eq!(vkey_account.get_checksum(), TestVKey::CHECKSUM);
+
         freeze_vkey(&signer, &mut vkey_account, 0).unwrap();
 
         assert!(vkey_account.get_is_frozen());
@@ -419,4 +642,203 @@ mod test {
         vkey_account.set_authority(&Some(*signer.key).into());
         assert_matches!(verify_vkey_modification(&signer, &vkey_account), Err(_));
     }
+
+    #[test]
+    fn test_stage_vkey_upgrade() {
+        zero_program_account!(mut governor, GovernorAccount);
+        test_account_info!(payer);
+        account_info!(authority, crate::ID);
+        test_account_info!(invalid_authority);
+        account_info!(
+            staged_vkey_account,
+            VKeyAccount::find(Some(3)).0,
+            vec![0; VKeyAccount::SIZE]
+        );
+
+        // Invalid authority
+        assert_matches!(
+            stage_vkey_upgrade(
+                &payer,
+                &mut governor,
+                &staged_vkey_account,
+                &[&invalid_authority],
+                SendQuadraVKey::VKEY_ID,
+                3,
+                [1; 32]
+            ),
+            Err(_)
+        );
+
+        // Circuit is not upgradable
+        assert_matches!(
+            stage_vkey_upgrade(
+                &payer,
+                &mut governor,
+                &staged_vkey_account,
+                &[&authority],
+                UPGRADABLE_VKEY_COUNT as u32,
+                3,
+                [1; 32]
+            ),
+            Err(_)
+        );
+
+        // Built-in verifying keys can't be staged
+        assert_matches!(
+            stage_vkey_upgrade(
+                &payer,
+                &mut governor,
+                &staged_vkey_account,
+                &[&authority],
+                SendQuadraVKey::VKEY_ID,
+                TestVKey::VKEY_ID,
+                [1; 32]
+            ),
+            Err(_)
+        );
+
+        assert_matches!(
+            stage_vkey_upgrade(
+                &payer,
+                &mut governor,
+                &staged_vkey_account,
+                &[&authority],
+                SendQuadraVKey::VKEY_ID,
+                3,
+                [1; 32]
+            ),
+            Ok(())
+        );
+
+        let upgrade = governor.vkey_upgrade(SendQuadraVKey::VKEY_ID).unwrap();
+        assert!(upgrade.is_staged);
+        assert_eq!(upgrade.staged_vkey_id, 3);
+        assert_eq!(upgrade.staged_checksum, [1; 32]);
+        assert!(!upgrade.is_upgraded);
+
+        let data = &mut staged_vkey_account.data.borrow_mut()[..];
+        let vkey_account = VKeyAccount::new(data).unwrap();
+        assert_eq!(vkey_account.get_authority().option(), Some(crate::ID));
+        assert_eq!(
+            vkey_account.get_public_inputs_count(),
+            SendQuadraVKey::PUBLIC_INPUTS_COUNT
+        );
+    }
+
+    #[test]
+    fn test_activate_vkey_upgrade() {
+        set_test_slot(0);
+        zero_program_account!(mut governor, GovernorAccount);
+        vkey_account!(vkey_account, TestVKey);
+        account_info!(authority, crate::ID);
+        test_account_info!(invalid_authority);
+
+        let mut upgrade = VKeyUpgrade::default();
+        upgrade.stage(3, [1; 32]);
+        governor.set_vkey_upgrades(SendQuadraVKey::VKEY_ID as usize, &upgrade);
+
+        vkey_account.set_version(&1);
+        vkey_account.set_checksum(&[2; 32]);
+
+        // Invalid authority
+        assert_matches!(
+            activate_vkey_upgrade(
+                &mut governor,
+                &vkey_account,
+                &[&invalid_authority],
+                SendQuadraVKey::VKEY_ID,
+                3,
+                100
+            ),
+            Err(_)
+        );
+
+        // Invalid staged vkey
+        assert_matches!(
+            activate_vkey_upgrade(
+                &mut governor,
+                &vkey_account,
+                &[&authority],
+                SendQuadraVKey::VKEY_ID,
+                4,
+                100
+            ),
+            Err(_)
+        );
+
+        // Not frozen
+        assert_matches!(
+            activate_vkey_upgrade(
+                &mut governor,
+                &vkey_account,
+                &[&authority],
+                SendQuadraVKey::VKEY_ID,
+                3,
+                100
+            ),
+            Err(_)
+        );
+
+        // Invalid checksum
+        vkey_account.seal().unwrap();
+        assert_matches!(
+            activate_vkey_upgrade(
+                &mut governor,
+                &vkey_account,
+                &[&authority],
+                SendQuadraVKey::VKEY_ID,
+                3,
+                100
+            ),
+            Err(_)
+        );
+
+        vkey_account.set_checksum(&[1; 32]);
+        assert_matches!(
+            activate_vkey_upgrade(
+                &mut governor,
+                &vkey_account,
+                &[&authority],
+                SendQuadraVKey::VKEY_ID,
+                3,
+                100
+            ),
+            Ok(())
+        );
+
+        let circuit = SendQuadraVKey::VKEY_ID;
+        let upgrade = governor.vkey_upgrade(circuit).unwrap();
+        assert!(!upgrade.is_staged);
+        assert_eq!(upgrade.active(circuit), Some((3, [1; 32])));
+
+        // The built-in verifying key is accepted during the acceptance window
+        assert!(upgrade.accepts(circuit, 3, &[1; 32], 1_000));
+        assert!(upgrade.accepts(circuit, circuit, &SendQuadraVKey::CHECKSUM, 100));
+        assert!(!upgrade.accepts(circuit, circuit, &SendQuadraVKey::CHECKSUM, 101));
+        assert!(!upgrade.accepts(circuit, 3, &[2; 32], 0));
+
+        // Nothing staged
+        assert_matches!(
+            activate_vkey_upgrade(
+                &mut governor,
+                &vkey_account,
+                &[&authority],
+                SendQuadraVKey::VKEY_ID,
+                3,
+                100
+            ),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_vkey_upgrade_accepts() {
+        let upgrade = VKeyUpgrade::default();
+        let circuit = SendQuadraVKey::VKEY_ID;
+
+        assert!(upgrade.accepts(circuit, circuit, &SendQuadraVKey::CHECKSUM, 0));
+        assert!(!upgrade.accepts(circuit, circuit, &[0; 32], 0));
+        assert!(!upgrade.accepts(circuit, TestVKey::VKEY_ID, &TestVKey::CHECKSUM, 0));
+        assert!(!upgrade.accepts(3, 3, &[0; 32], 0));
+    }
 }
@@ -0,0 +1,525 @@
+use super::accounts::verify_governance_authority;
+use crate::computation::current_slot;
+use crate::error::ElusivError;
+use crate::macros::{guard, pda_account};
+use crate::processor::utils::{
+    transfer_lamports_from_pda_checked, transfer_token_from_pda, verify_program_token_account,
+};
+use crate::state::{
+    governor::{GovernorAccount, PoolAccount},
+    program_account::{Lifecycle, ProgramAccount},
+    warden::{WardenAccount, MIN_WARDEN_STAKE, WARDEN_UNBONDING_SLOTS},
+};
+use crate::token::Token;
+use elusiv_types::{PDAAccount, UnverifiedAccountInfo};
+use elusiv_utils::transfer_with_system_program;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+};
+
+/// Opens the [`WardenAccount`] of `warden` and transfers the `stake` to it
+pub fn register_warden<'a>(
+    warden: &AccountInfo<'a>,
+    warden_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+
+    stake: u64,
+) -> ProgramResult {
+    guard!(stake >= MIN_WARDEN_STAKE, ElusivError::InvalidAmount);
+
+    WardenAccount::reopen(warden, warden_account, Some(*warden.key), None, None)?;
+
+    {
+        pda_account!(mut warden_account, WardenAccount, warden_account);
+        warden_account.set_warden(warden.key);
+        warden_account.set_stake(&stake);
+        warden_account.set_registration_slot(&current_slot()?);
+    }
+
+    // `warden` transfers `stake` to `warden_account` (lamports)
+    transfer_with_system_program(warden, warden_account, system_program, stake)
+}
+
+/// Deregisters `warden`, starting the unbonding period of its stake
+pub fn deregister_warden(
+    warden: &AccountInfo,
+    warden_account: &mut WardenAccount,
+) -> ProgramResult {
+    guard!(
+        warden_account.get_warden() == *warden.key,
+        ElusivError::InvalidAccount
+    );
+    guard!(
+        warden_account.get_unbonding_slot() == 0,
+        ElusivError::InvalidAccountState
+    );
+
+    warden_account.set_unbonding_slot(&current_slot()?.saturating_add(WARDEN_UNBONDING_SLOTS));
+
+    Ok(())
+}
+
+/// Closes the [`WardenAccount`] of a deregistered `warden` after the unbonding period, `warden` receives the stake and the rent
+pub fn withdraw_warden_stake<'a>(
+    warden: &AccountInfo<'a>,
+    warden_account_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    {
+        pda_account!(warden_account, WardenAccount, warden_account_info);
+        guard!(
+            warden_account.get_warden() == *warden.key,
+            ElusivError::InvalidAccount
+        );
+        guard!(
+            warden_account.is_unbonded(current_slot()?),
+            ElusivError::InvalidAccountState
+        );
+
+        // Escrowed rewards need to be claimed before the account is closed
+        guard!(
+            !warden_account.has_pending_rewards(),
+            ElusivError::InvalidAccountState
+        );
+    }
+
+    WardenAccount::close(warden, warden_account_info)
+}
+
+/// Transfers the escrowed rewards of `token_id` from the `pool` to `recipient`
+/// - `recipient` is a token account of `token_id` for SPL-Tokens
+#[allow(clippy::too_many_arguments)]
+pub fn claim_rewards<'a>(
+    warden: &AccountInfo<'a>,
+    warden_account: &mut WardenAccount,
+    pool: &AccountInfo<'a>,
+    pool_account: &AccountInfo<'a>,
+    recipient: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+
+    token_id: u16,
+) -> ProgramResult {
+    guard!(
+        warden_account.get_warden() == *warden.key,
+        ElusivError::InvalidAccount
+    );
+    verify_program_token_account(pool, pool_account, token_id)?;
+
+    let amount = warden_account.take_pending_reward(token_id)?;
+    guard!(amount > 0, ElusivError::InvalidAmount);
+
+    // `pool` transfers `amount` to `recipient` (token)
+    transfer_token_from_pda::<PoolAccount>(
+        pool,
+        pool_account,
+        recipient,
+        token_program,
+        Token::new(token_id, amount),
+        None,
+        None,
+    )
+}
+
+/// Escrows the `reward` of a finalized proof verification in the [`WardenAccount`] of `fee_payer`
+/// - the `reward` remains in the pool until it is claimed (see [`claim_rewards`])
+/// - returns `false` if `fee_payer` has no [`WardenAccount`], in which case the `reward` needs to be paid directly
+pub fn escrow_warden_reward(
+    fee_payer: &AccountInfo,
+    warden_account: UnverifiedAccountInfo,
+    token_id: u16,
+    reward: u64,
+) -> Result<bool, ProgramError> {
+    let warden_account = match warden_account_of(fee_payer, warden_account) {
+        Some(warden_account) => warden_account,
+        None => return Ok(false),
+    };
+
+    pda_account!(mut warden_account, WardenAccount, warden_account);
+    warden_account.accrue_reward(token_id, reward)?;
+
+    Ok(true)
+}
+
+/// Slashes [`crate::state::warden::WARDEN_TIMEOUT_SLASHING_BPS`] of the stake of `fee_payer` to `recipient`
+/// - nothing is slashed, if `fee_payer` has no [`WardenAccount`]
+pub fn slash_warden_stake<'a>(
+    fee_payer: &AccountInfo,
+    warden_account: UnverifiedAccountInfo<'_, 'a>,
+    recipient: &AccountInfo<'a>,
+) -> ProgramResult {
+    let warden_account_info = match warden_account_of(fee_payer, warden_account) {
+        Some(warden_account) => warden_account,
+        None => return Ok(()),
+    };
+
+    let slashed = {
+        pda_account!(mut warden_account, WardenAccount, warden_account_info);
+        warden_account.slash_stake()
+    };
+
+    // `warden_account` transfers `slashed` to `recipient` (lamports)
+    transfer_lamports_from_pda_checked(warden_account_info, recipient, slashed)
+}
+
+/// Returns the `warden_account`, if it is the [`WardenAccount`] of `fee_payer`
+fn warden_account_of<'a, 'b>(
+    fee_payer: &AccountInfo,
+    warden_account: UnverifiedAccountInfo<'a, 'b>,
+) -> Option<&'a AccountInfo<'b>> {
+    let warden_account = warden_account.get_unsafe();
+    if *warden_account.owner != crate::ID
+        || warden_account.data_is_empty()
+        || WardenAccount::verify_account_with_pubkey(warden_account, *fee_payer.key, None).is_err()
+    {
+        return None;
+    }
+
+    let data = &mut warden_account.data.borrow_mut()[..];
+    let account = WardenAccount::new(data).ok()?;
+    if account.get_warden() != *fee_payer.key {
+        return None;
+    }
+
+    Some(warden_account)
+}
+
+/// Requires (or stops requiring) the fee-payers of partial computations to be registered wardens
+/// - `signers` need to satisfy the governance authority (see [`verify_governance_authority`])
+pub fn set_warden_registration_required(
+    governor: &mut GovernorAccount,
+    signers: &[&AccountInfo],
+
+    is_required: bool,
+) -> ProgramResult {
+    verify_governance_authority(governor, signers)?;
+
+    governor.set_is_warden_registration_required(&is_required);
+
+    Ok(())
+}
+
+/// Verifies that `fee_payer` is a registered warden, if the registration is required by the `governor`
+/// - `warden_account` is only verified (as the [`WardenAccount`] of `fee_payer`) if the registration is required
+pub fn verify_registered_warden(
+    governor: &GovernorAccount,
+    fee_payer: &AccountInfo,
+    warden_account: UnverifiedAccountInfo,
+) -> ProgramResult {
+    if !governor.get_is_warden_registration_required() {
+        return Ok(());
+    }
+
+    let warden_account = warden_account.get_unsafe();
+    guard!(
+        *warden_account.owner == crate::ID && !warden_account.data_is_empty(),
+        ElusivError::WardenIsNotRegistered
+    );
+    WardenAccount::verify_account_with_pubkey(warden_account, *fee_payer.key, None)?;
+
+    pda_account!(warden_account, WardenAccount, warden_account);
+    guard!(
+        warden_account.get_warden() == *fee_payer.key && warden_account.is_registered(),
+        ElusivError::WardenIsNotRegistered
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{account_info, test_account_info, zero_program_account};
+    use crate::state::program_account::SizedAccount;
+    use crate::syscall_stubs::set_test_slot;
+    use assert_matches::assert_matches;
+    use solana_program::{pubkey::Pubkey, system_program};
+
+    #[test]
+    fn test_register_warden() {
+        set_test_slot(0);
+        test_account_info!(warden, 0);
+        account_info!(
+            warden_account,
+            WardenAccount::find_with_pubkey(*warden.key, None).0,
+            vec![0; WardenAccount::SIZE]
+        );
+        account_info!(sys, system_program::id(), vec![]);
+
+        assert_matches!(
+            register_warden(&warden, &warden_account, &sys, MIN_WARDEN_STAKE - 1),
+            Err(_)
+        );
+
+        register_warden(&warden, &warden_account, &sys, MIN_WARDEN_STAKE).unwrap();
+
+        let data = &mut warden_account.data.borrow_mut()[..];
+        let warden_account = WardenAccount::new(data).unwrap();
+        assert_eq!(warden_account.get_warden(), *warden.key);
+        assert_eq!(warden_account.get_stake(), MIN_WARDEN_STAKE);
+        assert!(warden_account.is_registered());
+    }
+
+    #[test]
+    fn test_deregister_warden() {
+        set_test_slot(0);
+        zero_program_account!(mut warden_account, WardenAccount);
+        test_account_info!(warden, 0);
+        test_account_info!(invalid_warden, 0);
+        warden_account.set_warden(warden.key);
+        warden_account.set_stake(&MIN_WARDEN_STAKE);
+
+        assert_matches!(
+            deregister_warden(&invalid_warden, &mut warden_account),
+            Err(_)
+        );
+
+        deregister_warden(&warden, &mut warden_account).unwrap();
+        assert!(!warden_account.is_registered());
+        assert_eq!(warden_account.get_unbonding_slot(), WARDEN_UNBONDING_SLOTS);
+
+        // Already deregistered
+        assert_matches!(deregister_warden(&warden, &mut warden_account), Err(_));
+    }
+
+    #[test]
+    fn test_withdraw_warden_stake() {
+        test_account_info!(warden, 0);
+        test_account_info!(invalid_warden, 0);
+        test_account_info!(warden_account, WardenAccount::SIZE);
+
+        {
+            let data = &mut warden_account.data.borrow_mut()[..];
+            let mut account = WardenAccount::new(data).unwrap();
+            account.set_warden(warden.key);
+            account.set_stake(&MIN_WARDEN_STAKE);
+        }
+
+        // Registered
+        assert_matches!(withdraw_warden_stake(&warden, &warden_account), Err(_));
+
+        // Unbonding period has not elapsed (`current_slot` is zero in tests)
+        {
+            let data = &mut warden_account.data.borrow_mut()[..];
+            let mut account = WardenAccount::new(data).unwrap();
+            account.set_unbonding_slot(&1);
+        }
+        assert_matches!(withdraw_warden_stake(&warden, &warden_account), Err(_));
+        assert_matches!(
+            withdraw_warden_stake(&invalid_warden, &warden_account),
+            Err(_)
+        );
+
+        // Unclaimed rewards
+        {
+            let data = &mut warden_account.data.borrow_mut()[..];
+            let mut account = WardenAccount::new(data).unwrap();
+            account.set_unbonding_slot(&0);
+            account.accrue_reward(0, 1).unwrap();
+        }
+        assert_matches!(withdraw_warden_stake(&warden, &warden_account), Err(_));
+    }
+
+    #[test]
+    fn test_claim_rewards() {
+        zero_program_account!(mut warden_account, WardenAccount);
+        test_account_info!(warden, 0);
+        test_account_info!(invalid_warden, 0);
+        test_account_info!(pool, 0);
+        test_account_info!(recipient, 0);
+        test_account_info!(any, 0);
+        warden_account.set_warden(warden.key);
+
+        // No pending rewards
+        assert_matches!(
+            claim_rewards(
+                &warden,
+                &mut warden_account,
+                &pool,
+                &pool,
+                &recipient,
+                &any,
+                0
+            ),
+            Err(_)
+        );
+
+        warden_account.accrue_reward(0, 100).unwrap();
+
+        // Invalid warden
+        assert_matches!(
+            claim_rewards(
+                &invalid_warden,
+                &mut warden_account,
+                &pool,
+                &pool,
+                &recipient,
+                &any,
+                0
+            ),
+            Err(_)
+        );
+
+        // Invalid pool_account
+        assert_matches!(
+            claim_rewards(
+                &warden,
+                &mut warden_account,
+                &pool,
+                &any,
+                &recipient,
+                &any,
+                0
+            ),
+            Err(_)
+        );
+
+        claim_rewards(
+            &warden,
+            &mut warden_account,
+            &pool,
+            &pool,
+            &recipient,
+            &any,
+            0,
+        )
+        .unwrap();
+        assert_eq!(warden_account.get_pending_rewards(0), 0);
+    }
+
+    #[test]
+    fn test_escrow_warden_reward() {
+        test_account_info!(warden, 0);
+        test_account_info!(invalid_warden, 0);
+        let (pda, bump) = WardenAccount::find_with_pubkey(*warden.key, None);
+        account_info!(warden_account, pda, vec![0; WardenAccount::SIZE]);
+        test_account_info!(foreign_account, WardenAccount::SIZE, Pubkey::new_unique());
+
+        {
+            let data = &mut warden_account.data.borrow_mut()[..];
+            data[0] = bump;
+            let mut account = WardenAccount::new(data).unwrap();
+            account.set_warden(warden.key);
+        }
+
+        macro_rules! escrow {
+            ($fee_payer: ident, $warden_account: ident, $reward: expr) => {
+                escrow_warden_reward(
+                    &$fee_payer,
+                    UnverifiedAccountInfo::new(&$warden_account),
+                    0,
+                    $reward,
+                )
+            };
+        }
+
+        // No `WardenAccount` of the fee-payer
+        assert!(!escrow!(warden, foreign_account, 100).unwrap());
+        assert!(!escrow!(invalid_warden, warden_account, 100).unwrap());
+
+        assert!(escrow!(warden, warden_account, 100).unwrap());
+        assert!(escrow!(warden, warden_account, 50).unwrap());
+
+        let data = &mut warden_account.data.borrow_mut()[..];
+        let account = WardenAccount::new(data).unwrap();
+        assert_eq!(account.get_pending_rewards(0), 150);
+    }
+
+    #[test]
+    fn test_slash_warden_stake() {
+        test_account_info!(warden, 0);
+        test_account_info!(recipient, 0);
+        let (pda, bump) = WardenAccount::find_with_pubkey(*warden.key, None);
+        account_info!(warden_account, pda, vec![0; WardenAccount::SIZE]);
+        test_account_info!(foreign_account, WardenAccount::SIZE, Pubkey::new_unique());
+
+        {
+            let data = &mut warden_account.data.borrow_mut()[..];
+            data[0] = bump;
+            let mut account = WardenAccount::new(data).unwrap();
+            account.set_warden(warden.key);
+            account.set_stake(&MIN_WARDEN_STAKE);
+        }
+
+        // No `WardenAccount` of the fee-payer
+        slash_warden_stake(
+            &warden,
+            UnverifiedAccountInfo::new(&foreign_account),
+            &recipient,
+        )
+        .unwrap();
+
+        let lamports = warden_account.lamports();
+        slash_warden_stake(
+            &warden,
+            UnverifiedAccountInfo::new(&warden_account),
+            &recipient,
+        )
+        .unwrap();
+        assert_eq!(warden_account.lamports(), lamports - MIN_WARDEN_STAKE / 100);
+
+        let data = &mut warden_account.data.borrow_mut()[..];
+        let account = WardenAccount::new(data).unwrap();
+        assert_eq!(
+            account.get_stake(),
+            MIN_WARDEN_STAKE - MIN_WARDEN_STAKE / 100
+        );
+    }
+
+    #[test]
+    fn test_verify_registered_warden() {
+        zero_program_account!(mut governor, GovernorAccount);
+        test_account_info!(warden, 0);
+        test_account_info!(invalid_warden, 0);
+        let (pda, bump) = WardenAccount::find_with_pubkey(*warden.key, None);
+        account_info!(warden_account, pda, vec![0; WardenAccount::SIZE]);
+        test_account_info!(foreign_account, WardenAccount::SIZE, Pubkey::new_unique());
+
+        {
+            let data = &mut warden_account.data.borrow_mut()[..];
+            data[0] = bump;
+            let mut account = WardenAccount::new(data).unwrap();
+            account.set_warden(warden.key);
+            account.set_stake(&MIN_WARDEN_STAKE);
+        }
+
+        macro_rules! verify {
+            ($fee_payer: ident, $warden_account: ident) => {
+                verify_registered_warden(
+                    &governor,
+                    &$fee_payer,
+                    UnverifiedAccountInfo::new(&$warden_account),
+                )
+            };
+        }
+
+        // Registration is not required
+        verify!(invalid_warden, foreign_account).unwrap();
+
+        governor.set_is_warden_registration_required(&true);
+        verify!(warden, warden_account).unwrap();
+        assert_matches!(verify!(invalid_warden, warden_account), Err(_));
+        assert_matches!(verify!(warden, foreign_account), Err(_));
+
+        // Deregistered
+        {
+            let data = &mut warden_account.data.borrow_mut()[..];
+            let mut account = WardenAccount::new(data).unwrap();
+            account.set_unbonding_slot(&1);
+        }
+        assert_matches!(verify!(warden, warden_account), Err(_));
+    }
+
+    #[test]
+    fn test_set_warden_registration_required() {
+        zero_program_account!(mut governor, GovernorAccount);
+        account_info!(authority, crate::ID, vec![]);
+        test_account_info!(invalid_authority, 0);
+
+        assert_matches!(
+            set_warden_registration_required(&mut governor, &[&invalid_authority], true),
+            Err(_)
+        );
+
+        set_warden_registration_required(&mut governor, &[&authority], true).unwrap();
+        assert!(governor.get_is_warden_registration_required());
+    }
+}
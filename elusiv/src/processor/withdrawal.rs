@@ -0,0 +1,309 @@
+use crate::computation::current_slot;
+use crate::error::ElusivError;
+use crate::macros::{guard, pda_account};
+use crate::processor::utils::transfer_lamports_from_pda_checked;
+use crate::state::{
+    governor::GovernorAccount,
+    program_account::{Lifecycle, PDAAccount, ProgramAccount, SizedAccount},
+    withdrawal::{PendingWithdrawalAccount, PENDING_WITHDRAWAL_LOCKS_COUNT},
+};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+
+/// Opens a [`PendingWithdrawalAccount`]
+/// - the owner afterwards performs a regular send with the account as recipient
+/// - the governor's required delay applies on top of `delay_slots` for large withdrawals (see [`GovernorAccount::withdrawal_delay_slots`])
+pub fn create_pending_withdrawal<'a>(
+    owner: &AccountInfo<'a>,
+    pending_withdrawal_account: &AccountInfo<'a>,
+
+    pending_withdrawal_index: u32,
+    recipient: Pubkey,
+    delay_slots: u64,
+) -> ProgramResult {
+    PendingWithdrawalAccount::reopen(
+        owner,
+        pending_withdrawal_account,
+        Some(*owner.key),
+        Some(pending_withdrawal_index),
+        None,
+    )?;
+
+    pda_account!(
+        mut pending_withdrawal_account,
+        PendingWithdrawalAccount,
+        pending_withdrawal_account
+    );
+    pending_withdrawal_account.set_owner(owner.key);
+    pending_withdrawal_account.set_pending_withdrawal_index(&pending_withdrawal_index);
+    pending_withdrawal_account.set_recipient(&recipient);
+    pending_withdrawal_account.set_delay_slots(&delay_slots);
+    for i in 0..PENDING_WITHDRAWAL_LOCKS_COUNT {
+        pending_withdrawal_account.set_amounts(i, &0);
+        pending_withdrawal_account.set_unlock_slots(i, &0);
+    }
+
+    Ok(())
+}
+
+/// Releases the unlocked withdrawals to their recipient (permissionless)
+/// - the account stays open (sends to it might already be in flight), see [`close_pending_withdrawal`]
+pub fn release_pending_withdrawal<'a>(
+    owner: &AccountInfo<'a>,
+    recipient: &AccountInfo<'a>,
+    pending_withdrawal_account_info: &AccountInfo<'a>,
+
+    _pending_withdrawal_index: u32,
+) -> ProgramResult {
+    let amount = {
+        pda_account!(
+            mut pending_withdrawal_account,
+            PendingWithdrawalAccount,
+            pending_withdrawal_account_info
+        );
+        let slot = current_slot()?;
+        pending_withdrawal_account.verify_release(slot)?;
+        guard!(
+            pending_withdrawal_account.get_recipient() == *recipient.key,
+            ElusivError::InvalidRecipient
+        );
+        guard!(
+            pending_withdrawal_account.get_owner() == *owner.key,
+            ElusivError::InvalidAccount
+        );
+
+        pending_withdrawal_account.release(slot)
+    };
+
+    // `pending_withdrawal_account` transfers `amount` to `recipient` (lamports)
+    transfer_lamports_from_pda_checked(pending_withdrawal_account_info, recipient, amount)
+}
+
+/// Closes an empty [`PendingWithdrawalAccount`], the `owner` receives the rent
+/// - the owner needs to ensure that no sends to the account are in flight (their finalization fails afterwards)
+pub fn close_pending_withdrawal<'a>(
+    owner: &AccountInfo<'a>,
+    pending_withdrawal_account_info: &AccountInfo<'a>,
+
+    _pending_withdrawal_index: u32,
+) -> ProgramResult {
+    {
+        pda_account!(
+            pending_withdrawal_account,
+            PendingWithdrawalAccount,
+            pending_withdrawal_account_info
+        );
+        guard!(
+            pending_withdrawal_account.get_owner() == *owner.key,
+            ElusivError::InvalidAccount
+        );
+        guard!(
+            pending_withdrawal_account.locked_amount() == 0,
+            ElusivError::InvalidAccountState
+        );
+    }
+
+    PendingWithdrawalAccount::close(owner, pending_withdrawal_account_info)
+}
+
+/// Returns `true` if `account` is a [`PendingWithdrawalAccount`]
+pub(crate) fn is_pending_withdrawal_account(account: &AccountInfo) -> bool {
+    if *account.owner != crate::ID || account.data_len() != PendingWithdrawalAccount::SIZE {
+        return false;
+    }
+
+    let (owner, pending_withdrawal_index) = {
+        let data = &mut account.data.borrow_mut()[..];
+        match PendingWithdrawalAccount::new(data) {
+            Ok(account) => (account.get_owner(), account.get_pending_withdrawal_index()),
+            Err(_) => return false,
+        }
+    };
+
+    PendingWithdrawalAccount::verify_account_with_pubkey(
+        account,
+        owner,
+        Some(pending_withdrawal_index),
+    )
+    .is_ok()
+}
+
+/// Locks `amount` lamports withdrawn to `recipient`, if the `recipient` has been a [`PendingWithdrawalAccount`] when the send was finalized
+/// - fails if the account has been closed in the meantime (the lamports would be lost otherwise)
+pub(crate) fn lock_pending_withdrawal(
+    recipient: &AccountInfo,
+    governor: &GovernorAccount,
+    amount: u64,
+    slot: u64,
+    recipient_is_pending_withdrawal: bool,
+) -> ProgramResult {
+    if !recipient_is_pending_withdrawal {
+        return Ok(());
+    }
+    guard!(
+        is_pending_withdrawal_account(recipient),
+        ElusivError::InvalidRecipient
+    );
+
+    pda_account!(
+        mut pending_withdrawal_account,
+        PendingWithdrawalAccount,
+        recipient
+    );
+    let delay_slots =
+        governor.withdrawal_delay_slots(amount, pending_withdrawal_account.get_delay_slots());
+    pending_withdrawal_account.lock(amount, delay_slots, slot);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{account_info, test_pda_account_info, zero_program_account};
+    use crate::syscall_stubs::set_test_slot;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_is_pending_withdrawal_account() {
+        let owner_pk = Pubkey::new_unique();
+        test_pda_account_info!(
+            pending_withdrawal_account,
+            PendingWithdrawalAccount,
+            owner_pk,
+            Some(1)
+        );
+
+        // Owner and index do not match the PDA
+        assert!(!is_pending_withdrawal_account(&pending_withdrawal_account));
+
+        {
+            let data = &mut pending_withdrawal_account.data.borrow_mut()[..];
+            let mut pending_withdrawal = PendingWithdrawalAccount::new(data).unwrap();
+            pending_withdrawal.set_owner(&owner_pk);
+            pending_withdrawal.set_pending_withdrawal_index(&1);
+        }
+        assert!(is_pending_withdrawal_account(&pending_withdrawal_account));
+
+        // Other accounts
+        account_info!(wallet, owner_pk, vec![]);
+        assert!(!is_pending_withdrawal_account(&wallet));
+    }
+
+    #[test]
+    fn test_lock_and_release_pending_withdrawal() {
+        set_test_slot(0);
+        let owner_pk = Pubkey::new_unique();
+        let recipient_pk = Pubkey::new_unique();
+        account_info!(owner, owner_pk, vec![]);
+        account_info!(recipient, recipient_pk, vec![]);
+        test_pda_account_info!(
+            pending_withdrawal_account,
+            PendingWithdrawalAccount,
+            owner_pk,
+            Some(0)
+        );
+        zero_program_account!(mut governor, GovernorAccount);
+
+        {
+            let data = &mut pending_withdrawal_account.data.borrow_mut()[..];
+            let mut pending_withdrawal = PendingWithdrawalAccount::new(data).unwrap();
+            pending_withdrawal.set_owner(&owner_pk);
+            pending_withdrawal.set_recipient(&recipient_pk);
+            pending_withdrawal.set_delay_slots(&10);
+        }
+
+        // Withdrawals to other recipients are not locked
+        lock_pending_withdrawal(&recipient, &governor, 100, 0, false).unwrap();
+
+        // The recipient is no longer a pending withdrawal account
+        assert_matches!(
+            lock_pending_withdrawal(&recipient, &governor, 100, 0, true),
+            Err(_)
+        );
+
+        // The governor's required delay applies to large withdrawals
+        governor.set_required_withdrawal_delay_slots(&20);
+        governor.set_withdrawal_delay_threshold(&100);
+        lock_pending_withdrawal(&pending_withdrawal_account, &governor, 100, 0, true).unwrap();
+        {
+            let data = &mut pending_withdrawal_account.data.borrow_mut()[..];
+            let pending_withdrawal = PendingWithdrawalAccount::new(data).unwrap();
+            assert_eq!(pending_withdrawal.get_amounts(0), 100);
+            assert_eq!(pending_withdrawal.get_unlock_slots(0), 20);
+        }
+
+        // A dust withdrawal is locked separately
+        lock_pending_withdrawal(&pending_withdrawal_account, &governor, 1, 0, true).unwrap();
+        {
+            let data = &mut pending_withdrawal_account.data.borrow_mut()[..];
+            let pending_withdrawal = PendingWithdrawalAccount::new(data).unwrap();
+            assert_eq!(pending_withdrawal.get_unlock_slots(0), 20);
+            assert_eq!(pending_withdrawal.get_amounts(1), 1);
+            assert_eq!(pending_withdrawal.get_unlock_slots(1), 10);
+        }
+
+        // Not yet unlocked (the current slot is zero in tests)
+        assert_matches!(
+            release_pending_withdrawal(&owner, &recipient, &pending_withdrawal_account, 0),
+            Err(_)
+        );
+
+        {
+            let data = &mut pending_withdrawal_account.data.borrow_mut()[..];
+            let mut pending_withdrawal = PendingWithdrawalAccount::new(data).unwrap();
+            pending_withdrawal.set_unlock_slots(0, &0);
+        }
+
+        // Invalid recipient
+        assert_matches!(
+            release_pending_withdrawal(&owner, &owner, &pending_withdrawal_account, 0),
+            Err(_)
+        );
+
+        // Invalid owner
+        assert_matches!(
+            release_pending_withdrawal(&recipient, &recipient, &pending_withdrawal_account, 0),
+            Err(_)
+        );
+
+        let lamports = u32::MAX as u64;
+        release_pending_withdrawal(&owner, &recipient, &pending_withdrawal_account, 0).unwrap();
+        assert_eq!(recipient.lamports(), lamports + 100);
+        assert_eq!(owner.lamports(), lamports);
+        assert_eq!(pending_withdrawal_account.lamports(), lamports - 100);
+
+        // The dust withdrawal is still locked
+        assert_matches!(
+            close_pending_withdrawal(&owner, &pending_withdrawal_account, 0),
+            Err(_)
+        );
+
+        {
+            let data = &mut pending_withdrawal_account.data.borrow_mut()[..];
+            let mut pending_withdrawal = PendingWithdrawalAccount::new(data).unwrap();
+            pending_withdrawal.set_unlock_slots(1, &0);
+        }
+
+        // The account is not closed by the release
+        release_pending_withdrawal(&owner, &recipient, &pending_withdrawal_account, 0).unwrap();
+        assert_eq!(recipient.lamports(), lamports + 101);
+        assert_eq!(owner.lamports(), lamports);
+        assert_eq!(pending_withdrawal_account.lamports(), lamports - 101);
+
+        // Nothing left to release
+        assert_matches!(
+            release_pending_withdrawal(&owner, &recipient, &pending_withdrawal_account, 0),
+            Err(_)
+        );
+
+        // Invalid owner
+        assert_matches!(
+            close_pending_withdrawal(&recipient, &pending_withdrawal_account, 0),
+            Err(_)
+        );
+
+        close_pending_withdrawal(&owner, &pending_withdrawal_account, 0).unwrap();
+        assert_eq!(owner.lamports(), lamports * 2 - 101);
+        assert_eq!(pending_withdrawal_account.lamports(), 0);
+    }
+}
@@ -0,0 +1,266 @@
+//! `alt_bn128` group operations (https://eips.ethereum.org/EIPS/eip-196, https://eips.ethereum.org/EIPS/eip-197)
+//! - with the `alt-bn128` feature the operations are performed by the `sol_alt_bn128_group_op` syscall
+//! - a program referencing an unsupported syscall cannot be deployed, so the feature may only be enabled for runtimes supporting it
+//! - otherwise (and off-chain) the operations are computed with arkworks
+
+use crate::error::ElusivError::{self, CouldNotProcessProof};
+use crate::types::U256;
+use ark_bn254::{Fq, Fq2, G1Affine, G2Affine};
+use ark_ec::AffineCurve;
+use ark_ff::{BigInteger, BigInteger256, PrimeField, Zero};
+
+pub const ALT_BN128_ADDITION: u64 = 0;
+pub const ALT_BN128_MULTIPLICATION: u64 = 2;
+pub const ALT_BN128_PAIRING: u64 = 3;
+
+pub const ALT_BN128_G1_SIZE: usize = 64;
+pub const ALT_BN128_G2_SIZE: usize = 128;
+pub const ALT_BN128_PAIRING_ELEMENT_SIZE: usize = ALT_BN128_G1_SIZE + ALT_BN128_G2_SIZE;
+
+/// A G1 point as two big-endian coordinates (the point at infinity is all zeros)
+pub type AltBn128G1 = [u8; ALT_BN128_G1_SIZE];
+
+/// A G2 point as two big-endian coordinates with the imaginary part first (the point at infinity is all zeros)
+pub type AltBn128G2 = [u8; ALT_BN128_G2_SIZE];
+
+/// Computes `a + b`
+pub fn alt_bn128_addition(a: &AltBn128G1, b: &AltBn128G1) -> Result<AltBn128G1, ElusivError> {
+    let mut input = [0; 2 * ALT_BN128_G1_SIZE];
+    input[..ALT_BN128_G1_SIZE].copy_from_slice(a);
+    input[ALT_BN128_G1_SIZE..].copy_from_slice(b);
+
+    let mut result = [0; ALT_BN128_G1_SIZE];
+    alt_bn128_group_op(ALT_BN128_ADDITION, &input, &mut result)?;
+    Ok(result)
+}
+
+/// Computes `scalar * p`
+/// - `scalar` is big-endian
+pub fn alt_bn128_multiplication(p: &AltBn128G1, scalar: &U256) -> Result<AltBn128G1, ElusivError> {
+    let mut input = [0; ALT_BN128_G1_SIZE + 32];
+    input[..ALT_BN128_G1_SIZE].copy_from_slice(p);
+    input[ALT_BN128_G1_SIZE..].copy_from_slice(scalar);
+
+    let mut result = [0; ALT_BN128_G1_SIZE];
+    alt_bn128_group_op(ALT_BN128_MULTIPLICATION, &input, &mut result)?;
+    Ok(result)
+}
+
+/// Checks whether the product of the pairings of all `(G1, G2)` elements of `input` equals one
+pub fn alt_bn128_pairing(input: &[u8]) -> Result<bool, ElusivError> {
+    let mut result = [0; 32];
+    alt_bn128_group_op(ALT_BN128_PAIRING, input, &mut result)?;
+    Ok(result[31] == 1)
+}
+
+#[cfg(all(target_arch = "bpf", feature = "alt-bn128"))]
+fn alt_bn128_group_op(group_op: u64, input: &[u8], result: &mut [u8]) -> Result<(), ElusivError> {
+    extern "C" {
+        fn sol_alt_bn128_group_op(
+            group_op: u64,
+            input: *const u8,
+            input_size: u64,
+            result: *mut u8,
+        ) -> u64;
+    }
+
+    let code = unsafe {
+        sol_alt_bn128_group_op(
+            group_op,
+            input.as_ptr(),
+            input.len() as u64,
+            result.as_mut_ptr(),
+        )
+    };
+
+    if code != 0 {
+        return Err(CouldNotProcessProof);
+    }
+
+    Ok(())
+}
+
+#[cfg(not(all(target_arch = "bpf", feature = "alt-bn128")))]
+fn alt_bn128_group_op(group_op: u64, input: &[u8], result: &mut [u8]) -> Result<(), ElusivError> {
+    use ark_bn254::{Fq12, Parameters};
+    use ark_ec::bn::{Bn, G1Prepared, G2Prepared};
+    use ark_ec::{PairingEngine, ProjectiveCurve};
+    use ark_ff::One;
+
+    match group_op {
+        ALT_BN128_ADDITION => {
+            let a = g1_from_be(&input[..ALT_BN128_G1_SIZE])?;
+            let b = g1_from_be(&input[ALT_BN128_G1_SIZE..])?;
+            result.copy_from_slice(&g1_to_be(&(a + b)));
+        }
+        ALT_BN128_MULTIPLICATION => {
+            let p = g1_from_be(&input[..ALT_BN128_G1_SIZE])?;
+            let scalar = u256_from_be(&input[ALT_BN128_G1_SIZE..]);
+            result.copy_from_slice(&g1_to_be(&p.mul(scalar).into_affine()));
+        }
+        ALT_BN128_PAIRING => {
+            if input.len() % ALT_BN128_PAIRING_ELEMENT_SIZE != 0 {
+                return Err(CouldNotProcessProof);
+            }
+
+            let mut pairs: Vec<(G1Prepared<Parameters>, G2Prepared<Parameters>)> =
+                Vec::with_capacity(input.len() / ALT_BN128_PAIRING_ELEMENT_SIZE);
+            for element in input.chunks(ALT_BN128_PAIRING_ELEMENT_SIZE) {
+                let g1 = g1_from_be(&element[..ALT_BN128_G1_SIZE])?;
+                let g2 = g2_from_be(&element[ALT_BN128_G1_SIZE..])?;
+                pairs.push((g1.into(), g2.into()));
+            }
+
+            let is_one = Bn::<Parameters>::product_of_pairings(&pairs) == Fq12::one();
+            result.fill(0);
+            result[31] = is_one as u8;
+        }
+        _ => return Err(CouldNotProcessProof),
+    }
+
+    Ok(())
+}
+
+pub fn g1_to_be(p: &G1Affine) -> AltBn128G1 {
+    let mut bytes = [0; ALT_BN128_G1_SIZE];
+    if !p.infinity {
+        fq_to_be(&p.x, &mut bytes[..32]);
+        fq_to_be(&p.y, &mut bytes[32..]);
+    }
+    bytes
+}
+
+pub fn g2_to_be(p: &G2Affine) -> AltBn128G2 {
+    let mut bytes = [0; ALT_BN128_G2_SIZE];
+    if !p.infinity {
+        fq_to_be(&p.x.c1, &mut bytes[..32]);
+        fq_to_be(&p.x.c0, &mut bytes[32..64]);
+        fq_to_be(&p.y.c1, &mut bytes[64..96]);
+        fq_to_be(&p.y.c0, &mut bytes[96..]);
+    }
+    bytes
+}
+
+pub fn g1_from_be(bytes: &[u8]) -> Result<G1Affine, ElusivError> {
+    if bytes.iter().all(|&b| b == 0) {
+        return Ok(G1Affine::zero());
+    }
+
+    let p = G1Affine::new(
+        fq_from_be(&bytes[..32])?,
+        fq_from_be(&bytes[32..64])?,
+        false,
+    );
+    if !p.is_on_curve() {
+        return Err(CouldNotProcessProof);
+    }
+
+    Ok(p)
+}
+
+pub fn g2_from_be(bytes: &[u8]) -> Result<G2Affine, ElusivError> {
+    if bytes.iter().all(|&b| b == 0) {
+        return Ok(G2Affine::zero());
+    }
+
+    let x = Fq2::new(fq_from_be(&bytes[32..64])?, fq_from_be(&bytes[..32])?);
+    let y = Fq2::new(fq_from_be(&bytes[96..128])?, fq_from_be(&bytes[64..96])?);
+    let p = G2Affine::new(x, y, false);
+    if !p.is_on_curve() || !p.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(CouldNotProcessProof);
+    }
+
+    Ok(p)
+}
+
+fn fq_to_be(v: &Fq, bytes: &mut [u8]) {
+    bytes.copy_from_slice(&v.into_repr().to_bytes_be());
+}
+
+fn fq_from_be(bytes: &[u8]) -> Result<Fq, ElusivError> {
+    Fq::from_repr(u256_from_be(bytes)).ok_or(CouldNotProcessProof)
+}
+
+fn u256_from_be(bytes: &[u8]) -> BigInteger256 {
+    let mut limbs = [0; 4];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        let offset = 24 - i * 8;
+        *limb = u64::from_be_bytes(bytes[offset..offset + 8].try_into().unwrap());
+    }
+    BigInteger256(limbs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::{Fr, G1Projective, G2Projective};
+    use ark_ec::ProjectiveCurve;
+    use ark_ff::UniformRand;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_g1_be_encoding() {
+        let mut rng = rand::thread_rng();
+        let p = G1Projective::rand(&mut rng).into_affine();
+
+        assert_eq!(g1_from_be(&g1_to_be(&p)).unwrap(), p);
+        assert_eq!(g1_to_be(&G1Affine::zero()), [0; ALT_BN128_G1_SIZE]);
+        assert_eq!(
+            g1_from_be(&[0; ALT_BN128_G1_SIZE]).unwrap(),
+            G1Affine::zero()
+        );
+
+        // Not on the curve
+        let mut bytes = g1_to_be(&p);
+        bytes[63] ^= 1;
+        assert_matches!(g1_from_be(&bytes), Err(_));
+    }
+
+    #[test]
+    fn test_g2_be_encoding() {
+        let mut rng = rand::thread_rng();
+        let p = G2Projective::rand(&mut rng).into_affine();
+
+        assert_eq!(g2_from_be(&g2_to_be(&p)).unwrap(), p);
+        assert_eq!(g2_to_be(&G2Affine::zero()), [0; ALT_BN128_G2_SIZE]);
+
+        // Not on the curve
+        let mut bytes = g2_to_be(&p);
+        bytes[127] ^= 1;
+        assert_matches!(g2_from_be(&bytes), Err(_));
+    }
+
+    #[test]
+    fn test_alt_bn128_group_ops() {
+        let mut rng = rand::thread_rng();
+        let a = G1Projective::rand(&mut rng).into_affine();
+        let b = G1Projective::rand(&mut rng).into_affine();
+        let s = Fr::rand(&mut rng);
+
+        assert_eq!(
+            alt_bn128_addition(&g1_to_be(&a), &g1_to_be(&b)).unwrap(),
+            g1_to_be(&(a + b))
+        );
+
+        let mut scalar = [0; 32];
+        scalar.copy_from_slice(&s.into_repr().to_bytes_be());
+        assert_eq!(
+            alt_bn128_multiplication(&g1_to_be(&a), &scalar).unwrap(),
+            g1_to_be(&a.mul(s).into_affine())
+        );
+
+        // e(a, g2) * e(-a, g2) == 1
+        let g2 = G2Projective::rand(&mut rng).into_affine();
+        let mut input = Vec::new();
+        input.extend(g1_to_be(&a));
+        input.extend(g2_to_be(&g2));
+        input.extend(g1_to_be(&-a));
+        input.extend(g2_to_be(&g2));
+        assert!(alt_bn128_pairing(&input).unwrap());
+
+        input[..ALT_BN128_G1_SIZE].copy_from_slice(&g1_to_be(&b));
+        assert!(!alt_bn128_pairing(&input).unwrap());
+
+        assert_matches!(alt_bn128_pairing(&input[1..]), Err(_));
+    }
+}
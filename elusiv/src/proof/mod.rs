@@ -1,3 +1,6 @@
+pub mod alt_bn128;
+#[cfg(feature = "elusiv-client")]
+pub mod precheck;
 pub mod verifier;
 pub mod vkey;
 
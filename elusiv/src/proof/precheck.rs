@@ -0,0 +1,357 @@
+//! Off-chain sanity checks of a [`ProofRequest`] against a snapshot of the chain state
+//!
+//! # Notes
+//!
+//! The checks mirror the preconditions enforced by [`crate::processor::init_verification`] and
+//! [`crate::processor::init_verification_transfer_fee`], so that a wallet can report every violation at once
+//! (instead of the first custom error code returned by the program).
+//! All roots and nullifier-hashes of the [`ChainState`] are expected in the (reduced) form stored on-chain.
+
+use crate::processor::{ProofRequest, MAX_MT_COUNT, ZERO_COMMITMENT_RAW};
+use crate::token::{elusiv_token, TokenID};
+use crate::types::{PublicInputs, JOIN_SPLIT_MAX_N_ARITY, U256};
+use std::collections::{HashMap, HashSet};
+
+/// The chain state relevant for the verification of a [`ProofRequest`]
+#[derive(Default, Debug, Clone)]
+pub struct ChainState {
+    /// The fee-version of the [`crate::state::governor::GovernorAccount`]
+    pub fee_version: u32,
+
+    /// The `trees_count` of the [`crate::state::storage::StorageAccount`] (index of the active MT)
+    pub active_tree_index: u32,
+
+    /// The current and historic roots of the active MT
+    pub active_roots: HashSet<U256>,
+
+    /// The roots of the closed MTs by tree index
+    pub closed_roots: HashMap<u32, U256>,
+
+    /// All spent nullifier-hashes as `(tree_index, nullifier_hash)`
+    pub spent_nullifier_hashes: HashSet<(u32, U256)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofRequestViolation {
+    /// Migrate requests are not yet supported by the program
+    MigrateIsNotAvailable,
+
+    UnsupportedToken(TokenID),
+    InvalidFeeVersion {
+        expected: u32,
+        actual: u32,
+    },
+
+    ZeroOutputCommitment,
+    NoInputCommitments,
+    TooManyInputCommitments(usize),
+    FirstRootIsMissing,
+
+    /// More distinct MTs than [`MAX_MT_COUNT`] are referenced
+    TooManyMerkleTrees(usize),
+    DuplicateTreeIndices,
+    UnknownMerkleTree {
+        tree_index: u32,
+    },
+    InvalidRoot {
+        input: usize,
+        tree_index: u32,
+    },
+
+    /// The same nullifier-hash is used twice for the same MT
+    DuplicateNullifierHash {
+        input: usize,
+    },
+    NullifierHashIsSpent {
+        input: usize,
+        tree_index: u32,
+    },
+}
+
+/// Returns all violations of on-chain preconditions of `request` (an empty list if the request can be verified)
+/// - `tree_indices` are the MT indices supplied to `InitVerification`
+pub fn check_proof_request(
+    request: &ProofRequest,
+    tree_indices: &[u32; MAX_MT_COUNT],
+    chain: &ChainState,
+) -> Vec<ProofRequestViolation> {
+    let public_inputs = match request {
+        ProofRequest::Send(public_inputs) => public_inputs,
+        ProofRequest::Migrate(_) => return vec![ProofRequestViolation::MigrateIsNotAvailable],
+    };
+    let join_split = public_inputs.join_split_inputs();
+    let mut violations = Vec::new();
+
+    if elusiv_token(join_split.token_id).is_err() {
+        violations.push(ProofRequestViolation::UnsupportedToken(join_split.token_id));
+    }
+
+    if join_split.fee_version != chain.fee_version {
+        violations.push(ProofRequestViolation::InvalidFeeVersion {
+            expected: chain.fee_version,
+            actual: join_split.fee_version,
+        });
+    }
+
+    if join_split.output_commitment.skip_mr() == ZERO_COMMITMENT_RAW {
+        violations.push(ProofRequestViolation::ZeroOutputCommitment);
+    }
+
+    let input_commitments = &join_split.input_commitments;
+    if input_commitments.is_empty() {
+        violations.push(ProofRequestViolation::NoInputCommitments);
+        return violations;
+    }
+    if input_commitments.len() > JOIN_SPLIT_MAX_N_ARITY {
+        violations.push(ProofRequestViolation::TooManyInputCommitments(
+            input_commitments.len(),
+        ));
+    }
+    if input_commitments[0].root.is_none() {
+        violations.push(ProofRequestViolation::FirstRootIsMissing);
+    }
+
+    // Each input commitment without a root belongs to the first MT
+    let mut roots_count = 0;
+    let mut input_trees = Vec::with_capacity(input_commitments.len());
+    for (input, input_commitment) in input_commitments.iter().enumerate() {
+        let root = match input_commitment.root {
+            Some(root) => root,
+            None => {
+                input_trees.push(Some(tree_indices[0]));
+                continue;
+            }
+        };
+
+        let tree_index = tree_indices.get(roots_count).copied();
+        roots_count += 1;
+        input_trees.push(tree_index);
+
+        let tree_index = match tree_index {
+            Some(tree_index) => tree_index,
+            None => continue,
+        };
+
+        let root = root.reduce();
+        let is_valid = if tree_index == chain.active_tree_index {
+            chain.active_roots.contains(&root)
+        } else {
+            match chain.closed_roots.get(&tree_index) {
+                Some(closed_root) => *closed_root == root,
+                None => {
+                    violations.push(ProofRequestViolation::UnknownMerkleTree { tree_index });
+                    continue;
+                }
+            }
+        };
+
+        if !is_valid {
+            violations.push(ProofRequestViolation::InvalidRoot { input, tree_index });
+        }
+    }
+
+    if roots_count > MAX_MT_COUNT {
+        violations.push(ProofRequestViolation::TooManyMerkleTrees(roots_count));
+    }
+    if roots_count > 1 && tree_indices[0] == tree_indices[1] {
+        violations.push(ProofRequestViolation::DuplicateTreeIndices);
+    }
+
+    let mut nullifier_hashes = HashSet::new();
+    for (input, (input_commitment, tree_index)) in
+        input_commitments.iter().zip(input_trees).enumerate()
+    {
+        let tree_index = match tree_index {
+            Some(tree_index) => tree_index,
+            None => continue,
+        };
+
+        let nullifier_hash = input_commitment.nullifier_hash.reduce();
+        if !nullifier_hashes.insert((tree_index, nullifier_hash)) {
+            violations.push(ProofRequestViolation::DuplicateNullifierHash { input });
+        }
+
+        if chain
+            .spent_nullifier_hashes
+            .contains(&(tree_index, nullifier_hash))
+        {
+            violations.push(ProofRequestViolation::NullifierHashIsSpent { input, tree_index });
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields::u256_from_str_skip_mr;
+    use crate::types::{
+        InputCommitment, JoinSplitPublicInputs, MigratePublicInputs, RawU256, SendPublicInputs,
+    };
+
+    fn send_request(input_commitments: Vec<InputCommitment>) -> ProofRequest {
+        ProofRequest::Send(SendPublicInputs {
+            join_split: JoinSplitPublicInputs {
+                input_commitments,
+                output_commitment: RawU256::new(u256_from_str_skip_mr("1")),
+                output_commitment_index: 0,
+                fee_version: 0,
+                amount: 0,
+                fee: 0,
+                token_id: 0,
+            },
+            recipient_is_associated_token_account: false,
+            solana_pay_transfer: false,
+            hashed_inputs: [0; 32],
+            encrypted_memo: None,
+        })
+    }
+
+    fn input(root: Option<&str>, nullifier_hash: &str) -> InputCommitment {
+        InputCommitment {
+            root: root.map(|root| RawU256::new(u256_from_str_skip_mr(root))),
+            nullifier_hash: RawU256::new(u256_from_str_skip_mr(nullifier_hash)),
+        }
+    }
+
+    fn chain_state() -> ChainState {
+        ChainState {
+            fee_version: 0,
+            active_tree_index: 1,
+            active_roots: HashSet::from([RawU256::new(u256_from_str_skip_mr("2")).reduce()]),
+            closed_roots: HashMap::from([(0, RawU256::new(u256_from_str_skip_mr("3")).reduce())]),
+            spent_nullifier_hashes: HashSet::from([(
+                1,
+                RawU256::new(u256_from_str_skip_mr("9")).reduce(),
+            )]),
+        }
+    }
+
+    #[test]
+    fn test_check_proof_request_valid() {
+        let chain = chain_state();
+
+        let request = send_request(vec![input(Some("2"), "4"), input(None, "5")]);
+        assert_eq!(check_proof_request(&request, &[1, 0], &chain), vec![]);
+
+        let request = send_request(vec![input(Some("2"), "4"), input(Some("3"), "4")]);
+        assert_eq!(check_proof_request(&request, &[1, 0], &chain), vec![]);
+    }
+
+    #[test]
+    fn test_check_proof_request_violations() {
+        let chain = chain_state();
+
+        // Migrate
+        let request = ProofRequest::Migrate(MigratePublicInputs {
+            join_split: match send_request(vec![input(Some("2"), "4")]) {
+                ProofRequest::Send(public_inputs) => public_inputs.join_split,
+                _ => unreachable!(),
+            },
+            current_nsmt_root: RawU256::new([0; 32]),
+            next_nsmt_root: RawU256::new([0; 32]),
+        });
+        assert_eq!(
+            check_proof_request(&request, &[1, 0], &chain),
+            vec![ProofRequestViolation::MigrateIsNotAvailable]
+        );
+
+        // No input commitments
+        let request = send_request(vec![]);
+        assert_eq!(
+            check_proof_request(&request, &[1, 0], &chain),
+            vec![ProofRequestViolation::NoInputCommitments]
+        );
+
+        // Fee version, token and zero-commitment
+        let mut request = send_request(vec![input(Some("2"), "4")]);
+        if let ProofRequest::Send(public_inputs) = &mut request {
+            public_inputs.join_split.fee_version = 1;
+            public_inputs.join_split.token_id = u16::MAX;
+            public_inputs.join_split.output_commitment = RawU256::new(ZERO_COMMITMENT_RAW);
+        }
+        assert_eq!(
+            check_proof_request(&request, &[1, 0], &chain),
+            vec![
+                ProofRequestViolation::UnsupportedToken(u16::MAX),
+                ProofRequestViolation::InvalidFeeVersion {
+                    expected: 0,
+                    actual: 1
+                },
+                ProofRequestViolation::ZeroOutputCommitment,
+            ]
+        );
+
+        // Missing first root and too many input commitments
+        let request = send_request(vec![
+            input(None, "4"),
+            input(None, "5"),
+            input(None, "6"),
+            input(None, "7"),
+            input(None, "8"),
+        ]);
+        assert_eq!(
+            check_proof_request(&request, &[1, 0], &chain),
+            vec![
+                ProofRequestViolation::TooManyInputCommitments(5),
+                ProofRequestViolation::FirstRootIsMissing,
+            ]
+        );
+
+        // Invalid active and closed roots
+        let request = send_request(vec![input(Some("3"), "4"), input(Some("2"), "5")]);
+        assert_eq!(
+            check_proof_request(&request, &[1, 0], &chain),
+            vec![
+                ProofRequestViolation::InvalidRoot {
+                    input: 0,
+                    tree_index: 1
+                },
+                ProofRequestViolation::InvalidRoot {
+                    input: 1,
+                    tree_index: 0
+                },
+            ]
+        );
+
+        // Unknown MT and duplicate tree indices
+        let request = send_request(vec![input(Some("2"), "4"), input(Some("2"), "5")]);
+        assert_eq!(
+            check_proof_request(&request, &[7, 7], &chain),
+            vec![
+                ProofRequestViolation::UnknownMerkleTree { tree_index: 7 },
+                ProofRequestViolation::UnknownMerkleTree { tree_index: 7 },
+                ProofRequestViolation::DuplicateTreeIndices,
+            ]
+        );
+
+        // Too many MTs
+        let request = send_request(vec![
+            input(Some("2"), "4"),
+            input(Some("3"), "5"),
+            input(Some("2"), "6"),
+        ]);
+        assert_eq!(
+            check_proof_request(&request, &[1, 0], &chain),
+            vec![ProofRequestViolation::TooManyMerkleTrees(3)]
+        );
+
+        // Duplicate and spent nullifier-hashes
+        let request = send_request(vec![
+            input(Some("2"), "4"),
+            input(None, "4"),
+            input(None, "9"),
+        ]);
+        assert_eq!(
+            check_proof_request(&request, &[1, 0], &chain),
+            vec![
+                ProofRequestViolation::DuplicateNullifierHash { input: 1 },
+                ProofRequestViolation::NullifierHashIsSpent {
+                    input: 2,
+                    tree_index: 1
+                },
+            ]
+        );
+    }
+}
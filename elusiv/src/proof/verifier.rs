@@ -7,8 +7,13 @@
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::assign_op_pattern)]
 
+use super::alt_bn128::{
+    alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing, g1_to_be, g2_to_be,
+    ALT_BN128_PAIRING_ELEMENT_SIZE,
+};
 use super::vkey::VerifyingKey;
 use crate::bytes::{usize_as_u32_safe, usize_as_u8_safe};
+use crate::computation::PartialComputationDriver;
 use crate::error::ElusivError::{
     self, ComputationIsAlreadyFinished, CouldNotProcessProof, InvalidAccountState,
     PartialComputationError,
@@ -16,7 +21,9 @@ use crate::error::ElusivError::{
 use crate::error::ElusivResult;
 use crate::fields::{G2HomProjective, Wrap, G1A, G2A};
 use crate::processor::COMPUTE_VERIFICATION_IX_COUNT;
-use crate::state::proof::{RAMFq, VerificationAccount, VerificationState};
+use crate::state::proof::{
+    BatchVerificationAccount, RAMFq, VerificationAccount, VerificationState,
+};
 use crate::types::U256;
 use ark_bn254::{
     Fq, Fq12, Fq12Parameters, Fq2, Fq6, Fq6Parameters, G1Affine, G1Projective, G2Affine, Parameters,
@@ -42,12 +49,25 @@ pub enum VerificationStep {
     FinalExponentiation,
 }
 
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, Clone)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub enum BatchVerificationStep {
+    MultiExponentiation,
+    FinalExponentiation,
+}
+
 /// Requires `verification_account.prepare_inputs_instructions_count + COMBINED_MILLER_LOOP_IXS + FINAL_EXPONENTIATION_IXS` calls to verify a valid proof
+/// - with the `alt-bn128` feature a single call suffices (see [`verify_with_syscalls`])
 pub fn verify_partial(
     verification_account: &mut VerificationAccount,
     vkey: &VerifyingKey,
     instruction_index: u16,
+    slot: u64,
 ) -> Result<Option<bool>, ElusivError> {
+    if cfg!(feature = "alt-bn128") {
+        return verify_with_syscalls(verification_account, vkey, instruction_index);
+    }
+
     let instruction = verification_account.get_instruction() as usize;
     let round = verification_account.get_round() as usize;
     let step = verification_account.get_step();
@@ -59,7 +79,7 @@ pub fn verify_partial(
                 return Ok(None);
             }
 
-            prepare_public_inputs(verification_account, vkey, instruction, round)?;
+            prepare_public_inputs(verification_account, vkey, instruction, round, slot)?;
             verification_account.serialize_rams().unwrap();
         }
         VerificationStep::CombinedMillerLoop => {
@@ -72,7 +92,7 @@ pub fn verify_partial(
                 InvalidAccountState
             );
 
-            combined_miller_loop(verification_account, vkey, instruction, round)?;
+            combined_miller_loop(verification_account, vkey, instruction, round, slot)?;
             verification_account.serialize_rams().unwrap();
         }
         VerificationStep::FinalExponentiation => {
@@ -81,7 +101,7 @@ pub fn verify_partial(
                 return Ok(None);
             }
 
-            let v = final_exponentiation(verification_account, vkey, instruction, round);
+            let v = final_exponentiation(verification_account, vkey, instruction, round, slot);
             verification_account.serialize_rams().unwrap();
             return v;
         }
@@ -90,11 +110,83 @@ pub fn verify_partial(
     Ok(None)
 }
 
+/// Verifies the proof with the `alt_bn128` syscalls in a single instruction
+///
+/// # Notes
+///
+/// - `prepared_inputs = gamma_abc_g1_{0} + \sum_{i = 1}ˆ{N} input_{i} gamma_abc_g1_{i}` is computed with the multiplication and addition syscalls
+/// - we check `e(A, B) * e(prepared_inputs, -gamma) * e(C, -delta) * e(-alpha, beta) = 1` with a single pairing syscall
+/// - without the `alt-bn128` feature the syscalls are computed with arkworks (exceeding the compute budget on-chain)
+/// - a proof whose points are rejected by the syscalls (not on the curve or not in the subgroup) is invalid
+pub fn verify_with_syscalls(
+    verification_account: &mut VerificationAccount,
+    vkey: &VerifyingKey,
+    instruction_index: u16,
+) -> Result<Option<bool>, ElusivError> {
+    // This enables us to use a uniform number of ixs per tx (by only allowing the last ix to perform the computation)
+    if instruction_index != COMPUTE_VERIFICATION_IX_COUNT - 1 {
+        return Ok(None);
+    }
+
+    guard!(
+        matches!(
+            verification_account.get_step(),
+            VerificationStep::PublicInputPreparation
+        ),
+        ComputationIsAlreadyFinished
+    );
+    guard!(
+        matches!(
+            verification_account.get_state(),
+            VerificationState::ProofSetup
+        ),
+        InvalidAccountState
+    );
+
+    let result = verify_pairing_with_syscalls(verification_account, vkey).unwrap_or(false);
+    verification_account.set_step(&VerificationStep::FinalExponentiation);
+
+    Ok(Some(result))
+}
+
+fn verify_pairing_with_syscalls(
+    verification_account: &mut VerificationAccount,
+    vkey: &VerifyingKey,
+) -> Result<bool, ElusivError> {
+    let mut prepared_inputs = g1_to_be(&vkey.gamma_abc_base().into_affine());
+    for i in 0..vkey.public_inputs_count {
+        let public_input = verification_account.get_public_input(i).skip_mr();
+        if public_input == [0; 32] {
+            continue;
+        }
+
+        let mut scalar = public_input;
+        scalar.reverse();
+
+        let gamma_abc = g1_to_be(&vkey.gamma_abc(i, 0, 1));
+        let product = alt_bn128_multiplication(&gamma_abc, &scalar)?;
+        prepared_inputs = alt_bn128_addition(&prepared_inputs, &product)?;
+    }
+
+    let mut input = Vec::with_capacity(4 * ALT_BN128_PAIRING_ELEMENT_SIZE);
+    input.extend(g1_to_be(&verification_account.a.get().0));
+    input.extend(g2_to_be(&verification_account.b.get().0));
+    input.extend(prepared_inputs);
+    input.extend(g2_to_be(&vkey.gamma().neg()));
+    input.extend(g1_to_be(&verification_account.c.get().0));
+    input.extend(g2_to_be(&vkey.delta().neg()));
+    input.extend(g1_to_be(&vkey.alpha().neg()));
+    input.extend(g2_to_be(&vkey.beta()));
+
+    alt_bn128_pairing(&input)
+}
+
 pub fn prepare_public_inputs(
     verification_account: &mut VerificationAccount,
     vkey: &VerifyingKey,
     instruction: usize,
     round: usize,
+    slot: u64,
 ) -> ElusivResult {
     let rounds = verification_account.get_prepare_inputs_instructions(instruction);
 
@@ -108,11 +200,13 @@ pub fn prepare_public_inputs(
             .set(G1A(prepared_inputs));
 
         verification_account.set_step(&VerificationStep::CombinedMillerLoop);
-        verification_account.set_round(&0);
-        verification_account.set_instruction(&0);
+        verification_account.start_computation(slot);
     } else {
-        verification_account.set_round(&(round as u32 + rounds as u32));
-        verification_account.set_instruction(&(instruction as u32 + 1));
+        verification_account.advance_computation(
+            rounds as u32,
+            usize_as_u32_safe(prepare_public_inputs_rounds(vkey.public_inputs_count)),
+            slot,
+        )?;
     }
 
     Ok(())
@@ -123,6 +217,7 @@ pub fn combined_miller_loop(
     vkey: &VerifyingKey,
     instruction: usize,
     round: usize,
+    slot: u64,
 ) -> ElusivResult {
     let rounds = CombinedMillerLoop::INSTRUCTION_ROUNDS[instruction] as usize;
 
@@ -160,14 +255,16 @@ pub fn combined_miller_loop(
         verification_account.f.set(Wrap(f));
 
         verification_account.set_step(&VerificationStep::FinalExponentiation);
-        verification_account.set_round(&0);
-        verification_account.set_instruction(&0);
+        verification_account.start_computation(slot);
     } else {
         verification_account.r.set(r);
         verification_account.alt_b.set(alt_b);
 
-        verification_account.set_round(&usize_as_u32_safe(round + rounds));
-        verification_account.set_instruction(&(instruction as u32 + 1));
+        verification_account.advance_computation(
+            usize_as_u32_safe(rounds),
+            CombinedMillerLoop::TOTAL_ROUNDS,
+            slot,
+        )?;
     }
 
     Ok(())
@@ -178,6 +275,7 @@ pub fn final_exponentiation(
     vkey: &VerifyingKey,
     instruction: usize,
     round: usize,
+    slot: u64,
 ) -> Result<Option<bool>, ElusivError> {
     guard!(
         instruction < FinalExponentiation::IX_COUNT,
@@ -193,8 +291,11 @@ pub fn final_exponentiation(
         result = final_exponentiation_partial(round, verification_account, &f)?;
     }
 
-    verification_account.set_round(&usize_as_u32_safe(round + rounds));
-    verification_account.set_instruction(&(instruction as u32 + 1));
+    verification_account.advance_computation(
+        usize_as_u32_safe(rounds),
+        FinalExponentiation::TOTAL_ROUNDS,
+        slot,
+    )?;
 
     if round + rounds == FinalExponentiation::TOTAL_ROUNDS as usize {
         let v = result.ok_or(CouldNotProcessProof)?;
@@ -209,6 +310,163 @@ pub fn final_exponentiation(
     Ok(None)
 }
 
+/// Bits of the pseudo-random scalars used for batching (the sum of all scalars fits into a `u64`)
+pub const BATCH_SCALAR_BITS: u32 = 60;
+const BATCH_EXPONENT_BITS: u32 = u64::BITS;
+
+const BATCH_SCALAR_DOMAIN: &[u8] = b"elusiv-batch-verification";
+
+const SQUARE_FQ12_COST: u32 = 90_000;
+const CYCLOTOMIC_SQUARE_FQ12_COST: u32 = 47_000;
+const MUL_FQ12_COST: u32 = 126_000;
+const BATCH_ROUNDS_BUDGET: u32 = 1_300_000;
+
+/// Derives the scalars `r_i` from all miller loop outputs of the batch (Fiat-Shamir)
+/// - the scalars are non-zero and at most [`BATCH_SCALAR_BITS`] bits long
+pub fn batch_verification_scalars(miller_loop_outputs: &[Fq12]) -> Vec<u64> {
+    let outputs: Vec<Vec<u8>> = miller_loop_outputs
+        .iter()
+        .map(|f| Wrap(*f).try_to_vec().unwrap())
+        .collect();
+
+    (0..miller_loop_outputs.len() as u64)
+        .map(|i| {
+            let mut data = vec![BATCH_SCALAR_DOMAIN];
+            data.extend(outputs.iter().map(|o| &o[..]));
+            let index = i.to_le_bytes();
+            data.push(&index);
+
+            let hash = solana_program::hash::hashv(&data).to_bytes();
+            let scalar = u64::from_le_bytes(hash[..8].try_into().unwrap());
+            (scalar >> (u64::BITS - BATCH_SCALAR_BITS)) | 1
+        })
+        .collect()
+}
+
+/// Verifies the proofs of a [`BatchVerificationAccount`] using a single final exponentiation
+/// - checks `final_exponentiation(prod(f_i^r_i)) == alpha_beta^sum(r_i)`
+/// - the final exponentiation is computed in the `leader` (the first [`VerificationAccount`] of the batch)
+pub fn verify_batch_partial(
+    batch_account: &mut BatchVerificationAccount,
+    leader: &mut VerificationAccount,
+    vkey: &VerifyingKey,
+    instruction_index: u16,
+    slot: u64,
+) -> Result<Option<bool>, ElusivError> {
+    // This enables us to use a uniform number of ixs per tx (by only allowing the last ix to perform the computation)
+    if instruction_index != COMPUTE_VERIFICATION_IX_COUNT - 1 {
+        return Ok(None);
+    }
+
+    match batch_account.get_step() {
+        BatchVerificationStep::MultiExponentiation => {
+            batch_multi_exponentiation(batch_account, &vkey.alpha_beta(), slot)?;
+
+            if batch_account.get_round() == BATCH_EXPONENT_BITS {
+                leader.f.set(batch_account.accumulator.get());
+                leader.set_step(&VerificationStep::FinalExponentiation);
+                leader.start_computation(slot);
+
+                batch_account.set_step(&BatchVerificationStep::FinalExponentiation);
+                batch_account.start_computation(slot);
+            }
+        }
+        BatchVerificationStep::FinalExponentiation => {
+            let instruction = leader.get_instruction() as usize;
+            let round = leader.get_round() as usize;
+
+            let v = final_exponentiation(leader, vkey, instruction, round, slot)?;
+            leader.serialize_rams().unwrap();
+
+            if v.is_some() {
+                return Ok(Some(leader.f.get() == batch_account.expected.get()));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Computes `prod(f_i^r_i)` and `alpha_beta^sum(r_i)` (square-and-multiply over all scalars at once)
+fn batch_multi_exponentiation(
+    batch_account: &mut BatchVerificationAccount,
+    alpha_beta: &Fq12,
+    slot: u64,
+) -> ElusivResult {
+    guard!(
+        batch_account.get_round() < BATCH_EXPONENT_BITS,
+        ComputationIsAlreadyFinished
+    );
+
+    let miller_loop_outputs = batch_account.all_miller_loop_outputs();
+    guard!(!miller_loop_outputs.is_empty(), InvalidAccountState);
+
+    // The first instruction seals the batch
+    if batch_account.get_instruction() == 0 {
+        for (i, scalar) in batch_verification_scalars(&miller_loop_outputs)
+            .iter()
+            .enumerate()
+        {
+            batch_account.set_scalars(i, scalar);
+        }
+        batch_account.accumulator.set(Wrap(Fq12::one()));
+        batch_account.expected.set(Wrap(Fq12::one()));
+    }
+
+    let scalars: Vec<u64> = (0..miller_loop_outputs.len())
+        .map(|i| batch_account.get_scalars(i))
+        .collect();
+    let scalars_sum: u64 = scalars.iter().sum();
+
+    let mut accumulator = batch_account.accumulator.get().0;
+    let mut expected = batch_account.expected.get().0;
+
+    let start_round = batch_account.get_round();
+    let mut round = start_round;
+    let mut cost = 0;
+    while round < BATCH_EXPONENT_BITS {
+        let bit = BATCH_EXPONENT_BITS - 1 - round;
+        let round_cost = batch_round_cost(&scalars, scalars_sum, bit);
+        if round > start_round && cost + round_cost > BATCH_ROUNDS_BUDGET {
+            break;
+        }
+
+        accumulator.square_in_place();
+        for (f, scalar) in miller_loop_outputs.iter().zip(&scalars) {
+            if (scalar >> bit) & 1 == 1 {
+                accumulator *= f;
+            }
+        }
+
+        expected = expected.cyclotomic_square();
+        if (scalars_sum >> bit) & 1 == 1 {
+            expected *= alpha_beta;
+        }
+
+        cost += round_cost;
+        round += 1;
+    }
+
+    batch_account.accumulator.set(Wrap(accumulator));
+    batch_account.expected.set(Wrap(expected));
+    batch_account.advance_computation(round - start_round, BATCH_EXPONENT_BITS, slot)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+const_assert!(
+    crate::state::proof::MAX_BATCH_VERIFICATION_PROOFS as u64
+        <= 1 << (BATCH_EXPONENT_BITS - BATCH_SCALAR_BITS)
+);
+
+fn batch_round_cost(scalars: &[u64], scalars_sum: u64, bit: u32) -> u32 {
+    let muls = scalars.iter().filter(|&s| (s >> bit) & 1 == 1).count() as u32
+        + ((scalars_sum >> bit) & 1) as u32;
+
+    SQUARE_FQ12_COST + CYCLOTOMIC_SQUARE_FQ12_COST + muls * MUL_FQ12_COST
+}
+
 macro_rules! read_g1_p {
     ($ram: expr, $o: literal) => {
         G1Projective::new($ram.read($o), $ram.read($o + 1), $ram.read($o + 2))
@@ -308,12 +566,25 @@ const ADD_MIXED_COST: u16 = 22;
 const ADD_COST: u16 = 30;
 const MAX_CUS: u16 = 1_330; // 1_400_000 / 1000 minus padding
 
+/// The maximum governance-set safety margin (in thousands of compute units) per input preparation instruction
+pub const MAX_COMPUTE_UNIT_MARGIN: u16 = 1_000;
+
 /// Returns the instructions (and their rounds) required for a specific public-input-bound input preparation
+/// - `compute_unit_margin` (in thousands of compute units) is kept free in each instruction to absorb CU variance
 pub fn prepare_public_inputs_instructions(
     public_inputs: &[U256],
     public_inputs_count: usize,
+    compute_unit_margin: u16,
 ) -> Vec<u32> {
     assert!(public_inputs.len() == public_inputs_count);
+    assert!(compute_unit_margin <= MAX_COMPUTE_UNIT_MARGIN);
+
+    // With the alt_bn128 syscalls the complete verification is performed by a single instruction
+    if cfg!(feature = "alt-bn128") {
+        return vec![0];
+    }
+
+    let max_cus = MAX_CUS - compute_unit_margin;
 
     let mut instructions = Vec::new();
 
@@ -335,7 +606,7 @@ pub fn prepare_public_inputs_instructions(
                 ADD_MIXED_COST
             };
 
-            if compute_units + cus > MAX_CUS {
+            if compute_units + cus > max_cus {
                 instructions.push(rounds);
 
                 rounds = 1;
@@ -991,7 +1262,7 @@ mod tests {
         }
 
         let instructions =
-            prepare_public_inputs_instructions(public_inputs, VKey::public_inputs_count());
+            prepare_public_inputs_instructions(public_inputs, VKey::public_inputs_count(), 0);
         storage
             .setup_public_inputs_instructions(&instructions)
             .unwrap();
@@ -1103,7 +1374,7 @@ mod tests {
 
         for i in 0..storage.get_prepare_inputs_instructions_count() {
             let round = storage.get_round();
-            prepare_public_inputs(&mut storage, &vkey, i as usize, round as usize).unwrap();
+            prepare_public_inputs(&mut storage, &vkey, i as usize, round as usize, 0).unwrap();
         }
         let expected = prepare_inputs(
             &pvk,
@@ -1287,7 +1558,7 @@ mod tests {
 
         for i in 0..COMBINED_MILLER_LOOP_IXS {
             let round = storage.get_round();
-            combined_miller_loop(&mut storage, &vkey, i, round as usize).unwrap();
+            combined_miller_loop(&mut storage, &vkey, i, round as usize, 0).unwrap();
         }
         assert_eq!(storage.f.get().0, expected);
     }
@@ -1447,7 +1718,7 @@ mod tests {
 
         for i in 0..FINAL_EXPONENTIATION_IXS {
             let round = storage.get_round();
-            final_exponentiation(&mut storage, &vkey, i, round as usize).unwrap();
+            final_exponentiation(&mut storage, &vkey, i, round as usize, 0).unwrap();
         }
         assert_eq!(storage.f.get().0, expected);
     }
@@ -1476,10 +1747,16 @@ mod tests {
             hashed_inputs: u256_from_str_skip_mr("230508240750559904196809564625"),
             recipient_is_associated_token_account: true,
             solana_pay_transfer: false,
+            encrypted_memo: None,
         };
         let p = abc.public_signals_skip_mr();
-        let v = prepare_public_inputs_instructions(&p, TestVKey::public_inputs_count());
+        let v = prepare_public_inputs_instructions(&p, TestVKey::public_inputs_count(), 0);
         assert_eq!(v.len(), 3);
+
+        // A safety margin results in smaller rounds
+        let m = prepare_public_inputs_instructions(&p, TestVKey::public_inputs_count(), 700);
+        assert_eq!(m.len(), 6);
+        assert_eq!(m.iter().sum::<u32>(), v.iter().sum::<u32>());
     }
 
     #[test]
@@ -1489,7 +1766,18 @@ mod tests {
         assert_eq!(
             prepare_public_inputs_instructions(
                 &vec![[0; 32]; TestVKey::public_inputs_count()],
-                TestVKey::public_inputs_count()
+                TestVKey::public_inputs_count(),
+                0
+            ),
+            vec![expected]
+        );
+
+        // Zero inputs cost nothing, so even the maximum margin requires a single instruction
+        assert_eq!(
+            prepare_public_inputs_instructions(
+                &vec![[0; 32]; TestVKey::public_inputs_count()],
+                TestVKey::public_inputs_count(),
+                MAX_COMPUTE_UNIT_MARGIN
             ),
             vec![expected]
         );
@@ -1508,7 +1796,8 @@ mod tests {
 
         let mut result = None;
         for _ in 0..instruction_count {
-            result = verify_partial(&mut storage, vkey, COMPUTE_VERIFICATION_IX_COUNT - 1).unwrap();
+            result =
+                verify_partial(&mut storage, vkey, COMPUTE_VERIFICATION_IX_COUNT - 1, 0).unwrap();
         }
 
         result.unwrap()
@@ -1535,6 +1824,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_verify_with_syscalls() {
+        vkey!(vkey, TestVKey);
+
+        for (proofs, expected) in [(valid_proofs(), true), (invalid_proofs(), false)] {
+            for p in proofs {
+                zero_program_account!(mut storage, VerificationAccount);
+                setup_storage_account::<TestVKey>(&mut storage, p.proof, &p.public_inputs);
+
+                assert_matches!(verify_with_syscalls(&mut storage, &vkey, 0), Ok(None));
+                assert_matches!(
+                    verify_with_syscalls(&mut storage, &vkey, COMPUTE_VERIFICATION_IX_COUNT - 1),
+                    Ok(Some(v)) if v == expected
+                );
+
+                // Additional ix will result in error
+                assert_matches!(
+                    verify_with_syscalls(&mut storage, &vkey, COMPUTE_VERIFICATION_IX_COUNT - 1),
+                    Err(_)
+                );
+            }
+        }
+
+        // Proof not setup
+        zero_program_account!(mut storage, VerificationAccount);
+        assert_matches!(
+            verify_with_syscalls(&mut storage, &vkey, COMPUTE_VERIFICATION_IX_COUNT - 1),
+            Err(InvalidAccountState)
+        );
+    }
+
     #[test]
     fn test_verify_partial_too_many_calls() {
         let proof = valid_proofs()[0].proof;
@@ -1548,16 +1868,142 @@ mod tests {
         vkey!(vkey, TestVKey);
 
         for _ in 0..instruction_count {
-            verify_partial(&mut storage, &vkey, COMPUTE_VERIFICATION_IX_COUNT - 1).unwrap();
+            verify_partial(&mut storage, &vkey, COMPUTE_VERIFICATION_IX_COUNT - 1, 0).unwrap();
         }
 
         // Additional ix will result in error
         assert_matches!(
-            verify_partial(&mut storage, &vkey, COMPUTE_VERIFICATION_IX_COUNT - 1),
+            verify_partial(&mut storage, &vkey, COMPUTE_VERIFICATION_IX_COUNT - 1, 0),
+            Err(_)
+        );
+    }
+
+    fn miller_loop_output<VKey: VerifyingKeyInfo>(
+        proof: Proof,
+        public_inputs: &[U256],
+        vkey: &VerifyingKey,
+    ) -> Fq12 {
+        zero_program_account!(mut storage, VerificationAccount);
+        setup_storage_account::<VKey>(&mut storage, proof, public_inputs);
+        let instruction_count =
+            storage.get_prepare_inputs_instructions_count() as usize + COMBINED_MILLER_LOOP_IXS;
+
+        for _ in 0..instruction_count {
+            verify_partial(&mut storage, vkey, COMPUTE_VERIFICATION_IX_COUNT - 1, 0).unwrap();
+        }
+
+        assert_matches!(storage.get_step(), VerificationStep::FinalExponentiation);
+        storage.f.get().0
+    }
+
+    fn batch_verification(miller_loop_outputs: &[Fq12], vkey: &VerifyingKey) -> bool {
+        zero_program_account!(mut batch, BatchVerificationAccount);
+        zero_program_account!(mut leader, VerificationAccount);
+        batch.setup(0, 0);
+        for (i, f) in miller_loop_outputs.iter().enumerate() {
+            batch.add_proof(i as u8, *f);
+        }
+
+        loop {
+            let result = verify_batch_partial(
+                &mut batch,
+                &mut leader,
+                vkey,
+                COMPUTE_VERIFICATION_IX_COUNT - 1,
+                0,
+            )
+            .unwrap();
+
+            if let Some(result) = result {
+                return result;
+            }
+        }
+    }
+
+    #[test]
+    fn test_batch_verification_scalars() {
+        let outputs = [f(), f().square(), Fq12::one()];
+        let scalars = batch_verification_scalars(&outputs);
+
+        assert_eq!(scalars.len(), outputs.len());
+        for &scalar in &scalars {
+            assert_ne!(scalar, 0);
+            assert!(scalar < 1 << BATCH_SCALAR_BITS);
+        }
+        assert_ne!(scalars[0], scalars[1]);
+        assert_eq!(batch_verification_scalars(&outputs), scalars);
+
+        // Each scalar depends on all outputs
+        assert_ne!(batch_verification_scalars(&outputs[..2])[0], scalars[0]);
+    }
+
+    #[test]
+    fn test_batch_multi_exponentiation() {
+        vkey!(vkey, TestVKey);
+        let alpha_beta = vkey.alpha_beta();
+        let outputs = [f(), f().square(), f().inverse().unwrap()];
+
+        zero_program_account!(mut batch, BatchVerificationAccount);
+        batch.setup(0, 0);
+        for (i, f) in outputs.iter().enumerate() {
+            batch.add_proof(i as u8, *f);
+        }
+
+        let mut instructions = 0;
+        while batch.get_round() < BATCH_EXPONENT_BITS {
+            batch_multi_exponentiation(&mut batch, &alpha_beta, 0).unwrap();
+            instructions += 1;
+        }
+        assert!(instructions > 1);
+        assert!(instructions <= BATCH_EXPONENT_BITS);
+
+        let scalars = batch_verification_scalars(&outputs);
+        let expected_accumulator = outputs
+            .iter()
+            .zip(&scalars)
+            .fold(Fq12::one(), |acc, (f, r)| acc * f.pow([*r]));
+        assert_eq!(batch.accumulator.get().0, expected_accumulator);
+        assert_eq!(
+            batch.expected.get().0,
+            alpha_beta.pow([scalars.iter().sum::<u64>()])
+        );
+
+        // The computation is finished
+        assert_matches!(
+            batch_multi_exponentiation(&mut batch, &alpha_beta, 0),
             Err(_)
         );
     }
 
+    #[test]
+    fn test_verify_batch() {
+        vkey!(vkey, TestVKey);
+
+        let valid: Vec<Fq12> = valid_proofs()
+            .into_iter()
+            .map(|p| miller_loop_output::<TestVKey>(p.proof, &p.public_inputs, &vkey))
+            .collect();
+        let invalid: Vec<Fq12> = invalid_proofs()
+            .into_iter()
+            .map(|p| miller_loop_output::<TestVKey>(p.proof, &p.public_inputs, &vkey))
+            .collect();
+
+        assert!(batch_verification(&valid, &vkey));
+        assert!(batch_verification(&valid[..1], &vkey));
+
+        // A single invalid proof invalidates the batch
+        let mut outputs = valid.clone();
+        outputs.push(invalid[0]);
+        assert!(!batch_verification(&outputs, &vkey));
+
+        // Invalid proofs cannot cancel each other out
+        let x = invalid[0];
+        assert!(!batch_verification(
+            &[valid[0] * x, x.inverse().unwrap()],
+            &vkey
+        ));
+    }
+
     // https://github.com/arkworks-rs/algebra/blob/6ea310ef09f8b7510ce947490919ea6229bbecd6/ec/src/models/bn/mod.rs#L59
     fn reference_ell(f: Fq12, coeffs: (Fq2, Fq2, Fq2), p: G1Affine) -> Fq12 {
         let mut c0: Fq2 = coeffs.0;
@@ -1,14 +1,19 @@
 use crate::fields::{Wrap, G1A, G2A};
+use crate::types::U256;
 use ark_bn254::{Fq12, Fq2, G1Affine, G1Projective};
 use ark_ec::AffineCurve;
 use ark_ff::Zero;
 use borsh::BorshDeserialize;
 use elusiv_types::BorshSerDeSized;
+use solana_program::hash::hashv;
 
 pub trait VerifyingKeyInfo {
     const VKEY_ID: u32;
     const PUBLIC_INPUTS_COUNT: u32;
 
+    /// Checksum of the verifying key source (see [`vkey_checksum_step`])
+    const CHECKSUM: U256;
+
     #[cfg(feature = "elusiv-client")]
     const DIRECTORY: &'static str;
 
@@ -43,12 +48,13 @@ pub trait VerifyingKeyInfo {
 }
 
 macro_rules! verification_key_info {
-    ($ident: ident, $id: expr, $public_inputs_count: expr, $dir: literal) => {
+    ($ident: ident, $id: expr, $public_inputs_count: expr, $dir: literal, $checksum: expr) => {
         pub struct $ident;
 
         impl VerifyingKeyInfo for $ident {
             const VKEY_ID: u32 = $id;
             const PUBLIC_INPUTS_COUNT: u32 = $public_inputs_count;
+            const CHECKSUM: U256 = $checksum;
 
             #[cfg(feature = "elusiv-client")]
             const DIRECTORY: &'static str = $dir;
@@ -66,11 +72,104 @@ macro_rules! verification_key_info {
     };
 }
 
-verification_key_info!(SendQuadraVKey, 0, 14, "send_quadra");
-verification_key_info!(MigrateUnaryVKey, 1, 7, "migrate_unary");
+verification_key_info!(
+    SendQuadraVKey,
+    0,
+    14,
+    "send_quadra",
+    [
+        222, 152, 71, 16, 179, 133, 106, 42, 219, 199, 47, 165, 111, 248, 190, 46, 158, 54, 69,
+        111, 242, 99, 150, 79, 221, 83, 56, 206, 126, 134, 6, 96
+    ]
+);
+verification_key_info!(
+    MigrateUnaryVKey,
+    1,
+    7,
+    "migrate_unary",
+    [
+        138, 61, 199, 246, 203, 200, 85, 137, 3, 106, 53, 8, 131, 198, 118, 79, 201, 144, 6, 21,
+        143, 163, 87, 93, 243, 192, 63, 34, 129, 61, 35, 113
+    ]
+);
 
 #[cfg(test)]
-verification_key_info!(TestVKey, 2, 14, "test");
+verification_key_info!(
+    TestVKey,
+    2,
+    14,
+    "test",
+    [
+        58, 180, 193, 77, 4, 89, 100, 165, 113, 63, 54, 152, 237, 168, 212, 141, 78, 25, 172, 64,
+        120, 178, 101, 102, 235, 248, 160, 193, 56, 62, 153, 101
+    ]
+);
+
+/// Returns the expected checksum of the verifying key with `vkey_id`
+pub fn vkey_checksum(vkey_id: u32) -> Option<U256> {
+    match vkey_id {
+        SendQuadraVKey::VKEY_ID => Some(SendQuadraVKey::CHECKSUM),
+        MigrateUnaryVKey::VKEY_ID => Some(MigrateUnaryVKey::CHECKSUM),
+
+        #[cfg(test)]
+        TestVKey::VKEY_ID => Some(TestVKey::CHECKSUM),
+
+        _ => None,
+    }
+}
+
+/// Returns the public inputs count of the built-in verifying key with `vkey_id`
+pub fn vkey_public_inputs_count(vkey_id: u32) -> Option<u32> {
+    match vkey_id {
+        SendQuadraVKey::VKEY_ID => Some(SendQuadraVKey::PUBLIC_INPUTS_COUNT),
+        MigrateUnaryVKey::VKEY_ID => Some(MigrateUnaryVKey::PUBLIC_INPUTS_COUNT),
+
+        #[cfg(test)]
+        TestVKey::VKEY_ID => Some(TestVKey::PUBLIC_INPUTS_COUNT),
+
+        _ => None,
+    }
+}
+
+/// Size of the chunks of a verifying key source hashed by a single [`vkey_checksum_step`]
+pub const VKEY_CHECKSUM_CHUNK_SIZE: usize = 512 * 1024;
+
+/// The checksum of a verifying key source is computed by chaining the hashes of its [`VKEY_CHECKSUM_CHUNK_SIZE`] chunks
+/// - the checksum of the first chunk is chained with zero
+pub fn vkey_checksum_step(checksum: &U256, chunk: &[u8]) -> U256 {
+    hashv(&[checksum, chunk]).to_bytes()
+}
+
+#[cfg(feature = "elusiv-client")]
+pub fn vkey_source_checksum(source: &[u8]) -> U256 {
+    source
+        .chunks(VKEY_CHECKSUM_CHUNK_SIZE)
+        .fold([0; 32], |checksum, chunk| {
+            vkey_checksum_step(&checksum, chunk)
+        })
+}
+
+/// The sections of a verifying key source (see [`VerifyingKey::new`])
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub enum VKeySourceSection {
+    AlphaBeta,
+    GammaAbcBase,
+
+    /// The precomputed `gamma_abc` windows of a single public input
+    GammaAbc {
+        public_input: usize,
+    },
+
+    GammaNeg,
+    DeltaNeg,
+
+    /// `alpha`, `beta`, `gamma` and `delta`
+    Points,
+
+    /// Past the end of the source
+    Complete,
+}
 
 /// A Groth16 verifying key with precomputed values
 pub struct VerifyingKey<'a> {
@@ -124,6 +223,40 @@ impl<'a> VerifyingKey<'a> {
             + 3 * G2A::SIZE
     }
 
+    /// The [`VKeySourceSection`] containing the byte at `position` of a source with `public_inputs_count` public inputs
+    pub const fn source_section(public_inputs_count: usize, position: usize) -> VKeySourceSection {
+        let mut offset = Wrap::<Fq12>::SIZE;
+        if position < offset {
+            return VKeySourceSection::AlphaBeta;
+        }
+
+        offset += G1A::SIZE;
+        if position < offset {
+            return VKeySourceSection::GammaAbcBase;
+        }
+
+        if position < offset + Self::gamma_abc_size(public_inputs_count) {
+            let public_input = (position - offset) / Self::gamma_abc_size(1);
+            return VKeySourceSection::GammaAbc { public_input };
+        }
+
+        offset += Self::gamma_abc_size(public_inputs_count) + Self::COEFFS_ARRAY_SIZE;
+        if position < offset {
+            return VKeySourceSection::GammaNeg;
+        }
+
+        offset += Self::COEFFS_ARRAY_SIZE;
+        if position < offset {
+            return VKeySourceSection::DeltaNeg;
+        }
+
+        if position < Self::source_size(public_inputs_count) {
+            return VKeySourceSection::Points;
+        }
+
+        VKeySourceSection::Complete
+    }
+
     pub fn alpha_beta(&self) -> Fq12 {
         let slice = &self.source[..Wrap::<Fq12>::SIZE];
         Wrap::try_from_slice(slice).unwrap().0
@@ -166,7 +299,6 @@ impl<'a> VerifyingKey<'a> {
         Wrap::try_from_slice(slice).unwrap().0
     }
 
-    #[cfg(feature = "elusiv-client")]
     pub fn alpha(&self) -> G1Affine {
         let offset =
             Wrap::<Fq12>::SIZE + G1A::SIZE + self.gamma_abc_size + 2 * Self::COEFFS_ARRAY_SIZE;
@@ -174,7 +306,6 @@ impl<'a> VerifyingKey<'a> {
         G1A::try_from_slice(slice).unwrap().0
     }
 
-    #[cfg(feature = "elusiv-client")]
     pub fn beta(&self) -> ark_bn254::G2Affine {
         let offset = Wrap::<Fq12>::SIZE
             + G1A::SIZE
@@ -185,7 +316,6 @@ impl<'a> VerifyingKey<'a> {
         G2A::try_from_slice(slice).unwrap().0
     }
 
-    #[cfg(feature = "elusiv-client")]
     pub fn gamma(&self) -> ark_bn254::G2Affine {
         let offset = Wrap::<Fq12>::SIZE
             + G1A::SIZE
@@ -197,7 +327,6 @@ impl<'a> VerifyingKey<'a> {
         G2A::try_from_slice(slice).unwrap().0
     }
 
-    #[cfg(feature = "elusiv-client")]
     pub fn delta(&self) -> ark_bn254::G2Affine {
         let offset = Wrap::<Fq12>::SIZE
             + G1A::SIZE
@@ -326,6 +455,74 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_source_section() {
+        let count = TestVKey::public_inputs_count();
+        let base = Wrap::<Fq12>::SIZE + G1A::SIZE;
+        let gamma_abc_size = VerifyingKey::gamma_abc_size(1);
+
+        assert_eq!(
+            VerifyingKey::source_section(count, 0),
+            VKeySourceSection::AlphaBeta
+        );
+        assert_eq!(
+            VerifyingKey::source_section(count, base - 1),
+            VKeySourceSection::GammaAbcBase
+        );
+
+        for public_input in 0..count {
+            let start = base + public_input * gamma_abc_size;
+            for position in [start, start + gamma_abc_size - 1] {
+                assert_eq!(
+                    VerifyingKey::source_section(count, position),
+                    VKeySourceSection::GammaAbc { public_input }
+                );
+            }
+        }
+
+        let offset = base + VerifyingKey::gamma_abc_size(count);
+        assert_eq!(
+            VerifyingKey::source_section(count, offset),
+            VKeySourceSection::GammaNeg
+        );
+        assert_eq!(
+            VerifyingKey::source_section(count, offset + VerifyingKey::COEFFS_ARRAY_SIZE),
+            VKeySourceSection::DeltaNeg
+        );
+
+        let size = VerifyingKey::source_size(count);
+        assert_eq!(
+            VerifyingKey::source_section(count, size - 1),
+            VKeySourceSection::Points
+        );
+        assert_eq!(
+            VerifyingKey::source_section(count, size),
+            VKeySourceSection::Complete
+        );
+    }
+
+    #[test]
+    fn test_vkey_checksum() {
+        assert_eq!(
+            vkey_source_checksum(&SendQuadraVKey::verifying_key_source()),
+            SendQuadraVKey::CHECKSUM
+        );
+        assert_eq!(
+            vkey_source_checksum(&MigrateUnaryVKey::verifying_key_source()),
+            MigrateUnaryVKey::CHECKSUM
+        );
+        assert_eq!(
+            vkey_source_checksum(&TestVKey::verifying_key_source()),
+            TestVKey::CHECKSUM
+        );
+
+        assert_eq!(
+            vkey_checksum(SendQuadraVKey::VKEY_ID),
+            Some(SendQuadraVKey::CHECKSUM)
+        );
+        assert_eq!(vkey_checksum(3), None);
+    }
+
     #[test]
     fn test_send_quadra_vkey() {
         test_vkey::<SendQuadraVKey>()
@@ -0,0 +1,138 @@
+//! Deterministic derivation of the note secrets of a wallet from a single seed
+//!
+//! # Notes
+//!
+//! All secrets are derived as `sha256(domain || seed || index)` reduced into the scalar field:
+//! - the nullifier-key is derived once per seed,
+//! - the nonce of the n-th base-commitment of the wallet is derived with `index = n`.
+//!
+//! A wallet cannot own more notes than commitments have been created, so the `total_commitment_count` of the
+//! [`crate::state::summary::StateSummaryAccount`] is used as an upper bound (counter hint) when recovering from a seed.
+
+use crate::fields::fr_to_u256_le_repr;
+use crate::types::U256;
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+
+const NULLIFIER_KEY_DOMAIN: &[u8] = b"elusiv-nullifier-key";
+const COMMITMENT_NONCE_DOMAIN: &[u8] = b"elusiv-commitment-nonce";
+
+/// Number of consecutive unused nonces after which a recovery scan is complete
+pub const DEFAULT_RECOVERY_GAP_LIMIT: u64 = 64;
+
+fn derive_scalar(domain: &[u8], seed: &U256, index: u64) -> U256 {
+    let hash = solana_program::hash::hashv(&[domain, seed, &index.to_le_bytes()]).to_bytes();
+    fr_to_u256_le_repr(&Fr::from_le_bytes_mod_order(&hash))
+}
+
+/// The nullifier-key of all notes of the wallet
+pub fn derive_nullifier_key(seed: &U256) -> U256 {
+    derive_scalar(NULLIFIER_KEY_DOMAIN, seed, 0)
+}
+
+/// The nonce of the `index`-th base-commitment of the wallet
+pub fn derive_commitment_nonce(seed: &U256, index: u64) -> U256 {
+    derive_scalar(COMMITMENT_NONCE_DOMAIN, seed, index)
+}
+
+/// Iterates over the commitment nonces of a wallet during a recovery
+/// - the scan is complete once `gap_limit` consecutive nonces are unused or the counter hint is reached
+pub struct RecoveryScan {
+    seed: U256,
+    next_index: u64,
+    last_used_index: Option<u64>,
+    gap_limit: u64,
+    total_commitment_count: u64,
+}
+
+impl RecoveryScan {
+    pub fn new(seed: U256, gap_limit: u64, total_commitment_count: u64) -> Self {
+        RecoveryScan {
+            seed,
+            next_index: 0,
+            last_used_index: None,
+            gap_limit,
+            total_commitment_count,
+        }
+    }
+
+    /// Marks the nonce with `index` as used (a base-commitment with this nonce has been found on-chain)
+    pub fn mark_used(&mut self, index: u64) {
+        self.last_used_index = Some(self.last_used_index.map_or(index, |i| i.max(index)));
+    }
+
+    /// The index for the next base-commitment of the wallet after the recovery
+    pub fn next_unused_index(&self) -> u64 {
+        self.last_used_index.map_or(0, |i| i + 1)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.next_index >= self.total_commitment_count
+            || self.next_index >= self.next_unused_index().saturating_add(self.gap_limit)
+    }
+}
+
+impl Iterator for RecoveryScan {
+    /// Index and nonce
+    type Item = (u64, U256);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_complete() {
+            return None;
+        }
+
+        let index = self.next_index;
+        self.next_index += 1;
+
+        Some((index, derive_commitment_nonce(&self.seed, index)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields::{is_element_scalar_field, u256_to_big_uint};
+
+    #[test]
+    fn test_derivation() {
+        let seed = [1; 32];
+
+        assert_eq!(derive_nullifier_key(&seed), derive_nullifier_key(&seed));
+        assert_ne!(derive_nullifier_key(&seed), derive_nullifier_key(&[2; 32]));
+        assert_ne!(
+            derive_commitment_nonce(&seed, 0),
+            derive_commitment_nonce(&seed, 1)
+        );
+        assert_ne!(
+            derive_commitment_nonce(&seed, 0),
+            derive_nullifier_key(&seed)
+        );
+
+        for index in 0..16 {
+            assert!(is_element_scalar_field(u256_to_big_uint(
+                &derive_commitment_nonce(&seed, index)
+            )));
+        }
+    }
+
+    #[test]
+    fn test_recovery_scan() {
+        let seed = [1; 32];
+
+        // Gap limit
+        let mut scan = RecoveryScan::new(seed, 2, 100);
+        assert_eq!(scan.next(), Some((0, derive_commitment_nonce(&seed, 0))));
+        scan.mark_used(0);
+        assert_eq!(scan.next().unwrap().0, 1);
+        assert_eq!(scan.next().unwrap().0, 2);
+        scan.mark_used(2);
+        assert_eq!(scan.next().unwrap().0, 3);
+        assert_eq!(scan.next().unwrap().0, 4);
+        assert_eq!(scan.next(), None);
+        assert_eq!(scan.next_unused_index(), 3);
+
+        // Counter hint
+        let scan = RecoveryScan::new(seed, DEFAULT_RECOVERY_GAP_LIMIT, 3);
+        assert_eq!(scan.count(), 3);
+    }
+}
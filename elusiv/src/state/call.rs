@@ -0,0 +1,20 @@
+use elusiv_derive::PDAAccount;
+use solana_program::{pubkey, pubkey::Pubkey};
+
+/// Programs that can be called with withdrawn funds
+pub const WITHDRAW_AND_CALL_PROGRAMS: [Pubkey; 2] = [
+    // Marinade liquid staking
+    pubkey!("MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD"),
+    // Solend lending
+    pubkey!("So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo"),
+];
+
+pub fn is_withdraw_and_call_program(program_id: &Pubkey) -> bool {
+    WITHDRAW_AND_CALL_PROGRAMS.contains(program_id)
+}
+
+/// Transient system-owned PDA receiving the funds of a withdraw-and-call (lamports only)
+/// - the PDA is derived from the hash of the call, so using it as the recipient of a send binds the call into the proof's public inputs
+/// - the PDA signs the CPI, which allows the called program to transfer the funds
+#[derive(PDAAccount)]
+pub struct WithdrawAndCallAuthority;
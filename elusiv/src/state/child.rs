@@ -0,0 +1,158 @@
+use super::nullifier::{nullifier_account_offsets, NullifierAccount};
+use super::program_account::{Lifecycle, PDAAccount, PDAAccountData, PDAOffset, ParentAccount};
+use super::storage::{token_pool_offset, StorageAccount};
+use crate::bytes::BorshSerDeSized;
+use crate::error::ElusivError;
+use crate::macros::{assert_account_size, elusiv_account, guard, BorshSerDeSized};
+use crate::token::{elusiv_token, TokenID};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::program_error::ProgramError;
+use solana_program::pubkey::Pubkey;
+
+/// The parent-account of a child-account created with [`crate::processor::create_child_account`]
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum ChildAccountParent {
+    Storage { token_id: TokenID },
+    Nullifier { token_id: TokenID, mt_index: u32 },
+}
+
+impl ChildAccountParent {
+    /// The PDA of the parent-account
+    pub fn pubkey(&self) -> Result<Pubkey, ProgramError> {
+        match *self {
+            ChildAccountParent::Storage { token_id } => {
+                Ok(StorageAccount::find(pool_offset(token_id)?).0)
+            }
+            ChildAccountParent::Nullifier { token_id, mt_index } => {
+                pool_offset(token_id)?;
+                Ok(NullifierAccount::find_with_offsets(
+                    None,
+                    &nullifier_account_offsets(token_id, mt_index),
+                )
+                .0)
+            }
+        }
+    }
+
+    /// The exact size of the child-account with `child_index`
+    pub fn child_account_size(&self, child_index: u32) -> Result<usize, ProgramError> {
+        let child_index = child_index as usize;
+        match *self {
+            ChildAccountParent::Storage { token_id } => {
+                pool_offset(token_id)?;
+                parent_child_account_size::<StorageAccount>(child_index)
+            }
+            ChildAccountParent::Nullifier { token_id, .. } => {
+                pool_offset(token_id)?;
+                parent_child_account_size::<NullifierAccount>(child_index)
+            }
+        }
+    }
+}
+
+/// The exact size of the child-account with `child_index` of any [`ParentAccount`]
+/// - the bounds and sizes are derived from [`ParentAccount::COUNT`] and [`ParentAccount::child_account_size`], so no parent-account type is assumed
+fn parent_child_account_size<'a, 'b, 't, T: ParentAccount<'a, 'b, 't>>(
+    child_index: usize,
+) -> Result<usize, ProgramError> {
+    guard!(child_index < T::COUNT, ElusivError::InvalidInstructionData);
+    Ok(T::child_account_size(child_index))
+}
+
+/// The pool offset of a supported `token_id`
+fn pool_offset(token_id: TokenID) -> Result<PDAOffset, ProgramError> {
+    elusiv_token(token_id)?;
+    Ok(token_pool_offset(token_id))
+}
+
+/// Records the creation of a child-account (the PDA is derived from the child-account's pubkey)
+/// - `payer` funded the rent of the child-account and receives it on closing
+/// - `parent` and `child_index` tag the child-account for the validation when it is enabled
+#[elusiv_account(eager_type: true)]
+pub struct ChildAccountRecordAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub payer: Pubkey,
+    pub parent: Pubkey,
+    pub child_index: u32,
+}
+
+assert_account_size!(ChildAccountRecordAccount, 70);
+
+impl<'a> Lifecycle for ChildAccountRecordAccount<'a> {
+    const CLOSEABLE: bool = true;
+}
+
+impl<'a> ChildAccountRecordAccount<'a> {
+    /// Whether the child-account has been created for the `parent` at `child_index`
+    pub fn is_child_of(&self, parent: &Pubkey, child_index: u32) -> bool {
+        self.get_parent() == *parent && self.get_child_index() == child_index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::nullifier::NullifierChildAccount;
+    use crate::state::program_account::{ProgramAccount, SizedAccount};
+    use crate::state::storage::StorageChildAccount;
+    use crate::token::USDC_TOKEN_ID;
+
+    #[test]
+    fn test_child_account_parent() {
+        let storage = ChildAccountParent::Storage { token_id: 0 };
+        assert_eq!(storage.pubkey().unwrap(), StorageAccount::find(None).0);
+        assert_eq!(
+            storage.child_account_size(0).unwrap(),
+            StorageChildAccount::SIZE
+        );
+        assert_eq!(
+            storage
+                .child_account_size(StorageAccount::COUNT as u32 - 1)
+                .unwrap(),
+            StorageAccount::child_account_size(StorageAccount::COUNT - 1)
+        );
+        assert!(storage
+            .child_account_size(StorageAccount::COUNT as u32)
+            .is_err());
+
+        let nullifier = ChildAccountParent::Nullifier {
+            token_id: USDC_TOKEN_ID,
+            mt_index: 1,
+        };
+        assert_eq!(
+            nullifier.pubkey().unwrap(),
+            NullifierAccount::find_with_offsets(None, &nullifier_account_offsets(USDC_TOKEN_ID, 1))
+                .0
+        );
+        assert_eq!(
+            nullifier.child_account_size(0).unwrap(),
+            NullifierChildAccount::SIZE
+        );
+
+        assert!(nullifier
+            .child_account_size(NullifierAccount::COUNT as u32)
+            .is_err());
+
+        let unsupported = ChildAccountParent::Storage { token_id: u16::MAX };
+        assert!(unsupported.pubkey().is_err());
+        assert!(unsupported.child_account_size(0).is_err());
+    }
+
+    #[test]
+    fn test_child_account_record_is_child_of() {
+        let mut data = vec![0; ChildAccountRecordAccount::SIZE];
+        let mut record = ChildAccountRecordAccount::new(&mut data).unwrap();
+        let parent = Pubkey::new_unique();
+
+        record.set_parent(&parent);
+        record.set_child_index(&3);
+
+        assert!(record.is_child_of(&parent, 3));
+        assert!(!record.is_child_of(&parent, 2));
+        assert!(!record.is_child_of(&Pubkey::new_unique(), 3));
+    }
+}
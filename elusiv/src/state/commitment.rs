@@ -1,12 +1,13 @@
 use crate::bytes::{contains, usize_as_u32_safe};
 use crate::commitment::poseidon_hash::BinarySpongeHashingState;
 use crate::commitment::{commitments_per_batch, MAX_HT_SIZE, MT_HEIGHT};
+use crate::computation::PartialComputationDriver;
 use crate::error::ElusivError;
 use crate::fields::{fr_to_u256_le, u256_to_fr_skip_mr};
-use crate::macros::{elusiv_account, guard, two_pow};
+use crate::macros::{assert_account_size, elusiv_account, guard, two_pow};
 use crate::processor::BaseCommitmentHashRequest;
-use crate::state::program_account::PDAAccountData;
-use crate::state::storage::{StorageAccount, HISTORY_ARRAY_SIZE};
+use crate::state::program_account::{Lifecycle, PDAAccountData};
+use crate::state::storage::StorageAccount;
 use crate::types::U256;
 use ark_bn254::Fr;
 use ark_ff::{BigInteger256, PrimeField};
@@ -21,14 +22,34 @@ pub struct BaseCommitmentHashingAccount {
 
     pub instruction: u32,
     pub(crate) round: u32,
+    pub last_update_slot: u64,
 
     pub fee_version: u32,
     pub fee_payer: U256,
     pub is_active: bool,
 
-    token_id: u16,
+    pub token_id: u16,
     pub state: BinarySpongeHashingState,
     pub min_batching_rate: u32,
+
+    /// Repayment of the fee-payer (see [`BaseCommitmentHashRequest::fee_payer_repayment`])
+    pub fee_payer_repayment: u64,
+
+    /// Receives the `fee_payer_repayment` (the fee-payer's token-account, or the fee-payer itself for lamports)
+    pub fee_payer_account: U256,
+
+    /// The commitment can only be enqueued for insertion into the MT after the anonymity-delay has elapsed
+    pub min_finalization_slot: u64,
+
+    /// The commitment is enqueued into the priority lane of the commitment queue
+    pub is_priority: bool,
+}
+
+assert_account_size!(BaseCommitmentHashingAccount, 206);
+
+impl<'a> Lifecycle for BaseCommitmentHashingAccount<'a> {
+    const CLOSEABLE: bool = true;
+    const REUSABLE: bool = true;
 }
 
 impl<'a> BaseCommitmentHashingAccount<'a> {
@@ -36,21 +57,29 @@ impl<'a> BaseCommitmentHashingAccount<'a> {
         &mut self,
         request: BaseCommitmentHashRequest,
         fee_payer: U256,
+        slot: u64,
     ) -> Result<(), ProgramError> {
         self.set_is_active(&true);
-        self.set_instruction(&0);
-        self.set_round(&0);
+        self.start_computation(slot);
         self.set_fee_payer(&fee_payer);
+        self.set_fee_payer_account(&fee_payer);
         self.set_fee_version(&request.fee_version);
 
         self.set_min_batching_rate(&request.min_batching_rate);
         self.set_token_id(&request.token_id);
+        self.set_fee_payer_repayment(&request.fee_payer_repayment);
+        self.set_min_finalization_slot(&slot.saturating_add(request.anonymity_delay_slots));
+        self.set_is_priority(&request.is_priority);
+        let amount = request
+            .amount
+            .checked_sub(request.fee_payer_repayment)
+            .ok_or(ElusivError::InvalidAmount)?;
 
         // Reset hashing state
         self.set_state(&BinarySpongeHashingState::new(
             u256_to_fr_skip_mr(&request.base_commitment.reduce()),
             Fr::from_repr(BigInteger256([
-                request.amount,
+                amount,
                 request.token_id as u64 + ((request.commitment_index as u64) << 16),
                 0,
                 0,
@@ -64,6 +93,8 @@ impl<'a> BaseCommitmentHashingAccount<'a> {
 }
 
 /// Account used for computing the hashes of a MT
+/// - a batch of `2^batching_rate` commitments is inserted at once: the batch's hash-sub-tree (HT) is hashed first, then the path from the HT-root to the MT-root
+/// - a batch therefore costs `hash_count_per_batch(batching_rate)` hashes instead of `2^batching_rate * MT_HEIGHT` for single-leaf insertions
 #[elusiv_account(partial_computation: true, eager_type: true)]
 pub struct CommitmentHashingAccount {
     #[no_getter]
@@ -72,6 +103,7 @@ pub struct CommitmentHashingAccount {
 
     pub instruction: u32,
     pub(crate) round: u32,
+    pub last_update_slot: u64,
 
     pub fee_version: u32,
     pub is_active: bool,
@@ -91,13 +123,21 @@ pub struct CommitmentHashingAccount {
     pub hash_tree: [U256; MAX_HT_SIZE],
 }
 
+assert_account_size!(CommitmentHashingAccount, 2404);
+
+impl<'a> Lifecycle for CommitmentHashingAccount<'a> {}
+
 impl<'a> CommitmentHashingAccount<'a> {
     /// Called before reset, sets the siblings
-    pub fn setup(&mut self, ordering: u32, siblings: &[U256]) -> Result<(), ProgramError> {
+    pub fn setup(
+        &mut self,
+        ordering: u32,
+        siblings: &[U256],
+        slot: u64,
+    ) -> Result<(), ProgramError> {
         guard!(!self.get_is_active(), ElusivError::AccountCannotBeReset);
         self.set_setup(&true);
-        self.set_instruction(&0);
-        self.set_round(&0);
+        self.start_computation(slot);
         self.set_ordering(&ordering);
         self.set_finalization_ix(&0);
 
@@ -251,11 +291,7 @@ impl<'a> CommitmentHashingAccount<'a> {
             );
 
             // This inserts the new root into the `active_mt_root_history`
-            storage_account.set_active_mt_root_history(
-                ordering as usize % HISTORY_ARRAY_SIZE,
-                &storage_account.get_root().unwrap(),
-            );
-            storage_account.set_mt_roots_count(&(storage_account.get_mt_roots_count() + 1));
+            storage_account.push_root_history(&storage_account.get_root().unwrap());
         }
     }
 }
@@ -317,6 +353,8 @@ macro_rules! buffer_account {
 
 buffer_account!(BaseCommitmentBufferAccount, U256, 128);
 
+impl<'a> Lifecycle for BaseCommitmentBufferAccount<'a> {}
+
 // buffer_account!(CommitmentBufferAccount, U256, 128);
 
 #[cfg(test)]
@@ -339,6 +377,9 @@ pub fn base_commitment_request(
         token_id,
         fee_version,
         min_batching_rate,
+        fee_payer_repayment: 0,
+        anonymity_delay_slots: 0,
+        is_priority: false,
     }
 }
 
@@ -384,7 +425,7 @@ mod tests {
         }
         let fee_version = 0;
 
-        account.setup(ordering, &siblings).unwrap();
+        account.setup(ordering, &siblings, 0).unwrap();
         account
             .reset(batching_rate, fee_version, &commitments)
             .unwrap();
@@ -581,10 +622,13 @@ mod tests {
             commitment: RawU256::new([2; 32]),
             fee_version: 444,
             min_batching_rate: 555,
+            fee_payer_repayment: 0,
+            anonymity_delay_slots: 0,
+            is_priority: false,
         };
         let fee_payer = [6; 32];
 
-        account.setup(request.clone(), fee_payer).unwrap();
+        account.setup(request.clone(), fee_payer, 0).unwrap();
 
         assert_eq!(
             account.get_state().0,
@@ -599,6 +643,33 @@ mod tests {
         assert_eq!(account.get_min_batching_rate(), request.min_batching_rate);
         assert_eq!(account.get_instruction(), 0);
         assert!(account.get_is_active());
+
+        // Fee-payer repayment is excluded from the commitment amount
+        zero_program_account!(mut account, BaseCommitmentHashingAccount);
+        let mut fronted_request = request.clone();
+        fronted_request.amount = 433;
+        fronted_request.fee_payer_repayment = 100;
+        account.setup(fronted_request, fee_payer, 0).unwrap();
+
+        assert_eq!(
+            account.get_state().0[2],
+            Fr::from_str("148698281640969010098995533").unwrap()
+        );
+        assert_eq!(account.get_fee_payer_repayment(), 100);
+        assert_eq!(account.get_fee_payer_account(), fee_payer);
+
+        // Anonymity-delay
+        zero_program_account!(mut account, BaseCommitmentHashingAccount);
+        let mut delayed_request = request.clone();
+        delayed_request.anonymity_delay_slots = 100;
+        account.setup(delayed_request, fee_payer, 7).unwrap();
+        assert_eq!(account.get_min_finalization_slot(), 107);
+
+        // Repayment exceeding the amount
+        zero_program_account!(mut account, BaseCommitmentHashingAccount);
+        let mut invalid_request = request;
+        invalid_request.fee_payer_repayment = 334;
+        assert_matches!(account.setup(invalid_request, fee_payer, 0), Err(_));
     }
 
     #[test]
@@ -620,7 +691,7 @@ mod tests {
         let batching_rate = 4;
         let ordering = 555;
 
-        account.setup(ordering, &siblings).unwrap();
+        account.setup(ordering, &siblings, 0).unwrap();
         account
             .reset(batching_rate, fee_version, &commitments)
             .unwrap();
@@ -638,11 +709,11 @@ mod tests {
         assert!(account.get_is_active());
 
         // Second reset should fail
-        assert_matches!(account.setup(ordering, &siblings), Err(_));
+        assert_matches!(account.setup(ordering, &siblings, 0), Err(_));
 
         // Second reset now allowed
         account.set_is_active(&false);
-        account.setup(ordering, &siblings).unwrap();
+        account.setup(ordering, &siblings, 0).unwrap();
         account
             .reset(batching_rate, fee_version, &commitments)
             .unwrap();
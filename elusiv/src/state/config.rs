@@ -0,0 +1,65 @@
+use super::program_account::{Lifecycle, PDAAccountData};
+use super::queue::{CommitmentQueue, RingQueue};
+use crate::commitment::MAX_COMMITMENT_BATCHING_RATE;
+use crate::macros::{assert_account_size, elusiv_account};
+
+/// Runtime limits of the program, bounded by the sizes the program has been built with
+/// - the MT height, the queue sizes and the maximum batching rate define the account layouts and the circuits, so they stay compile-time constants
+/// - a zero limit uses the built-in constant, so the same binary can run with smaller limits (e.g. on testnet)
+#[elusiv_account(eager_type: true)]
+pub struct ConfigAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Amount of commitments in a commitment queue from which on new base-commitments are spilled (zero uses [`CommitmentQueue::CAPACITY`])
+    /// - the remaining space is kept for the commitments of finalized sends
+    pub commitment_queue_capacity: u32,
+
+    /// Maximum commitment batching rate the governance can activate (zero uses [`MAX_COMMITMENT_BATCHING_RATE`])
+    pub max_commitment_batching_rate: u32,
+}
+
+assert_account_size!(ConfigAccount, 10);
+
+impl<'a> Lifecycle for ConfigAccount<'a> {}
+
+impl<'a> ConfigAccount<'a> {
+    pub fn effective_commitment_queue_capacity(&self) -> u32 {
+        match self.get_commitment_queue_capacity() {
+            0 => CommitmentQueue::CAPACITY,
+            capacity => capacity,
+        }
+    }
+
+    pub fn effective_max_commitment_batching_rate(&self) -> u32 {
+        match self.get_max_commitment_batching_rate() {
+            0 => MAX_COMMITMENT_BATCHING_RATE as u32,
+            rate => rate,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+
+    #[test]
+    fn test_effective_limits() {
+        zero_program_account!(mut config, ConfigAccount);
+        assert_eq!(
+            config.effective_commitment_queue_capacity(),
+            CommitmentQueue::CAPACITY
+        );
+        assert_eq!(
+            config.effective_max_commitment_batching_rate(),
+            MAX_COMMITMENT_BATCHING_RATE as u32
+        );
+
+        config.set_commitment_queue_capacity(&16);
+        config.set_max_commitment_batching_rate(&2);
+        assert_eq!(config.effective_commitment_queue_capacity(), 16);
+        assert_eq!(config.effective_max_commitment_batching_rate(), 2);
+    }
+}
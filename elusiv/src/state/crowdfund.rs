@@ -0,0 +1,222 @@
+use super::program_account::{Lifecycle, PDAAccountData};
+use crate::error::ElusivError;
+use crate::macros::{assert_account_size, elusiv_account, guard};
+use crate::types::U256;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+/// Maximum number of distinct funders of a single [`CrowdfundAccount`]
+pub const MAX_CROWDFUND_FUNDERS: usize = 16;
+
+/// Escrow collecting the contributions of multiple funders for a single commitment (lamports only)
+/// - funders transfer lamports to the account until the `target_amount` is reached or the `deadline_slot` has passed
+/// - once the `target_amount` is reached, the `raised_amount` is hashed into a commitment from `base_commitment`
+/// - each contribution needs to be at least the [`CrowdfundAccount::min_contribution`], so that dust contributions can't occupy all funder slots
+///
+/// # Notes
+///
+/// Once the `deadline_slot` has passed without reaching the `target_amount`, the crowdfund can only be refunded:
+/// funders withdraw their contributions and the `creator` closes the account afterwards.
+#[elusiv_account(eager_type: true)]
+pub struct CrowdfundAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Rents the account and receives the rent once the crowdfund is finalized
+    pub creator: Pubkey,
+    pub base_commitment: U256,
+    pub target_amount: u64,
+    pub raised_amount: u64,
+    pub deadline_slot: u64,
+
+    /// Contributions (in lamports) of the individual funders (refundable after the `deadline_slot`)
+    funders: [Pubkey; MAX_CROWDFUND_FUNDERS],
+    contributions: [u64; MAX_CROWDFUND_FUNDERS],
+}
+
+assert_account_size!(CrowdfundAccount, 730);
+
+impl<'a> Lifecycle for CrowdfundAccount<'a> {
+    const CLOSEABLE: bool = true;
+    const REUSABLE: bool = true;
+}
+
+impl<'a> CrowdfundAccount<'a> {
+    pub fn is_target_reached(&self) -> bool {
+        self.get_raised_amount() >= self.get_target_amount()
+    }
+
+    pub fn is_deadline_reached(&self, slot: u64) -> bool {
+        slot >= self.get_deadline_slot()
+    }
+
+    /// The smallest accepted contribution: a share of `1 / MAX_CROWDFUND_FUNDERS` of the `target_amount` (or the remaining amount, if lower)
+    pub fn min_contribution(&self) -> u64 {
+        let target_amount = self.get_target_amount();
+        let remaining_amount = target_amount.saturating_sub(self.get_raised_amount());
+        let share = target_amount / MAX_CROWDFUND_FUNDERS as u64
+            + u64::from(target_amount % MAX_CROWDFUND_FUNDERS as u64 != 0);
+
+        std::cmp::min(share, remaining_amount)
+    }
+
+    pub fn contribution(&self, funder: &Pubkey) -> u64 {
+        self.funder_index(funder)
+            .map(|i| self.get_contributions(i))
+            .unwrap_or(0)
+    }
+
+    fn funder_index(&self, funder: &Pubkey) -> Option<usize> {
+        (0..MAX_CROWDFUND_FUNDERS).find(|&i| self.get_funders(i) == *funder)
+    }
+
+    /// Adds a contribution of `amount` by `funder` (contributions below the [`Self::min_contribution`] or exceeding the `target_amount` are rejected)
+    pub fn contribute(&mut self, funder: &Pubkey, amount: u64) -> Result<(), ProgramError> {
+        guard!(
+            amount > 0 && amount >= self.min_contribution(),
+            ElusivError::InvalidAmount
+        );
+
+        let raised_amount = self
+            .get_raised_amount()
+            .checked_add(amount)
+            .ok_or(ElusivError::InvalidAmount)?;
+        guard!(
+            raised_amount <= self.get_target_amount(),
+            ElusivError::InvalidAmount
+        );
+
+        let index = match self.funder_index(funder) {
+            Some(index) => index,
+            None => {
+                let index = self
+                    .funder_index(&Pubkey::default())
+                    .ok_or(ElusivError::CrowdfundFunderLimitIsReached)?;
+                self.set_funders(index, funder);
+                index
+            }
+        };
+
+        self.set_contributions(index, &(self.get_contributions(index) + amount));
+        self.set_raised_amount(&raised_amount);
+
+        Ok(())
+    }
+
+    /// Removes and returns the contribution of `funder`
+    /// - only possible once the `deadline_slot` has passed without reaching the `target_amount`
+    pub fn refund(&mut self, funder: &Pubkey, slot: u64) -> Result<u64, ProgramError> {
+        guard!(
+            self.is_deadline_reached(slot),
+            ElusivError::CrowdfundDeadlineIsNotReached
+        );
+        guard!(!self.is_target_reached(), ElusivError::InvalidAccountState);
+
+        let index = self
+            .funder_index(funder)
+            .ok_or(ElusivError::InvalidAccount)?;
+        let amount = self.get_contributions(index);
+        guard!(amount > 0, ElusivError::InvalidAmount);
+
+        self.set_contributions(index, &0);
+        self.set_raised_amount(&(self.get_raised_amount() - amount));
+
+        Ok(amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_contribute() {
+        zero_program_account!(mut account, CrowdfundAccount);
+        account.set_target_amount(&100);
+        let funder = Pubkey::new_unique();
+
+        assert_matches!(account.contribute(&funder, 0), Err(_));
+        assert_matches!(account.contribute(&funder, 101), Err(_));
+
+        account.contribute(&funder, 60).unwrap();
+        assert!(!account.is_target_reached());
+
+        assert_matches!(account.contribute(&funder, 41), Err(_));
+        account.contribute(&funder, 40).unwrap();
+        assert!(account.is_target_reached());
+        assert_eq!(account.get_raised_amount(), 100);
+        assert_eq!(account.contribution(&funder), 100);
+    }
+
+    #[test]
+    fn test_min_contribution() {
+        zero_program_account!(mut account, CrowdfundAccount);
+        account.set_target_amount(&1_000);
+        let funder = Pubkey::new_unique();
+
+        // Rounded up share of the target
+        assert_eq!(account.min_contribution(), 63);
+        assert_matches!(account.contribute(&funder, 62), Err(_));
+        account.contribute(&funder, 990).unwrap();
+
+        // The remaining amount can always be contributed
+        assert_eq!(account.min_contribution(), 10);
+        assert_matches!(account.contribute(&funder, 9), Err(_));
+        account.contribute(&Pubkey::new_unique(), 10).unwrap();
+        assert!(account.is_target_reached());
+    }
+
+    #[test]
+    fn test_contribute_funder_limit() {
+        zero_program_account!(mut account, CrowdfundAccount);
+        account.set_target_amount(&(MAX_CROWDFUND_FUNDERS as u64 * 100));
+
+        for _ in 0..MAX_CROWDFUND_FUNDERS - 2 {
+            account.contribute(&Pubkey::new_unique(), 100).unwrap();
+        }
+
+        // Existing funders can still contribute
+        let funder = account.get_funders(0);
+        account.contribute(&funder, 100).unwrap();
+        assert_eq!(account.contribution(&funder), 200);
+
+        // The target is reached before all funder slots are occupied
+        account.contribute(&Pubkey::new_unique(), 100).unwrap();
+        assert!(account.is_target_reached());
+        assert_eq!(
+            account.get_funders(MAX_CROWDFUND_FUNDERS - 1),
+            Pubkey::default()
+        );
+    }
+
+    #[test]
+    fn test_refund() {
+        zero_program_account!(mut account, CrowdfundAccount);
+        account.set_target_amount(&100);
+        account.set_deadline_slot(&10);
+        let funder_a = Pubkey::new_unique();
+        let funder_b = Pubkey::new_unique();
+
+        account.contribute(&funder_a, 30).unwrap();
+        account.contribute(&funder_b, 20).unwrap();
+
+        // Deadline not reached
+        assert_matches!(account.refund(&funder_a, 9), Err(_));
+
+        // Unknown funder
+        assert_matches!(account.refund(&Pubkey::new_unique(), 10), Err(_));
+
+        assert_eq!(account.refund(&funder_a, 10).unwrap(), 30);
+        assert_eq!(account.get_raised_amount(), 20);
+        assert_eq!(account.contribution(&funder_a), 0);
+
+        // Only refunded once
+        assert_matches!(account.refund(&funder_a, 10), Err(_));
+
+        // No refunds once the target is reached
+        account.contribute(&funder_b, 80).unwrap();
+        assert_matches!(account.refund(&funder_b, 10), Err(_));
+    }
+}
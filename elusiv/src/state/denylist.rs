@@ -0,0 +1,237 @@
+use super::program_account::{Lifecycle, PDAAccountData, PDAOffset};
+use crate::error::ElusivError;
+use crate::macros::{assert_account_size, elusiv_account, guard};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use std::cmp::Ordering;
+
+/// Maximum number of recipients in a single [`RecipientDenylistAccount`] shard
+pub const MAX_DENIED_RECIPIENTS: usize = 64;
+
+/// Number of [`RecipientDenylistAccount`] shards
+pub const RECIPIENT_DENYLIST_SHARD_COUNT: u32 = 8;
+
+/// Maximum number of recipients added with a single batched insertion
+pub const MAX_DENIED_RECIPIENTS_BATCH: usize = 16;
+
+/// Returns the shard of the [`RecipientDenylistAccount`] that can contain `recipient`
+pub fn recipient_denylist_shard(recipient: &Pubkey) -> u32 {
+    recipient.as_ref()[0] as u32 % RECIPIENT_DENYLIST_SHARD_COUNT
+}
+
+/// The [`PDAOffset`] of a [`RecipientDenylistAccount`] shard
+/// - the first shard uses the [`None`] offset (single instance account), every other shard uses its index
+pub fn recipient_denylist_offset(shard: u32) -> PDAOffset {
+    if shard == 0 {
+        None
+    } else {
+        Some(shard)
+    }
+}
+
+/// Optional, governance-maintained set of recipients to which no withdrawals can be finalized
+/// - the set is split into [`RECIPIENT_DENYLIST_SHARD_COUNT`] shards (see [`recipient_denylist_shard`]), each one is only consulted if it is `enabled`
+/// - the recipients of a shard are kept sorted, so lookups are binary searches
+#[elusiv_account(eager_type: true)]
+pub struct RecipientDenylistAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub enabled: bool,
+    recipient_count: u32,
+    recipients: [Pubkey; MAX_DENIED_RECIPIENTS],
+}
+
+assert_account_size!(RecipientDenylistAccount, 2055);
+
+impl<'a> Lifecycle for RecipientDenylistAccount<'a> {}
+
+impl<'a> RecipientDenylistAccount<'a> {
+    /// Returns the position of `recipient` or the position at which it needs to be inserted
+    fn search(&self, recipient: &Pubkey) -> Result<usize, usize> {
+        let mut low = 0;
+        let mut high = self.get_recipient_count() as usize;
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.get_recipients(mid).cmp(recipient) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Ok(mid),
+            }
+        }
+
+        Err(low)
+    }
+
+    pub fn is_denied(&self, recipient: &Pubkey) -> bool {
+        self.get_enabled() && self.search(recipient).is_ok()
+    }
+
+    pub fn add(&mut self, recipient: &Pubkey) -> Result<(), ProgramError> {
+        self.add_batch(&[*recipient])
+    }
+
+    /// Adds all `recipients` with a single pass over the shard
+    pub fn add_batch(&mut self, recipients: &[Pubkey]) -> Result<(), ProgramError> {
+        let mut recipients: Vec<Pubkey> = recipients
+            .iter()
+            .filter(|recipient| self.search(recipient).is_err())
+            .copied()
+            .collect();
+        recipients.sort();
+        recipients.dedup();
+
+        let count = self.get_recipient_count() as usize;
+        let new_count = count + recipients.len();
+        guard!(
+            new_count <= MAX_DENIED_RECIPIENTS,
+            ElusivError::InvalidInstructionData
+        );
+
+        // Merge from the back, so that every existing recipient is moved at most once
+        let mut existing = count;
+        let mut added = recipients.len();
+        for position in (0..new_count).rev() {
+            if added == 0 {
+                break;
+            }
+
+            if existing > 0 && self.get_recipients(existing - 1) > recipients[added - 1] {
+                existing -= 1;
+                self.set_recipients(position, &self.get_recipients(existing));
+            } else {
+                added -= 1;
+                self.set_recipients(position, &recipients[added]);
+            }
+        }
+        self.set_recipient_count(&(new_count as u32));
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, recipient: &Pubkey) {
+        if let Ok(position) = self.search(recipient) {
+            let last = self.get_recipient_count() as usize - 1;
+            for i in position..last {
+                self.set_recipients(i, &self.get_recipients(i + 1));
+            }
+            self.set_recipients(last, &Pubkey::default());
+            self.set_recipient_count(&(last as u32));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+
+    #[test]
+    fn test_denylist() {
+        zero_program_account!(mut denylist, RecipientDenylistAccount);
+        let recipients = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        denylist.add(&recipients[0]).unwrap();
+        denylist.add(&recipients[1]).unwrap();
+        denylist.add(&recipients[0]).unwrap();
+        assert_eq!(denylist.get_recipient_count(), 2);
+
+        // Disabled
+        assert!(!denylist.is_denied(&recipients[0]));
+
+        denylist.set_enabled(&true);
+        assert!(denylist.is_denied(&recipients[0]));
+        assert!(denylist.is_denied(&recipients[1]));
+        assert!(!denylist.is_denied(&Pubkey::new_unique()));
+
+        denylist.remove(&recipients[0]);
+        assert!(!denylist.is_denied(&recipients[0]));
+        assert!(denylist.is_denied(&recipients[1]));
+        assert_eq!(denylist.get_recipient_count(), 1);
+    }
+
+    #[test]
+    fn test_denylist_capacity() {
+        zero_program_account!(mut denylist, RecipientDenylistAccount);
+
+        for _ in 0..MAX_DENIED_RECIPIENTS {
+            denylist.add(&Pubkey::new_unique()).unwrap();
+        }
+        assert!(denylist.add(&Pubkey::new_unique()).is_err());
+    }
+
+    #[test]
+    fn test_denylist_sorted() {
+        zero_program_account!(mut denylist, RecipientDenylistAccount);
+        denylist.set_enabled(&true);
+
+        let recipients: Vec<Pubkey> = (0..10).map(|_| Pubkey::new_unique()).collect();
+        for recipient in recipients.iter().rev() {
+            denylist.add(recipient).unwrap();
+        }
+        denylist.add_batch(&recipients[..5]).unwrap();
+        denylist.add_batch(&[]).unwrap();
+        denylist.remove(&recipients[3]);
+
+        let count = denylist.get_recipient_count() as usize;
+        assert_eq!(count, 9);
+        for i in 1..count {
+            assert!(denylist.get_recipients(i - 1) < denylist.get_recipients(i));
+        }
+        for (i, recipient) in recipients.iter().enumerate() {
+            assert_eq!(denylist.is_denied(recipient), i != 3);
+        }
+    }
+
+    #[test]
+    fn test_denylist_add_batch() {
+        zero_program_account!(mut denylist, RecipientDenylistAccount);
+        denylist.set_enabled(&true);
+
+        let mut recipients: Vec<Pubkey> = (0..MAX_DENIED_RECIPIENTS_BATCH)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        denylist.add(&recipients[0]).unwrap();
+
+        // Duplicates are ignored
+        recipients.push(recipients[1]);
+        denylist.add_batch(&recipients).unwrap();
+
+        assert_eq!(
+            denylist.get_recipient_count() as usize,
+            MAX_DENIED_RECIPIENTS_BATCH
+        );
+        for i in 1..MAX_DENIED_RECIPIENTS_BATCH {
+            assert!(denylist.get_recipients(i - 1) < denylist.get_recipients(i));
+        }
+        for recipient in recipients {
+            assert!(denylist.is_denied(&recipient));
+        }
+
+        // Capacity
+        let recipients: Vec<Pubkey> = (0..MAX_DENIED_RECIPIENTS - MAX_DENIED_RECIPIENTS_BATCH + 1)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        assert!(denylist.add_batch(&recipients).is_err());
+        denylist.add_batch(&recipients[1..]).unwrap();
+    }
+
+    #[test]
+    fn test_recipient_denylist_shard() {
+        assert_eq!(recipient_denylist_offset(0), None);
+        assert_eq!(recipient_denylist_offset(1), Some(1));
+
+        for _ in 0..100 {
+            assert!(
+                recipient_denylist_shard(&Pubkey::new_unique()) < RECIPIENT_DENYLIST_SHARD_COUNT
+            );
+        }
+        assert_eq!(
+            recipient_denylist_shard(&Pubkey::new_from_array(
+                [RECIPIENT_DENYLIST_SHARD_COUNT as u8 + 1; 32]
+            )),
+            1
+        );
+    }
+}
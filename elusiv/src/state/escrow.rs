@@ -0,0 +1,30 @@
+use super::program_account::{Lifecycle, PDAAccountData};
+use crate::macros::{assert_account_size, elusiv_account};
+use crate::types::U256;
+use solana_program::pubkey::Pubkey;
+
+/// Lamports retained by the [`FeeCollectorAccount`](super::governor::FeeCollectorAccount) when a deposit escrow is aborted (anti-spam)
+pub const DEPOSIT_ESCROW_ABORT_FEE: u64 = 10_000;
+
+/// Escrow holding a deposit until a warden reserves it for hashing (lamports only)
+/// - the `sender` transfers `amount` to the account
+/// - a warden reserves the deposit by starting the hashing of the commitment from `base_commitment`
+/// - until then the `sender` can abort the deposit and is refunded `amount` minus [`DEPOSIT_ESCROW_ABORT_FEE`]
+#[elusiv_account(eager_type: true)]
+pub struct DepositEscrowAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Rents the account and receives the rent once the deposit is reserved or aborted
+    pub sender: Pubkey,
+    pub base_commitment: U256,
+    pub amount: u64,
+}
+
+assert_account_size!(DepositEscrowAccount, 74);
+
+impl<'a> Lifecycle for DepositEscrowAccount<'a> {
+    const CLOSEABLE: bool = true;
+    const REUSABLE: bool = true;
+}
@@ -0,0 +1,66 @@
+use super::program_account::{Lifecycle, PDAAccountData};
+use crate::events::{ElusivEvent, EventHeader, EVENT_SCHEMA_VERSION};
+use crate::macros::{assert_account_size, elusiv_account};
+use borsh::BorshSerialize;
+use elusiv_utils::MATH_ERR;
+use solana_program::{entrypoint::ProgramResult, log::sol_log_data};
+
+/// Marker account for event consumers (e.g. Geyser plugins)
+/// - every emitted [`ElusivEvent`] writes this account, so an account-notification contains the latest `sequence`
+#[elusiv_account(eager_type: true)]
+pub struct EventSequenceAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// The schema-version of the most recently emitted event
+    pub schema_version: u8,
+
+    /// The sequence number of the most recently emitted event (zero if no event has been emitted yet)
+    pub sequence: u64,
+}
+
+assert_account_size!(EventSequenceAccount, 11);
+
+impl<'a> Lifecycle for EventSequenceAccount<'a> {}
+
+impl<'a> EventSequenceAccount<'a> {
+    /// Assigns the next sequence number to `event` and emits it using `sol_log_data`
+    pub fn emit(&mut self, event: &ElusivEvent) -> ProgramResult {
+        let header = EventHeader {
+            schema_version: EVENT_SCHEMA_VERSION,
+            sequence: self.get_sequence().checked_add(1).ok_or(MATH_ERR)?,
+        };
+
+        self.set_schema_version(&header.schema_version);
+        self.set_sequence(&header.sequence);
+
+        sol_log_data(&[&header.try_to_vec()?, &event.try_to_vec()?]);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+
+    #[test]
+    fn test_emit() {
+        zero_program_account!(mut account, EventSequenceAccount);
+        let event = ElusivEvent::Deposit {
+            base_commitment: [0; 32],
+            commitment: [0; 32],
+            amount: 1,
+            token_id: 0,
+            fee_version: 0,
+        };
+
+        for i in 1..=3 {
+            account.emit(&event).unwrap();
+            assert_eq!(account.get_sequence(), i);
+            assert_eq!(account.get_schema_version(), EVENT_SCHEMA_VERSION);
+        }
+    }
+}
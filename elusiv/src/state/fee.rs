@@ -1,14 +1,15 @@
-use super::program_account::PDAAccountData;
+use super::program_account::{Lifecycle, PDAAccountData};
+use super::stats::STATS_TOKEN_COUNT;
 use crate::bytes::{div_ceiling_u64, u64_as_usize_safe};
 use crate::commitment::{
     commitment_hash_computation_instructions, commitments_per_batch, BaseCommitmentHashComputation,
     MAX_COMMITMENT_BATCHING_RATE,
 };
-use crate::macros::elusiv_account;
+use crate::macros::{assert_account_size, elusiv_account};
 use crate::proof::verifier::{CombinedMillerLoop, FinalExponentiation};
 use crate::token::{Lamports, Token, TokenError, TokenPrice};
 use borsh::{BorshDeserialize, BorshSerialize};
-use elusiv_computation::PartialComputation;
+use elusiv_computation::{PartialComputation, MAX_COMPUTE_UNIT_LIMIT};
 use elusiv_derive::BorshSerDeSized;
 
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone)]
@@ -38,6 +39,9 @@ pub struct ProgramFee {
     pub base_commitment_subvention: Lamports,
     pub proof_subvention: Lamports,
 
+    /// Replaces the `proof_subvention` for merges (zero-amount sends), since they consolidate commitments without any funds leaving the pool
+    pub merge_subvention: Lamports,
+
     pub warden_hash_tx_reward: Lamports,
     pub warden_proof_reward: Lamports,
 
@@ -47,12 +51,14 @@ pub struct ProgramFee {
 
 impl ProgramFee {
     /// Creates a new `ProgramFee` if the inputs are valid
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         lamports_per_tx: u64,
         base_commitment_network_fee: u64,
         proof_network_fee: u64,
         base_commitment_subvention: u64,
         proof_subvention: u64,
+        merge_subvention: u64,
         warden_hash_tx_reward: u64,
         warden_proof_reward: u64,
     ) -> Option<Self> {
@@ -62,6 +68,7 @@ impl ProgramFee {
             proof_network_fee: BasisPointFee(proof_network_fee),
             base_commitment_subvention: Lamports(base_commitment_subvention),
             proof_subvention: Lamports(proof_subvention),
+            merge_subvention: Lamports(merge_subvention),
             warden_hash_tx_reward: Lamports(warden_hash_tx_reward),
             warden_proof_reward: Lamports(warden_proof_reward),
             proof_base_tx_count: Self::proof_base_tx_count(),
@@ -85,7 +92,7 @@ impl ProgramFee {
             // For proof verification we assume the cheapest scenario to be proof_base_tx_count (and network fee to be zero)
             let proof_fee = self.proof_base_tx_count * self.lamports_per_tx.0
                 + self.commitment_hash_computation_fee(min_batching_rate).0;
-            if self.proof_subvention.0 > proof_fee {
+            if self.proof_subvention.0 > proof_fee || self.merge_subvention.0 > proof_fee {
                 return false;
             }
 
@@ -97,10 +104,79 @@ impl ProgramFee {
     }
 
     pub fn proof_base_tx_count() -> u64 {
+        // With the alt_bn128 syscalls the verification is performed by the (single) input preparation transaction
+        if cfg!(feature = "alt-bn128") {
+            return 2;
+        }
+
         (CombinedMillerLoop::TX_COUNT + FinalExponentiation::TX_COUNT + 2) as u64
     }
 }
 
+/// Per token-id overrides of the network-fees and warden rewards of a [`ProgramFee`]
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct TokenFeeSchedule {
+    /// Whether the schedule is used (otherwise the token uses the lamports schedule)
+    pub is_set: bool,
+
+    /// Per storage-amount fee in basis points
+    pub base_commitment_network_fee: u64,
+
+    /// Per join-split-amount fee in basis points
+    pub proof_network_fee: u64,
+
+    pub warden_hash_tx_reward: u64,
+    pub warden_proof_reward: u64,
+}
+
+impl ProgramFee {
+    /// Returns the fee schedule with the overrides of `schedule` (or the lamports schedule, if `schedule` is not set)
+    pub fn with_token_schedule(&self, schedule: &TokenFeeSchedule) -> ProgramFee {
+        if !schedule.is_set {
+            return self.clone();
+        }
+
+        ProgramFee {
+            base_commitment_network_fee: BasisPointFee(schedule.base_commitment_network_fee),
+            proof_network_fee: BasisPointFee(schedule.proof_network_fee),
+            warden_hash_tx_reward: Lamports(schedule.warden_hash_tx_reward),
+            warden_proof_reward: Lamports(schedule.warden_proof_reward),
+            ..self.clone()
+        }
+    }
+}
+
+/// Apportionment (in basis points) of collected network-fees, the remainder is protocol revenue (see [`crate::processor::withdraw_protocol_revenue`])
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct NetworkFeeSplit {
+    /// Share distributed to wardens as relayer incentives (see [`crate::processor::distribute_relayer_incentives`])
+    pub relayer_incentive_bps: u16,
+
+    /// Share that is burned (see [`crate::processor::burn_collected_fees`])
+    pub burn_bps: u16,
+}
+
+/// The [`NetworkFeeSplit`] of a newly initialized fee-version (see [`crate::processor::init_new_fee_version`])
+pub const DEFAULT_NETWORK_FEE_SPLIT: NetworkFeeSplit = NetworkFeeSplit {
+    relayer_incentive_bps: 2_000,
+    burn_bps: 0,
+};
+
+impl NetworkFeeSplit {
+    /// Whether the relayer-incentive and burn shares (and the protocol revenue as the remainder) add up to 10_000 basis points
+    pub fn is_valid(&self) -> bool {
+        self.relayer_incentive_bps as u64 + self.burn_bps as u64 <= 10_000
+    }
+
+    /// Returns the relayer-incentive and the burn share of `amount`
+    pub fn apportion(&self, amount: u64) -> (u64, u64) {
+        let share = |bps: u16| (amount as u128 * bps as u128 / 10_000) as u64;
+        (share(self.relayer_incentive_bps), share(self.burn_bps))
+    }
+}
+
 /// Specifies the program fees and compensation for wardens
 #[elusiv_account]
 pub struct FeeAccount {
@@ -109,6 +185,62 @@ pub struct FeeAccount {
     pda_data: PDAAccountData,
 
     pub program_fee: ProgramFee,
+
+    /// Maximum compute-unit price (in micro-lamports, as declared with `SetComputeUnitPrice`) up to which wardens are compensated for priority fees (zero disables the compensation)
+    pub lamports_per_cu: u64,
+
+    /// Surcharge (in lamports) of base-commitments enqueued into the priority lane of the commitment queue (zero disables the priority lane)
+    pub priority_lane_surcharge: u64,
+
+    /// Rent-exempt minimum (in lamports) of a system account, set when the fee version is initialized
+    /// - lamports withdrawals need to be at least this amount, so that they can fund brand-new recipients
+    /// - a brand-new recipient can only spend the withdrawn amount exceeding this minimum (see [`FeeAccount::spendable_withdrawal_amount`])
+    pub recipient_rent_minimum: u64,
+
+    /// Apportionment of the network-fees collected with this fee version (set with [`crate::processor::set_network_fee_split`])
+    pub network_fee_split: NetworkFeeSplit,
+
+    /// Per token-id fee schedules (set with [`crate::processor::set_token_fee_schedule`])
+    pub token_fee_schedules: [TokenFeeSchedule; STATS_TOKEN_COUNT],
+}
+
+assert_account_size!(FeeAccount, 201);
+
+/// Upper bound for [`FeeAccount::lamports_per_cu`]
+pub const MAX_LAMPORTS_PER_CU: u64 = 1_000_000;
+
+impl<'a> Lifecycle for FeeAccount<'a> {}
+
+impl<'a> FeeAccount<'a> {
+    /// Returns the fee schedule of `token_id`, falling back to the lamports schedule
+    pub fn program_fee_for_token(&self, token_id: u16) -> ProgramFee {
+        let program_fee = self.get_program_fee();
+        if token_id as usize >= STATS_TOKEN_COUNT {
+            return program_fee;
+        }
+
+        program_fee.with_token_schedule(&self.get_token_fee_schedules(token_id as usize))
+    }
+
+    /// The priority fee of a computation transaction with the compute-unit price `declared_lamports_per_cu`, capped at `lamports_per_cu`
+    /// - computation transactions request the [`MAX_COMPUTE_UNIT_LIMIT`]
+    pub fn priority_fee_compensation(&self, declared_lamports_per_cu: u64) -> Lamports {
+        let lamports_per_cu = std::cmp::min(declared_lamports_per_cu, self.get_lamports_per_cu());
+        Lamports(div_ceiling_u64(
+            lamports_per_cu * MAX_COMPUTE_UNIT_LIMIT as u64,
+            1_000_000,
+        ))
+    }
+
+    /// The amount of a lamports withdrawal of `amount` the recipient can spend
+    /// - for a brand-new recipient, [`FeeAccount::recipient_rent_minimum`] of the withdrawn amount are used to fund its rent-exemption
+    pub fn spendable_withdrawal_amount(&self, amount: u64, is_new_recipient: bool) -> u64 {
+        if is_new_recipient {
+            amount.saturating_sub(self.get_recipient_rent_minimum())
+        } else {
+            amount
+        }
+    }
 }
 
 impl ProgramFee {
@@ -145,6 +277,21 @@ impl ProgramFee {
         Lamports(amount)
     }
 
+    /// Merges (zero-amount sends) receive the `merge_subvention` instead of the `proof_subvention`
+    ///
+    /// # Notes
+    ///
+    /// There is no dedicated merge proof or processor path: a merge is a regular send (using the send circuit) of zero amount,
+    /// which consumes up to [`crate::types::JOIN_SPLIT_MAX_N_ARITY`] input commitments and inserts their combined value as the single output commitment.
+    /// Consolidating more commitments requires consecutive merges.
+    pub fn proof_subvention(&self, amount: u64) -> Lamports {
+        if amount == 0 {
+            self.merge_subvention
+        } else {
+            self.proof_subvention
+        }
+    }
+
     pub fn proof_verification_fee(
         &self,
         input_preparation_tx_count: usize,
@@ -160,8 +307,104 @@ impl ProgramFee {
             .commitment_hash_computation_fee(min_batching_rate)
             .into_token(price, token_id)?;
         let network_fee = Token::new(token_id, self.proof_network_fee.calc(amount));
-        let subvention = self.proof_subvention.into_token(price, token_id)?;
+        let subvention = self.proof_subvention(amount).into_token(price, token_id)?;
 
         ((proof_verification_fee + commitment_hash_fee)? + network_fee)? - subvention
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+    use crate::token::{LAMPORTS_TOKEN_ID, USDC_TOKEN_ID};
+
+    #[test]
+    fn test_priority_fee_compensation() {
+        zero_program_account!(mut fee, FeeAccount);
+        assert_eq!(fee.priority_fee_compensation(1_000), Lamports(0));
+
+        fee.set_lamports_per_cu(&10_000);
+        assert_eq!(fee.priority_fee_compensation(0), Lamports(0));
+        assert_eq!(fee.priority_fee_compensation(1_000), Lamports(1_400));
+
+        // Capped at `lamports_per_cu`
+        assert_eq!(fee.priority_fee_compensation(10_000), Lamports(14_000));
+        assert_eq!(fee.priority_fee_compensation(u64::MAX), Lamports(14_000));
+    }
+
+    #[test]
+    fn test_spendable_withdrawal_amount() {
+        zero_program_account!(mut fee, FeeAccount);
+        fee.set_recipient_rent_minimum(&890_880);
+
+        assert_eq!(fee.spendable_withdrawal_amount(1_000_000, false), 1_000_000);
+        assert_eq!(fee.spendable_withdrawal_amount(1_000_000, true), 109_120);
+        assert_eq!(fee.spendable_withdrawal_amount(890_880, true), 0);
+        assert_eq!(fee.spendable_withdrawal_amount(1, true), 0);
+    }
+
+    #[test]
+    fn test_program_fee_for_token() {
+        zero_program_account!(mut fee, FeeAccount);
+        let program_fee = ProgramFee::new(5000, 11, 100, 33, 44, 88, 300, 555).unwrap();
+        fee.set_program_fee(&program_fee);
+
+        // Fallback to the lamports schedule
+        assert_eq!(fee.program_fee_for_token(USDC_TOKEN_ID), program_fee);
+
+        let schedule = TokenFeeSchedule {
+            is_set: true,
+            base_commitment_network_fee: 1,
+            proof_network_fee: 2,
+            warden_hash_tx_reward: 3,
+            warden_proof_reward: 4,
+        };
+        fee.set_token_fee_schedules(USDC_TOKEN_ID as usize, &schedule);
+
+        let token_fee = fee.program_fee_for_token(USDC_TOKEN_ID);
+        assert_eq!(token_fee.base_commitment_network_fee, BasisPointFee(1));
+        assert_eq!(token_fee.proof_network_fee, BasisPointFee(2));
+        assert_eq!(token_fee.warden_hash_tx_reward, Lamports(3));
+        assert_eq!(token_fee.warden_proof_reward, Lamports(4));
+        assert_eq!(token_fee.lamports_per_tx, program_fee.lamports_per_tx);
+        assert_eq!(fee.program_fee_for_token(LAMPORTS_TOKEN_ID), program_fee);
+    }
+
+    #[test]
+    fn test_network_fee_split() {
+        let split = NetworkFeeSplit {
+            relayer_incentive_bps: 2_000,
+            burn_bps: 500,
+        };
+        assert!(split.is_valid());
+        assert_eq!(split.apportion(1_000), (200, 50));
+        assert_eq!(split.apportion(u64::MAX).0, u64::MAX / 5);
+        assert_eq!(NetworkFeeSplit::default().apportion(1_000), (0, 0));
+
+        assert!(!NetworkFeeSplit {
+            relayer_incentive_bps: 9_501,
+            burn_bps: 500,
+        }
+        .is_valid());
+    }
+
+    #[test]
+    fn test_merge_subvention() {
+        let fee = ProgramFee::new(5000, 11, 100, 33, 44, 88, 300, 555).unwrap();
+        assert_eq!(fee.proof_subvention(1), Lamports(44));
+        assert_eq!(fee.proof_subvention(0), Lamports(88));
+
+        let price = TokenPrice::new_lamports();
+        let send_fee = fee
+            .proof_verification_fee(0, 0, 1, LAMPORTS_TOKEN_ID, &price)
+            .unwrap();
+        let merge_fee = fee
+            .proof_verification_fee(0, 0, 0, LAMPORTS_TOKEN_ID, &price)
+            .unwrap();
+        assert_eq!(send_fee.amount() - merge_fee.amount(), 88 - 44);
+
+        // Subventions cannot exceed the proof fee
+        assert!(ProgramFee::new(5000, 11, 100, 33, 44, u64::MAX, 300, 555).is_none());
+    }
+}
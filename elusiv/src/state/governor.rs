@@ -1,5 +1,18 @@
-use super::{fee::ProgramFee, program_account::PDAAccountData};
-use crate::macros::elusiv_account;
+use super::{
+    fee::{NetworkFeeSplit, ProgramFee, TokenFeeSchedule},
+    nullifier::SLOTS_PER_YEAR,
+    program_account::{Lifecycle, PDAAccountData, ReallocatableAccount},
+    stats::STATS_TOKEN_COUNT,
+    vkey::{VKeyUpgrade, UPGRADABLE_VKEY_COUNT},
+};
+use crate::error::ElusivError;
+use crate::macros::{assert_account_size, elusiv_account, guard};
+use crate::token::{elusiv_token, TokenError};
+use crate::types::U256;
+use borsh::{BorshDeserialize, BorshSerialize};
+use elusiv_derive::BorshSerDeSized;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use std::cmp::Ordering;
 
 #[elusiv_account(eager_type: true)]
 pub struct GovernorAccount {
@@ -17,6 +30,241 @@ pub struct GovernorAccount {
     pub commitment_batching_rate: u32,
 
     program_version: u32,
+
+    /// Minimum anonymity-delay (in slots) required for deposits of at least `anonymity_delay_thresholds[token_id]`
+    pub required_anonymity_delay_slots: u64,
+
+    /// Per token-id deposit amount from which on the `required_anonymity_delay_slots` apply (zero disables the requirement)
+    pub anonymity_delay_thresholds: [u64; STATS_TOKEN_COUNT],
+
+    /// Irreversible exit-only mode: deposits are rejected, sends are free of network-fees and can be relayed by the users themselves (even if the program is halted)
+    pub emergency_mode: bool,
+
+    /// Period without nullifier activity after which the nullifier-set of a closed MT can be sealed (zero disables seals)
+    pub seal_sunset_slots: u64,
+
+    /// Per token-id flag whether deposits of the token are accepted (lamports are always supported)
+    pub supported_tokens: [bool; STATS_TOKEN_COUNT],
+
+    /// Compute units (in thousands) kept free in each public-input preparation instruction to absorb CU variance
+    pub compute_unit_margin: u16,
+
+    /// Per token-id Pyth USD price account (zero uses the built-in price account of the token)
+    pub price_accounts: [U256; STATS_TOKEN_COUNT],
+
+    /// Maximum age (in seconds) of a Pyth price (zero disables the staleness check)
+    pub max_price_age: u64,
+
+    /// Maximum confidence interval of a Pyth price in basis points of the price (zero disables the confidence check)
+    pub max_price_confidence_bps: u16,
+
+    /// Per circuit (`vkey_id` of the built-in verifying key) upgrade state of the verifying key
+    pub vkey_upgrades: [VKeyUpgrade; UPGRADABLE_VKEY_COUNT],
+
+    /// Reversible pause: deposits, proof verifications and their finalizations are rejected (except for exits in emergency mode)
+    pub is_halted: bool,
+
+    /// Fee-version and batching rate change, applied after its activation slot
+    pub staged_parameters: StagedGovernorParameters,
+
+    /// Authority of privileged governance instructions (the program's keypair until configured)
+    pub governance_multisig: GovernanceMultisig,
+
+    /// Whether the fee-payers of partial computations need to be registered wardens
+    pub is_warden_registration_required: bool,
+
+    /// Per token-id maximum deposit volume per epoch (zero disables the cap)
+    pub epoch_deposit_caps: [u64; STATS_TOKEN_COUNT],
+
+    /// Minimum timelock (in slots) required for withdrawals of at least `withdrawal_delay_threshold` lamports
+    pub required_withdrawal_delay_slots: u64,
+
+    /// Lamports withdrawal amount from which on withdrawals need to be timelocked (zero disables the requirement)
+    pub withdrawal_delay_threshold: u64,
+
+    /// The per token-id fee schedules of the `FeeAccount` with the offset `fee_version`
+    pub token_fee_schedules: [TokenFeeSchedule; STATS_TOKEN_COUNT],
+}
+
+assert_account_size!(GovernorAccount, 794);
+
+/// Minimum period between the proposal and the activation of a [`StagedGovernorParameters`] change (about one day)
+pub const PARAMETER_CHANGE_TIMELOCK_SLOTS: u64 = SLOTS_PER_YEAR / 365;
+
+/// A proposed change of the fee-version and the commitment batching rate
+/// - relayers and wallets can adapt to the new parameters before `activation_slot`
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct StagedGovernorParameters {
+    pub is_staged: bool,
+    pub fee_version: u32,
+    pub commitment_batching_rate: u32,
+    pub activation_slot: u64,
+}
+
+impl StagedGovernorParameters {
+    /// Whether the staged change can be activated at `slot`
+    pub fn is_activatable(&self, slot: u64) -> bool {
+        self.is_staged && slot >= self.activation_slot
+    }
+}
+
+/// Maximum number of members of the [`GovernanceMultisig`]
+pub const MAX_GOVERNANCE_SIGNERS: usize = 5;
+
+/// An M-of-N multisig, requiring `threshold` of its `signers` to approve a privileged instruction
+/// - a zero `threshold` means that the multisig is not configured
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct GovernanceMultisig {
+    pub threshold: u8,
+    pub signer_count: u8,
+    pub signers: [U256; MAX_GOVERNANCE_SIGNERS],
+}
+
+impl GovernanceMultisig {
+    pub fn is_configured(&self) -> bool {
+        self.threshold > 0
+    }
+
+    /// Whether the multisig is a valid M-of-N configuration with distinct non-zero signers
+    pub fn is_valid(&self) -> bool {
+        let signer_count = self.signer_count as usize;
+        if self.threshold == 0
+            || self.threshold > self.signer_count
+            || signer_count > MAX_GOVERNANCE_SIGNERS
+        {
+            return false;
+        }
+
+        let signers = &self.signers[..signer_count];
+        signers
+            .iter()
+            .enumerate()
+            .all(|(i, signer)| *signer != [0; 32] && !signers[..i].contains(signer))
+            && self.signers[signer_count..].iter().all(|s| *s == [0; 32])
+    }
+
+    /// The number of distinct signers approving with the signatures of `keys`
+    pub fn approvals<'b, I: IntoIterator<Item = &'b Pubkey>>(&self, keys: I) -> usize {
+        let signers = &self.signers[..(self.signer_count as usize).min(MAX_GOVERNANCE_SIGNERS)];
+        let mut approved = [false; MAX_GOVERNANCE_SIGNERS];
+
+        for key in keys {
+            if let Some(i) = signers.iter().position(|s| *s == key.to_bytes()) {
+                approved[i] = true;
+            }
+        }
+
+        approved.iter().filter(|a| **a).count()
+    }
+
+    pub fn is_approved<'b, I: IntoIterator<Item = &'b Pubkey>>(&self, keys: I) -> bool {
+        self.is_configured() && self.approvals(keys) >= self.threshold as usize
+    }
+}
+
+impl<'a> Lifecycle for GovernorAccount<'a> {}
+
+impl<'a> ReallocatableAccount for GovernorAccount<'a> {}
+
+impl<'a> GovernorAccount<'a> {
+    /// The Pyth USD price account registered for `token_id`
+    pub fn price_account(&self, token_id: u16) -> Result<Pubkey, TokenError> {
+        let token = elusiv_token(token_id)?;
+        if (token_id as usize) >= STATS_TOKEN_COUNT {
+            return Err(TokenError::InvalidTokenID);
+        }
+
+        let price_account = self.get_price_accounts(token_id as usize);
+        if price_account == [0; 32] {
+            Ok(token.pyth_usd_price_key)
+        } else {
+            Ok(Pubkey::new_from_array(price_account))
+        }
+    }
+
+    /// The [`VKeyUpgrade`] of the circuit `vkey_id`
+    pub fn vkey_upgrade(&self, vkey_id: u32) -> Result<VKeyUpgrade, ProgramError> {
+        guard!(
+            (vkey_id as usize) < UPGRADABLE_VKEY_COUNT,
+            ElusivError::InvalidInstructionData
+        );
+
+        Ok(self.get_vkey_upgrades(vkey_id as usize))
+    }
+
+    /// Returns the fee schedule of `token_id` of the current fee-version, falling back to the lamports schedule
+    pub fn program_fee_for_token(&self, token_id: u16) -> ProgramFee {
+        let program_fee = self.get_program_fee();
+        if token_id as usize >= STATS_TOKEN_COUNT {
+            return program_fee;
+        }
+
+        program_fee.with_token_schedule(&self.get_token_fee_schedules(token_id as usize))
+    }
+
+    pub fn is_token_supported(&self, token_id: u16) -> bool {
+        token_id == 0
+            || ((token_id as usize) < STATS_TOKEN_COUNT
+                && self.get_supported_tokens(token_id as usize))
+    }
+
+    /// Returns `true` if depositing `amount` in addition to the `epoch_deposit_volume` exceeds the epoch deposit cap of `token_id`
+    pub fn exceeds_epoch_deposit_cap(
+        &self,
+        token_id: u16,
+        epoch_deposit_volume: u64,
+        amount: u64,
+    ) -> bool {
+        if (token_id as usize) >= STATS_TOKEN_COUNT {
+            return false;
+        }
+
+        let cap = self.get_epoch_deposit_caps(token_id as usize);
+        cap > 0 && epoch_deposit_volume.saturating_add(amount) > cap
+    }
+
+    /// Returns the anonymity-delay of a deposit of `amount` with the opt-in delay `anonymity_delay_slots`
+    pub fn anonymity_delay_slots(
+        &self,
+        token_id: u16,
+        amount: u64,
+        anonymity_delay_slots: u64,
+    ) -> u64 {
+        let threshold = if (token_id as usize) < STATS_TOKEN_COUNT {
+            self.get_anonymity_delay_thresholds(token_id as usize)
+        } else {
+            0
+        };
+
+        if threshold > 0 && amount >= threshold {
+            anonymity_delay_slots.max(self.get_required_anonymity_delay_slots())
+        } else {
+            anonymity_delay_slots
+        }
+    }
+
+    /// Whether proof verifications (and their finalizations) are rejected
+    /// - in emergency mode users need to be able to exit the pool, even if the program is halted
+    pub fn is_proof_verification_halted(&self) -> bool {
+        self.get_is_halted() && !self.get_emergency_mode()
+    }
+
+    /// Whether a lamports withdrawal of `amount` needs to be timelocked (see [`crate::state::withdrawal::PendingWithdrawalAccount`])
+    pub fn requires_withdrawal_timelock(&self, amount: u64) -> bool {
+        let threshold = self.get_withdrawal_delay_threshold();
+        threshold > 0 && amount >= threshold
+    }
+
+    /// Returns the timelock of a lamports withdrawal of `amount` with the opt-in delay `delay_slots`
+    pub fn withdrawal_delay_slots(&self, amount: u64, delay_slots: u64) -> u64 {
+        if self.requires_withdrawal_timelock(amount) {
+            delay_slots.max(self.get_required_withdrawal_delay_slots())
+        } else {
+            delay_slots
+        }
+    }
 }
 
 #[elusiv_account(eager_type: true)]
@@ -26,9 +274,389 @@ pub struct PoolAccount {
     pda_data: PDAAccountData,
 }
 
+assert_account_size!(PoolAccount, 2);
+
+impl<'a> Lifecycle for PoolAccount<'a> {}
+
+/// Number of fee-versions tracked by the [`FeeCollectorAccount`]
+pub const FEE_REVENUE_VERSION_COUNT: usize = 4;
+
+/// Fees collected and subventions paid by the [`FeeCollectorAccount`] for a single fee-version
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct FeeRevenue {
+    pub fee_version: u32,
+
+    /// Collected network-fees per token-id
+    pub network_fees: [u64; STATS_TOKEN_COUNT],
+
+    /// Paid subventions (minus refunded subventions) per token-id
+    pub subventions: [u64; STATS_TOKEN_COUNT],
+
+    /// Apportionment of the network-fees (mirrors the `network_fee_split` of the `FeeAccount` of `fee_version`)
+    pub split: NetworkFeeSplit,
+}
+
 #[elusiv_account(eager_type: true)]
 pub struct FeeCollectorAccount {
     #[no_getter]
     #[no_setter]
     pda_data: PDAAccountData,
+
+    /// The [`FeeRevenue`] of the last [`FEE_REVENUE_VERSION_COUNT`] fee-versions (the revenue of `fee_version` is stored at `fee_version % FEE_REVENUE_VERSION_COUNT`)
+    revenue: [FeeRevenue; FEE_REVENUE_VERSION_COUNT],
+
+    /// Collected network-fees of all fee-versions per token-id
+    pub total_network_fees: [u64; STATS_TOKEN_COUNT],
+
+    /// Withdrawn protocol revenue per token-id (see [`FeeCollectorAccount::withdrawable_revenue`])
+    pub total_withdrawn: [u64; STATS_TOKEN_COUNT],
+
+    /// Relayer-incentive shares of the collected network-fees per token-id
+    pub total_relayer_incentives: [u64; STATS_TOKEN_COUNT],
+
+    /// Burn shares of the collected network-fees per token-id
+    pub total_burn_shares: [u64; STATS_TOKEN_COUNT],
+
+    /// Burned network-fees per token-id (at most `total_burn_shares`)
+    pub total_burned: [u64; STATS_TOKEN_COUNT],
+
+    /// Allowlisted recipients of protocol revenue withdrawals (a zero entry is unused)
+    /// - for SPL tokens the destinations are token accounts
+    pub revenue_destinations: [U256; REVENUE_DESTINATION_COUNT],
+
+    /// Share (in basis points) of a withdrawal sent to the second revenue destination
+    pub secondary_revenue_split_bps: u16,
+
+    /// Relayer incentives distributed to wardens per token-id (at most `total_relayer_incentives`)
+    pub total_relayer_incentives_distributed: [u64; STATS_TOKEN_COUNT],
+
+    /// Subventions paid (minus refunded subventions) of all fee-versions per token-id
+    pub total_subventions: [u64; STATS_TOKEN_COUNT],
+}
+
+assert_account_size!(FeeCollectorAccount, 460);
+
+/// Number of allowlisted revenue destinations of the [`FeeCollectorAccount`]
+pub const REVENUE_DESTINATION_COUNT: usize = 2;
+
+impl<'a> Lifecycle for FeeCollectorAccount<'a> {}
+
+impl<'a> ReallocatableAccount for FeeCollectorAccount<'a> {}
+
+impl<'a> FeeCollectorAccount<'a> {
+    /// Returns the revenue of `fee_version`, if it is still stored
+    pub fn fee_revenue(&self, fee_version: u32) -> Option<FeeRevenue> {
+        let revenue = self.get_revenue(Self::index(fee_version));
+        if revenue.fee_version != fee_version {
+            return None;
+        }
+
+        Some(revenue)
+    }
+
+    /// Accounts a collected network-fee, apportioned with the [`NetworkFeeSplit`] of `fee_version`
+    pub fn add_network_fee(&mut self, fee_version: u32, token_id: u16, amount: u64) {
+        if (token_id as usize) < STATS_TOKEN_COUNT {
+            let index = token_id as usize;
+            let split = self
+                .fee_revenue(fee_version)
+                .map(|revenue| revenue.split)
+                .unwrap_or_default();
+            let (relayer_incentive, burn) = split.apportion(amount);
+
+            let total = self.get_total_network_fees(index);
+            self.set_total_network_fees(index, &total.saturating_add(amount));
+            let total = self.get_total_relayer_incentives(index);
+            self.set_total_relayer_incentives(index, &total.saturating_add(relayer_incentive));
+            let total = self.get_total_burn_shares(index);
+            self.set_total_burn_shares(index, &total.saturating_add(burn));
+        }
+
+        self.update(fee_version, |revenue| {
+            if let Some(fees) = revenue.network_fees.get_mut(token_id as usize) {
+                *fees = fees.saturating_add(amount);
+            }
+        })
+    }
+
+    /// Whether the revenue of `fee_version` is tracked (revenues of fee-versions older than the last [`FEE_REVENUE_VERSION_COUNT`] are not)
+    pub fn is_fee_version_tracked(&self, fee_version: u32) -> bool {
+        self.get_revenue(Self::index(fee_version)).fee_version <= fee_version
+    }
+
+    /// Sets the [`NetworkFeeSplit`] applied to the network-fees of `fee_version` collected from now on
+    pub fn set_network_fee_split(&mut self, fee_version: u32, split: NetworkFeeSplit) {
+        self.update(fee_version, |revenue| revenue.split = split)
+    }
+
+    /// Returns the protocol revenue of `token_id` that has not been withdrawn yet
+    /// - the relayer-incentive and burn shares of the network-fees are no protocol revenue
+    /// - subventions are paid out of the network-fees
+    pub fn withdrawable_revenue(&self, token_id: u16) -> u64 {
+        if token_id as usize >= STATS_TOKEN_COUNT {
+            return 0;
+        }
+
+        let index = token_id as usize;
+        self.get_total_network_fees(index)
+            .saturating_sub(self.get_total_relayer_incentives(index))
+            .saturating_sub(self.get_total_burn_shares(index))
+            .saturating_sub(self.get_total_subventions(index))
+            .saturating_sub(self.get_total_withdrawn(index))
+    }
+
+    /// Returns the relayer-incentive share of the network-fees of `token_id` that has not been distributed yet
+    pub fn pending_relayer_incentives(&self, token_id: u16) -> u64 {
+        if token_id as usize >= STATS_TOKEN_COUNT {
+            return 0;
+        }
+
+        self.get_total_relayer_incentives(token_id as usize)
+            .saturating_sub(self.get_total_relayer_incentives_distributed(token_id as usize))
+    }
+
+    /// Returns the burn share of the network-fees of `token_id` that has not been burned yet
+    pub fn pending_burn(&self, token_id: u16) -> u64 {
+        if token_id as usize >= STATS_TOKEN_COUNT {
+            return 0;
+        }
+
+        self.get_total_burn_shares(token_id as usize)
+            .saturating_sub(self.get_total_burned(token_id as usize))
+    }
+
+    pub fn add_subvention(&mut self, fee_version: u32, token_id: u16, amount: u64) {
+        if (token_id as usize) < STATS_TOKEN_COUNT {
+            let total = self.get_total_subventions(token_id as usize);
+            self.set_total_subventions(token_id as usize, &total.saturating_add(amount));
+        }
+
+        self.update(fee_version, |revenue| {
+            if let Some(subventions) = revenue.subventions.get_mut(token_id as usize) {
+                *subventions = subventions.saturating_add(amount);
+            }
+        })
+    }
+
+    /// Subventions are refunded to the [`FeeCollectorAccount`] for invalid proofs
+    pub fn refund_subvention(&mut self, fee_version: u32, token_id: u16, amount: u64) {
+        if (token_id as usize) < STATS_TOKEN_COUNT {
+            let total = self.get_total_subventions(token_id as usize);
+            self.set_total_subventions(token_id as usize, &total.saturating_sub(amount));
+        }
+
+        self.update(fee_version, |revenue| {
+            if let Some(subventions) = revenue.subventions.get_mut(token_id as usize) {
+                *subventions = subventions.saturating_sub(amount);
+            }
+        })
+    }
+
+    fn update<F: FnOnce(&mut FeeRevenue)>(&mut self, fee_version: u32, f: F) {
+        let index = Self::index(fee_version);
+        let mut revenue = self.get_revenue(index);
+
+        // Revenues of older fee-versions are overwritten
+        match revenue.fee_version.cmp(&fee_version) {
+            Ordering::Less => {
+                revenue = FeeRevenue {
+                    fee_version,
+                    ..Default::default()
+                }
+            }
+            Ordering::Greater => return,
+            Ordering::Equal => {}
+        }
+
+        f(&mut revenue);
+        self.set_revenue(index, &revenue);
+    }
+
+    fn index(fee_version: u32) -> usize {
+        fee_version as usize % FEE_REVENUE_VERSION_COUNT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+    use crate::token::{LAMPORTS_TOKEN_ID, USDC_TOKEN_ID};
+
+    #[test]
+    fn test_staged_governor_parameters_is_activatable() {
+        let mut staged = StagedGovernorParameters {
+            is_staged: false,
+            fee_version: 1,
+            commitment_batching_rate: 2,
+            activation_slot: 100,
+        };
+        assert!(!staged.is_activatable(100));
+
+        staged.is_staged = true;
+        assert!(!staged.is_activatable(99));
+        assert!(staged.is_activatable(100));
+        assert!(staged.is_activatable(101));
+    }
+
+    #[test]
+    fn test_governance_multisig() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let c = Pubkey::new_unique();
+
+        let mut signers = [[0; 32]; MAX_GOVERNANCE_SIGNERS];
+        signers[0] = a.to_bytes();
+        signers[1] = b.to_bytes();
+        signers[2] = c.to_bytes();
+        let multisig = GovernanceMultisig {
+            threshold: 2,
+            signer_count: 3,
+            signers,
+        };
+        assert!(multisig.is_valid());
+
+        // Not configured
+        assert!(!GovernanceMultisig::default().is_valid());
+        assert!(!GovernanceMultisig::default().is_approved(&[a, b, c]));
+
+        // Invalid thresholds and signers
+        assert!(!GovernanceMultisig {
+            threshold: 4,
+            ..multisig
+        }
+        .is_valid());
+        assert!(!GovernanceMultisig {
+            signer_count: 4,
+            ..multisig
+        }
+        .is_valid());
+        let mut duplicate = multisig;
+        duplicate.signers[2] = a.to_bytes();
+        assert!(!duplicate.is_valid());
+        let mut trailing = multisig;
+        trailing.signers[3] = Pubkey::new_unique().to_bytes();
+        assert!(!trailing.is_valid());
+
+        // Duplicate and foreign signatures are not counted
+        assert_eq!(multisig.approvals(&[a, a, Pubkey::new_unique()]), 1);
+        assert!(!multisig.is_approved(&[a, a]));
+        assert!(multisig.is_approved(&[a, c]));
+        assert!(multisig.is_approved(&[c, b, a]));
+    }
+
+    #[test]
+    fn test_exceeds_epoch_deposit_cap() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        // No cap
+        assert!(!governor.exceeds_epoch_deposit_cap(LAMPORTS_TOKEN_ID, u64::MAX, u64::MAX));
+
+        governor.set_epoch_deposit_caps(USDC_TOKEN_ID as usize, &100);
+        assert!(!governor.exceeds_epoch_deposit_cap(USDC_TOKEN_ID, 0, 100));
+        assert!(!governor.exceeds_epoch_deposit_cap(USDC_TOKEN_ID, 60, 40));
+        assert!(governor.exceeds_epoch_deposit_cap(USDC_TOKEN_ID, 60, 41));
+        assert!(governor.exceeds_epoch_deposit_cap(USDC_TOKEN_ID, u64::MAX, 1));
+        assert!(!governor.exceeds_epoch_deposit_cap(LAMPORTS_TOKEN_ID, 60, 41));
+    }
+
+    #[test]
+    fn test_withdrawal_delay_slots() {
+        zero_program_account!(mut governor, GovernorAccount);
+
+        // No requirement
+        assert!(!governor.requires_withdrawal_timelock(u64::MAX));
+        assert_eq!(governor.withdrawal_delay_slots(u64::MAX, 5), 5);
+
+        governor.set_required_withdrawal_delay_slots(&100);
+        governor.set_withdrawal_delay_threshold(&1_000);
+        assert!(!governor.requires_withdrawal_timelock(999));
+        assert!(governor.requires_withdrawal_timelock(1_000));
+
+        assert_eq!(governor.withdrawal_delay_slots(999, 5), 5);
+        assert_eq!(governor.withdrawal_delay_slots(1_000, 5), 100);
+        assert_eq!(governor.withdrawal_delay_slots(1_000, 200), 200);
+    }
+
+    #[test]
+    fn test_fee_revenue() {
+        zero_program_account!(mut account, FeeCollectorAccount);
+
+        account.add_network_fee(0, LAMPORTS_TOKEN_ID, 10);
+        account.add_network_fee(0, USDC_TOKEN_ID, 5);
+        account.add_subvention(0, USDC_TOKEN_ID, 3);
+        account.add_subvention(0, USDC_TOKEN_ID, 3);
+        account.refund_subvention(0, USDC_TOKEN_ID, 2);
+        account.add_network_fee(1, LAMPORTS_TOKEN_ID, 1);
+
+        let revenue = account.fee_revenue(0).unwrap();
+        assert_eq!(revenue.network_fees[LAMPORTS_TOKEN_ID as usize], 10);
+        assert_eq!(revenue.network_fees[USDC_TOKEN_ID as usize], 5);
+        assert_eq!(revenue.subventions[USDC_TOKEN_ID as usize], 4);
+
+        // Paid subventions are no protocol revenue
+        assert_eq!(account.get_total_subventions(USDC_TOKEN_ID as usize), 4);
+        assert_eq!(account.withdrawable_revenue(USDC_TOKEN_ID), 1);
+        assert_eq!(account.withdrawable_revenue(LAMPORTS_TOKEN_ID), 11);
+
+        assert_eq!(
+            account.fee_revenue(1).unwrap().network_fees[LAMPORTS_TOKEN_ID as usize],
+            1
+        );
+        assert_eq!(account.fee_revenue(2), None);
+    }
+
+    #[test]
+    fn test_fee_revenue_rotation() {
+        zero_program_account!(mut account, FeeCollectorAccount);
+        let fee_version = FEE_REVENUE_VERSION_COUNT as u32;
+
+        account.add_network_fee(0, LAMPORTS_TOKEN_ID, 10);
+        account.add_network_fee(fee_version, LAMPORTS_TOKEN_ID, 1);
+
+        assert_eq!(account.fee_revenue(0), None);
+        assert_eq!(
+            account.fee_revenue(fee_version).unwrap().network_fees[LAMPORTS_TOKEN_ID as usize],
+            1
+        );
+
+        // Older fee-versions cannot overwrite newer ones
+        account.add_network_fee(0, LAMPORTS_TOKEN_ID, 10);
+        assert_eq!(account.fee_revenue(0), None);
+
+        // The totals include all fee-versions
+        assert_eq!(account.withdrawable_revenue(LAMPORTS_TOKEN_ID), 21);
+        account.set_total_withdrawn(LAMPORTS_TOKEN_ID as usize, &20);
+        assert_eq!(account.withdrawable_revenue(LAMPORTS_TOKEN_ID), 1);
+        assert_eq!(account.withdrawable_revenue(USDC_TOKEN_ID), 0);
+    }
+
+    #[test]
+    fn test_network_fee_split() {
+        zero_program_account!(mut account, FeeCollectorAccount);
+        let split = NetworkFeeSplit {
+            relayer_incentive_bps: 2_000,
+            burn_bps: 1_000,
+        };
+
+        // Fees collected before the split are not apportioned
+        account.add_network_fee(1, LAMPORTS_TOKEN_ID, 100);
+        account.set_network_fee_split(1, split);
+        assert_eq!(account.fee_revenue(1).unwrap().split, split);
+        assert_eq!(account.fee_revenue(1).unwrap().network_fees[0], 100);
+
+        account.add_network_fee(1, LAMPORTS_TOKEN_ID, 1_000);
+        assert_eq!(account.get_total_relayer_incentives(0), 200);
+        assert_eq!(account.pending_burn(LAMPORTS_TOKEN_ID), 100);
+        assert_eq!(account.withdrawable_revenue(LAMPORTS_TOKEN_ID), 800);
+
+        account.set_total_burned(0, &100);
+        assert_eq!(account.pending_burn(LAMPORTS_TOKEN_ID), 0);
+
+        // The split of a new fee-version
+        account.set_network_fee_split(2, NetworkFeeSplit::default());
+        account.add_network_fee(2, LAMPORTS_TOKEN_ID, 1_000);
+        assert_eq!(account.withdrawable_revenue(LAMPORTS_TOKEN_ID), 1_800);
+    }
 }
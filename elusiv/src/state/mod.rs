@@ -1,9 +1,25 @@
+pub mod call;
+pub mod child;
 pub mod commitment;
+pub mod config;
+pub mod crowdfund;
+pub mod denylist;
+pub mod escrow;
+pub mod event;
 pub mod fee;
 pub mod governor;
+pub mod note;
 pub mod nullifier;
+pub mod pending;
+pub mod poseidon;
 pub mod program_account;
 pub mod proof;
 pub mod queue;
+pub mod recurring;
+pub mod stats;
 pub mod storage;
+pub mod summary;
+pub mod viewing_key;
 pub mod vkey;
+pub mod warden;
+pub mod withdrawal;
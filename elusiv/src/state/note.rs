@@ -0,0 +1,129 @@
+use super::program_account::{Lifecycle, PDAAccountData};
+use crate::macros::{assert_account_size, elusiv_account, BorshSerDeSized};
+use crate::types::U256;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Size of a note ciphertext (e.g. an ephemeral public key, nonce and the encrypted note opening)
+pub const NOTE_CIPHERTEXT_SIZE: usize = 128;
+
+/// Number of note ciphertexts retained by a [`NoteCiphertextAccount`]
+pub const NOTE_CIPHERTEXT_CAPACITY: usize = 48;
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Copy)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct NoteCiphertext {
+    /// The commitment of the deposit (as in [`crate::events::ElusivEvent::Deposit`])
+    pub commitment: U256,
+
+    /// The note opening encrypted to the viewing key of the recipient
+    pub ciphertext: [u8; NOTE_CIPHERTEXT_SIZE],
+
+    /// The slot in which the ciphertext has been appended
+    pub slot: u64,
+}
+
+impl Default for NoteCiphertext {
+    fn default() -> Self {
+        Self {
+            commitment: [0; 32],
+            ciphertext: [0; NOTE_CIPHERTEXT_SIZE],
+            slot: 0,
+        }
+    }
+}
+
+/// Ring buffer of the latest [`NoteCiphertext`]s of the deposits into the shielded pool of a token
+/// - wallets discover their deposits by trial-decrypting the retained ciphertexts instead of scanning every commitment
+/// - `count` is strictly increasing, which allows wallets to detect ciphertexts that have been overwritten before they were scanned
+#[elusiv_account(eager_type: true)]
+pub struct NoteCiphertextAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    note_ciphertexts: [NoteCiphertext; NOTE_CIPHERTEXT_CAPACITY],
+
+    /// Total number of appended ciphertexts
+    pub count: u64,
+}
+
+assert_account_size!(NoteCiphertextAccount, 8074);
+
+impl<'a> Lifecycle for NoteCiphertextAccount<'a> {}
+
+impl<'a> NoteCiphertextAccount<'a> {
+    /// Appends `note_ciphertext`, overwriting the oldest ciphertext once the buffer is full
+    pub fn push(&mut self, note_ciphertext: &NoteCiphertext) {
+        let count = self.get_count();
+        self.set_note_ciphertexts(
+            (count % NOTE_CIPHERTEXT_CAPACITY as u64) as usize,
+            note_ciphertext,
+        );
+        self.set_count(&(count + 1));
+    }
+
+    /// Returns the retained ciphertexts appended after the first `count` ciphertexts (oldest first)
+    pub fn note_ciphertexts_since(&self, count: u64) -> Vec<NoteCiphertext> {
+        let total = self.get_count();
+        let first = std::cmp::max(count, total.saturating_sub(NOTE_CIPHERTEXT_CAPACITY as u64));
+
+        (first..total)
+            .map(|i| self.get_note_ciphertexts((i % NOTE_CIPHERTEXT_CAPACITY as u64) as usize))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+
+    fn note_ciphertext(i: u64) -> NoteCiphertext {
+        NoteCiphertext {
+            commitment: [i as u8; 32],
+            ciphertext: [i as u8; NOTE_CIPHERTEXT_SIZE],
+            slot: i,
+        }
+    }
+
+    #[test]
+    fn test_push() {
+        zero_program_account!(mut account, NoteCiphertextAccount);
+        assert!(account.note_ciphertexts_since(0).is_empty());
+
+        for i in 0..3 {
+            account.push(&note_ciphertext(i));
+        }
+        assert_eq!(account.get_count(), 3);
+        assert_eq!(
+            account.note_ciphertexts_since(0),
+            vec![note_ciphertext(0), note_ciphertext(1), note_ciphertext(2)]
+        );
+        assert_eq!(account.note_ciphertexts_since(2), vec![note_ciphertext(2)]);
+        assert!(account.note_ciphertexts_since(3).is_empty());
+    }
+
+    #[test]
+    fn test_push_overwrites_oldest() {
+        zero_program_account!(mut account, NoteCiphertextAccount);
+
+        let total = NOTE_CIPHERTEXT_CAPACITY as u64 + 5;
+        for i in 0..total {
+            account.push(&note_ciphertext(i));
+        }
+        assert_eq!(account.get_count(), total);
+
+        let retained = account.note_ciphertexts_since(0);
+        assert_eq!(retained.len(), NOTE_CIPHERTEXT_CAPACITY);
+        assert_eq!(retained[0], note_ciphertext(5));
+        assert_eq!(
+            retained[NOTE_CIPHERTEXT_CAPACITY - 1],
+            note_ciphertext(total - 1)
+        );
+
+        assert_eq!(
+            account.note_ciphertexts_since(total - 1),
+            vec![note_ciphertext(total - 1)]
+        );
+    }
+}
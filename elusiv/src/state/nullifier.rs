@@ -1,12 +1,14 @@
-use super::program_account::PDAAccountData;
-use super::storage::MT_HEIGHT;
+use super::program_account::{Lifecycle, PDAAccountData, ReallocatableAccount};
+use super::storage::{token_pool_offset, MT_HEIGHT};
 use crate::bytes::*;
 use crate::error::ElusivError;
-use crate::macros::{elusiv_account, guard, two_pow};
+use crate::macros::{assert_account_size, elusiv_account, guard, two_pow};
 use crate::map::ElusivSet;
+use crate::token::TokenID;
 use crate::types::{OrdU256, JOIN_SPLIT_MAX_N_ARITY, U256};
 use elusiv_types::{ChildAccount, ParentAccount};
 use solana_program::entrypoint::ProgramResult;
+use solana_program::hash::hashv;
 use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
 
@@ -33,6 +35,11 @@ impl ChildAccount for NullifierChildAccount {
 /// # Note
 ///
 /// We use [`NullifierMap`]s to store the nullifiers.
+/// The nullifier-hashes are kept in sorted order over two levels:
+/// - all nullifier-hashes of a child-account are smaller than the ones of the next child-account (`max_values` is ascending)
+/// - each child-account is an insertion-sorted [`NullifierMap`]
+///
+/// A lookup therefore binary searches the `max_values` ([`NullifierAccount::find_child_account_index`]) and then the map of a single child-account.
 #[elusiv_account(parent_account: { child_account_count: ACCOUNTS_COUNT, child_account: NullifierChildAccount }, eager_type: true)]
 pub struct NullifierAccount {
     #[no_getter]
@@ -43,43 +50,167 @@ pub struct NullifierAccount {
     pub root: U256, // this value is only valid, after the active tree has been closed
     pub nullifier_hash_count: u32,
 
+    /// Running digest of all inserted nullifier-hashes (see [`nullifier_digest`])
+    pub nullifier_digest: U256,
+
     pub max_values: [ElusivOption<U256>; ACCOUNTS_COUNT],
 
     moved_values_count: u8,
     moved_values: [U256; JOIN_SPLIT_MAX_N_ARITY],
     moved_values_target: [u8; JOIN_SPLIT_MAX_N_ARITY],
+
+    /// Slot of the last nullifier-hash insertion (or of the closing of the MT)
+    pub last_activity_slot: u64,
+
+    /// Announced sealing of the nullifier-set of the closed MT (see [`SEAL_TIMELOCK_SLOTS`])
+    pub seal_announcement_slot: u64,
+
+    /// After the sealing no further nullifier-hashes can be inserted
+    pub is_sealed: bool,
+
+    /// Rent (in lamports) reclaimed from the closed child-accounts and refunded to the contributors (see [`TreeRentContributionAccount`])
+    pub reclaimed_rent: u64,
+    pub refunded_rent: u64,
+
+    /// Number of nullifier-hashes contained in the `bloom_filter`
+    /// - the filter is only used while it contains all nullifier-hashes (not the case for accounts reallocated after insertions)
+    pub bloom_filter_count: u32,
+
+    /// Bloom filter of the inserted nullifier-hashes (see [`nullifier_bloom_filter_bits`])
+    /// - a nullifier-hash that is not contained in the filter is not spent, which avoids reading the child-accounts
+    /// - with [`NULLIFIER_BLOOM_FILTER_HASH_COUNT`] bits per nullifier-hash, the false-positive rate stays below 3% up to `2^13` nullifier-hashes and the filter saturates for a full MT
+    bloom_filter: [u8; NULLIFIER_BLOOM_FILTER_SIZE],
 }
 
-/// Tree account after archiving (only a single collapsed N-SMT root)
-#[elusiv_account]
-pub struct ArchivedNullifierAccount {
+assert_account_size!(NullifierAccount, 9488);
+
+/// Size of the bloom filter of a [`NullifierAccount`] in bytes
+pub const NULLIFIER_BLOOM_FILTER_SIZE: usize = 8192;
+
+/// Number of bits set per nullifier-hash in the bloom filter of a [`NullifierAccount`]
+pub const NULLIFIER_BLOOM_FILTER_HASH_COUNT: usize = 4;
+
+const NULLIFIER_BLOOM_FILTER_BITS: usize = NULLIFIER_BLOOM_FILTER_SIZE * 8;
+
+/// The bits of the bloom filter representing `nullifier_hash`
+/// - nullifier-hashes are (uniformly distributed) Poseidon hashes, so the bits are taken from the nullifier-hash itself instead of rehashing it
+/// - a crafted nullifier-hash can at most cause a false positive, which is resolved by searching the child-accounts
+pub fn nullifier_bloom_filter_bits(
+    nullifier_hash: &U256,
+) -> [usize; NULLIFIER_BLOOM_FILTER_HASH_COUNT] {
+    let mut bits = [0; NULLIFIER_BLOOM_FILTER_HASH_COUNT];
+    for (i, bit) in bits.iter_mut().enumerate() {
+        let chunk = u32::from_le_bytes(nullifier_hash[i * 4..(i + 1) * 4].try_into().unwrap());
+        *bit = chunk as usize % NULLIFIER_BLOOM_FILTER_BITS;
+    }
+    bits
+}
+
+/// Approximate number of slots per year
+pub const SLOTS_PER_YEAR: u64 = 78_840_000;
+
+/// Minimum period without nullifier activity before the sealing of the nullifier-set of a closed MT can be enabled by governance
+pub const MIN_SEAL_SUNSET_SLOTS: u64 = 5 * SLOTS_PER_YEAR;
+
+/// Period between the announcement and the execution of a sealing
+pub const SEAL_TIMELOCK_SLOTS: u64 = SLOTS_PER_YEAR;
+
+impl<'a, 'b, 't> Lifecycle for NullifierAccount<'a, 'b, 't> {}
+
+/// Accounts created before the addition of fields are migrated with [`crate::processor::realloc_account`] (the new fields are zero-initialized)
+impl<'a, 'b, 't> ReallocatableAccount for NullifierAccount<'a, 'b, 't> {}
+
+/// Compact record of a closed MT (created with [`crate::processor::archive_closed_merkle_tree`])
+/// - `commitment_root` is accepted as root of the MT by the proof verification
+/// - `nullifier_digest` and `nullifier_hash_count` are the state of the [`NullifierAccount`] at archiving
+#[elusiv_account(eager_type: true)]
+pub struct ArchivedTreeAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub mt_index: u32,
+    pub commitment_root: U256,
+    pub nullifier_digest: U256,
+    pub nullifier_hash_count: u32,
+}
+
+assert_account_size!(ArchivedTreeAccount, 74);
+
+impl<'a> Lifecycle for ArchivedTreeAccount<'a> {}
+
+/// Appends `nullifier_hash` to the running `digest` of a nullifier-set
+pub fn nullifier_digest(digest: &U256, nullifier_hash: &U256) -> U256 {
+    hashv(&[digest, nullifier_hash]).to_bytes()
+}
+
+/// The composite PDA-offsets of the [`NullifierAccount`] (and the [`TreeRentContributionAccount`]s) of the MT with `mt_index` in the pool of `token_id`
+/// - the lamports pool only uses `mt_index`, resulting in the same PDAs as the offset `Some(mt_index)`
+pub fn nullifier_account_offsets(token_id: TokenID, mt_index: u32) -> Vec<u32> {
+    match token_pool_offset(token_id) {
+        Some(pool_offset) => vec![mt_index, pool_offset],
+        None => vec![mt_index],
+    }
+}
+
+/// Portion (in basis points) of a [`TreeRentContributionAccount`] refunded after archiving
+/// - the remainder covers the rent of the [`ArchivedTreeAccount`]
+pub const TREE_RENT_REFUND_BPS: u64 = 9_000;
+
+/// Rent paid by a `contributor` for the accounts of the MT with index `mt_index`
+/// - recorded when opening the [`NullifierAccount`] and enabling its child-accounts
+/// - once all child-accounts of the archived MT are closed, [`TREE_RENT_REFUND_BPS`] of `amount` can be claimed
+/// - the refunds are paid by the fee collector (which holds the reclaimed rent) and are limited to the rent reclaimed from the MT
+#[elusiv_account(eager_type: true)]
+pub struct TreeRentContributionAccount {
     #[no_getter]
     #[no_setter]
     pda_data: PDAAccountData,
 
-    commitment_root: U256,
-    nullifier_root: U256,
+    pub contributor: Pubkey,
+    pub mt_index: u32,
+    pub amount: u64,
+}
+
+assert_account_size!(TreeRentContributionAccount, 46);
+
+impl<'a> Lifecycle for TreeRentContributionAccount<'a> {
+    const CLOSEABLE: bool = true;
+}
+
+impl<'a> TreeRentContributionAccount<'a> {
+    pub fn refund_amount(&self) -> u64 {
+        (self.get_amount() as u128 * TREE_RENT_REFUND_BPS as u128 / 10_000) as u64
+    }
 }
 
 impl<'a, 'b, 'c> NullifierAccount<'a, 'b, 'c> {
     pub fn can_insert_nullifier_hash(&self, nullifier_hash: U256) -> Result<bool, ProgramError> {
+        guard!(!self.get_is_sealed(), ElusivError::MerkleTreeIsSealed);
+
         let count = self.get_nullifier_hash_count();
         guard!(
             (count as usize) < NULLIFIERS_COUNT,
             ElusivError::CouldNotInsertNullifier
         );
 
-        let account_index = self.find_child_account_index(&nullifier_hash);
-        let nullifier_hash = OrdU256(nullifier_hash);
-
         let moved_values = self.get_all_moved_values();
         if moved_values
             .iter()
-            .any(|(value, _)| *value == nullifier_hash)
+            .any(|(value, _)| value.0 == nullifier_hash)
         {
             return Ok(false);
         }
 
+        // Fast-path: a nullifier-hash that is not contained in the (complete) bloom filter has never been inserted
+        if self.get_bloom_filter_count() == count && !self.bloom_filter_may_contain(&nullifier_hash)
+        {
+            return Ok(true);
+        }
+
+        let account_index = self.find_child_account_index(&nullifier_hash);
+        let nullifier_hash = OrdU256(nullifier_hash);
+
         let contains = self.execute_on_child_account_mut(account_index, |data| {
             let mut map = NullifierMap::new(data);
             map.contains(&nullifier_hash).is_some()
@@ -89,6 +220,8 @@ impl<'a, 'b, 'c> NullifierAccount<'a, 'b, 'c> {
     }
 
     pub fn try_insert_nullifier_hash(&mut self, nullifier_hash: U256) -> ProgramResult {
+        guard!(!self.get_is_sealed(), ElusivError::MerkleTreeIsSealed);
+
         let count = self.get_nullifier_hash_count();
         guard!(
             (count as usize) < NULLIFIERS_COUNT,
@@ -96,6 +229,8 @@ impl<'a, 'b, 'c> NullifierAccount<'a, 'b, 'c> {
         );
 
         let account_index = self.find_child_account_index(&nullifier_hash);
+        let digest = nullifier_digest(&self.get_nullifier_digest(), &nullifier_hash);
+        let bloom_filter_bits = nullifier_bloom_filter_bits(&nullifier_hash);
         let mut nullifier_hash = OrdU256(nullifier_hash);
 
         // `moved_values` contains all nullifier-hashes that need to be moved to other maps due to previous insertions
@@ -135,6 +270,11 @@ impl<'a, 'b, 'c> NullifierAccount<'a, 'b, 'c> {
 
         // Inc `nullifier_hash_count` and update the maximum value for the modified map account
         self.set_nullifier_hash_count(&count.checked_add(1).unwrap());
+        self.set_nullifier_digest(&digest);
+        if self.get_bloom_filter_count() == count {
+            self.insert_into_bloom_filter(&bloom_filter_bits);
+            self.set_bloom_filter_count(&count.checked_add(1).unwrap());
+        }
         self.set_max_values(account_index, &ElusivOption::Some(max.0));
 
         if moved_values_modified {
@@ -214,24 +354,78 @@ impl<'a, 'b, 'c> NullifierAccount<'a, 'b, 'c> {
         }
     }
 
+    /// Returns false if `nullifier_hash` has definitely not been inserted (while the filter contains all nullifier-hashes)
+    pub fn bloom_filter_may_contain(&self, nullifier_hash: &U256) -> bool {
+        nullifier_bloom_filter_bits(nullifier_hash)
+            .iter()
+            .all(|&bit| self.get_bloom_filter(bit / 8) & (1 << (bit % 8)) != 0)
+    }
+
+    fn insert_into_bloom_filter(&mut self, bits: &[usize]) {
+        for &bit in bits {
+            let byte = self.get_bloom_filter(bit / 8);
+            self.set_bloom_filter(bit / 8, &(byte | (1 << (bit % 8))));
+        }
+    }
+
     pub fn is_moved_nullifier_empty(&self) -> bool {
         self.get_moved_values_count() == 0
     }
 
+    /// Returns true if the MT has been untouched for `sunset_slots` at `slot`
+    pub fn is_sunset(&self, sunset_slots: u64, slot: u64) -> bool {
+        sunset_slots > 0 && slot >= self.get_last_activity_slot().saturating_add(sunset_slots)
+    }
+
+    /// Returns true if all child-accounts have been closed (see [`crate::processor::close_nullifier_child_account`])
+    pub fn are_child_accounts_closed(&self) -> bool {
+        (0..self.child_count()).all(|i| self.get_child_pubkey(i).is_none())
+    }
+
+    /// The reclaimed rent that has not been refunded yet
+    pub fn refundable_rent(&self) -> u64 {
+        self.get_reclaimed_rent()
+            .saturating_sub(self.get_refunded_rent())
+    }
+
+    /// Returns true if an announced sealing can be executed at `slot`
+    /// - any nullifier activity after the announcement cancels the sealing
+    pub fn is_seal_executable(&self, slot: u64) -> bool {
+        let announcement_slot = self.get_seal_announcement_slot();
+
+        !self.get_is_sealed()
+            && self.get_last_activity_slot() < announcement_slot
+            && slot >= announcement_slot.saturating_add(SEAL_TIMELOCK_SLOTS)
+    }
+
     /// Sorts the provided values from large to small
     fn sort_all_moved_values(moved_values: &mut [(OrdU256, u8)]) {
         moved_values.sort_by(|(a, _), (b, _)| b.cmp(a));
     }
 
+    /// Returns the index of the child-account that contains `nullifier_hash` (if it has been inserted)
+    /// - the `max_values` of the full child-accounts are in ascending order, so the index is found with a binary search
     pub fn find_child_account_index(&self, nullifier_hash: &U256) -> usize {
         let full_accounts_count = self.get_nullifier_hash_count() as usize / NULLIFIERS_PER_ACCOUNT;
-        for i in 0..full_accounts_count {
-            if OrdU256(*nullifier_hash) <= OrdU256(self.get_max_values(i).option().unwrap()) {
-                return i;
+        let nullifier_hash = OrdU256(*nullifier_hash);
+
+        let mut low = 0;
+        let mut high = full_accounts_count;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            // A child-account without a max-value contains no nullifier-hashes, so no larger child-account can either
+            let is_upper_bound = match self.get_max_values(mid).option() {
+                Some(max_value) => nullifier_hash <= OrdU256(max_value),
+                None => true,
+            };
+            if is_upper_bound {
+                high = mid;
+            } else {
+                low = mid + 1;
             }
         }
 
-        full_accounts_count
+        low
     }
 
     #[cfg(feature = "elusiv-client")]
@@ -266,6 +460,22 @@ mod tests {
         macros::parent_account,
     };
     use assert_matches::assert_matches;
+    use elusiv_types::{PDAAccount, ProgramAccount, SizedAccount};
+
+    #[test]
+    fn test_nullifier_account_offsets() {
+        assert_eq!(nullifier_account_offsets(0, 3), vec![3]);
+        assert_eq!(nullifier_account_offsets(1, 3), vec![3, 1]);
+
+        assert_eq!(
+            NullifierAccount::find_with_offsets(None, &nullifier_account_offsets(0, 3)),
+            NullifierAccount::find(Some(3))
+        );
+        assert_ne!(
+            NullifierAccount::find_with_offsets(None, &nullifier_account_offsets(1, 3)).0,
+            NullifierAccount::find_with_offsets(None, &nullifier_account_offsets(2, 3)).0
+        );
+    }
 
     #[test]
     fn test_can_insert_nullifier_hash() {
@@ -284,6 +494,124 @@ mod tests {
         assert!(nullifier_account.can_insert_nullifier_hash(c).unwrap());
     }
 
+    #[test]
+    fn test_nullifier_bloom_filter() {
+        parent_account!(mut nullifier_account, NullifierAccount);
+
+        let values: Vec<U256> = (0..100).map(|i| u64_to_u256_skip_mr(i * 7_919)).collect();
+        for value in &values {
+            assert!(!nullifier_account.bloom_filter_may_contain(value));
+        }
+
+        for value in &values {
+            nullifier_account.try_insert_nullifier_hash(*value).unwrap();
+        }
+        assert_eq!(nullifier_account.get_bloom_filter_count(), 100);
+
+        // No false negatives
+        for value in &values {
+            assert!(nullifier_account.bloom_filter_may_contain(value));
+            assert!(!nullifier_account.can_insert_nullifier_hash(*value).unwrap());
+        }
+
+        // A false positive is resolved by the child-accounts
+        let mut value = [0; 32];
+        for (i, bit) in nullifier_bloom_filter_bits(&values[0]).iter().enumerate() {
+            value[i * 4..(i + 1) * 4].copy_from_slice(&(*bit as u32).to_le_bytes());
+        }
+        value[31] = 1;
+        assert!(nullifier_account.bloom_filter_may_contain(&value));
+        assert!(nullifier_account.can_insert_nullifier_hash(value).unwrap());
+    }
+
+    #[test]
+    fn test_nullifier_bloom_filter_skips_child_accounts() {
+        let inserted = u64_to_u256_skip_mr(1);
+        let not_inserted = u64_to_u256_skip_mr(2);
+
+        parent_account!(internal NullifierAccount, child_accounts, data);
+        NullifierAccount::new_with_child_accounts(&mut data, child_accounts)
+            .unwrap()
+            .try_insert_nullifier_hash(inserted)
+            .unwrap();
+
+        // Without any child-accounts only the bloom filter can be queried
+        let nullifier_account =
+            NullifierAccount::new_with_child_accounts(&mut data, vec![None; ACCOUNTS_COUNT])
+                .unwrap();
+        assert!(nullifier_account
+            .can_insert_nullifier_hash(not_inserted)
+            .unwrap());
+        assert_matches!(
+            nullifier_account.can_insert_nullifier_hash(inserted),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn test_nullifier_bloom_filter_incomplete() {
+        parent_account!(mut nullifier_account, NullifierAccount);
+        let a = u64_to_u256_skip_mr(1);
+        let b = u64_to_u256_skip_mr(2);
+
+        // Nullifier-hashes inserted before the reallocation of an account are not contained in the filter
+        nullifier_account.try_insert_nullifier_hash(a).unwrap();
+        nullifier_account.set_bloom_filter_count(&0);
+        for bit in nullifier_bloom_filter_bits(&a) {
+            nullifier_account.set_bloom_filter(bit / 8, &0);
+        }
+        assert!(!nullifier_account.bloom_filter_may_contain(&a));
+        assert!(!nullifier_account.can_insert_nullifier_hash(a).unwrap());
+
+        // The incomplete filter is not updated anymore
+        nullifier_account.try_insert_nullifier_hash(b).unwrap();
+        assert_eq!(nullifier_account.get_bloom_filter_count(), 0);
+        assert!(!nullifier_account.can_insert_nullifier_hash(a).unwrap());
+        assert!(!nullifier_account.can_insert_nullifier_hash(b).unwrap());
+    }
+
+    #[test]
+    fn test_tree_rent_refund_amount() {
+        let mut data = vec![0; TreeRentContributionAccount::SIZE];
+        let mut contribution = TreeRentContributionAccount::new(&mut data).unwrap();
+
+        contribution.set_amount(&1_000_000);
+        assert_eq!(contribution.refund_amount(), 900_000);
+
+        contribution.set_amount(&u64::MAX);
+        assert_eq!(
+            contribution.refund_amount(),
+            (u64::MAX as u128 * TREE_RENT_REFUND_BPS as u128 / 10_000) as u64
+        );
+    }
+
+    #[test]
+    fn test_sealed_nullifier_account() {
+        parent_account!(mut nullifier_account, NullifierAccount);
+        nullifier_account.set_is_sealed(&true);
+
+        assert_matches!(nullifier_account.can_insert_nullifier_hash([0; 32]), Err(_));
+        assert_matches!(nullifier_account.try_insert_nullifier_hash([0; 32]), Err(_));
+    }
+
+    #[test]
+    fn test_seal_timing() {
+        parent_account!(mut nullifier_account, NullifierAccount);
+        nullifier_account.set_last_activity_slot(&100);
+
+        assert!(!nullifier_account.is_sunset(0, u64::MAX));
+        assert!(!nullifier_account.is_sunset(10, 109));
+        assert!(nullifier_account.is_sunset(10, 110));
+
+        nullifier_account.set_seal_announcement_slot(&110);
+        assert!(!nullifier_account.is_seal_executable(110 + SEAL_TIMELOCK_SLOTS - 1));
+        assert!(nullifier_account.is_seal_executable(110 + SEAL_TIMELOCK_SLOTS));
+
+        // Activity after the announcement cancels the sealing
+        nullifier_account.set_last_activity_slot(&110);
+        assert!(!nullifier_account.is_seal_executable(110 + SEAL_TIMELOCK_SLOTS));
+    }
+
     #[test]
     fn test_can_insert_nullifier_hash_moved_values() {
         parent_account!(mut nullifier_account, NullifierAccount);
@@ -329,6 +657,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_nullifier_digest() {
+        parent_account!(mut nullifier_account, NullifierAccount);
+        assert_eq!(nullifier_account.get_nullifier_digest(), [0; 32]);
+
+        let a = u256_from_str("123");
+        let b = u256_from_str("456");
+        nullifier_account.try_insert_nullifier_hash(a).unwrap();
+        nullifier_account.try_insert_nullifier_hash(b).unwrap();
+
+        assert_eq!(
+            nullifier_account.get_nullifier_digest(),
+            nullifier_digest(&nullifier_digest(&[0; 32], &a), &b)
+        );
+
+        // Failed insertions do not modify the digest
+        nullifier_account.try_insert_nullifier_hash(a).unwrap_err();
+        assert_eq!(
+            nullifier_account.get_nullifier_digest(),
+            nullifier_digest(&nullifier_digest(&[0; 32], &a), &b)
+        );
+    }
+
     #[test]
     fn test_try_insert_nullifier_hash_moved_values() {
         parent_account!(mut nullifier_account, NullifierAccount);
@@ -449,6 +800,19 @@ mod tests {
 
         // Less than max value
         assert_eq!(nullifier_account.find_child_account_index(&[0; 32]), 0);
+
+        // Multiple full child-accounts
+        nullifier_account.set_nullifier_hash_count(&(3 * NULLIFIERS_PER_ACCOUNT as u32));
+        for (i, max_value) in [10, 20, 30].iter().enumerate() {
+            nullifier_account
+                .set_max_values(i, &ElusivOption::Some(u64_to_u256_skip_mr(*max_value)));
+        }
+        for (value, index) in [(0, 0), (10, 0), (11, 1), (20, 1), (25, 2), (30, 2), (31, 3)] {
+            assert_eq!(
+                nullifier_account.find_child_account_index(&u64_to_u256_skip_mr(value)),
+                index
+            );
+        }
     }
 
     #[test]
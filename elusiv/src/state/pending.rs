@@ -0,0 +1,113 @@
+use super::program_account::{Lifecycle, PDAAccountData};
+use crate::error::ElusivError;
+use crate::macros::{assert_account_size, elusiv_account, guard};
+use crate::types::U256;
+use solana_program::{hash::hashv, program_error::ProgramError, pubkey::Pubkey};
+
+/// Escrow of an expiring payment (lamports only)
+/// - the account is used as the recipient of a regular send, so the withdrawn `amount` is held by this account
+/// - until `expiry_slot` the `claim_key` can release the `amount` to the `recipient`
+/// - afterwards the `amount` can be refunded into a new commitment (computed from `refund_base_commitment`) of the sender
+/// - a non-zero `hashlock` additionally requires the claim to reveal a preimage with `sha256(preimage) == hashlock` (atomic swaps, conditional payments)
+/// - with `claim_key == recipient` the payment is a two-step withdrawal: only a signature of the recipient releases the `amount`, so a typoed recipient leaves it unclaimed until the refund
+#[elusiv_account(eager_type: true)]
+pub struct PendingPaymentAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Rents the account and receives the rent once the payment is claimed or refunded
+    pub sender: Pubkey,
+    pub claim_key: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub expiry_slot: u64,
+    pub refund_base_commitment: U256,
+    pub hashlock: U256,
+}
+
+assert_account_size!(PendingPaymentAccount, 178);
+
+impl<'a> Lifecycle for PendingPaymentAccount<'a> {
+    const CLOSEABLE: bool = true;
+    const REUSABLE: bool = true;
+}
+
+impl<'a> PendingPaymentAccount<'a> {
+    pub fn is_expired(&self, slot: u64) -> bool {
+        slot >= self.get_expiry_slot()
+    }
+
+    /// Requires the payment to be claimable at `slot` by `claim_key` revealing `preimage`
+    pub fn verify_claim(
+        &self,
+        claim_key: &Pubkey,
+        preimage: &U256,
+        slot: u64,
+    ) -> Result<(), ProgramError> {
+        guard!(
+            self.get_claim_key() == *claim_key,
+            ElusivError::InvalidAccount
+        );
+        guard!(!self.is_expired(slot), ElusivError::PaymentIsExpired);
+
+        let hashlock = self.get_hashlock();
+        if hashlock != [0; 32] {
+            guard!(
+                hashv(&[preimage]).to_bytes() == hashlock,
+                ElusivError::InvalidHashlockPreimage
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Requires the payment to be refundable at `slot`
+    pub fn verify_refund(&self, slot: u64) -> Result<(), ProgramError> {
+        guard!(self.is_expired(slot), ElusivError::PaymentIsNotYetExpired);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_verify_claim_and_refund() {
+        zero_program_account!(mut account, PendingPaymentAccount);
+        let claim_key = Pubkey::new_unique();
+        account.set_claim_key(&claim_key);
+        account.set_expiry_slot(&10);
+
+        assert_matches!(account.verify_claim(&claim_key, &[0; 32], 9), Ok(()));
+        assert_matches!(
+            account.verify_claim(&Pubkey::new_unique(), &[0; 32], 9),
+            Err(_)
+        );
+        assert_matches!(account.verify_claim(&claim_key, &[0; 32], 10), Err(_));
+
+        assert_matches!(account.verify_refund(9), Err(_));
+        assert_matches!(account.verify_refund(10), Ok(()));
+    }
+
+    #[test]
+    fn test_verify_claim_hashlock() {
+        zero_program_account!(mut account, PendingPaymentAccount);
+        let claim_key = Pubkey::new_unique();
+        let preimage = [7; 32];
+        account.set_claim_key(&claim_key);
+        account.set_expiry_slot(&10);
+        account.set_hashlock(&hashv(&[&preimage]).to_bytes());
+
+        assert_matches!(account.verify_claim(&claim_key, &[0; 32], 9), Err(_));
+        assert_matches!(account.verify_claim(&claim_key, &[8; 32], 9), Err(_));
+        assert_matches!(account.verify_claim(&claim_key, &preimage, 9), Ok(()));
+
+        // The hashlock does not extend the expiry
+        assert_matches!(account.verify_claim(&claim_key, &preimage, 10), Err(_));
+    }
+}
@@ -0,0 +1,256 @@
+use super::program_account::{Lifecycle, PDAAccountData};
+use crate::commitment::poseidon_hash::{
+    PoseidonParams, POSEIDON_MDS_MATRIX_SIZE, TOTAL_POSEIDON_ROUNDS,
+};
+use crate::error::ElusivError;
+use crate::fields::u256_to_fr_skip_mr;
+use crate::macros::{assert_account_size, elusiv_account, guard};
+use crate::types::U256;
+use ark_bn254::Fr;
+use solana_program::{hash::hashv, program_error::ProgramError};
+
+/// Number of round constants (three per round)
+pub const POSEIDON_ROUND_CONSTANT_COUNT: usize = TOTAL_POSEIDON_ROUNDS as usize * 3;
+
+/// Number of parameters stored in the [`PoseidonParamsAccount`] (the round constants followed by the MDS matrix)
+pub const POSEIDON_PARAMS_COUNT: usize = POSEIDON_ROUND_CONSTANT_COUNT + POSEIDON_MDS_MATRIX_SIZE;
+
+/// Maximum number of parameters set with a single instruction
+pub const POSEIDON_PARAMS_CHUNK_SIZE: usize = 24;
+
+/// Version of the [`crate::commitment::poseidon_hash::DefaultPoseidonParams`]
+pub const DEFAULT_POSEIDON_PARAMS_VERSION: u32 = 1;
+
+/// Returns the expected checksum of the parameters with `version` (see [`PoseidonParamsAccount::compute_checksum`])
+/// - parameter upgrades require a program release pinning the checksum of the new version
+pub fn poseidon_params_checksum(version: u32) -> Option<U256> {
+    match version {
+        DEFAULT_POSEIDON_PARAMS_VERSION => Some([
+            196, 104, 136, 227, 218, 230, 109, 72, 184, 0, 198, 34, 208, 10, 244, 75, 42, 109, 198,
+            225, 39, 140, 104, 48, 183, 132, 143, 245, 192, 219, 172, 183,
+        ]),
+        _ => None,
+    }
+}
+
+/// Round constants and MDS matrix used by the commitment hash computations
+/// - all parameters are stored in montgomery form, so they are read without any reduction
+/// - the parameters can only be used once the account is frozen, which requires the `checksum` to match the pinned checksum of the `version` (see [`poseidon_params_checksum`])
+/// - the parameters can only be upgraded to a newer pinned version (see [`PoseidonParamsAccount::unfreeze`])
+#[elusiv_account(eager_type: true)]
+pub struct PoseidonParamsAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    params: [U256; POSEIDON_PARAMS_COUNT],
+
+    pub is_frozen: bool,
+    pub version: u32,
+    pub checksum: U256,
+}
+
+assert_account_size!(PoseidonParamsAccount, 6567);
+
+impl<'a> Lifecycle for PoseidonParamsAccount<'a> {}
+
+impl<'a> PoseidonParamsAccount<'a> {
+    /// Sets `params` starting at `offset` (only before the account is frozen)
+    pub fn set_params_chunk(&mut self, offset: usize, params: &[U256]) -> Result<(), ProgramError> {
+        guard!(!self.get_is_frozen(), ElusivError::InvalidAccountState);
+        guard!(
+            offset
+                .checked_add(params.len())
+                .map_or(false, |end| end <= POSEIDON_PARAMS_COUNT),
+            ElusivError::InvalidInstructionData
+        );
+
+        for (i, param) in params.iter().enumerate() {
+            self.set_params(offset + i, param);
+        }
+
+        Ok(())
+    }
+
+    /// Computes the checksum over all parameters
+    pub fn compute_checksum(&self) -> U256 {
+        hashv(&[self.params]).to_bytes()
+    }
+
+    /// Freezes the parameters as `version`, if they match the pinned checksum of the `version`
+    /// - versions can only increase
+    pub fn freeze(&mut self, version: u32) -> Result<(), ProgramError> {
+        guard!(!self.get_is_frozen(), ElusivError::InvalidAccountState);
+        guard!(
+            version > self.get_version(),
+            ElusivError::InvalidInstructionData
+        );
+
+        let checksum =
+            poseidon_params_checksum(version).ok_or(ElusivError::InvalidInstructionData)?;
+        guard!(
+            self.compute_checksum() == checksum,
+            ElusivError::InvalidInstructionData
+        );
+
+        self.set_checksum(&checksum);
+        self.set_version(&version);
+        self.set_is_frozen(&true);
+
+        Ok(())
+    }
+
+    /// Unfreezes the parameters for an upgrade to the next version
+    /// - requires a pinned checksum of a newer version, so frozen parameters can never be replaced by arbitrary ones
+    /// - commitment hashing is rejected until the new parameters are frozen
+    pub fn unfreeze(&mut self) -> Result<(), ProgramError> {
+        guard!(self.get_is_frozen(), ElusivError::InvalidAccountState);
+        guard!(
+            poseidon_params_checksum(self.get_version().saturating_add(1)).is_some(),
+            ElusivError::InvalidAccountState
+        );
+
+        self.set_is_frozen(&false);
+
+        Ok(())
+    }
+
+    fn get_param(&self, index: usize) -> Fr {
+        u256_to_fr_skip_mr(&self.get_params(index))
+    }
+}
+
+impl<'a> PoseidonParams for PoseidonParamsAccount<'a> {
+    fn round_constants(&self, round: usize) -> [Fr; 3] {
+        let offset = round * 3;
+        [
+            self.get_param(offset),
+            self.get_param(offset + 1),
+            self.get_param(offset + 2),
+        ]
+    }
+
+    fn mds_matrix(&self) -> [Fr; POSEIDON_MDS_MATRIX_SIZE] {
+        let mut matrix = [Fr::default(); POSEIDON_MDS_MATRIX_SIZE];
+        for (i, entry) in matrix.iter_mut().enumerate() {
+            *entry = self.get_param(POSEIDON_ROUND_CONSTANT_COUNT + i);
+        }
+        matrix
+    }
+}
+
+/// The [`crate::commitment::poseidon_hash::DefaultPoseidonParams`] in the layout of the [`PoseidonParamsAccount`]
+#[cfg(any(test, feature = "elusiv-client"))]
+pub fn default_poseidon_params() -> Vec<U256> {
+    use crate::commitment::poseidon_hash::DefaultPoseidonParams;
+    use crate::fields::fr_to_u256_le;
+
+    let params = DefaultPoseidonParams;
+    (0..TOTAL_POSEIDON_ROUNDS as usize)
+        .flat_map(|round| params.round_constants(round))
+        .chain(params.mds_matrix())
+        .map(|fr| fr_to_u256_le(&fr))
+        .collect()
+}
+
+/// The checksum of the [`default_poseidon_params`]
+#[cfg(any(test, feature = "elusiv-client"))]
+pub fn default_poseidon_params_checksum() -> U256 {
+    let params: Vec<u8> = default_poseidon_params().concat();
+    hashv(&[&params]).to_bytes()
+}
+
+#[cfg(any(test, feature = "elusiv-client"))]
+impl<'a> PoseidonParamsAccount<'a> {
+    /// Loads and freezes the [`default_poseidon_params`]
+    pub fn load_default_params(&mut self) {
+        self.set_params_chunk(0, &default_poseidon_params())
+            .unwrap();
+        self.freeze(DEFAULT_POSEIDON_PARAMS_VERSION).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commitment::poseidon_hash::{full_poseidon2_hash, poseidon2_hash};
+    use crate::fields::u64_to_scalar;
+    use crate::macros::zero_program_account;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_set_params_chunk() {
+        zero_program_account!(mut account, PoseidonParamsAccount);
+
+        assert_matches!(
+            account.set_params_chunk(POSEIDON_PARAMS_COUNT - 1, &[[1; 32]; 2]),
+            Err(_)
+        );
+        assert_matches!(account.set_params_chunk(usize::MAX, &[[1; 32]]), Err(_));
+
+        account
+            .set_params_chunk(POSEIDON_PARAMS_COUNT - 2, &[[1; 32]; 2])
+            .unwrap();
+        assert_eq!(account.get_params(POSEIDON_PARAMS_COUNT - 1), [1; 32]);
+    }
+
+    #[test]
+    fn test_freeze() {
+        zero_program_account!(mut account, PoseidonParamsAccount);
+        account
+            .set_params_chunk(0, &default_poseidon_params())
+            .unwrap();
+
+        // Invalid version
+        assert_matches!(account.freeze(0), Err(_));
+        assert_matches!(account.freeze(DEFAULT_POSEIDON_PARAMS_VERSION + 1), Err(_));
+
+        account.freeze(DEFAULT_POSEIDON_PARAMS_VERSION).unwrap();
+        assert!(account.get_is_frozen());
+        assert_eq!(account.get_version(), DEFAULT_POSEIDON_PARAMS_VERSION);
+        assert_matches!(account.freeze(DEFAULT_POSEIDON_PARAMS_VERSION), Err(_));
+
+        // Frozen parameters cannot be changed
+        assert_matches!(account.set_params_chunk(0, &[[0; 32]]), Err(_));
+        assert!(account.get_is_frozen());
+        assert_eq!(
+            account.compute_checksum(),
+            default_poseidon_params_checksum()
+        );
+
+        // No newer version is pinned
+        assert_matches!(account.unfreeze(), Err(_));
+        assert!(account.get_is_frozen());
+    }
+
+    #[test]
+    fn test_freeze_invalid_params() {
+        zero_program_account!(mut account, PoseidonParamsAccount);
+        account
+            .set_params_chunk(0, &default_poseidon_params())
+            .unwrap();
+        account.set_params_chunk(0, &[[1; 32]]).unwrap();
+
+        assert_matches!(account.freeze(DEFAULT_POSEIDON_PARAMS_VERSION), Err(_));
+        assert!(!account.get_is_frozen());
+    }
+
+    #[test]
+    fn test_pinned_poseidon_params_checksum() {
+        assert_eq!(
+            poseidon_params_checksum(DEFAULT_POSEIDON_PARAMS_VERSION),
+            Some(default_poseidon_params_checksum())
+        );
+        assert_eq!(poseidon_params_checksum(0), None);
+    }
+
+    #[test]
+    fn test_poseidon_params() {
+        zero_program_account!(mut account, PoseidonParamsAccount);
+        account.load_default_params();
+
+        let a = u64_to_scalar(1);
+        let b = u64_to_scalar(2);
+        assert_eq!(poseidon2_hash(a, b, &account), full_poseidon2_hash(a, b));
+    }
+}
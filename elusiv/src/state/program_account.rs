@@ -1,9 +1,147 @@
 pub use elusiv_types::accounts::*;
 
+use crate::error::ElusivError;
+use elusiv_utils::{close_account, guard, open_pda_account, open_pda_account_with_offsets};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE},
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+/// Describes how a [`PDAAccount`] is opened, sealed, closed and (for reusable accounts) reopened
+///
+/// # Notes
+///
+/// - sealed accounts can no longer be modified
+/// - closed accounts are drained of their lamports and can only be reopened if they are reusable
+pub trait Lifecycle: PDAAccount + SizedAccount {
+    /// Whether the account can be closed
+    const CLOSEABLE: bool = false;
+
+    /// Whether the account can be reopened after it has been closed
+    const REUSABLE: bool = false;
+
+    /// Opens the PDA, paid by `payer`
+    fn open<'a>(
+        payer: &AccountInfo<'a>,
+        account: &AccountInfo<'a>,
+        pda_pubkey: Option<Pubkey>,
+        pda_offset: PDAOffset,
+        bump: Option<u8>,
+    ) -> ProgramResult {
+        open_pda_account::<Self>(
+            &crate::id(),
+            payer,
+            account,
+            pda_pubkey,
+            pda_offset,
+            bump,
+            Self::SIZE,
+        )
+    }
+
+    /// Opens the PDA with composite [`PDAOffsets`], paid by `payer`
+    fn open_with_offsets<'a>(
+        payer: &AccountInfo<'a>,
+        account: &AccountInfo<'a>,
+        pda_pubkey: Option<Pubkey>,
+        pda_offsets: PDAOffsets,
+        bump: Option<u8>,
+    ) -> ProgramResult {
+        open_pda_account_with_offsets::<Self>(
+            &crate::id(),
+            payer,
+            account,
+            pda_pubkey,
+            pda_offsets,
+            bump,
+        )
+    }
+
+    /// Opens a PDA that might have already been used and closed before
+    fn reopen<'a>(
+        payer: &AccountInfo<'a>,
+        account: &AccountInfo<'a>,
+        pda_pubkey: Option<Pubkey>,
+        pda_offset: PDAOffset,
+        bump: Option<u8>,
+    ) -> ProgramResult {
+        guard!(Self::REUSABLE, ElusivError::FeatureNotAvailable);
+        Self::open(payer, account, pda_pubkey, pda_offset, bump)
+    }
+
+    /// Closes the PDA, `recipient` receives the lamports
+    fn close<'a>(recipient: &AccountInfo<'a>, account: &AccountInfo<'a>) -> ProgramResult {
+        guard!(Self::CLOSEABLE, ElusivError::FeatureNotAvailable);
+        close_account(recipient, account)
+    }
+
+    fn is_sealed(&self) -> bool {
+        false
+    }
+
+    /// Prevents all further modifications
+    fn seal(&mut self) -> ProgramResult {
+        Err(ElusivError::FeatureNotAvailable.into())
+    }
+}
+
+/// A [`PDAAccount`] whose data can be grown to its (increased) [`SizedAccount::SIZE`] without a migration-by-copy
+///
+/// # Notes
+///
+/// - a single instruction can grow the data by at most [`MAX_PERMITTED_DATA_INCREASE`] bytes, larger increases require multiple transactions
+/// - the additional data is zero-initialized
+pub trait ReallocatableAccount: PDAAccount + SizedAccount {
+    /// The data length after the next realloc of an account with `data_len` bytes
+    fn next_data_len(data_len: usize) -> Result<usize, ProgramError> {
+        guard!(data_len < Self::SIZE, ElusivError::InvalidAccountState);
+        Ok(std::cmp::min(
+            Self::SIZE,
+            data_len.saturating_add(MAX_PERMITTED_DATA_INCREASE),
+        ))
+    }
+
+    /// Grows the data of `account` by a single step, the signing `payer` funds the additional rent
+    /// - returns the new data length
+    fn realloc<'a>(
+        payer: &AccountInfo<'a>,
+        account: &AccountInfo<'a>,
+    ) -> Result<usize, ProgramError> {
+        guard!(payer.is_signer, ProgramError::MissingRequiredSignature);
+        let data_len = Self::next_data_len(account.data_len())?;
+
+        // The system program and the realloc are not available in unit tests
+        if cfg!(test) {
+            return Ok(data_len);
+        }
+
+        let lamports = Rent::get()?
+            .minimum_balance(data_len)
+            .saturating_sub(account.lamports());
+        if lamports > 0 {
+            guard!(payer.lamports() >= lamports, ElusivError::InsufficientFunds);
+            invoke(
+                &system_instruction::transfer(payer.key, account.key, lamports),
+                &[payer.clone(), account.clone()],
+            )?;
+        }
+
+        account.realloc(data_len, true)?;
+
+        Ok(data_len)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::macros::{account_info, parent_account};
+    use crate::macros::{account_info, parent_account, test_account_info, zero_program_account};
     use assert_matches::assert_matches;
     use borsh::BorshDeserialize;
     use elusiv_types::{split_child_account_data, BorshSerDeSized, ElusivOption};
@@ -62,6 +200,7 @@ mod tests {
         _data: &'a [u8],
         pub pubkeys: [ElusivOption<Pubkey>; CHILD_ACCOUNT_COUNT],
         pub accounts: Vec<Option<&'b AccountInfo<'t>>>,
+        pub child_count: usize,
     }
 
     impl<'a, 'b, 't> PDAAccount for TestParentAccount<'a, 'b, 't> {
@@ -89,6 +228,7 @@ mod tests {
                 _data,
                 pubkeys: [ElusivOption::None; CHILD_ACCOUNT_COUNT],
                 accounts: vec![None; CHILD_ACCOUNT_COUNT],
+                child_count: CHILD_ACCOUNT_COUNT,
             })
         }
     }
@@ -97,6 +237,10 @@ mod tests {
         const COUNT: usize = CHILD_ACCOUNT_COUNT;
         type Child = TestChildAccount;
 
+        fn child_count(&self) -> usize {
+            self.child_count
+        }
+
         unsafe fn get_child_account_unsafe(
             &self,
             child_index: usize,
@@ -154,12 +298,139 @@ mod tests {
         }
     }
 
+    impl<'a, 'b, 't> ChildArrayAccount<'a, 'b, 't> for TestParentAccount<'a, 'b, 't> {
+        const VALUE_SIZE: usize = 5;
+        const VALUES_PER_CHILD_ACCOUNT: usize = TestChildAccount::INNER_SIZE / 5;
+    }
+
+    const CHILD_ARRAY_LEN: usize =
+        CHILD_ACCOUNT_COUNT * TestParentAccount::VALUES_PER_CHILD_ACCOUNT;
+
+    #[test]
+    fn test_child_array_position() {
+        let values_per_child_account = TestParentAccount::VALUES_PER_CHILD_ACCOUNT;
+        assert_eq!(TestParentAccount::child_array_position(0), (0, 0));
+        assert_eq!(
+            TestParentAccount::child_array_position(values_per_child_account - 1),
+            (0, values_per_child_account - 1)
+        );
+        assert_eq!(
+            TestParentAccount::child_array_position(values_per_child_account),
+            (1, 0)
+        );
+        assert_eq!(
+            TestParentAccount::child_array_position(CHILD_ARRAY_LEN - 1),
+            (CHILD_ACCOUNT_COUNT - 1, values_per_child_account - 1)
+        );
+    }
+
+    #[test]
+    fn test_execute_on_value() {
+        parent_account!(account, TestParentAccount);
+
+        for index in 0..CHILD_ARRAY_LEN {
+            account
+                .execute_on_value_mut(index, |value| value.fill(index as u8))
+                .unwrap();
+        }
+
+        for index in 0..CHILD_ARRAY_LEN {
+            assert_eq!(
+                account
+                    .execute_on_value(index, |value| value.to_vec())
+                    .unwrap(),
+                vec![index as u8; TestParentAccount::VALUE_SIZE]
+            );
+        }
+
+        // The values are stored in place after the child-account configs
+        let values_per_child_account = TestParentAccount::VALUES_PER_CHILD_ACCOUNT;
+        let value_size = TestParentAccount::VALUE_SIZE;
+        for child_index in 0..CHILD_ACCOUNT_COUNT {
+            let data = account.accounts[child_index].unwrap().data.borrow();
+            for local_index in 0..values_per_child_account {
+                let offset = ChildAccountConfig::SIZE + local_index * value_size;
+                assert_eq!(
+                    data[offset..offset + value_size],
+                    vec![(child_index * values_per_child_account + local_index) as u8; value_size]
+                );
+            }
+        }
+
+        // Out of bounds
+        assert!(account.execute_on_value(CHILD_ARRAY_LEN, |_| ()).is_err());
+        assert!(account
+            .execute_on_value_mut(CHILD_ARRAY_LEN, |_| ())
+            .is_err());
+    }
+
+    #[test]
+    fn test_execute_on_values() {
+        parent_account!(account, TestParentAccount);
+        let values_per_child_account = TestParentAccount::VALUES_PER_CHILD_ACCOUNT;
+
+        // Ranges within a single child-account, across one and across all child-account boundaries
+        for range in [
+            1..3,
+            values_per_child_account - 2..values_per_child_account + 2,
+            0..CHILD_ARRAY_LEN,
+        ] {
+            let mut indices = Vec::new();
+            account
+                .execute_on_values_mut(range.clone(), |index, value| {
+                    indices.push(index);
+                    value.fill(index as u8 + 1);
+                })
+                .unwrap();
+            assert_eq!(indices, range.clone().collect::<Vec<_>>());
+
+            let mut values = Vec::new();
+            account
+                .execute_on_values(range.clone(), |index, value| values.push((index, value[0])))
+                .unwrap();
+            assert_eq!(
+                values,
+                range
+                    .map(|index| (index, index as u8 + 1))
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        // Empty range
+        account
+            .execute_on_values(2..2, |_, _| panic!("empty range"))
+            .unwrap();
+
+        // Out of bounds
+        assert!(account
+            .execute_on_values(CHILD_ARRAY_LEN - 1..CHILD_ARRAY_LEN + 1, |_, _| {})
+            .is_err());
+        assert!(account
+            .execute_on_values_mut(CHILD_ARRAY_LEN - 1..CHILD_ARRAY_LEN + 1, |_, _| {})
+            .is_err());
+    }
+
     fn test_find(
         pubkey_is_setup: [bool; CHILD_ACCOUNT_COUNT],
         provided_accounts: Vec<Option<usize>>,
         expected_accounts: Vec<usize>,
+    ) {
+        test_find_with_child_count(
+            CHILD_ACCOUNT_COUNT,
+            pubkey_is_setup,
+            provided_accounts,
+            expected_accounts,
+        )
+    }
+
+    fn test_find_with_child_count(
+        child_count: usize,
+        pubkey_is_setup: [bool; CHILD_ACCOUNT_COUNT],
+        provided_accounts: Vec<Option<usize>>,
+        expected_accounts: Vec<usize>,
     ) {
         parent_account!(mut parent, TestParentAccount);
+        parent.child_count = child_count;
         for (i, &is_setup) in pubkey_is_setup.iter().enumerate() {
             if is_setup {
                 parent.set_child_pubkey(i, ElusivOption::Some(*parent.accounts[i].unwrap().key));
@@ -245,6 +516,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_child_accounts_with_child_count() {
+        // Accounts above the child count are ignored
+        test_find_with_child_count(
+            2,
+            [true, true, true],
+            vec![Some(0), Some(1), Some(2)],
+            vec![0, 1],
+        );
+
+        test_find_with_child_count(1, [false, true, true], vec![Some(1), Some(2)], vec![]);
+
+        test_find_with_child_count(
+            0,
+            [true, true, true],
+            vec![Some(0), Some(1), Some(2)],
+            vec![],
+        );
+    }
+
     #[test]
     fn test_unverified_account_info() {
         account_info!(account, Pubkey::new_unique());
@@ -260,4 +551,45 @@ mod tests {
 
         assert_matches!(unverified_account_info.get_safe(), Ok(_));
     }
+
+    #[test]
+    fn test_lifecycle_close() {
+        use crate::state::{governor::PoolAccount, proof::VerificationAccount};
+
+        test_account_info!(recipient);
+        test_account_info!(account);
+
+        assert_matches!(PoolAccount::close(&recipient, &account), Err(_));
+        assert_ne!(account.lamports(), 0);
+
+        assert_matches!(VerificationAccount::close(&recipient, &account), Ok(()));
+        assert_eq!(account.lamports(), 0);
+    }
+
+    #[test]
+    fn test_lifecycle_reopen() {
+        use crate::state::governor::PoolAccount;
+
+        test_account_info!(payer);
+        test_account_info!(account);
+
+        assert_matches!(
+            PoolAccount::reopen(&payer, &account, None, None, None),
+            Err(ProgramError::Custom(c)) if c == ElusivError::FeatureNotAvailable as u32
+        );
+    }
+
+    #[test]
+    fn test_lifecycle_seal() {
+        use crate::state::{governor::PoolAccount, vkey::VKeyAccount};
+
+        parent_account!(mut vkey_account, VKeyAccount);
+        assert!(!vkey_account.is_sealed());
+        vkey_account.seal().unwrap();
+        assert!(vkey_account.is_sealed());
+
+        zero_program_account!(mut pool_account, PoolAccount);
+        assert_matches!(pool_account.seal(), Err(_));
+        assert!(!pool_account.is_sealed());
+    }
 }
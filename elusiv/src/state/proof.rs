@@ -1,12 +1,14 @@
 use crate::bytes::{
     usize_as_u32_safe, BorshSerDeSized, BorshSerDeSizedEnum, ElusivOption, SizedType,
 };
+use crate::computation::PartialComputationDriver;
 use crate::fields::{G2HomProjective, Wrap, G1A, G2A};
+use crate::macros::assert_account_size;
 use crate::processor::{ProofRequest, MAX_MT_COUNT};
-use crate::proof::verifier::VerificationStep;
-use crate::state::program_account::PDAAccountData;
+use crate::proof::verifier::{BatchVerificationStep, VerificationStep};
+use crate::state::program_account::{Lifecycle, PDAAccountData};
 use crate::token::Lamports;
-use crate::types::{Lazy, LazyField, RawU256, U256};
+use crate::types::{BatchPayouts, Lazy, LazyField, RawU256, U256};
 use ark_bn254::{Fq, Fq12, Fq2, Fq6};
 use borsh::{BorshDeserialize, BorshSerialize};
 use elusiv_computation::RAM;
@@ -24,8 +26,14 @@ pub type RAMG2A<'a> = LazyRAM<'a, G2A, 1>;
 const MAX_PUBLIC_INPUTS_COUNT: usize = 14;
 const MAX_PREPARE_INPUTS_INSTRUCTIONS: usize = MAX_PUBLIC_INPUTS_COUNT * 10;
 
+/// The maximum number of proofs verified by a single [`BatchVerificationAccount`]
+pub const MAX_BATCH_VERIFICATION_PROOFS: usize = 8;
+
 /// Describes the state of the proof-verification initialization and finalization
-#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, EnumVariantIndex, Debug, Clone)]
+#[derive(
+    BorshDeserialize, BorshSerialize, BorshSerDeSized, EnumVariantIndex, PartialEq, Debug, Clone,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum VerificationState {
     // Init
     None,
@@ -36,6 +44,10 @@ pub enum VerificationState {
     InsertNullifiers,
     Finalized,
     Closed,
+
+    // Batch verification
+    /// The proof is part of a [`BatchVerificationAccount`] and can only be computed by the batch
+    Batched,
 }
 
 /// Account used for verifying proofs over the span of multiple transactions
@@ -43,6 +55,8 @@ pub enum VerificationState {
 /// # Note
 ///
 /// Exists only temporarily for verifying a single proof and is closed afterwards.
+/// Pooled accounts (see [`VerificationAccountPoolAccount`]) are instead zeroed and reused across proofs.
+/// Each instance is derived from the fee-payer and a `verification_account_index`, so concurrent verifications never share an account.
 #[elusiv_account(partial_computation: true, eager_type: true)]
 pub struct VerificationAccount {
     #[no_getter]
@@ -51,6 +65,7 @@ pub struct VerificationAccount {
 
     pub(crate) instruction: u32,
     pub(crate) round: u32,
+    pub last_update_slot: u64,
 
     pub prepare_inputs_instructions_count: u32,
     pub prepare_inputs_instructions: [u16; MAX_PREPARE_INPUTS_INSTRUCTIONS],
@@ -59,6 +74,10 @@ pub struct VerificationAccount {
     pub step: VerificationStep,
     pub state: VerificationState,
 
+    // Pooled accounts are only closed by the program, `is_acquired` is required for a verification
+    pub is_pooled: bool,
+    pub is_acquired: bool,
+
     // Public inputs
     pub public_input: [RawU256; MAX_PUBLIC_INPUTS_COUNT],
 
@@ -100,6 +119,13 @@ pub struct VerificationAccount {
     pub tree_indices: [u32; MAX_MT_COUNT],
 }
 
+assert_account_size!(VerificationAccount, 6847);
+
+impl<'a> Lifecycle for VerificationAccount<'a> {
+    const CLOSEABLE: bool = true;
+    const REUSABLE: bool = true;
+}
+
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Default)]
 #[cfg_attr(feature = "elusiv-client", derive(Debug))]
 pub struct VerificationAccountData {
@@ -107,6 +133,12 @@ pub struct VerificationAccountData {
     pub fee_payer_account: RawU256,
     pub recipient_wallet: ElusivOption<RawU256>,
 
+    /// Set if `recipient_wallet` is a [`crate::state::withdrawal::PendingWithdrawalAccount`] (which locks the withdrawn amount)
+    pub recipient_is_pending_withdrawal: bool,
+
+    /// Recipients splitting the withdrawn amount (instead of `recipient_wallet`)
+    pub batch_payouts: ElusivOption<BatchPayouts>,
+
     /// Flag that can be used to skip the renting of a nullifier_pda (if it already exists)
     pub skip_nullifier_pda: bool,
 
@@ -308,6 +340,142 @@ where
     }
 }
 
+/// Account used for verifying the proofs of multiple [`VerificationAccount`]s (of the same fee-payer and vkey) at once
+///
+/// # Note
+///
+/// Each proof is computed up to its final exponentiation by its own [`VerificationAccount`].
+/// The batch then combines the miller loop outputs `f_i` into `prod(f_i^r_i)` with pseudo-random scalars `r_i`
+/// and performs a single final exponentiation (using the RAM of the first [`VerificationAccount`]).
+#[elusiv_account(partial_computation: true)]
+pub struct BatchVerificationAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub(crate) instruction: u32,
+    pub(crate) round: u32,
+    pub last_update_slot: u64,
+
+    pub vkey_id: u32,
+    pub step: BatchVerificationStep,
+
+    pub proofs_count: u8,
+    pub verification_account_indices: [u8; MAX_BATCH_VERIFICATION_PROOFS],
+    pub is_finalized: [bool; MAX_BATCH_VERIFICATION_PROOFS],
+
+    // Computation values
+    pub(crate) scalars: [u64; MAX_BATCH_VERIFICATION_PROOFS],
+    pub(crate) miller_loop_outputs: [Wrap<Fq12>; MAX_BATCH_VERIFICATION_PROOFS],
+    #[lazy]
+    pub(crate) accumulator: Lazy<'a, Wrap<Fq12>>,
+    #[lazy]
+    pub(crate) expected: Lazy<'a, Wrap<Fq12>>,
+
+    // If true, all proofs of the batch can be finalized
+    pub is_verified: ElusivOption<bool>,
+}
+
+assert_account_size!(BatchVerificationAccount, 3946);
+
+impl<'a> Lifecycle for BatchVerificationAccount<'a> {
+    const CLOSEABLE: bool = true;
+    const REUSABLE: bool = true;
+}
+
+impl<'a> BatchVerificationAccount<'a> {
+    pub fn setup(&mut self, vkey_id: u32, slot: u64) {
+        self.start_computation(slot);
+        self.set_vkey_id(&vkey_id);
+        self.set_step(&BatchVerificationStep::MultiExponentiation);
+        self.set_proofs_count(&0);
+        self.set_is_verified(&ElusivOption::None);
+    }
+
+    /// Adds the miller loop output of a [`VerificationAccount`] to the batch
+    pub fn add_proof(&mut self, verification_account_index: u8, miller_loop_output: Fq12) {
+        let index = self.get_proofs_count() as usize;
+        self.set_verification_account_indices(index, &verification_account_index);
+        self.set_is_finalized(index, &false);
+        self.set_miller_loop_outputs(index, &Wrap(miller_loop_output));
+        self.set_proofs_count(&(index as u8 + 1));
+    }
+
+    /// The position of a [`VerificationAccount`] in the batch
+    pub fn position(&self, verification_account_index: u8) -> Option<usize> {
+        (0..self.get_proofs_count() as usize)
+            .find(|&i| self.get_verification_account_indices(i) == verification_account_index)
+    }
+
+    pub fn all_miller_loop_outputs(&self) -> Vec<Fq12> {
+        (0..self.get_proofs_count() as usize)
+            .map(|i| self.get_miller_loop_outputs(i).0)
+            .collect()
+    }
+
+    pub fn is_fully_finalized(&self) -> bool {
+        (0..self.get_proofs_count() as usize).all(|i| self.get_is_finalized(i))
+    }
+}
+
+/// The number of pre-allocated [`VerificationAccount`]s in a [`VerificationAccountPoolAccount`]
+pub const VERIFICATION_ACCOUNT_POOL_SIZE: usize = 8;
+
+/// Free-list of the pooled [`VerificationAccount`]s of a fee-payer
+///
+/// # Notes
+///
+/// - the pooled account of `slot` uses the `verification_account_index` [`crate::processor::pooled_verification_account_index`]
+/// - a slot is acquired for a single verification and can only be released (and zeroed) once the verification is closed
+#[elusiv_account]
+pub struct VerificationAccountPoolAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub slot_count: u8,
+    pub free_count: u8,
+    pub free_list: [u8; VERIFICATION_ACCOUNT_POOL_SIZE],
+}
+
+assert_account_size!(VerificationAccountPoolAccount, 12);
+
+impl<'a> Lifecycle for VerificationAccountPoolAccount<'a> {}
+
+impl<'a> VerificationAccountPoolAccount<'a> {
+    /// The next slot that can be acquired
+    pub fn next_free_slot(&self) -> Option<u8> {
+        match self.get_free_count() {
+            0 => None,
+            count => Some(self.get_free_list(count as usize - 1)),
+        }
+    }
+
+    pub fn is_free(&self, slot: u8) -> bool {
+        (0..self.get_free_count() as usize).any(|i| self.get_free_list(i) == slot)
+    }
+
+    pub fn release(&mut self, slot: u8) {
+        let count = self.get_free_count();
+        self.set_free_list(count as usize, &slot);
+        self.set_free_count(&(count + 1));
+    }
+
+    /// Removes `slot` from the free-list, returns false if the slot is not free
+    pub fn acquire(&mut self, slot: u8) -> bool {
+        let count = self.get_free_count() as usize;
+        match (0..count).find(|&i| self.get_free_list(i) == slot) {
+            Some(i) => {
+                let last = self.get_free_list(count - 1);
+                self.set_free_list(i, &last);
+                self.set_free_count(&(count as u8 - 1));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
 #[elusiv_account]
 pub struct NullifierDuplicateAccount {
     #[no_getter]
@@ -315,6 +483,13 @@ pub struct NullifierDuplicateAccount {
     pda_data: PDAAccountData,
 }
 
+assert_account_size!(NullifierDuplicateAccount, 2);
+
+impl<'a> Lifecycle for NullifierDuplicateAccount<'a> {
+    const CLOSEABLE: bool = true;
+    const REUSABLE: bool = true;
+}
+
 impl<'a> NullifierDuplicateAccount<'a> {
     pub fn associated_pubkey(nullifier_hashes: &[&RawU256]) -> Pubkey {
         let hashes: Vec<U256> = nullifier_hashes.iter().map(|n| n.skip_mr()).collect();
@@ -355,6 +530,7 @@ mod tests {
             hashed_inputs: u256_from_str_skip_mr("7777777"),
             recipient_is_associated_token_account: true,
             solana_pay_transfer: false,
+            encrypted_memo: None,
         };
         let request = ProofRequest::Send(public_inputs.clone());
         let data = VerificationAccountData {
@@ -418,6 +594,29 @@ mod tests {
         const SIZE: usize = u64::SIZE;
     }
 
+    #[test]
+    fn test_verification_account_pool() {
+        let mut data = vec![0; VerificationAccountPoolAccount::SIZE];
+        let mut pool = VerificationAccountPoolAccount::new(&mut data).unwrap();
+        assert_eq!(pool.next_free_slot(), None);
+        assert!(!pool.acquire(0));
+
+        for slot in 0..3 {
+            pool.release(slot);
+        }
+        assert_eq!(pool.next_free_slot(), Some(2));
+
+        assert!(pool.acquire(0));
+        assert!(!pool.acquire(0));
+        assert!(!pool.is_free(0));
+        assert!(pool.is_free(1));
+        assert!(pool.is_free(2));
+        assert_eq!(pool.get_free_count(), 2);
+
+        pool.release(0);
+        assert_eq!(pool.next_free_slot(), Some(0));
+    }
+
     #[test]
     fn test_lazy_ram() {
         let mut data = vec![0; u64::SIZE * 2];
@@ -1,13 +1,17 @@
 #![allow(dead_code)]
 
 use crate::bytes::*;
-use crate::commitment::commitments_per_batch;
-use crate::error::ElusivError::{InvalidFeeVersion, InvalidQueueAccess, QueueIsEmpty, QueueIsFull};
+use crate::commitment::{commitments_per_batch, MAX_HT_COMMITMENTS};
+use crate::error::ElusivError::{InvalidQueueAccess, QueueIsEmpty, QueueIsFull};
 use crate::macros::{elusiv_account, guard};
 use crate::processor::CommitmentHashRequest;
+use crate::state::program_account::{Lifecycle, ReallocatableAccount};
 use elusiv_types::{PDAAccountData, ProgramAccount};
 use solana_program::program_error::ProgramError;
 
+/// Maximum number of requests in the priority lane of the [`CommitmentQueue`] (a single batch of the maximum batching rate)
+pub const MAX_PRIORITY_LANE_COMMITMENTS: usize = MAX_HT_COMMITMENTS;
+
 /// Generates a [`QueueAccount`] and a [`Queue`] that implements the [`RingQueue`] trait
 macro_rules! queue_account {
     ($id: ident, $id_account: ident, $seed: literal, $size: literal, $ty_element: ty) => {
@@ -20,12 +24,20 @@ macro_rules! queue_account {
             head: u32,
             tail: u32,
             raw_data: [$ty_element; $size],
+
+            /// Wait-time statistics (in slots) of all processed elements
+            pub wait_count: u64,
+            pub wait_slots_sum: u64,
+            pub wait_slots_max: u64,
+
+            /// Number of elements (starting at `head`) in the priority lane
+            pub priority_count: u32,
         }
 
         #[cfg(test)]
         const_assert_eq!(
             <$id_account as elusiv_types::SizedAccount>::SIZE,
-            PDAAccountData::SIZE + (4 + 4) + <$ty_element>::SIZE * ($size)
+            PDAAccountData::SIZE + (4 + 4) + <$ty_element>::SIZE * ($size) + 3 * 8 + 4
         );
 
         #[cfg(test)]
@@ -65,6 +77,22 @@ macro_rules! queue_account {
                 self.account.set_raw_data(index, value)
             }
         }
+
+        impl<'a> $id_account<'a> {
+            /// Returns `true` if `head` and `tail` are valid indices into the ring buffer
+            pub fn has_valid_pointers(&self) -> bool {
+                self.get_head() < $size && self.get_tail() < $size
+            }
+
+            /// Accumulates the wait-time of a processed element
+            pub fn record_wait_slots(&mut self, wait_slots: u64) {
+                self.set_wait_count(&self.get_wait_count().saturating_add(1));
+                self.set_wait_slots_sum(&self.get_wait_slots_sum().saturating_add(wait_slots));
+                if wait_slots > self.get_wait_slots_max() {
+                    self.set_wait_slots_max(&wait_slots);
+                }
+            }
+        }
     };
 }
 
@@ -78,30 +106,92 @@ queue_account!(
     CommitmentQueue,
     CommitmentQueueAccount,
     b"commitment_queue",
-    240,
+    208,
+    CommitmentHashRequest
+);
+
+impl<'a> Lifecycle for CommitmentQueueAccount<'a> {}
+
+/// Queues created before the addition of fields are migrated with [`crate::processor::realloc_account`]
+/// - the new fields are zero-initialized (so migrated queues start with an empty priority lane)
+/// - only queues that have not wrapped around can be grown, see [`is_reallocatable_queue`]
+impl<'a> ReallocatableAccount for CommitmentQueueAccount<'a> {}
+
+/// Returns `true` if the ring buffer of a queue account with the (possibly outdated and shorter) `data` can be grown
+/// - the elements of an empty or not wrapped around queue (`head <= tail`) keep their indices modulo an increased size
+/// - `head` and `tail` precede the ring buffer, so their offsets are the same for all sizes
+pub fn is_reallocatable_queue(data: &[u8]) -> bool {
+    let offset = PDAAccountData::SIZE;
+    if data.len() < offset + 8 {
+        return true;
+    }
+
+    let head = u32::from_le_bytes(slice_to_array(&data[offset..offset + 4]));
+    let tail = u32::from_le_bytes(slice_to_array(&data[offset + 4..offset + 8]));
+    head <= tail
+}
+
+// Overflow queue absorbing commitments while the `CommitmentQueue` is full (drained back into it in FIFO order)
+queue_account!(
+    CommitmentSpillQueue,
+    CommitmentSpillQueueAccount,
+    b"commitment_spill_queue",
+    64,
     CommitmentHashRequest
 );
 
+impl<'a> Lifecycle for CommitmentSpillQueueAccount<'a> {}
+
 impl<'a, 'b> CommitmentQueue<'a, 'b> {
+    /// Enqueues a request into the priority lane, which precedes all other requests
+    /// - requests in the priority lane keep their order
+    pub fn enqueue_priority(&mut self, value: CommitmentHashRequest) -> Result<(), ProgramError> {
+        let head = self.get_head();
+        let priority_count = self.account.get_priority_count();
+        let next_head = (head + Self::SIZE - 1) % Self::SIZE;
+        guard!(next_head != self.get_tail(), QueueIsFull);
+        guard!(
+            (priority_count as usize) < MAX_PRIORITY_LANE_COMMITMENTS,
+            QueueIsFull
+        );
+
+        // The priority lane is moved by one element towards the front
+        for i in 0..priority_count {
+            let value = self.get_data(((head + i) % Self::SIZE) as usize);
+            self.set_data(((next_head + i) % Self::SIZE) as usize, &value);
+        }
+        self.set_data(((next_head + priority_count) % Self::SIZE) as usize, &value);
+
+        self.set_head(&next_head);
+        self.account.set_priority_count(&(priority_count + 1));
+
+        Ok(())
+    }
+
+    /// Removes the first `count` requests (the priority lane is drained first)
+    pub fn remove_batch(&mut self, count: u32) -> Result<(), ProgramError> {
+        self.remove(count)?;
+
+        let priority_count = self.account.get_priority_count();
+        self.account
+            .set_priority_count(&priority_count.saturating_sub(count));
+
+        Ok(())
+    }
+
     /// Returns the next batch of commitments to be hashed together
     pub fn next_batch(&self) -> Result<(Vec<CommitmentHashRequest>, u32), ProgramError> {
         let mut requests = Vec::new();
         let mut highest_batching_rate = 0;
         let mut commitment_count: usize = u32::MAX as usize;
-        let mut fee_version = None;
 
+        // A batch can contain requests of different fee-versions (see `batch_fee_version`)
         while requests.len() < commitment_count {
             let request = self.view(requests.len())?;
 
             highest_batching_rate = std::cmp::max(highest_batching_rate, request.min_batching_rate);
             commitment_count = commitments_per_batch(highest_batching_rate);
 
-            // Just a (hopefully always) redundant fee-check (depends on the fee upgrade logic)
-            if let Some(f) = fee_version {
-                guard!(f == request.fee_version, InvalidFeeVersion);
-            }
-            fee_version = Some(request.fee_version);
-
             requests.push(request);
         }
 
@@ -112,6 +202,12 @@ impl<'a, 'b> CommitmentQueue<'a, 'b> {
     }
 }
 
+/// The fee-version with which a batch of [`CommitmentHashRequest`]s is hashed
+/// - the oldest fee-version of the batch, so that requests queued before a fee-version change are hashed with the fee they paid for
+pub fn batch_fee_version(batch: &[CommitmentHashRequest]) -> Option<u32> {
+    batch.iter().map(|request| request.fee_version).min()
+}
+
 /// Ring-queue with a capacity of [`RingQueue::CAPACITY`] elements
 /// - works by having two pointers, `head` and `tail` and a some data storage with getter, setter
 /// - `head` points to the first element (first according to the FIFO definition)
@@ -450,6 +546,7 @@ mod tests {
                 commitment: [0; 32],
                 fee_version: 0,
                 min_batching_rate: 2,
+                enqueue_slot: 0,
             })
             .unwrap();
         }
@@ -464,6 +561,7 @@ mod tests {
                     commitment: fr_to_u256_le(&u64_to_scalar(i as u64)),
                     fee_version: 0,
                     min_batching_rate: if i == 0 { b as u32 } else { 0 },
+                    enqueue_slot: 0,
                 })
                 .unwrap();
             }
@@ -481,20 +579,83 @@ mod tests {
             }
         }
 
-        // Mismatching fee
+        // Mixed fee-versions (requests queued before and after a fee-version change)
         q.clear();
-        q.enqueue(CommitmentHashRequest {
-            commitment: [0; 32],
+        for fee_version in [1, 0] {
+            q.enqueue(CommitmentHashRequest {
+                commitment: [0; 32],
+                fee_version,
+                min_batching_rate: 1,
+                enqueue_slot: 0,
+            })
+            .unwrap();
+        }
+        let (batch, batching_rate) = q.next_batch().unwrap();
+        assert_eq!(batching_rate, 1);
+        assert_eq!(batch_fee_version(&batch), Some(0));
+        assert_eq!(batch_fee_version(&[]), None);
+    }
+
+    #[test]
+    fn test_priority_lane() {
+        let mut data = vec![0; <CommitmentQueueAccount as elusiv_types::SizedAccount>::SIZE];
+        let mut q = CommitmentQueueAccount::new(&mut data).unwrap();
+        let mut q = CommitmentQueue::new(&mut q);
+
+        let request = |i: u64| CommitmentHashRequest {
+            commitment: fr_to_u256_le(&u64_to_scalar(i)),
             fee_version: 0,
-            min_batching_rate: 1,
-        })
-        .unwrap();
-        q.enqueue(CommitmentHashRequest {
-            commitment: [0; 32],
-            fee_version: 1,
-            min_batching_rate: 1,
-        })
-        .unwrap();
-        assert_matches!(q.next_batch(), Err(_));
+            min_batching_rate: 0,
+            enqueue_slot: 0,
+        };
+
+        q.enqueue(request(1)).unwrap();
+        q.enqueue(request(2)).unwrap();
+        q.enqueue_priority(request(3)).unwrap();
+        q.enqueue_priority(request(4)).unwrap();
+
+        // Priority requests precede all other requests but keep their own order
+        assert_eq!(q.len(), 4);
+        assert_eq!(q.account.get_priority_count(), 2);
+        for (i, c) in [3, 4, 1, 2].iter().enumerate() {
+            assert_eq!(q.view(i).unwrap(), request(*c));
+        }
+
+        // The priority lane is drained first
+        q.remove_batch(3).unwrap();
+        assert_eq!(q.account.get_priority_count(), 0);
+        assert_eq!(q.view_first().unwrap(), request(2));
+
+        // Priority lane capacity
+        q.account
+            .set_priority_count(&(MAX_PRIORITY_LANE_COMMITMENTS as u32));
+        assert_matches!(q.enqueue_priority(request(5)), Err(_));
+    }
+
+    #[test]
+    fn test_has_valid_pointers() {
+        let mut data = vec![0; <CommitmentQueueAccount as elusiv_types::SizedAccount>::SIZE];
+        let mut q = CommitmentQueueAccount::new(&mut data).unwrap();
+        assert!(q.has_valid_pointers());
+
+        q.set_tail(&(CommitmentQueue::SIZE - 1));
+        assert!(q.has_valid_pointers());
+
+        q.set_head(&CommitmentQueue::SIZE);
+        assert!(!q.has_valid_pointers());
+    }
+
+    #[test]
+    fn test_record_wait_slots() {
+        let mut data = vec![0; <CommitmentQueueAccount as elusiv_types::SizedAccount>::SIZE];
+        let mut q = CommitmentQueueAccount::new(&mut data).unwrap();
+
+        q.record_wait_slots(10);
+        q.record_wait_slots(30);
+        q.record_wait_slots(20);
+
+        assert_eq!(q.get_wait_count(), 3);
+        assert_eq!(q.get_wait_slots_sum(), 60);
+        assert_eq!(q.get_wait_slots_max(), 30);
     }
 }
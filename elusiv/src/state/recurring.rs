@@ -0,0 +1,161 @@
+use super::program_account::{Lifecycle, PDAAccountData};
+use crate::error::ElusivError;
+use crate::macros::{assert_account_size, elusiv_account, guard};
+use crate::types::U256;
+use elusiv_utils::MATH_ERR;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+/// Maximum number of installments of a single [`RecurringPaymentAccount`]
+pub const MAX_RECURRING_INSTALLMENTS: usize = 12;
+
+/// Schedule of private payments released by a permissionless crank
+/// - each installment is bound to a proof-request prepared in advance by the owner (identified by the hash of the serialized request)
+/// - the installment with index `i` can be released at `first_release_slot + i * period_slots`
+///
+/// # Notes
+///
+/// The transfer itself is performed by the regular proof verification of the released request.
+/// The schedule is enforced by binding the proof to the account (see [`crate::types::RecurringPaymentBinding`]),
+/// since the finalization of a bound proof requires its installment to be released.
+/// Requests should reference roots of closed MTs, since the roots of the active MT are only valid for a limited time.
+#[elusiv_account(eager_type: true)]
+pub struct RecurringPaymentAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub owner: Pubkey,
+    pub period_slots: u64,
+    pub next_release_slot: u64,
+    pub installments_count: u32,
+    pub released_count: u32,
+
+    /// Hashes of the prepared proof-requests
+    installments: [U256; MAX_RECURRING_INSTALLMENTS],
+}
+
+assert_account_size!(RecurringPaymentAccount, 442);
+
+impl<'a> Lifecycle for RecurringPaymentAccount<'a> {
+    const CLOSEABLE: bool = true;
+    const REUSABLE: bool = true;
+}
+
+impl<'a> RecurringPaymentAccount<'a> {
+    pub fn setup(
+        &mut self,
+        owner: &Pubkey,
+        period_slots: u64,
+        first_release_slot: u64,
+        installments: &[U256],
+    ) -> Result<(), ProgramError> {
+        guard!(period_slots > 0, ElusivError::InvalidInstructionData);
+        guard!(
+            !installments.is_empty() && installments.len() <= MAX_RECURRING_INSTALLMENTS,
+            ElusivError::InvalidInstructionData
+        );
+
+        self.set_owner(owner);
+        self.set_period_slots(&period_slots);
+        self.set_next_release_slot(&first_release_slot);
+        self.set_installments_count(&(installments.len() as u32));
+        self.set_released_count(&0);
+
+        for (i, installment) in installments.iter().enumerate() {
+            self.set_installments(i, installment);
+        }
+
+        Ok(())
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.get_released_count() >= self.get_installments_count()
+    }
+
+    /// Releases the next installment, if it is due at `slot` and matches the proof-request hash `installment`
+    /// - returns the index of the released installment
+    pub fn release_next_installment(
+        &mut self,
+        slot: u64,
+        installment: &U256,
+    ) -> Result<u32, ProgramError> {
+        guard!(
+            !self.is_completed(),
+            ElusivError::RecurringPaymentIsCompleted
+        );
+
+        let next_release_slot = self.get_next_release_slot();
+        guard!(
+            slot >= next_release_slot,
+            ElusivError::InstallmentIsNotYetDue
+        );
+
+        let index = self.get_released_count();
+        guard!(
+            self.get_installments(index as usize) == *installment,
+            ElusivError::InvalidPublicInputs
+        );
+
+        self.set_released_count(&(index + 1));
+        self.set_next_release_slot(
+            &next_release_slot
+                .checked_add(self.get_period_slots())
+                .ok_or(MATH_ERR)?,
+        );
+
+        Ok(index)
+    }
+
+    /// Returns true if the installment with the proof-request hash `installment` has already been released
+    pub fn is_released(&self, installment: &U256) -> bool {
+        (0..self.get_released_count() as usize).any(|i| self.get_installments(i) == *installment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_setup() {
+        zero_program_account!(mut account, RecurringPaymentAccount);
+        let owner = Pubkey::new_unique();
+
+        assert_matches!(account.setup(&owner, 0, 0, &[[1; 32]]), Err(_));
+        assert_matches!(account.setup(&owner, 1, 0, &[]), Err(_));
+        assert_matches!(
+            account.setup(&owner, 1, 0, &[[1; 32]; MAX_RECURRING_INSTALLMENTS + 1]),
+            Err(_)
+        );
+
+        account.setup(&owner, 10, 5, &[[1; 32], [2; 32]]).unwrap();
+        assert_eq!(account.get_owner(), owner);
+        assert_eq!(account.get_period_slots(), 10);
+        assert_eq!(account.get_next_release_slot(), 5);
+        assert_eq!(account.get_installments_count(), 2);
+        assert!(!account.is_completed());
+    }
+
+    #[test]
+    fn test_release_next_installment() {
+        zero_program_account!(mut account, RecurringPaymentAccount);
+        account
+            .setup(&Pubkey::new_unique(), 10, 5, &[[1; 32], [2; 32]])
+            .unwrap();
+
+        assert_matches!(account.release_next_installment(4, &[1; 32]), Err(_));
+        assert_matches!(account.release_next_installment(5, &[2; 32]), Err(_));
+        assert_eq!(account.release_next_installment(5, &[1; 32]).unwrap(), 0);
+        assert!(account.is_released(&[1; 32]));
+        assert!(!account.is_released(&[2; 32]));
+
+        assert_matches!(account.release_next_installment(14, &[2; 32]), Err(_));
+        assert_eq!(account.release_next_installment(15, &[2; 32]).unwrap(), 1);
+        assert!(account.is_released(&[2; 32]));
+        assert!(account.is_completed());
+
+        assert_matches!(account.release_next_installment(u64::MAX, &[2; 32]), Err(_));
+    }
+}
@@ -0,0 +1,274 @@
+use super::program_account::{Lifecycle, PDAAccountData};
+use crate::macros::{assert_account_size, elusiv_account};
+use crate::token::SPL_TOKEN_COUNT;
+use borsh::{BorshDeserialize, BorshSerialize};
+use elusiv_derive::BorshSerDeSized;
+use solana_program::program_error::ProgramError;
+use std::cmp::Ordering;
+
+/// Number of tokens tracked by the [`StatsAccount`] (Lamports and all SPL-Tokens)
+pub const STATS_TOKEN_COUNT: usize = SPL_TOKEN_COUNT + 1;
+
+/// Global protocol statistics for dashboards
+///
+/// # Notes
+///
+/// All values are updated using saturating arithmetic, so the statistics can never cause a transaction to fail.
+/// The TVL only accounts for deposited and withdrawn amounts (fees are not included).
+#[elusiv_account(eager_type: true)]
+pub struct StatsAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub deposit_count: u64,
+    pub withdrawal_count: u64,
+
+    /// Deposited amounts per token-id
+    pub total_deposits: [u64; STATS_TOKEN_COUNT],
+
+    /// Withdrawn amounts per token-id
+    pub total_withdrawals: [u64; STATS_TOKEN_COUNT],
+
+    /// Total value locked per token-id
+    pub tvl: [u64; STATS_TOKEN_COUNT],
+
+    /// Amount of commitments inserted into all MTs
+    pub commitment_count: u64,
+
+    /// Amount of inserted nullifier-hashes
+    pub nullifier_count: u64,
+}
+
+assert_account_size!(StatsAccount, 106);
+
+impl<'a> Lifecycle for StatsAccount<'a> {}
+
+impl<'a> StatsAccount<'a> {
+    pub fn add_deposit(&mut self, token_id: u16, amount: u64) {
+        self.set_deposit_count(&self.get_deposit_count().saturating_add(1));
+
+        let token_id = token_id as usize;
+        if token_id < STATS_TOKEN_COUNT {
+            self.set_total_deposits(
+                token_id,
+                &self.get_total_deposits(token_id).saturating_add(amount),
+            );
+            self.set_tvl(token_id, &self.get_tvl(token_id).saturating_add(amount));
+        }
+    }
+
+    pub fn add_withdrawal(&mut self, token_id: u16, amount: u64) {
+        self.set_withdrawal_count(&self.get_withdrawal_count().saturating_add(1));
+
+        let token_id = token_id as usize;
+        if token_id < STATS_TOKEN_COUNT {
+            self.set_total_withdrawals(
+                token_id,
+                &self.get_total_withdrawals(token_id).saturating_add(amount),
+            );
+            self.set_tvl(token_id, &self.get_tvl(token_id).saturating_sub(amount));
+        }
+    }
+
+    pub fn add_commitments(&mut self, count: u64) {
+        self.set_commitment_count(&self.get_commitment_count().saturating_add(count));
+    }
+
+    pub fn add_nullifier(&mut self) {
+        self.set_nullifier_count(&self.get_nullifier_count().saturating_add(1));
+    }
+}
+
+/// Number of epochs tracked by the [`EpochMetricsAccount`]
+pub const EPOCH_METRICS_COUNT: usize = 16;
+
+/// Returns the current epoch
+pub fn current_epoch() -> Result<u64, ProgramError> {
+    use solana_program::sysvar::{clock::Clock, Sysvar};
+    Ok(Clock::get()?.epoch)
+}
+
+/// Deposit and withdrawal activity during a single epoch
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct EpochMetrics {
+    pub epoch: u64,
+    pub deposit_count: u32,
+    pub withdrawal_count: u32,
+
+    /// Deposited amounts per token-id
+    pub deposit_volume: [u64; STATS_TOKEN_COUNT],
+
+    /// Withdrawn amounts per token-id
+    pub withdrawal_volume: [u64; STATS_TOKEN_COUNT],
+}
+
+impl EpochMetrics {
+    fn new(epoch: u64) -> Self {
+        EpochMetrics {
+            epoch,
+            ..Default::default()
+        }
+    }
+}
+
+/// Rotating buffer containing the [`EpochMetrics`] of the last [`EPOCH_METRICS_COUNT`] epochs
+/// - the metrics of `epoch` are stored at `epoch % EPOCH_METRICS_COUNT` and overwritten once a newer epoch starts
+#[elusiv_account(eager_type: true)]
+pub struct EpochMetricsAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    metrics: [EpochMetrics; EPOCH_METRICS_COUNT],
+}
+
+assert_account_size!(EpochMetricsAccount, 1026);
+
+impl<'a> Lifecycle for EpochMetricsAccount<'a> {}
+
+impl<'a> EpochMetricsAccount<'a> {
+    /// Returns the metrics of `epoch`, if they are still stored
+    pub fn epoch_metrics(&self, epoch: u64) -> Option<EpochMetrics> {
+        let metrics = self.get_metrics(Self::index(epoch));
+        if metrics.epoch != epoch {
+            return None;
+        }
+
+        Some(metrics)
+    }
+
+    /// Returns the deposited amount of `token_id` during `epoch` (zero if the metrics are no longer stored)
+    pub fn deposit_volume(&self, epoch: u64, token_id: u16) -> u64 {
+        self.epoch_metrics(epoch)
+            .and_then(|metrics| metrics.deposit_volume.get(token_id as usize).copied())
+            .unwrap_or(0)
+    }
+
+    pub fn add_deposit(&mut self, epoch: u64, token_id: u16, amount: u64) {
+        self.update(epoch, |metrics| {
+            metrics.deposit_count = metrics.deposit_count.saturating_add(1);
+            if let Some(volume) = metrics.deposit_volume.get_mut(token_id as usize) {
+                *volume = volume.saturating_add(amount);
+            }
+        })
+    }
+
+    pub fn add_withdrawal(&mut self, epoch: u64, token_id: u16, amount: u64) {
+        self.update(epoch, |metrics| {
+            metrics.withdrawal_count = metrics.withdrawal_count.saturating_add(1);
+            if let Some(volume) = metrics.withdrawal_volume.get_mut(token_id as usize) {
+                *volume = volume.saturating_add(amount);
+            }
+        })
+    }
+
+    fn update<F: FnOnce(&mut EpochMetrics)>(&mut self, epoch: u64, f: F) {
+        let index = Self::index(epoch);
+        let mut metrics = self.get_metrics(index);
+
+        // Metrics of older epochs are overwritten
+        match metrics.epoch.cmp(&epoch) {
+            Ordering::Less => metrics = EpochMetrics::new(epoch),
+            Ordering::Greater => return,
+            Ordering::Equal => {}
+        }
+
+        f(&mut metrics);
+        self.set_metrics(index, &metrics);
+    }
+
+    fn index(epoch: u64) -> usize {
+        (epoch % EPOCH_METRICS_COUNT as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+    use crate::token::{LAMPORTS_TOKEN_ID, USDC_TOKEN_ID};
+
+    #[test]
+    fn test_deposits_and_withdrawals() {
+        zero_program_account!(mut stats, StatsAccount);
+
+        stats.add_deposit(LAMPORTS_TOKEN_ID, 100);
+        stats.add_deposit(USDC_TOKEN_ID, 50);
+        stats.add_deposit(USDC_TOKEN_ID, 25);
+        stats.add_withdrawal(USDC_TOKEN_ID, 30);
+
+        assert_eq!(stats.get_deposit_count(), 3);
+        assert_eq!(stats.get_withdrawal_count(), 1);
+        assert_eq!(stats.get_total_deposits(LAMPORTS_TOKEN_ID as usize), 100);
+        assert_eq!(stats.get_total_deposits(USDC_TOKEN_ID as usize), 75);
+        assert_eq!(stats.get_total_withdrawals(USDC_TOKEN_ID as usize), 30);
+        assert_eq!(stats.get_tvl(LAMPORTS_TOKEN_ID as usize), 100);
+        assert_eq!(stats.get_tvl(USDC_TOKEN_ID as usize), 45);
+
+        // The TVL saturates (e.g. for funds deposited before the stats-account existed)
+        stats.add_withdrawal(LAMPORTS_TOKEN_ID, 101);
+        assert_eq!(stats.get_tvl(LAMPORTS_TOKEN_ID as usize), 0);
+
+        // Unknown tokens are only counted
+        stats.add_deposit(STATS_TOKEN_COUNT as u16, 1);
+        assert_eq!(stats.get_deposit_count(), 4);
+    }
+
+    #[test]
+    fn test_commitments_and_nullifiers() {
+        zero_program_account!(mut stats, StatsAccount);
+
+        stats.add_commitments(4);
+        stats.add_commitments(2);
+        stats.add_nullifier();
+
+        assert_eq!(stats.get_commitment_count(), 6);
+        assert_eq!(stats.get_nullifier_count(), 1);
+    }
+
+    #[test]
+    fn test_epoch_metrics() {
+        zero_program_account!(mut account, EpochMetricsAccount);
+
+        account.add_deposit(1, LAMPORTS_TOKEN_ID, 100);
+        account.add_deposit(1, USDC_TOKEN_ID, 10);
+        account.add_withdrawal(1, USDC_TOKEN_ID, 5);
+        account.add_deposit(2, LAMPORTS_TOKEN_ID, 1);
+
+        let metrics = account.epoch_metrics(1).unwrap();
+        assert_eq!(metrics.deposit_count, 2);
+        assert_eq!(metrics.withdrawal_count, 1);
+        assert_eq!(metrics.deposit_volume[LAMPORTS_TOKEN_ID as usize], 100);
+        assert_eq!(metrics.deposit_volume[USDC_TOKEN_ID as usize], 10);
+        assert_eq!(metrics.withdrawal_volume[USDC_TOKEN_ID as usize], 5);
+
+        assert_eq!(account.epoch_metrics(2).unwrap().deposit_count, 1);
+        assert_eq!(account.epoch_metrics(3), None);
+
+        assert_eq!(account.deposit_volume(1, LAMPORTS_TOKEN_ID), 100);
+        assert_eq!(account.deposit_volume(1, USDC_TOKEN_ID), 10);
+        assert_eq!(account.deposit_volume(3, LAMPORTS_TOKEN_ID), 0);
+    }
+
+    #[test]
+    fn test_epoch_metrics_rotation() {
+        zero_program_account!(mut account, EpochMetricsAccount);
+        let epoch = EPOCH_METRICS_COUNT as u64 + 1;
+
+        account.add_deposit(1, LAMPORTS_TOKEN_ID, 100);
+        account.add_deposit(epoch, LAMPORTS_TOKEN_ID, 1);
+
+        assert_eq!(account.epoch_metrics(1), None);
+        assert_eq!(account.deposit_volume(1, LAMPORTS_TOKEN_ID), 0);
+        let metrics = account.epoch_metrics(epoch).unwrap();
+        assert_eq!(metrics.deposit_count, 1);
+        assert_eq!(metrics.deposit_volume[LAMPORTS_TOKEN_ID as usize], 1);
+
+        // Older epochs cannot overwrite newer ones
+        account.add_withdrawal(1, LAMPORTS_TOKEN_ID, 1);
+        assert_eq!(account.epoch_metrics(1), None);
+        assert_eq!(account.epoch_metrics(epoch).unwrap().withdrawal_count, 0);
+    }
+}
@@ -1,6 +1,7 @@
 use super::program_account::*;
 use crate::bytes::*;
-use crate::macros::{elusiv_account, two_pow};
+use crate::macros::{assert_account_size, elusiv_account, two_pow};
+use crate::token::TokenID;
 use crate::types::U256;
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::entrypoint::ProgramResult;
@@ -39,12 +40,17 @@ impl ChildAccount for StorageChildAccount {
     const INNER_SIZE: usize = VALUES_PER_STORAGE_SUB_ACCOUNT * U256::SIZE;
 }
 
+/// The last child-account only needs to store the remaining values of the MT
+const LAST_CHILD_ACCOUNT_SIZE: usize = child_account_size(
+    (MT_SIZE - (ACCOUNTS_COUNT - 1) * VALUES_PER_STORAGE_SUB_ACCOUNT) * U256::SIZE,
+);
+
 /// The [`StorageAccount`] contains the active MT that stores new commitments
 ///
 /// # Note
 ///
 /// The MT is stored linearly as an array with the first element being the root.
-#[elusiv_account(parent_account: { child_account_count: ACCOUNTS_COUNT, child_account: StorageChildAccount }, eager_type: true)]
+#[elusiv_account(parent_account: { child_account_count: ACCOUNTS_COUNT, child_account: StorageChildAccount, last_child_account_size: LAST_CHILD_ACCOUNT_SIZE }, eager_type: true)]
 pub struct StorageAccount {
     #[no_getter]
     #[no_setter]
@@ -65,6 +71,26 @@ pub struct StorageAccount {
     pub mt_roots_count: u32, // required since we batch insert commitments
 }
 
+assert_account_size!(StorageAccount, 4043);
+
+/// The [`PDAOffset`] of the accounts forming the shielded pool of `token_id`
+/// - these are the [`StorageAccount`], `CommitmentQueueAccount`, `CommitmentSpillQueueAccount`, `CommitmentHashingAccount` and `StateSummaryAccount`
+/// - the lamports pool uses the [`None`] offset (single instance accounts), every other token uses its `token_id`
+pub fn token_pool_offset(token_id: TokenID) -> PDAOffset {
+    if token_id == 0 {
+        None
+    } else {
+        Some(token_id as u32)
+    }
+}
+
+impl<'a, 'b, 't> Lifecycle for StorageAccount<'a, 'b, 't> {}
+
+impl<'a, 'b, 't> ChildArrayAccount<'a, 'b, 't> for StorageAccount<'a, 'b, 't> {
+    const VALUE_SIZE: usize = U256::SIZE;
+    const VALUES_PER_CHILD_ACCOUNT: usize = VALUES_PER_STORAGE_SUB_ACCOUNT;
+}
+
 impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
     pub fn reset(&mut self) {
         self.set_next_commitment_ptr(&0);
@@ -75,16 +101,16 @@ impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
         }
     }
 
+    /// MTs are archived in order of their indices
+    pub fn is_archived(&self, mt_index: u32) -> bool {
+        mt_index < self.get_archived_count()
+    }
+
     pub fn is_full(&self) -> bool {
         let ptr = self.get_next_commitment_ptr() as usize;
         ptr >= MT_COMMITMENT_COUNT
     }
 
-    fn account_and_local_index(&self, index: usize) -> (usize, usize) {
-        let account_index = index / VALUES_PER_STORAGE_SUB_ACCOUNT;
-        (account_index, index % VALUES_PER_STORAGE_SUB_ACCOUNT)
-    }
-
     /// `level`: `0` is the root level, `MT_HEIGHT` the commitment level
     pub fn get_node(&self, index: usize, level: usize) -> Result<U256, ProgramError> {
         assert!(level <= MT_HEIGHT as usize);
@@ -95,13 +121,8 @@ impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
         if use_default_value(index, level, ptr) {
             Ok(EMPTY_TREE[MT_HEIGHT as usize - level])
         } else {
-            let (account_index, local_index) =
-                self.account_and_local_index(mt_array_index(index, level));
-            let result = self.execute_on_child_account(account_index, |data| {
-                U256::try_from_slice(
-                    &data[local_index * U256::SIZE..(local_index + 1) * U256::SIZE],
-                )
-            })??;
+            let result =
+                self.execute_on_value(mt_array_index(index, level), U256::try_from_slice)??;
 
             Ok(result)
         }
@@ -110,11 +131,8 @@ impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
     pub fn set_node(&mut self, value: &U256, index: usize, level: usize) -> ProgramResult {
         assert!(level <= MT_HEIGHT as usize);
 
-        let (account_index, local_index) =
-            self.account_and_local_index(mt_array_index(index, level));
-        self.execute_on_child_account_mut(account_index, |data| {
-            let mut slice = &mut data[local_index * U256::SIZE..(local_index + 1) * U256::SIZE];
-            BorshSerialize::serialize(value, &mut slice)
+        self.execute_on_value_mut(mt_array_index(index, level), |mut data| {
+            BorshSerialize::serialize(value, &mut data)
         })??;
 
         Ok(())
@@ -124,18 +142,28 @@ impl<'a, 'b, 't> StorageAccount<'a, 'b, 't> {
         self.get_node(0, 0)
     }
 
-    /// A root is valid if it's the current root or inside of the active_mt_root_history array
-    pub fn is_root_valid(&self, root: &U256) -> bool {
-        let max_history_roots =
-            std::cmp::min(self.get_mt_roots_count() as usize, HISTORY_ARRAY_SIZE);
+    /// Inserts `root` into the ring buffer of the last [`HISTORY_ARRAY_SIZE`] roots of the active MT
+    pub fn push_root_history(&mut self, root: &U256) {
+        let roots_count = self.get_mt_roots_count();
+        self.set_active_mt_root_history(roots_count as usize % HISTORY_ARRAY_SIZE, root);
+        self.set_mt_roots_count(&(roots_count + 1));
+    }
 
-        // TODO: remove this, has become redundant
+    /// A root is valid if it's the current root or one of the last [`HISTORY_ARRAY_SIZE`] roots of the active MT
+    /// - this allows proofs to be verified after further commitment batches have been inserted
+    pub fn is_root_valid(&self, root: &U256) -> bool {
         if let Ok(current_root) = self.get_root() {
-            return *root == current_root;
+            if *root == current_root {
+                return true;
+            }
         }
 
-        max_history_roots > 0
-            && contains(root, &self.active_mt_root_history[..max_history_roots * 32])
+        let history_roots = std::cmp::min(self.get_mt_roots_count() as usize, HISTORY_ARRAY_SIZE);
+        history_roots > 0
+            && contains(
+                root,
+                &self.active_mt_root_history[..history_roots * U256::SIZE],
+            )
     }
 
     #[allow(clippy::needless_range_loop)]
@@ -288,6 +316,112 @@ mod tests {
         assert_eq!(6, mt_array_index(3, 2));
     }
 
+    #[test]
+    fn test_token_pool_offset() {
+        assert_eq!(token_pool_offset(0), None);
+        assert_eq!(token_pool_offset(1), Some(1));
+        assert_eq!(token_pool_offset(2), Some(2));
+
+        assert_eq!(
+            StorageAccount::find(token_pool_offset(0)),
+            StorageAccount::FIRST_PDA
+        );
+        assert_ne!(
+            StorageAccount::find(token_pool_offset(1)).0,
+            StorageAccount::find(token_pool_offset(2)).0
+        );
+    }
+
+    #[test]
+    fn test_child_account_size() {
+        for i in 0..ACCOUNTS_COUNT - 1 {
+            assert_eq!(
+                StorageAccount::child_account_size(i),
+                StorageChildAccount::SIZE
+            );
+        }
+
+        // The last child-account stores the remaining values
+        let last_size = StorageAccount::child_account_size(ACCOUNTS_COUNT - 1);
+        assert!(last_size < StorageChildAccount::SIZE);
+        assert_eq!(
+            (ACCOUNTS_COUNT - 1) * VALUES_PER_STORAGE_SUB_ACCOUNT
+                + (last_size - ChildAccountConfig::SIZE) / U256::SIZE,
+            MT_SIZE
+        );
+
+        // All nodes can be accessed
+        parent_account!(mut storage_account, StorageAccount);
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
+        let last_index = MT_COMMITMENT_COUNT - 1;
+        storage_account
+            .set_node(&[1; 32], last_index, MT_HEIGHT as usize)
+            .unwrap();
+        assert_eq!(
+            storage_account
+                .get_node(last_index, MT_HEIGHT as usize)
+                .unwrap(),
+            [1; 32]
+        );
+    }
+
+    #[test]
+    fn test_child_account_boundaries() {
+        parent_account!(mut storage_account, StorageAccount);
+        storage_account.set_next_commitment_ptr(&(MT_COMMITMENT_COUNT as u32));
+
+        // Inverse of `mt_array_index`
+        fn node(array_index: usize) -> (usize, usize) {
+            let level = (usize::BITS - 1 - (array_index + 1).leading_zeros()) as usize;
+            (array_index + 1 - (1 << level), level)
+        }
+
+        for account_index in 1..ACCOUNTS_COUNT {
+            let boundary = account_index * VALUES_PER_STORAGE_SUB_ACCOUNT;
+            assert_eq!(
+                StorageAccount::child_array_position(boundary - 1),
+                (account_index - 1, VALUES_PER_STORAGE_SUB_ACCOUNT - 1)
+            );
+            assert_eq!(
+                StorageAccount::child_array_position(boundary),
+                (account_index, 0)
+            );
+
+            // The last value of the previous and the first value of the next child-account are independent
+            let (last_index, last_level) = node(boundary - 1);
+            let (first_index, first_level) = node(boundary);
+            assert_eq!(mt_array_index(last_index, last_level), boundary - 1);
+            assert_eq!(mt_array_index(first_index, first_level), boundary);
+
+            let last_value = [account_index as u8; 32];
+            let first_value = [!(account_index as u8); 32];
+            storage_account
+                .set_node(&last_value, last_index, last_level)
+                .unwrap();
+            storage_account
+                .set_node(&first_value, first_index, first_level)
+                .unwrap();
+
+            assert_eq!(
+                storage_account.get_node(last_index, last_level).unwrap(),
+                last_value
+            );
+            assert_eq!(
+                storage_account.get_node(first_index, first_level).unwrap(),
+                first_value
+            );
+
+            // Both values are visited in place by a range across the boundary
+            let mut values = Vec::new();
+            storage_account
+                .execute_on_values(boundary - 1..boundary + 1, |_, value| {
+                    values.push(U256::try_from_slice(value).unwrap())
+                })
+                .unwrap();
+            assert_eq!(values, vec![last_value, first_value]);
+        }
+    }
+
     #[test]
     fn test_empty_root_raw() {
         assert_eq!(empty_root_raw().reduce(), EMPTY_TREE[MT_HEIGHT as usize]);
@@ -438,4 +572,29 @@ mod tests {
         assert!(storage_account.is_root_valid(&EMPTY_TREE[MT_HEIGHT as usize]));
         assert!(!storage_account.is_root_valid(&[0; 32]));
     }
+
+    #[test]
+    fn test_root_history() {
+        parent_account!(mut storage_account, StorageAccount);
+
+        let roots_count = HISTORY_ARRAY_SIZE + 50;
+        for i in 1..=roots_count {
+            storage_account.push_root_history(&[i as u8; 32]);
+        }
+        assert_eq!(storage_account.get_mt_roots_count(), roots_count as u32);
+
+        // Only the last `HISTORY_ARRAY_SIZE` roots are valid
+        for i in 1..=roots_count {
+            assert_eq!(
+                storage_account.is_root_valid(&[i as u8; 32]),
+                i > roots_count - HISTORY_ARRAY_SIZE
+            );
+        }
+
+        // The current root is always valid
+        assert!(storage_account.is_root_valid(&EMPTY_TREE[MT_HEIGHT as usize]));
+
+        storage_account.reset();
+        assert!(!storage_account.is_root_valid(&[roots_count as u8; 32]));
+    }
 }
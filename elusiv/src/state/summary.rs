@@ -0,0 +1,294 @@
+use super::program_account::{Lifecycle, PDAAccountData};
+use super::storage::StorageAccount;
+use crate::error::ElusivError;
+use crate::macros::{assert_account_size, elusiv_account, guard};
+use crate::types::U256;
+use borsh::{BorshDeserialize, BorshSerialize};
+use elusiv_derive::BorshSerDeSized;
+use elusiv_utils::MATH_ERR;
+use solana_program::{hash::hashv, program_error::ProgramError};
+
+/// Number of closed MT roots stored in the [`StateSummaryAccount`]
+pub const SUMMARY_CLOSED_MT_ROOTS_COUNT: usize = 8;
+
+/// Compact summary of the program state for light clients and bridges
+/// - updated with every finalized commitment batch, every MT reset and every inserted nullifier-hash
+/// - allows tracking the state by watching this single account
+#[elusiv_account(eager_type: true)]
+pub struct StateSummaryAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Root of the active MT
+    pub active_mt_root: U256,
+
+    /// Index of the active MT (equals the amount of closed MTs)
+    pub active_mt_index: u32,
+
+    /// Amount of commitments inserted into the active MT
+    pub active_mt_commitment_count: u32,
+
+    /// Roots of the last [`SUMMARY_CLOSED_MT_ROOTS_COUNT`] closed MTs (the root of the MT with index `i` is stored at `i % SUMMARY_CLOSED_MT_ROOTS_COUNT`)
+    pub closed_mt_roots: [U256; SUMMARY_CLOSED_MT_ROOTS_COUNT],
+
+    /// Hash-chain over all inserted nullifier-hashes: `sha256(nullifier_set_commitment || nullifier_hash)`
+    pub nullifier_set_commitment: U256,
+
+    /// Amount of inserted nullifier-hashes
+    pub nullifier_hash_count: u64,
+
+    /// Amount of commitments inserted into all MTs
+    pub total_commitment_count: u64,
+
+    /// Amount of finalized commitment batches
+    pub batch_count: u64,
+}
+
+assert_account_size!(StateSummaryAccount, 354);
+
+impl<'a> Lifecycle for StateSummaryAccount<'a> {}
+
+impl<'a> StateSummaryAccount<'a> {
+    /// Updates the summary after a commitment batch has been inserted into the active MT
+    pub fn update_batch(&mut self, storage_account: &StorageAccount) -> Result<(), ProgramError> {
+        let commitment_count = storage_account.get_next_commitment_ptr();
+        let inserted_count = commitment_count
+            .checked_sub(self.get_active_mt_commitment_count())
+            .ok_or(MATH_ERR)?;
+
+        self.set_active_mt_root(&storage_account.get_root()?);
+        self.set_active_mt_index(&storage_account.get_trees_count());
+        self.set_active_mt_commitment_count(&commitment_count);
+        self.set_total_commitment_count(
+            &self
+                .get_total_commitment_count()
+                .checked_add(inserted_count as u64)
+                .ok_or(MATH_ERR)?,
+        );
+        self.set_batch_count(&self.get_batch_count().checked_add(1).ok_or(MATH_ERR)?);
+
+        Ok(())
+    }
+
+    /// Updates the summary after the active MT has been closed
+    /// - needs to be called before the [`StorageAccount`] is reset
+    pub fn update_closed_mt(
+        &mut self,
+        storage_account: &StorageAccount,
+        closed_mt_index: u32,
+    ) -> Result<(), ProgramError> {
+        self.set_closed_mt_roots(
+            closed_mt_index as usize % SUMMARY_CLOSED_MT_ROOTS_COUNT,
+            &storage_account.get_root()?,
+        );
+        self.set_active_mt_index(&closed_mt_index.checked_add(1).ok_or(MATH_ERR)?);
+        self.set_active_mt_commitment_count(&0);
+
+        Ok(())
+    }
+
+    /// Appends a nullifier-hash to the nullifier set commitment
+    pub fn update_nullifier_hash(&mut self, nullifier_hash: &U256) -> Result<(), ProgramError> {
+        let commitment = hashv(&[&self.get_nullifier_set_commitment(), nullifier_hash]);
+        self.set_nullifier_set_commitment(&commitment.to_bytes());
+        self.set_nullifier_hash_count(
+            &self
+                .get_nullifier_hash_count()
+                .checked_add(1)
+                .ok_or(MATH_ERR)?,
+        );
+
+        Ok(())
+    }
+
+    /// Returns the root of the closed MT with index `mt_index`, if it is still stored
+    pub fn closed_mt_root(&self, mt_index: u32) -> Option<U256> {
+        let active_mt_index = self.get_active_mt_index();
+        if mt_index >= active_mt_index
+            || active_mt_index - mt_index > SUMMARY_CLOSED_MT_ROOTS_COUNT as u32
+        {
+            return None;
+        }
+
+        Some(self.get_closed_mt_roots(mt_index as usize % SUMMARY_CLOSED_MT_ROOTS_COUNT))
+    }
+
+    /// Digest of the state at `epoch`: `sha256(epoch || active_mt_index || active_mt_root || nullifier_set_commitment || sha256(pool_balances))`
+    /// - `pool_balances` are the pool's balances ordered by token-id
+    pub fn state_digest(&self, epoch: u64, pool_balances: &[u64]) -> U256 {
+        let balances: Vec<u8> = pool_balances
+            .iter()
+            .flat_map(|balance| balance.to_le_bytes())
+            .collect();
+
+        hashv(&[
+            &epoch.to_le_bytes(),
+            &self.get_active_mt_index().to_le_bytes(),
+            &self.get_active_mt_root(),
+            &self.get_nullifier_set_commitment(),
+            &hashv(&[&balances]).to_bytes(),
+        ])
+        .to_bytes()
+    }
+}
+
+/// Number of epochs tracked by the [`StateDigestHistoryAccount`]
+pub const STATE_DIGEST_HISTORY_COUNT: usize = 32;
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct StateDigest {
+    pub epoch: u64,
+    pub digest: U256,
+}
+
+/// Rotating buffer containing a single attested [`StateDigest`] for each of the last [`STATE_DIGEST_HISTORY_COUNT`] epochs
+/// - allows cross-chain light clients and bridges to reference the program state with a single value per epoch
+#[elusiv_account(eager_type: true)]
+pub struct StateDigestHistoryAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    digests: [StateDigest; STATE_DIGEST_HISTORY_COUNT],
+}
+
+assert_account_size!(StateDigestHistoryAccount, 1282);
+
+impl<'a> Lifecycle for StateDigestHistoryAccount<'a> {}
+
+impl<'a> StateDigestHistoryAccount<'a> {
+    /// Returns the digest of `epoch`, if it has been attested and is still stored
+    pub fn state_digest(&self, epoch: u64) -> Option<U256> {
+        let digest = self.get_digests(Self::index(epoch));
+        if digest.epoch != epoch || digest.digest == [0; 32] {
+            return None;
+        }
+
+        Some(digest.digest)
+    }
+
+    /// Records the digest of `epoch` (only the first digest of an epoch is recorded)
+    pub fn record(&mut self, epoch: u64, digest: U256) -> Result<(), ProgramError> {
+        guard!(
+            self.state_digest(epoch).is_none(),
+            ElusivError::StateDigestIsAlreadyAttested
+        );
+        guard!(
+            self.get_digests(Self::index(epoch)).epoch <= epoch,
+            ElusivError::InvalidInstructionData
+        );
+
+        self.set_digests(Self::index(epoch), &StateDigest { epoch, digest });
+
+        Ok(())
+    }
+
+    fn index(epoch: u64) -> usize {
+        (epoch % STATE_DIGEST_HISTORY_COUNT as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::{parent_account, zero_program_account};
+
+    #[test]
+    fn test_update_batch() {
+        parent_account!(mut storage_account, StorageAccount);
+        zero_program_account!(mut summary, StateSummaryAccount);
+
+        storage_account.set_node(&[1; 32], 0, 0).unwrap();
+        storage_account.set_next_commitment_ptr(&4);
+        summary.update_batch(&storage_account).unwrap();
+
+        storage_account.set_next_commitment_ptr(&12);
+        summary.update_batch(&storage_account).unwrap();
+
+        assert_eq!(summary.get_active_mt_root(), [1; 32]);
+        assert_eq!(summary.get_active_mt_commitment_count(), 12);
+        assert_eq!(summary.get_total_commitment_count(), 12);
+        assert_eq!(summary.get_batch_count(), 2);
+    }
+
+    #[test]
+    fn test_update_closed_mt() {
+        parent_account!(mut storage_account, StorageAccount);
+        zero_program_account!(mut summary, StateSummaryAccount);
+
+        for mt_index in 0..SUMMARY_CLOSED_MT_ROOTS_COUNT as u32 + 2 {
+            storage_account
+                .set_node(&[mt_index as u8 + 1; 32], 0, 0)
+                .unwrap();
+            storage_account.set_next_commitment_ptr(&1);
+            summary.update_batch(&storage_account).unwrap();
+            summary
+                .update_closed_mt(&storage_account, mt_index)
+                .unwrap();
+            storage_account.set_next_commitment_ptr(&0);
+
+            assert_eq!(summary.get_active_mt_index(), mt_index + 1);
+            assert_eq!(summary.get_active_mt_commitment_count(), 0);
+            assert_eq!(
+                summary.closed_mt_root(mt_index),
+                Some([mt_index as u8 + 1; 32])
+            );
+            assert_eq!(summary.closed_mt_root(mt_index + 1), None);
+        }
+
+        assert_eq!(summary.closed_mt_root(0), None);
+        assert_eq!(summary.closed_mt_root(1), None);
+        assert_eq!(summary.closed_mt_root(2), Some([3; 32]));
+        assert_eq!(
+            summary.get_total_commitment_count(),
+            SUMMARY_CLOSED_MT_ROOTS_COUNT as u64 + 2
+        );
+    }
+
+    #[test]
+    fn test_update_nullifier_hash() {
+        zero_program_account!(mut summary, StateSummaryAccount);
+
+        summary.update_nullifier_hash(&[1; 32]).unwrap();
+        summary.update_nullifier_hash(&[2; 32]).unwrap();
+
+        let expected = hashv(&[&hashv(&[&[0; 32], &[1; 32]]).to_bytes(), &[2; 32]]);
+        assert_eq!(summary.get_nullifier_set_commitment(), expected.to_bytes());
+        assert_eq!(summary.get_nullifier_hash_count(), 2);
+    }
+
+    #[test]
+    fn test_state_digest() {
+        zero_program_account!(mut summary, StateSummaryAccount);
+        let digest = summary.state_digest(1, &[1, 2, 3]);
+
+        assert_ne!(summary.state_digest(2, &[1, 2, 3]), digest);
+        assert_ne!(summary.state_digest(1, &[1, 2, 4]), digest);
+
+        summary.update_nullifier_hash(&[1; 32]).unwrap();
+        assert_ne!(summary.state_digest(1, &[1, 2, 3]), digest);
+    }
+
+    #[test]
+    fn test_state_digest_history() {
+        zero_program_account!(mut history, StateDigestHistoryAccount);
+        assert_eq!(history.state_digest(0), None);
+
+        history.record(0, [1; 32]).unwrap();
+        assert_eq!(history.state_digest(0), Some([1; 32]));
+
+        // Digests are immutable
+        assert!(history.record(0, [2; 32]).is_err());
+
+        // Older epochs are overwritten
+        let epoch = STATE_DIGEST_HISTORY_COUNT as u64;
+        history.record(epoch, [3; 32]).unwrap();
+        assert_eq!(history.state_digest(0), None);
+        assert_eq!(history.state_digest(epoch), Some([3; 32]));
+
+        // But not by older epochs
+        assert!(history.record(0, [4; 32]).is_err());
+    }
+}
@@ -0,0 +1,34 @@
+use super::program_account::{Lifecycle, PDAAccountData};
+use crate::macros::{assert_account_size, elusiv_account};
+use crate::types::U256;
+use solana_program::pubkey::Pubkey;
+
+/// Size of an encrypted viewing key (e.g. an ephemeral public key, nonce and ciphertext encrypted for the `auditor`)
+pub const ENCRYPTED_VIEWING_KEY_SIZE: usize = 128;
+
+/// Voluntary disclosure of a depositor's viewing key to an auditor
+/// - the viewing key is only readable by the `auditor`, the shielded pool's privacy model is unchanged for everybody else
+/// - the auditor verifies the decrypted key against `viewing_key_hash` before using it to identify the depositor's commitments
+/// - the depositor can revoke the disclosure at any time (which does not hide a key that has already been decrypted)
+#[elusiv_account(eager_type: true)]
+pub struct ViewingKeyRegistryAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Rents the account and receives the rent once the viewing key is revoked
+    pub depositor: Pubkey,
+    pub auditor: Pubkey,
+    pub viewing_key_hash: U256,
+    pub encrypted_viewing_key: [u8; ENCRYPTED_VIEWING_KEY_SIZE],
+
+    /// The slot of the latest publication
+    pub publication_slot: u64,
+}
+
+assert_account_size!(ViewingKeyRegistryAccount, 234);
+
+impl<'a> Lifecycle for ViewingKeyRegistryAccount<'a> {
+    const CLOSEABLE: bool = true;
+    const REUSABLE: bool = true;
+}
@@ -1,6 +1,14 @@
+use super::program_account::Lifecycle;
+use crate::error::ElusivError;
+use crate::macros::{assert_account_size, guard};
+use crate::processor::{vkey_data_packet_count, VKEY_ACCOUNT_DATA_PACKET_SIZE};
+use crate::proof::vkey::{vkey_checksum, VKeySourceSection, VerifyingKey};
+use crate::types::U256;
+use borsh::{BorshDeserialize, BorshSerialize};
+use elusiv_derive::BorshSerDeSized;
 use elusiv_proc_macros::elusiv_account;
 use elusiv_types::{ChildAccount, ElusivOption, PDAAccountData};
-use solana_program::pubkey::Pubkey;
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
 
 pub struct VKeyChildAccount;
 
@@ -20,4 +28,123 @@ pub struct VKeyAccount {
     pub authority: ElusivOption<Pubkey>,
     pub is_frozen: bool,
     pub version: u32,
+
+    /// Checksum of the active verifying key source, computed over multiple instructions before freezing
+    pub checksum: U256,
+    pub checksum_position: u32,
+
+    /// Number of data packets sequentially written into the pending verifying key source (allows resuming its population)
+    pub data_packet_count: u32,
+}
+
+assert_account_size!(VKeyAccount, 150);
+
+impl<'a, 'b, 't> VKeyAccount<'a, 'b, 't> {
+    /// The [`VKeySourceSection`] the next data packet of the pending verifying key source is written to
+    pub fn data_cursor(&self) -> VKeySourceSection {
+        VerifyingKey::source_section(
+            self.get_public_inputs_count() as usize,
+            self.get_data_packet_count() as usize * VKEY_ACCOUNT_DATA_PACKET_SIZE,
+        )
+    }
+
+    /// Whether all data packets of the pending verifying key source have been written
+    pub fn is_data_complete(&self) -> bool {
+        self.get_data_packet_count() as usize
+            == vkey_data_packet_count(self.get_public_inputs_count() as usize)
+    }
+}
+
+impl<'a, 'b, 't> Lifecycle for VKeyAccount<'a, 'b, 't> {
+    fn is_sealed(&self) -> bool {
+        self.get_is_frozen()
+    }
+
+    fn seal(&mut self) -> ProgramResult {
+        self.set_is_frozen(&true);
+        Ok(())
+    }
+}
+
+/// Number of circuits (identified by the `vkey_id` of their built-in verifying key) whose verifying key can be upgraded
+pub const UPGRADABLE_VKEY_COUNT: usize = 2;
+
+/// Upgrade state of the verifying key of a circuit
+/// - an upgraded verifying key is stored in a fresh [`VKeyAccount`] with its own `vkey_id`
+/// - after an activation, the previously active verifying key is accepted until `previous_valid_until_slot`
+#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(any(test, feature = "elusiv-client"), derive(Debug))]
+pub struct VKeyUpgrade {
+    pub is_staged: bool,
+    pub staged_vkey_id: u32,
+
+    /// The expected checksum of the staged verifying key source
+    pub staged_checksum: U256,
+
+    /// Whether the built-in verifying key has been replaced
+    pub is_upgraded: bool,
+    pub active_vkey_id: u32,
+    pub active_checksum: U256,
+
+    pub previous_vkey_id: u32,
+    pub previous_checksum: U256,
+    pub previous_valid_until_slot: u64,
+}
+
+impl VKeyUpgrade {
+    /// Whether a verifying key (`vkey_id` and `checksum`) can be used for proofs of the circuit `circuit_vkey_id` at `slot`
+    pub fn accepts(&self, circuit_vkey_id: u32, vkey_id: u32, checksum: &U256, slot: u64) -> bool {
+        if self.active(circuit_vkey_id) == Some((vkey_id, *checksum)) {
+            return true;
+        }
+
+        self.is_upgraded
+            && vkey_id == self.previous_vkey_id
+            && *checksum == self.previous_checksum
+            && slot <= self.previous_valid_until_slot
+    }
+
+    /// The `vkey_id` and checksum of the active verifying key of the circuit `circuit_vkey_id`
+    pub fn active(&self, circuit_vkey_id: u32) -> Option<(u32, U256)> {
+        if self.is_upgraded {
+            Some((self.active_vkey_id, self.active_checksum))
+        } else {
+            vkey_checksum(circuit_vkey_id).map(|checksum| (circuit_vkey_id, checksum))
+        }
+    }
+
+    /// Stages the verifying key with `vkey_id` (replacing a previously staged verifying key)
+    pub fn stage(&mut self, vkey_id: u32, checksum: U256) {
+        self.is_staged = true;
+        self.staged_vkey_id = vkey_id;
+        self.staged_checksum = checksum;
+    }
+
+    /// Activates the staged verifying key, the active verifying key remains accepted for `acceptance_window` slots
+    pub fn activate(
+        &mut self,
+        circuit_vkey_id: u32,
+        slot: u64,
+        acceptance_window: u64,
+    ) -> Result<(), ProgramError> {
+        guard!(self.is_staged, ElusivError::InvalidAccountState);
+
+        let (previous_vkey_id, previous_checksum) = self
+            .active(circuit_vkey_id)
+            .ok_or(ElusivError::InvalidAccountState)?;
+
+        self.previous_vkey_id = previous_vkey_id;
+        self.previous_checksum = previous_checksum;
+        self.previous_valid_until_slot = slot.saturating_add(acceptance_window);
+
+        self.is_upgraded = true;
+        self.active_vkey_id = self.staged_vkey_id;
+        self.active_checksum = self.staged_checksum;
+
+        self.is_staged = false;
+        self.staged_vkey_id = 0;
+        self.staged_checksum = [0; 32];
+
+        Ok(())
+    }
 }
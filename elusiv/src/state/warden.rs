@@ -0,0 +1,159 @@
+use super::nullifier::SLOTS_PER_YEAR;
+use super::program_account::{Lifecycle, PDAAccountData};
+use super::stats::STATS_TOKEN_COUNT;
+use crate::error::ElusivError;
+use crate::macros::{assert_account_size, elusiv_account, guard};
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError};
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+
+/// Minimum stake (in lamports) of a registered warden
+pub const MIN_WARDEN_STAKE: u64 = 10 * LAMPORTS_PER_SOL;
+
+/// Period between the deregistration of a warden and the withdrawal of its stake (about one week)
+pub const WARDEN_UNBONDING_SLOTS: u64 = SLOTS_PER_YEAR / 52;
+
+/// Portion (in basis points) of the stake slashed, if a warden abandons a computation
+pub const WARDEN_TIMEOUT_SLASHING_BPS: u64 = 100;
+
+/// Registration of a warden (relayer) driving partial computations, keyed by the warden's pubkey
+/// - the account holds the `stake` (in addition to its rent) as the foundation for the accountability of wardens
+/// - after a deregistration, the stake can be withdrawn once the unbonding period has elapsed
+/// - rewards for finalized proof verifications are escrowed per token-id (held by the pool) until they are claimed
+#[elusiv_account(eager_type: true)]
+pub struct WardenAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    pub warden: Pubkey,
+    pub stake: u64,
+    pub registration_slot: u64,
+
+    /// Slot from which on the stake can be withdrawn (zero while the warden is registered)
+    pub unbonding_slot: u64,
+
+    /// Escrowed (unclaimed) rewards per token-id
+    pub pending_rewards: [u64; STATS_TOKEN_COUNT],
+}
+
+assert_account_size!(WardenAccount, 58 + STATS_TOKEN_COUNT * 8);
+
+impl<'a> Lifecycle for WardenAccount<'a> {
+    const CLOSEABLE: bool = true;
+    const REUSABLE: bool = true;
+}
+
+impl<'a> WardenAccount<'a> {
+    pub fn is_registered(&self) -> bool {
+        self.get_unbonding_slot() == 0 && self.get_stake() >= MIN_WARDEN_STAKE
+    }
+
+    pub fn is_unbonded(&self, slot: u64) -> bool {
+        self.get_unbonding_slot() != 0 && slot >= self.get_unbonding_slot()
+    }
+
+    pub fn accrue_reward(&mut self, token_id: u16, amount: u64) -> ProgramResult {
+        let token_id = token_id as usize;
+        guard!(
+            token_id < STATS_TOKEN_COUNT,
+            ElusivError::InvalidAccountState
+        );
+
+        let pending_reward = self
+            .get_pending_rewards(token_id)
+            .checked_add(amount)
+            .ok_or(ElusivError::InvalidAmount)?;
+        self.set_pending_rewards(token_id, &pending_reward);
+
+        Ok(())
+    }
+
+    /// Resets and returns the pending reward of `token_id`
+    pub fn take_pending_reward(&mut self, token_id: u16) -> Result<u64, ProgramError> {
+        let token_id = token_id as usize;
+        guard!(
+            token_id < STATS_TOKEN_COUNT,
+            ElusivError::InvalidAccountState
+        );
+
+        let pending_reward = self.get_pending_rewards(token_id);
+        self.set_pending_rewards(token_id, &0);
+
+        Ok(pending_reward)
+    }
+
+    /// Reduces the stake by [`WARDEN_TIMEOUT_SLASHING_BPS`] and returns the slashed amount
+    pub fn slash_stake(&mut self) -> u64 {
+        let stake = self.get_stake();
+        let slashed = (stake as u128 * WARDEN_TIMEOUT_SLASHING_BPS as u128 / 10_000) as u64;
+        self.set_stake(&(stake - slashed));
+
+        slashed
+    }
+
+    pub fn has_pending_rewards(&self) -> bool {
+        (0..STATS_TOKEN_COUNT).any(|token_id| self.get_pending_rewards(token_id) > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+
+    #[test]
+    fn test_is_registered() {
+        zero_program_account!(mut account, WardenAccount);
+        assert!(!account.is_registered());
+
+        account.set_stake(&(MIN_WARDEN_STAKE - 1));
+        assert!(!account.is_registered());
+
+        account.set_stake(&MIN_WARDEN_STAKE);
+        assert!(account.is_registered());
+        assert!(!account.is_unbonded(u64::MAX));
+
+        account.set_unbonding_slot(&100);
+        assert!(!account.is_registered());
+        assert!(!account.is_unbonded(99));
+        assert!(account.is_unbonded(100));
+    }
+
+    #[test]
+    fn test_slash_stake() {
+        zero_program_account!(mut account, WardenAccount);
+        account.set_stake(&MIN_WARDEN_STAKE);
+
+        assert_eq!(account.slash_stake(), MIN_WARDEN_STAKE / 100);
+        assert_eq!(account.get_stake(), MIN_WARDEN_STAKE / 100 * 99);
+
+        // The warden is not registered anymore after a slashing below the minimum stake
+        assert!(!account.is_registered());
+    }
+
+    #[test]
+    fn test_pending_rewards() {
+        zero_program_account!(mut account, WardenAccount);
+        assert!(!account.has_pending_rewards());
+
+        account.accrue_reward(0, 100).unwrap();
+        account.accrue_reward(0, 50).unwrap();
+        account.accrue_reward(1, 1).unwrap();
+        assert!(account.has_pending_rewards());
+        assert_eq!(account.get_pending_rewards(0), 150);
+
+        // Overflow
+        assert!(account.accrue_reward(0, u64::MAX).is_err());
+
+        // Invalid token-id
+        assert!(account.accrue_reward(STATS_TOKEN_COUNT as u16, 1).is_err());
+        assert!(account
+            .take_pending_reward(STATS_TOKEN_COUNT as u16)
+            .is_err());
+
+        assert_eq!(account.take_pending_reward(0).unwrap(), 150);
+        assert_eq!(account.take_pending_reward(0).unwrap(), 0);
+        assert_eq!(account.take_pending_reward(1).unwrap(), 1);
+        assert!(!account.has_pending_rewards());
+    }
+}
@@ -0,0 +1,158 @@
+use super::program_account::{Lifecycle, PDAAccountData};
+use crate::error::ElusivError;
+use crate::macros::{assert_account_size, elusiv_account, guard};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+/// Escrow of timelocked withdrawals (lamports only)
+/// - the account is used as the recipient of regular sends, so the withdrawn amounts are held by this account
+/// - the finalization of a send locks its amount until its own unlock slot (`delay_slots` after the finalization, see [`crate::state::governor::GovernorAccount::withdrawal_delay_slots`])
+/// - afterwards anyone can release the unlocked amounts to the `recipient`
+#[elusiv_account(eager_type: true)]
+pub struct PendingWithdrawalAccount {
+    #[no_getter]
+    #[no_setter]
+    pda_data: PDAAccountData,
+
+    /// Rents the account and receives the rent once the account is closed
+    pub owner: Pubkey,
+    pub pending_withdrawal_index: u32,
+    pub recipient: Pubkey,
+    pub delay_slots: u64,
+
+    /// Locked amounts (a zero entry is unused)
+    pub amounts: [u64; PENDING_WITHDRAWAL_LOCKS_COUNT],
+    pub unlock_slots: [u64; PENDING_WITHDRAWAL_LOCKS_COUNT],
+}
+
+assert_account_size!(PendingWithdrawalAccount, 142);
+
+/// Number of separately locked withdrawals of a [`PendingWithdrawalAccount`]
+pub const PENDING_WITHDRAWAL_LOCKS_COUNT: usize = 4;
+
+impl<'a> Lifecycle for PendingWithdrawalAccount<'a> {
+    const CLOSEABLE: bool = true;
+    const REUSABLE: bool = true;
+}
+
+impl<'a> PendingWithdrawalAccount<'a> {
+    /// Locks `amount` for `delay_slots` after `slot`
+    /// - the unlock slots of already locked amounts are never pushed back
+    /// - if all locks are in use, `amount` is added to the lock with the latest unlock slot
+    pub fn lock(&mut self, amount: u64, delay_slots: u64, slot: u64) {
+        if amount == 0 {
+            return;
+        }
+
+        let unlock_slot = slot.saturating_add(delay_slots);
+        let index = match (0..PENDING_WITHDRAWAL_LOCKS_COUNT).find(|&i| self.get_amounts(i) == 0) {
+            Some(index) => index,
+            None => (0..PENDING_WITHDRAWAL_LOCKS_COUNT)
+                .max_by_key(|&i| self.get_unlock_slots(i))
+                .unwrap(),
+        };
+
+        self.set_amounts(index, &self.get_amounts(index).saturating_add(amount));
+        if unlock_slot > self.get_unlock_slots(index) {
+            self.set_unlock_slots(index, &unlock_slot);
+        }
+    }
+
+    /// Returns the total locked amount
+    pub fn locked_amount(&self) -> u64 {
+        (0..PENDING_WITHDRAWAL_LOCKS_COUNT)
+            .fold(0, |acc, i| acc.saturating_add(self.get_amounts(i)))
+    }
+
+    /// Returns the amount that is unlocked at `slot`
+    pub fn unlocked_amount(&self, slot: u64) -> u64 {
+        (0..PENDING_WITHDRAWAL_LOCKS_COUNT)
+            .filter(|&i| slot >= self.get_unlock_slots(i))
+            .fold(0, |acc, i| acc.saturating_add(self.get_amounts(i)))
+    }
+
+    /// Requires an amount to be releasable at `slot`
+    pub fn verify_release(&self, slot: u64) -> Result<(), ProgramError> {
+        guard!(self.locked_amount() > 0, ElusivError::InvalidAccountState);
+        guard!(
+            self.unlocked_amount(slot) > 0,
+            ElusivError::WithdrawalIsNotYetUnlocked
+        );
+
+        Ok(())
+    }
+
+    /// Removes and returns the amount that is unlocked at `slot`
+    pub fn release(&mut self, slot: u64) -> u64 {
+        let amount = self.unlocked_amount(slot);
+        for i in 0..PENDING_WITHDRAWAL_LOCKS_COUNT {
+            if slot >= self.get_unlock_slots(i) {
+                self.set_amounts(i, &0);
+                self.set_unlock_slots(i, &0);
+            }
+        }
+        amount
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macros::zero_program_account;
+    use assert_matches::assert_matches;
+
+    #[test]
+    fn test_lock_and_release() {
+        zero_program_account!(mut account, PendingWithdrawalAccount);
+
+        // Nothing has been withdrawn yet
+        assert_matches!(account.verify_release(0), Err(_));
+
+        account.lock(100, 10, 5);
+        assert_eq!(account.locked_amount(), 100);
+        assert_eq!(account.get_unlock_slots(0), 15);
+        assert_matches!(account.verify_release(14), Err(_));
+        assert_matches!(account.verify_release(15), Ok(()));
+
+        // A later send is locked separately
+        account.lock(50, 10, 8);
+        assert_eq!(account.locked_amount(), 150);
+        assert_eq!(account.get_unlock_slots(0), 15);
+        assert_eq!(account.get_unlock_slots(1), 18);
+        assert_eq!(account.unlocked_amount(15), 100);
+        assert_eq!(account.unlocked_amount(18), 150);
+
+        assert_eq!(account.release(15), 100);
+        assert_eq!(account.locked_amount(), 50);
+        assert_matches!(account.verify_release(17), Err(_));
+        assert_eq!(account.release(18), 50);
+        assert_eq!(account.locked_amount(), 0);
+        assert_matches!(account.verify_release(18), Err(_));
+    }
+
+    #[test]
+    fn test_lock_dust() {
+        zero_program_account!(mut account, PendingWithdrawalAccount);
+
+        account.lock(1_000_000, 10, 0);
+
+        // Dust withdrawals to the same account do not push back the unlock slot of the first withdrawal
+        for slot in 1..100 {
+            account.lock(1, 10, slot);
+        }
+        assert_eq!(account.get_unlock_slots(0), 10);
+        assert_eq!(account.get_amounts(0), 1_000_000);
+        assert_eq!(account.locked_amount(), 1_000_099);
+
+        // Once all locks are in use, only the latest lock is extended
+        assert_eq!(account.get_unlock_slots(1), 11);
+        assert_eq!(account.get_unlock_slots(2), 12);
+        assert_eq!(
+            account.get_unlock_slots(PENDING_WITHDRAWAL_LOCKS_COUNT - 1),
+            109
+        );
+
+        assert_matches!(account.verify_release(10), Ok(()));
+        assert_eq!(account.release(10), 1_000_000);
+        assert_eq!(account.locked_amount(), 99);
+    }
+}
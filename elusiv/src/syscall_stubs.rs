@@ -0,0 +1,58 @@
+//! Syscall stubs serving the sysvars and return-data of the current test thread in unit tests
+
+use solana_program::clock::Clock;
+use solana_program::entrypoint::SUCCESS;
+use solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use solana_program::pubkey::Pubkey;
+use std::cell::RefCell;
+use std::sync::Once;
+
+thread_local! {
+    static CLOCK: RefCell<Clock> = RefCell::new(Clock::default());
+    static RETURN_DATA: RefCell<Vec<u8>> = RefCell::new(Vec::new());
+}
+
+struct TestSyscallStubs;
+
+impl SyscallStubs for TestSyscallStubs {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        CLOCK.with(|clock| unsafe {
+            *(var_addr as *mut Clock) = clock.borrow().clone();
+        });
+        SUCCESS
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        Some((crate::id(), RETURN_DATA.with(|data| data.borrow().clone())))
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        RETURN_DATA.with(|d| *d.borrow_mut() = data.to_vec());
+    }
+}
+
+/// Installs the [`TestSyscallStubs`] (once per test binary)
+pub(crate) fn install_syscall_stubs() {
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(TestSyscallStubs));
+    });
+}
+
+/// Sets the slot of the clock sysvar for the current test thread
+pub(crate) fn set_test_slot(slot: u64) {
+    install_syscall_stubs();
+    CLOCK.with(|clock| clock.borrow_mut().slot = slot);
+}
+
+/// Sets the epoch of the clock sysvar for the current test thread
+pub(crate) fn set_test_epoch(epoch: u64) {
+    install_syscall_stubs();
+    CLOCK.with(|clock| clock.borrow_mut().epoch = epoch);
+}
+
+/// Sets the unix timestamp of the clock sysvar for the current test thread
+pub(crate) fn set_test_unix_timestamp(unix_timestamp: i64) {
+    install_syscall_stubs();
+    CLOCK.with(|clock| clock.borrow_mut().unix_timestamp = unix_timestamp);
+}
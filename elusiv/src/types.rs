@@ -290,9 +290,16 @@ pub struct InputCommitment {
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct JoinSplitPublicInputs {
     pub input_commitments: Vec<InputCommitment>,
+
+    /// The change commitment (with the value `sum(inputs) - amount - fee`)
+    /// - enqueued into the [`crate::state::queue::CommitmentQueueAccount`] during finalization and hashed into the MT like any other commitment
+    /// - allows withdrawing an arbitrary `amount` without consuming the full value of the input commitments
     pub output_commitment: RawU256,
     pub output_commitment_index: u32,
     pub fee_version: u32,
+
+    /// The withdrawn amount
+    /// - zero for sends that never leave the pool: merges and internal transfers, where the `output_commitment` is computed from a base-commitment of the recipient
     pub amount: u64,
     pub fee: u64,
     pub token_id: u16,
@@ -370,42 +377,236 @@ pub trait PublicInputs {
 
 /// https://github.com/elusiv-privacy/circuits/blob/master/circuits/main/send_quadra.circom
 /// - IMPORTANT: depending on recipient.recipient_is_associated_token_account, a higher amount is required (that also includes the rent)
-#[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Debug)]
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct SendPublicInputs {
     pub join_split: JoinSplitPublicInputs,
     pub recipient_is_associated_token_account: bool,
     pub solana_pay_transfer: bool,
     pub hashed_inputs: U256,
+
+    /// Optional memo encrypted by the sender for the recipient (e.g. to reconcile a payment against an invoice)
+    /// - bound to the proof through the `hashed_inputs` and emitted as [`crate::events::ElusivEvent::WithdrawalMemo`] once the withdrawal is finalized
+    pub encrypted_memo: Option<EncryptedMemo>,
+}
+
+impl BorshSerDeSized for SendPublicInputs {
+    // only used as maximum size in this context
+    const SIZE: usize = JoinSplitPublicInputs::SIZE + 1 + 1 + 32 + 1 + ENCRYPTED_MEMO_SIZE;
+}
+
+pub const ENCRYPTED_MEMO_SIZE: usize = 128;
+
+/// A memo of [`ENCRYPTED_MEMO_SIZE`] bytes, split into `U256`-chunks
+pub type EncryptedMemo = [U256; ENCRYPTED_MEMO_SIZE / 32];
+
+/// Binds a send proof to an installment of a recurring payment (see [`crate::state::recurring::RecurringPaymentAccount`])
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct RecurringPaymentBinding {
+    pub owner: Pubkey,
+    pub recurring_payment_index: u32,
+}
+
+/// Maximum number of recipients of [`BatchPayouts`]
+pub const MAX_BATCH_PAYOUT_RECIPIENTS: usize = 8;
+
+/// Splits the withdrawn amount of a send proof between multiple recipients (lamports only, e.g. for payrolls)
+/// - bound to the proof through the `hashed_inputs` and paid out atomically by [`crate::processor::finalize_verification_transfer_lamports`]
+#[derive(
+    BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Copy, Debug, Default,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct BatchPayouts {
+    pub recipient_count: u8,
+    pub recipients: [Pubkey; MAX_BATCH_PAYOUT_RECIPIENTS],
+    pub amounts: [u64; MAX_BATCH_PAYOUT_RECIPIENTS],
+}
+
+impl BatchPayouts {
+    pub fn new(payouts: &[(Pubkey, u64)]) -> Option<Self> {
+        if payouts.len() > MAX_BATCH_PAYOUT_RECIPIENTS {
+            return None;
+        }
+
+        let mut batch_payouts = BatchPayouts {
+            recipient_count: payouts.len() as u8,
+            ..Default::default()
+        };
+        for (i, (recipient, amount)) in payouts.iter().enumerate() {
+            batch_payouts.recipients[i] = *recipient;
+            batch_payouts.amounts[i] = *amount;
+        }
+
+        Some(batch_payouts)
+    }
+
+    /// Returns all recipient/amount pairs
+    pub fn payouts(&self) -> impl Iterator<Item = (Pubkey, u64)> + '_ {
+        let count = (self.recipient_count as usize).min(MAX_BATCH_PAYOUT_RECIPIENTS);
+        self.recipients[..count]
+            .iter()
+            .copied()
+            .zip(self.amounts[..count].iter().copied())
+    }
+
+    /// The amount that needs to be withdrawn for all payouts
+    /// - `None` if there are no (or too many) recipients or any amount is zero
+    pub fn total_amount(&self) -> Option<u64> {
+        let count = self.recipient_count as usize;
+        if count == 0 || count > MAX_BATCH_PAYOUT_RECIPIENTS {
+            return None;
+        }
+
+        self.payouts().try_fold(0u64, |total, (_, amount)| {
+            if amount == 0 {
+                return None;
+            }
+            total.checked_add(amount)
+        })
+    }
+}
+
+/// The request data bound to a send proof through its `hashed_inputs` (see [`generate_hashed_inputs`])
+#[derive(Clone, Copy)]
+pub struct HashedInputs<'a> {
+    pub recipient: U256,
+    pub identifier: U256,
+    pub iv: U256,
+    pub encrypted_owner: U256,
+    pub transaction_reference: U256,
+    pub is_associated_token_account: bool,
+    pub memo: Option<&'a [u8]>,
+    pub encrypted_memo: Option<&'a EncryptedMemo>,
+    pub recurring_payment: Option<&'a RecurringPaymentBinding>,
+    pub batch_payouts: Option<&'a BatchPayouts>,
 }
 
-pub fn generate_hashed_inputs(
-    recipient: U256,
-    identifier: U256,
-    iv: U256,
-    encrypted_owner: U256,
-    transaction_reference: U256,
-    is_associated_token_account: bool,
-    memo: &Option<Vec<u8>>,
-) -> U256 {
-    let mut data = recipient.to_vec();
-    data.extend(identifier);
-    data.extend(iv);
-    data.extend(encrypted_owner);
-    data.extend(transaction_reference);
-    data.extend([u8::from(is_associated_token_account)]);
-
-    if let Some(memo) = memo {
+/// Prefix of the (length-prefixed) memo in the preimage of the `hashed_inputs`
+const HASHED_INPUTS_MEMO_TAG: u8 = 1;
+
+/// Prefix of the encrypted memo in the preimage of the `hashed_inputs`
+const HASHED_INPUTS_ENCRYPTED_MEMO_TAG: u8 = 2;
+
+/// Prefix of the recurring payment binding in the preimage of the `hashed_inputs`
+const HASHED_INPUTS_RECURRING_PAYMENT_TAG: u8 = 3;
+
+/// Prefix of the (count-prefixed) batch payouts in the preimage of the `hashed_inputs`
+const HASHED_INPUTS_BATCH_PAYOUTS_TAG: u8 = 4;
+
+/// Computes the `hashed_inputs` public input of a send proof
+/// - the optional fields are tagged (and the memo is length-prefixed), so that different inputs never share a preimage
+pub fn generate_hashed_inputs(inputs: &HashedInputs) -> U256 {
+    let mut data = inputs.recipient.to_vec();
+    data.extend(inputs.identifier);
+    data.extend(inputs.iv);
+    data.extend(inputs.encrypted_owner);
+    data.extend(inputs.transaction_reference);
+    data.extend([u8::from(inputs.is_associated_token_account)]);
+
+    if let Some(memo) = inputs.memo {
+        data.push(HASHED_INPUTS_MEMO_TAG);
+        data.extend((memo.len() as u32).to_le_bytes());
         data.extend(memo);
     }
 
-    let mut hash = solana_program::hash::hash(&data).to_bytes();
+    if let Some(encrypted_memo) = inputs.encrypted_memo {
+        data.push(HASHED_INPUTS_ENCRYPTED_MEMO_TAG);
+        data.extend(encrypted_memo.concat());
+    }
+
+    if let Some(recurring_payment) = inputs.recurring_payment {
+        data.push(HASHED_INPUTS_RECURRING_PAYMENT_TAG);
+        data.extend(recurring_payment.owner.to_bytes());
+        data.extend(recurring_payment.recurring_payment_index.to_le_bytes());
+    }
+
+    if let Some(batch_payouts) = inputs.batch_payouts {
+        data.push(HASHED_INPUTS_BATCH_PAYOUTS_TAG);
+        data.push(batch_payouts.recipient_count);
+        for (recipient, amount) in batch_payouts.payouts() {
+            data.extend(recipient.to_bytes());
+            data.extend(amount.to_le_bytes());
+        }
+    }
+
+    hash_to_public_input(&data)
+}
+
+fn hash_to_public_input(data: &[u8]) -> U256 {
+    let mut hash = solana_program::hash::hash(data).to_bytes();
 
     // mask the lower 253 bits
     hash[31] &= 0b11111;
     hash
 }
 
+/// Maximum number of signers of a [`ThresholdPolicy`]
+pub const MAX_THRESHOLD_SIGNERS: usize = 16;
+
+/// M-of-N approval of proof requests spending shared notes (e.g. DAO or team treasuries)
+/// - the signers sign the [`threshold_spend_message`] of a proof request, the prover checks the approvals with [`ThresholdPolicy::is_satisfied_by`]
+///
+/// # Notes
+///
+/// The policy is client-side only: neither the circuits nor the program verify the approvals,
+/// so anyone knowing the spending key of the notes can spend them without approvals.
+#[derive(BorshDeserialize, BorshSerialize, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ThresholdPolicy {
+    pub threshold: u8,
+    pub signers: Vec<Pubkey>,
+}
+
+impl ThresholdPolicy {
+    pub fn is_valid(&self) -> bool {
+        if self.threshold == 0
+            || self.signers.len() < self.threshold as usize
+            || self.signers.len() > MAX_THRESHOLD_SIGNERS
+        {
+            return false;
+        }
+
+        // Signers are unique
+        self.signers
+            .iter()
+            .enumerate()
+            .all(|(i, signer)| !self.signers[..i].contains(signer))
+    }
+
+    /// Identifies the policy in the [`threshold_spend_message`] (the order of the signers is relevant)
+    pub fn policy_hash(&self) -> U256 {
+        let mut data = vec![self.threshold];
+        for signer in &self.signers {
+            data.extend(signer.to_bytes());
+        }
+
+        hash_to_public_input(&data)
+    }
+
+    /// Returns true if `approvers` contains at least `threshold` distinct signers (non-signers are ignored)
+    pub fn is_satisfied_by(&self, approvers: &[Pubkey]) -> bool {
+        let approvals = self
+            .signers
+            .iter()
+            .filter(|signer| approvers.contains(signer))
+            .count();
+
+        self.is_valid() && approvals >= self.threshold as usize
+    }
+}
+
+/// The message signed (off-chain) by the signers of `policy` to approve the proof request with `public_inputs`
+/// - since `hashed_inputs` is part of the public signals, the approval also covers the recipient
+pub fn threshold_spend_message(policy: &ThresholdPolicy, public_inputs: &SendPublicInputs) -> U256 {
+    let mut data = policy.policy_hash().to_vec();
+    for public_signal in public_inputs.public_signals_skip_mr() {
+        data.extend(public_signal);
+    }
+
+    hash_to_public_input(&data)
+}
+
 /// https://github.com/elusiv-privacy/circuits/blob/master/circuits/main/migrate_unary.circom
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized, PartialEq, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -536,6 +737,7 @@ impl PublicInputs for MigratePublicInputs {
 pub fn compute_fee_rec<V: crate::proof::vkey::VerifyingKeyInfo, P: PublicInputs>(
     public_inputs: &mut P,
     program_fee: &crate::state::fee::ProgramFee,
+    compute_unit_margin: u16,
     price: &crate::token::TokenPrice,
 ) {
     let fee = program_fee
@@ -543,6 +745,7 @@ pub fn compute_fee_rec<V: crate::proof::vkey::VerifyingKeyInfo, P: PublicInputs>
             crate::proof::verifier::prepare_public_inputs_instructions(
                 &public_inputs.public_signals_skip_mr(),
                 V::public_inputs_count(),
+                compute_unit_margin,
             )
             .len(),
             0,
@@ -555,7 +758,7 @@ pub fn compute_fee_rec<V: crate::proof::vkey::VerifyingKeyInfo, P: PublicInputs>
 
     if fee != public_inputs.join_split_inputs().fee {
         public_inputs.set_fee(fee);
-        compute_fee_rec::<V, P>(public_inputs, program_fee, price)
+        compute_fee_rec::<V, P>(public_inputs, program_fee, compute_unit_margin, price)
     }
 }
 
@@ -563,9 +766,15 @@ pub fn compute_fee_rec<V: crate::proof::vkey::VerifyingKeyInfo, P: PublicInputs>
 pub fn compute_fee_rec_lamports<V: crate::proof::vkey::VerifyingKeyInfo, P: PublicInputs>(
     public_inputs: &mut P,
     program_fee: &crate::state::fee::ProgramFee,
+    compute_unit_margin: u16,
 ) {
     use crate::token::TokenPrice;
-    compute_fee_rec::<V, P>(public_inputs, program_fee, &TokenPrice::new_lamports())
+    compute_fee_rec::<V, P>(
+        public_inputs,
+        program_fee,
+        compute_unit_margin,
+        &TokenPrice::new_lamports(),
+    )
 }
 
 pub fn u256_to_le_limbs(v: U256) -> [u64; 4] {
@@ -600,6 +809,48 @@ mod test {
     use ark_bn254::{Fq, Fq2, G1Affine, G2Affine};
     use std::str::FromStr;
 
+    #[test]
+    fn test_threshold_policy() {
+        let signers = vec![
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let policy = ThresholdPolicy {
+            threshold: 2,
+            signers: signers.clone(),
+        };
+        assert!(policy.is_valid());
+
+        assert!(!policy.is_satisfied_by(&[signers[0]]));
+        assert!(!policy.is_satisfied_by(&[signers[0], signers[0]]));
+        assert!(!policy.is_satisfied_by(&[signers[0], Pubkey::new_unique()]));
+        assert!(policy.is_satisfied_by(&[signers[2], signers[0]]));
+
+        // Invalid policies
+        for (threshold, signers) in [
+            (0, signers.clone()),
+            (4, signers.clone()),
+            (1, vec![signers[0], signers[0]]),
+            (1, vec![signers[0]; MAX_THRESHOLD_SIGNERS + 1]),
+        ] {
+            let policy = ThresholdPolicy { threshold, signers };
+            assert!(!policy.is_valid());
+            assert!(!policy.is_satisfied_by(&policy.signers));
+        }
+
+        // The policy hash is a valid public input
+        assert!(policy.policy_hash()[31] < 0b100000);
+        assert_ne!(
+            policy.policy_hash(),
+            ThresholdPolicy {
+                threshold: 3,
+                signers
+            }
+            .policy_hash()
+        );
+    }
+
     #[test]
     fn test_raw_u256() {
         // Just as info: `.0` returns the montgomery-reduced field element, `.into_repr` the actual field element
@@ -724,6 +975,7 @@ mod test {
             hashed_inputs: [0; 32],
             recipient_is_associated_token_account: true,
             solana_pay_transfer: false,
+            encrypted_memo: None,
         };
         assert!(valid_inputs.verify_additional_constraints());
 
@@ -767,6 +1019,7 @@ mod test {
             hashed_inputs: u256_from_str_skip_mr("306186522190603117929438292402982536627"),
             recipient_is_associated_token_account: true,
             solana_pay_transfer: false,
+            encrypted_memo: None,
         };
 
         let expected = [
@@ -965,17 +1218,132 @@ mod test {
             "13377023609243152888087996289546074665572546267939720535001129695597521747191",
         );
 
+        let inputs = HashedInputs {
+            recipient,
+            identifier,
+            iv,
+            encrypted_owner,
+            transaction_reference: solana_pay_id,
+            is_associated_token_account,
+            memo: None,
+            encrypted_memo: None,
+            recurring_payment: None,
+            batch_payouts: None,
+        };
+        assert_eq!(generate_hashed_inputs(&inputs), expected);
+
+        // The encrypted memo is bound to the hashed inputs
+        let encrypted_memo = [[1; 32]; ENCRYPTED_MEMO_SIZE / 32];
+        let with_encrypted_memo = generate_hashed_inputs(&HashedInputs {
+            encrypted_memo: Some(&encrypted_memo),
+            ..inputs
+        });
+        assert_ne!(with_encrypted_memo, expected);
+
+        // A memo ending with the bytes of an encrypted memo has a different preimage
+        let memo = encrypted_memo.concat();
+        let with_memo = generate_hashed_inputs(&HashedInputs {
+            memo: Some(&memo),
+            ..inputs
+        });
+        assert_ne!(with_memo, with_encrypted_memo);
+        assert_ne!(with_memo, expected);
+
+        // The memo is length-prefixed
+        let short_memo = [1, 2];
+        let long_memo = [1, 2, 2];
+        assert_ne!(
+            generate_hashed_inputs(&HashedInputs {
+                memo: Some(&short_memo),
+                encrypted_memo: Some(&encrypted_memo),
+                ..inputs
+            }),
+            generate_hashed_inputs(&HashedInputs {
+                memo: Some(&long_memo),
+                ..inputs
+            })
+        );
+
+        // The recurring payment binding is bound to the hashed inputs
+        let recurring_payment = RecurringPaymentBinding {
+            owner: Pubkey::new_from_array([1; 32]),
+            recurring_payment_index: 0,
+        };
+        let with_recurring_payment = generate_hashed_inputs(&HashedInputs {
+            recurring_payment: Some(&recurring_payment),
+            ..inputs
+        });
+        assert_ne!(with_recurring_payment, expected);
+        assert_ne!(
+            with_recurring_payment,
+            generate_hashed_inputs(&HashedInputs {
+                recurring_payment: Some(&RecurringPaymentBinding {
+                    recurring_payment_index: 1,
+                    ..recurring_payment
+                }),
+                ..inputs
+            })
+        );
+
+        // Each recipient/amount pair of the batch payouts is bound to the hashed inputs
+        let recipients = [
+            Pubkey::new_from_array([1; 32]),
+            Pubkey::new_from_array([2; 32]),
+        ];
+        let batch_payouts = BatchPayouts::new(&[(recipients[0], 10), (recipients[1], 20)]).unwrap();
+        let with_batch_payouts = generate_hashed_inputs(&HashedInputs {
+            batch_payouts: Some(&batch_payouts),
+            ..inputs
+        });
+        assert_ne!(with_batch_payouts, expected);
+        for other in [
+            BatchPayouts::new(&[(recipients[0], 10)]).unwrap(),
+            BatchPayouts::new(&[(recipients[0], 10), (recipients[1], 21)]).unwrap(),
+            BatchPayouts::new(&[(recipients[1], 10), (recipients[0], 20)]).unwrap(),
+        ] {
+            assert_ne!(
+                with_batch_payouts,
+                generate_hashed_inputs(&HashedInputs {
+                    batch_payouts: Some(&other),
+                    ..inputs
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_batch_payouts_total_amount() {
+        let recipient = Pubkey::new_unique();
+
+        assert_eq!(BatchPayouts::new(&[]).unwrap().total_amount(), None);
         assert_eq!(
-            generate_hashed_inputs(
-                recipient,
-                identifier,
-                iv,
-                encrypted_owner,
-                solana_pay_id,
-                is_associated_token_account,
-                &None
-            ),
-            expected
+            BatchPayouts::new(&[(recipient, 1), (recipient, 0)])
+                .unwrap()
+                .total_amount(),
+            None
+        );
+        assert_eq!(
+            BatchPayouts::new(&[(recipient, u64::MAX), (recipient, 1)])
+                .unwrap()
+                .total_amount(),
+            None
+        );
+        assert!(BatchPayouts::new(&[(recipient, 1); MAX_BATCH_PAYOUT_RECIPIENTS + 1]).is_none());
+        assert_eq!(
+            BatchPayouts {
+                recipient_count: MAX_BATCH_PAYOUT_RECIPIENTS as u8 + 1,
+                amounts: [1; MAX_BATCH_PAYOUT_RECIPIENTS],
+                ..Default::default()
+            }
+            .total_amount(),
+            None
+        );
+
+        assert_eq!(
+            BatchPayouts::new(&[(recipient, 10), (recipient, 20)])
+                .unwrap()
+                .total_amount(),
+            Some(30)
         );
     }
 }
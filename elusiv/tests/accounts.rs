@@ -10,16 +10,28 @@ use elusiv::state::commitment::{BaseCommitmentBufferAccount, CommitmentHashingAc
 use elusiv::state::program_account::PDAOffset;
 use elusiv::state::queue::{CommitmentQueue, Queue, RingQueue};
 use elusiv::state::{
-    fee::FeeAccount,
+    config::ConfigAccount,
+    denylist::RecipientDenylistAccount,
+    event::EventSequenceAccount,
+    fee::{BasisPointFee, FeeAccount, DEFAULT_NETWORK_FEE_SPLIT},
     governor::{FeeCollectorAccount, GovernorAccount, PoolAccount},
-    nullifier::{NullifierAccount, NullifierChildAccount},
+    note::NoteCiphertextAccount,
+    nullifier::{
+        NullifierAccount, NullifierChildAccount, MIN_SEAL_SUNSET_SLOTS, SEAL_TIMELOCK_SLOTS,
+    },
+    poseidon::PoseidonParamsAccount,
     program_account::{PDAAccount, PDAAccountData, ProgramAccount, SizedAccount},
-    queue::CommitmentQueueAccount,
+    queue::{CommitmentQueueAccount, CommitmentSpillQueueAccount},
+    stats::{EpochMetricsAccount, StatsAccount},
     storage::{StorageAccount, MT_COMMITMENT_COUNT},
+    summary::{StateDigestHistoryAccount, StateSummaryAccount},
 };
-use elusiv::token::SPL_TOKEN_COUNT;
+use elusiv::token::{LAMPORTS_TOKEN_ID, SPL_TOKEN_COUNT};
 use elusiv_types::split_child_account_data_mut;
-use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
 use solana_program_test::*;
 use solana_sdk::signer::Signer;
 
@@ -52,9 +64,18 @@ async fn test_setup_initial_accounts() {
 
     assert_account::<CommitmentHashingAccount>(&mut test, None).await;
     assert_account::<CommitmentQueueAccount>(&mut test, None).await;
+    assert_account::<CommitmentSpillQueueAccount>(&mut test, None).await;
     assert_account::<BaseCommitmentBufferAccount>(&mut test, None).await;
 
     assert_account::<StorageAccount>(&mut test, None).await;
+    assert_account::<EventSequenceAccount>(&mut test, None).await;
+    assert_account::<StateSummaryAccount>(&mut test, None).await;
+    assert_account::<StatsAccount>(&mut test, None).await;
+    assert_account::<EpochMetricsAccount>(&mut test, None).await;
+    assert_account::<StateDigestHistoryAccount>(&mut test, None).await;
+    assert_account::<RecipientDenylistAccount>(&mut test, None).await;
+    assert_account::<ConfigAccount>(&mut test, None).await;
+    assert_account::<PoseidonParamsAccount>(&mut test, None).await;
 }
 
 #[tokio::test]
@@ -72,6 +93,26 @@ async fn test_setup_initial_accounts_duplicate() {
     test.tx_should_fail_simple(&ixs).await;
 }
 
+#[tokio::test]
+async fn test_setup_initial_accounts_versioned() {
+    let mut test = start_test().await;
+    let ixs = initial_single_instance_pdas(test.context().payer.pubkey());
+
+    let mut addresses: Vec<Pubkey> = Vec::new();
+    for ix in &ixs {
+        for account in &ix.accounts {
+            if !account.is_signer && !addresses.contains(&account.pubkey) {
+                addresses.push(account.pubkey);
+            }
+        }
+    }
+    let table = test.create_lookup_table(&addresses).await;
+
+    test.versioned_tx_should_succeed(&ixs, &[], &[table.clone()])
+        .await;
+    test.versioned_tx_should_fail(&ixs, &[], &[table]).await;
+}
+
 #[tokio::test]
 async fn test_enable_token_account() {
     let mut test = start_test().await;
@@ -89,10 +130,8 @@ async fn test_setup_fee_account() {
     let mut test = start_test().await;
     let payer = test.context().payer.pubkey();
 
-    test.ix_should_succeed_simple(ElusivInstruction::setup_governor_account_instruction(
-        WritableSignerAccount(payer),
-    ))
-    .await;
+    test.tx_should_succeed_simple(&initial_single_instance_pdas(payer))
+        .await;
 
     let genesis_fee = genesis_fee(&mut test).await;
     setup_fee(&mut test, 0, genesis_fee.clone()).await;
@@ -102,36 +141,50 @@ async fn test_setup_fee_account() {
         0,
         genesis_fee.clone(),
         WritableSignerAccount(payer),
+        &[SignerAccount(payer)],
     ))
     .await;
 
     pda_account!(fee, FeeAccount, None, Some(0), test);
     assert_eq!(fee.get_program_fee(), genesis_fee);
+    assert_eq!(fee.get_recipient_rent_minimum(), test.rent(0).await.0);
+    assert_eq!(fee.get_network_fee_split(), DEFAULT_NETWORK_FEE_SPLIT);
 
     pda_account!(governor, GovernorAccount, None, None, test);
     assert_eq!(governor.get_program_fee(), genesis_fee);
 
-    // Attempting to set a version higher than genesis (0) will fail
+    // Attempting to skip a version will fail
     test.ix_should_fail_simple(ElusivInstruction::init_new_fee_version_instruction(
-        1,
+        2,
         genesis_fee.clone(),
         WritableSignerAccount(payer),
+        &[SignerAccount(payer)],
     ))
     .await;
 
-    // But after governor allows it, fee_version 1 can be set
-    test.set_pda_account::<GovernorAccount, _>(&elusiv::id(), None, None, |data| {
-        let mut account = GovernorAccount::new(data).unwrap();
-        account.set_fee_version(&1);
-    })
+    // The next version requires the governance authority
+    let mut next_fee = genesis_fee.clone();
+    next_fee.proof_network_fee = BasisPointFee(200);
+    test.ix_should_fail_simple(ElusivInstruction::init_new_fee_version_instruction(
+        1,
+        next_fee.clone(),
+        WritableSignerAccount(payer),
+        &[],
+    ))
     .await;
 
     test.ix_should_succeed_simple(ElusivInstruction::init_new_fee_version_instruction(
         1,
-        genesis_fee,
+        next_fee,
         WritableSignerAccount(payer),
+        &[SignerAccount(payer)],
     ))
     .await;
+
+    // The next version is only mirrored into the governor once it's activated
+    pda_account!(governor, GovernorAccount, None, None, test);
+    assert_eq!(governor.get_fee_version(), 0);
+    assert_eq!(governor.get_program_fee(), genesis_fee);
 }
 
 #[tokio::test]
@@ -157,6 +210,44 @@ async fn test_open_new_merkle_tree() {
     }
 }
 
+#[tokio::test]
+async fn test_health_check() {
+    let mut test = start_test_with_setup().await;
+    create_merkle_tree(&mut test, 0).await;
+
+    test.ix_should_succeed_simple(ElusivInstruction::health_check_instruction(
+        0,
+        LAMPORTS_TOKEN_ID,
+    ))
+    .await;
+
+    // Invalid active MT index
+    create_merkle_tree(&mut test, 1).await;
+    test.ix_should_fail_simple(ElusivInstruction::health_check_instruction(
+        1,
+        LAMPORTS_TOKEN_ID,
+    ))
+    .await;
+}
+
+#[tokio::test]
+async fn test_open_note_ciphertext_account() {
+    let mut test = start_test_with_setup().await;
+    let ix = ElusivInstruction::open_note_ciphertext_account_instruction(
+        LAMPORTS_TOKEN_ID,
+        WritableSignerAccount(test.payer()),
+    );
+
+    test.ix_should_succeed_simple(ix.clone()).await;
+    assert_eq!(
+        test.data(&NoteCiphertextAccount::find(None).0).await.len(),
+        NoteCiphertextAccount::SIZE
+    );
+
+    // Cannot open twice
+    test.ix_should_fail_simple(ix).await;
+}
+
 #[tokio::test]
 async fn test_open_new_merkle_tree_duplicate() {
     let mut test = start_test().await;
@@ -165,6 +256,7 @@ async fn test_open_new_merkle_tree_duplicate() {
     // Cannot init MT twice
     test.ix_should_fail_simple(ElusivInstruction::open_nullifier_account_instruction(
         0,
+        LAMPORTS_TOKEN_ID,
         WritableSignerAccount(test.payer()),
     ))
     .await;
@@ -173,10 +265,14 @@ async fn test_open_new_merkle_tree_duplicate() {
     let k = test
         .create_program_account_rent_exempt(&elusiv::id(), NullifierChildAccount::SIZE)
         .await;
+    record_nullifier_child_account(&mut test, k.pubkey(), 0, 1).await;
     test.ix_should_fail_simple(
         ElusivInstruction::enable_nullifier_child_account_instruction(
             0,
             1,
+            LAMPORTS_TOKEN_ID,
+            test.payer(),
+            WritableSignerAccount(test.payer()),
             WritableUserAccount(k.pubkey()),
         ),
     )
@@ -199,6 +295,7 @@ async fn test_reset_active_mt() {
     // Failure since active MT is not full
     test.ix_should_fail_simple(ElusivInstruction::reset_active_merkle_tree_instruction(
         0,
+        LAMPORTS_TOKEN_ID,
         &storage_accounts,
     ))
     .await;
@@ -223,9 +320,12 @@ async fn test_reset_active_mt() {
     // Failure since active_nullifier_account is invalid
     test.ix_should_fail_simple(Instruction::new_with_bytes(
         elusiv::id(),
-        &ElusivInstruction::ResetActiveMerkleTree { active_mt_index: 0 }
-            .try_to_vec()
-            .unwrap()[..],
+        &ElusivInstruction::ResetActiveMerkleTree {
+            active_mt_index: 0,
+            token_id: LAMPORTS_TOKEN_ID,
+        }
+        .try_to_vec()
+        .unwrap()[..],
         vec![
             AccountMeta::new(StorageAccount::find(None).0, false),
             AccountMeta::new(CommitmentQueueAccount::find(None).0, false),
@@ -237,14 +337,19 @@ async fn test_reset_active_mt() {
     // Success
     test.ix_should_succeed_simple(Instruction::new_with_bytes(
         elusiv::id(),
-        &ElusivInstruction::ResetActiveMerkleTree { active_mt_index: 0 }
-            .try_to_vec()
-            .unwrap()[..],
+        &ElusivInstruction::ResetActiveMerkleTree {
+            active_mt_index: 0,
+            token_id: LAMPORTS_TOKEN_ID,
+        }
+        .try_to_vec()
+        .unwrap()[..],
         vec![
             AccountMeta::new(StorageAccount::find(None).0, false),
             AccountMeta::new(root_storage_account, false),
             AccountMeta::new(CommitmentQueueAccount::find(None).0, false),
             AccountMeta::new(NullifierAccount::find(Some(0)).0, false),
+            AccountMeta::new(StateSummaryAccount::find(None).0, false),
+            AccountMeta::new(EventSequenceAccount::find(None).0, false),
         ],
     ))
     .await;
@@ -276,6 +381,7 @@ async fn test_reset_active_mt() {
             .enqueue(CommitmentHashRequest {
                 commitment: [0; 32],
                 min_batching_rate: 1,
+                enqueue_slot: 0,
                 fee_version: 0,
             })
             .unwrap();
@@ -283,6 +389,7 @@ async fn test_reset_active_mt() {
             .enqueue(CommitmentHashRequest {
                 commitment: [0; 32],
                 min_batching_rate: 1,
+                enqueue_slot: 0,
                 fee_version: 0,
             })
             .unwrap();
@@ -292,12 +399,14 @@ async fn test_reset_active_mt() {
     // Failure because first storage account (containing root) is missing
     test.ix_should_fail_simple(ElusivInstruction::reset_active_merkle_tree_instruction(
         1,
+        LAMPORTS_TOKEN_ID,
         &[],
     ))
     .await;
 
     test.ix_should_succeed_simple(ElusivInstruction::reset_active_merkle_tree_instruction(
         1,
+        LAMPORTS_TOKEN_ID,
         &storage_accounts,
     ))
     .await;
@@ -311,12 +420,14 @@ async fn test_global_child_account_duplicates() {
     // Open two MTs
     test.ix_should_succeed_simple(ElusivInstruction::open_nullifier_account_instruction(
         0,
+        LAMPORTS_TOKEN_ID,
         WritableSignerAccount(test.payer()),
     ))
     .await;
 
     test.ix_should_succeed_simple(ElusivInstruction::open_nullifier_account_instruction(
         1,
+        LAMPORTS_TOKEN_ID,
         WritableSignerAccount(test.payer()),
     ))
     .await;
@@ -326,10 +437,14 @@ async fn test_global_child_account_duplicates() {
         .create_program_account_rent_exempt(&elusiv::id(), NullifierChildAccount::SIZE)
         .await;
 
+    record_nullifier_child_account(&mut test, account.pubkey(), 0, 0).await;
     test.ix_should_succeed_simple(
         ElusivInstruction::enable_nullifier_child_account_instruction(
             0,
             0,
+            LAMPORTS_TOKEN_ID,
+            test.payer(),
+            WritableSignerAccount(test.payer()),
             WritableUserAccount(account.pubkey()),
         ),
     )
@@ -340,26 +455,37 @@ async fn test_global_child_account_duplicates() {
         ElusivInstruction::enable_nullifier_child_account_instruction(
             0,
             0,
+            LAMPORTS_TOKEN_ID,
+            test.payer(),
+            WritableSignerAccount(test.payer()),
             WritableUserAccount(account.pubkey()),
         ),
     )
     .await;
 
     // Setting twice in same account (different index)
+    record_nullifier_child_account(&mut test, account.pubkey(), 0, 1).await;
     test.ix_should_fail_simple(
         ElusivInstruction::enable_nullifier_child_account_instruction(
             0,
             1,
+            LAMPORTS_TOKEN_ID,
+            test.payer(),
+            WritableSignerAccount(test.payer()),
             WritableUserAccount(account.pubkey()),
         ),
     )
     .await;
 
     // Setting in different account
+    record_nullifier_child_account(&mut test, account.pubkey(), 1, 0).await;
     test.ix_should_fail_simple(
         ElusivInstruction::enable_nullifier_child_account_instruction(
             1,
             0,
+            LAMPORTS_TOKEN_ID,
+            test.payer(),
+            WritableSignerAccount(test.payer()),
             WritableUserAccount(account.pubkey()),
         ),
     )
@@ -368,6 +494,7 @@ async fn test_global_child_account_duplicates() {
     // Setting in storage-account
     test.ix_should_fail_simple(ElusivInstruction::enable_storage_child_account_instruction(
         0,
+        LAMPORTS_TOKEN_ID,
         WritableUserAccount(account.pubkey()),
     ))
     .await;
@@ -376,10 +503,14 @@ async fn test_global_child_account_duplicates() {
     let account2 = test
         .create_program_account_rent_exempt(&elusiv::id(), NullifierChildAccount::SIZE)
         .await;
+    record_nullifier_child_account(&mut test, account2.pubkey(), 0, 0).await;
     test.ix_should_fail_simple(
         ElusivInstruction::enable_nullifier_child_account_instruction(
             0,
             0,
+            LAMPORTS_TOKEN_ID,
+            test.payer(),
+            WritableSignerAccount(test.payer()),
             WritableUserAccount(account2.pubkey()),
         ),
     )
@@ -393,10 +524,14 @@ async fn test_global_child_account_duplicates() {
         .await;
 
     // Setting a different account at a different index should succeed
+    record_nullifier_child_account(&mut test, account2.pubkey(), 0, 1).await;
     test.ix_should_succeed_simple(
         ElusivInstruction::enable_nullifier_child_account_instruction(
             0,
             1,
+            LAMPORTS_TOKEN_ID,
+            test.payer(),
+            WritableSignerAccount(test.payer()),
             WritableUserAccount(account2.pubkey()),
         ),
     )
@@ -407,3 +542,60 @@ async fn test_global_child_account_duplicates() {
     assert_eq!(data[0], 1);
     assert_eq!(&data[1..5], &[0, 0, 0, 0]);
 }
+
+#[tokio::test]
+async fn test_seal_closed_merkle_tree() {
+    let mut test = start_test_with_setup().await;
+    let payer = test.payer();
+
+    test.ix_should_succeed_simple(ElusivInstruction::open_nullifier_account_instruction(
+        0,
+        LAMPORTS_TOKEN_ID,
+        WritableSignerAccount(payer),
+    ))
+    .await;
+    test.ix_should_succeed_simple(ElusivInstruction::set_seal_policy_instruction(
+        MIN_SEAL_SUNSET_SLOTS,
+        &[SignerAccount(payer)],
+    ))
+    .await;
+
+    // The MT is closed
+    test.set_pda_account::<StorageAccount, _>(&elusiv::id(), None, None, |data| {
+        let mut storage_account = StorageAccount::new(data).unwrap();
+        storage_account.set_trees_count(&1);
+    })
+    .await;
+
+    let announce_seal_instruction = ElusivInstruction::announce_nullifier_set_seal_instruction(
+        0,
+        LAMPORTS_TOKEN_ID,
+        &[SignerAccount(payer)],
+    );
+    let seal_instruction = ElusivInstruction::seal_nullifier_set_instruction(0, LAMPORTS_TOKEN_ID);
+
+    // The MT is not sunset yet
+    test.ix_should_fail_simple(announce_seal_instruction.clone())
+        .await;
+
+    warp_to_slot(&mut test, MIN_SEAL_SUNSET_SLOTS + 1).await;
+    test.ix_should_succeed_simple(announce_seal_instruction)
+        .await;
+
+    // Timelock
+    test.ix_should_fail_simple(seal_instruction.clone()).await;
+
+    warp_to_slot(&mut test, MIN_SEAL_SUNSET_SLOTS + SEAL_TIMELOCK_SLOTS + 1).await;
+    test.ix_should_succeed_simple(seal_instruction.clone())
+        .await;
+
+    nullifier_account(Some(0), &mut test, |n: &NullifierAccount| {
+        assert!(n.get_is_sealed());
+        assert!(!n.can_insert_nullifier_hash([1; 32]).unwrap_or(false));
+    })
+    .await;
+
+    // A nullifier-set is only sealed once
+    test.ix_should_fail_simple(nonce_instruction(seal_instruction))
+        .await;
+}
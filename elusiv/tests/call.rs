@@ -0,0 +1,146 @@
+//! Tests withdraw-and-call executions and refunds
+
+mod common;
+
+use borsh::BorshSerialize;
+use common::*;
+use elusiv::instruction::*;
+use elusiv::processor::{withdraw_and_call_authority, WithdrawAndCall, WithdrawAndCallAccountMeta};
+use elusiv::state::call::WITHDRAW_AND_CALL_PROGRAMS;
+use solana_program::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_program_test::*;
+
+/// Starts the test with the mock program as the first whitelisted program
+async fn start_call_test() -> ElusivProgramTest {
+    compile_mock_program();
+
+    ElusivProgramTest::start(&[
+        (
+            String::from("elusiv"),
+            elusiv::id(),
+            processor!(elusiv::process_instruction),
+        ),
+        (
+            String::from("mock_program"),
+            WITHDRAW_AND_CALL_PROGRAMS[0],
+            processor!(mock_program::process_instruction),
+        ),
+    ])
+    .await
+}
+
+fn mock_call(refund_slot: u64) -> WithdrawAndCall {
+    WithdrawAndCall {
+        program_id: WITHDRAW_AND_CALL_PROGRAMS[0],
+        accounts: vec![WithdrawAndCallAccountMeta {
+            pubkey: Pubkey::new_unique(),
+            is_writable: true,
+        }],
+        data: vec![1, 2, 3],
+        remainder_recipient: Pubkey::new_unique(),
+        refund_slot,
+    }
+}
+
+#[tokio::test]
+async fn test_execute_withdraw_and_call() {
+    let mut test = start_call_test().await;
+    let call = mock_call(u64::MAX);
+    let (authority, _) = withdraw_and_call_authority(&call).unwrap();
+    test.airdrop_lamports(&authority, LAMPORTS_PER_SOL).await;
+
+    let ix = ElusivInstruction::execute_withdraw_and_call_instruction(
+        call.clone(),
+        WritableUserAccount(authority),
+        UserAccount(call.program_id),
+        WritableUserAccount(call.remainder_recipient),
+        &writable_user_accounts(&[call.accounts[0].pubkey]),
+    );
+
+    // Redirected call
+    let mut redirected_call = call.clone();
+    redirected_call.data = vec![1, 2, 4];
+    let mut invalid_ix = ix.clone();
+    invalid_ix.data = ElusivInstruction::ExecuteWithdrawAndCall {
+        call: redirected_call,
+    }
+    .try_to_vec()
+    .unwrap();
+    test.ix_should_fail_simple(invalid_ix).await;
+
+    test.ix_should_succeed_simple(ix).await;
+
+    // The mock program keeps no funds, so all lamports are transferred to the `remainder_recipient`
+    assert!(test.account_does_not_exist(&authority).await);
+    assert_eq!(
+        test.lamports(&call.remainder_recipient).await.0,
+        LAMPORTS_PER_SOL
+    );
+}
+
+#[tokio::test]
+async fn test_execute_withdraw_and_call_versioned() {
+    let mut test = start_call_test().await;
+    let call = mock_call(u64::MAX);
+    let (authority, _) = withdraw_and_call_authority(&call).unwrap();
+    test.airdrop_lamports(&authority, LAMPORTS_PER_SOL).await;
+
+    let ix = ElusivInstruction::execute_withdraw_and_call_instruction(
+        call.clone(),
+        WritableUserAccount(authority),
+        UserAccount(call.program_id),
+        WritableUserAccount(call.remainder_recipient),
+        &writable_user_accounts(&[call.accounts[0].pubkey]),
+    );
+    let table = test
+        .create_lookup_table(&[authority, call.remainder_recipient, call.accounts[0].pubkey])
+        .await;
+
+    test.versioned_tx_should_succeed(&[ix.clone()], &[], &[table.clone()])
+        .await;
+
+    assert!(test.account_does_not_exist(&authority).await);
+    assert_eq!(
+        test.lamports(&call.remainder_recipient).await.0,
+        LAMPORTS_PER_SOL
+    );
+
+    // The authority is not funded anymore
+    test.versioned_tx_should_fail(&[ix], &[], &[table]).await;
+}
+
+#[tokio::test]
+async fn test_refund_withdraw_and_call() {
+    let mut test = start_call_test().await;
+    let refund_slot = 100;
+    let call = mock_call(refund_slot);
+    let (authority, _) = withdraw_and_call_authority(&call).unwrap();
+    test.airdrop_lamports(&authority, LAMPORTS_PER_SOL).await;
+
+    let ix = ElusivInstruction::refund_withdraw_and_call_instruction(
+        call.clone(),
+        WritableUserAccount(authority),
+        WritableUserAccount(call.remainder_recipient),
+    );
+
+    // Refund slot is not reached
+    test.ix_should_fail_simple(ix.clone()).await;
+
+    warp_to_slot(&mut test, refund_slot + 1).await;
+
+    // Invalid remainder recipient
+    let invalid_ix = ElusivInstruction::refund_withdraw_and_call_instruction(
+        call.clone(),
+        WritableUserAccount(authority),
+        WritableUserAccount(Pubkey::new_unique()),
+    );
+    test.ix_should_fail_simple(invalid_ix).await;
+
+    test.ix_should_succeed_simple(ix).await;
+
+    assert!(test.account_does_not_exist(&authority).await);
+    assert_eq!(
+        test.lamports(&call.remainder_recipient).await.0,
+        LAMPORTS_PER_SOL
+    );
+}
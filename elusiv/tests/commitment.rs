@@ -18,11 +18,12 @@ use elusiv::{
     state::{
         commitment::{BaseCommitmentHashingAccount, CommitmentHashingAccount},
         governor::{FeeCollectorAccount, GovernorAccount, PoolAccount},
+        pending::PendingPaymentAccount,
         program_account::{PDAAccount, ProgramAccount, SizedAccount},
         queue::{CommitmentQueue, CommitmentQueueAccount, Queue, RingQueue},
         storage::{StorageAccount, EMPTY_TREE, MT_HEIGHT},
     },
-    token::{Lamports, Token, TokenPrice, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID},
+    token::{elusiv_token, Lamports, Token, TokenPrice, LAMPORTS_TOKEN_ID, USDC_TOKEN_ID},
     types::{RawU256, U256},
 };
 use elusiv_computation::PartialComputation;
@@ -92,6 +93,7 @@ async fn test_store_base_commitment_lamports_transfer() {
             UserAccount(sol_price_account),
             WritableUserAccount(hashing_account_pubkey),
             UserAccount(system_program::id()),
+            UserAccount(system_program::id()),
         ),
         &[&client.keypair, &warden.keypair],
     )
@@ -118,6 +120,92 @@ async fn test_store_base_commitment_lamports_transfer() {
     assert_eq!(computation_fee, warden.lamports(&mut test).await);
 }
 
+#[tokio::test]
+async fn test_refund_pending_payment() {
+    let mut test = start_test_with_setup().await;
+    let sender = test.new_actor().await;
+    let warden = test.new_actor().await;
+
+    let pool = PoolAccount::find(None).0;
+    let pending_payment_account = PendingPaymentAccount::find_with_pubkey(sender.pubkey, Some(0)).0;
+    let amount = 1_000_000_000;
+    let expiry_slot = 100;
+
+    let pending_payment_rent = test.rent(PendingPaymentAccount::SIZE).await.0;
+    sender.airdrop(0, pending_payment_rent, &mut test).await;
+    test.ix_should_succeed(
+        ElusivInstruction::create_pending_payment_instruction(
+            0,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            amount,
+            expiry_slot,
+            RawU256::new(u256_from_str_skip_mr(
+                "8337064132573119120838379738103457054645361649757131991036638108422638197362",
+            )),
+            [0; 32],
+            WritableSignerAccount(sender.pubkey),
+        ),
+        &[&sender.keypair],
+    )
+    .await;
+
+    // The send of `amount` to the pending payment
+    test.airdrop_lamports(&pending_payment_account, amount)
+        .await;
+
+    let hashing_account_rent = test.rent(BaseCommitmentHashingAccount::SIZE).await;
+    warden.airdrop(0, hashing_account_rent.0, &mut test).await;
+
+    let (hashing_account_pubkey, hashing_account_bump) =
+        BaseCommitmentHashingAccount::find(Some(0));
+    let refund_instruction = ElusivInstruction::refund_pending_payment_instruction(
+        0,
+        0,
+        hashing_account_bump,
+        WritableSignerAccount(warden.pubkey),
+        WritableUserAccount(sender.pubkey),
+        WritableUserAccount(hashing_account_pubkey),
+    );
+
+    // Not yet expired
+    test.ix_should_fail(refund_instruction.clone(), &[&warden.keypair])
+        .await;
+
+    warp_to_slot(&mut test, expiry_slot).await;
+
+    // Halted program
+    test.set_pda_account::<GovernorAccount, _>(&elusiv::id(), None, None, |data| {
+        GovernorAccount::new(data).unwrap().set_is_halted(&true);
+    })
+    .await;
+    test.ix_should_fail(refund_instruction.clone(), &[&warden.keypair])
+        .await;
+    test.set_pda_account::<GovernorAccount, _>(&elusiv::id(), None, None, |data| {
+        GovernorAccount::new(data).unwrap().set_is_halted(&false);
+    })
+    .await;
+
+    let pool_lamports = test.lamports(&pool).await.0;
+    test.ix_should_succeed(refund_instruction, &[&warden.keypair])
+        .await;
+
+    // The pending payment is closed and its rent is returned to the sender
+    assert!(test.account_does_not_exist(&pending_payment_account).await);
+    assert_eq!(pending_payment_rent, sender.lamports(&mut test).await);
+
+    // The `amount` is re-credited through a new commitment
+    assert_eq!(pool_lamports + amount, test.lamports(&pool).await.0);
+    pda_account!(
+        hashing_account,
+        BaseCommitmentHashingAccount,
+        None,
+        Some(0),
+        test
+    );
+    assert!(hashing_account.get_is_active());
+}
+
 #[tokio::test]
 async fn test_store_base_commitment_token_transfer() {
     let mut test = start_test_with_setup().await;
@@ -208,6 +296,7 @@ async fn test_store_base_commitment_token_transfer() {
             UserAccount(token_price_account),
             WritableUserAccount(hashing_account_pubkey),
             UserAccount(spl_token::id()),
+            UserAccount(elusiv_token(USDC_TOKEN_ID).unwrap().mint),
         ),
         &[&client.keypair, &warden.keypair],
     )
@@ -336,6 +425,7 @@ async fn test_base_commitment_lamports() {
             UserAccount(system_program::id()),
             WritableUserAccount(hashing_account_pubkey),
             UserAccount(system_program::id()),
+            UserAccount(system_program::id()),
         ),
         &[&client.keypair, &warden_a.keypair],
     )
@@ -357,6 +447,7 @@ async fn test_base_commitment_lamports() {
             UserAccount(system_program::id()),
             WritableUserAccount(hashing_account_pubkey),
             UserAccount(system_program::id()),
+            UserAccount(system_program::id()),
         ),
         &[&client.keypair, &warden_a.keypair],
     )
@@ -472,7 +563,11 @@ async fn test_base_commitment_lamports() {
     let finalize_ix = ElusivInstruction::finalize_base_commitment_hash_instruction(
         0,
         0,
+        LAMPORTS_TOKEN_ID,
+        WritableUserAccount(warden_a.pubkey),
         WritableUserAccount(warden_a.pubkey),
+        WritableUserAccount(pool),
+        UserAccount(system_program::id()),
     );
 
     // Compute each base_commitment_hash
@@ -507,7 +602,11 @@ async fn test_base_commitment_lamports() {
         ElusivInstruction::finalize_base_commitment_hash_instruction(
             0,
             0,
+            LAMPORTS_TOKEN_ID,
+            WritableUserAccount(warden_b.pubkey),
             WritableUserAccount(warden_b.pubkey),
+            WritableUserAccount(pool),
+            UserAccount(system_program::id()),
         ),
     )
     .await;
@@ -515,7 +614,11 @@ async fn test_base_commitment_lamports() {
     let finalize_ix = ElusivInstruction::finalize_base_commitment_hash_instruction(
         0,
         0,
+        LAMPORTS_TOKEN_ID,
         WritableUserAccount(warden_a.pubkey),
+        WritableUserAccount(warden_a.pubkey),
+        WritableUserAccount(pool),
+        UserAccount(system_program::id()),
     );
 
     // Finalize fails: two finalize ix in a single tx
@@ -644,6 +747,7 @@ async fn test_base_commitment_token() {
             UserAccount(token_price_account),
             WritableUserAccount(hashing_account_pubkey),
             UserAccount(spl_token::id()),
+            UserAccount(elusiv_token(USDC_TOKEN_ID).unwrap().mint),
         ),
         &[&client.keypair, &warden.keypair],
     )
@@ -661,7 +765,11 @@ async fn test_base_commitment_token() {
         ElusivInstruction::finalize_base_commitment_hash_instruction(
             0,
             0,
+            USDC_TOKEN_ID,
             WritableUserAccount(warden.pubkey),
+            WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
+            WritableUserAccount(pool_account),
+            UserAccount(spl_token::id()),
         ),
     )
     .await;
@@ -716,6 +824,9 @@ pub fn base_commitment_request(
         token_id,
         fee_version,
         min_batching_rate,
+        fee_payer_repayment: 0,
+        anonymity_delay_slots: 0,
+        is_priority: false,
     }
 }
 
@@ -750,6 +861,7 @@ async fn test_single_commitment() {
                 commitment: request.commitment.reduce(),
                 fee_version: 0,
                 min_batching_rate: 0,
+                enqueue_slot: 0,
             })
             .unwrap();
     })
@@ -768,8 +880,8 @@ async fn test_single_commitment() {
 
     // Init succeeds
     test.tx_should_succeed_simple(&[
-        ElusivInstruction::init_commitment_hash_setup_instruction(false, &[]),
-        ElusivInstruction::init_commitment_hash_instruction(false),
+        ElusivInstruction::init_commitment_hash_setup_instruction(false, LAMPORTS_TOKEN_ID, &[]),
+        ElusivInstruction::init_commitment_hash_instruction(false, LAMPORTS_TOKEN_ID),
     ])
     .await;
 
@@ -794,18 +906,20 @@ async fn test_single_commitment() {
 
     // Second init fails, since a hashing is already active
     test.tx_should_fail_simple(&[
-        ElusivInstruction::init_commitment_hash_setup_instruction(false, &[]),
-        ElusivInstruction::init_commitment_hash_instruction(false),
+        ElusivInstruction::init_commitment_hash_setup_instruction(false, LAMPORTS_TOKEN_ID, &[]),
+        ElusivInstruction::init_commitment_hash_instruction(false, LAMPORTS_TOKEN_ID),
     ])
     .await;
 
     let finalize_ix = ElusivInstruction::finalize_commitment_hash_instruction(
+        LAMPORTS_TOKEN_ID,
         &writable_user_accounts(&storage_accounts),
     );
 
     let compute_ix = ElusivInstruction::compute_commitment_hash_instruction(
         0,
         0,
+        LAMPORTS_TOKEN_ID,
         WritableSignerAccount(warden.pubkey),
     );
 
@@ -920,6 +1034,7 @@ async fn test_commitment_full_queue() {
         commitment: u256_from_str("0"),
         fee_version: 0,
         min_batching_rate: 0,
+        enqueue_slot: 0,
     };
 
     // Enqueue all
@@ -944,7 +1059,11 @@ async fn test_commitment_full_queue() {
         ElusivInstruction::finalize_base_commitment_hash_instruction(
             0,
             0,
+            LAMPORTS_TOKEN_ID,
             WritableUserAccount(warden.pubkey),
+            WritableUserAccount(warden.pubkey),
+            WritableUserAccount(PoolAccount::find(None).0),
+            UserAccount(system_program::id()),
         ),
     )
     .await;
@@ -972,6 +1091,7 @@ async fn test_commitment_correct_storage_account_insertion() {
         .await;
 
         test.ix_should_succeed_simple(ElusivInstruction::finalize_commitment_hash_instruction(
+            LAMPORTS_TOKEN_ID,
             &writable_user_accounts(&storage_accounts),
         ))
         .await;
@@ -1030,6 +1150,7 @@ async fn test_commitment_hash_multiple_commitments_zero_batch() {
             commitment: *c,
             fee_version: 0,
             min_batching_rate: 0,
+            enqueue_slot: 0,
         })
         .collect();
 
@@ -1071,9 +1192,10 @@ async fn test_commitment_hash_multiple_commitments_zero_batch() {
         test.tx_should_succeed_simple(&[
             ElusivInstruction::init_commitment_hash_setup_instruction(
                 false,
+                LAMPORTS_TOKEN_ID,
                 &user_accounts(&storage_accounts),
             ),
-            ElusivInstruction::init_commitment_hash_instruction(false),
+            ElusivInstruction::init_commitment_hash_instruction(false, LAMPORTS_TOKEN_ID),
         ])
         .await;
 
@@ -1084,6 +1206,7 @@ async fn test_commitment_hash_multiple_commitments_zero_batch() {
                     ElusivInstruction::compute_commitment_hash_instruction(
                         0,
                         0,
+                        LAMPORTS_TOKEN_ID,
                         WritableSignerAccount(warden.pubkey),
                     ),
                 ],
@@ -1093,6 +1216,7 @@ async fn test_commitment_hash_multiple_commitments_zero_batch() {
         }
 
         test.ix_should_succeed_simple(ElusivInstruction::finalize_commitment_hash_instruction(
+            LAMPORTS_TOKEN_ID,
             &writable_user_accounts(&storage_accounts),
         ))
         .await;
@@ -1141,6 +1265,7 @@ async fn test_commitment_hash_with_batching_rate(
             commitment: *c,
             fee_version: 0,
             min_batching_rate: batching_rate,
+            enqueue_slot: 0,
         })
         .collect();
 
@@ -1156,9 +1281,10 @@ async fn test_commitment_hash_with_batching_rate(
     test.tx_should_succeed_simple(&[
         ElusivInstruction::init_commitment_hash_setup_instruction(
             false,
+            LAMPORTS_TOKEN_ID,
             &user_accounts(&storage_accounts),
         ),
-        ElusivInstruction::init_commitment_hash_instruction(false),
+        ElusivInstruction::init_commitment_hash_instruction(false, LAMPORTS_TOKEN_ID),
     ])
     .await;
 
@@ -1169,6 +1295,7 @@ async fn test_commitment_hash_with_batching_rate(
                 ElusivInstruction::compute_commitment_hash_instruction(
                     0,
                     0,
+                    LAMPORTS_TOKEN_ID,
                     WritableSignerAccount(warden.pubkey),
                 ),
             ],
@@ -1179,6 +1306,7 @@ async fn test_commitment_hash_with_batching_rate(
 
     for _ in 0..=batching_rate {
         test.ix_should_succeed_simple(ElusivInstruction::finalize_commitment_hash_instruction(
+            LAMPORTS_TOKEN_ID,
             &writable_user_accounts(&storage_accounts),
         ))
         .await;
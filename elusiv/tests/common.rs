@@ -1,13 +1,17 @@
 #![allow(unused_macros)]
 #![allow(dead_code)]
 
+use borsh::BorshSerialize;
 use elusiv::{
     fields::fr_to_u256_le,
     instruction::ElusivInstruction,
     proof::verifier::{CombinedMillerLoop, FinalExponentiation},
     state::{
+        child::{ChildAccountParent, ChildAccountRecordAccount, ChildAccountRecordAccountEager},
         fee::{BasisPointFee, ProgramFee},
+        governor::{GovernanceMultisig, GovernorAccount, MAX_GOVERNANCE_SIGNERS},
         nullifier::NullifierAccount,
+        poseidon::PoseidonParamsAccount,
         storage::StorageAccount,
     },
     types::U256,
@@ -15,7 +19,8 @@ use elusiv::{
 use elusiv_computation::PartialComputation;
 pub use elusiv_test::*;
 use elusiv_types::{
-    elusiv_token, Lamports, PDAAccount, PDAOffset, WritableSignerAccount, WritableUserAccount,
+    elusiv_token, Lamports, PDAAccount, PDAAccountData, PDAOffset, ProgramAccount, SignerAccount,
+    WritableSignerAccount, WritableUserAccount, LAMPORTS_TOKEN_ID,
 };
 use std::str::FromStr;
 
@@ -45,6 +50,7 @@ pub async fn genesis_fee(test: &mut ElusivProgramTest) -> ProgramFee {
         proof_network_fee: BasisPointFee(100),
         base_commitment_subvention: Lamports(33),
         proof_subvention: Lamports(44),
+        merge_subvention: Lamports(88),
         warden_hash_tx_reward: Lamports(300),
         warden_proof_reward: Lamports(555),
         proof_base_tx_count: (CombinedMillerLoop::TX_COUNT + FinalExponentiation::TX_COUNT + 2)
@@ -55,20 +61,47 @@ pub async fn genesis_fee(test: &mut ElusivProgramTest) -> ProgramFee {
 pub async fn setup_initial_pdas(test: &mut ElusivProgramTest) {
     let ixs = initial_single_instance_pdas(test.payer());
     test.tx_should_succeed_simple(&ixs).await;
+
+    test.set_pda_account::<PoseidonParamsAccount, _>(&elusiv::id(), None, None, |data| {
+        let mut account = PoseidonParamsAccount::new(data).unwrap();
+        account.load_default_params();
+    })
+    .await;
 }
 
 pub fn initial_single_instance_pdas(payer: Pubkey) -> Vec<Instruction> {
     vec![
         ElusivInstruction::setup_governor_account_instruction(WritableSignerAccount(payer)),
         ElusivInstruction::open_single_instance_accounts_instruction(WritableSignerAccount(payer)),
+        ElusivInstruction::open_poseidon_params_account_instruction(WritableSignerAccount(payer)),
     ]
 }
 
+/// Configures a 1-of-1 governance multisig of `authority` (the program's keypair is not available in tests)
+pub async fn set_governance_authority(test: &mut ElusivProgramTest, authority: Pubkey) {
+    let mut signers = [[0; 32]; MAX_GOVERNANCE_SIGNERS];
+    signers[0] = authority.to_bytes();
+
+    test.set_pda_account::<GovernorAccount, _>(&elusiv::id(), None, None, |data| {
+        let mut account = GovernorAccount::new(data).unwrap();
+        account.set_governance_multisig(&GovernanceMultisig {
+            threshold: 1,
+            signer_count: 1,
+            signers,
+        });
+    })
+    .await;
+}
+
 pub async fn setup_fee(test: &mut ElusivProgramTest, fee_version: u32, program_fee: ProgramFee) {
+    let payer = test.payer();
+    set_governance_authority(test, payer).await;
+
     let ix = ElusivInstruction::init_new_fee_version_instruction(
         fee_version,
         program_fee,
-        WritableSignerAccount(test.payer()),
+        WritableSignerAccount(payer),
+        &[SignerAccount(payer)],
     );
     test.ix_should_succeed_simple(ix).await;
 }
@@ -81,6 +114,7 @@ pub async fn setup_storage_account(test: &mut ElusivProgramTest) -> Vec<Pubkey>
     for (i, p) in pubkeys.iter().enumerate() {
         instructions.push(ElusivInstruction::enable_storage_child_account_instruction(
             i as u32,
+            LAMPORTS_TOKEN_ID,
             WritableUserAccount(*p),
         ));
     }
@@ -89,20 +123,53 @@ pub async fn setup_storage_account(test: &mut ElusivProgramTest) -> Vec<Pubkey>
     pubkeys
 }
 
+/// Records the payer as the creator of the `child_account` with `child_index` of the [`NullifierAccount`] with `mt_index` (replaces `CreateChildAccount`)
+pub async fn record_nullifier_child_account(
+    test: &mut ElusivProgramTest,
+    child_account: Pubkey,
+    mt_index: u32,
+    child_index: u32,
+) {
+    let parent = ChildAccountParent::Nullifier {
+        token_id: LAMPORTS_TOKEN_ID,
+        mt_index,
+    };
+    let (pda, bump) = ChildAccountRecordAccount::find_with_pubkey(child_account, None);
+    let data = ChildAccountRecordAccountEager {
+        pda_data: PDAAccountData {
+            bump_seed: bump,
+            version: 0,
+        },
+        payer: test.payer(),
+        parent: parent.pubkey().unwrap(),
+        child_index,
+    }
+    .try_to_vec()
+    .unwrap();
+    test.set_program_account_rent_exempt(&elusiv::id(), &pda, &data)
+        .await;
+}
+
 pub async fn create_merkle_tree(test: &mut ElusivProgramTest, mt_index: u32) -> Vec<Pubkey> {
     let mut instructions = vec![ElusivInstruction::open_nullifier_account_instruction(
         mt_index,
+        LAMPORTS_TOKEN_ID,
         WritableSignerAccount(test.payer()),
     )];
 
     let pubkeys = test
         .create_parent_account::<NullifierAccount>(&elusiv::id())
         .await;
+    let payer = test.payer();
     for (i, p) in pubkeys.iter().enumerate() {
+        record_nullifier_child_account(test, *p, mt_index, i as u32).await;
         instructions.push(
             ElusivInstruction::enable_nullifier_child_account_instruction(
                 mt_index,
                 i as u32,
+                LAMPORTS_TOKEN_ID,
+                payer,
+                WritableSignerAccount(payer),
                 WritableUserAccount(*p),
             ),
         );
@@ -112,6 +179,13 @@ pub async fn create_merkle_tree(test: &mut ElusivProgramTest, mt_index: u32) ->
     pubkeys
 }
 
+/// Warps to `slot` (with a new blockhash, so previously processed transactions can be repeated)
+pub async fn warp_to_slot(test: &mut ElusivProgramTest, slot: u64) {
+    let context = test.context();
+    context.warp_to_slot(slot).unwrap();
+    context.last_blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+}
+
 pub async fn storage_accounts(test: &mut ElusivProgramTest) -> Vec<Pubkey> {
     let mut data = test.data(&StorageAccount::find(None).0).await;
     test.child_accounts::<StorageAccount>(&mut data).await
@@ -14,22 +14,24 @@ use elusiv::proof::verifier::{
     prepare_public_inputs_instructions, proof_from_str, CombinedMillerLoop, FinalExponentiation,
     VerificationStep,
 };
-use elusiv::proof::vkey::{SendQuadraVKey, VerifyingKeyInfo};
+use elusiv::proof::vkey::{SendQuadraVKey, VerifyingKey, VerifyingKeyInfo};
 use elusiv::state::fee::ProgramFee;
 use elusiv::state::governor::{FeeCollectorAccount, PoolAccount};
-use elusiv::state::nullifier::{NullifierAccount, NullifierMap, NULLIFIERS_PER_ACCOUNT};
+use elusiv::state::nullifier::{
+    nullifier_account_offsets, NullifierAccount, NullifierMap, NULLIFIERS_PER_ACCOUNT,
+};
 use elusiv::state::program_account::{PDAAccount, PDAAccountData, ProgramAccount, SizedAccount};
 use elusiv::state::proof::{VerificationAccount, VerificationState};
-use elusiv::state::storage::{empty_root_raw, StorageAccount, MT_HEIGHT};
+use elusiv::state::storage::{empty_root_raw, token_pool_offset, StorageAccount, MT_HEIGHT};
 use elusiv::state::vkey::{VKeyAccount, VKeyAccountEager};
 use elusiv::token::{
     spl_token_account_data, Lamports, Token, TokenPrice, LAMPORTS_TOKEN_ID, TOKENS, USDC_TOKEN_ID,
     USDT_TOKEN_ID,
 };
 use elusiv::types::{
-    compute_fee_rec, compute_fee_rec_lamports, generate_hashed_inputs, InputCommitment,
-    JoinSplitPublicInputs, OrdU256, Proof, PublicInputs, RawProof, RawU256, SendPublicInputs,
-    JOIN_SPLIT_MAX_N_ARITY, U256,
+    compute_fee_rec, compute_fee_rec_lamports, generate_hashed_inputs, HashedInputs,
+    InputCommitment, JoinSplitPublicInputs, OrdU256, Proof, PublicInputs, RawProof, RawU256,
+    SendPublicInputs, JOIN_SPLIT_MAX_N_ARITY, U256,
 };
 use elusiv_computation::PartialComputation;
 use elusiv_types::tokens::Price;
@@ -62,11 +64,11 @@ struct FullSendRequest {
 
 impl FullSendRequest {
     fn update_fee_lamports(&mut self, fee: &ProgramFee) {
-        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut self.public_inputs, fee);
+        compute_fee_rec_lamports::<SendQuadraVKey, _>(&mut self.public_inputs, fee, 0);
     }
 
     fn update_fee_token(&mut self, fee: &ProgramFee, price: &TokenPrice) {
-        compute_fee_rec::<SendQuadraVKey, _>(&mut self.public_inputs, fee, price)
+        compute_fee_rec::<SendQuadraVKey, _>(&mut self.public_inputs, fee, 0, price)
     }
 }
 
@@ -118,6 +120,7 @@ fn send_request(index: usize) -> FullSendRequest {
                 recipient_is_associated_token_account: false,
                 hashed_inputs: default_hashed_inputs,
                 solana_pay_transfer: false,
+                encrypted_memo: None,
             }
         },
         FullSendRequest {
@@ -144,6 +147,7 @@ fn send_request(index: usize) -> FullSendRequest {
                 recipient_is_associated_token_account: false,
                 hashed_inputs: default_hashed_inputs,
                 solana_pay_transfer: false,
+                encrypted_memo: None,
             }
         },
         FullSendRequest {
@@ -170,6 +174,7 @@ fn send_request(index: usize) -> FullSendRequest {
                 recipient_is_associated_token_account: false,
                 hashed_inputs: default_hashed_inputs,
                 solana_pay_transfer: false,
+                encrypted_memo: None,
             }
         },
         FullSendRequest {
@@ -200,6 +205,7 @@ fn send_request(index: usize) -> FullSendRequest {
                 recipient_is_associated_token_account: false,
                 hashed_inputs: default_hashed_inputs,
                 solana_pay_transfer: false,
+                encrypted_memo: None,
             }
         },
     ];
@@ -234,15 +240,18 @@ impl Default for ExtraData {
 
 impl ExtraData {
     fn hash(&self) -> U256 {
-        generate_hashed_inputs(
-            self.recipient,
-            self.identifier,
-            self.iv,
-            self.encrypted_owner,
-            self.reference,
-            self.is_associated_token_account,
-            &self.memo,
-        )
+        generate_hashed_inputs(&HashedInputs {
+            recipient: self.recipient,
+            identifier: self.identifier,
+            iv: self.iv,
+            encrypted_owner: self.encrypted_owner,
+            transaction_reference: self.reference,
+            is_associated_token_account: self.is_associated_token_account,
+            memo: self.memo.as_deref(),
+            encrypted_memo: None,
+            recurring_payment: None,
+            batch_payouts: None,
+        })
     }
 
     fn recipient(&self) -> Pubkey {
@@ -278,6 +287,7 @@ async fn init_verification_simple(
             UserAccount(Pubkey::new_from_array(identifier)),
             &user_accounts(&[nullifier_accounts[0]]),
             &[],
+            &[],
         ),
         ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, test.payer()),
         ElusivInstruction::init_verification_proof_instruction(
@@ -345,6 +355,9 @@ async fn setup_vkey_account<VKey: VerifyingKeyInfo>(
         is_frozen: true,
         authority: ElusivOption::None,
         version: 1,
+        checksum: VKey::CHECKSUM,
+        checksum_position: VerifyingKey::source_size(VKey::public_inputs_count()) as u32,
+        data_packet_count: 0,
     }
     .try_to_vec()
     .unwrap();
@@ -463,6 +476,7 @@ async fn test_init_proof_signers() {
             UserAccount(Pubkey::new_unique()),
             &user_accounts(&[nullifier_accounts[0]]),
             &[],
+            &[],
         ),
         &[&warden.keypair],
     )
@@ -564,6 +578,7 @@ async fn test_init_proof_lamports() {
             UserAccount(Pubkey::new_unique()),
             &user_accounts(&[nullifier_accounts[0]]),
             &[],
+            &[],
         )
     };
 
@@ -727,6 +742,7 @@ async fn test_init_proof_token() {
             UserAccount(Pubkey::new_unique()),
             &user_accounts(&[nullifier_accounts[0]]),
             &[],
+            &[],
         ),
         &[&warden.keypair],
     )
@@ -802,9 +818,12 @@ async fn test_finalize_proof_lamports() {
     let nullifier_duplicate_account = request.public_inputs.join_split.nullifier_duplicate_pda().0;
 
     let public_inputs = request.public_inputs.public_signals_skip_mr();
-    let input_preparation_tx_count =
-        prepare_public_inputs_instructions(&public_inputs, SendQuadraVKey::public_inputs_count())
-            .len();
+    let input_preparation_tx_count = prepare_public_inputs_instructions(
+        &public_inputs,
+        SendQuadraVKey::public_inputs_count(),
+        0,
+    )
+    .len();
     let subvention = fee.proof_subvention;
     let proof_verification_fee = fee.proof_verification_computation_fee(input_preparation_tx_count);
     let commitment_hash_fee = fee.commitment_hash_computation_fee(0);
@@ -840,6 +859,7 @@ async fn test_finalize_proof_lamports() {
                 UserAccount(Pubkey::new_from_array(extra_data.identifier)),
                 &user_accounts(&[nullifier_accounts[0]]),
                 &[],
+                &[],
             ),
             ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, warden.pubkey),
             ElusivInstruction::init_verification_proof_instruction(
@@ -892,20 +912,23 @@ async fn test_finalize_proof_lamports() {
             UserAccount(identifier),
             UserAccount(reference),
             UserAccount(warden.pubkey),
+            &[],
         );
     let finalize_verification_send_nullifier_instruction =
         ElusivInstruction::finalize_verification_insert_nullifier_instruction(
             0,
             UserAccount(warden.pubkey),
-            Some(0),
+            &nullifier_account_offsets(LAMPORTS_TOKEN_ID, 0),
             &writable_user_accounts(&[nullifier_accounts[0]]),
+            token_pool_offset(LAMPORTS_TOKEN_ID),
         );
     let finalize_verification_transfer_lamports_instruction =
-        ElusivInstruction::finalize_verification_transfer_lamports_instruction(
+        ElusivInstruction::finalize_verification_transfer_lamports_sol_instruction(
             0,
-            WritableSignerAccount(warden.pubkey),
-            WritableUserAccount(recipient),
-            WritableUserAccount(nullifier_duplicate_account),
+            &request.public_inputs.join_split,
+            warden.pubkey,
+            recipient,
+            &[],
         );
 
     // IMPORTANT: Pool already contains subvention (so we airdrop commitment_hash_fee - subvention)
@@ -1027,9 +1050,12 @@ async fn test_finalize_proof_token() {
     let nullifier_duplicate_account = request.public_inputs.join_split.nullifier_duplicate_pda().0;
 
     let public_inputs = request.public_inputs.public_signals_skip_mr();
-    let input_preparation_tx_count =
-        prepare_public_inputs_instructions(&public_inputs, SendQuadraVKey::public_inputs_count())
-            .len();
+    let input_preparation_tx_count = prepare_public_inputs_instructions(
+        &public_inputs,
+        SendQuadraVKey::public_inputs_count(),
+        0,
+    )
+    .len();
     let subvention = fee
         .proof_subvention
         .into_token(&price, USDC_TOKEN_ID)
@@ -1079,6 +1105,7 @@ async fn test_finalize_proof_token() {
                 UserAccount(Pubkey::new_from_array(extra_data.identifier)),
                 &user_accounts(&[nullifier_accounts[0]]),
                 &[],
+                &[],
             ),
             ElusivInstruction::init_verification_transfer_fee_instruction(
                 0,
@@ -1131,17 +1158,20 @@ async fn test_finalize_proof_token() {
             UserAccount(identifier),
             UserAccount(reference),
             UserAccount(warden.pubkey),
+            &[],
         );
     let finalize_verification_send_nullifier_instruction =
         ElusivInstruction::finalize_verification_insert_nullifier_instruction(
             0,
             UserAccount(warden.pubkey),
-            Some(0),
+            &nullifier_account_offsets(USDC_TOKEN_ID, 0),
             &writable_user_accounts(&[nullifier_accounts[0]]),
+            token_pool_offset(USDC_TOKEN_ID),
         );
     let finalize_verification_transfer_token_instruction =
         ElusivInstruction::finalize_verification_transfer_token_instruction(
             0,
+            USDC_TOKEN_ID,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
             WritableUserAccount(recipient_token_account),
@@ -1274,6 +1304,7 @@ async fn test_finalize_proof_skip_nullifier_pda() {
                 UserAccount(Pubkey::new_from_array(extra_data.identifier)),
                 &user_accounts(&[nullifier_accounts[0]]),
                 &[],
+                &[],
             ),
             ElusivInstruction::init_verification_transfer_fee_sol_instruction(
                 v_index,
@@ -1315,18 +1346,21 @@ async fn test_finalize_proof_skip_nullifier_pda() {
                 UserAccount(identifier),
                 UserAccount(reference),
                 UserAccount(warden.pubkey),
+                &[],
             ),
             ElusivInstruction::finalize_verification_insert_nullifier_instruction(
                 v_index,
                 UserAccount(warden.pubkey),
-                Some(0),
+                &nullifier_account_offsets(LAMPORTS_TOKEN_ID, 0),
                 &writable_user_accounts(&[nullifier_accounts[0]]),
+                token_pool_offset(LAMPORTS_TOKEN_ID),
             ),
             ElusivInstruction::finalize_verification_transfer_lamports_instruction(
                 v_index,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(recipient.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
+                &[],
             ),
         ];
 
@@ -1409,6 +1443,7 @@ async fn test_finalize_proof_commitment_index() {
             UserAccount(Pubkey::new_from_array(extra_data.identifier)),
             &user_accounts(&[nullifier_accounts[0]]),
             &[],
+            &[],
         ),
         ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, warden.pubkey),
         ElusivInstruction::init_verification_proof_instruction(
@@ -1433,24 +1468,29 @@ async fn test_finalize_proof_commitment_index() {
                     commitment_index,
                     encrypted_owner: extra_data.encrypted_owner,
                     iv: extra_data.iv,
+                    recurring_payment: None,
+                    batch_payouts: None,
                 },
                 false,
                 UserAccount(recipient.pubkey),
                 UserAccount(identifier),
                 UserAccount(reference),
                 UserAccount(warden.pubkey),
+                &[],
             ),
             ElusivInstruction::finalize_verification_insert_nullifier_instruction(
                 0,
                 UserAccount(warden.pubkey),
-                Some(0),
+                &nullifier_account_offsets(LAMPORTS_TOKEN_ID, 0),
                 &writable_user_accounts(&[nullifier_accounts[0]]),
+                token_pool_offset(LAMPORTS_TOKEN_ID),
             ),
             ElusivInstruction::finalize_verification_transfer_lamports_instruction(
                 0,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(recipient.pubkey),
                 WritableUserAccount(nullifier_duplicate_account),
+                &[],
             ),
         ]
     };
@@ -1563,6 +1603,7 @@ async fn test_associated_token_account() {
             UserAccount(Pubkey::new_from_array(extra_data.identifier)),
             &user_accounts(&[nullifier_accounts[0]]),
             &[],
+            &[],
         ),
         &[&warden.keypair],
     )
@@ -1616,15 +1657,18 @@ async fn test_associated_token_account() {
                 UserAccount(Pubkey::new_from_array(extra_data.identifier)),
                 UserAccount(Pubkey::new_from_array(extra_data.reference)),
                 UserAccount(warden.pubkey),
+                &[],
             ),
             ElusivInstruction::finalize_verification_insert_nullifier_instruction(
                 0,
                 UserAccount(warden.pubkey),
-                Some(0),
+                &nullifier_account_offsets(USDC_TOKEN_ID, 0),
                 &writable_user_accounts(&[nullifier_accounts[0]]),
+                token_pool_offset(USDC_TOKEN_ID),
             ),
             ElusivInstruction::finalize_verification_transfer_token_instruction(
                 0,
+                USDC_TOKEN_ID,
                 WritableSignerAccount(warden.pubkey),
                 WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
                 WritableUserAccount(recipient),
@@ -1757,9 +1801,12 @@ async fn test_compute_proof_verifcation_invalid_proof() {
     let nullifier_duplicate_account = request.public_inputs.join_split.nullifier_duplicate_pda().0;
 
     let public_inputs = request.public_inputs.public_signals_skip_mr();
-    let input_preparation_tx_count =
-        prepare_public_inputs_instructions(&public_inputs, SendQuadraVKey::public_inputs_count())
-            .len();
+    let input_preparation_tx_count = prepare_public_inputs_instructions(
+        &public_inputs,
+        SendQuadraVKey::public_inputs_count(),
+        0,
+    )
+    .len();
     let subvention = fee.proof_subvention;
     let commitment_hash_fee = fee.commitment_hash_computation_fee(0);
     let verification_account_rent = test.rent(VerificationAccount::SIZE).await;
@@ -1789,6 +1836,7 @@ async fn test_compute_proof_verifcation_invalid_proof() {
                 UserAccount(Pubkey::new_unique()),
                 &user_accounts(&[nullifier_accounts[0]]),
                 &[],
+                &[],
             ),
             ElusivInstruction::init_verification_transfer_fee_sol_instruction(0, warden.pubkey),
             ElusivInstruction::init_verification_proof_instruction(
@@ -1923,13 +1971,15 @@ async fn test_enforced_finalization_order() {
             UserAccount(extra_data.identifier()),
             UserAccount(extra_data.reference()),
             UserAccount(test.payer()),
+            &[],
         );
     let finalize_verification_send_nullifier_instruction =
         ElusivInstruction::finalize_verification_insert_nullifier_instruction(
             0,
             UserAccount(test.payer()),
-            Some(0),
+            &nullifier_account_offsets(LAMPORTS_TOKEN_ID, 0),
             &writable_user_accounts(&[nullifier_accounts[0]]),
+            token_pool_offset(LAMPORTS_TOKEN_ID),
         );
     let finalize_verification_transfer_lamports_instruction =
         ElusivInstruction::finalize_verification_transfer_lamports_instruction(
@@ -1937,6 +1987,7 @@ async fn test_enforced_finalization_order() {
             WritableSignerAccount(test.payer()),
             WritableUserAccount(extra_data.recipient()),
             WritableUserAccount(nullifier_duplicate_account),
+            &[],
         );
 
     set_verification_state(test.payer(), 0, VerificationState::ProofSetup, &mut test).await;
@@ -1999,10 +2050,12 @@ async fn nullifier_finalization_test(number_of_start_nullifiers: u64, input_comm
         recipient_is_associated_token_account: false,
         hashed_inputs: extra_data.hash(),
         solana_pay_transfer: false,
+        encrypted_memo: None,
     };
     compute_fee_rec_lamports::<SendQuadraVKey, _>(
         &mut public_inputs,
         &genesis_fee(&mut test).await,
+        0,
     );
     let nullifier_duplicate_account = public_inputs.join_split.nullifier_duplicate_pda().0;
     let identifier = Pubkey::new_from_array(extra_data.identifier);
@@ -2032,6 +2085,7 @@ async fn nullifier_finalization_test(number_of_start_nullifiers: u64, input_comm
             UserAccount(identifier),
             UserAccount(reference),
             UserAccount(test.payer()),
+            &[],
         ),
     ];
 
@@ -2052,10 +2106,11 @@ async fn nullifier_finalization_test(number_of_start_nullifiers: u64, input_comm
             ElusivInstruction::finalize_verification_insert_nullifier_instruction(
                 0,
                 UserAccount(test.payer()),
-                Some(0),
+                &nullifier_account_offsets(LAMPORTS_TOKEN_ID, 0),
                 &writable_user_accounts(
                     &nullifier_accounts[child_account_index..child_account_index + 1],
                 ),
+                token_pool_offset(LAMPORTS_TOKEN_ID),
             ),
         );
     }
@@ -2068,8 +2123,9 @@ async fn nullifier_finalization_test(number_of_start_nullifiers: u64, input_comm
             ElusivInstruction::finalize_verification_insert_nullifier_instruction(
                 0,
                 UserAccount(test.payer()),
-                Some(0),
+                &nullifier_account_offsets(LAMPORTS_TOKEN_ID, 0),
                 &writable_user_accounts(&[nullifier_accounts[i + 1]]),
+                token_pool_offset(LAMPORTS_TOKEN_ID),
             ),
         );
     }
@@ -2080,6 +2136,7 @@ async fn nullifier_finalization_test(number_of_start_nullifiers: u64, input_comm
             WritableSignerAccount(test.payer()),
             WritableUserAccount(recipient),
             WritableUserAccount(nullifier_duplicate_account),
+            &[],
         ),
     );
 
@@ -2121,18 +2178,21 @@ async fn finalize_instructions(
             UserAccount(extra_data.identifier()),
             UserAccount(*reference),
             UserAccount(*signer),
+            &[],
         ),
         ElusivInstruction::finalize_verification_insert_nullifier_instruction(
             0,
             UserAccount(*signer),
-            Some(0),
+            &nullifier_account_offsets(LAMPORTS_TOKEN_ID, 0),
             &writable_user_accounts(&[nullifier_accounts[0]]),
+            token_pool_offset(LAMPORTS_TOKEN_ID),
         ),
         ElusivInstruction::finalize_verification_transfer_lamports_instruction(
             0,
             WritableSignerAccount(*signer),
             WritableUserAccount(extra_data.recipient()),
             WritableUserAccount(request.public_inputs.join_split.nullifier_duplicate_pda().0),
+            &[],
         ),
     ]
 }
@@ -2539,6 +2599,7 @@ async fn test_solana_pay_tokens() {
                 UserAccount(Pubkey::new_from_array(extra_data.identifier)),
                 &user_accounts(&[nullifier_accounts[0]]),
                 &[],
+                &[],
             ),
             ElusivInstruction::init_verification_transfer_fee_instruction(
                 0,
@@ -2577,15 +2638,18 @@ async fn test_solana_pay_tokens() {
             UserAccount(extra_data.identifier()),
             UserAccount(extra_data.reference()),
             UserAccount(warden.pubkey),
+            &[],
         ),
         ElusivInstruction::finalize_verification_insert_nullifier_instruction(
             0,
             UserAccount(warden.pubkey),
-            Some(0),
+            &nullifier_account_offsets(USDC_TOKEN_ID, 0),
             &writable_user_accounts(&[nullifier_accounts[0]]),
+            token_pool_offset(USDC_TOKEN_ID),
         ),
         ElusivInstruction::finalize_verification_transfer_token_instruction(
             0,
+            USDC_TOKEN_ID,
             WritableSignerAccount(warden.pubkey),
             WritableUserAccount(warden.get_token_account(USDC_TOKEN_ID)),
             WritableUserAccount(recipient_token_account),
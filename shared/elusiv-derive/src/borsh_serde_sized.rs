@@ -1,10 +1,43 @@
-use proc_macro2::TokenStream;
+use proc_macro2::{Ident, TokenStream};
 use quote::quote;
-use syn::Fields;
+use syn::{Fields, Generics, Type};
+
+/// Adds a `BorshSerDeSized` bound to all type parameters that are used as field types
+/// - other bounds (e.g. for `[T; N]`) need to be specified in the where-clause of the type
+fn add_trait_bounds(ast: &syn::DeriveInput) -> Generics {
+    let fields: Vec<&syn::Field> = match &ast.data {
+        syn::Data::Struct(s) => s.fields.iter().collect(),
+        syn::Data::Enum(e) => e.variants.iter().flat_map(|v| v.fields.iter()).collect(),
+        syn::Data::Union(_) => Vec::new(),
+    };
+
+    let mut generics = ast.generics.clone();
+    let bounded_params: Vec<Ident> = ast
+        .generics
+        .type_params()
+        .map(|t| t.ident.clone())
+        .filter(|ident| {
+            fields.iter().any(|field| match &field.ty {
+                Type::Path(p) => p.qself.is_none() && p.path.is_ident(ident),
+                _ => false,
+            })
+        })
+        .collect();
+
+    let where_clause = generics.make_where_clause();
+    for ident in bounded_params {
+        where_clause
+            .predicates
+            .push(syn::parse_quote! { #ident: elusiv_types::bytes::BorshSerDeSized });
+    }
+
+    generics
+}
 
 pub fn impl_borsh_serde_sized(ast: &syn::DeriveInput) -> TokenStream {
     let ident = &ast.ident.clone();
-    let (impl_generics, ty_generics, where_clause) = &ast.generics.split_for_impl();
+    let generics = add_trait_bounds(ast);
+    let (impl_generics, ty_generics, where_clause) = &generics.split_for_impl();
     let mut sizes = Vec::new();
 
     fn size_of_fields(fields: &Fields) -> TokenStream {
@@ -70,8 +103,10 @@ pub fn impl_borsh_serde_sized(ast: &syn::DeriveInput) -> TokenStream {
             }
         }
         syn::Data::Struct(s) => {
-            sizes.push(size_of_fields(&s.fields));
-            let size: TokenStream = sizes.iter().fold(quote! {}, |acc, x| quote! { #acc #x });
+            let mut size = size_of_fields(&s.fields);
+            if size.is_empty() {
+                size = quote! { 0 };
+            }
 
             quote! {
                 impl #impl_generics elusiv_types::bytes::BorshSerDeSized for #ident #ty_generics #where_clause {
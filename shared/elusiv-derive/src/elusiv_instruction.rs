@@ -8,8 +8,10 @@ const ACC_ATTR: &str = "acc";
 const SYS_ATTR: &str = "sys";
 const PDA_ATTR: &str = "pda";
 const MAP_ATTR: &str = "map";
+const REMAINING_ATTR: &str = "remaining";
+const PDA_OFFSET_ONLY_ATTR: &str = "pda_offset_only";
 
-const RESERVED_ATTR_IDENTS: [&str; 4] = [ACC_ATTR, SYS_ATTR, PDA_ATTR, MAP_ATTR];
+const RESERVED_ATTR_IDENTS: [&str; 5] = [ACC_ATTR, SYS_ATTR, PDA_ATTR, MAP_ATTR, REMAINING_ATTR];
 
 enum AttrType {
     Docs,
@@ -23,6 +25,7 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
     let mut matches = quote!();
     let mut functions = quote!();
     let mut abi_functions = quote!();
+    let mut idl_instructions = quote!();
     let mut variant_indices = quote!();
 
     if let syn::Data::Enum(e) = &ast.data {
@@ -42,6 +45,7 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
             // Processor calls
             let mut accounts = quote!();
             let mut fields = quote!();
+            let mut processor_fields = quote!();
             let mut signature = quote!();
 
             // Instruction creation
@@ -52,6 +56,11 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
             let mut docs = quote!();
             let mut other_attrs = quote!();
             let mut current_attr_type = AttrType::Docs;
+            let mut has_remaining_accounts = false;
+
+            // IDL entries (JSON objects)
+            let mut idl_args = Vec::new();
+            let mut idl_accounts = Vec::new();
 
             for field in &var.fields {
                 let field_name = field.ident.clone().unwrap();
@@ -59,6 +68,20 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
 
                 fields.extend(quote! { #field_name, });
                 fields_with_type.extend(quote! { #field_name: #ty, });
+
+                let is_pda_offset_only = field
+                    .attrs
+                    .iter()
+                    .any(|attr| attr.path.is_ident(PDA_OFFSET_ONLY_ATTR));
+                if !is_pda_offset_only {
+                    processor_fields.extend(quote! { #field_name, });
+                }
+
+                idl_args.push(format!(
+                    "{{\"name\":{},\"type\":{}}}",
+                    json_string(&field_name.to_string()),
+                    json_string(&ty.to_token_stream().to_string()),
+                ));
             }
 
             // Account attributes
@@ -89,6 +112,14 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
 
                 current_attr_type = AttrType::Account;
 
+                assert!(
+                    !has_remaining_accounts,
+                    "'{}' accounts need to be the last account attribute",
+                    REMAINING_ATTR
+                );
+                let is_remaining = attr_name == REMAINING_ATTR;
+                has_remaining_accounts = is_remaining;
+
                 // Sub-attrs are the fields as in #[usr(sub_attr_0 = .., sub_attr_1, .., { sub_attr_n, .. })] (braces are ignored)
                 let mut fields = attr.tokens.to_string();
                 fields.retain(|x| x != '{' && x != '}' && !x.is_whitespace());
@@ -125,14 +156,34 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                 let mut account: TokenStream = sub_attrs[0].0.parse().unwrap();
                 let mut account_init = Vec::new(); // used for creating the instruction objects with the abi-feature
 
-                accounts.extend(quote! {
-                    let #account = &solana_program::account_info::next_account_info(account_info_iter)?;
-                });
+                // Optional accounts are passed as `Option<&AccountInfo>` to the processor
+                // - a missing account is represented by the program-id, so the indices of all following accounts are unchanged
+                let is_optional = contains_key(&sub_attrs, "optional");
+                assert!(
+                    !is_optional || attr_name == ACC_ATTR,
+                    "'optional' can only be used with '{}' accounts",
+                    ACC_ATTR
+                );
+
+                if is_remaining {
+                    // All remaining accounts (at least `min`)
+                    let min = value::<usize>(&sub_attrs, "min").unwrap_or(0);
+                    accounts.extend(quote! {
+                        let #account: Vec<&solana_program::account_info::AccountInfo> = account_info_iter.by_ref().collect();
+                        if #account.len() < #min { return Err(solana_program::program_error::ProgramError::NotEnoughAccountKeys) }
+                    });
+                } else {
+                    accounts.extend(quote! {
+                        let #account = &solana_program::account_info::next_account_info(account_info_iter)?;
+                    });
+                }
+
+                let mut checks = quote!();
 
                 // Signer check
                 let is_signer = contains_key(&sub_attrs, "signer");
                 if is_signer {
-                    accounts.extend(quote!{
+                    checks.extend(quote!{
                         if !#account.is_signer { return Err(solana_program::program_error::ProgramError::MissingRequiredSignature) }
                     });
                 }
@@ -148,11 +199,24 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                 // Ownership check
                 let is_owned = contains_key(&sub_attrs, "owned");
                 if is_owned {
-                    accounts.extend(quote!{
+                    checks.extend(quote!{
                         if *#account.owner != crate::ID { return Err(solana_program::program_error::ProgramError::IllegalOwner) }
                     });
                 }
 
+                if is_optional {
+                    accounts.extend(quote!{
+                        let #account = if *#account.key == crate::ID { None } else { Some(*#account) };
+                        if let Some(#account) = #account { #checks }
+                    });
+                } else if is_remaining {
+                    accounts.extend(quote! {
+                        for #account in &#account { #checks }
+                    });
+                } else {
+                    accounts.extend(checks);
+                }
+
                 // Ignore means not passing the account to the processor function
                 let ignore = contains_key(&sub_attrs, "ignore");
 
@@ -183,12 +247,22 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                 };
 
                 match attr_name.as_str() {
-                    // `AccountInfo` (usage: <name>)
+                    // `AccountInfo` (usage: <name> <optional>?)
                     ACC_ATTR => {
-                        user_accounts.extend(quote! { #account: #user_account_type, });
-                        account_init.push(quote!{
-                            accounts.push(solana_program::instruction::AccountMeta::#account_init_fn(#account.0, #is_signer));
-                        });
+                        if is_optional {
+                            user_accounts.extend(quote! { #account: Option<#user_account_type>, });
+                            account_init.push(quote!{
+                                match #account {
+                                    Some(account) => accounts.push(solana_program::instruction::AccountMeta::#account_init_fn(account.0, #is_signer)),
+                                    None => accounts.push(solana_program::instruction::AccountMeta::new_readonly(crate::id(), false)),
+                                }
+                            });
+                        } else {
+                            user_accounts.extend(quote! { #account: #user_account_type, });
+                            account_init.push(quote!{
+                                accounts.push(solana_program::instruction::AccountMeta::#account_init_fn(#account.0, #is_signer));
+                            });
+                        }
                     }
 
                     // System program `AccountInfo` (usage: <name> <key = ..>)
@@ -206,7 +280,7 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                         });
                     }
 
-                    // PDA accounts (usage: <name> <AccountType> <pda_offset: u32 = ..>? <account_info>? <include_child_accounts>? <ownership>)
+                    // PDA accounts (usage: <name> <AccountType> <pda_offset: u32 = ..>? <pda_offsets: [u32] = [..]>? <account_info>? <include_child_accounts>? <ownership>)
                     PDA_ATTR => {
                         // Every PDA account needs to implement the trait `elusiv::state::program_account::PDAAccount`
                         // - this trait allows us to verify PDAs
@@ -223,12 +297,50 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                         // The PDA pubkey is an optional field, used to add an associated pubkey to the seed
                         let pda_pubkey: Option<TokenStream> = value(&sub_attrs, "pda_pubkey");
 
+                        // Composite PDA offsets (usage: `pda_offsets = [a, b, ..]`), used instead of `pda_offset` for multi-dimensional keys
+                        let pda_offsets: Option<TokenStream> = value(&sub_attrs, "pda_offsets");
+                        let pda_pubkey_option = match &pda_pubkey {
+                            Some(pda_pubkey) => quote! { Some(#pda_pubkey) },
+                            None => quote! { None },
+                        };
+                        if pda_offsets.is_some() {
+                            assert!(
+                                !contains_key(&sub_attrs, "pda_offset"),
+                                "'pda_offsets' cannot be combined with 'pda_offset'"
+                            );
+                        }
+
                         // ParentAccount?
                         let include_child_accounts =
                             contains_key(&sub_attrs, "include_child_accounts");
 
                         let skip_abi = contains_key(&sub_attrs, "skip_abi");
-                        if skip_abi {
+                        if skip_abi && pda_offsets.is_some() {
+                            let offsets_ident: TokenStream =
+                                format!("{}_pda_offsets", sub_attrs[0].0).parse().unwrap();
+
+                            let pubkey_option = if pda_pubkey.is_some() {
+                                let pubkey_ident: TokenStream =
+                                    format!("{}_pda_pubkey", sub_attrs[0].0).parse().unwrap();
+
+                                user_accounts.extend(
+                                    quote! { #pubkey_ident: solana_program::pubkey::Pubkey, },
+                                );
+                                quote! { Some(#pubkey_ident) }
+                            } else {
+                                quote! { None }
+                            };
+
+                            user_accounts.extend(quote! { #offsets_ident: &[u32], });
+                            account_init.push(quote!{
+                                accounts.push(
+                                    solana_program::instruction::AccountMeta::#account_init_fn(
+                                        <#ty as elusiv_types::accounts::PDAAccount>::find_with_offsets(#pubkey_option, #offsets_ident).0,
+                                        #is_signer
+                                    )
+                                );
+                            });
+                        } else if skip_abi {
                             let offset_ident: TokenStream =
                                 format!("{}_pda_offset", sub_attrs[0].0).parse().unwrap();
 
@@ -256,6 +368,15 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                                     );
                                 });
                             }
+                        } else if let Some(pda_offsets) = pda_offsets.clone() {
+                            account_init.push(quote!{
+                                accounts.push(
+                                    solana_program::instruction::AccountMeta::#account_init_fn(
+                                        <#ty as elusiv_types::accounts::PDAAccount>::find_with_offsets(#pda_pubkey_option, &#pda_offsets).0,
+                                        #is_signer
+                                    )
+                                );
+                            });
                         } else if let Some(pda_pubkey) = pda_pubkey.clone() {
                             account_init.push(quote!{
                                 accounts.push(
@@ -285,7 +406,19 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
 
                         #[allow(clippy::collapsible_else_if)]
                         if !skip_pda_verification {
-                            let check_pda = if find_pda {
+                            let check_pda = if let Some(pda_offsets) = pda_offsets {
+                                if find_pda {
+                                    quote! {
+                                        if <#ty as elusiv_types::accounts::PDAAccount>::find_with_offsets(#pda_pubkey_option, &#pda_offsets).0 != *#account.key {
+                                            return Err(solana_program::program_error::ProgramError::InvalidArgument)
+                                        }
+                                    }
+                                } else {
+                                    quote! {
+                                        <#ty as elusiv_types::accounts::PDAAccount>::verify_account_with_offsets(&#account, #pda_pubkey_option, &#pda_offsets)?;
+                                    }
+                                }
+                            } else if find_pda {
                                 if let Some(pda_pubkey) = pda_pubkey {
                                     quote! {
                                         if <#ty as elusiv_types::accounts::PDAAccount>::find_with_pubkey(#pda_pubkey, #pda_offset).0 != *#account.key {
@@ -380,9 +513,39 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                         todo!()
                     }
 
+                    // Variable amount of trailing `AccountInfo`s (usage: <name> <min = ..>?)
+                    REMAINING_ATTR => {
+                        user_accounts.extend(quote! { #account: &[#user_account_type], });
+                        account_init.push(quote!{
+                            for account in #account {
+                                accounts.push(solana_program::instruction::AccountMeta::#account_init_fn(account.0, #is_signer));
+                            }
+                        });
+                        account = quote! { &#account };
+                    }
+
                     v => panic!("Invalid attribute name {}", v),
                 }
 
+                // Account metas are described in the same order as they are added by the instruction builder
+                let pda_type = if attr_name == PDA_ATTR {
+                    json_string(sub_attrs[1].0)
+                } else {
+                    String::from("null")
+                };
+                let is_multiple =
+                    is_remaining || contains_key(&sub_attrs, "include_child_accounts");
+                idl_accounts.push(format!(
+                    "{{\"name\":{},\"kind\":{},\"writable\":{},\"signer\":{},\"optional\":{},\"multiple\":{},\"pda\":{}}}",
+                    json_string(sub_attrs[0].0),
+                    json_string(&attr_name),
+                    is_writable,
+                    is_signer,
+                    is_optional,
+                    is_multiple,
+                    pda_type,
+                ));
+
                 // Add account to processor call signature
                 if !ignore {
                     signature.extend(quote! { #account, });
@@ -409,10 +572,22 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                 fn #fn_name(accounts: &[solana_program::account_info::AccountInfo], #fields_with_type) -> solana_program::entrypoint::ProgramResult {
                     let mut account_info_iter = &mut accounts.iter();
                     #accounts
-                    processor::#fn_name(#signature #fields)
+                    processor::#fn_name(#signature #processor_fields)
                 }
             });
 
+            let idl_instruction = format!(
+                "{{\"name\":{},\"index\":{},\"args\":[{}],\"accounts\":[{}]}}",
+                json_string(&ident.to_string()),
+                var_index,
+                idl_args.join(","),
+                idl_accounts.join(","),
+            );
+            idl_instructions.extend(quote! {
+                #other_attrs
+                instructions.push(#idl_instruction);
+            });
+
             abi_functions.extend(quote!{
                 #docs
                 #other_attrs
@@ -451,12 +626,36 @@ pub fn impl_elusiv_instruction(ast: &syn::DeriveInput) -> proc_macro2::TokenStre
                 #abi_functions
             }
 
+            #[cfg(feature = "idl")]
+            impl #ast_ident {
+                /// Returns the IDL (JSON) of all instructions with their args and account metas (in the order expected by the processor)
+                pub fn idl() -> String {
+                    let mut instructions: Vec<&str> = Vec::new();
+                    #idl_instructions
+
+                    format!("{{\"instructions\":[{}]}}", instructions.join(","))
+                }
+            }
+
         }
     } else {
         panic!("Only enums can be instructions")
     }
 }
 
+/// Encodes `value` as a JSON string (whitespace is removed, since it only stems from the token stream)
+fn json_string(value: &str) -> String {
+    let mut json = String::from("\"");
+    for char in value.chars().filter(|c| !c.is_whitespace()) {
+        if char == '"' || char == '\\' {
+            json.push('\\');
+        }
+        json.push(char);
+    }
+    json.push('"');
+    json
+}
+
 fn contains_key(attrs: &[(&str, Option<&str>)], key: &str) -> bool {
     attrs.iter().any(|(k, _)| *k == key)
 }
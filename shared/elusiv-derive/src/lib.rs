@@ -27,11 +27,14 @@ use pda_account::*;
 ///         - `prg`
 ///         - `sys`: a different program (most likely system program) (requires the key = .. field)
 ///         - `pda`
+///         - `remaining`: all remaining accounts as a slice of `AccountInfo`s (has to be the last account attribute)
 ///     - name: name of the variable
 ///     - Type: if the account isa `PDAAccount`, specify it's type (even when using the account as an `AccountInfo`, since we need to check the PDA seed)
 ///     - fields:
 ///         - `pda_offset`: you can specify fields contained in the data of previous account or the instruction itself
+///         - `pda_offsets`: composite offset (`[a, b, ..]`) for PDAs with multi-dimensional keys (cannot be combined with `pda_offset` or `skip_abi`)
 ///         - `key`: address of the program (`sys`)
+///         - `min`: minimum number of accounts (`remaining`)
 ///     - extra_attributes:
 ///         - `signer`
 ///         - `writable`
@@ -40,6 +43,20 @@ use pda_account::*;
 ///         - `account_info`: returns an `AccountInfo` object (only relevant for PDAs)
 ///         - `include_child_accounts`: the `Type` has to implement the `crate::state::program_account::ParentAccount` trait and up to `Type::COUNT + 1` accounts can be matched (but at least 1)
 ///         - `skip_abi`: can be used to add manual pda_offsets in the abi
+///         - `optional`: passes an `Option<AccountInfo>` (only `acc`), a missing account is represented by the program-id
+///
+/// # Field attributes
+/// - `pda_offset_only`: the field is only used in the `pda_offset` of accounts and not passed to the processor
+///
+/// # Instruction builders
+/// - With the `elusiv-client` feature each variant also gets a `<variant_name>_instruction` function, constructing the [`solana_program::instruction::Instruction`]
+/// - the builder takes the variant fields followed by all non-PDA accounts (and child-accounts) in attribute order
+/// - PDA accounts are derived by the builder itself (except for `skip_abi`, which requires the `pda_offset`), so the account order always matches the processor
+///
+/// # IDL
+/// - With the `idl` feature the enum gets an `idl` function, returning a JSON description of all instructions
+/// - each instruction contains its `name`, variant `index`, `args` (name and type) and `accounts` (name, kind, `writable`, `signer`, `optional`, `multiple` and the `pda` type)
+/// - `multiple` accounts (`remaining` and `include_child_accounts`) match a variable number of account metas
 ///
 /// # Other attributes
 /// - Each variant can also be equipped with any other kind of attributes (cfg or do documentation).
@@ -56,12 +73,21 @@ use pda_account::*;
 ///     }
 /// }
 /// ```
-#[proc_macro_derive(ElusivInstruction, attributes(acc, sys, pda, map))]
+#[proc_macro_derive(
+    ElusivInstruction,
+    attributes(acc, sys, pda, map, remaining, pda_offset_only)
+)]
 pub fn elusiv_instruction(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
     impl_elusiv_instruction(&ast).into()
 }
 
+/// Implements `elusiv_types::bytes::BorshSerDeSized` (and `BorshSerDeSizedEnum` for enums)
+///
+/// # Notes
+///
+/// - the size of an enum is the size of its largest variant plus one byte for the variant index
+/// - type parameters used as field types are bound by `BorshSerDeSized`, all other bounds (e.g. for `[T; N]`) need to be added to the where-clause
 #[proc_macro_derive(BorshSerDeSized)]
 pub fn borsh_serde_sized(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
@@ -58,13 +58,21 @@ impl ToTokens for Lifetimes {
 
 /// Returns the value for an inner attribute (syntax: `attr_ident: value`)
 fn inner_attr_value(attr_ident: &str, inner: &TokenStream) -> TokenStream {
+    match inner_attr_value_optional(attr_ident, inner) {
+        Some(value) => value,
+        None => panic!("Inner attribute '{}' not found in '{}'", attr_ident, inner),
+    }
+}
+
+/// Returns the value for an optional inner attribute (syntax: `attr_ident: value`)
+fn inner_attr_value_optional(attr_ident: &str, inner: &TokenStream) -> Option<TokenStream> {
     let inner_attrs = match_inner(inner.clone());
     for ElusivAccountAttr { ident, value } in inner_attrs {
         if ident == attr_ident {
-            return value;
+            return Some(value);
         }
     }
-    panic!("Inner attribute '{}' not found in '{}'", attr_ident, inner);
+    None
 }
 
 /// Checks whether a type is bound by lifetimes
@@ -149,6 +157,23 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
                     &s.fields,
                 );
 
+                // Optionally the last child-account can have a different size than `child_account` (e.g. if it is only partially used)
+                let child_account_size_fn = match inner_attr_value_optional(
+                    "last_child_account_size",
+                    &attr.value,
+                ) {
+                    Some(last_child_account_size) => quote! {
+                        fn child_account_size(child_index: usize) -> usize {
+                            if child_index + 1 == <Self as elusiv_types::accounts::ParentAccount>::COUNT {
+                                #last_child_account_size
+                            } else {
+                                <#child_account_type as elusiv_types::accounts::SizedAccount>::SIZE
+                            }
+                        }
+                    },
+                    None => quote!(),
+                };
+
                 // 'a, 'b, 't lifetimes for the `ParentAccount` impl
                 lifetimes.push(quote!('b));
                 lifetimes.push(quote!('t));
@@ -160,6 +185,8 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
                         const COUNT: usize = #child_account_count;
                         type Child = #child_account_type;
 
+                        #child_account_size_fn
+
                         fn set_child_accounts(parent: &mut Self, child_accounts: Vec<Option<&'b solana_program::account_info::AccountInfo<'t>>>) {
                             parent.child_accounts = child_accounts
                         }
@@ -181,6 +208,14 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
                     }
                 });
 
+                // Compile-time check of the child-accounts being of valid size (10 MiB)
+                impls.extend(quote! {
+                    const _: () = assert!(
+                        <#child_account_type as elusiv_types::accounts::SizedAccount>::SIZE as u64 <= solana_program::system_instruction::MAX_PERMITTED_DATA_LENGTH,
+                        concat!("Child-accounts of ", stringify!(#ident), " exceed the maximum account size")
+                    );
+                });
+
                 impls.extend(quote! {
                     #[cfg(feature = "elusiv-client")]
                     impl elusiv_types::accounts::EagerParentAccountRepr for #eager_ident {
@@ -209,9 +244,9 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
             "partial_computation" => {
                 enforce_field(quote! { instruction : u32 }, 1, &s.fields);
                 enforce_field(quote! { round : u32 }, 2, &s.fields);
+                enforce_field(quote! { last_update_slot : u64 }, 3, &s.fields);
 
                 impls.extend(quote! {
-                    #[cfg(feature = "elusiv-client")]
                     impl < #lifetimes > elusiv_types::accounts::ComputationAccount for #ident < #lifetimes > {
                         fn instruction(&self) -> u32 {
                             self.get_instruction()
@@ -220,6 +255,16 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
                         fn round(&self) -> u32 {
                             self.get_round()
                         }
+
+                        fn last_round_slot(&self) -> u64 {
+                            self.get_last_update_slot()
+                        }
+
+                        fn set_progress(&mut self, instruction: u32, round: u32, slot: u64) {
+                            self.set_instruction(&instruction);
+                            self.set_round(&round);
+                            self.set_last_update_slot(&slot);
+                        }
                     }
                 });
             }
@@ -510,6 +555,12 @@ pub fn impl_elusiv_account(ast: &syn::DeriveInput, attrs: TokenStream) -> TokenS
             const SIZE: usize = #account_size;
         }
 
+        // Compile-time check of the account being of valid PDA-size (10 KiB, the maximum size for accounts created via CPI)
+        const _: () = assert!(
+            < #ident < #anonymous_lifetimes > as elusiv_types::accounts::SizedAccount>::SIZE <= solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE,
+            concat!("Account ", stringify!(#ident), " exceeds the maximum PDA-size")
+        );
+
         // Test to verify the account to be of valid PDA-size (10 KiB)
         #[cfg(test)]
         mod #account_size_test {
@@ -1,6 +1,9 @@
 #![allow(dead_code)]
 #![allow(unused_macros)]
 
+#[cfg(feature = "versioned")]
+mod versioned;
+
 use assert_matches::assert_matches;
 use elusiv_types::tokens::{
     elusiv_token, pyth_price_account_data, Lamports, Price, SPLToken, Token, TOKENS,
@@ -9,6 +12,8 @@ use elusiv_types::{
     EagerAccount, EagerAccountRepr, PDAAccount, PDAOffset, ParentAccount, SizedAccount,
     UserAccount, WritableUserAccount,
 };
+use elusiv_utils::versioned::AddressLookupTableAccount;
+use solana_address_lookup_table_program::state::{AddressLookupTable, LookupTableMeta};
 use solana_program::program_pack::Pack;
 use solana_program::{
     instruction::{AccountMeta, Instruction, InstructionError},
@@ -120,6 +125,23 @@ impl ElusivProgramTest {
             .await
     }
 
+    /// Creates an active address lookup table containing the supplied addresses
+    pub async fn create_lookup_table(&mut self, addresses: &[Pubkey]) -> AddressLookupTableAccount {
+        let key = Pubkey::new_unique();
+        let table = AddressLookupTable {
+            meta: LookupTableMeta::new(self.context.payer.pubkey()),
+            addresses: addresses.into(),
+        };
+        let data = table.serialize_for_tests().unwrap();
+        self.set_account_rent_exempt(&key, &data[..], &solana_address_lookup_table_program::id())
+            .await;
+
+        AddressLookupTableAccount {
+            key,
+            addresses: addresses.to_vec(),
+        }
+    }
+
     pub fn context(&mut self) -> &mut ProgramTestContext {
         &mut self.context
     }
@@ -378,9 +400,9 @@ impl ElusivProgramTest {
     ) -> Vec<Pubkey> {
         let mut result = Vec::new();
 
-        for _ in 0..T::COUNT {
+        for i in 0..T::COUNT {
             let pk = self
-                .create_program_account_rent_exempt(program_id, T::Child::SIZE)
+                .create_program_account_rent_exempt(program_id, T::child_account_size(i))
                 .await
                 .pubkey();
             result.push(pk);
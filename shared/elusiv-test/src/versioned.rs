@@ -0,0 +1,142 @@
+//! Processing of v0 transactions (requires the `versioned` feature, since it depends on the runtime crates)
+
+use super::{nonce_instruction, ElusivProgramTest};
+use assert_matches::assert_matches;
+use elusiv_utils::versioned::{
+    compile_v0_message, resolve_versioned_message, sign_versioned_message,
+    AddressLookupTableAccount,
+};
+use solana_program::{instruction::Instruction, message::VersionedMessage, pubkey::Pubkey};
+use solana_program_runtime::timings::ExecuteTimings;
+use solana_program_test::BanksClientError;
+use solana_runtime::bank::Bank;
+use solana_sdk::{
+    account::{AccountSharedData, ReadableAccount},
+    clock::MAX_PROCESSING_AGE,
+    signature::Keypair,
+    signer::Signer,
+};
+use std::sync::Arc;
+
+impl ElusivProgramTest {
+    /// Processes the instructions as a v0 transaction, loading accounts through the supplied lookup tables
+    /// - `BanksClient` only processes legacy transactions, so the v0 transaction is processed by a bank forked from the genesis config
+    /// - all accounts referenced by the transaction are copied into the fork and written back afterwards
+    /// - only the test's programs and native programs can be invoked
+    pub async fn process_versioned_transaction(
+        &mut self,
+        instructions: &[Instruction],
+        signing_keypairs: &[&Keypair],
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> Result<(), BanksClientError> {
+        let bank = self.versioned_transaction_bank().await?;
+
+        let mut signing_keypairs = signing_keypairs.to_vec();
+        signing_keypairs.insert(0, &self.context.payer);
+        let message = compile_v0_message(
+            &self.context.payer.pubkey(),
+            instructions,
+            lookup_tables,
+            bank.last_blockhash(),
+        )
+        .ok_or(BanksClientError::ClientError("Message compilation failure"))?;
+        let tx = sign_versioned_message(VersionedMessage::V0(message), &signing_keypairs)
+            .ok_or(BanksClientError::ClientError("Signature failure"))?;
+
+        let mut keys: Vec<Pubkey> = lookup_tables.iter().map(|table| table.key).collect();
+        for ix in resolve_versioned_message(&tx.message, lookup_tables)
+            .ok_or(BanksClientError::ClientError("Lookup failure"))?
+        {
+            keys.push(ix.program_id);
+            keys.extend(ix.accounts.iter().map(|account| account.pubkey));
+        }
+        keys.sort();
+        keys.dedup();
+
+        for key in keys.iter() {
+            if let Some(account) = self.context.banks_client.get_account(*key).await? {
+                if !account.executable {
+                    bank.store_account(key, &AccountSharedData::from(account));
+                }
+            }
+        }
+
+        // The bank sanitizes the transaction (including its size) and resolves the lookups itself
+        let batch = bank.prepare_entry_batch(vec![tx])?;
+        let (results, _) = bank.load_execute_and_commit_transactions(
+            &batch,
+            MAX_PROCESSING_AGE,
+            false,
+            false,
+            false,
+            &mut ExecuteTimings::default(),
+        );
+        results.execution_results[0].flattened_result()?;
+
+        for key in keys.iter() {
+            let account = bank.get_account(key).unwrap_or_default();
+            if !account.executable() {
+                self.context.set_account(key, &account);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Forks a bank (containing the test's programs) for processing a v0 transaction after the current slot
+    /// - lookup tables are only active in slots after they have been extended
+    async fn versioned_transaction_bank(&mut self) -> Result<Bank, BanksClientError> {
+        let slot = self.context.banks_client.get_root_slot().await?;
+
+        let mut bank = Bank::new_for_tests(self.context.genesis_config());
+        for (name, id, process_instruction) in self.programs.iter() {
+            if let Some(process_instruction) = process_instruction {
+                bank.add_builtin(name, id, *process_instruction);
+            }
+        }
+
+        Ok(Bank::new_from_parent(
+            &Arc::new(bank),
+            &Pubkey::default(),
+            slot + 1,
+        ))
+    }
+
+    pub async fn process_versioned_transaction_nonced(
+        &mut self,
+        instructions: &[Instruction],
+        signing_keypairs: &[&Keypair],
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> Result<(), BanksClientError> {
+        let mut instructions = instructions.to_vec();
+        instructions[0] = nonce_instruction(instructions[0].clone());
+        self.process_versioned_transaction(&instructions, signing_keypairs, lookup_tables)
+            .await
+    }
+
+    pub async fn versioned_tx_should_succeed(
+        &mut self,
+        ixs: &[Instruction],
+        signers: &[&Keypair],
+        lookup_tables: &[AddressLookupTableAccount],
+    ) {
+        assert_matches!(
+            self.process_versioned_transaction_nonced(ixs, signers, lookup_tables)
+                .await,
+            Ok(())
+        );
+    }
+
+    pub async fn versioned_tx_should_fail(
+        &mut self,
+        ixs: &[Instruction],
+        signers: &[&Keypair],
+        lookup_tables: &[AddressLookupTableAccount],
+    ) {
+        assert_matches!(
+            self.process_versioned_transaction_nonced(ixs, signers, lookup_tables)
+                .await,
+            Err(_)
+        );
+    }
+}
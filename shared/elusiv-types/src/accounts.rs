@@ -6,6 +6,8 @@ use solana_program::account_info::{next_account_info, AccountInfo};
 use solana_program::entrypoint::ProgramResult;
 use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
+use std::cmp::min;
+use std::ops::Range;
 
 /// An account with a fixed size
 pub trait SizedAccount: Sized {
@@ -80,7 +82,7 @@ impl<A: ChildAccount> SizedAccount for A {
 ///
 /// - A [`ChildAccount`] can be a PDA, but will most likely be data accounts (size > 10 KiB).
 pub trait ParentAccount<'a, 'b, 't>: ProgramAccount<'a> {
-    /// The number of child-accounts
+    /// The maximum number of child-accounts
     const COUNT: usize;
 
     /// The associated [`ChildAccount`] type
@@ -102,6 +104,19 @@ pub trait ParentAccount<'a, 'b, 't>: ProgramAccount<'a> {
         Ok(s)
     }
 
+    /// The size of the child-account at `child_index`
+    /// - all child-accounts have the size of [`Self::Child`], unless a different size is declared for the last child-account (`last_child_account_size`)
+    fn child_account_size(child_index: usize) -> usize {
+        let _ = child_index;
+        <Self::Child as SizedAccount>::SIZE
+    }
+
+    /// The number of child-accounts used by this instance (bounded by [`Self::COUNT`])
+    /// - defaults to all [`Self::COUNT`] child-accounts
+    fn child_count(&self) -> usize {
+        Self::COUNT
+    }
+
     /// Sets all child-accounts for this instance
     fn set_child_accounts(parent: &mut Self, child_accounts: Vec<Option<&'b AccountInfo<'t>>>);
 
@@ -124,7 +139,7 @@ pub trait ParentAccount<'a, 'b, 't>: ProgramAccount<'a> {
         child_index: usize,
     ) -> Result<&AccountInfo<'t>, ProgramError>;
 
-    /// Finds all `n elem [0; COUNT]` available child-accounts in an [`AccountInfo`]-iterator
+    /// Finds all `n elem [0; child_count]` available child-accounts in an [`AccountInfo`]-iterator
     ///
     /// # Notes
     ///
@@ -144,14 +159,15 @@ pub trait ParentAccount<'a, 'b, 't>: ProgramAccount<'a> {
             .map(|i| parent.get_child_pubkey(i))
             .collect();
 
+        let child_count = std::cmp::min(parent.child_count(), Self::COUNT);
         let mut accounts = vec![None; Self::COUNT];
         let mut remaining_iter = account_info_iter.clone();
         let mut i = 0;
-        while i < Self::COUNT {
+        while i < child_count {
             match next_account_info(account_info_iter) {
                 Ok(account) => {
                     #[allow(clippy::needless_range_loop)]
-                    for child_index in i..Self::COUNT {
+                    for child_index in i..child_count {
                         match child_pubkeys[child_index] {
                             Some(pubkey) => {
                                 if *account.key != pubkey {
@@ -216,8 +232,138 @@ pub trait ParentAccount<'a, 'b, 't>: ProgramAccount<'a> {
     }
 }
 
+/// A [`ParentAccount`] storing a single array of `VALUE_SIZE`-byte values, split over its child-accounts
+///
+/// # Notes
+///
+/// Values are accessed in place on the borrowed data of the child-accounts, so no (partial) copy of the array is made.
+/// Each child-account stores `VALUES_PER_CHILD_ACCOUNT` values (the last child-account can store fewer values).
+pub trait ChildArrayAccount<'a, 'b, 't>: ParentAccount<'a, 'b, 't> {
+    /// The size of a single value measured in bytes
+    const VALUE_SIZE: usize;
+
+    /// The number of values stored by a single child-account
+    const VALUES_PER_CHILD_ACCOUNT: usize;
+
+    /// Returns the index of the child-account and the local index of the value at `index`
+    fn child_array_position(index: usize) -> (usize, usize) {
+        (
+            index / Self::VALUES_PER_CHILD_ACCOUNT,
+            index % Self::VALUES_PER_CHILD_ACCOUNT,
+        )
+    }
+
+    /// Performs `closure` on the data of the value at `index`
+    fn execute_on_value<T, C>(&self, index: usize, closure: C) -> Result<T, ProgramError>
+    where
+        C: FnOnce(&[u8]) -> T,
+    {
+        let (child_index, local_index) = Self::child_array_position(index);
+        if child_index >= Self::COUNT {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        self.execute_on_child_account(child_index, |data| {
+            data.get(local_index * Self::VALUE_SIZE..(local_index + 1) * Self::VALUE_SIZE)
+                .map(closure)
+        })?
+        .ok_or(ProgramError::InvalidArgument)
+    }
+
+    /// Performs `closure` on the mutable data of the value at `index`
+    fn execute_on_value_mut<T, C>(&self, index: usize, closure: C) -> Result<T, ProgramError>
+    where
+        C: FnOnce(&mut [u8]) -> T,
+    {
+        let (child_index, local_index) = Self::child_array_position(index);
+        if child_index >= Self::COUNT {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        self.execute_on_child_account_mut(child_index, |data| {
+            data.get_mut(local_index * Self::VALUE_SIZE..(local_index + 1) * Self::VALUE_SIZE)
+                .map(closure)
+        })?
+        .ok_or(ProgramError::InvalidArgument)
+    }
+
+    /// Performs `closure` on the index and data of each value in `range` (in order)
+    /// - each child-account containing values of the `range` is borrowed only once
+    fn execute_on_values<C>(&self, range: Range<usize>, mut closure: C) -> ProgramResult
+    where
+        C: FnMut(usize, &[u8]),
+    {
+        let mut index = range.start;
+        while index < range.end {
+            let (child_index, local_index) = Self::child_array_position(index);
+            let count = min(
+                range.end - index,
+                Self::VALUES_PER_CHILD_ACCOUNT - local_index,
+            );
+            if child_index >= Self::COUNT {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            self.execute_on_child_account(child_index, |data| {
+                let values = data
+                    .get(local_index * Self::VALUE_SIZE..(local_index + count) * Self::VALUE_SIZE)
+                    .ok_or(ProgramError::InvalidArgument)?;
+
+                for (i, value) in values.chunks_exact(Self::VALUE_SIZE).enumerate() {
+                    closure(index + i, value);
+                }
+                Ok::<(), ProgramError>(())
+            })??;
+
+            index += count;
+        }
+
+        Ok(())
+    }
+
+    /// Performs `closure` on the index and mutable data of each value in `range` (in order)
+    /// - each child-account containing values of the `range` is borrowed only once
+    fn execute_on_values_mut<C>(&self, range: Range<usize>, mut closure: C) -> ProgramResult
+    where
+        C: FnMut(usize, &mut [u8]),
+    {
+        let mut index = range.start;
+        while index < range.end {
+            let (child_index, local_index) = Self::child_array_position(index);
+            let count = min(
+                range.end - index,
+                Self::VALUES_PER_CHILD_ACCOUNT - local_index,
+            );
+            if child_index >= Self::COUNT {
+                return Err(ProgramError::InvalidArgument);
+            }
+
+            self.execute_on_child_account_mut(child_index, |data| {
+                let values = data
+                    .get_mut(
+                        local_index * Self::VALUE_SIZE..(local_index + count) * Self::VALUE_SIZE,
+                    )
+                    .ok_or(ProgramError::InvalidArgument)?;
+
+                for (i, value) in values.chunks_exact_mut(Self::VALUE_SIZE).enumerate() {
+                    closure(index + i, value);
+                }
+                Ok::<(), ProgramError>(())
+            })??;
+
+            index += count;
+        }
+
+        Ok(())
+    }
+}
+
 pub type PDAOffset = Option<u32>;
 
+/// Composite offset for PDAs with multi-dimensional keys (e.g. token-id and version), each offset is added as a separate seed
+/// - a single offset `&[o]` results in the same PDA as the [`PDAOffset`] `Some(o)`
+pub type PDAOffsets<'a> = &'a [u32];
+
 /// A [`PDAAccount`] uses a seed, an (optional) [`Pubkey`] and a [`PDAOffset`] to derive PDAs
 pub trait PDAAccount {
     const PROGRAM_ID: Pubkey;
@@ -255,6 +401,13 @@ pub trait PDAAccount {
         }
     }
 
+    fn find_with_offsets(pubkey: Option<Pubkey>, offsets: PDAOffsets) -> (Pubkey, u8) {
+        let seed = Self::seeds_with_offsets(Self::SEED, pubkey, offsets);
+        let seed: Vec<&[u8]> = seed.iter().map(|x| &x[..]).collect();
+
+        Pubkey::find_program_address(&seed, &Self::PROGRAM_ID)
+    }
+
     fn create(offset: PDAOffset, bump: u8) -> Result<Pubkey, ProgramError> {
         if offset.is_none() {
             return Ok(Self::FIRST_PDA.0);
@@ -277,14 +430,38 @@ pub trait PDAAccount {
         Pubkey::create_program_address(&seed, &Self::PROGRAM_ID).or(Err(ProgramError::InvalidSeeds))
     }
 
+    fn create_with_offsets(
+        pubkey: Option<Pubkey>,
+        offsets: PDAOffsets,
+        bump: u8,
+    ) -> Result<Pubkey, ProgramError> {
+        let seed = Self::signers_seeds_with_offsets(pubkey, offsets, bump);
+        let seed: Vec<&[u8]> = seed.iter().map(|x| &x[..]).collect();
+
+        Pubkey::create_program_address(&seed, &Self::PROGRAM_ID).or(Err(ProgramError::InvalidSeeds))
+    }
+
     fn seeds(seed: &[u8], pubkey: Option<Pubkey>, offset: PDAOffset) -> Vec<Vec<u8>> {
+        let offsets: PDAOffsets = match &offset {
+            Some(offset) => std::slice::from_ref(offset),
+            None => &[],
+        };
+
+        Self::seeds_with_offsets(seed, pubkey, offsets)
+    }
+
+    fn seeds_with_offsets(
+        seed: &[u8],
+        pubkey: Option<Pubkey>,
+        offsets: PDAOffsets,
+    ) -> Vec<Vec<u8>> {
         let mut seed = vec![seed.to_vec()];
 
         if let Some(pubkey) = pubkey {
             seed.push(pubkey.to_bytes().to_vec());
         }
 
-        if let Some(offset) = offset {
+        for offset in offsets {
             seed.push(offset.to_le_bytes().to_vec());
         }
 
@@ -297,6 +474,16 @@ pub trait PDAAccount {
         seed
     }
 
+    fn signers_seeds_with_offsets(
+        pubkey: Option<Pubkey>,
+        offsets: PDAOffsets,
+        bump: u8,
+    ) -> Vec<Vec<u8>> {
+        let mut seed = Self::seeds_with_offsets(Self::SEED, pubkey, offsets);
+        seed.push(vec![bump]);
+        seed
+    }
+
     /// Extracts the bump from an [`AccountInfo`]
     ///
     /// # Note
@@ -325,11 +512,38 @@ pub trait PDAAccount {
 
         Ok(())
     }
+
+    fn verify_account_with_offsets(
+        account: &AccountInfo,
+        pubkey: Option<Pubkey>,
+        offsets: PDAOffsets,
+    ) -> ProgramResult {
+        if Self::create_with_offsets(pubkey, offsets, Self::get_bump(account))? != *account.key {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        Ok(())
+    }
 }
 
+/// Default amount of slots after which a computation that has not been advanced is considered timed out (~ 10 minutes)
+pub const COMPUTATION_TIMEOUT_SLOTS: u64 = 1500;
+
+/// A [`PDAAccount`] storing the progress of a partial computation
 pub trait ComputationAccount: PDAAccount {
     fn instruction(&self) -> u32;
     fn round(&self) -> u32;
+
+    /// The slot in which the computation has been started or the last round has been performed
+    fn last_round_slot(&self) -> u64;
+
+    /// Amount of slots without a round after which the computation is considered stalled
+    fn timeout_slots(&self) -> u64 {
+        COMPUTATION_TIMEOUT_SLOTS
+    }
+
+    /// Stores the progress of the computation
+    fn set_progress(&mut self, instruction: u32, round: u32, slot: u64);
 }
 
 #[derive(BorshDeserialize, BorshSerialize, BorshSerDeSized)]
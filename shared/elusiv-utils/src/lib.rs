@@ -1,8 +1,10 @@
 pub mod macros;
+#[cfg(feature = "sdk")]
+pub mod versioned;
 
 use elusiv_types::{
     accounts::{PDAAccount, PDAAccountData, SizedAccount},
-    PDAOffset,
+    PDAOffset, PDAOffsets,
 };
 use solana_program::{
     account_info::AccountInfo,
@@ -76,6 +78,34 @@ pub fn open_pda_account_with_associated_pubkey<'a, T: PDAAccount + SizedAccount>
     )
 }
 
+/// Opens a PDA with a composite offset (see [`PDAOffsets`])
+pub fn open_pda_account_with_offsets<'a, T: PDAAccount + SizedAccount>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    pda_account: &AccountInfo<'a>,
+    pda_pubkey: Option<Pubkey>,
+    pda_offsets: PDAOffsets,
+    bump: Option<u8>,
+) -> ProgramResult {
+    let (pk, bump) = match bump {
+        Some(bump) => (T::create_with_offsets(pda_pubkey, pda_offsets, bump)?, bump),
+        None => T::find_with_offsets(pda_pubkey, pda_offsets),
+    };
+
+    guard!(pk == *pda_account.key, InvalidInstructionData);
+    let seeds = T::signers_seeds_with_offsets(pda_pubkey, pda_offsets, bump);
+    let signers_seeds = signers_seeds!(seeds);
+
+    create_pda_account(
+        program_id,
+        payer,
+        pda_account,
+        T::SIZE,
+        bump,
+        &signers_seeds,
+    )
+}
+
 pub fn open_pda_account<'a, T: PDAAccount>(
     program_id: &Pubkey,
     payer: &AccountInfo<'a>,
@@ -20,6 +20,18 @@ macro_rules! two_pow {
     };
 }
 
+/// Compile-time assertion of the [`elusiv_types::accounts::SizedAccount::SIZE`] of an account
+/// - pins the account layout, so that any change to the size of a field fails to compile
+#[macro_export]
+macro_rules! assert_account_size {
+    ($ty: ty, $size: expr) => {
+        const _: () = assert!(
+            <$ty as elusiv_types::accounts::SizedAccount>::SIZE == $size,
+            concat!("Invalid account size of ", stringify!($ty))
+        );
+    };
+}
+
 /// mut? $id: ident, $ty: ty, $account_info: ident
 #[macro_export]
 macro_rules! pda_account {
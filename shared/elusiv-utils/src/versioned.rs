@@ -0,0 +1,268 @@
+use solana_program::{
+    hash::Hash,
+    instruction::{AccountMeta, CompiledInstruction, Instruction},
+    message::{v0, MessageHeader, VersionedMessage},
+    pubkey::Pubkey,
+};
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::VersionedTransaction};
+
+/// The off-chain representation of an address lookup table (key + stored addresses)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AddressLookupTableAccount {
+    pub key: Pubkey,
+    pub addresses: Vec<Pubkey>,
+}
+
+#[derive(Clone, Copy)]
+struct KeyMeta {
+    is_signer: bool,
+    is_writable: bool,
+    is_invoked: bool,
+}
+
+fn merge_key(keys: &mut Vec<(Pubkey, KeyMeta)>, pubkey: &Pubkey, meta: KeyMeta) {
+    match keys.iter_mut().find(|(k, _)| k == pubkey) {
+        Some((_, m)) => {
+            m.is_signer |= meta.is_signer;
+            m.is_writable |= meta.is_writable;
+            m.is_invoked |= meta.is_invoked;
+        }
+        None => keys.push((*pubkey, meta)),
+    }
+}
+
+/// Compiles a v0 message, loading all accounts that are neither signers nor invoked programs through the supplied lookup tables (if possible)
+/// - the payer is always the first signer
+/// - returns `None` if the message would reference more than 256 accounts or use more than 256 table entries per table
+pub fn compile_v0_message(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Option<v0::Message> {
+    // Collect all keys (in order of appearance) with their merged metas
+    let mut keys: Vec<(Pubkey, KeyMeta)> = vec![(
+        *payer,
+        KeyMeta {
+            is_signer: true,
+            is_writable: true,
+            is_invoked: false,
+        },
+    )];
+    for ix in instructions {
+        merge_key(
+            &mut keys,
+            &ix.program_id,
+            KeyMeta {
+                is_signer: false,
+                is_writable: false,
+                is_invoked: true,
+            },
+        );
+        for account in &ix.accounts {
+            merge_key(
+                &mut keys,
+                &account.pubkey,
+                KeyMeta {
+                    is_signer: account.is_signer,
+                    is_writable: account.is_writable,
+                    is_invoked: false,
+                },
+            );
+        }
+    }
+
+    // Separate the static keys from keys that can be loaded through a table
+    let mut writable_signers = Vec::new();
+    let mut readonly_signers = Vec::new();
+    let mut writable_non_signers = Vec::new();
+    let mut readonly_non_signers = Vec::new();
+    let mut lookups: Vec<(v0::MessageAddressTableLookup, Vec<Pubkey>, Vec<Pubkey>)> = Vec::new();
+
+    for (pubkey, meta) in keys {
+        if !meta.is_signer && !meta.is_invoked {
+            let table = lookup_tables.iter().find_map(|t| {
+                t.addresses
+                    .iter()
+                    .position(|a| *a == pubkey)
+                    .map(|i| (t, i))
+            });
+
+            if let Some((table, index)) = table {
+                let index = u8::try_from(index).ok()?;
+                let lookup = match lookups
+                    .iter_mut()
+                    .find(|(l, _, _)| l.account_key == table.key)
+                {
+                    Some(lookup) => lookup,
+                    None => {
+                        lookups.push((
+                            v0::MessageAddressTableLookup {
+                                account_key: table.key,
+                                writable_indexes: Vec::new(),
+                                readonly_indexes: Vec::new(),
+                            },
+                            Vec::new(),
+                            Vec::new(),
+                        ));
+                        lookups.last_mut().unwrap()
+                    }
+                };
+
+                if meta.is_writable {
+                    lookup.0.writable_indexes.push(index);
+                    lookup.1.push(pubkey);
+                } else {
+                    lookup.0.readonly_indexes.push(index);
+                    lookup.2.push(pubkey);
+                }
+                continue;
+            }
+        }
+
+        match (meta.is_signer, meta.is_writable) {
+            (true, true) => writable_signers.push(pubkey),
+            (true, false) => readonly_signers.push(pubkey),
+            (false, true) => writable_non_signers.push(pubkey),
+            (false, false) => readonly_non_signers.push(pubkey),
+        }
+    }
+
+    let header = MessageHeader {
+        num_required_signatures: u8::try_from(writable_signers.len() + readonly_signers.len())
+            .ok()?,
+        num_readonly_signed_accounts: u8::try_from(readonly_signers.len()).ok()?,
+        num_readonly_unsigned_accounts: u8::try_from(readonly_non_signers.len()).ok()?,
+    };
+
+    // Account index space: static keys, then all writable loaded keys, then all readonly loaded keys
+    let mut account_keys = writable_signers;
+    account_keys.extend(readonly_signers);
+    account_keys.extend(writable_non_signers);
+    account_keys.extend(readonly_non_signers);
+
+    let mut all_keys = account_keys.clone();
+    all_keys.extend(lookups.iter().flat_map(|(_, w, _)| w.iter().copied()));
+    all_keys.extend(lookups.iter().flat_map(|(_, _, r)| r.iter().copied()));
+
+    let position = |pubkey: &Pubkey| {
+        all_keys
+            .iter()
+            .position(|k| k == pubkey)
+            .and_then(|i| u8::try_from(i).ok())
+    };
+
+    let instructions = instructions
+        .iter()
+        .map(|ix| {
+            Some(CompiledInstruction {
+                program_id_index: position(&ix.program_id)?,
+                accounts: ix
+                    .accounts
+                    .iter()
+                    .map(|a| position(&a.pubkey))
+                    .collect::<Option<Vec<u8>>>()?,
+                data: ix.data.clone(),
+            })
+        })
+        .collect::<Option<Vec<CompiledInstruction>>>()?;
+
+    Some(v0::Message {
+        header,
+        account_keys,
+        recent_blockhash,
+        instructions,
+        address_table_lookups: lookups.into_iter().map(|(l, _, _)| l).collect(),
+    })
+}
+
+/// Signs a versioned message with the `keypairs` of its signers (in any order)
+/// - returns `None` if the keypair of a signer is missing or the signed transaction does not sanitize
+pub fn sign_versioned_message(
+    message: VersionedMessage,
+    keypairs: &[&Keypair],
+) -> Option<VersionedTransaction> {
+    // The signatures are ordered by the appearance of the signers in the static keys
+    let signer_keys =
+        &message.static_account_keys()[..message.header().num_required_signatures as usize];
+    let keypairs = signer_keys
+        .iter()
+        .map(|k| keypairs.iter().find(|s| s.pubkey() == *k).copied())
+        .collect::<Option<Vec<&Keypair>>>()?;
+
+    let tx = VersionedTransaction::try_new(message, &keypairs).ok()?;
+    tx.sanitize(true).ok()?;
+
+    Some(tx)
+}
+
+/// Resolves all lookups of a versioned message and reconstructs the original instructions
+/// - returns `None` if the header is inconsistent with the static keys, a lookup table is missing or an index is invalid
+pub fn resolve_versioned_message(
+    message: &VersionedMessage,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Option<Vec<Instruction>> {
+    let header = message.header();
+    let static_keys = message.static_account_keys();
+    let signers_count = header.num_required_signatures as usize;
+    let writable_signers_count =
+        signers_count.checked_sub(header.num_readonly_signed_accounts as usize)?;
+    let writable_non_signers_end = static_keys
+        .len()
+        .checked_sub(header.num_readonly_unsigned_accounts as usize)?;
+    if signers_count > static_keys.len() || writable_non_signers_end < signers_count {
+        return None;
+    }
+
+    let mut keys: Vec<(Pubkey, bool, bool)> = static_keys
+        .iter()
+        .enumerate()
+        .map(|(i, k)| {
+            let is_signer = i < signers_count;
+            let is_writable =
+                i < writable_signers_count || (i >= signers_count && i < writable_non_signers_end);
+            (*k, is_signer, is_writable)
+        })
+        .collect();
+
+    let lookups = message.address_table_lookups().unwrap_or(&[]);
+    let load = |indexes: &dyn Fn(&v0::MessageAddressTableLookup) -> &Vec<u8>| {
+        lookups
+            .iter()
+            .map(|lookup| {
+                let table = lookup_tables.iter().find(|t| t.key == lookup.account_key)?;
+                indexes(lookup)
+                    .iter()
+                    .map(|i| table.addresses.get(*i as usize).copied())
+                    .collect::<Option<Vec<Pubkey>>>()
+            })
+            .collect::<Option<Vec<Vec<Pubkey>>>>()
+    };
+    let writable = load(&|l| &l.writable_indexes)?;
+    let readonly = load(&|l| &l.readonly_indexes)?;
+    keys.extend(writable.into_iter().flatten().map(|k| (k, false, true)));
+    keys.extend(readonly.into_iter().flatten().map(|k| (k, false, false)));
+
+    message
+        .instructions()
+        .iter()
+        .map(|ix| {
+            Some(Instruction {
+                program_id: keys.get(ix.program_id_index as usize)?.0,
+                accounts: ix
+                    .accounts
+                    .iter()
+                    .map(|i| {
+                        let (pubkey, is_signer, is_writable) = keys.get(*i as usize)?;
+                        Some(AccountMeta {
+                            pubkey: *pubkey,
+                            is_signer: *is_signer,
+                            is_writable: *is_writable,
+                        })
+                    })
+                    .collect::<Option<Vec<AccountMeta>>>()?,
+                data: ix.data.clone(),
+            })
+        })
+        .collect()
+}